@@ -0,0 +1,180 @@
+//! Headless rchat: a direct-message/relay node and database export, without
+//! the desktop app.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use rchat_core::headless::{self, ExportFormat, HeadlessNode, MediaExportMode};
+
+const USAGE: &str = "\
+Usage: rchat-cli <command> [options]
+
+Commands:
+  id                       Print this node's peer id
+  listen                   Receive messages (one JSON object per line)
+      --port <port>        Port for TCP and QUIC (default: random)
+      --relay              Also act as a circuit relay
+      --external <addr>    Address others reach this node at
+  send                     Send a text message and wait for the answer
+      --to <addr>          Multiaddr ending in /p2p/<peer id>
+      --text <text>
+  chats                    List chats in this machine's rchat database
+  export                   Export a chat from this machine's rchat database
+      --chat <id>
+      --out <path>
+      --format <json|markdown|html>         (default: json)
+      --media <none|linked|embedded>        (default: none)
+
+Options for id, listen and send:
+  --key <path>             Identity file, created if missing
+";
+
+struct Args {
+    command: String,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = std::env::args().skip(1).peekable();
+        let command = args.next().ok_or("missing command")?;
+        let mut options = Vec::new();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument {}", arg))?
+                .to_string();
+            let value = args.next_if(|next| !next.starts_with("--"));
+            options.push((name, value));
+        }
+        Ok(Self { command, options })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.value(name)
+            .ok_or_else(|| format!("--{} is required", name))
+    }
+
+    /// A snake_case enum value, parsed as the app's commands would.
+    fn choice<T: serde::de::DeserializeOwned>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.value(name) {
+            Some(value) => serde_json::from_value(serde_json::Value::String(value.to_string()))
+                .map_err(|_| format!("invalid --{} {}", name, value)),
+            None => Ok(default),
+        }
+    }
+
+    fn node(&self, relay: bool) -> Result<HeadlessNode, String> {
+        let key_path = self
+            .value("key")
+            .map(PathBuf::from)
+            .unwrap_or_else(headless::default_key_path);
+        let key = headless::load_or_create_key(&key_path).map_err(|e| e.to_string())?;
+        HeadlessNode::new(key, relay).map_err(|e| e.to_string())
+    }
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    match args.command.as_str() {
+        "id" => {
+            println!("{}", args.node(false)?.peer_id());
+        }
+        "listen" => {
+            let mut node = args.node(args.flag("relay"))?;
+            if let Some(addr) = args.value("external") {
+                node.add_external_address(
+                    addr.parse()
+                        .map_err(|e| format!("invalid --external {}: {}", addr, e))?,
+                );
+            }
+            let port = match args.value("port") {
+                Some(port) => port.parse().map_err(|_| format!("invalid port {}", port))?,
+                None => 0,
+            };
+            node.listen(port).map_err(|e| e.to_string())?;
+            node.run(|text| {
+                if let Ok(line) = serde_json::to_string(&text) {
+                    println!("{}", line);
+                }
+            })
+            .await;
+        }
+        "send" => {
+            let to = args.required("to")?;
+            let to: libp2p::Multiaddr = to
+                .parse()
+                .map_err(|e| format!("invalid --to {}: {}", to, e))?;
+            let text = args.required("text")?;
+            let response = args
+                .node(false)?
+                .send_text(to, text)
+                .await
+                .map_err(|e| e.to_string())?;
+            match response.status.as_str() {
+                "delivered" => println!("delivered {}", response.msg_id),
+                status => {
+                    return Err(format!(
+                        "{}: {}",
+                        status,
+                        response.error.unwrap_or_default()
+                    ))
+                }
+            }
+        }
+        "chats" => {
+            for chat in headless::list_chats().map_err(|e| e.to_string())? {
+                println!(
+                    "{}\t{}{}",
+                    chat.id,
+                    chat.name,
+                    if chat.is_group { "\t(group)" } else { "" }
+                );
+            }
+        }
+        "export" => {
+            let summary = headless::export_chat(
+                args.required("chat")?,
+                args.choice("format", ExportFormat::Json)?,
+                args.choice("media", MediaExportMode::None)?,
+                std::path::Path::new(args.required("out")?),
+            )
+            .map_err(|e| e.to_string())?;
+            println!(
+                "Exported {} message(s) and {} media file(s) to {}",
+                summary.message_count, summary.media_count, summary.path
+            );
+        }
+        "help" | "--help" | "-h" => print!("{}", USAGE),
+        other => return Err(format!("unknown command {}", other)),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("rchat-cli: {}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("rchat-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
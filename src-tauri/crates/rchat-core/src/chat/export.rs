@@ -0,0 +1,477 @@
+//! Portable chat transcripts (JSON, Markdown, HTML) with optional media.
+//!
+//! Messages are read from the database first and written out once the database
+//! lock is released; media is resolved through the object store (taking the
+//! lock per file) and either copied next to the transcript (`Linked`) or
+//! inlined as base64 (`Embedded`).
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::storage::{self, db::Message};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaExportMode {
+    None,
+    Linked,
+    Embedded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub message_count: usize,
+    pub media_count: usize,
+    pub missing_media: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportedMedia {
+    file_hash: String,
+    file_name: Option<String>,
+    mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_base64: Option<String>,
+}
+
+impl ExportedMedia {
+    fn display_name(&self) -> &str {
+        self.file_name.as_deref().unwrap_or(&self.file_hash)
+    }
+
+    /// Link target usable in Markdown/HTML, if the media was exported.
+    fn href(&self) -> Option<String> {
+        if let Some(path) = &self.path {
+            return Some(path.clone());
+        }
+        self.data_base64.as_ref().map(|data| {
+            format!(
+                "data:{};base64,{}",
+                self.mime_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream"),
+                data
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportedMessage<'a> {
+    id: &'a str,
+    timestamp: i64,
+    time: String,
+    sender_id: &'a str,
+    sender: &'a str,
+    content_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<&'a str>,
+    status: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media: Option<ExportedMedia>,
+}
+
+/// `YYYY-MM-DD HH:MM:SS UTC` for a Unix timestamp.
+pub fn format_timestamp(unix_secs: i64) -> String {
+    let secs_of_day = unix_secs.rem_euclid(86_400);
+    format!(
+        "{} {:02}:{:02}:{:02} UTC",
+        storage::calendar::day_label(unix_secs),
+        secs_of_day / 3_600,
+        (secs_of_day % 3_600) / 60,
+        secs_of_day % 60
+    )
+}
+
+pub fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Backslash-escape characters Markdown would treat as formatting, for names
+/// and labels that must show up literally.
+pub fn markdown_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | '`' | '*' | '_' | '{' | '}' | '[' | ']' | '(' | ')' | '#' | '+' | '-' | '.'
+            | '!' | '|' | '<' | '>' | '~' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            '\n' | '\r' => out.push(' '),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    cleaned.trim_matches('.').to_string()
+}
+
+struct MediaResolver {
+    mode: MediaExportMode,
+    media_dir: PathBuf,
+    media_dir_name: String,
+    media_count: usize,
+    missing_media: usize,
+}
+
+impl MediaResolver {
+    fn resolve(&mut self, db: &Mutex<Connection>, file_hash: &str) -> Result<ExportedMedia> {
+        let lock = || db.lock().map_err(|e| anyhow::anyhow!(e.to_string()));
+        let info = storage::object::info(&lock()?, file_hash)?;
+        let mut media = ExportedMedia {
+            file_hash: file_hash.to_string(),
+            file_name: info.as_ref().and_then(|i| i.file_name.clone()),
+            mime_type: info.as_ref().and_then(|i| i.mime_type.clone()),
+            path: None,
+            data_base64: None,
+        };
+
+        if self.mode == MediaExportMode::None {
+            return Ok(media);
+        }
+        if !info.map(|i| i.is_complete).unwrap_or(false) {
+            self.missing_media += 1;
+            return Ok(media);
+        }
+        let loaded = storage::object::load(&lock()?, file_hash, None);
+        let data = match loaded {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("[Export] Media {} unavailable: {}", file_hash, e);
+                self.missing_media += 1;
+                return Ok(media);
+            }
+        };
+
+        match self.mode {
+            MediaExportMode::Linked => {
+                std::fs::create_dir_all(&self.media_dir)
+                    .context("Failed to create media directory")?;
+                let short_hash = &file_hash[..file_hash.len().min(12)];
+                let file_name = match media.file_name.as_deref().map(sanitize_file_name) {
+                    Some(name) if !name.is_empty() => format!("{}-{}", short_hash, name),
+                    _ => short_hash.to_string(),
+                };
+                std::fs::write(self.media_dir.join(&file_name), &data)
+                    .with_context(|| format!("Failed to write media {}", file_name))?;
+                media.path = Some(format!("{}/{}", self.media_dir_name, file_name));
+            }
+            MediaExportMode::Embedded => {
+                media.data_base64 = Some(BASE64.encode(&data));
+            }
+            MediaExportMode::None => {}
+        }
+        self.media_count += 1;
+        Ok(media)
+    }
+}
+
+fn write_header(
+    out: &mut impl Write,
+    format: ExportFormat,
+    chat_id: &str,
+    chat_name: &str,
+    exported_at: i64,
+) -> Result<()> {
+    match format {
+        ExportFormat::Json => {
+            write!(
+                out,
+                "{{\"chat_id\":{},\"chat_name\":{},\"exported_at\":{},\"messages\":[",
+                serde_json::to_string(chat_id)?,
+                serde_json::to_string(chat_name)?,
+                exported_at
+            )?;
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "# {}\n", markdown_escape(chat_name))?;
+            writeln!(out, "_Exported {}_\n", format_timestamp(exported_at))?;
+        }
+        ExportFormat::Html => {
+            let title = html_escape(chat_name);
+            writeln!(
+                out,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\nbody {{ font-family: sans-serif; max-width: 720px; margin: 2em auto; }}\n.msg {{ margin: 0.8em 0; }}\n.meta {{ color: #777; font-size: 0.85em; }}\n.text {{ white-space: pre-wrap; }}\nimg, video {{ max-width: 100%; }}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n<p class=\"meta\">Exported {}</p>",
+                title,
+                title,
+                format_timestamp(exported_at)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_message(
+    out: &mut impl Write,
+    format: ExportFormat,
+    msg: &ExportedMessage<'_>,
+    first: bool,
+) -> Result<()> {
+    match format {
+        ExportFormat::Json => {
+            if !first {
+                write!(out, ",")?;
+            }
+            serde_json::to_writer(&mut *out, msg)?;
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "**{}** · {}\n", markdown_escape(msg.sender), msg.time)?;
+            if let Some(media) = &msg.media {
+                let label = markdown_escape(media.display_name());
+                match (media.href(), msg.content_type) {
+                    (Some(href), "image") | (Some(href), "sticker") => {
+                        writeln!(out, "![{}]({})\n", label, href)?
+                    }
+                    (Some(href), _) => writeln!(out, "[{}]({})\n", label, href)?,
+                    (None, kind) => writeln!(out, "_[{}: {}]_\n", kind, label)?,
+                }
+            }
+            if let Some(text) = msg.text {
+                for line in text.lines() {
+                    writeln!(out, "> {}", line)?;
+                }
+                writeln!(out)?;
+            }
+        }
+        ExportFormat::Html => {
+            writeln!(
+                out,
+                "<div class=\"msg\"><div class=\"meta\"><strong>{}</strong> · {}</div>",
+                html_escape(msg.sender),
+                html_escape(&msg.time)
+            )?;
+            if let Some(media) = &msg.media {
+                let label = html_escape(media.display_name());
+                match (media.href(), msg.content_type) {
+                    (Some(href), "image") | (Some(href), "sticker") => writeln!(
+                        out,
+                        "<img src=\"{}\" alt=\"{}\">",
+                        html_escape(&href),
+                        label
+                    )?,
+                    (Some(href), "video") => writeln!(
+                        out,
+                        "<video controls src=\"{}\"></video>",
+                        html_escape(&href)
+                    )?,
+                    (Some(href), "audio") => writeln!(
+                        out,
+                        "<audio controls src=\"{}\"></audio>",
+                        html_escape(&href)
+                    )?,
+                    (Some(href), _) => writeln!(
+                        out,
+                        "<a href=\"{}\" download=\"{}\">{}</a>",
+                        html_escape(&href),
+                        label,
+                        label
+                    )?,
+                    (None, kind) => writeln!(out, "<em>[{}: {}]</em>", kind, label)?,
+                }
+            }
+            if let Some(text) = msg.text {
+                writeln!(out, "<div class=\"text\">{}</div>", html_escape(text))?;
+            }
+            writeln!(out, "</div>")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_footer(out: &mut impl Write, format: ExportFormat) -> Result<()> {
+    match format {
+        ExportFormat::Json => writeln!(out, "]}}")?,
+        ExportFormat::Markdown => {}
+        ExportFormat::Html => writeln!(out, "</body>\n</html>")?,
+    }
+    Ok(())
+}
+
+fn sender_label(conn: &Connection, cache: &mut HashMap<String, String>, msg: &Message) -> String {
+    if msg.peer_id == "Me" {
+        return msg.sender_alias.clone().unwrap_or_else(|| "Me".to_string());
+    }
+    if let Some(alias) = msg.sender_alias.as_deref().filter(|a| !a.trim().is_empty()) {
+        return alias.to_string();
+    }
+    cache
+        .entry(msg.peer_id.clone())
+        .or_insert_with(|| {
+            storage::db::get_peer_alias(conn, &msg.peer_id)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| msg.peer_id.clone())
+        })
+        .clone()
+}
+
+/// Write a transcript of `chat_id` to `out_path`. `db` is only locked while
+/// rows and media are read, not while the file is written.
+pub fn export_chat(
+    db: &Mutex<Connection>,
+    chat_id: &str,
+    format: ExportFormat,
+    media_mode: MediaExportMode,
+    out_path: &Path,
+) -> Result<ExportSummary> {
+    let (chat_name, messages) = {
+        let conn = db.lock().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let chat_name = storage::db::get_chat_name(&conn, chat_id)?
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| chat_id.to_string());
+        let mut alias_cache = HashMap::new();
+        let mut messages = Vec::new();
+        storage::db::for_each_message(&conn, chat_id, |msg| {
+            let sender = sender_label(&conn, &mut alias_cache, &msg);
+            messages.push((msg, sender));
+            Ok(())
+        })?;
+        (chat_name, messages)
+    };
+    let exported_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let stem = out_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "transcript".to_string());
+    let media_dir_name = format!("{}_media", stem);
+    let mut resolver = MediaResolver {
+        mode: media_mode,
+        media_dir: out_path.with_file_name(&media_dir_name),
+        media_dir_name,
+        media_count: 0,
+        missing_media: 0,
+    };
+
+    let file = std::fs::File::create(out_path)
+        .with_context(|| format!("Failed to create {}", out_path.display()))?;
+    let mut out = BufWriter::new(file);
+    write_header(&mut out, format, chat_id, &chat_name, exported_at)?;
+
+    let mut message_count = 0usize;
+    for (msg, sender) in &messages {
+        let media = match msg.content.file_hash() {
+            Some(hash) => Some(resolver.resolve(db, hash)?),
+            None => None,
+        };
+        let exported = ExportedMessage {
+            id: &msg.id,
+            timestamp: msg.timestamp,
+            time: format_timestamp(msg.timestamp),
+            sender_id: &msg.peer_id,
+            sender,
+            content_type: msg.content.content_type(),
+            text: msg.content.text_content(),
+            status: &msg.status,
+            media,
+        };
+        write_message(&mut out, format, &exported, message_count == 0)?;
+        message_count += 1;
+    }
+
+    write_footer(&mut out, format)?;
+    out.flush()?;
+
+    Ok(ExportSummary {
+        path: out_path.to_string_lossy().to_string(),
+        message_count,
+        media_count: resolver.media_count,
+        missing_media: resolver.missing_media,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_timestamps_in_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_704_067_199), "2023-12-31 23:59:59 UTC");
+    }
+
+    #[test]
+    fn escapes_html_and_sanitizes_names() {
+        assert_eq!(
+            html_escape("<b>\"hi\" & 'bye'</b>"),
+            "&lt;b&gt;&quot;hi&quot; &amp; &#39;bye&#39;&lt;/b&gt;"
+        );
+        assert_eq!(sanitize_file_name("../my file?.png"), "_my_file_.png");
+    }
+
+    #[test]
+    fn markdown_names_and_labels_are_escaped() {
+        assert_eq!(
+            markdown_escape("**bold** [x](javascript:y)"),
+            "\\*\\*bold\\*\\* \\[x\\]\\(javascript:y\\)"
+        );
+        assert_eq!(markdown_escape("# a\nb"), "\\# a b");
+
+        let msg = ExportedMessage {
+            id: "1",
+            timestamp: 0,
+            time: format_timestamp(0),
+            sender_id: "peer",
+            sender: "_me_",
+            content_type: "document",
+            text: None,
+            status: "delivered",
+            media: Some(ExportedMedia {
+                file_hash: "abc".to_string(),
+                file_name: Some("a](b).pdf".to_string()),
+                mime_type: None,
+                path: Some("t_media/abc.pdf".to_string()),
+                data_base64: None,
+            }),
+        };
+        let mut out = Vec::new();
+        write_message(&mut out, ExportFormat::Markdown, &msg, true).expect("write");
+        let text = String::from_utf8(out).expect("utf8");
+        assert!(text.starts_with("**\\_me\\_**"));
+        assert!(text.contains("[a\\]\\(b\\)\\.pdf](t_media/abc.pdf)"));
+    }
+}
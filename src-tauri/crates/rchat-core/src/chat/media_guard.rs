@@ -0,0 +1,86 @@
+//! Export restrictions for received media.
+//!
+//! Chats with `restrict_media_export` set refuse to save received media out of
+//! the app (the save-to-file commands and media in chat exports). The user can
+//! override a single file by re-entering the vault password, which grants a
+//! one-shot pass for that file hash valid for [`OVERRIDE_WINDOW_SECS`].
+
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::storage::db;
+
+pub const EXPORT_RESTRICTED_CODE: &str = "EXPORT_RESTRICTED";
+pub const OVERRIDE_WINDOW_SECS: i64 = 60;
+
+/// file_hash -> unix secs until which one export is allowed.
+static OVERRIDES: Mutex<BTreeMap<String, i64>> = Mutex::new(BTreeMap::new());
+
+pub fn grant_override(file_hash: &str, now: i64) {
+    if let Ok(mut overrides) = OVERRIDES.lock() {
+        overrides.retain(|_, until| *until > now);
+        overrides.insert(file_hash.to_string(), now + OVERRIDE_WINDOW_SECS);
+    }
+}
+
+/// Consume a pending override for `file_hash`, if one is still valid.
+fn take_override(file_hash: &str, now: i64) -> bool {
+    let Ok(mut overrides) = OVERRIDES.lock() else {
+        return false;
+    };
+    overrides.remove(file_hash).is_some_and(|until| until > now)
+}
+
+/// An export refused by the chat's setting; the app reports it as
+/// [`EXPORT_RESTRICTED_CODE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportRestricted;
+
+impl std::fmt::Display for ExportRestricted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(
+            "Saving media from this chat is disabled. Re-enter your password to allow it once.",
+        )
+    }
+}
+
+impl std::error::Error for ExportRestricted {}
+
+/// Gate for writing `file_hash` outside the app.
+pub fn ensure_exportable(conn: &Connection, file_hash: &str, now: i64) -> anyhow::Result<()> {
+    if !db::is_media_export_restricted(conn, file_hash)? {
+        return Ok(());
+    }
+    if take_override(file_hash, now) {
+        println!(
+            "[Export] 🔓 One-time export override used for {}",
+            file_hash
+        );
+        return Ok(());
+    }
+    Err(ExportRestricted.into())
+}
+
+/// Gate for exporting a whole chat with its media.
+pub fn ensure_chat_media_exportable(conn: &Connection, chat_id: &str) -> anyhow::Result<()> {
+    if db::get_chat_settings(conn, chat_id)?.restrict_media_export {
+        return Err(ExportRestricted.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_are_single_use_and_expire() {
+        grant_override("h1", 100);
+        assert!(take_override("h1", 101));
+        assert!(!take_override("h1", 101));
+
+        grant_override("h2", 100);
+        assert!(!take_override("h2", 100 + OVERRIDE_WINDOW_SECS));
+    }
+}
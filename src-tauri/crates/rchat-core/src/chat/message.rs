@@ -0,0 +1,557 @@
+use serde::{Deserialize, Serialize};
+
+const POSTER_MAX_SIDE_PX: u32 = 320;
+const POSTER_JPEG_QUALITY: u8 = 80;
+
+/// Message delivery status; see `storage::db::status_transition_allowed`
+/// for how it may change.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageStatus {
+    Pending,
+    /// Handed to the transport, not yet acknowledged by the peer.
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+}
+
+impl MessageStatus {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "pending" => Self::Pending,
+            "sent" => Self::Sent,
+            "delivered" => Self::Delivered,
+            "read" => Self::Read,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Sent => "sent",
+            Self::Delivered => "delivered",
+            Self::Read => "read",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Cached metadata for media files (stored in content_metadata JSON column)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ContentMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub word_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    /// Object hash of a JPEG poster frame (videos).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_hash: Option<String>,
+}
+
+/// Message content variants.
+///
+/// `storage::db::Message` holds its content as a `MessageContent`. The
+/// messages table, the UI's message JSON and the wire payloads that old peers
+/// and signed group envelopes depend on keep it flat, as `content_type` /
+/// `text_content` / `file_hash`; since protocol version 3 the payloads also
+/// carry the typed content (with its media metadata) next to them.
+/// [`MessageContent::from_parts`] and [`MessageContent::into_parts`] convert
+/// between the two.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MessageContent {
+    Text {
+        text: String,
+    },
+    /// A Note to Self task; `text` is the task title.
+    Task {
+        text: String,
+    },
+    #[serde(alias = "image")]
+    Photo {
+        file_hash: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        caption: Option<String>,
+        #[serde(flatten)]
+        metadata: ContentMetadata,
+    },
+    Sticker {
+        file_hash: String,
+        #[serde(flatten)]
+        metadata: ContentMetadata,
+    },
+    Video {
+        file_hash: String,
+        file_name: String,
+        #[serde(flatten)]
+        metadata: ContentMetadata,
+    },
+    Document {
+        file_hash: String,
+        file_name: String,
+        #[serde(flatten)]
+        metadata: ContentMetadata,
+    },
+    Audio {
+        file_hash: String,
+        file_name: String,
+        #[serde(flatten)]
+        metadata: ContentMetadata,
+    },
+}
+
+impl MessageContent {
+    /// Build from the flat columns, where `text_content` is the caption of a
+    /// photo and the file name of other files. Unknown content types are read
+    /// as text.
+    pub fn from_parts(
+        content_type: &str,
+        text_content: Option<String>,
+        file_hash: Option<String>,
+        metadata: ContentMetadata,
+    ) -> Self {
+        let file_hash = file_hash.unwrap_or_default();
+        match content_type {
+            "task" => Self::Task {
+                text: text_content.unwrap_or_default(),
+            },
+            "photo" | "image" => Self::Photo {
+                file_hash,
+                caption: text_content,
+                metadata,
+            },
+            "sticker" => Self::Sticker {
+                file_hash,
+                metadata,
+            },
+            "video" => Self::Video {
+                file_hash,
+                file_name: text_content.unwrap_or_else(|| "video".to_string()),
+                metadata,
+            },
+            "document" => Self::Document {
+                file_hash,
+                file_name: text_content.unwrap_or_else(|| "document".to_string()),
+                metadata,
+            },
+            "audio" => Self::Audio {
+                file_hash,
+                file_name: text_content.unwrap_or_else(|| "audio".to_string()),
+                metadata,
+            },
+            _ => Self::Text {
+                text: text_content.unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Build from the stored columns, with the media metadata cached in the
+    /// `content_metadata` JSON.
+    pub fn from_columns(
+        content_type: &str,
+        text_content: Option<String>,
+        file_hash: Option<String>,
+        content_metadata: Option<&str>,
+    ) -> Self {
+        let metadata = content_metadata
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        Self::from_parts(content_type, text_content, file_hash, metadata)
+    }
+
+    /// Split into `(content_type, text_content, file_hash)`.
+    pub fn into_parts(self) -> (&'static str, Option<String>, Option<String>) {
+        let content_type = self.content_type();
+        match self {
+            Self::Text { text } | Self::Task { text } => (content_type, Some(text), None),
+            Self::Photo {
+                file_hash, caption, ..
+            } => (content_type, caption, Some(file_hash)),
+            Self::Video {
+                file_hash,
+                file_name,
+                ..
+            }
+            | Self::Document {
+                file_hash,
+                file_name,
+                ..
+            }
+            | Self::Audio {
+                file_hash,
+                file_name,
+                ..
+            } => (content_type, Some(file_name), Some(file_hash)),
+            Self::Sticker { file_hash, .. } => (content_type, None, Some(file_hash)),
+        }
+    }
+
+    /// The `content_type` column value; photos are stored as `image`, like
+    /// the wire kinds.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Text { .. } => "text",
+            Self::Task { .. } => "task",
+            Self::Photo { .. } => "image",
+            Self::Sticker { .. } => "sticker",
+            Self::Video { .. } => "video",
+            Self::Document { .. } => "document",
+            Self::Audio { .. } => "audio",
+        }
+    }
+
+    /// Text typed by the sender: the message, task title or photo caption.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::Text { text } | Self::Task { text } => Some(text),
+            Self::Photo { caption, .. } => caption.as_deref(),
+            Self::Sticker { .. }
+            | Self::Video { .. }
+            | Self::Document { .. }
+            | Self::Audio { .. } => None,
+        }
+    }
+
+    /// The `text_content` column value: the text, caption or file name.
+    pub fn text_content(&self) -> Option<&str> {
+        match self {
+            Self::Text { text } | Self::Task { text } => Some(text),
+            Self::Photo { caption, .. } => caption.as_deref(),
+            Self::Video { file_name, .. }
+            | Self::Document { file_name, .. }
+            | Self::Audio { file_name, .. } => Some(file_name),
+            Self::Sticker { .. } => None,
+        }
+    }
+
+    /// Whether `into_parts` gives these flat fields, so typed content from the
+    /// wire can be checked against the flat fields sent next to it.
+    pub fn matches_parts(
+        &self,
+        content_type: &str,
+        text_content: Option<&str>,
+        file_hash: Option<&str>,
+    ) -> bool {
+        let (own_type, own_text, own_hash) = self.clone().into_parts();
+        let same_type =
+            own_type == content_type || (own_type == "image" && content_type == "photo");
+        same_type && own_text.as_deref() == text_content && own_hash.as_deref() == file_hash
+    }
+
+    /// Get the file hash if this is a file-based message
+    pub fn file_hash(&self) -> Option<&str> {
+        match self {
+            Self::Text { .. } | Self::Task { .. } => None,
+            Self::Photo { file_hash, .. }
+            | Self::Sticker { file_hash, .. }
+            | Self::Video { file_hash, .. }
+            | Self::Document { file_hash, .. }
+            | Self::Audio { file_hash, .. } => Some(file_hash),
+        }
+    }
+
+    /// Get metadata reference
+    pub fn metadata(&self) -> Option<&ContentMetadata> {
+        match self {
+            Self::Text { .. } | Self::Task { .. } => None,
+            Self::Photo { metadata, .. }
+            | Self::Sticker { metadata, .. }
+            | Self::Video { metadata, .. }
+            | Self::Document { metadata, .. }
+            | Self::Audio { metadata, .. } => Some(metadata),
+        }
+    }
+}
+
+/// Complete message with metadata and content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub id: String,
+    pub chat_id: String,
+    pub peer_id: String,
+    pub timestamp: i64,
+    pub status: MessageStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_alias: Option<String>,
+    pub content: MessageContent,
+}
+
+impl Message {
+    /// Convert from DB row (flat structure) to rich Message
+    pub fn from_db_row(db_msg: &crate::storage::db::Message) -> Self {
+        Self {
+            id: db_msg.id.clone(),
+            chat_id: db_msg.chat_id.clone(),
+            peer_id: db_msg.peer_id.clone(),
+            timestamp: db_msg.timestamp,
+            status: MessageStatus::from_str(&db_msg.status),
+            sender_alias: db_msg.sender_alias.clone(),
+            content: db_msg.content.clone(),
+        }
+    }
+
+    /// Convert rich Message to DB row (flat structure)
+    pub fn to_db_row(&self) -> crate::storage::db::Message {
+        // Serialize metadata to JSON for storage
+        let content_metadata = self
+            .content
+            .metadata()
+            .and_then(|m| serde_json::to_string(m).ok());
+        crate::storage::db::Message {
+            id: self.id.clone(),
+            chat_id: self.chat_id.clone(),
+            peer_id: self.peer_id.clone(),
+            timestamp: self.timestamp,
+            content: self.content.clone(),
+            status: self.status.as_str().to_string(),
+            content_metadata,
+            sender_alias: self.sender_alias.clone(),
+            logical: 0,
+        }
+    }
+
+    /// Check if metadata needs to be computed (returns true if has file but no cached metadata)
+    pub fn needs_hydration(&self) -> bool {
+        match &self.content {
+            MessageContent::Text { .. } | MessageContent::Task { .. } => false,
+            MessageContent::Photo { metadata, .. } => {
+                metadata.width.is_none() || metadata.size_bytes.is_none()
+            }
+            MessageContent::Sticker { metadata, .. } => metadata.size_bytes.is_none(),
+            MessageContent::Video { metadata, .. } => {
+                metadata.thumbnail_hash.is_none() && metadata.size_bytes.is_none()
+            }
+            MessageContent::Document { metadata, .. } => metadata.size_bytes.is_none(),
+            MessageContent::Audio { metadata, .. } => metadata.duration_secs.is_none(),
+        }
+    }
+
+    /// Hydrate metadata by computing from file and caching in DB.
+    /// Returns true if metadata was updated and should be cached.
+    pub fn hydrate(&mut self, conn: &rusqlite::Connection) -> bool {
+        // Only hydrate if needed
+        if !self.needs_hydration() {
+            return false;
+        }
+
+        let file_hash = match self.content.file_hash() {
+            Some(h) => h.to_string(),
+            None => return false,
+        };
+
+        // Load file data from chunks
+        let file_data = match crate::storage::object::load(conn, &file_hash, None) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("[Hydrate] Failed to load file {}: {}", file_hash, e);
+                return false;
+            }
+        };
+
+        // Compute metadata based on content type
+        let updated = match &mut self.content {
+            MessageContent::Photo { metadata, .. } => {
+                if let Some((width, height)) = compute_image_dimensions(&file_data) {
+                    metadata.width = Some(width);
+                    metadata.height = Some(height);
+                    metadata.size_bytes = Some(file_data.len() as i64);
+                    true
+                } else {
+                    false
+                }
+            }
+            MessageContent::Sticker { metadata, .. } => {
+                metadata.size_bytes = Some(file_data.len() as i64);
+                true
+            }
+            MessageContent::Video { metadata, .. } => {
+                // Dimensions and duration would need ffprobe; the poster frame
+                // is attempted once, together with the size.
+                metadata.size_bytes = Some(file_data.len() as i64);
+                metadata.thumbnail_hash = store_video_poster(conn, &file_hash, &file_data);
+                true
+            }
+            MessageContent::Document { metadata, .. } => {
+                metadata.size_bytes = Some(file_data.len() as i64);
+                // Word count would need document parsing library
+                true
+            }
+            MessageContent::Audio { metadata, .. } => {
+                metadata.size_bytes = Some(file_data.len() as i64);
+                // Duration would need audio parsing
+                true
+            }
+            MessageContent::Text { .. } | MessageContent::Task { .. } => false,
+        };
+
+        // Cache in DB if updated
+        if updated {
+            if let Some(metadata) = self.content.metadata() {
+                if let Ok(json) = serde_json::to_string(metadata) {
+                    let _ = crate::storage::db::update_content_metadata(conn, &self.id, &json);
+                }
+            }
+        }
+
+        updated
+    }
+}
+
+/// Grab a frame near the start of the video at `path` with the system
+/// `ffmpeg` and scale it to a JPEG thumbnail. `None` if ffmpeg is missing or
+/// can't decode the file.
+pub fn video_poster(path: &std::path::Path) -> Option<Vec<u8>> {
+    // Clips shorter than the first seek point have no frame there.
+    for seek in ["1", "0"] {
+        let output = std::process::Command::new("ffmpeg")
+            .args(["-v", "error", "-ss", seek, "-i"])
+            .arg(path)
+            .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"])
+            .output()
+            .ok()?;
+        if !output.status.success() || output.stdout.is_empty() {
+            continue;
+        }
+        let frame = image::load_from_memory(&output.stdout).ok()?;
+        let thumbnail = frame
+            .thumbnail(POSTER_MAX_SIDE_PX, POSTER_MAX_SIDE_PX)
+            .to_rgb8();
+        let mut out = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, POSTER_JPEG_QUALITY)
+            .encode_image(&thumbnail)
+            .ok()?;
+        return Some(out);
+    }
+    None
+}
+
+/// Store a poster frame for a received video, which only exists in the
+/// object store, via a temporary copy ffmpeg can seek in.
+fn store_video_poster(
+    conn: &rusqlite::Connection,
+    file_hash: &str,
+    file_data: &[u8],
+) -> Option<String> {
+    let temp_path = std::env::temp_dir().join(format!("rchat-poster-{}", file_hash));
+    std::fs::write(&temp_path, file_data).ok()?;
+    let poster = video_poster(&temp_path);
+    let _ = std::fs::remove_file(&temp_path);
+    crate::storage::object::create(conn, &poster?, Some("poster.jpg"), Some("image/jpeg"), None)
+        .map_err(|e| eprintln!("[Hydrate] Failed to store poster for {}: {}", file_hash, e))
+        .ok()
+}
+
+/// Compute image dimensions from raw bytes
+fn compute_image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    use image::ImageReader;
+    use std::io::Cursor;
+
+    match ImageReader::new(Cursor::new(data)).with_guessed_format() {
+        Ok(reader) => match reader.into_dimensions() {
+            Ok((w, h)) => Some((w, h)),
+            Err(e) => {
+                eprintln!("[Hydrate] Failed to get dimensions: {}", e);
+                None
+            }
+        },
+        Err(e) => {
+            eprintln!("[Hydrate] Failed to read image: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_status_roundtrip() {
+        assert_eq!(MessageStatus::from_str("pending"), MessageStatus::Pending);
+        assert_eq!(
+            MessageStatus::from_str("delivered"),
+            MessageStatus::Delivered
+        );
+        assert_eq!(MessageStatus::from_str("read"), MessageStatus::Read);
+        assert_eq!(MessageStatus::Pending.as_str(), "pending");
+        assert_eq!(
+            MessageStatus::from_str(MessageStatus::Sent.as_str()),
+            MessageStatus::Sent
+        );
+    }
+
+    #[test]
+    fn test_message_content_serialization() {
+        let content = MessageContent::Photo {
+            file_hash: "abc123".to_string(),
+            caption: Some("Test".to_string()),
+            metadata: ContentMetadata {
+                width: Some(1920),
+                height: Some(1080),
+                ..Default::default()
+            },
+        };
+
+        let json = serde_json::to_string(&content).unwrap();
+        assert!(json.contains("\"type\":\"photo\""));
+        assert!(json.contains("\"width\":1920"));
+    }
+
+    #[test]
+    fn test_flat_parts_roundtrip() {
+        let photo = MessageContent::from_parts(
+            "photo",
+            Some("beach".to_string()),
+            Some("abc".to_string()),
+            ContentMetadata::default(),
+        );
+        assert_eq!(photo.text(), Some("beach"));
+        assert_eq!(
+            photo.into_parts(),
+            ("image", Some("beach".to_string()), Some("abc".to_string()))
+        );
+
+        let video = MessageContent::from_parts(
+            "video",
+            Some("clip.mp4".to_string()),
+            Some("def".to_string()),
+            ContentMetadata::default(),
+        );
+        assert_eq!(video.text(), None);
+        assert!(video.matches_parts("video", Some("clip.mp4"), Some("def")));
+        assert!(!video.matches_parts("audio", Some("clip.mp4"), Some("def")));
+
+        let task = MessageContent::from_parts(
+            "task",
+            Some("buy milk".to_string()),
+            None,
+            ContentMetadata::default(),
+        );
+        assert_eq!(task.content_type(), "task");
+        assert_eq!(task.file_hash(), None);
+
+        let unknown = MessageContent::from_parts(
+            "hologram",
+            Some("hi".to_string()),
+            None,
+            ContentMetadata::default(),
+        );
+        assert_eq!(
+            unknown,
+            MessageContent::Text {
+                text: "hi".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Message ids.
+//!
+//! New messages get a UUIDv7 (RFC 9562): a 48-bit Unix millisecond timestamp
+//! followed by random bits, so ids from different devices don't collide and
+//! sort by creation time. Within this process ids are strictly increasing:
+//! the 12 bits after the version are a counter for ids created in the same
+//! millisecond, and a clock that goes backwards keeps the last timestamp.
+//!
+//! Messages created before this were stored as `<unix seconds>-<random u32>`.
+//! Those ids are kept as they are; [`is_message_id`] accepts both forms.
+
+use rand::RngCore;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_COUNTER: u16 = 0x0fff;
+
+/// Timestamp and counter of the last id handed out.
+static LAST: Mutex<(u64, u16)> = Mutex::new((0, 0));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A new message id.
+pub fn new_message_id() -> String {
+    let now = now_millis();
+    let (millis, counter) = {
+        let mut last = LAST.lock().unwrap_or_else(|e| e.into_inner());
+        *last = next_timestamp(*last, now);
+        *last
+    };
+    let mut random = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut random);
+    format_uuid_v7(millis, counter, random)
+}
+
+/// Timestamp and counter for the id after `last` at wall-clock `now`.
+fn next_timestamp((last_millis, last_counter): (u64, u16), now: u64) -> (u64, u16) {
+    if now > last_millis {
+        (now, 0)
+    } else if last_counter < MAX_COUNTER {
+        (last_millis, last_counter + 1)
+    } else {
+        // Counter exhausted: borrow the next millisecond.
+        (last_millis + 1, 0)
+    }
+}
+
+fn format_uuid_v7(millis: u64, counter: u16, random: [u8; 8]) -> String {
+    let mut bytes = [0u8; 16];
+    bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+    bytes[6] = 0x70 | ((counter >> 8) as u8 & 0x0f);
+    bytes[7] = counter as u8;
+    bytes[8..].copy_from_slice(&random);
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn is_uuid_v7(id: &str) -> bool {
+    let groups: Vec<&str> = id.split('-').collect();
+    groups.iter().map(|g| g.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|g| g.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')))
+        && groups[2].starts_with('7')
+        && matches!(groups[3].as_bytes()[0], b'8' | b'9' | b'a' | b'b')
+}
+
+fn is_legacy_message_id(id: &str) -> bool {
+    let mut parts = id.split('-');
+    let (Some(ts), Some(rand), None) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    ts.parse::<i64>().is_ok() && rand.parse::<u32>().is_ok()
+}
+
+/// Whether `id` has the shape of a stored chat message id, new or legacy.
+pub fn is_message_id(id: &str) -> bool {
+    is_uuid_v7(id) || is_legacy_message_id(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_v7_and_strictly_increasing() {
+        let ids: Vec<String> = (0..2000).map(|_| new_message_id()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|id| is_message_id(id) && is_uuid_v7(id)));
+
+        let id = format_uuid_v7(0x0186_9a3b_2c4d, 0x0abc, [0xff; 8]);
+        assert_eq!(id, "01869a3b-2c4d-7abc-bfff-ffffffffffff");
+
+        assert_eq!(next_timestamp((10, 3), 11), (11, 0));
+        assert_eq!(next_timestamp((10, 3), 9), (10, 4));
+        assert_eq!(next_timestamp((10, MAX_COUNTER), 10), (11, 0));
+
+        assert!(is_message_id("1712345678901-4000000000"));
+        assert!(!is_message_id("call-1712345678901-42"));
+        assert!(!is_message_id("01869a3b-2c4d-4abc-bfff-ffffffffffff"));
+    }
+}
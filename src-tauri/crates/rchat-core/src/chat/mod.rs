@@ -0,0 +1,4 @@
+pub mod export;
+pub mod media_guard;
+pub mod message;
+pub mod message_id;
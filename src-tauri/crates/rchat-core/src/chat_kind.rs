@@ -110,6 +110,11 @@ pub fn generate_temp_direct_chat_id() -> String {
     format!("tempdm:{}", generate_uuid_v4())
 }
 
+/// Imported history is archived: readable, but not tied to a live chat.
+pub fn generate_imported_chat_id(source: &str) -> String {
+    format!("archived:import-{}:{}", source, generate_uuid_v4())
+}
+
 fn generate_uuid_v4() -> String {
     let mut bytes = [0u8; 16];
     rand::thread_rng().fill_bytes(&mut bytes);
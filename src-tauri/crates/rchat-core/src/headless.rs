@@ -0,0 +1,46 @@
+//! What the app can do without Tauri, for the `rchat-cli` binary: run a
+//! direct-message/relay node and read the local database.
+
+use std::path::Path;
+
+pub use crate::chat::export::{ExportFormat, ExportSummary, MediaExportMode};
+pub use crate::network::headless::{
+    default_key_path, load_or_create_key, HeadlessNode, IncomingText,
+};
+pub use crate::storage::db::ChatListItem;
+
+/// Chats in this machine's rchat database.
+pub fn list_chats() -> anyhow::Result<Vec<ChatListItem>> {
+    let conn = crate::storage::db::connect_to_db()?;
+    crate::storage::db::list_chats(&conn)
+}
+
+/// Export a chat from this machine's rchat database, as the app's export
+/// does: temporary chats can't be exported, restricted media can't leave.
+pub fn export_chat(
+    chat_id: &str,
+    format: ExportFormat,
+    media: MediaExportMode,
+    out_path: &Path,
+) -> anyhow::Result<ExportSummary> {
+    if matches!(
+        crate::chat_kind::parse_chat_kind(chat_id),
+        crate::chat_kind::ChatKind::TemporaryDirect | crate::chat_kind::ChatKind::TemporaryGroup
+    ) {
+        anyhow::bail!("Temporary chats must be archived before exporting");
+    }
+    let conn = crate::storage::db::connect_to_db()?;
+    if !crate::storage::db::chat_exists(&conn, chat_id) {
+        anyhow::bail!("No chat {}", chat_id);
+    }
+    if media != MediaExportMode::None {
+        crate::chat::media_guard::ensure_chat_media_exportable(&conn, chat_id)?;
+    }
+    crate::chat::export::export_chat(
+        &std::sync::Mutex::new(conn),
+        chat_id,
+        format,
+        media,
+        out_path,
+    )
+}
@@ -0,0 +1,17 @@
+//! The parts of rchat that don't need Tauri: the SQLite store and object
+//! chunks, the encrypted config and vault, message content and export,
+//! invites and the friend key tree, and the direct-message protocol. The
+//! desktop app re-exports these at their old paths; `rchat-cli` uses them
+//! through [`headless`].
+//!
+//! The desktop `NetworkManager` is not here. It is driven by the app's Tauri
+//! state and events throughout, and the headless node runs its own swarm over
+//! the same protocol instead.
+
+pub mod chat;
+pub mod chat_identity;
+pub mod chat_kind;
+pub mod headless;
+pub mod logging;
+pub mod network;
+pub mod storage;
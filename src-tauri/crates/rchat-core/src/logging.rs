@@ -0,0 +1,116 @@
+//! What may appear in log output.
+//!
+//! Logs go to stdout/stderr and end up pasted into bug reports, so they
+//! follow one policy:
+//! - Message bodies, tokens and passwords are never logged, and neither is
+//!   anything derived from them (a password's length included). Log ids and
+//!   sizes instead.
+//! - Peer ids go through [`peer`], which logs a short hash. That still lets
+//!   you follow one peer through a log without identifying them. Debug output
+//!   of libp2p types (events, dial errors, multiaddrs) goes through [`scrub`],
+//!   which does the same for every peer id in it. Set `RCHAT_DEBUG_LOGS=1` to
+//!   log full peer ids while debugging.
+
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const DEBUG_LOGS_ENV: &str = "RCHAT_DEBUG_LOGS";
+
+static FULL_PEER_IDS: AtomicBool = AtomicBool::new(false);
+
+/// Read the debug flag from the environment; called once at startup.
+pub fn init() {
+    let enabled = std::env::var(DEBUG_LOGS_ENV)
+        .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+        .unwrap_or(false);
+    FULL_PEER_IDS.store(enabled, Ordering::Relaxed);
+    if enabled {
+        println!(
+            "[Logging] ⚠️ {} is set, logging full peer ids",
+            DEBUG_LOGS_ENV
+        );
+    }
+}
+
+/// A peer id as it may be logged.
+pub fn peer<T: Display + ?Sized>(id: &T) -> String {
+    redact_peer(&id.to_string(), FULL_PEER_IDS.load(Ordering::Relaxed))
+}
+
+/// `text` with every peer id in it replaced as [`peer`] would log it.
+pub fn scrub(text: &str) -> String {
+    scrub_peers(text, FULL_PEER_IDS.load(Ordering::Relaxed))
+}
+
+fn is_base58(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() && !matches!(ch, '0' | 'O' | 'I' | 'l')
+}
+
+/// Ed25519 peer ids (`12D3KooW…`, 52 chars) and legacy SHA-256 ones (`Qm…`,
+/// 46 chars), as base58 runs.
+fn looks_like_peer_id(run: &str) -> bool {
+    (run.starts_with("12D3KooW") && run.len() == 52) || (run.starts_with("Qm") && run.len() == 46)
+}
+
+fn scrub_peers(text: &str, full: bool) -> String {
+    if full {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(is_base58) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|ch: char| !is_base58(ch)).unwrap_or(rest.len());
+        let run = &rest[..end];
+        if looks_like_peer_id(run) {
+            out.push_str(&redact_peer(run, false));
+        } else {
+            out.push_str(run);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn redact_peer(id: &str, full: bool) -> String {
+    if full {
+        return id.to_string();
+    }
+    let digest = Sha256::digest(id.as_bytes());
+    format!("peer#{}", hex::encode(&digest[..4]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_ids_are_hashed_unless_debugging() {
+        let id = "12D3KooWLk1GoEB3MbHbRLHTxXrvNGSxC2UALaCuKAgKuYXkXazU";
+        let redacted = redact_peer(id, false);
+        assert!(redacted.starts_with("peer#"));
+        assert_eq!(redacted.len(), "peer#".len() + 8);
+        assert!(!redacted.contains("KooW"));
+        assert_eq!(redacted, redact_peer(id, false));
+        assert_ne!(redacted, redact_peer("12D3KooWOther", false));
+        assert_eq!(redact_peer(id, true), id);
+    }
+
+    #[test]
+    fn scrub_hashes_peer_ids_inside_text() {
+        let id = "12D3KooWLk1GoEB3MbHbRLHTxXrvNGSxC2UALaCuKAgKuYXkXazU";
+        let text = format!("/ip4/1.2.3.4/tcp/1/p2p/{}/p2p-circuit, peer_id: {}", id, id);
+        let scrubbed = scrub_peers(&text, false);
+        assert!(!scrubbed.contains(id));
+        assert_eq!(scrubbed.matches(&redact_peer(id, false)).count(), 2);
+        assert!(scrubbed.starts_with("/ip4/1.2.3.4/tcp/1/p2p/peer#"));
+        assert_eq!(
+            scrub_peers("Dial error: timeout", false),
+            "Dial error: timeout"
+        );
+        assert_eq!(scrub_peers(&text, true), text);
+    }
+}
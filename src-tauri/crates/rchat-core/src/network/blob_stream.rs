@@ -0,0 +1,118 @@
+//! `/rchat/blob/1.0.0`: chunk streaming for large files.
+//!
+//! Fetching a file over direct messages costs one CBOR request and response
+//! per chunk, all in flight at once. For files of at least
+//! [`BLOB_STREAM_THRESHOLD`] bytes the downloader instead opens a blob stream
+//! once the file metadata (the chunk manifest) arrives, sends a
+//! [`BlobRequest`] naming the chunks it wants and reads one [`BlobChunk`] per
+//! chunk, sealed with the same per-peer chunk key as `ChunkResponse`s. Records
+//! are written one at a time, so a slow reader holds the sender back through
+//! the stream's flow control. Peers that don't speak the protocol fail the
+//! stream negotiation and get per-chunk requests as before.
+
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub const BLOB_PROTOCOL: &str = "/rchat/blob/1.0.0";
+/// Files at least this large are streamed.
+pub const BLOB_STREAM_THRESHOLD: i64 = 4 * 1024 * 1024;
+const BLOB_RECORD_LEN: usize = 4;
+/// Chunks are at most 64 KB; sealed and base64-encoded they stay well below.
+pub const MAX_BLOB_RECORD_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobRequest {
+    pub file_hash: String,
+    /// Chunks to send, in order.
+    pub chunk_hashes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobChunk {
+    pub chunk_hash: String,
+    /// Base64 ciphertext, see `crate::network::chunk_crypto`.
+    pub data: String,
+    pub nonce: String,
+}
+
+pub async fn write_blob_record<W, T>(writer: &mut W, record: &T) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let encoded = serde_json::to_vec(record)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    if encoded.len() > MAX_BLOB_RECORD_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "blob record too large",
+        ));
+    }
+    writer
+        .write_all(&(encoded.len() as u32).to_be_bytes())
+        .await?;
+    writer.write_all(&encoded).await?;
+    writer.flush().await
+}
+
+pub async fn read_blob_record<R, T>(reader: &mut R) -> std::io::Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut len_buf = [0u8; BLOB_RECORD_LEN];
+    reader.read_exact(&mut len_buf).await?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_BLOB_RECORD_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "blob record too large",
+        ));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_roundtrip_and_reject_oversized_lengths() {
+        let request = BlobRequest {
+            file_hash: "file".to_string(),
+            chunk_hashes: vec!["a".to_string(), "b".to_string()],
+        };
+        let chunk = BlobChunk {
+            chunk_hash: "a".to_string(),
+            data: "c2VhbGVk".to_string(),
+            nonce: "bm9uY2U=".to_string(),
+        };
+        let mut buf = Vec::new();
+        write_blob_record(&mut buf, &request).await.unwrap();
+        write_blob_record(&mut buf, &chunk).await.unwrap();
+
+        let mut reader = futures::io::Cursor::new(buf);
+        assert_eq!(
+            read_blob_record::<_, BlobRequest>(&mut reader)
+                .await
+                .unwrap(),
+            request
+        );
+        assert_eq!(
+            read_blob_record::<_, BlobChunk>(&mut reader).await.unwrap(),
+            chunk
+        );
+        let eof = read_blob_record::<_, BlobChunk>(&mut reader).await;
+        assert_eq!(eof.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let oversized = ((MAX_BLOB_RECORD_BYTES as u32) + 1).to_be_bytes().to_vec();
+        let err = read_blob_record::<_, BlobChunk>(&mut futures::io::Cursor::new(oversized))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
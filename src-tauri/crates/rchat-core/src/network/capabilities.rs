@@ -0,0 +1,179 @@
+//! Optional features peers advertise to each other.
+//!
+//! Every rchat peer sends its identify agent version as
+//! `rchat/<version> (caps: e2ee-v2,blob-stream,receipts)`. Peers from before
+//! the list existed only send the `rchat/1.0.0` protocol version; they get the
+//! [`LEGACY`] set, plus `blob-stream` if they list the blob protocol. Anything
+//! else (relays, other libp2p nodes) supports nothing. Optional protocols are
+//! only used with peers that advertise them, so old and new versions keep
+//! talking.
+
+use std::collections::{BTreeSet, HashMap};
+
+use libp2p::PeerId;
+use serde::Serialize;
+
+const AGENT_PREFIX: &str = "rchat/";
+const CAPS_MARKER: &str = "(caps:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// End-to-end sealed file chunks and group envelopes; an unsealed chunk
+    /// from such a peer is a downgrade and gets dropped.
+    E2eeV2,
+    /// `/rchat/blob/1.0.0` chunk streaming.
+    BlobStream,
+    /// Message reactions. Not implemented here yet, so never advertised.
+    Reactions,
+    /// Read receipts.
+    Receipts,
+}
+
+impl Capability {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::E2eeV2 => "e2ee-v2",
+            Self::BlobStream => "blob-stream",
+            Self::Reactions => "reactions",
+            Self::Receipts => "receipts",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "e2ee-v2" => Some(Self::E2eeV2),
+            "blob-stream" => Some(Self::BlobStream),
+            "reactions" => Some(Self::Reactions),
+            "receipts" => Some(Self::Receipts),
+            _ => None,
+        }
+    }
+}
+
+/// What this build supports.
+pub const LOCAL: &[Capability] = &[
+    Capability::E2eeV2,
+    Capability::BlobStream,
+    Capability::Receipts,
+];
+
+/// What rchat peers without a capability list support.
+pub const LEGACY: &[Capability] = &[Capability::Receipts];
+
+pub fn local_agent_version() -> String {
+    agent_version(LOCAL)
+}
+
+/// The identify agent version of a node supporting `caps`.
+pub fn agent_version(caps: &[Capability]) -> String {
+    let caps: Vec<&str> = caps.iter().map(|cap| cap.as_str()).collect();
+    format!(
+        "{}{} {} {})",
+        AGENT_PREFIX,
+        env!("CARGO_PKG_VERSION"),
+        CAPS_MARKER,
+        caps.join(",")
+    )
+}
+
+pub type PeerCapabilities = BTreeSet<Capability>;
+
+/// Capabilities from a peer's identify info. Unknown names are skipped.
+pub fn from_identify<'a>(
+    protocol_version: &str,
+    agent_version: &str,
+    mut protocols: impl Iterator<Item = &'a str>,
+) -> PeerCapabilities {
+    if agent_version.starts_with(AGENT_PREFIX) {
+        if let Some((_, list)) = agent_version.split_once(CAPS_MARKER) {
+            return list
+                .trim_end_matches(')')
+                .split(',')
+                .filter_map(Capability::parse)
+                .collect();
+        }
+    }
+    if !protocol_version.starts_with(AGENT_PREFIX) {
+        return PeerCapabilities::new();
+    }
+    let mut caps: PeerCapabilities = LEGACY.iter().copied().collect();
+    if protocols.any(|protocol| protocol == super::blob_stream::BLOB_PROTOCOL) {
+        caps.insert(Capability::BlobStream);
+    }
+    caps
+}
+
+/// Capabilities of the peers we've identified.
+#[derive(Default)]
+pub struct CapabilityRegistry {
+    by_peer: HashMap<PeerId, PeerCapabilities>,
+}
+
+impl CapabilityRegistry {
+    /// Returns true when the peer's capabilities changed.
+    pub fn record(&mut self, peer: PeerId, caps: PeerCapabilities) -> bool {
+        self.by_peer.insert(peer, caps.clone()).as_ref() != Some(&caps)
+    }
+
+    pub fn get(&self, peer: &PeerId) -> Option<&PeerCapabilities> {
+        self.by_peer.get(peer)
+    }
+
+    /// Peers not identified yet are treated as legacy rchat peers.
+    pub fn supports(&self, peer: &PeerId, cap: Capability) -> bool {
+        match self.by_peer.get(peer) {
+            Some(caps) => caps.contains(&cap),
+            None => LEGACY.contains(&cap),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_legacy_and_foreign_peers_are_told_apart() {
+        let ours = from_identify("rchat/1.0.0", &local_agent_version(), std::iter::empty());
+        assert_eq!(ours, LOCAL.iter().copied().collect());
+        assert!(!ours.contains(&Capability::Reactions));
+
+        let newer = from_identify(
+            "rchat/1.0.0",
+            "rchat/9.0.0 (caps: receipts, reactions,teleport)",
+            std::iter::empty(),
+        );
+        assert_eq!(
+            newer,
+            [Capability::Reactions, Capability::Receipts]
+                .into_iter()
+                .collect()
+        );
+
+        let legacy = from_identify(
+            "rchat/1.0.0",
+            "rust-libp2p/0.44.0",
+            ["/rchat/dm/1.0.0", super::super::blob_stream::BLOB_PROTOCOL].into_iter(),
+        );
+        assert_eq!(
+            legacy,
+            [Capability::BlobStream, Capability::Receipts]
+                .into_iter()
+                .collect()
+        );
+        assert!(from_identify("ipfs/0.1.0", "rust-libp2p/0.44.0", std::iter::empty()).is_empty());
+        // Headless nodes advertise an empty list, which isn't the legacy set.
+        assert!(from_identify("rchat/1.0.0", &agent_version(&[]), std::iter::empty()).is_empty());
+
+        let mut registry = CapabilityRegistry::default();
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        assert!(registry.supports(&peer, Capability::Receipts));
+        assert!(!registry.supports(&peer, Capability::BlobStream));
+        assert!(registry.record(peer, ours.clone()));
+        assert!(!registry.record(peer, ours));
+        assert!(registry.supports(&peer, Capability::E2eeV2));
+    }
+}
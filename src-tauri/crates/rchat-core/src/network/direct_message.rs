@@ -0,0 +1,332 @@
+//! Direct Message Protocol for 1:1 chats
+//! Uses libp2p request-response for reliable message delivery
+
+use serde::{Deserialize, Serialize};
+
+/// Request-response protocol carrying [`DirectMessageRequest`]s.
+pub const DM_PROTOCOL: &str = "/rchat/dm/1.0.0";
+
+/// Chunk metadata for file transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkInfo {
+    pub chunk_hash: String,
+    pub chunk_order: i64,
+    pub chunk_size: i64,
+}
+
+/// Wire-level message kind for request-response DMs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectMessageKind {
+    Text,
+    Image,
+    Sticker,
+    Document,
+    Video,
+    Audio,
+    ReadReceipt,
+    FileMetadataRequest,
+    FileMetadataResponse,
+    ChunkRequest,
+    ChunkResponse,
+    InviteHandshake,
+    TempHandshake,
+    CallOffer,
+    CallOfferVideo,
+    CallAccept,
+    CallAcceptVideo,
+    CallReject,
+    CallBusy,
+    CallEnd,
+    BroadcastOffer,
+    BroadcastAccept,
+    BroadcastReject,
+    BroadcastBusy,
+    BroadcastEnd,
+    /// One piece of an oversized request; see `crate::network::fragment`.
+    Fragment,
+    /// Profile sync: sender's alias (`sender_alias`), avatar object hash
+    /// (`file_hash`), which friends fetch with the file protocol, and mDNS
+    /// discovery key (`text_content`) for resolving anonymous advertisements.
+    IdentityUpdate,
+    /// The sender pressed Connect; `text_content` holds a
+    /// `connection_request::SignedConnectionRequest`.
+    ConnectionRequest,
+    /// Redeeming a group invite; `text_content` holds a
+    /// `group_invite::GroupJoinRequest`.
+    GroupJoinRequest,
+    /// Answer to a `GroupJoinRequest`; `text_content` holds a
+    /// `group_invite::GroupWelcome` sealed with the invite's welcome key.
+    GroupWelcome,
+    /// Asks an admin for a page of group history; `text_content` holds a
+    /// `group_history::HistoryRequest`.
+    GroupHistoryRequest,
+    /// `text_content` holds a `group_history::HistoryPage`.
+    GroupHistoryPage,
+    /// A kind added after this build; acknowledged and otherwise ignored.
+    #[serde(other)]
+    Unknown,
+}
+
+impl DirectMessageKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Image => "image",
+            Self::Sticker => "sticker",
+            Self::Document => "document",
+            Self::Video => "video",
+            Self::Audio => "audio",
+            Self::ReadReceipt => "read_receipt",
+            Self::FileMetadataRequest => "file_metadata_request",
+            Self::FileMetadataResponse => "file_metadata_response",
+            Self::ChunkRequest => "chunk_request",
+            Self::ChunkResponse => "chunk_response",
+            Self::InviteHandshake => "invite_handshake",
+            Self::TempHandshake => "temp_handshake",
+            Self::CallOffer => "call_offer",
+            Self::CallOfferVideo => "call_offer_video",
+            Self::CallAccept => "call_accept",
+            Self::CallAcceptVideo => "call_accept_video",
+            Self::CallReject => "call_reject",
+            Self::CallBusy => "call_busy",
+            Self::CallEnd => "call_end",
+            Self::BroadcastOffer => "broadcast_offer",
+            Self::BroadcastAccept => "broadcast_accept",
+            Self::BroadcastReject => "broadcast_reject",
+            Self::BroadcastBusy => "broadcast_busy",
+            Self::BroadcastEnd => "broadcast_end",
+            Self::Fragment => "fragment",
+            Self::IdentityUpdate => "identity_update",
+            Self::ConnectionRequest => "connection_request",
+            Self::GroupJoinRequest => "group_join_request",
+            Self::GroupWelcome => "group_welcome",
+            Self::GroupHistoryRequest => "group_history_request",
+            Self::GroupHistoryPage => "group_history_page",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn needs_file_transfer(self) -> bool {
+        matches!(
+            self,
+            Self::Image | Self::Sticker | Self::Document | Self::Video | Self::Audio
+        )
+    }
+
+    /// Bandwidth accounting bucket for this kind.
+    pub fn bandwidth_protocol(self) -> &'static str {
+        match self {
+            Self::FileMetadataRequest
+            | Self::FileMetadataResponse
+            | Self::ChunkRequest
+            | Self::ChunkResponse => crate::storage::bandwidth::FILE_TRANSFER,
+            _ => crate::storage::bandwidth::DIRECT_MESSAGE,
+        }
+    }
+}
+
+/// Machine translation sent alongside the original text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageTranslation {
+    /// Target language code, e.g. "de".
+    pub lang: String,
+    pub text: String,
+}
+
+/// Direct message request - sent from sender to recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessageRequest {
+    /// Unique message ID
+    pub id: String,
+    /// Sender's peer ID
+    pub sender_id: String,
+    /// Message type.
+    pub msg_type: DirectMessageKind,
+    /// Text content (for text messages)
+    pub text_content: Option<String>,
+    /// File hash (for image messages and file transfer)
+    pub file_hash: Option<String>,
+    /// Unix timestamp
+    pub timestamp: i64,
+
+    // === Chunk Transfer Fields ===
+    /// Chunk hash (for chunk_request)
+    pub chunk_hash: Option<String>,
+    /// Base64-encoded chunk data (for chunk_response)
+    pub chunk_data: Option<String>,
+    /// Base64 nonce when `chunk_data` is sealed with the per-peer chunk key
+    #[serde(default)]
+    pub chunk_nonce: Option<String>,
+    /// List of chunks (for file_metadata_response)
+    pub chunk_list: Option<Vec<ChunkInfo>>,
+    /// Sender's display name/alias
+    #[serde(default)]
+    pub sender_alias: Option<String>,
+    /// Translation of `text_content` when the chat has "send translated" on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<MessageTranslation>,
+    /// Sender's logical clock for the chat message, see
+    /// `storage::db::Message::logical`. Absent from older peers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logical: Option<i64>,
+    /// Typed content of a chat message, with the sender's media metadata.
+    /// Has to agree with `msg_type`, `text_content` and `file_hash`, which
+    /// stay for older peers; absent before protocol version 3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<crate::chat::message::MessageContent>,
+    /// Sender's protocol version, see `crate::network::wire`. All zeros from
+    /// older peers.
+    #[serde(default)]
+    pub wire: crate::network::wire::WireVersion,
+}
+
+impl DirectMessageRequest {
+    /// Rough encoded size: the variable-length fields plus a fixed allowance
+    /// for field names and framing. Cheap enough to call on every message.
+    pub fn approx_wire_len(&self) -> usize {
+        const FIXED_OVERHEAD: usize = 128;
+        let opt = |value: &Option<String>| value.as_ref().map_or(0, String::len);
+        FIXED_OVERHEAD
+            + self.id.len()
+            + self.sender_id.len()
+            + opt(&self.text_content)
+            + opt(&self.file_hash)
+            + opt(&self.chunk_hash)
+            + opt(&self.chunk_data)
+            + opt(&self.chunk_nonce)
+            + opt(&self.sender_alias)
+            + self.chunk_list.as_ref().map_or(0, |chunks| {
+                chunks.iter().map(|chunk| chunk.chunk_hash.len() + 32).sum()
+            })
+            + self.translation.as_ref().map_or(0, |translation| {
+                translation.lang.len() + translation.text.len()
+            })
+            // Typed content repeats the flat fields plus a little metadata.
+            + self.content.as_ref().map_or(0, |_| {
+                FIXED_OVERHEAD + opt(&self.text_content) + opt(&self.file_hash)
+            })
+    }
+}
+
+/// Direct message response - sent back to sender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessageResponse {
+    /// Original message ID
+    pub msg_id: String,
+    /// Status: "delivered", "error", or "ignored" for a type or protocol
+    /// version the recipient doesn't support
+    pub status: String,
+    /// Error message if status is "error"
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_kind_serialization_is_wire_compatible() {
+        let kinds = [
+            (DirectMessageKind::Text, "\"text\""),
+            (DirectMessageKind::Image, "\"image\""),
+            (DirectMessageKind::Sticker, "\"sticker\""),
+            (DirectMessageKind::Document, "\"document\""),
+            (DirectMessageKind::Video, "\"video\""),
+            (DirectMessageKind::Audio, "\"audio\""),
+            (DirectMessageKind::ReadReceipt, "\"read_receipt\""),
+            (
+                DirectMessageKind::FileMetadataRequest,
+                "\"file_metadata_request\"",
+            ),
+            (
+                DirectMessageKind::FileMetadataResponse,
+                "\"file_metadata_response\"",
+            ),
+            (DirectMessageKind::ChunkRequest, "\"chunk_request\""),
+            (DirectMessageKind::ChunkResponse, "\"chunk_response\""),
+            (DirectMessageKind::InviteHandshake, "\"invite_handshake\""),
+            (DirectMessageKind::TempHandshake, "\"temp_handshake\""),
+            (DirectMessageKind::CallOffer, "\"call_offer\""),
+            (DirectMessageKind::CallOfferVideo, "\"call_offer_video\""),
+            (DirectMessageKind::CallAccept, "\"call_accept\""),
+            (DirectMessageKind::CallAcceptVideo, "\"call_accept_video\""),
+            (DirectMessageKind::CallReject, "\"call_reject\""),
+            (DirectMessageKind::CallBusy, "\"call_busy\""),
+            (DirectMessageKind::CallEnd, "\"call_end\""),
+            (DirectMessageKind::BroadcastOffer, "\"broadcast_offer\""),
+            (DirectMessageKind::BroadcastAccept, "\"broadcast_accept\""),
+            (DirectMessageKind::BroadcastReject, "\"broadcast_reject\""),
+            (DirectMessageKind::BroadcastBusy, "\"broadcast_busy\""),
+            (DirectMessageKind::BroadcastEnd, "\"broadcast_end\""),
+            (DirectMessageKind::Fragment, "\"fragment\""),
+            (DirectMessageKind::IdentityUpdate, "\"identity_update\""),
+            (
+                DirectMessageKind::ConnectionRequest,
+                "\"connection_request\"",
+            ),
+            (
+                DirectMessageKind::GroupJoinRequest,
+                "\"group_join_request\"",
+            ),
+            (DirectMessageKind::GroupWelcome, "\"group_welcome\""),
+            (
+                DirectMessageKind::GroupHistoryRequest,
+                "\"group_history_request\"",
+            ),
+            (
+                DirectMessageKind::GroupHistoryPage,
+                "\"group_history_page\"",
+            ),
+        ];
+
+        for (kind, expected_json) in kinds {
+            let encoded = serde_json::to_string(&kind).expect("serialize kind");
+            assert_eq!(encoded, expected_json);
+        }
+    }
+
+    #[test]
+    fn test_file_transfer_kinds_cover_document_and_video() {
+        assert!(DirectMessageKind::Image.needs_file_transfer());
+        assert!(DirectMessageKind::Sticker.needs_file_transfer());
+        assert!(DirectMessageKind::Document.needs_file_transfer());
+        assert!(DirectMessageKind::Video.needs_file_transfer());
+        assert!(DirectMessageKind::Audio.needs_file_transfer());
+        assert!(!DirectMessageKind::Text.needs_file_transfer());
+    }
+
+    #[test]
+    fn test_unknown_kinds_and_unversioned_requests_still_parse() {
+        let legacy: DirectMessageRequest = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "sender_id": "peer",
+            "msg_type": "text",
+            "text_content": "hi",
+            "file_hash": null,
+            "timestamp": 1,
+            "chunk_hash": null,
+            "chunk_data": null,
+            "chunk_list": null,
+        }))
+        .expect("legacy request");
+        assert_eq!(legacy.wire, crate::network::wire::WireVersion::default());
+        assert!(legacy.wire.is_supported());
+
+        let newer: DirectMessageRequest = serde_json::from_value(serde_json::json!({
+            "id": "m2",
+            "sender_id": "peer",
+            "msg_type": "poll_vote",
+            "text_content": null,
+            "file_hash": null,
+            "timestamp": 1,
+            "chunk_hash": null,
+            "chunk_data": null,
+            "chunk_list": null,
+            "wire": { "version": 4, "min_supported_version": 2 },
+        }))
+        .expect("request of a newer kind");
+        assert_eq!(newer.msg_type, DirectMessageKind::Unknown);
+        assert_eq!(newer.wire.version, 4);
+    }
+}
@@ -0,0 +1,285 @@
+//! A node without the app around it, for `rchat-cli`.
+//!
+//! It speaks the app's direct message protocol, so it can send texts to rchat
+//! peers and receive theirs, and can serve as a circuit relay for peers behind
+//! NAT. It advertises no optional capabilities: groups, calls, receipts and
+//! file transfer stay in the app. Logs go to stderr, leaving stdout to the
+//! CLI's output.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{self, OutboundRequestId, ProtocolSupport};
+use libp2p::swarm::{behaviour::toggle::Toggle, NetworkBehaviour, SwarmEvent};
+use libp2p::{identify, identity::Keypair, ping, relay, Multiaddr, PeerId, Swarm, SwarmBuilder};
+use serde::Serialize;
+
+use super::direct_message::{
+    DirectMessageKind, DirectMessageRequest, DirectMessageResponse, DM_PROTOCOL,
+};
+
+/// How long [`HeadlessNode::send_text`] waits for the peer's answer.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(NetworkBehaviour)]
+pub struct HeadlessBehaviour {
+    identify: identify::Behaviour,
+    ping: ping::Behaviour,
+    direct_message: request_response::cbor::Behaviour<DirectMessageRequest, DirectMessageResponse>,
+    relay: Toggle<relay::Behaviour>,
+}
+
+impl HeadlessBehaviour {
+    fn new(key: &Keypair, relay: bool) -> Self {
+        let peer_id = key.public().to_peer_id();
+        Self {
+            identify: identify::Behaviour::new(
+                identify::Config::new("rchat/1.0.0".into(), key.public())
+                    .with_agent_version(super::capabilities::agent_version(&[])),
+            ),
+            ping: ping::Behaviour::default(),
+            direct_message: request_response::cbor::Behaviour::new(
+                [(
+                    libp2p::StreamProtocol::new(DM_PROTOCOL),
+                    ProtocolSupport::Full,
+                )],
+                request_response::Config::default(),
+            ),
+            relay: Toggle::from(
+                relay.then(|| relay::Behaviour::new(peer_id, relay::Config::default())),
+            ),
+        }
+    }
+}
+
+/// A text message another peer sent us.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncomingText {
+    pub id: String,
+    pub from: String,
+    pub sender_alias: Option<String>,
+    pub timestamp: i64,
+    pub text: String,
+}
+
+/// `<data dir>/headless.key`, next to the app's data but not its identity.
+pub fn default_key_path() -> PathBuf {
+    directories::ProjectDirs::from("io.github", "ata-sesli", "RChat")
+        .map(|dirs| dirs.data_dir().join("headless.key"))
+        .unwrap_or_else(|| PathBuf::from("headless.key"))
+}
+
+/// The node's identity from `path`, created on first use.
+pub fn load_or_create_key(path: &Path) -> Result<Keypair> {
+    if path.exists() {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read key {}", path.display()))?;
+        return Keypair::from_protobuf_encoding(&bytes)
+            .with_context(|| format!("{} is not an rchat key", path.display()));
+    }
+    let key = Keypair::generate_ed25519();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, key.to_protobuf_encoding()?)
+        .with_context(|| format!("Failed to write key {}", path.display()))?;
+    eprintln!("[Headless] 🔑 Created a new identity at {}", path.display());
+    Ok(key)
+}
+
+pub struct HeadlessNode {
+    swarm: Swarm<HeadlessBehaviour>,
+}
+
+impl HeadlessNode {
+    pub fn new(key: Keypair, relay: bool) -> Result<Self> {
+        let swarm = SwarmBuilder::with_existing_identity(key)
+            .with_tokio()
+            .with_tcp(
+                libp2p::tcp::Config::default(),
+                super::configure_noise,
+                || libp2p::yamux::Config::default(),
+            )?
+            .with_quic()
+            .with_behaviour(|key| HeadlessBehaviour::new(key, relay))?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+            .build();
+        Ok(Self { swarm })
+    }
+
+    pub fn peer_id(&self) -> PeerId {
+        *self.swarm.local_peer_id()
+    }
+
+    /// Listen on TCP and QUIC, IPv4 and IPv6; port 0 picks one.
+    pub fn listen(&mut self, port: u16) -> Result<()> {
+        self.swarm
+            .listen_on(format!("/ip4/0.0.0.0/tcp/{}", port).parse()?)?;
+        self.swarm
+            .listen_on(format!("/ip6/::/tcp/{}", port).parse()?)?;
+        self.swarm
+            .listen_on(format!("/ip4/0.0.0.0/udp/{}/quic-v1", port).parse()?)?;
+        self.swarm
+            .listen_on(format!("/ip6/::/udp/{}/quic-v1", port).parse()?)?;
+        Ok(())
+    }
+
+    /// An address others reach us at; relay reservations hand it out.
+    pub fn add_external_address(&mut self, addr: Multiaddr) {
+        self.swarm.add_external_address(addr);
+    }
+
+    /// Dial `addr`, which must end in `/p2p/<peer id>`, and send it `text`.
+    pub async fn send_text(
+        &mut self,
+        addr: Multiaddr,
+        text: &str,
+    ) -> Result<DirectMessageResponse> {
+        let Some(Protocol::P2p(peer)) = addr.iter().last() else {
+            anyhow::bail!("{} does not end in /p2p/<peer id>", addr);
+        };
+        self.swarm.dial(addr)?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let request = DirectMessageRequest {
+            id: crate::chat::message_id::new_message_id(),
+            sender_id: self.peer_id().to_string(),
+            msg_type: DirectMessageKind::Text,
+            text_content: Some(text.to_string()),
+            file_hash: None,
+            timestamp,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: super::wire::WireVersion::CURRENT,
+        };
+        // Queued until the connection is up.
+        let request_id = self
+            .swarm
+            .behaviour_mut()
+            .direct_message
+            .send_request(&peer, request);
+        tokio::time::timeout(SEND_TIMEOUT, self.await_response(request_id))
+            .await
+            .context("No answer from the peer")?
+    }
+
+    async fn await_response(
+        &mut self,
+        request_id: OutboundRequestId,
+    ) -> Result<DirectMessageResponse> {
+        loop {
+            match self.swarm.select_next_some().await {
+                SwarmEvent::Behaviour(HeadlessBehaviourEvent::DirectMessage(
+                    request_response::Event::Message {
+                        message:
+                            request_response::Message::Response {
+                                request_id: answered,
+                                response,
+                            },
+                        ..
+                    },
+                )) if answered == request_id => return Ok(response),
+                SwarmEvent::Behaviour(HeadlessBehaviourEvent::DirectMessage(
+                    request_response::Event::OutboundFailure {
+                        request_id: failed,
+                        error,
+                        ..
+                    },
+                )) if failed == request_id => anyhow::bail!("Send failed: {}", error),
+                event => self.handle_event(event, &mut |_| {}),
+            }
+        }
+    }
+
+    /// Serve until the process stops, handing each incoming text to
+    /// `on_text`.
+    pub async fn run(&mut self, mut on_text: impl FnMut(IncomingText)) {
+        loop {
+            let event = self.swarm.select_next_some().await;
+            self.handle_event(event, &mut on_text);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        event: SwarmEvent<HeadlessBehaviourEvent>,
+        on_text: &mut dyn FnMut(IncomingText),
+    ) {
+        match event {
+            SwarmEvent::NewListenAddr { address, .. } => {
+                eprintln!(
+                    "[Headless] 👂 Listening on {}/p2p/{}",
+                    address,
+                    self.peer_id()
+                );
+            }
+            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                eprintln!(
+                    "[Headless] 🔗 Connected to {}",
+                    crate::logging::peer(&peer_id)
+                );
+            }
+            SwarmEvent::Behaviour(HeadlessBehaviourEvent::Relay(
+                relay::Event::ReservationReqAccepted { src_peer_id, .. },
+            )) => {
+                eprintln!(
+                    "[Headless] 🛰️ Relaying for {}",
+                    crate::logging::peer(&src_peer_id)
+                );
+            }
+            SwarmEvent::Behaviour(HeadlessBehaviourEvent::DirectMessage(
+                request_response::Event::Message {
+                    peer,
+                    message:
+                        request_response::Message::Request {
+                            request, channel, ..
+                        },
+                    ..
+                },
+            )) => {
+                let (status, error) = if !request.wire.is_supported()
+                    || request.msg_type == DirectMessageKind::Unknown
+                {
+                    ("ignored", Some("unsupported message type".to_string()))
+                } else if request.msg_type == DirectMessageKind::Text {
+                    on_text(IncomingText {
+                        id: request.id.clone(),
+                        from: peer.to_string(),
+                        sender_alias: request.sender_alias.clone(),
+                        timestamp: request.timestamp,
+                        text: request.text_content.clone().unwrap_or_default(),
+                    });
+                    ("delivered", None)
+                } else {
+                    (
+                        "error",
+                        Some(format!(
+                            "{} is not supported by headless nodes",
+                            request.msg_type.as_str()
+                        )),
+                    )
+                };
+                let _ = self.swarm.behaviour_mut().direct_message.send_response(
+                    channel,
+                    DirectMessageResponse {
+                        msg_id: request.id,
+                        status: status.to_string(),
+                        error,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+}
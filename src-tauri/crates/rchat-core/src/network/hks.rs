@@ -0,0 +1,722 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::Verifier;
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use rvault_core::crypto;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::prelude::*;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+const TREE_DEPTH: u32 = 12;
+const MAX_NODES: usize = (1 << (TREE_DEPTH + 1)) - 1; // 8191 for depth 12
+const LEAF_START_IDX: usize = (1 << TREE_DEPTH) - 1; // 4095
+const MAX_FRIENDS: usize = 15000;
+const FRIENDS_PER_LEAF: usize = 4;
+/// Up to this many friends the payload is sealed for each of them directly
+/// instead of going through the tree.
+pub const DIRECT_PAYLOAD_MAX_FRIENDS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FriendEntry {
+    pub name: String,
+    pub x25519_pubkey: String,      // Base64
+    pub encrypted_leaf_key: String, // Encrypted with Shared Secret
+    pub nonce: String,
+    pub leaf_index: usize,
+}
+
+/// Invitation blob with TTL tracking (2-minute lifetime)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedInvite {
+    /// Salt for Argon2 key derivation (Base64)
+    pub salt: String,
+    /// XChaCha20 nonce (Base64)
+    pub nonce: String,
+    /// Encrypted payload (Base64)
+    pub ciphertext: String,
+    /// Unix timestamp when invite was created
+    pub created_at: u64,
+}
+
+/// Shadow invite for bidirectional hole punching
+/// Created by invitee and posted to their own Gist
+/// Encrypted with the same 18-digit key as the original invite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowInvite {
+    /// Target username (the original inviter) - for routing
+    pub target_username: String,
+    /// Salt for Argon2 key derivation (Base64)
+    pub salt: String,
+    /// XChaCha20 nonce (Base64)
+    pub nonce: String,
+    /// Encrypted ShadowPayload (Base64)
+    pub ciphertext: String,
+    /// Unix timestamp when shadow was created
+    pub created_at: u64,
+}
+
+/// Payload inside the encrypted shadow invite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowPayload {
+    /// Invitee's QUIC address (STUN-discovered)
+    pub invitee_address: String,
+    /// Invitee's libp2p peer ID
+    pub invitee_peer_id: String,
+    /// Unix timestamp
+    pub timestamp: u64,
+    /// Invitee's public keys, so the inviter can list them as a friend
+    #[serde(default)]
+    pub invitee_keys: Option<super::invite::IdentityKeys>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HksTree {
+    // We persist the raw keys for all nodes.
+    // Index 0 is root.
+    // Index i children: 2*i + 1, 2*i + 2.
+    // Index i parent: (i-1) / 2.
+    pub nodes: Vec<[u8; 32]>,
+    pub roster: HashMap<String, FriendEntry>,
+    pub next_friend_idx: usize,
+    /// Up-links of the last export, reused while neither end is rekeyed.
+    #[serde(skip)]
+    links: HashMap<usize, (String, String)>,
+    /// Nodes rekeyed since `links` was built.
+    #[serde(skip)]
+    rekeyed: HashSet<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedBlob {
+    pub payload: String, // Encrypted IP info
+    pub payload_nonce: String,
+    // Up-Links: Map of NodeIndex -> (Nonce, Ciphertext of ParentKey encrypted by NodeKey).
+    // Sorted maps keep the signed JSON the same after a parse round trip.
+    pub tree_links: BTreeMap<usize, (String, String)>,
+    pub roster: BTreeMap<String, FriendEntry>,
+    /// Small rosters: X25519 key (Base64) -> (Nonce, Ciphertext of the payload
+    /// under the shared secret). `payload`, `tree_links` and `roster` are
+    /// empty then.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub direct_payloads: BTreeMap<String, (String, String)>,
+    pub signature: String, // Signed by Ed25519
+    pub sender_x25519_pubkey: String,
+    /// Encrypted invitations with 2-minute TTL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invitations: Vec<TrackedInvite>,
+    /// Shadow invites for bidirectional hole punching (created by invitees)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadow_invites: Vec<ShadowInvite>,
+    /// Key rotations, oldest first, so friends can follow us to new keys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rotations: Vec<KeyRotation>,
+}
+
+const ROTATION_CONTEXT: &str = "rchat-key-rotation-v1";
+
+/// Announces that the identity moved from `old_ed25519` to a new key pair.
+/// Signed by the old identity key; the blob carrying it is signed by the new one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub old_ed25519: String, // Base64
+    pub new_ed25519: String, // Base64
+    pub new_x25519: String,  // Base64
+    pub rotated_at: u64,
+    pub signature: String,
+}
+
+impl KeyRotation {
+    pub fn new(
+        old_key: &SigningKey,
+        new_ed25519: &VerifyingKey,
+        new_x25519: &X25519PublicKey,
+        rotated_at: u64,
+    ) -> Self {
+        let mut rotation = Self {
+            old_ed25519: BASE64.encode(old_key.verifying_key().to_bytes()),
+            new_ed25519: BASE64.encode(new_ed25519.to_bytes()),
+            new_x25519: BASE64.encode(new_x25519.as_bytes()),
+            rotated_at,
+            signature: String::new(),
+        };
+        rotation.signature = BASE64.encode(old_key.sign(&rotation.signing_payload()).to_bytes());
+        rotation
+    }
+
+    fn signing_payload(&self) -> Vec<u8> {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            ROTATION_CONTEXT, self.old_ed25519, self.new_ed25519, self.new_x25519, self.rotated_at
+        )
+        .into_bytes()
+    }
+
+    /// Whether the old key really signed this record.
+    pub fn verify(&self) -> bool {
+        let Ok(old_bytes) = BASE64.decode(&self.old_ed25519) else {
+            return false;
+        };
+        let Ok(old_bytes) = <[u8; 32]>::try_from(old_bytes) else {
+            return false;
+        };
+        let Ok(old_key) = VerifyingKey::from_bytes(&old_bytes) else {
+            return false;
+        };
+        let Ok(signature_bytes) = BASE64.decode(&self.signature) else {
+            return false;
+        };
+        let Ok(signature) = ed25519_dalek::Signature::from_slice(&signature_bytes) else {
+            return false;
+        };
+        old_key.verify(&self.signing_payload(), &signature).is_ok()
+    }
+}
+
+/// Follow validly signed rotations starting from the `known_ed25519` key
+/// (Base64) and return the newest one reached, if any.
+pub fn follow_rotations<'a>(
+    known_ed25519: &str,
+    rotations: &'a [KeyRotation],
+) -> Option<&'a KeyRotation> {
+    let mut current = known_ed25519;
+    let mut latest = None;
+    // At most one hop per record, so a cyclic chain cannot loop forever.
+    for _ in 0..rotations.len() {
+        let Some(next) = rotations
+            .iter()
+            .find(|rotation| rotation.old_ed25519 == current && rotation.verify())
+        else {
+            break;
+        };
+        latest = Some(next);
+        current = &next.new_ed25519;
+    }
+    latest.filter(|rotation| rotation.new_ed25519 != known_ed25519)
+}
+
+/// Encrypt `plaintext` for a friend with our X25519 shared secret. Returns
+/// (ciphertext, nonce).
+fn seal_for_friend(
+    friend_pubkey_b64: &str,
+    plaintext: &str,
+    my_secret: &StaticSecret,
+) -> Result<(String, String)> {
+    let friend_pubkey_bytes = BASE64.decode(friend_pubkey_b64)?;
+    let friend_pubkey_array: [u8; 32] = friend_pubkey_bytes
+        .try_into()
+        .map_err(|_| anyhow!("Invalid public key length"))?;
+    let friend_public = X25519PublicKey::from(friend_pubkey_array);
+    let shared_secret = my_secret.diffie_hellman(&friend_public);
+    crypto::encrypt_with_key(&shared_secret.to_bytes(), plaintext.as_bytes())
+        .map_err(|e| anyhow!("Encryption failed: {}", e))
+}
+
+/// Below this many links, encrypting them on one thread is faster than
+/// spreading them out.
+const PARALLEL_LINKS_MIN: usize = 256;
+
+/// Encrypt the up-link of every node in `indices`: the parent's key under the
+/// node's key, as (nonce, ciphertext), the order `import` reads. Large batches
+/// are split across the available cores.
+fn encrypt_links(nodes: &[[u8; 32]], indices: &[usize]) -> Result<Vec<(usize, (String, String))>> {
+    let encrypt = |chunk: &[usize]| -> Result<Vec<(usize, (String, String))>> {
+        chunk
+            .iter()
+            .map(|&i| {
+                let parent_key_b64 = BASE64.encode(nodes[(i - 1) / 2]);
+                let (cipher, nonce) =
+                    crypto::encrypt_with_key(&nodes[i], parent_key_b64.as_bytes())
+                        .map_err(|e| anyhow!("Link {} encryption failed: {}", i, e))?;
+                Ok((i, (nonce, cipher)))
+            })
+            .collect()
+    };
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if indices.len() < PARALLEL_LINKS_MIN || threads == 1 {
+        return encrypt(indices);
+    }
+    let chunk_len = indices.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = indices
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || encrypt(chunk)))
+            .collect();
+        let mut links = Vec::with_capacity(indices.len());
+        for worker in workers {
+            let chunk = worker
+                .join()
+                .map_err(|_| anyhow!("Link encryption thread panicked"))??;
+            links.extend(chunk);
+        }
+        Ok(links)
+    })
+}
+
+impl HksTree {
+    pub fn new() -> Self {
+        let mut nodes = Vec::with_capacity(MAX_NODES);
+        // Initialize all nodes with random keys
+        for _ in 0..MAX_NODES {
+            nodes.push(rvault_core::crypto::generate_raw_key());
+        }
+
+        Self {
+            nodes,
+            roster: HashMap::new(),
+            next_friend_idx: 0,
+            links: HashMap::new(),
+            rekeyed: HashSet::new(),
+        }
+    }
+
+    /// Rebuild a tree from node keys stored with [`Self::node_keys_b64`]. The
+    /// roster starts empty; links are built on the first export.
+    pub fn from_node_keys(keys_b64: &[String]) -> Result<Self> {
+        if keys_b64.len() != MAX_NODES {
+            return Err(anyhow!(
+                "Expected {} tree keys, got {}",
+                MAX_NODES,
+                keys_b64.len()
+            ));
+        }
+        let nodes = keys_b64
+            .iter()
+            .map(|key| {
+                BASE64
+                    .decode(key)?
+                    .try_into()
+                    .map_err(|_| anyhow!("Tree key must be 32 bytes"))
+            })
+            .collect::<Result<Vec<[u8; 32]>>>()?;
+        Ok(Self {
+            nodes,
+            roster: HashMap::new(),
+            next_friend_idx: 0,
+            links: HashMap::new(),
+            rekeyed: HashSet::new(),
+        })
+    }
+
+    pub fn node_keys_b64(&self) -> Vec<String> {
+        self.nodes.iter().map(|key| BASE64.encode(key)).collect()
+    }
+
+    pub fn is_leaf(index: usize) -> bool {
+        (LEAF_START_IDX..MAX_NODES).contains(&index)
+    }
+
+    /// Replace the keys of `leaf_index` and every node above it, so whoever
+    /// held the old leaf key can no longer reach the root. Friends left on
+    /// the leaf get the new leaf key. Returns the rekeyed nodes, leaf first.
+    pub fn rekey_path(
+        &mut self,
+        leaf_index: usize,
+        my_secret: &StaticSecret,
+    ) -> Result<Vec<usize>> {
+        if !Self::is_leaf(leaf_index) {
+            return Err(anyhow!("{} is not a leaf", leaf_index));
+        }
+        let mut path = vec![leaf_index];
+        let mut idx = leaf_index;
+        while idx > 0 {
+            idx = (idx - 1) / 2;
+            path.push(idx);
+        }
+        for &idx in &path {
+            self.nodes[idx] = rvault_core::crypto::generate_raw_key();
+            self.rekeyed.insert(idx);
+        }
+
+        let leaf_key_b64 = BASE64.encode(self.nodes[leaf_index]);
+        for entry in self.roster.values_mut() {
+            if entry.leaf_index == leaf_index {
+                let (ciphertext, nonce) =
+                    seal_for_friend(&entry.x25519_pubkey, &leaf_key_b64, my_secret)?;
+                entry.encrypted_leaf_key = ciphertext;
+                entry.nonce = nonce;
+            }
+        }
+        Ok(path)
+    }
+
+    /// Drop a friend from the roster and rekey their path to the root.
+    /// Returns the leaf they were on, or `None` if they weren't listed.
+    pub fn remove_friend(
+        &mut self,
+        friend_pubkey_b64: &str,
+        my_secret: &StaticSecret,
+    ) -> Result<Option<usize>> {
+        let Some(entry) = self.roster.remove(friend_pubkey_b64) else {
+            return Ok(None);
+        };
+        self.next_friend_idx = self.next_friend_idx.saturating_sub(1);
+        self.rekey_path(entry.leaf_index, my_secret)?;
+        Ok(Some(entry.leaf_index))
+    }
+
+    pub fn root_key(&self) -> &[u8; 32] {
+        &self.nodes[0]
+    }
+
+    /// Add a friend to the roster, on the first leaf with room.
+    pub fn add_friend(
+        &mut self,
+        name: &str,
+        friend_pubkey_b64: &str,
+        my_secret: &StaticSecret,
+    ) -> Result<usize> {
+        let leaf_index = (LEAF_START_IDX..MAX_NODES)
+            .find(|&leaf| self.leaf_has_room(leaf))
+            .ok_or_else(|| anyhow!("Tree capacity exceeded"))?;
+        self.add_friend_to_leaf(name, friend_pubkey_b64, my_secret, leaf_index)?;
+        Ok(leaf_index)
+    }
+
+    fn leaf_has_room(&self, leaf_index: usize) -> bool {
+        self.roster
+            .values()
+            .filter(|entry| entry.leaf_index == leaf_index)
+            .count()
+            < FRIENDS_PER_LEAF
+    }
+
+    /// Add a friend on a given leaf, e.g. the one they had before a restart.
+    pub fn add_friend_to_leaf(
+        &mut self,
+        name: &str,
+        friend_pubkey_b64: &str,
+        my_secret: &StaticSecret,
+        leaf_index: usize,
+    ) -> Result<()> {
+        if self.next_friend_idx >= MAX_FRIENDS {
+            return Err(anyhow!("Friend limit reached (15000)"));
+        }
+        if !Self::is_leaf(leaf_index) || !self.leaf_has_room(leaf_index) {
+            return Err(anyhow!("Leaf {} is not available", leaf_index));
+        }
+
+        // Encrypt Leaf Key for Friend
+        let leaf_key_b64 = BASE64.encode(self.nodes[leaf_index]);
+        let (ciphertext, nonce) = seal_for_friend(friend_pubkey_b64, &leaf_key_b64, my_secret)?;
+
+        let entry = FriendEntry {
+            name: name.to_string(),
+            x25519_pubkey: friend_pubkey_b64.to_string(),
+            encrypted_leaf_key: ciphertext,
+            nonce,
+            leaf_index,
+        };
+
+        self.roster.insert(friend_pubkey_b64.to_string(), entry);
+        self.next_friend_idx += 1;
+        Ok(())
+    }
+
+    /// Nodes on the paths from occupied leaves to the root, root excluded.
+    fn occupied_paths(&self) -> HashSet<usize> {
+        let mut nodes = HashSet::new();
+        for entry in self.roster.values() {
+            let mut idx = entry.leaf_index;
+            while idx > 0 && nodes.insert(idx) {
+                idx = (idx - 1) / 2;
+            }
+        }
+        nodes
+    }
+
+    /// Export the tree and payload. Only links on occupied paths are
+    /// published; with at most [`DIRECT_PAYLOAD_MAX_FRIENDS`] friends the
+    /// payload is sealed for each friend instead.
+    pub fn export(
+        &mut self,
+        payload_data: &str,
+        signing_key: &SigningKey,
+        my_secret: &StaticSecret,
+        rotations: &[KeyRotation],
+    ) -> Result<String> {
+        let encryption_pubkey = X25519PublicKey::from(my_secret);
+        let mut blob = PublishedBlob {
+            payload: String::new(),
+            payload_nonce: String::new(),
+            tree_links: BTreeMap::new(),
+            roster: BTreeMap::new(),
+            direct_payloads: BTreeMap::new(),
+            signature: String::new(),
+            sender_x25519_pubkey: BASE64.encode(encryption_pubkey.as_bytes()),
+            invitations: vec![],
+            shadow_invites: vec![],
+            rotations: rotations.to_vec(),
+        };
+
+        if self.roster.len() <= DIRECT_PAYLOAD_MAX_FRIENDS {
+            for friend_pubkey_b64 in self.roster.keys() {
+                let (cipher, nonce) = seal_for_friend(friend_pubkey_b64, payload_data, my_secret)?;
+                blob.direct_payloads
+                    .insert(friend_pubkey_b64.clone(), (nonce, cipher));
+            }
+        } else {
+            // 1. Encrypt Payload with Root Key
+            let (payload_cipher, payload_nonce) =
+                crypto::encrypt_with_key(self.root_key(), payload_data.as_bytes())
+                    .map_err(|e| anyhow!("Payload encryption failed: {}", e))?;
+            blob.payload = payload_cipher;
+            blob.payload_nonce = payload_nonce;
+
+            // 2. Build Tree Links (Up-Links): child key encrypts parent key.
+            // Cached links are reused unless either end was rekeyed.
+            let paths = self.occupied_paths();
+            let stale: Vec<usize> = paths
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    !self.links.contains_key(&i)
+                        || self.rekeyed.contains(&i)
+                        || self.rekeyed.contains(&((i - 1) / 2))
+                })
+                .collect();
+            self.links.extend(encrypt_links(&self.nodes, &stale)?);
+            for i in paths {
+                blob.tree_links.insert(i, self.links[&i].clone());
+            }
+            self.rekeyed.clear();
+            blob.roster = self
+                .roster
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.clone()))
+                .collect();
+        }
+
+        // 4. Serialize & Sign
+        let json = serde_json::to_string(&blob)?;
+        let signature = signing_key.sign(json.as_bytes());
+        let mut final_blob = blob;
+        final_blob.signature = BASE64.encode(signature.to_bytes());
+
+        let final_json = serde_json::to_string(&final_blob)?;
+
+        // 5. Compress & Encode
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(final_json.as_bytes())?;
+        let compressed = encoder.finish()?;
+        Ok(BASE64.encode(compressed))
+    }
+
+    /// Import a blob
+    pub fn import(
+        blob_b64: &str,
+        my_pubkey_b64: &str,
+        my_secret: &StaticSecret,
+        friend_identity_pubkey: &VerifyingKey,
+    ) -> Result<String> {
+        // Decode & Decompress
+        let compressed = BASE64.decode(blob_b64)?;
+        let mut decoder = ZlibDecoder::new(&compressed[..]);
+        let mut json = String::new();
+        decoder.read_to_string(&mut json)?;
+
+        let blob: PublishedBlob = serde_json::from_str(&json)?;
+
+        // Verify Signature
+        let mut unsigned_blob = blob.clone();
+        unsigned_blob.signature = String::new();
+        let unsigned_json = serde_json::to_string(&unsigned_blob)?;
+        let signature_bytes = BASE64.decode(&blob.signature)?;
+        let signature = ed25519_dalek::Signature::from_slice(&signature_bytes)?;
+        friend_identity_pubkey
+            .verify(unsigned_json.as_bytes(), &signature)
+            .map_err(|_| anyhow!("Invalid signature"))?;
+
+        let sender_pubkey_bytes = BASE64.decode(&blob.sender_x25519_pubkey)?;
+        let sender_public = X25519PublicKey::from(
+            <[u8; 32]>::try_from(sender_pubkey_bytes)
+                .map_err(|_| anyhow!("Invalid sender public key length"))?,
+        );
+        let shared_secret = my_secret.diffie_hellman(&sender_public);
+
+        // Small rosters: the payload is sealed for us directly
+        if let Some((nonce, cipher)) = blob.direct_payloads.get(my_pubkey_b64) {
+            return crypto::decrypt_with_key(&shared_secret.to_bytes(), cipher, nonce)
+                .map_err(|e| anyhow!("Payload decrypt failed: {}", e));
+        }
+
+        // Find my entry
+        let entry = blob
+            .roster
+            .get(my_pubkey_b64)
+            .ok_or_else(|| anyhow!("Not in roster"))?;
+
+        // Decrypt Leaf Key
+
+        let leaf_key_json = crypto::decrypt_with_key(
+            &shared_secret.to_bytes(),
+            &entry.encrypted_leaf_key,
+            &entry.nonce,
+        )
+        .map_err(|e| anyhow!("Decrypt leaf failed: {}", e))?;
+        let mut current_key = BASE64.decode(leaf_key_json)?;
+
+        // Traverse Up: Leaf -> Root
+        let mut current_idx = entry.leaf_index;
+        while current_idx > 0 {
+            let (nonce, cipher) = blob
+                .tree_links
+                .get(&current_idx)
+                .ok_or_else(|| anyhow!("Broken link at {}", current_idx))?;
+
+            let parent_key_json = crypto::decrypt_with_key(&current_key, cipher, nonce)
+                .map_err(|e| anyhow!("Decrypt link {} failed: {}", current_idx, e))?;
+
+            current_key = BASE64.decode(parent_key_json)?;
+            current_idx = (current_idx - 1) / 2;
+        }
+
+        // Decrypt Payload with Root Key (current_key)
+        let payload = crypto::decrypt_with_key(&current_key, &blob.payload, &blob.payload_nonce)
+            .map_err(|e| anyhow!("Payload decrypt failed: {}", e))?;
+
+        Ok(payload)
+    }
+}
+
+/// Parse compressed Base64 blob into PublishedBlob
+pub fn parse_blob(blob_b64: &str) -> Result<PublishedBlob> {
+    // 1. Decode Base64
+    let compressed = BASE64
+        .decode(blob_b64)
+        .map_err(|e| anyhow::anyhow!("Failed to decode blob: {}", e))?;
+
+    // 2. Decompress
+    let mut decoder = ZlibDecoder::new(&compressed[..]);
+    let mut json_str = String::new();
+    decoder.read_to_string(&mut json_str)?;
+
+    // 3. Deserialize
+    let blob: PublishedBlob = serde_json::from_str(&json_str)?;
+    Ok(blob)
+}
+
+/// Serialize PublishedBlob to compressed Base64
+pub fn serialize_blob(blob: &PublishedBlob) -> Result<String> {
+    // 1. Serialize to JSON
+    let json_str = serde_json::to_string(blob)?;
+
+    // 2. Compress
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(json_str.as_bytes())?;
+    let compressed = encoder.finish()?;
+
+    // 3. Encode Base64
+    Ok(BASE64.encode(compressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_chain_is_followed_only_through_valid_signatures() {
+        let mut csprng = rand::rngs::OsRng;
+        let first = SigningKey::generate(&mut csprng);
+        let second = SigningKey::generate(&mut csprng);
+        let third = SigningKey::generate(&mut csprng);
+        let x25519 = X25519PublicKey::from(&StaticSecret::random_from_rng(&mut csprng));
+
+        let hop1 = KeyRotation::new(&first, &second.verifying_key(), &x25519, 10);
+        let hop2 = KeyRotation::new(&second, &third.verifying_key(), &x25519, 20);
+        let first_b64 = BASE64.encode(first.verifying_key().to_bytes());
+        let rotations = vec![hop1.clone(), hop2.clone()];
+        assert_eq!(follow_rotations(&first_b64, &rotations), Some(&hop2));
+        assert_eq!(follow_rotations(&hop2.new_ed25519, &rotations), None);
+
+        // A record the old key did not sign breaks the chain.
+        let mut forged = hop1.clone();
+        forged.new_ed25519 = hop2.new_ed25519.clone();
+        assert!(!forged.verify());
+        assert_eq!(follow_rotations(&first_b64, &[forged]), None);
+    }
+
+    #[test]
+    fn removing_a_friend_rekeys_their_path_and_keeps_others_reachable() {
+        let mut csprng = rand::rngs::OsRng;
+        let signing = SigningKey::generate(&mut csprng);
+        let me = StaticSecret::random_from_rng(&mut csprng);
+        let ada = StaticSecret::random_from_rng(&mut csprng);
+        let bob = StaticSecret::random_from_rng(&mut csprng);
+        let b64 = |secret: &StaticSecret| BASE64.encode(X25519PublicKey::from(secret).as_bytes());
+
+        let mut tree = HksTree::new();
+        let leaf = tree.add_friend("ada", &b64(&ada), &me).unwrap();
+        assert_eq!(tree.add_friend("bob", &b64(&bob), &me).unwrap(), leaf);
+        tree.export("v1", &signing, &me, &[]).unwrap();
+        let old_root = *tree.root_key();
+        let sibling_key = tree.nodes[leaf + 1];
+
+        assert_eq!(tree.remove_friend(&b64(&bob), &me).unwrap(), Some(leaf));
+        assert_ne!(*tree.root_key(), old_root);
+        assert_eq!(tree.nodes[leaf + 1], sibling_key);
+
+        let blob = tree.export("v2", &signing, &me, &[]).unwrap();
+        let verifying = signing.verifying_key();
+        assert_eq!(
+            HksTree::import(&blob, &b64(&ada), &ada, &verifying).unwrap(),
+            "v2"
+        );
+        assert!(HksTree::import(&blob, &b64(&bob), &bob, &verifying).is_err());
+
+        let restored = HksTree::from_node_keys(&tree.node_keys_b64()).unwrap();
+        assert_eq!(restored.root_key(), tree.root_key());
+    }
+
+    #[test]
+    fn export_publishes_only_occupied_paths_or_direct_payloads() {
+        let mut csprng = rand::rngs::OsRng;
+        let signing = SigningKey::generate(&mut csprng);
+        let verifying = signing.verifying_key();
+        let me = StaticSecret::random_from_rng(&mut csprng);
+        let friends: Vec<StaticSecret> = (0..DIRECT_PAYLOAD_MAX_FRIENDS + 2)
+            .map(|_| StaticSecret::random_from_rng(&mut csprng))
+            .collect();
+        let b64 = |secret: &StaticSecret| BASE64.encode(X25519PublicKey::from(secret).as_bytes());
+
+        let mut tree = HksTree::new();
+        for (i, friend) in friends.iter().enumerate().take(DIRECT_PAYLOAD_MAX_FRIENDS) {
+            tree.add_friend(&format!("f{}", i), &b64(friend), &me)
+                .unwrap();
+        }
+        let small = parse_blob(&tree.export("small", &signing, &me, &[]).unwrap()).unwrap();
+        assert_eq!(small.direct_payloads.len(), DIRECT_PAYLOAD_MAX_FRIENDS);
+        assert!(small.tree_links.is_empty() && small.roster.is_empty());
+
+        for (i, friend) in friends.iter().enumerate().skip(DIRECT_PAYLOAD_MAX_FRIENDS) {
+            tree.add_friend(&format!("f{}", i), &b64(friend), &me)
+                .unwrap();
+        }
+        let blob_b64 = tree.export("large", &signing, &me, &[]).unwrap();
+        let large = parse_blob(&blob_b64).unwrap();
+        // Three leaves share most of their path to the root.
+        assert!(large.tree_links.len() <= 3 * TREE_DEPTH as usize);
+        assert!(large.direct_payloads.is_empty());
+        for friend in &friends {
+            assert_eq!(
+                HksTree::import(&blob_b64, &b64(friend), friend, &verifying).unwrap(),
+                "large"
+            );
+        }
+    }
+
+    #[test]
+    fn links_encrypted_in_parallel_open_with_the_child_key() {
+        let tree = HksTree::new();
+        let indices: Vec<usize> = (1..=PARALLEL_LINKS_MIN * 2).collect();
+        let links = encrypt_links(&tree.nodes, &indices).unwrap();
+        assert_eq!(links.len(), indices.len());
+        for (i, (nonce, cipher)) in links {
+            let parent = crypto::decrypt_with_key(&tree.nodes[i], &cipher, &nonce).unwrap();
+            assert_eq!(parent, BASE64.encode(tree.nodes[(i - 1) / 2]));
+        }
+    }
+}
@@ -39,6 +39,19 @@ pub struct InvitePayload {
     /// Inviter's libp2p peer id for canonical DM chat identity.
     #[serde(default)]
     pub inviter_peer_id: Option<String>,
+    /// Inviter's public keys, so the invitee can list them as a friend.
+    #[serde(default)]
+    pub inviter_keys: Option<IdentityKeys>,
+}
+
+/// Public keys exchanged inside invites, so both sides end up with each
+/// other on their HKS roster.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Zeroize)]
+pub struct IdentityKeys {
+    /// Base64
+    pub x25519_pubkey: String,
+    /// Base64
+    pub ed25519_pubkey: String,
 }
 
 // ============================================================================
@@ -221,6 +234,7 @@ pub fn decrypt_invite(
 /// * `inviter` - Sender's username
 /// * `invitee` - Receiver's username
 /// * `ip_address` - The secret data to share
+/// * `inviter_keys` - Sender's public keys
 /// * `ttl_secs` - How long the invite is valid (in seconds from now)
 pub fn generate_invite(
     password: &str,
@@ -228,6 +242,7 @@ pub fn generate_invite(
     invitee: &str,
     ip_address: &str,
     inviter_peer_id: &str,
+    inviter_keys: Option<IdentityKeys>,
     ttl_secs: u64,
 ) -> Result<EncryptedInvite> {
     // 1. Generate Harvester Key
@@ -244,6 +259,7 @@ pub fn generate_invite(
         ip_address: ip_address.to_string(),
         ttl_timestamp: now + ttl_secs,
         inviter_peer_id: Some(inviter_peer_id.to_string()),
+        inviter_keys,
     };
 
     // 3. Encrypt
@@ -316,12 +332,14 @@ use super::hks::{ShadowInvite, ShadowPayload};
 /// * `invitee` - This user's username (the one creating the shadow)
 /// * `invitee_address` - Invitee's QUIC address (STUN-discovered)
 /// * `invitee_peer_id` - Invitee's libp2p peer ID
+/// * `invitee_keys` - Invitee's public keys
 pub fn generate_shadow_invite(
     password: &str,
     inviter: &str,
     invitee: &str,
     invitee_address: &str,
     invitee_peer_id: &str,
+    invitee_keys: Option<IdentityKeys>,
 ) -> Result<ShadowInvite> {
     use rand::RngCore;
 
@@ -338,6 +356,7 @@ pub fn generate_shadow_invite(
         invitee_address: invitee_address.to_string(),
         invitee_peer_id: invitee_peer_id.to_string(),
         timestamp: now,
+        invitee_keys,
     };
 
     // 3. Generate random salt for Argon2
@@ -457,6 +476,7 @@ mod tests {
             invitee,
             "192.168.1.100",
             "12D3KooWLk1GoEB3MbHbRLHTxXrvNGSxC2UALaCuKAgKuYXkXazU",
+            None,
             3600,
         )
         .unwrap();
@@ -480,6 +500,7 @@ mod tests {
             "Bob",
             "192.168.1.100",
             "12D3KooWLk1GoEB3MbHbRLHTxXrvNGSxC2UALaCuKAgKuYXkXazU",
+            None,
             3600,
         )
         .unwrap();
@@ -498,6 +519,7 @@ mod tests {
             "Bob",
             "192.168.1.100",
             "12D3KooWLk1GoEB3MbHbRLHTxXrvNGSxC2UALaCuKAgKuYXkXazU",
+            None,
             3600,
         )
         .unwrap();
@@ -0,0 +1,13 @@
+pub mod blob_stream;
+pub mod capabilities;
+pub mod direct_message;
+pub mod headless;
+pub mod hks;
+pub mod invite;
+pub mod wire;
+
+pub fn configure_noise(
+    keypair: &libp2p::identity::Keypair,
+) -> Result<libp2p::noise::Config, libp2p::noise::Error> {
+    libp2p::noise::Config::new(keypair)
+}
@@ -0,0 +1,8 @@
+//! Protocol names that bandwidth is accounted under. The accounting itself
+//! lives in the app.
+
+pub const DIRECT_MESSAGE: &str = "direct_message";
+pub const FILE_TRANSFER: &str = "file_transfer";
+pub const GOSSIP: &str = "gossip";
+/// Peer id recorded for gossip we publish, which goes to the whole mesh.
+pub const ANY_PEER: &str = "*";
@@ -0,0 +1,35 @@
+//! UTC calendar dates for Unix timestamps, without a date crate.
+
+/// Convert days since the Unix epoch to a (year, month, day) civil date.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days_from_civil inverse.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// UTC date label (`YYYY-MM-DD`) for a Unix timestamp.
+pub fn day_label(unix_secs: i64) -> String {
+    let (y, m, d) = civil_from_days(unix_secs.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_label_matches_known_dates() {
+        assert_eq!(day_label(0), "1970-01-01");
+        assert_eq!(day_label(951_782_400), "2000-02-29");
+        assert_eq!(day_label(1_704_067_199), "2023-12-31");
+        assert_eq!(day_label(1_704_067_200), "2024-01-01");
+    }
+}
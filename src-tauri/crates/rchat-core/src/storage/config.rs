@@ -0,0 +1,1214 @@
+use anyhow::Result;
+use rvault_core;
+use rvault_core::session;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use super::config_migration::{self, CURRENT_SCHEMA_VERSION};
+use super::escrow;
+use super::keychain;
+use super::recovery;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use x25519_dalek::StaticSecret;
+
+// Re-export theme types from theme module
+pub use super::theme::{Appearance, CustomThemeEntry, ThemeConfig};
+
+/// A vault operation attempted while the [`ConfigManager`] is locked; the
+/// app reports it as `VAULT_LOCKED`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultLocked;
+
+impl std::fmt::Display for VaultLocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Vault is locked")
+    }
+}
+
+impl std::error::Error for VaultLocked {}
+
+// System Configuration, can be modified only internally.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SystemConfig {
+    pub github_username: Option<String>,
+    pub github_token: Option<String>,
+    pub public_key: Option<String>,
+    pub private_key: Option<String>,
+    pub master_hash: Option<String>,
+    /// Where peer info is published; see `network::discovery_backend`.
+    #[serde(default)]
+    pub discovery_backend: DiscoveryBackendKind,
+    #[serde(default)]
+    pub gitlab: Option<GitlabDiscovery>,
+    /// SOCKS5 proxy as `host:port` (e.g. Tor on `127.0.0.1:9050`); see
+    /// `network::proxy`.
+    #[serde(default)]
+    pub socks5_proxy: Option<String>,
+    /// Account Matrix bridges use unless a bridge sets its own; see `bridge`.
+    #[serde(default)]
+    pub matrix: Option<MatrixAccount>,
+    /// Per-bridge secrets by group chat id: a Matrix access token or an XMPP
+    /// `user@domain:password` login.
+    #[serde(default)]
+    pub bridge_secrets: std::collections::HashMap<String, String>,
+}
+
+impl SystemConfig {
+    /// Our name on the selected discovery backend, which friends use to find us.
+    pub fn discovery_username(&self) -> Option<&str> {
+        match self.discovery_backend {
+            DiscoveryBackendKind::Github => self.github_username.as_deref(),
+            DiscoveryBackendKind::Gitlab => self
+                .gitlab
+                .as_ref()
+                .and_then(|gitlab| gitlab.username.as_deref()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoveryBackendKind {
+    #[default]
+    Github,
+    Gitlab,
+}
+
+/// GitLab snippets discovery: peer info is a public snippet in `project`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabDiscovery {
+    /// Instance URL, e.g. `https://gitlab.com`.
+    pub base_url: String,
+    pub token: String,
+    /// Project path, e.g. `alice/rchat`.
+    pub project: String,
+    /// Filled in from the token when the backend is configured.
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+/// A Matrix login for the bridge.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatrixAccount {
+    /// Homeserver URL, e.g. `https://matrix.org`.
+    pub homeserver: String,
+    pub access_token: String,
+    /// Filled in from the token when the account is set.
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+// User Configuration, can be modified via UI.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FriendConfig {
+    pub username: String, // Gist ID / Username (unique ID)
+    #[serde(default)]
+    pub alias: Option<String>, // Display name / alias
+    pub x25519_pubkey: Option<String>, // Base64
+    pub ed25519_pubkey: Option<String>, // Base64
+    pub leaf_index: usize, // HKS Leaf Index
+    pub encrypted_leaf_key: Option<String>, // Base64
+    pub nonce: Option<String>, // Base64
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UserProfile {
+    pub alias: Option<String>,
+    pub avatar_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityMode {
+    Invisible,
+    Lan,
+    Reachable,
+    Custom,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ConnectivitySettings {
+    pub mode: ConnectivityMode,
+    pub mdns_enabled: bool,
+    pub github_sync_enabled: bool,
+    pub nat_keepalive_enabled: bool,
+    pub punch_assist_enabled: bool,
+}
+
+impl ConnectivitySettings {
+    pub fn invisible() -> Self {
+        Self {
+            mode: ConnectivityMode::Invisible,
+            mdns_enabled: false,
+            github_sync_enabled: false,
+            nat_keepalive_enabled: false,
+            punch_assist_enabled: false,
+        }
+    }
+
+    pub fn lan() -> Self {
+        Self {
+            mode: ConnectivityMode::Lan,
+            mdns_enabled: true,
+            github_sync_enabled: false,
+            nat_keepalive_enabled: false,
+            punch_assist_enabled: false,
+        }
+    }
+
+    pub fn reachable() -> Self {
+        Self {
+            mode: ConnectivityMode::Reachable,
+            mdns_enabled: true,
+            github_sync_enabled: true,
+            nat_keepalive_enabled: true,
+            punch_assist_enabled: true,
+        }
+    }
+
+    pub fn from_mode(mode: ConnectivityMode) -> Self {
+        match mode {
+            ConnectivityMode::Invisible => Self::invisible(),
+            ConnectivityMode::Lan => Self::lan(),
+            ConnectivityMode::Reachable => Self::reachable(),
+            ConnectivityMode::Custom => {
+                let mut settings = Self::reachable();
+                settings.mode = ConnectivityMode::Custom;
+                settings
+            }
+        }
+    }
+
+    pub fn derive_mode(&self) -> ConnectivityMode {
+        if *self == Self::invisible() {
+            ConnectivityMode::Invisible
+        } else if *self == Self::lan() {
+            ConnectivityMode::Lan
+        } else if *self == Self::reachable() {
+            ConnectivityMode::Reachable
+        } else {
+            ConnectivityMode::Custom
+        }
+    }
+
+    pub fn with_derived_mode(mut self) -> Self {
+        self.mode = self.derive_mode();
+        self
+    }
+}
+
+/// What our mDNS advertisement reveals to the LAN; see `network::mdns`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MdnsPrivacy {
+    /// Browse only; we are invisible to other devices.
+    Off,
+    /// A rotating token only friends holding our discovery key can resolve.
+    Anonymous,
+    /// Peer ID and alias in plain text.
+    #[default]
+    Full,
+}
+
+impl Default for ConnectivitySettings {
+    fn default() -> Self {
+        // Migration default: legacy users become reachable regardless of old is_online.
+        Self::reachable()
+    }
+}
+
+const MAX_AUTO_REPLY_CHARS: usize = 1_000;
+const MIN_AUTO_REPLY_WINDOW_SECS: i64 = 60;
+
+fn validate_model_endpoint(endpoint: Option<&str>) -> Result<(), String> {
+    match endpoint.map(str::trim) {
+        Some(endpoint)
+            if !endpoint.is_empty()
+                && !(endpoint.starts_with("https://") || endpoint.starts_with("http://")) =>
+        {
+            Err("Model endpoint must be an http(s) URL".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Smart reply suggestions. With no model configured the local heuristic is used.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ReplySuggestionSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub model_endpoint: Option<String>, // OpenAI-compatible chat completions URL
+    #[serde(default)]
+    pub model_name: Option<String>,
+    #[serde(default)]
+    pub model_api_key: Option<String>,
+}
+
+impl ReplySuggestionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_model_endpoint(self.model_endpoint.as_deref())
+    }
+}
+
+/// Away mode: answer friends' direct messages with a fixed reply, at most once
+/// per contact per `window_secs`. With keywords set, only messages containing
+/// one of them (case-insensitive) get the reply.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AutoReplySettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default = "default_auto_reply_window_secs")]
+    pub window_secs: i64,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+fn default_auto_reply_window_secs() -> i64 {
+    60 * 60
+}
+
+impl Default for AutoReplySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            message: String::new(),
+            window_secs: default_auto_reply_window_secs(),
+            keywords: vec![],
+        }
+    }
+}
+
+impl AutoReplySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.message.trim().is_empty() {
+            return Err("Auto reply message is required".to_string());
+        }
+        if self.message.chars().count() > MAX_AUTO_REPLY_CHARS {
+            return Err(format!(
+                "Auto reply message is limited to {} characters",
+                MAX_AUTO_REPLY_CHARS
+            ));
+        }
+        if self.window_secs < MIN_AUTO_REPLY_WINDOW_SECS {
+            return Err(format!(
+                "Auto reply window must be at least {} seconds",
+                MIN_AUTO_REPLY_WINDOW_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether an incoming `text` should trigger the reply (ignoring rate limits).
+    pub fn triggers_on(&self, text: &str) -> bool {
+        if !self.enabled || self.message.trim().is_empty() {
+            return false;
+        }
+        let text = text.to_lowercase();
+        let mut keywords = self
+            .keywords
+            .iter()
+            .map(|k| k.trim())
+            .filter(|k| !k.is_empty())
+            .peekable();
+        keywords.peek().is_none() || keywords.any(|k| text.contains(&k.to_lowercase()))
+    }
+}
+
+/// When incoming media is downloaded without the user asking for it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoDownloadPolicy {
+    Always,
+    WifiOnly,
+    Never,
+}
+
+impl AutoDownloadPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "always" => Some(Self::Always),
+            "wifi_only" => Some(Self::WifiOnly),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Global auto-download policy per media type; chats can override each one.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct AutoDownloadSettings {
+    pub image: AutoDownloadPolicy,
+    pub video: AutoDownloadPolicy,
+    pub audio: AutoDownloadPolicy,
+    pub document: AutoDownloadPolicy,
+    pub sticker: AutoDownloadPolicy,
+}
+
+impl Default for AutoDownloadSettings {
+    fn default() -> Self {
+        Self {
+            image: AutoDownloadPolicy::Always,
+            video: AutoDownloadPolicy::WifiOnly,
+            audio: AutoDownloadPolicy::Always,
+            document: AutoDownloadPolicy::WifiOnly,
+            sticker: AutoDownloadPolicy::Always,
+        }
+    }
+}
+
+impl AutoDownloadSettings {
+    /// Policy for a message `content_type`; `None` for types without media.
+    pub fn policy_for(&self, content_type: &str) -> Option<AutoDownloadPolicy> {
+        match content_type {
+            "image" => Some(self.image),
+            "video" => Some(self.video),
+            "audio" => Some(self.audio),
+            "document" => Some(self.document),
+            "sticker" => Some(self.sticker),
+            _ => None,
+        }
+    }
+}
+
+/// Per-peer limits on incoming gossip and direct messages. A peer that runs
+/// out of tokens `trips_before_ban` times within `ban_secs` is ignored for
+/// `ban_secs`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct FloodProtectionSettings {
+    pub gossip_burst: u32,
+    pub gossip_per_sec: f64,
+    pub direct_burst: u32,
+    pub direct_per_sec: f64,
+    pub trips_before_ban: u32,
+    pub ban_secs: u64,
+}
+
+impl Default for FloodProtectionSettings {
+    fn default() -> Self {
+        Self {
+            gossip_burst: 30,
+            gossip_per_sec: 5.0,
+            // File chunks and fragments arrive as direct messages too.
+            direct_burst: 120,
+            direct_per_sec: 20.0,
+            trips_before_ban: 3,
+            ban_secs: 10 * 60,
+        }
+    }
+}
+
+impl FloodProtectionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.gossip_burst == 0 || self.direct_burst == 0 {
+            return Err("Burst sizes must be at least 1".to_string());
+        }
+        let valid_rate = |rate: f64| rate.is_finite() && rate > 0.0;
+        if !valid_rate(self.gossip_per_sec) || !valid_rate(self.direct_per_sec) {
+            return Err("Message rates must be positive".to_string());
+        }
+        if self.trips_before_ban == 0 || self.ban_secs == 0 {
+            return Err("Ban threshold and duration must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// WebSocket listeners for peers whose networks only let web traffic
+/// through. `/wss` is served only when a certificate and key are set (PEM
+/// files). Unset ports are picked at random. Changes apply on the next
+/// network start.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct WebsocketSettings {
+    pub enabled: bool,
+    pub ws_port: Option<u16>,
+    pub wss_port: Option<u16>,
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+}
+
+impl WebsocketSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ws_port == Some(0) || self.wss_port == Some(0) {
+            return Err("WebSocket ports must be between 1 and 65535".to_string());
+        }
+        if self.ws_port.is_some() && self.ws_port == self.wss_port {
+            return Err("/ws and /wss need different ports".to_string());
+        }
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err("Set both the TLS certificate and key, or neither".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn serves_wss(&self) -> bool {
+        self.tls_cert_path.is_some() && self.tls_key_path.is_some()
+    }
+}
+
+/// Opt-in REST API on `127.0.0.1` for scripts; see `http_api`. Without a port
+/// it listens on `http_api::DEFAULT_PORT`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct HttpApiSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+impl HttpApiSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port == Some(0) {
+            return Err("HTTP API port must be between 1 and 65535".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Provider for outgoing message translation (OpenAI-compatible endpoint).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct TranslationSettings {
+    #[serde(default)]
+    pub model_endpoint: Option<String>,
+    #[serde(default)]
+    pub model_name: Option<String>,
+    #[serde(default)]
+    pub model_api_key: Option<String>,
+}
+
+impl TranslationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_model_endpoint(self.model_endpoint.as_deref())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserConfig {
+    pub dark_mode: bool,
+    pub timeout: u16,
+    pub identity_private_key: Option<String>, // Ed25519 Secret (Base64)
+    pub identity_public_key: Option<String>,  // Ed25519 Public (Base64)
+    pub encryption_private_key: Option<String>, // X25519 Secret (Base64)
+    pub friends: Vec<FriendConfig>,
+    pub hks_nodes: Vec<String>, // Base64 encoded keys of the tree (Depth 12 = 8191 nodes)
+
+    // New Features
+    pub profile: UserProfile,
+    #[serde(default)]
+    pub pinned_peers: Vec<String>,
+    #[serde(default)]
+    pub is_online: bool, // Offline/Online switch
+    #[serde(default)]
+    pub connectivity: ConnectivitySettings,
+    #[serde(default)]
+    pub libp2p_keypair: Option<String>, // Base64-encoded protobuf keypair for persistent peer ID
+    #[serde(default)]
+    pub pending_invitations: Option<Vec<String>>, // JSON-encoded TrackedInvite objects
+    #[serde(default)]
+    pub theme: ThemeConfig, // Customizable color theme
+    #[serde(default)]
+    pub selected_preset: Option<String>, // Currently selected theme preset key
+    #[serde(default)]
+    pub custom_themes: Vec<CustomThemeEntry>,
+    #[serde(default)]
+    pub github_peer_mapping: std::collections::HashMap<String, String>, // GitHub username → libp2p PeerId
+    #[serde(default)]
+    pub reply_suggestions: ReplySuggestionSettings,
+    #[serde(default)]
+    pub translation: TranslationSettings,
+    #[serde(default)]
+    pub appearance: Option<Appearance>, // None: presets render as authored
+    #[serde(default)]
+    pub auto_reply: AutoReplySettings,
+    #[serde(default)]
+    pub mdns_privacy: MdnsPrivacy,
+    #[serde(default)]
+    pub mdns_discovery_key: Option<String>, // Base64, shared with friends for anonymous mDNS
+    #[serde(default = "default_handshake_ttl_secs")]
+    pub handshake_ttl_secs: i64, // Unanswered connect requests are dropped after this
+    #[serde(default)]
+    pub key_rotations: Vec<String>, // JSON-encoded KeyRotation records, oldest first
+    #[serde(default)]
+    pub flood_protection: FloodProtectionSettings,
+    #[serde(default)]
+    pub bandwidth_monthly_cap_mb: Option<u64>, // None = unlimited
+    #[serde(default)]
+    pub media_cache_cap_mb: Option<u64>, // Received media kept on disk; None = unlimited
+    #[serde(default)]
+    pub auto_download: AutoDownloadSettings,
+    #[serde(default)]
+    pub websocket: WebsocketSettings,
+    #[serde(default)]
+    pub listen_port: Option<u16>, // TCP and QUIC; None = random each start, Some enables UPnP
+    #[serde(default)]
+    pub bot_api_enabled: bool, // Local socket for bots, see the app's bot_api
+    #[serde(default)]
+    pub http_api: HttpApiSettings,
+}
+
+fn default_handshake_ttl_secs() -> i64 {
+    7 * 24 * 60 * 60
+}
+
+impl UserConfig {
+    /// Create the mDNS discovery key on first use; returns whether it was generated.
+    pub fn ensure_mdns_discovery_key(&mut self) -> bool {
+        if self.mdns_discovery_key.is_some() {
+            return false;
+        }
+        use rand::RngCore;
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        self.mdns_discovery_key = Some(BASE64.encode(key));
+        true
+    }
+
+    /// Our public keys as sent in invites, if the identity is set up.
+    pub fn identity_keys(&self) -> Option<crate::network::invite::IdentityKeys> {
+        let secret: [u8; 32] = BASE64
+            .decode(self.encryption_private_key.as_deref()?)
+            .ok()?
+            .try_into()
+            .ok()?;
+        let x25519 = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::from(secret));
+        Some(crate::network::invite::IdentityKeys {
+            x25519_pubkey: BASE64.encode(x25519.as_bytes()),
+            ed25519_pubkey: self.identity_public_key.clone()?,
+        })
+    }
+
+    /// Add `username` to the friends list if missing and store their keys
+    /// when known. Returns whether anything changed.
+    pub fn remember_friend(
+        &mut self,
+        username: &str,
+        keys: Option<&crate::network::invite::IdentityKeys>,
+    ) -> bool {
+        let mut changed = false;
+        let index = match self.friends.iter().position(|f| f.username == username) {
+            Some(index) => index,
+            None => {
+                self.friends.push(FriendConfig {
+                    username: username.to_string(),
+                    alias: None,
+                    x25519_pubkey: None,
+                    ed25519_pubkey: None,
+                    leaf_index: 0,
+                    encrypted_leaf_key: None,
+                    nonce: None,
+                });
+                changed = true;
+                self.friends.len() - 1
+            }
+        };
+        let Some(keys) = keys else {
+            return changed;
+        };
+        let friend = &mut self.friends[index];
+        let x25519 = Some(keys.x25519_pubkey.clone());
+        let ed25519 = Some(keys.ed25519_pubkey.clone());
+        if friend.x25519_pubkey == x25519 && friend.ed25519_pubkey == ed25519 {
+            return changed;
+        }
+        friend.x25519_pubkey = x25519;
+        friend.ed25519_pubkey = ed25519;
+        true
+    }
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            timeout: 0, // 0 = disabled (manual lock only)
+            identity_private_key: None,
+            identity_public_key: None,
+            encryption_private_key: None,
+            friends: vec![],
+            hks_nodes: vec![],
+            profile: UserProfile::default(),
+            pinned_peers: vec![],
+            is_online: false,
+            connectivity: ConnectivitySettings::default(),
+            libp2p_keypair: None,
+            pending_invitations: None,
+            theme: ThemeConfig::default(),
+            selected_preset: None,
+            custom_themes: vec![],
+            github_peer_mapping: std::collections::HashMap::new(),
+            reply_suggestions: ReplySuggestionSettings::default(),
+            translation: TranslationSettings::default(),
+            appearance: None,
+            auto_reply: AutoReplySettings::default(),
+            mdns_privacy: MdnsPrivacy::default(),
+            mdns_discovery_key: None,
+            handshake_ttl_secs: default_handshake_ttl_secs(),
+            key_rotations: vec![],
+            flood_protection: FloodProtectionSettings::default(),
+            bandwidth_monthly_cap_mb: None,
+            media_cache_cap_mb: None,
+            auto_download: AutoDownloadSettings::default(),
+            websocket: WebsocketSettings::default(),
+            listen_port: None,
+            bot_api_enabled: false,
+            http_api: HttpApiSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Config {
+    /// Missing in configs written before versioning, i.e. v0.
+    #[serde(default)]
+    pub schema_version: u32,
+    pub system: SystemConfig,
+    pub user: UserConfig,
+}
+
+// Manager
+pub struct ConfigManager {
+    file_path: PathBuf,
+    key: Option<[u8; 32]>, // Session Key
+}
+
+// Helper to get rchat's keystore path (NOT rvault's path)
+fn rchat_keystore_path(app_dir: &PathBuf) -> PathBuf {
+    app_dir.join("rchat.keystore")
+}
+
+impl ConfigManager {
+    pub fn new(app_dir: PathBuf) -> Self {
+        Self {
+            file_path: app_dir.join("rchat.config"),
+            key: None,
+        }
+    }
+
+    pub fn unlock(&mut self, key: [u8; 32]) {
+        self.key = Some(key);
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.key.is_some()
+    }
+
+    pub fn lock(&mut self) {
+        self.key = None;
+    }
+
+    /// Sub-key for the message journal, derived from the session key.
+    pub fn journal_key(&self) -> Option<[u8; 32]> {
+        self.key.as_ref().map(journal_key_for)
+    }
+
+    pub fn exists(&self) -> bool {
+        self.file_path.exists()
+    }
+
+    /// Repair a config left torn by a crash during save; see `storage::recovery`.
+    pub fn recover_interrupted_save(&self) -> Vec<String> {
+        recovery::recover_config(&self.file_path, is_config_file)
+    }
+
+    /// Initialize new config with password
+    pub async fn init(&mut self, password: &str) -> Result<Config> {
+        if self.file_path.exists() {
+            return Err(anyhow::anyhow!("Config already exists"));
+        }
+
+        // Hash the password for storage
+        let hashed = rvault_core::crypto::hash_data(password.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Hashing failed: {}", e))?;
+
+        // Create rchat's own keystore (not rvault's!)
+        let keystore_path = rchat_keystore_path(&self.file_path.parent().unwrap().to_path_buf());
+        rvault_core::keystore::create_key_vault(password, &keystore_path)
+            .map_err(|e| anyhow::anyhow!("Keystore creation failed: {}", e))?;
+
+        // Load the MEK from our keystore
+        let key = rvault_core::keystore::load_key_from_vault(password, &keystore_path)
+            .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+
+        // Generate Keys
+        let mut csprng = OsRng;
+
+        // 1. Identity Key (Ed25519)
+        let identity_sk = SigningKey::generate(&mut csprng);
+        let identity_pk = identity_sk.verifying_key();
+
+        // 2. Encryption Key (X25519)
+        let encryption_sk = StaticSecret::random_from_rng(&mut csprng);
+
+        // Encode to Base64
+        let identity_sk_b64 = BASE64.encode(identity_sk.to_bytes());
+        let identity_pk_b64 = BASE64.encode(identity_pk.to_bytes());
+        let encryption_sk_b64 = BASE64.encode(encryption_sk.to_bytes());
+
+        let config = Config {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            system: SystemConfig {
+                master_hash: Some(hashed.hash),
+                ..Default::default()
+            },
+            user: UserConfig {
+                identity_private_key: Some(identity_sk_b64),
+                identity_public_key: Some(identity_pk_b64),
+                encryption_private_key: Some(encryption_sk_b64),
+                ..UserConfig::default()
+            },
+        };
+
+        // Update state
+        self.key = Some(key);
+
+        // Save using the derived key
+        Self::save_internal(&config, &key, &self.file_path).await?;
+
+        // Start Session
+        self.remember_session(&key);
+
+        Ok(config)
+    }
+
+    /// Unlock existing config with password
+    pub async fn unlock_with_password(&mut self, password: &str) -> Result<Config> {
+        if !self.file_path.exists() {
+            return Err(anyhow::anyhow!("Config file not found"));
+        }
+
+        let data = fs::read(&self.file_path).await?;
+        let wrapper: ConfigWrapper = serde_json::from_slice(&data)?;
+
+        // Verify password against stored hash first (for better UX/error messages)
+        if !rvault_core::crypto::verify_password(password.as_bytes(), &wrapper.master_hash) {
+            return Err(anyhow::anyhow!("Invalid password"));
+        }
+
+        // Load MEK from rchat's keystore
+        let keystore_path = rchat_keystore_path(&self.file_path.parent().unwrap().to_path_buf());
+        let key = rvault_core::keystore::load_key_from_vault(password, &keystore_path)
+            .map_err(|e| anyhow::anyhow!("Keystore unlock failed: {}", e))?;
+
+        let config = Self::open(&key, &wrapper, &data, &self.file_path)?;
+
+        // Update state
+        self.key = Some(key);
+
+        // Start Session
+        self.remember_session(&key);
+
+        Ok(config)
+    }
+
+    /// Generate a recovery phrase and escrow the current MEK under it,
+    /// replacing any earlier phrase. The phrase is returned once and not kept.
+    pub async fn create_recovery_phrase(&self) -> Result<String> {
+        let key = self.key.ok_or(VaultLocked)?;
+        let phrase = escrow::generate_phrase();
+        self.write_escrow(&phrase, &key)?;
+        Ok(phrase)
+    }
+
+    fn write_escrow(&self, phrase: &str, key: &[u8; 32]) -> Result<()> {
+        let sealed = escrow::seal(phrase, key)?;
+        let app_dir = self.file_path.parent().unwrap();
+        recovery::write_atomic(
+            &escrow::escrow_path(app_dir),
+            &serde_json::to_vec_pretty(&sealed)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn has_recovery_phrase(&self) -> bool {
+        escrow::escrow_path(self.file_path.parent().unwrap()).exists()
+    }
+
+    /// Open the vault with its recovery phrase and set `new_password`. The
+    /// keystore is rebuilt, so the MEK changes: the config is re-encrypted,
+    /// the escrow re-sealed under the same phrase, and the journal key of the
+    /// old MEK is returned so the caller can rekey the journal.
+    pub async fn recover(
+        &mut self,
+        phrase: &str,
+        new_password: &str,
+    ) -> Result<(Config, [u8; 32])> {
+        if !self.file_path.exists() {
+            return Err(anyhow::anyhow!("Config file not found"));
+        }
+        let app_dir = self.file_path.parent().unwrap().to_path_buf();
+        let escrow_data = fs::read(escrow::escrow_path(&app_dir))
+            .await
+            .map_err(|_| anyhow::anyhow!("No recovery phrase was set up for this vault"))?;
+        let old_key = escrow::open(phrase, &serde_json::from_slice(&escrow_data)?)?;
+
+        let data = fs::read(&self.file_path).await?;
+        let wrapper: ConfigWrapper = serde_json::from_slice(&data)?;
+        let mut config = Self::open(&old_key, &wrapper, &data, &self.file_path)?;
+
+        let hashed = rvault_core::crypto::hash_data(new_password.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Hashing failed: {}", e))?;
+        config.system.master_hash = Some(hashed.hash);
+
+        // Keep the old keystore until the new one exists.
+        let keystore_path = rchat_keystore_path(&app_dir);
+        let keystore_backup = recovery::backup_path(&keystore_path);
+        if keystore_path.exists() {
+            fs::rename(&keystore_path, &keystore_backup).await?;
+        }
+        let created = rvault_core::keystore::create_key_vault(new_password, &keystore_path)
+            .map_err(|e| anyhow::anyhow!("Keystore creation failed: {}", e))
+            .and_then(|_| {
+                rvault_core::keystore::load_key_from_vault(new_password, &keystore_path)
+                    .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))
+            });
+        let key = match created {
+            Ok(key) => key,
+            Err(e) => {
+                if keystore_backup.exists() {
+                    let _ = fs::rename(&keystore_backup, &keystore_path).await;
+                }
+                return Err(e);
+            }
+        };
+
+        Self::save_internal(&config, &key, &self.file_path).await?;
+        self.write_escrow(phrase, &key)?;
+        if keystore_backup.exists() {
+            let _ = fs::remove_file(&keystore_backup).await;
+        }
+        println!("[Config] 🔑 Vault recovered with recovery phrase");
+
+        self.key = Some(key);
+        if keychain::is_enabled(&app_dir) {
+            // The entry is bound to the old password hash.
+            if let Some(master_hash) = &config.system.master_hash {
+                if let Err(e) = keychain::store(&app_dir, &key, master_hash) {
+                    eprintln!("[Keychain] ⚠️ Failed to update keychain entry: {}", e);
+                }
+            }
+        }
+        self.remember_session(&key);
+        Ok((config, journal_key_for(&old_key)))
+    }
+
+    /// Re-check the vault password without touching the session, e.g. before
+    /// revealing hidden chats.
+    pub async fn verify_password(&self, password: &str) -> Result<bool> {
+        let data = fs::read(&self.file_path).await?;
+        let wrapper: ConfigWrapper = serde_json::from_slice(&data)?;
+        Ok(rvault_core::crypto::verify_password(
+            password.as_bytes(),
+            &wrapper.master_hash,
+        ))
+    }
+
+    pub async fn load(&self) -> Result<Config> {
+        let key = self.key.ok_or(VaultLocked)?;
+
+        if !self.file_path.exists() {
+            return Err(anyhow::anyhow!("Config file not found"));
+        }
+
+        let data = fs::read(&self.file_path).await?;
+        let wrapper: ConfigWrapper = serde_json::from_slice(&data)?;
+
+        Self::open(&key, &wrapper, &data, &self.file_path)
+    }
+
+    /// Synchronous version of load for use in sync contexts
+    pub fn load_sync(&self) -> Result<Config> {
+        let key = self.key.ok_or(VaultLocked)?;
+
+        if !self.file_path.exists() {
+            return Err(anyhow::anyhow!("Config file not found"));
+        }
+
+        let data = std::fs::read(&self.file_path)?;
+        let wrapper: ConfigWrapper = serde_json::from_slice(&data)?;
+
+        Self::open(&key, &wrapper, &data, &self.file_path)
+    }
+
+    pub async fn save(&self, config: &Config) -> Result<()> {
+        let key = self.key.ok_or(VaultLocked)?;
+        Self::save_internal(config, &key, &self.file_path).await
+    }
+
+    // Internal static save to avoid borrowing issues or for use in init
+    async fn save_internal(config: &Config, key: &[u8], path: &PathBuf) -> Result<()> {
+        let file_data = Self::encrypt(config, key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || write_config_file(&path, &file_data)).await??;
+        Ok(())
+    }
+
+    fn encrypt(config: &Config, key: &[u8]) -> Result<Vec<u8>> {
+        let plain_json = serde_json::to_string(config)?;
+        let (ciphertext, nonce) = rvault_core::crypto::encrypt_with_key(key, plain_json.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
+
+        // Ensure master_hash is present
+        let master_hash = config
+            .system
+            .master_hash
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("System config missing master_hash"))?;
+
+        let wrapper = ConfigWrapper {
+            master_hash,
+            ciphertext,
+            nonce,
+        };
+
+        Ok(serde_json::to_vec_pretty(&wrapper)?)
+    }
+
+    /// Decrypt a config file, upgrading it to the current schema first. An
+    /// upgraded config is written back; the original file is kept next to it
+    /// as `<name>.v<old>.bak`.
+    fn open(key: &[u8], wrapper: &ConfigWrapper, data: &[u8], path: &Path) -> Result<Config> {
+        let decrypted_json =
+            rvault_core::crypto::decrypt_with_key(key, &wrapper.ciphertext, &wrapper.nonce)
+                .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))?;
+
+        let mut value: serde_json::Value = serde_json::from_str(&decrypted_json)?;
+        let Some(from) = config_migration::migrate(&mut value)? else {
+            return Ok(serde_json::from_value(value)?);
+        };
+        let config: Config = serde_json::from_value(value)?;
+
+        recovery::write_atomic(&config_migration::versioned_backup_path(path, from), data)?;
+        write_config_file(path, &Self::encrypt(&config, key)?)?;
+        println!(
+            "[Config] ⬆️ Migrated config schema v{} -> v{}",
+            from, CURRENT_SCHEMA_VERSION
+        );
+        Ok(config)
+    }
+    pub async fn has_token(&self) -> bool {
+        if let Some(key) = self.key {
+            if let Ok(data) = fs::read(&self.file_path).await {
+                if let Ok(wrapper) = serde_json::from_slice::<ConfigWrapper>(&data) {
+                    if let Ok(config) = Self::open(&key, &wrapper, &data, &self.file_path) {
+                        return config.system.github_token.is_some();
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    pub async fn reset(&mut self) -> Result<()> {
+        if self.file_path.exists() {
+            fs::remove_file(&self.file_path).await?;
+        }
+        // Otherwise startup recovery would bring the old vault back.
+        let migration_backups = (0..CURRENT_SCHEMA_VERSION)
+            .map(|version| config_migration::versioned_backup_path(&self.file_path, version));
+        let app_dir = self.file_path.parent().unwrap();
+        for leftover in [
+            recovery::backup_path(&self.file_path),
+            recovery::temp_path(&self.file_path),
+            escrow::escrow_path(app_dir),
+        ]
+        .into_iter()
+        .chain(migration_backups)
+        {
+            if leftover.exists() {
+                fs::remove_file(&leftover).await?;
+            }
+        }
+        self.key = None;
+        let _ = session::end_session();
+        if let Err(e) = keychain::clear(app_dir) {
+            eprintln!("[Keychain] ⚠️ Failed to remove keychain entry: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Keep the key for the next start: in the OS keychain if the user opted
+    /// in (already stored there), otherwise in an rvault session file.
+    fn remember_session(&self, key: &[u8; 32]) {
+        if keychain::is_enabled(self.file_path.parent().unwrap()) {
+            return;
+        }
+        if let Ok(token) = session::start_session(key) {
+            let _ = session::write_current(&token);
+        }
+    }
+
+    fn stored_master_hash(&self) -> Result<String> {
+        let data = std::fs::read(&self.file_path)?;
+        let wrapper: ConfigWrapper = serde_json::from_slice(&data)?;
+        Ok(wrapper.master_hash)
+    }
+
+    pub fn keychain_unlock_enabled(&self) -> bool {
+        keychain::is_enabled(self.file_path.parent().unwrap())
+    }
+
+    /// Opt in to keychain unlock; the session file is no longer needed.
+    pub fn enable_keychain_unlock(&self) -> Result<()> {
+        let key = self.key.ok_or(VaultLocked)?;
+        keychain::store(
+            self.file_path.parent().unwrap(),
+            &key,
+            &self.stored_master_hash()?,
+        )?;
+        let _ = session::end_session();
+        Ok(())
+    }
+
+    /// Opt out again, falling back to a session file while unlocked.
+    pub fn disable_keychain_unlock(&self) -> Result<()> {
+        keychain::clear(self.file_path.parent().unwrap())?;
+        if let Some(key) = self.key {
+            self.remember_session(&key);
+        }
+        Ok(())
+    }
+
+    pub fn try_restore_session(&mut self) -> bool {
+        if self.keychain_unlock_enabled() {
+            let restored = self.stored_master_hash().and_then(|master_hash| {
+                keychain::load(self.file_path.parent().unwrap(), &master_hash)
+            });
+            match restored {
+                Ok(Some(key)) => {
+                    self.key = Some(key);
+                    return true;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("[Keychain] ⚠️ Keychain unlock failed: {}", e),
+            }
+        }
+        if let Ok(key_vec) = session::get_key_from_session() {
+            if let Ok(key) = key_vec.try_into() {
+                self.key = Some(key);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConfigWrapper {
+    master_hash: String,
+    ciphertext: String,
+    nonce: String,
+}
+
+fn journal_key_for(key: &[u8; 32]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(b"rchat-journal-v1");
+    hasher.update(key);
+    hasher.finalize().into()
+}
+
+/// Write an encrypted config, keeping the last good one so startup recovery
+/// can roll back to it.
+fn write_config_file(path: &Path, file_data: &[u8]) -> std::io::Result<()> {
+    if let Ok(current) = std::fs::read(path) {
+        if is_config_file(&current) {
+            recovery::write_atomic(&recovery::backup_path(path), &current)?;
+        }
+    }
+    recovery::write_atomic(path, file_data)
+}
+
+fn is_config_file(data: &[u8]) -> bool {
+    serde_json::from_slice::<ConfigWrapper>(data).is_ok()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connectivity_mode_derivation_matches_presets() {
+        assert_eq!(
+            ConnectivitySettings::invisible().derive_mode(),
+            ConnectivityMode::Invisible
+        );
+        assert_eq!(
+            ConnectivitySettings::lan().derive_mode(),
+            ConnectivityMode::Lan
+        );
+        assert_eq!(
+            ConnectivitySettings::reachable().derive_mode(),
+            ConnectivityMode::Reachable
+        );
+
+        let custom = ConnectivitySettings {
+            mode: ConnectivityMode::Reachable,
+            mdns_enabled: true,
+            github_sync_enabled: true,
+            nat_keepalive_enabled: false,
+            punch_assist_enabled: true,
+        };
+        assert_eq!(custom.derive_mode(), ConnectivityMode::Custom);
+    }
+
+    #[test]
+    fn connectivity_defaults_to_reachable_for_legacy_config() {
+        let legacy = r##"{
+          "dark_mode": true,
+          "timeout": 0,
+          "identity_private_key": null,
+          "identity_public_key": null,
+          "encryption_private_key": null,
+          "friends": [],
+          "hks_nodes": [],
+          "profile": { "alias": null, "avatar_path": null },
+          "pinned_peers": [],
+          "is_online": false,
+          "libp2p_keypair": null,
+          "pending_invitations": null,
+          "theme": {
+            "base": {"950":"#0b0f14","900":"#111827","800":"#1f2937","700":"#374151","600":"#4b5563","500":"#6b7280","400":"#9ca3af","300":"#d1d5db","200":"#e5e7eb","100":"#f3f4f6"},
+            "primary": {"600":"#0d9488","500":"#14b8a6","400":"#2dd4bf","300":"#5eead4"},
+            "secondary": {"600":"#7c3aed","500":"#8b5cf6","400":"#a78bfa","300":"#c4b5fd"},
+            "error": {"600":"#dc2626","500":"#ef4444","400":"#f87171","300":"#fca5a5"},
+            "success": {"600":"#16a34a","500":"#22c55e","400":"#4ade80","300":"#86efac"},
+            "info": {"600":"#2563eb","500":"#3b82f6","400":"#60a5fa","300":"#93c5fd"},
+            "warning": {"600":"#d97706","500":"#f59e0b","400":"#fbbf24","300":"#fcd34d"}
+          },
+          "selected_preset": null,
+          "custom_themes": [],
+          "github_peer_mapping": {}
+        }"##;
+
+        let parsed: UserConfig = serde_json::from_str(legacy).expect("legacy user config parses");
+        assert_eq!(parsed.connectivity, ConnectivitySettings::reachable());
+    }
+
+    #[test]
+    fn test_crypto_verification() {
+        let password = "test_password";
+        let hashed = rvault_core::crypto::hash_data(password.as_bytes()).expect("Hashing failed");
+        println!("Hash: {}", hashed.hash);
+        assert!(
+            rvault_core::crypto::verify_password(password.as_bytes(), &hashed.hash),
+            "Verification failed"
+        );
+
+        // This step verifies if get_encryption_key works with the password.
+        // It will fail if keystore.rvault is missing or password doesn't match the one in keystore.
+        // We expect it to fail in CI/clean env, but we want to see the error message.
+        match rvault_core::vault::Vault::get_encryption_key(password, &hashed.hash) {
+            Ok(_) => println!("get_encryption_key success"),
+            Err(e) => println!(
+                "get_encryption_key failed as expected (if no keystore): {}",
+                e
+            ),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Schema versioning for the encrypted config.
+//!
+//! The decrypted payload carries a `schema_version`. Configs written before it
+//! existed count as version 0. On load the raw JSON is upgraded one step at a
+//! time by [`migrate`] before it is deserialized, so renamed or removed fields
+//! can be moved explicitly instead of relying on serde defaults. The manager
+//! keeps a copy of the pre-migration file at [`versioned_backup_path`].
+
+use anyhow::Result;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+type Step = fn(&mut Value) -> Result<()>;
+
+/// `STEPS[n]` upgrades a version `n` payload to version `n + 1`.
+const STEPS: [Step; CURRENT_SCHEMA_VERSION as usize] = [v0_to_v1];
+
+/// v0 is every config written before versioning. Fields added since then
+/// already have serde defaults, so there is nothing to move yet.
+fn v0_to_v1(value: &mut Value) -> Result<()> {
+    if !value.get("user").is_some_and(Value::is_object) {
+        return Err(anyhow::anyhow!("Config has no user section"));
+    }
+    Ok(())
+}
+
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+/// Upgrade `value` in place to [`CURRENT_SCHEMA_VERSION`]. Returns the version
+/// it started at if any step ran.
+pub fn migrate(value: &mut Value) -> Result<Option<u32>> {
+    let from = schema_version(value);
+    if from > CURRENT_SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "Config uses schema v{} but this RChat only knows up to v{}; please update the app",
+            from,
+            CURRENT_SCHEMA_VERSION
+        ));
+    }
+    if from == CURRENT_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    for version in from..CURRENT_SCHEMA_VERSION {
+        STEPS[version as usize](value).map_err(|e| {
+            anyhow::anyhow!(
+                "Config migration v{} -> v{} failed: {}",
+                version,
+                version + 1,
+                e
+            )
+        })?;
+        value["schema_version"] = Value::from(version + 1);
+    }
+    Ok(Some(from))
+}
+
+/// Where the file is kept before a migration from `version`, e.g. `rchat.config.v0.bak`.
+pub fn versioned_backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_config_is_upgraded_and_newer_is_rejected() {
+        let mut legacy = serde_json::json!({ "system": {}, "user": { "dark_mode": true } });
+        assert_eq!(migrate(&mut legacy).unwrap(), Some(0));
+        assert_eq!(schema_version(&legacy), CURRENT_SCHEMA_VERSION);
+        assert_eq!(legacy["user"]["dark_mode"], true);
+        assert_eq!(migrate(&mut legacy).unwrap(), None);
+
+        let mut newer = serde_json::json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1 });
+        assert!(migrate(&mut newer).is_err());
+
+        assert_eq!(
+            versioned_backup_path(Path::new("/data/rchat.config"), 0),
+            PathBuf::from("/data/rchat.config.v0.bak")
+        );
+    }
+}
@@ -0,0 +1,4591 @@
+use rusqlite::{Connection, OptionalExtension};
+// use std::path::Path; // Unused
+use anyhow::Context;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::migrations::add_column_if_missing;
+use crate::chat::message::{MessageContent, MessageStatus};
+
+// --- 1. Rust Structs (Data Models) ---
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Peer {
+    pub id: String,
+    pub alias: String,
+    pub last_seen: i64, // Unix Timestamp
+    pub public_key: Vec<u8>,
+    pub method: String,              // "local", "gist", "manual", etc.
+    pub avatar_hash: Option<String>, // storage::object file hash
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "MessageRow", into = "MessageRow")]
+pub struct Message {
+    pub id: String,
+    pub chat_id: String,
+    pub peer_id: String,
+    pub timestamp: i64,
+    pub content: MessageContent,
+    pub status: String, // 'pending', 'sent', 'delivered', 'read', 'failed'
+    pub content_metadata: Option<String>, // JSON: {"width": 1920, "height": 1080, ...}
+    pub sender_alias: Option<String>, // Sender's display name
+    /// Lamport clock within the chat; 0 on a new message means "assign the
+    /// next one" when it is inserted.
+    pub logical: i64,
+}
+
+/// A [`Message`] as the messages table, the UI and older payloads lay it out:
+/// the content split into `content_type`, `text_content` and `file_hash`.
+#[derive(Serialize, Deserialize)]
+struct MessageRow {
+    id: String,
+    chat_id: String,
+    peer_id: String,
+    timestamp: i64,
+    content_type: String,
+    text_content: Option<String>,
+    file_hash: Option<String>,
+    status: String,
+    content_metadata: Option<String>,
+    sender_alias: Option<String>,
+    #[serde(default)]
+    logical: i64,
+}
+
+impl From<MessageRow> for Message {
+    fn from(row: MessageRow) -> Self {
+        Self {
+            content: MessageContent::from_columns(
+                &row.content_type,
+                row.text_content,
+                row.file_hash,
+                row.content_metadata.as_deref(),
+            ),
+            id: row.id,
+            chat_id: row.chat_id,
+            peer_id: row.peer_id,
+            timestamp: row.timestamp,
+            status: row.status,
+            content_metadata: row.content_metadata,
+            sender_alias: row.sender_alias,
+            logical: row.logical,
+        }
+    }
+}
+
+impl From<Message> for MessageRow {
+    fn from(msg: Message) -> Self {
+        let (content_type, text_content, file_hash) = msg.content.into_parts();
+        Self {
+            id: msg.id,
+            chat_id: msg.chat_id,
+            peer_id: msg.peer_id,
+            timestamp: msg.timestamp,
+            content_type: content_type.to_string(),
+            text_content,
+            file_hash,
+            status: msg.status,
+            content_metadata: msg.content_metadata,
+            sender_alias: msg.sender_alias,
+            logical: msg.logical,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub parent_id: Option<String>,
+    /// Position among envelopes with the same parent.
+    pub sort_order: i64,
+}
+
+/// Totals for an envelope including the envelopes nested in it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvelopeSummary {
+    pub envelope_id: String,
+    pub chat_count: i64,
+    pub unread_count: i64,
+    pub latest_activity: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatAssignment {
+    pub chat_id: String,
+    pub envelope_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sticker {
+    pub file_hash: String,
+    pub name: Option<String>,
+    pub created_at: i64,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatListItem {
+    pub id: String,
+    pub name: String,
+    pub is_group: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChatMember {
+    pub peer_id: String,
+    pub role: String,
+    pub joined_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ChatConnectionStats {
+    pub first_connected_at: Option<i64>,
+    pub last_connected_at: Option<i64>,
+    pub reconnect_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ChatContentBreakdown {
+    pub text: i64,
+    pub sticker: i64,
+    pub image: i64,
+    pub video: i64,
+    pub audio: i64,
+    pub document: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ChatMessageStats {
+    pub sent_total: i64,
+    pub received_total: i64,
+    pub sent: ChatContentBreakdown,
+    pub received: ChatContentBreakdown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatFileRow {
+    pub message_id: String,
+    pub timestamp: i64,
+    pub content_type: String,
+    pub file_hash: String,
+    pub file_name: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub mime_type: Option<String>,
+    pub sender: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatBridge {
+    pub chat_id: String,
+    pub kind: String,     // "matrix", "irc"
+    pub endpoint: String, // Matrix homeserver URL or IRC host:port
+    pub target: String,   // Matrix room id or IRC channel
+    pub nick: String,     // IRC nickname
+    /// Matrix access token or XMPP login. Kept in the encrypted config
+    /// (`SystemConfig::bridge_secrets`) and only filled in to run the bridge.
+    #[serde(skip)]
+    pub credential: String,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BridgeIdentity {
+    pub chat_id: String,
+    pub remote_user: String,
+    pub display_name: String,
+}
+
+/// The nickname a peer goes by on a bridge's remote side.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BridgeNick {
+    pub chat_id: String,
+    pub peer_id: String,
+    pub nick: String,
+}
+
+// --- 2. Database Initialization ---
+pub fn connect_to_db() -> anyhow::Result<Connection> {
+    if let Some(project_dirs) = ProjectDirs::from("io.github", "ata-sesli", "RChat") {
+        let project_dirs = project_dirs.data_dir();
+        let database_dir = project_dirs.join("databases");
+        std::fs::create_dir_all(&database_dir).context("Failed to create database directory")?;
+        let final_path = database_dir.join("rchat.sqlite");
+        let db_exists = final_path.exists();
+        let connection =
+            Connection::open(&final_path).context("Failed to open database connection")?;
+
+        // Always ensure schema exists!
+        create_tables(&connection)?;
+
+        // Enable Foreign Keys explicitly (SQLite default is OFF)
+        connection
+            .pragma_update(None, "foreign_keys", "ON")
+            .context("Failed to enable foreign keys")?;
+
+        // Set busy timeout to 5 seconds to avoid 'database is locked' errors
+        connection
+            .pragma_update(None, "busy_timeout", 5000)
+            .context("Failed to set busy timeout")?;
+
+        if !db_exists {
+            // Only verify or notify if needed, but creates happened above
+            println!("Successfully initialized database schema!");
+        }
+        Ok(connection)
+    } else {
+        anyhow::bail!("Failed to determine project directories")
+    }
+}
+
+/// A fresh database in memory with the full schema, for tests.
+pub fn connect_in_memory() -> anyhow::Result<Connection> {
+    let connection = Connection::open_in_memory().context("Failed to open in-memory database")?;
+    create_tables(&connection)?;
+    Ok(connection)
+}
+
+// Private helper to ensure tables exist
+pub(super) fn create_tables(conn: &Connection) -> anyhow::Result<()> {
+    // --- Critical Performance & Safety Settings ---
+    // Enable Write-Ahead Logging for concurrency (Readers don't block Writers)
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    // Relax sync slightly for SSD health (optional, good for desktop apps)
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    // Enforce Foreign Key constraints (SQLite disables them by default!)
+    conn.execute("PRAGMA foreign_keys = ON;", [])?;
+
+    // --- Schema Creation ---
+    super::migrations::run(conn)?;
+
+    // Hard cutover: remove legacy accidental "General" chat data.
+    remove_legacy_general_data(conn)?;
+
+    seed_defaults(conn)?;
+
+    Ok(())
+}
+
+/// Migration 1: the schema as it was before versioned migrations. Every step
+/// is idempotent, so it also brings older unversioned databases up to date.
+pub(super) fn baseline_schema(conn: &Connection) -> anyhow::Result<()> {
+    // 1. Peers
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS peers (
+             id TEXT NOT NULL PRIMARY KEY,
+             alias TEXT NOT NULL,
+             last_seen INTEGER,
+             public_key BLOB NOT NULL,
+             method TEXT NOT NULL DEFAULT 'unknown'
+         )",
+        [],
+    )?;
+
+    // Migration: per-peer reputation from observed network behaviour
+    add_column_if_missing(conn, "peers", "reputation", "REAL NOT NULL DEFAULT 0")?;
+    add_column_if_missing(
+        conn,
+        "peers",
+        "reputation_updated_at",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "peers",
+        "invalid_messages",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "peers",
+        "rate_limit_trips",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "peers",
+        "failed_deliveries",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+
+    // Migration: contact avatars and local overrides of what peers announce
+    add_column_if_missing(conn, "peers", "avatar_hash", "TEXT")?;
+    add_column_if_missing(
+        conn,
+        "peers",
+        "alias_is_local",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "peers",
+        "avatar_is_local",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+
+    // 2. Chats
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chats (
+             id TEXT NOT NULL PRIMARY KEY,
+             name TEXT NOT NULL,
+             is_group INTEGER DEFAULT 0 NOT NULL,
+             encryption_key BLOB NOT NULL
+         )",
+        [],
+    )?;
+
+    // SEED: Ensure 'Me' user exists
+    let me_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM peers WHERE id = ?1)",
+            ["Me"],
+            |row| row.get(0),
+        )
+        .unwrap_or(false);
+
+    if !me_exists {
+        println!("Seeding default 'Me' user...");
+        conn.execute(
+            "INSERT INTO peers (id, alias, last_seen, public_key, method) VALUES (?1, ?2, ?3, ?4, ?5)",
+            ("Me", "Me", 0, vec![0u8; 32], "self"), // method = "self" for the user's own entry
+        )?;
+    }
+
+    // 3. Chat Peers (Junction Table)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chat_peers (
+             chat_id TEXT NOT NULL,
+             peer_id TEXT NOT NULL,
+             role TEXT DEFAULT 'member' NOT NULL,
+             joined_at INTEGER NOT NULL,
+             PRIMARY KEY (chat_id, peer_id),
+             FOREIGN KEY (peer_id) REFERENCES peers(id),
+             FOREIGN KEY (chat_id) REFERENCES chats(id)
+         )",
+        [],
+    )?;
+
+    // 4. Files
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS files (
+             file_hash TEXT PRIMARY KEY,
+             file_name TEXT,
+             mime_type TEXT,
+             size_bytes INTEGER,
+             is_complete BOOLEAN DEFAULT 0
+         )",
+        [],
+    )?;
+
+    // 5. File Chunks
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS file_chunks (
+             file_hash TEXT NOT NULL,
+             chunk_order INTEGER NOT NULL,
+             chunk_hash TEXT NOT NULL,
+             chunk_size INTEGER NOT NULL,
+             PRIMARY KEY (file_hash, chunk_order),
+             FOREIGN KEY (file_hash) REFERENCES files(file_hash)
+         )",
+        [],
+    )?;
+
+    // 5b. Stickers (local sticker library registry)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stickers (
+             file_hash TEXT NOT NULL PRIMARY KEY,
+             name TEXT,
+             created_at INTEGER NOT NULL,
+             source TEXT NOT NULL DEFAULT 'local',
+             FOREIGN KEY (file_hash) REFERENCES files(file_hash) ON DELETE CASCADE
+         )",
+        [],
+    )?;
+
+    // 5c. Per-chat durable connection stats
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chat_connection_stats (
+             chat_id TEXT NOT NULL PRIMARY KEY,
+             first_connected_at INTEGER,
+             last_connected_at INTEGER,
+             reconnect_count INTEGER NOT NULL DEFAULT 0
+         )",
+        [],
+    )?;
+
+    // 6. Messages
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS messages (
+             id TEXT NOT NULL PRIMARY KEY,
+             chat_id TEXT NOT NULL,
+             peer_id TEXT NOT NULL,
+             timestamp INTEGER NOT NULL,
+             content_type TEXT NOT NULL,
+             text_content TEXT,
+             file_hash TEXT,
+             status TEXT NOT NULL DEFAULT 'pending',
+             FOREIGN KEY (chat_id) REFERENCES chats(id),
+             FOREIGN KEY (peer_id) REFERENCES peers(id),
+             FOREIGN KEY (file_hash) REFERENCES files(file_hash)
+         )",
+        [],
+    )?;
+
+    // Migration: Add status column if it doesn't exist
+    add_column_if_missing(
+        conn,
+        "messages",
+        "status",
+        "TEXT NOT NULL DEFAULT 'pending'",
+    )?;
+
+    // Migration: Add content_metadata column for cached computed attributes (width, height, duration, etc.)
+    add_column_if_missing(conn, "messages", "content_metadata", "TEXT")?;
+
+    // Migration: Add sender_alias column for display name from messages
+    add_column_if_missing(conn, "messages", "sender_alias", "TEXT")?;
+
+    // Migration: personal star/bookmark flag (local only, never synced)
+    add_column_if_missing(conn, "messages", "starred", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "messages", "starred_at", "INTEGER")?;
+
+    // Migration: hard-cut legacy voice content type to canonical audio
+    conn.execute(
+        "UPDATE messages SET content_type = 'audio' WHERE content_type = 'voice'",
+        [],
+    )?;
+
+    // Migration: Add source column to stickers table if missing
+    add_column_if_missing(conn, "stickers", "source", "TEXT NOT NULL DEFAULT 'local'")?;
+
+    // 7. Envelopes
+    // 7. Envelopes
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS envelopes (
+                id TEXT NOT NULL PRIMARY KEY,
+                name TEXT NOT NULL,
+                icon TEXT
+            )",
+        [],
+    )?;
+
+    // Attempt to add 'icon' column if it doesn't exist (Migration for existing DBs)
+    add_column_if_missing(conn, "envelopes", "icon", "TEXT")?;
+
+    // 8. Chat Envelopes (Assignments)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chat_envelopes (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                envelope_id TEXT NOT NULL,
+                FOREIGN KEY (envelope_id) REFERENCES envelopes(id) ON DELETE CASCADE
+            )",
+        [],
+    )?;
+
+    // 9. Known Devices table removed - using peers table instead
+
+    // 10. Chat Bridges (relay a group to a Matrix room / IRC channel)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chat_bridges (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                kind TEXT NOT NULL,
+                endpoint TEXT NOT NULL,
+                target TEXT NOT NULL,
+                credential TEXT NOT NULL DEFAULT '',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 11. Bridge Identities (remote user -> display name shown in rchat)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bridge_identities (
+                chat_id TEXT NOT NULL,
+                remote_user TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                PRIMARY KEY (chat_id, remote_user),
+                FOREIGN KEY (chat_id) REFERENCES chat_bridges(chat_id) ON DELETE CASCADE
+            )",
+        [],
+    )?;
+
+    // 12. Journal opt-in (chats mirrored into the encrypted export journal)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_chats (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                enabled_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 13. Per-chat notification settings (direct chat ids are peer ids, so this is per-peer too)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chat_settings (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                custom_sound_hash TEXT, -- audio object in the files table
+                priority TEXT NOT NULL DEFAULT 'normal',
+                updated_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // Migration: mute and notification level columns for chat_settings
+    add_column_if_missing(conn, "chat_settings", "mute_until", "INTEGER")?;
+    add_column_if_missing(
+        conn,
+        "chat_settings",
+        "notification_level",
+        "TEXT NOT NULL DEFAULT 'all'",
+    )?;
+
+    // Migration: per-chat outgoing translation
+    add_column_if_missing(
+        conn,
+        "chat_settings",
+        "send_translated",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(conn, "chat_settings", "translate_to", "TEXT")?;
+
+    // Migration: hidden chats (revealed only after re-entering the vault password)
+    add_column_if_missing(
+        conn,
+        "chat_settings",
+        "hidden",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+
+    // Migration: block saving received media out of sensitive chats
+    add_column_if_missing(
+        conn,
+        "chat_settings",
+        "restrict_media_export",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+
+    // 14. Spam scores for message requests (first-contact chats we have not replied to)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS message_request_scores (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                peer_id TEXT NOT NULL,
+                msg_id TEXT NOT NULL,
+                score INTEGER NOT NULL,
+                level TEXT NOT NULL,
+                reasons TEXT NOT NULL, -- JSON array of strings
+                scored_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 15. Unsent message drafts, one per chat
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS drafts (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                text TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 16. Done state for 'task' messages in the self chat
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS self_tasks (
+                message_id TEXT NOT NULL PRIMARY KEY,
+                done INTEGER NOT NULL DEFAULT 0,
+                done_at INTEGER,
+                FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+            )",
+        [],
+    )?;
+
+    // 17. Scheduled reminders for self-chat messages
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_id TEXT NOT NULL,
+                remind_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                fired_at INTEGER,
+                FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+            )",
+        [],
+    )?;
+
+    // 18. Local-only usage counters (see storage::metrics); never transmitted
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_metrics (
+                metric TEXT NOT NULL PRIMARY KEY,
+                count INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 19. Away-mode auto replies: last reply per chat, for rate limiting
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auto_replies (
+                chat_id TEXT NOT NULL PRIMARY KEY,
+                last_replied_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 20. mDNS discovery keys friends shared with us, by libp2p peer id
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS peer_discovery_keys (
+                peer_id TEXT NOT NULL PRIMARY KEY,
+                discovery_key TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 21. Connect handshakes still waiting for the other side ('outgoing'/'incoming')
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS handshake_state (
+                peer_id TEXT NOT NULL PRIMARY KEY,
+                direction TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+
+    // 22. Bytes exchanged per UTC day (YYYY-MM-DD), peer and protocol
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bandwidth_stats (
+                day TEXT NOT NULL,
+                peer_id TEXT NOT NULL,
+                protocol TEXT NOT NULL,
+                bytes_sent INTEGER NOT NULL DEFAULT 0,
+                bytes_received INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, peer_id, protocol)
+            )",
+        [],
+    )?;
+
+    // 23. Addresses we successfully dialed peers at, for reconnecting after restarts
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS peer_addresses (
+                peer_id TEXT NOT NULL,
+                address TEXT NOT NULL,
+                last_success_at INTEGER NOT NULL,
+                PRIMARY KEY (peer_id, address)
+            )",
+        [],
+    )?;
+
+    // 24. Messages to send later ('pending', 'sent' or 'failed')
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id TEXT NOT NULL,
+                text TEXT NOT NULL,
+                send_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                message_id TEXT,
+                error TEXT
+            )",
+        [],
+    )?;
+
+    // --- Indexes (Crucial for Speed) ---
+
+    // Speed up loading chat history (WHERE chat_id = ?)
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_messages_chat_id ON messages(chat_id)",
+        [],
+    )?;
+
+    // Speed up sorting messages (ORDER BY timestamp)
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_messages_timestamp ON messages(timestamp)",
+        [],
+    )?;
+
+    // Speed up finding chunks for a file (WHERE file_hash = ?)
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_file_chunks_file_hash ON file_chunks(file_hash)",
+        [],
+    )?;
+
+    // Speed up sticker list ordering
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_stickers_created_at ON stickers(created_at DESC)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_reminders_pending ON reminders(remind_at) WHERE fired_at IS NULL",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chat_connection_stats_last_connected
+         ON chat_connection_stats(last_connected_at DESC)",
+        [],
+    )?;
+
+    // known_devices index removed - table no longer exists
+
+    Ok(())
+}
+
+fn seed_defaults(conn: &Connection) -> anyhow::Result<()> {
+    // 1. Ensure 'Me' Peer exists
+    conn.execute(
+        "INSERT OR IGNORE INTO peers (id, alias, last_seen, public_key) 
+         VALUES (?1, ?2, ?3, ?4)",
+        (
+            "Me",
+            "Me (You)",
+            0,
+            Vec::new(), // Dummy empty key for self
+        ),
+    )?;
+
+    // 2. Ensure 'self' Chat exists
+    conn.execute(
+        "INSERT OR IGNORE INTO chats (id, name, is_group, encryption_key) 
+         VALUES (?1, ?2, ?3, ?4)",
+        (
+            "self",
+            "Note to Self",
+            0,
+            Vec::new(), // Dummy empty key for self chat
+        ),
+    )?;
+
+    // 3. Ensure joined_at for 'Me' in 'self' chat
+    conn.execute(
+        "INSERT OR IGNORE INTO chat_peers (chat_id, peer_id, role, joined_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        ("self", "Me", "admin", 0),
+    )?;
+
+    Ok(())
+}
+
+fn remove_legacy_general_data(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute(
+        "DELETE FROM messages WHERE chat_id = 'General' OR peer_id = 'General'",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM chat_peers WHERE chat_id = 'General' OR peer_id = 'General'",
+        [],
+    )?;
+    conn.execute("DELETE FROM chat_envelopes WHERE chat_id = 'General'", [])?;
+    conn.execute("DELETE FROM chats WHERE id = 'General'", [])?;
+    conn.execute("DELETE FROM peers WHERE id = 'General'", [])?;
+    Ok(())
+}
+
+fn merge_chat_connection_stats(
+    tx: &rusqlite::Transaction<'_>,
+    from_chat_id: &str,
+    to_chat_id: &str,
+) -> anyhow::Result<()> {
+    let from_stats = get_chat_connection_stats(tx, from_chat_id)?;
+    let to_stats = get_chat_connection_stats(tx, to_chat_id)?;
+
+    let first_connected_at = match (from_stats.first_connected_at, to_stats.first_connected_at) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (Some(a), None) => Some(a),
+        (None, Some(b)) => Some(b),
+        (None, None) => None,
+    };
+    let last_connected_at = match (from_stats.last_connected_at, to_stats.last_connected_at) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (Some(a), None) => Some(a),
+        (None, Some(b)) => Some(b),
+        (None, None) => None,
+    };
+    let reconnect_count = from_stats
+        .reconnect_count
+        .saturating_add(to_stats.reconnect_count);
+
+    tx.execute(
+        "INSERT INTO chat_connection_stats (chat_id, first_connected_at, last_connected_at, reconnect_count)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(chat_id) DO UPDATE SET
+             first_connected_at = excluded.first_connected_at,
+             last_connected_at = excluded.last_connected_at,
+             reconnect_count = excluded.reconnect_count",
+        rusqlite::params![
+            to_chat_id,
+            first_connected_at,
+            last_connected_at,
+            reconnect_count
+        ],
+    )?;
+
+    if from_chat_id != to_chat_id {
+        tx.execute(
+            "DELETE FROM chat_connection_stats WHERE chat_id = ?1",
+            [from_chat_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn migrate_chat_id_references(
+    tx: &rusqlite::Transaction<'_>,
+    old_chat_id: &str,
+    new_chat_id: &str,
+) -> anyhow::Result<()> {
+    if old_chat_id == new_chat_id {
+        return Ok(());
+    }
+
+    let old_chat_row = tx
+        .query_row(
+            "SELECT name, is_group, encryption_key FROM chats WHERE id = ?1",
+            [old_chat_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let Some((old_name, old_is_group, old_encryption_key)) = old_chat_row else {
+        return Ok(());
+    };
+
+    let new_chat_exists = chat_exists(tx, new_chat_id);
+    if !new_chat_exists {
+        tx.execute(
+            "INSERT INTO chats (id, name, is_group, encryption_key) VALUES (?1, ?2, ?3, ?4)",
+            (
+                new_chat_id,
+                old_name,
+                old_is_group,
+                old_encryption_key.clone(),
+            ),
+        )?;
+    }
+
+    tx.execute(
+        "UPDATE messages SET chat_id = ?1 WHERE chat_id = ?2",
+        (new_chat_id, old_chat_id),
+    )?;
+
+    tx.execute(
+        "INSERT OR IGNORE INTO chat_peers (chat_id, peer_id, role, joined_at)
+         SELECT ?1, peer_id, role, joined_at
+         FROM chat_peers
+         WHERE chat_id = ?2",
+        (new_chat_id, old_chat_id),
+    )?;
+    tx.execute("DELETE FROM chat_peers WHERE chat_id = ?1", [old_chat_id])?;
+
+    tx.execute(
+        "INSERT OR IGNORE INTO chat_envelopes (chat_id, envelope_id)
+         SELECT ?1, envelope_id FROM chat_envelopes WHERE chat_id = ?2",
+        (new_chat_id, old_chat_id),
+    )?;
+    tx.execute(
+        "DELETE FROM chat_envelopes WHERE chat_id = ?1",
+        [old_chat_id],
+    )?;
+
+    merge_chat_connection_stats(tx, old_chat_id, new_chat_id)?;
+    tx.execute("DELETE FROM chats WHERE id = ?1", [old_chat_id])?;
+    Ok(())
+}
+
+fn migrate_peer_id_reference(
+    tx: &rusqlite::Transaction<'_>,
+    old_peer_id: &str,
+    new_peer_id: &str,
+) -> anyhow::Result<()> {
+    if old_peer_id == new_peer_id {
+        return Ok(());
+    }
+
+    let old_peer = tx
+        .query_row(
+            "SELECT alias, last_seen, public_key, method FROM peers WHERE id = ?1",
+            [old_peer_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((alias, last_seen, public_key, method)) = old_peer else {
+        return Ok(());
+    };
+
+    if !is_peer(tx, new_peer_id) {
+        tx.execute(
+            "INSERT INTO peers (id, alias, last_seen, public_key, method) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (new_peer_id, alias, last_seen, public_key, method),
+        )?;
+    }
+
+    tx.execute(
+        "UPDATE messages SET peer_id = ?1 WHERE peer_id = ?2",
+        (new_peer_id, old_peer_id),
+    )?;
+
+    tx.execute(
+        "INSERT OR IGNORE INTO chat_peers (chat_id, peer_id, role, joined_at)
+         SELECT chat_id, ?1, role, joined_at
+         FROM chat_peers
+         WHERE peer_id = ?2",
+        (new_peer_id, old_peer_id),
+    )?;
+    tx.execute("DELETE FROM chat_peers WHERE peer_id = ?1", [old_peer_id])?;
+    tx.execute("DELETE FROM peers WHERE id = ?1", [old_peer_id])?;
+    Ok(())
+}
+
+fn migrate_legacy_github_chat_id_inner(
+    tx: &rusqlite::Transaction<'_>,
+    github_username: &str,
+    peer_id: &str,
+) -> anyhow::Result<()> {
+    let old_chat_id = format!("gh:{}", github_username);
+    let new_chat_id = crate::chat_identity::build_github_chat_id(github_username, peer_id);
+
+    migrate_chat_id_references(tx, &old_chat_id, &new_chat_id)?;
+    migrate_peer_id_reference(tx, &old_chat_id, &new_chat_id)?;
+
+    Ok(())
+}
+
+pub fn migrate_legacy_github_chat_ids(
+    conn: &mut Connection,
+    github_peer_mapping: &std::collections::HashMap<String, String>,
+) -> anyhow::Result<()> {
+    if github_peer_mapping.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for (github_username, peer_id) in github_peer_mapping {
+        migrate_legacy_github_chat_id_inner(&tx, github_username, peer_id)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn find_existing_local_chat_id_for_peer(
+    conn: &Connection,
+    peer_id: &str,
+) -> anyhow::Result<Option<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT id
+         FROM chats
+         WHERE is_group = 0
+           AND id LIKE ?1
+         ORDER BY id ASC
+         LIMIT 1",
+    )?;
+    stmt.query_row([format!("lh:%-{}", peer_id)], |row| row.get(0))
+        .optional()
+        .map_err(Into::into)
+}
+
+pub fn find_existing_github_chat_id_for_peer(
+    conn: &Connection,
+    peer_id: &str,
+) -> anyhow::Result<Option<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT id
+         FROM chats
+         WHERE is_group = 0
+           AND id LIKE ?1
+         ORDER BY id ASC
+         LIMIT 1",
+    )?;
+    stmt.query_row([format!("gh:%-{}", peer_id)], |row| row.get(0))
+        .optional()
+        .map_err(Into::into)
+}
+
+pub fn find_existing_direct_chat_id_for_peer(
+    conn: &Connection,
+    peer_id: &str,
+) -> anyhow::Result<Option<String>> {
+    if let Some(gh) = find_existing_github_chat_id_for_peer(conn, peer_id)? {
+        return Ok(Some(gh));
+    }
+    if let Some(lh) = find_existing_local_chat_id_for_peer(conn, peer_id)? {
+        return Ok(Some(lh));
+    }
+    if chat_exists(conn, peer_id) {
+        return Ok(Some(peer_id.to_string()));
+    }
+    Ok(None)
+}
+
+// --- Peer Functions ---
+
+/// Add a new peer to the database (used after handshake)
+pub fn add_peer(
+    conn: &Connection,
+    peer_id: &str,
+    alias: Option<&str>,
+    public_key: Option<&[u8]>,
+    method: &str, // "local", "gist", "manual"
+) -> anyhow::Result<()> {
+    let alias = alias.unwrap_or(peer_id);
+    let public_key = public_key.unwrap_or(&[0u8; 32]);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    conn.execute(
+        "INSERT INTO peers (id, alias, last_seen, public_key, method)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+             last_seen = ?3,
+             alias = COALESCE(?2, alias)",
+        (peer_id, alias, now, public_key, method),
+    )?;
+    Ok(())
+}
+
+/// Get all peers from database
+pub fn get_all_peers(conn: &Connection) -> anyhow::Result<Vec<Peer>> {
+    // Put "Me" first (method='self'), then sort others by last_seen DESC
+    let mut stmt = conn.prepare(
+        "SELECT id, alias, last_seen, public_key, method, avatar_hash FROM peers 
+         ORDER BY CASE WHEN id = 'Me' THEN 0 ELSE 1 END, last_seen DESC",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(Peer {
+            id: row.get(0)?,
+            alias: row.get(1)?,
+            last_seen: row.get(2)?,
+            public_key: row.get(3)?,
+            method: row.get(4)?,
+            avatar_hash: row.get(5)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Check if a peer_id exists in the peers table
+pub fn is_peer(conn: &Connection, peer_id: &str) -> bool {
+    conn.query_row("SELECT 1 FROM peers WHERE id = ?1", [peer_id], |_| Ok(()))
+        .is_ok()
+}
+
+/// Check if a chat exists for a given chat_id
+pub fn chat_exists(conn: &Connection, chat_id: &str) -> bool {
+    conn.query_row("SELECT 1 FROM chats WHERE id = ?1", [chat_id], |_| Ok(()))
+        .is_ok()
+}
+
+/// Create a new chat
+pub fn create_chat(
+    conn: &Connection,
+    chat_id: &str,
+    name: &str,
+    is_group: bool,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO chats (id, name, is_group, encryption_key) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO NOTHING",
+        (chat_id, name, if is_group { 1 } else { 0 }, vec![0u8; 32]),
+    )?;
+    Ok(())
+}
+
+pub fn upsert_chat(
+    conn: &Connection,
+    chat_id: &str,
+    name: &str,
+    is_group: bool,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO chats (id, name, is_group, encryption_key) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name,
+             is_group = excluded.is_group",
+        (chat_id, name, if is_group { 1 } else { 0 }, vec![0u8; 32]),
+    )?;
+    Ok(())
+}
+
+pub fn add_chat_member(
+    conn: &Connection,
+    chat_id: &str,
+    peer_id: &str,
+    role: &str,
+) -> anyhow::Result<()> {
+    let joined_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    conn.execute(
+        "INSERT OR IGNORE INTO chat_peers (chat_id, peer_id, role, joined_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        (chat_id, peer_id, role, joined_at),
+    )?;
+    Ok(())
+}
+
+pub fn remove_chat_member(conn: &Connection, chat_id: &str, peer_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "DELETE FROM chat_peers WHERE chat_id = ?1 AND peer_id = ?2",
+        (chat_id, peer_id),
+    )?;
+    Ok(())
+}
+
+pub fn delete_group_chat(conn: &Connection, chat_id: &str) -> anyhow::Result<()> {
+    conn.execute("DELETE FROM messages WHERE chat_id = ?1", [chat_id])?;
+    conn.execute("DELETE FROM chat_envelopes WHERE chat_id = ?1", [chat_id])?;
+    conn.execute("DELETE FROM chat_peers WHERE chat_id = ?1", [chat_id])?;
+    conn.execute("DELETE FROM group_keys WHERE group_id = ?1", [chat_id])?;
+    conn.execute(
+        "DELETE FROM chats WHERE id = ?1 AND is_group = 1",
+        [chat_id],
+    )?;
+    Ok(())
+}
+
+/// Change a member's role; returns false if they aren't a member.
+pub fn set_chat_member_role(
+    conn: &Connection,
+    chat_id: &str,
+    peer_id: &str,
+    role: &str,
+) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE chat_peers SET role = ?3 WHERE chat_id = ?1 AND peer_id = ?2",
+        (chat_id, peer_id, role),
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn set_chat_avatar(
+    conn: &Connection,
+    chat_id: &str,
+    avatar_hash: Option<&str>,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "UPDATE chats SET avatar_hash = ?2 WHERE id = ?1",
+        (chat_id, avatar_hash),
+    )?;
+    Ok(())
+}
+
+pub fn get_chat_avatar(conn: &Connection, chat_id: &str) -> anyhow::Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT avatar_hash FROM chats WHERE id = ?1",
+            [chat_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+/// `peer_id`'s role in `chat_id`, if they are a member.
+pub fn chat_member_role(
+    conn: &Connection,
+    chat_id: &str,
+    peer_id: &str,
+) -> anyhow::Result<Option<String>> {
+    conn.query_row(
+        "SELECT role FROM chat_peers WHERE chat_id = ?1 AND peer_id = ?2",
+        (chat_id, peer_id),
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+/// Store a key `admin_id` made for a group; returns false if the epoch was
+/// known already.
+pub fn store_group_key(
+    conn: &Connection,
+    group_id: &str,
+    epoch: i64,
+    key: &[u8; 32],
+    admin_id: &str,
+) -> anyhow::Result<bool> {
+    let created_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO group_keys (group_id, epoch, key, admin_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        (group_id, epoch, key.as_slice(), admin_id, created_at),
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Who made the first key we stored for `group_id`; later keys are only
+/// taken from them.
+pub fn group_key_admin(conn: &Connection, group_id: &str) -> anyhow::Result<Option<String>> {
+    conn.query_row(
+        "SELECT admin_id FROM group_keys WHERE group_id = ?1 ORDER BY epoch LIMIT 1",
+        [group_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+/// Whether a key update for `group_id` from `sender_id` may be stored. Once
+/// a key is known only its admin can add epochs; the first one must come
+/// from a member we hold as an admin.
+pub fn accepts_group_key_from(
+    conn: &Connection,
+    group_id: &str,
+    sender_id: &str,
+) -> anyhow::Result<bool> {
+    match group_key_admin(conn, group_id)? {
+        Some(admin) => Ok(admin == sender_id),
+        None => Ok(chat_member_role(conn, group_id, sender_id)?.as_deref() == Some("admin")),
+    }
+}
+
+fn group_key_row(row: &rusqlite::Row) -> rusqlite::Result<(i64, Vec<u8>)> {
+    Ok((row.get(0)?, row.get(1)?))
+}
+
+fn to_group_key((epoch, key): (i64, Vec<u8>)) -> anyhow::Result<(i64, [u8; 32])> {
+    let key = key
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Stored group key must be 32 bytes"))?;
+    Ok((epoch, key))
+}
+
+/// The newest key of `group_id` and its epoch.
+pub fn latest_group_key(
+    conn: &Connection,
+    group_id: &str,
+) -> anyhow::Result<Option<(i64, [u8; 32])>> {
+    conn.query_row(
+        "SELECT epoch, key FROM group_keys WHERE group_id = ?1 ORDER BY epoch DESC LIMIT 1",
+        [group_id],
+        group_key_row,
+    )
+    .optional()?
+    .map(to_group_key)
+    .transpose()
+}
+
+pub fn group_key(
+    conn: &Connection,
+    group_id: &str,
+    epoch: i64,
+) -> anyhow::Result<Option<[u8; 32]>> {
+    conn.query_row(
+        "SELECT epoch, key FROM group_keys WHERE group_id = ?1 AND epoch = ?2",
+        (group_id, epoch),
+        group_key_row,
+    )
+    .optional()?
+    .map(|row| to_group_key(row).map(|(_, key)| key))
+    .transpose()
+}
+
+pub fn get_chat_members(conn: &Connection, chat_id: &str) -> anyhow::Result<Vec<ChatMember>> {
+    let mut stmt = conn.prepare(
+        "SELECT peer_id, role, joined_at FROM chat_peers WHERE chat_id = ?1
+         ORDER BY joined_at, peer_id",
+    )?;
+    let rows = stmt
+        .query_map([chat_id], |row| {
+            Ok(ChatMember {
+                peer_id: row.get(0)?,
+                role: row.get(1)?,
+                joined_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// `(peer_id, alias)` of the members of `chat_id`, not counting us.
+pub fn get_chat_member_aliases(
+    conn: &Connection,
+    chat_id: &str,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT cp.peer_id, p.alias FROM chat_peers cp
+         JOIN peers p ON p.id = cp.peer_id
+         WHERE cp.chat_id = ?1 AND cp.peer_id != 'Me'",
+    )?;
+    let rows = stmt
+        .query_map([chat_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Rows of the `chats` table only, unlike [`get_chat_list`].
+pub fn list_chats(conn: &Connection) -> anyhow::Result<Vec<ChatListItem>> {
+    let mut stmt = conn.prepare("SELECT id, name, is_group FROM chats ORDER BY name, id")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ChatListItem {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                is_group: row.get::<_, i64>(2)? != 0,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+pub fn get_joined_group_chat_ids(
+    conn: &Connection,
+    my_peer_id: &str,
+) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.id
+         FROM chats c
+         INNER JOIN chat_peers cp ON cp.chat_id = c.id
+         WHERE c.is_group = 1 AND cp.peer_id = ?1",
+    )?;
+    let rows = stmt.query_map([my_peer_id], |row| row.get::<_, String>(0))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn get_chat_list(conn: &Connection) -> anyhow::Result<Vec<ChatListItem>> {
+    let mut items = Vec::new();
+    let mut seen_ids: HashSet<String> = HashSet::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT id, name, is_group
+         FROM chats",
+    )?;
+    let chat_rows = stmt.query_map([], |row| {
+        Ok(ChatListItem {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            is_group: row.get::<_, i64>(2)? != 0,
+        })
+    })?;
+
+    for row in chat_rows {
+        let item = row?;
+        seen_ids.insert(item.id.clone());
+        items.push(item);
+    }
+
+    // Include known peers without chat rows as direct chats.
+    let mut peer_stmt = conn.prepare(
+        "SELECT id, alias
+         FROM peers
+         WHERE id != 'Me'",
+    )?;
+    let peer_rows = peer_stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in peer_rows {
+        let (peer_id, alias) = row?;
+        let has_scoped_direct_chat = seen_ids.iter().any(|id| {
+            (id.starts_with("gh:") || id.starts_with("lh:"))
+                && id.ends_with(&format!("-{}", peer_id))
+        });
+        if !seen_ids.contains(&peer_id) && !has_scoped_direct_chat {
+            items.push(ChatListItem {
+                id: peer_id.clone(),
+                name: alias,
+                is_group: false,
+            });
+            seen_ids.insert(peer_id);
+        }
+    }
+
+    // Ensure self chat exists in list.
+    if !seen_ids.contains("self") {
+        items.push(ChatListItem {
+            id: "self".to_string(),
+            name: "Note to Self".to_string(),
+            is_group: false,
+        });
+    }
+
+    Ok(items)
+}
+
+pub fn get_chat_name(conn: &Connection, chat_id: &str) -> anyhow::Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM chats WHERE id = ?1 LIMIT 1")?;
+    let mut rows = stmt.query([chat_id])?;
+    if let Some(row) = rows.next()? {
+        return Ok(Some(row.get(0)?));
+    }
+    Ok(None)
+}
+
+pub fn get_peer_alias(conn: &Connection, peer_id: &str) -> anyhow::Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT alias FROM peers WHERE id = ?1 LIMIT 1")?;
+    let mut rows = stmt.query([peer_id])?;
+    if let Some(row) = rows.next()? {
+        return Ok(Some(row.get(0)?));
+    }
+    Ok(None)
+}
+
+/// Returns true when this was a reconnect rather than the first connection.
+pub fn record_chat_connection_established(
+    conn: &Connection,
+    chat_id: &str,
+    connected_at: i64,
+) -> anyhow::Result<bool> {
+    let existing = get_chat_connection_stats(conn, chat_id)?;
+    match existing.first_connected_at {
+        None => {
+            conn.execute(
+                "INSERT INTO chat_connection_stats (chat_id, first_connected_at, last_connected_at, reconnect_count)
+                 VALUES (?1, ?2, ?3, 0)
+                 ON CONFLICT(chat_id) DO UPDATE SET
+                    first_connected_at = COALESCE(chat_connection_stats.first_connected_at, excluded.first_connected_at),
+                    last_connected_at = excluded.last_connected_at,
+                    reconnect_count = chat_connection_stats.reconnect_count",
+                (chat_id, connected_at, connected_at),
+            )?;
+        }
+        Some(_) => {
+            conn.execute(
+                "UPDATE chat_connection_stats
+                 SET last_connected_at = ?2, reconnect_count = reconnect_count + 1
+                 WHERE chat_id = ?1",
+                (chat_id, connected_at),
+            )?;
+        }
+    }
+
+    Ok(existing.first_connected_at.is_some())
+}
+
+pub fn get_chat_connection_stats(
+    conn: &Connection,
+    chat_id: &str,
+) -> anyhow::Result<ChatConnectionStats> {
+    let mut stmt = conn.prepare(
+        "SELECT first_connected_at, last_connected_at, reconnect_count
+         FROM chat_connection_stats
+         WHERE chat_id = ?1",
+    )?;
+    let mut rows = stmt.query([chat_id])?;
+    if let Some(row) = rows.next()? {
+        return Ok(ChatConnectionStats {
+            first_connected_at: row.get(0)?,
+            last_connected_at: row.get(1)?,
+            reconnect_count: row.get::<_, i64>(2)?,
+        });
+    }
+
+    Ok(ChatConnectionStats::default())
+}
+
+/// Delete a peer and their related chat/messages
+pub fn delete_peer(conn: &Connection, peer_id: &str) -> anyhow::Result<()> {
+    conn.execute("DELETE FROM chat_peers WHERE peer_id = ?1", [peer_id])?;
+    // 1. Delete Messages
+    conn.execute(
+        "DELETE FROM messages WHERE peer_id = ?1 OR chat_id = ?1",
+        [peer_id],
+    )?;
+    // 2. Delete Chat (if 1:1)
+    conn.execute("DELETE FROM chats WHERE id = ?1", [peer_id])?;
+    // 3. Delete Peer
+    conn.execute("DELETE FROM peers WHERE id = ?1", [peer_id])?;
+    Ok(())
+}
+
+// --- 3. Database Operations ---
+
+/// A message keeps the logical clock it arrived with; new ones (0) get one
+/// past the highest in their chat, which also moves the chat's clock past
+/// every message received so far. A peer can move the clock ahead by at most
+/// [`MAX_LOGICAL_JUMP`], so one bogus value can't exhaust it.
+const INSERT_MESSAGE_SQL: &str =
+    "INSERT INTO messages (id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, status, content_metadata, sender_alias, logical)
+     SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+            CASE WHEN ?11 > 0 THEN MIN(?11, next + ?12) ELSE next END
+     FROM (SELECT COALESCE(MAX(logical), 0) + 1 AS next FROM messages WHERE chat_id = ?2)";
+
+const MAX_LOGICAL_JUMP: i64 = 1_000_000;
+
+fn message_params(msg: &Message) -> impl rusqlite::Params + '_ {
+    let (content_type, text_content, file_hash) = msg.content.clone().into_parts();
+    (
+        &msg.id,
+        &msg.chat_id,
+        &msg.peer_id,
+        &msg.timestamp,
+        content_type,
+        text_content,
+        file_hash,
+        &msg.status,
+        &msg.content_metadata,
+        &msg.sender_alias,
+        &msg.logical,
+        &MAX_LOGICAL_JUMP,
+    )
+}
+
+/// Map a row selected as `id, chat_id, peer_id, timestamp, content_type,
+/// text_content, file_hash, status, content_metadata, sender_alias, logical`.
+fn message_from_row(row: &rusqlite::Row) -> rusqlite::Result<Message> {
+    let content_type: String = row.get(4)?;
+    let content_metadata: Option<String> = row.get(8)?;
+    Ok(Message {
+        id: row.get(0)?,
+        chat_id: row.get(1)?,
+        peer_id: row.get(2)?,
+        timestamp: row.get(3)?,
+        content: MessageContent::from_columns(
+            &content_type,
+            row.get(5)?,
+            row.get(6)?,
+            content_metadata.as_deref(),
+        ),
+        status: row.get(7)?,
+        content_metadata,
+        sender_alias: row.get(9)?,
+        logical: row.get(10)?,
+    })
+}
+
+pub fn insert_message(conn: &Connection, msg: &Message) -> anyhow::Result<()> {
+    conn.execute(INSERT_MESSAGE_SQL, message_params(msg))?;
+    Ok(())
+}
+
+/// Insert `msgs` in one transaction with a single prepared statement. All or
+/// nothing: on error no message is inserted.
+pub fn insert_messages_batch(conn: &Connection, msgs: &[Message]) -> anyhow::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(INSERT_MESSAGE_SQL)?;
+        for msg in msgs {
+            stmt.execute(message_params(msg))
+                .with_context(|| format!("Failed to insert message {}", msg.id))?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Update the cached content_metadata for a message (computed attributes like width, height, duration)
+pub fn update_content_metadata(
+    conn: &Connection,
+    msg_id: &str,
+    metadata_json: &str,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "UPDATE messages SET content_metadata = ?1 WHERE id = ?2",
+        [metadata_json, msg_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_messages(conn: &Connection, chat_id: &str) -> anyhow::Result<Vec<Message>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, COALESCE(status, 'delivered') as status, content_metadata, sender_alias, logical
+         FROM messages 
+         WHERE chat_id = ?1 
+         ORDER BY logical ASC, timestamp ASC, id ASC",
+    )?;
+
+    let msg_iter = stmt.query_map([chat_id], message_from_row)?;
+
+    let mut messages = Vec::new();
+    for msg in msg_iter {
+        messages.push(msg?);
+    }
+    Ok(messages)
+}
+
+/// The logical clock a stored message was given.
+pub fn message_logical(conn: &Connection, msg_id: &str) -> anyhow::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT logical FROM messages WHERE id = ?1",
+        [msg_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+pub fn get_message(conn: &Connection, msg_id: &str) -> anyhow::Result<Option<Message>> {
+    conn.query_row(
+        "SELECT id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, COALESCE(status, 'delivered') as status, content_metadata, sender_alias, logical
+         FROM messages
+         WHERE id = ?1",
+        [msg_id],
+        message_from_row,
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+/// Up to `limit` messages of a chat sent at or after `since`, in logical
+/// order, starting after the `(logical, id)` cursor of the previous page.
+pub fn get_messages_page(
+    conn: &Connection,
+    chat_id: &str,
+    since: i64,
+    after: Option<(i64, &str)>,
+    limit: usize,
+) -> anyhow::Result<Vec<Message>> {
+    let (after_logical, after_id) = after.unzip();
+    let mut stmt = conn.prepare(
+        "SELECT id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, COALESCE(status, 'delivered') as status, content_metadata, sender_alias, logical
+         FROM messages
+         WHERE chat_id = ?1 AND timestamp >= ?2
+           AND (?3 IS NULL OR logical > ?3 OR (logical = ?3 AND id > ?4))
+         ORDER BY logical ASC, id ASC
+         LIMIT ?5",
+    )?;
+    let rows = stmt.query_map(
+        (chat_id, since, after_logical, after_id, limit as i64),
+        message_from_row,
+    )?;
+    rows.collect::<Result<_, _>>().map_err(Into::into)
+}
+
+/// Visit a chat's messages in timestamp order without collecting them into memory
+pub fn for_each_message(
+    conn: &Connection,
+    chat_id: &str,
+    mut visit: impl FnMut(Message) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, COALESCE(status, 'delivered') as status, content_metadata, sender_alias, logical
+         FROM messages
+         WHERE chat_id = ?1
+         ORDER BY logical ASC, timestamp ASC, id ASC",
+    )?;
+    let mut rows = stmt.query([chat_id])?;
+    while let Some(row) = rows.next()? {
+        visit(message_from_row(row)?)?;
+    }
+    Ok(())
+}
+
+/// Get the latest sender_alias for each peer from their messages
+pub fn get_peer_aliases(
+    conn: &Connection,
+) -> anyhow::Result<std::collections::HashMap<String, String>> {
+    let mut stmt = conn.prepare(
+        "SELECT chat_id, sender_alias
+         FROM messages
+         WHERE sender_alias IS NOT NULL AND sender_alias != ''
+           AND peer_id != 'Me'
+         GROUP BY chat_id
+         HAVING MAX(timestamp)",
+    )?;
+
+    let mut aliases = std::collections::HashMap::new();
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    for row in rows {
+        if let Ok((chat_id, alias)) = row {
+            aliases.insert(chat_id, alias);
+        }
+    }
+    Ok(aliases)
+}
+
+/// Whether a message may go from `from` to `to`. Outgoing messages move
+/// pending → sent → delivered → read and never back, so a delivery receipt
+/// arriving after the read receipt changes nothing. A send can fail while
+/// pending or sent; a failed message goes back to pending when retried, or
+/// straight on when the peer acknowledges it after all.
+pub fn status_transition_allowed(from: MessageStatus, to: MessageStatus) -> bool {
+    use MessageStatus::{Delivered, Failed, Pending, Read, Sent};
+    matches!(
+        (from, to),
+        (Pending, Sent | Delivered | Read | Failed)
+            | (Sent, Delivered | Read | Failed)
+            | (Delivered, Read)
+            | (Failed, Pending | Sent | Delivered | Read)
+    )
+}
+
+/// Move a message to `status` if [`status_transition_allowed`]. Returns
+/// whether it changed; unknown messages and rejected transitions don't.
+pub fn update_message_status(
+    conn: &Connection,
+    msg_id: &str,
+    status: &str,
+) -> anyhow::Result<bool> {
+    let to = MessageStatus::from_str(status);
+    if to.as_str() != status {
+        anyhow::bail!("Unknown message status {:?}", status);
+    }
+    let Some(from) = conn
+        .query_row(
+            "SELECT COALESCE(status, 'delivered') FROM messages WHERE id = ?1",
+            [msg_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+    else {
+        return Ok(false);
+    };
+    if !status_transition_allowed(MessageStatus::from_str(&from), to) {
+        return Ok(false);
+    }
+    let updated = conn.execute(
+        "UPDATE messages SET status = ?1 WHERE id = ?2 AND COALESCE(status, 'delivered') = ?3",
+        [status, msg_id, &from],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Mark all messages in a chat as read for a given sender
+pub fn mark_messages_read(
+    conn: &Connection,
+    chat_id: &str,
+    sender_id: &str,
+) -> anyhow::Result<Vec<String>> {
+    // Get IDs of messages that will be marked as read
+    let mut stmt = conn.prepare(
+        "SELECT id FROM messages WHERE chat_id = ?1 AND peer_id = ?2 AND status != 'read'",
+    )?;
+    let ids: Vec<String> = stmt
+        .query_map([chat_id, sender_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    // Update them
+    conn.execute(
+        "UPDATE messages SET status = 'read' WHERE chat_id = ?1 AND peer_id = ?2 AND status != 'read'",
+        [chat_id, sender_id],
+    )?;
+    Ok(ids)
+}
+
+pub fn mark_group_messages_read(conn: &Connection, chat_id: &str) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM messages WHERE chat_id = ?1 AND peer_id != 'Me' AND status != 'read'",
+    )?;
+    let ids: Vec<String> = stmt
+        .query_map([chat_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    conn.execute(
+        "UPDATE messages SET status = 'read' WHERE chat_id = ?1 AND peer_id != 'Me' AND status != 'read'",
+        [chat_id],
+    )?;
+
+    Ok(ids)
+}
+
+/// Get unread message count for each chat, skipping chats that are muted or
+/// have notifications turned off.
+pub fn get_unread_counts(
+    conn: &Connection,
+    my_peer_id: &str,
+) -> anyhow::Result<std::collections::HashMap<String, i64>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let mut stmt = conn.prepare(
+        "SELECT m.chat_id, COUNT(*) as count
+         FROM messages m
+         LEFT JOIN chat_settings s ON s.chat_id = m.chat_id
+         WHERE m.peer_id != ?1 AND m.status != 'read'
+           AND COALESCE(s.notification_level, 'all') != 'off'
+           AND (s.mute_until IS NULL OR s.mute_until <= ?2)
+         GROUP BY m.chat_id",
+    )?;
+
+    let mut counts = std::collections::HashMap::new();
+    let rows = stmt.query_map((my_peer_id, now), |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    for row in rows {
+        let (chat_id, count) = row?;
+        counts.insert(chat_id, count);
+    }
+    Ok(counts)
+}
+
+/// Get latest message timestamp for each chat (for sorting by recency)
+pub fn get_chat_latest_times(
+    conn: &Connection,
+) -> anyhow::Result<std::collections::HashMap<String, i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT chat_id, MAX(timestamp) as latest_time
+         FROM messages
+         GROUP BY chat_id",
+    )?;
+
+    let mut result = std::collections::HashMap::new();
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    for row in rows {
+        let (chat_id, latest_time) = row?;
+        result.insert(chat_id, latest_time);
+    }
+
+    Ok(result)
+}
+
+pub fn get_chat_message_stats(
+    conn: &Connection,
+    chat_id: &str,
+) -> anyhow::Result<ChatMessageStats> {
+    let mut stmt = conn.prepare(
+        "SELECT
+            SUM(CASE WHEN peer_id = 'Me' THEN 1 ELSE 0 END) AS sent_total,
+            SUM(CASE WHEN peer_id != 'Me' THEN 1 ELSE 0 END) AS received_total,
+            SUM(CASE WHEN peer_id = 'Me' AND content_type = 'text' THEN 1 ELSE 0 END) AS sent_text,
+            SUM(CASE WHEN peer_id = 'Me' AND content_type = 'sticker' THEN 1 ELSE 0 END) AS sent_sticker,
+            SUM(CASE WHEN peer_id = 'Me' AND (content_type = 'image' OR content_type = 'photo') THEN 1 ELSE 0 END) AS sent_image,
+            SUM(CASE WHEN peer_id = 'Me' AND content_type = 'video' THEN 1 ELSE 0 END) AS sent_video,
+            SUM(CASE WHEN peer_id = 'Me' AND content_type = 'audio' THEN 1 ELSE 0 END) AS sent_audio,
+            SUM(CASE WHEN peer_id = 'Me' AND content_type = 'document' THEN 1 ELSE 0 END) AS sent_document,
+            SUM(CASE WHEN peer_id != 'Me' AND content_type = 'text' THEN 1 ELSE 0 END) AS recv_text,
+            SUM(CASE WHEN peer_id != 'Me' AND content_type = 'sticker' THEN 1 ELSE 0 END) AS recv_sticker,
+            SUM(CASE WHEN peer_id != 'Me' AND (content_type = 'image' OR content_type = 'photo') THEN 1 ELSE 0 END) AS recv_image,
+            SUM(CASE WHEN peer_id != 'Me' AND content_type = 'video' THEN 1 ELSE 0 END) AS recv_video,
+            SUM(CASE WHEN peer_id != 'Me' AND content_type = 'audio' THEN 1 ELSE 0 END) AS recv_audio,
+            SUM(CASE WHEN peer_id != 'Me' AND content_type = 'document' THEN 1 ELSE 0 END) AS recv_document
+         FROM messages
+         WHERE chat_id = ?1",
+    )?;
+
+    let stats = stmt.query_row([chat_id], |row| {
+        let sent_total = row.get::<_, Option<i64>>(0)?.unwrap_or(0);
+        let received_total = row.get::<_, Option<i64>>(1)?.unwrap_or(0);
+        Ok(ChatMessageStats {
+            sent_total,
+            received_total,
+            sent: ChatContentBreakdown {
+                text: row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+                sticker: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                image: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+                video: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+                audio: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+                document: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+            },
+            received: ChatContentBreakdown {
+                text: row.get::<_, Option<i64>>(8)?.unwrap_or(0),
+                sticker: row.get::<_, Option<i64>>(9)?.unwrap_or(0),
+                image: row.get::<_, Option<i64>>(10)?.unwrap_or(0),
+                video: row.get::<_, Option<i64>>(11)?.unwrap_or(0),
+                audio: row.get::<_, Option<i64>>(12)?.unwrap_or(0),
+                document: row.get::<_, Option<i64>>(13)?.unwrap_or(0),
+            },
+        })
+    })?;
+
+    Ok(stats)
+}
+
+pub fn list_chat_files(
+    conn: &Connection,
+    chat_id: &str,
+    filter: &str,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<ChatFileRow>> {
+    let safe_limit = limit.clamp(1, 200);
+    let safe_offset = offset.max(0);
+    let filter_lower = filter.to_lowercase();
+
+    let mut stmt = conn.prepare(
+        "SELECT
+            m.id,
+            m.timestamp,
+            m.content_type,
+            m.file_hash,
+            COALESCE(f.file_name, m.text_content) AS file_name,
+            f.size_bytes,
+            f.mime_type,
+            m.peer_id
+         FROM messages m
+         LEFT JOIN files f ON f.file_hash = m.file_hash
+         WHERE m.chat_id = ?1
+           AND m.file_hash IS NOT NULL
+           AND (
+               ?2 = 'all'
+               OR (?2 = 'image' AND (m.content_type = 'image' OR m.content_type = 'photo'))
+               OR m.content_type = ?2
+           )
+         ORDER BY m.timestamp DESC
+         LIMIT ?3 OFFSET ?4",
+    )?;
+
+    let rows = stmt.query_map(
+        rusqlite::params![chat_id, filter_lower, safe_limit, safe_offset],
+        |row| {
+            Ok(ChatFileRow {
+                message_id: row.get(0)?,
+                timestamp: row.get(1)?,
+                content_type: row.get(2)?,
+                file_hash: row.get(3)?,
+                file_name: row.get(4)?,
+                size_bytes: row.get(5)?,
+                mime_type: row.get(6)?,
+                sender: row.get(7)?,
+            })
+        },
+    )?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+// --- Envelope Operations ---
+
+pub fn create_envelope(
+    conn: &Connection,
+    id: &str,
+    name: &str,
+    icon: Option<&str>,
+) -> anyhow::Result<()> {
+    create_nested_envelope(conn, id, name, icon, None)
+}
+
+/// Create an envelope inside `parent_id` (top level for `None`), after its
+/// existing siblings.
+pub fn create_nested_envelope(
+    conn: &Connection,
+    id: &str,
+    name: &str,
+    icon: Option<&str>,
+    parent_id: Option<&str>,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO envelopes (id, name, icon, parent_id, sort_order)
+         VALUES (?1, ?2, ?3, ?4,
+                 (SELECT COALESCE(MAX(sort_order), -1) + 1 FROM envelopes WHERE parent_id IS ?4))",
+        (id, name, icon, parent_id),
+    )?;
+    Ok(())
+}
+
+pub fn update_envelope(
+    conn: &Connection,
+    id: &str,
+    name: &str,
+    icon: Option<&str>,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "UPDATE envelopes SET name = ?1, icon = ?2 WHERE id = ?3",
+        (name, icon, id),
+    )?;
+    Ok(())
+}
+
+/// Delete an envelope. Envelopes nested in it move up to its parent.
+pub fn delete_envelope(conn: &Connection, id: &str) -> anyhow::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE envelopes SET parent_id = (SELECT parent_id FROM envelopes WHERE id = ?1)
+         WHERE parent_id = ?1",
+        (id,),
+    )?;
+    let count = tx.execute("DELETE FROM envelopes WHERE id = ?1", (id,))?;
+
+    if count == 0 {
+        return Err(anyhow::anyhow!(
+            "Envelope with id '{}' not found or not deleted",
+            id
+        ));
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn get_envelopes(conn: &Connection) -> anyhow::Result<Vec<Envelope>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, icon, parent_id, sort_order FROM envelopes
+         ORDER BY sort_order, name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Envelope {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            icon: row.get(2)?,
+            parent_id: row.get(3)?,
+            sort_order: row.get(4)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Put `ordered_ids` inside `parent_id` (top level for `None`) in this order.
+/// Fails without changing anything if that would nest an envelope in itself.
+pub fn reorder_envelopes(
+    conn: &Connection,
+    parent_id: Option<&str>,
+    ordered_ids: &[String],
+) -> anyhow::Result<()> {
+    if let Some(parent_id) = parent_id {
+        let mut ancestor = Some(parent_id.to_string());
+        while let Some(id) = ancestor {
+            if ordered_ids.contains(&id) {
+                anyhow::bail!("Envelope '{}' can't be nested inside itself", id);
+            }
+            ancestor = conn
+                .query_row(
+                    "SELECT parent_id FROM envelopes WHERE id = ?1",
+                    [&id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .flatten();
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for (position, id) in ordered_ids.iter().enumerate() {
+        let updated = tx.execute(
+            "UPDATE envelopes SET parent_id = ?1, sort_order = ?2 WHERE id = ?3",
+            (parent_id, position as i64, id),
+        )?;
+        if updated == 0 {
+            anyhow::bail!("Envelope '{}' not found", id);
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Unread messages (as counted by [`get_unread_counts`]), chats and latest
+/// activity per envelope, with nested envelopes counted into their ancestors.
+/// `chat_key` maps message chat ids to the ids chats are assigned under.
+pub fn get_envelope_summaries(
+    conn: &Connection,
+    my_peer_id: &str,
+    chat_key: impl Fn(&str) -> String,
+) -> anyhow::Result<Vec<EnvelopeSummary>> {
+    let envelopes = get_envelopes(conn)?;
+    let assignments = get_chat_assignments(conn)?;
+
+    let mut unread: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for (chat_id, count) in get_unread_counts(conn, my_peer_id)? {
+        *unread.entry(chat_key(&chat_id)).or_insert(0) += count;
+    }
+    let mut latest: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for (chat_id, ts) in get_chat_latest_times(conn)? {
+        let entry = latest.entry(chat_key(&chat_id)).or_insert(ts);
+        *entry = (*entry).max(ts);
+    }
+
+    let parents: std::collections::HashMap<&str, Option<&str>> = envelopes
+        .iter()
+        .map(|e| (e.id.as_str(), e.parent_id.as_deref()))
+        .collect();
+    let mut summaries: Vec<EnvelopeSummary> = envelopes
+        .iter()
+        .map(|e| EnvelopeSummary {
+            envelope_id: e.id.clone(),
+            chat_count: 0,
+            unread_count: 0,
+            latest_activity: None,
+        })
+        .collect();
+
+    // A chat tagged with several envelopes under the same ancestor counts once there.
+    let mut counted: HashSet<(&str, &str)> = HashSet::new();
+    for assignment in &assignments {
+        let chat_unread = unread.get(&assignment.chat_id).copied().unwrap_or(0);
+        let chat_latest = latest.get(&assignment.chat_id).copied();
+        // Walk up to the root; `seen` guards against a corrupted cycle.
+        let mut seen = HashSet::new();
+        let mut current = Some(assignment.envelope_id.as_str());
+        while let Some(id) = current.filter(|id| seen.insert(*id)) {
+            if counted.insert((id, assignment.chat_id.as_str())) {
+                if let Some(summary) = summaries.iter_mut().find(|s| s.envelope_id == id) {
+                    summary.chat_count += 1;
+                    summary.unread_count += chat_unread;
+                    summary.latest_activity = summary.latest_activity.max(chat_latest);
+                }
+            }
+            current = parents.get(id).copied().flatten();
+        }
+    }
+    Ok(summaries)
+}
+
+/// Move a chat into a single envelope, replacing its other tags; `None`
+/// removes all of them (moves it to the root).
+pub fn assign_chat_to_envelope(
+    conn: &Connection,
+    chat_id: &str,
+    envelope_id: Option<&str>,
+) -> anyhow::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM chat_envelopes WHERE chat_id = ?1", (chat_id,))?;
+    if let Some(env_id) = envelope_id {
+        tx.execute(
+            "INSERT INTO chat_envelopes (chat_id, envelope_id) VALUES (?1, ?2)",
+            (chat_id, env_id),
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Tag a chat with an envelope, keeping its other tags.
+pub fn add_chat_tag(conn: &Connection, chat_id: &str, envelope_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO chat_envelopes (chat_id, envelope_id) VALUES (?1, ?2)",
+        (chat_id, envelope_id),
+    )?;
+    Ok(())
+}
+
+pub fn remove_chat_tag(conn: &Connection, chat_id: &str, envelope_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "DELETE FROM chat_envelopes WHERE chat_id = ?1 AND envelope_id = ?2",
+        (chat_id, envelope_id),
+    )?;
+    Ok(())
+}
+
+/// Chats tagged with any (or, with `match_all`, every) envelope in
+/// `envelope_ids`. A tag on a nested envelope counts for its ancestors too.
+pub fn get_chat_ids_with_tags(
+    conn: &Connection,
+    envelope_ids: &[String],
+    match_all: bool,
+) -> anyhow::Result<HashSet<String>> {
+    let parents: std::collections::HashMap<String, Option<String>> = get_envelopes(conn)?
+        .into_iter()
+        .map(|e| (e.id, e.parent_id))
+        .collect();
+    let assignments = get_chat_assignments(conn)?;
+
+    let mut result: Option<HashSet<String>> = None;
+    for wanted in envelope_ids {
+        let tagged: HashSet<String> = assignments
+            .iter()
+            .filter(|a| {
+                let mut seen = HashSet::new();
+                let mut current = Some(a.envelope_id.as_str());
+                while let Some(id) = current.filter(|id| seen.insert(*id)) {
+                    if id == wanted {
+                        return true;
+                    }
+                    current = parents.get(id).and_then(|p| p.as_deref());
+                }
+                false
+            })
+            .map(|a| a.chat_id.clone())
+            .collect();
+        result = Some(match result {
+            None => tagged,
+            Some(acc) if match_all => acc.intersection(&tagged).cloned().collect(),
+            Some(acc) => acc.union(&tagged).cloned().collect(),
+        });
+    }
+    Ok(result.unwrap_or_default())
+}
+
+pub fn get_chat_assignments(conn: &Connection) -> anyhow::Result<Vec<ChatAssignment>> {
+    let mut stmt = conn.prepare("SELECT chat_id, envelope_id FROM chat_envelopes")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ChatAssignment {
+            chat_id: row.get(0)?,
+            envelope_id: row.get(1)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Delete message `msg_id` of chat `chat_id`; returns whether it existed.
+pub fn delete_chat_message(conn: &Connection, chat_id: &str, msg_id: &str) -> anyhow::Result<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM messages WHERE id = ?1 AND chat_id = ?2",
+        (msg_id, chat_id),
+    )?;
+    Ok(deleted > 0)
+}
+
+/// Whether any message or sticker still uses the stored file.
+pub fn is_file_referenced(conn: &Connection, file_hash: &str) -> anyhow::Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM messages WHERE file_hash = ?1)
+             OR EXISTS(SELECT 1 FROM stickers WHERE file_hash = ?1)",
+        [file_hash],
+        |row| row.get(0),
+    )?)
+}
+
+pub fn sticker_exists(conn: &Connection, file_hash: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM stickers WHERE file_hash = ?1",
+        [file_hash],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+pub fn upsert_sticker(
+    conn: &Connection,
+    file_hash: &str,
+    name: Option<&str>,
+    source: &str,
+) -> anyhow::Result<bool> {
+    let already_exists = sticker_exists(conn, file_hash);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    conn.execute(
+        "INSERT INTO stickers (file_hash, name, created_at, source)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(file_hash) DO UPDATE SET
+            name = COALESCE(excluded.name, stickers.name),
+            source = stickers.source",
+        (file_hash, name, now, source),
+    )?;
+
+    Ok(!already_exists)
+}
+
+pub fn list_stickers(conn: &Connection) -> anyhow::Result<Vec<Sticker>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.file_hash, s.name, s.created_at, COALESCE(f.size_bytes, 0) as size_bytes
+         FROM stickers s
+         LEFT JOIN files f ON f.file_hash = s.file_hash
+         ORDER BY s.created_at DESC",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(Sticker {
+            file_hash: row.get(0)?,
+            name: row.get(1)?,
+            created_at: row.get(2)?,
+            size_bytes: row.get(3)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+pub fn delete_sticker(conn: &Connection, file_hash: &str) -> anyhow::Result<()> {
+    let deleted = conn.execute("DELETE FROM stickers WHERE file_hash = ?1", [file_hash])?;
+    if deleted == 0 {
+        return Err(anyhow::anyhow!("Sticker not found: {}", file_hash));
+    }
+    Ok(())
+}
+
+// --- Bridge Operations ---
+
+pub fn upsert_chat_bridge(conn: &Connection, bridge: &ChatBridge) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO chat_bridges (chat_id, kind, endpoint, target, nick, credential, enabled, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, '', ?6, ?7)
+         ON CONFLICT(chat_id) DO UPDATE SET
+             kind = excluded.kind,
+             endpoint = excluded.endpoint,
+             target = excluded.target,
+             nick = excluded.nick,
+             credential = '',
+             enabled = excluded.enabled",
+        rusqlite::params![
+            bridge.chat_id,
+            bridge.kind,
+            bridge.endpoint,
+            bridge.target,
+            bridge.nick,
+            bridge.enabled,
+            bridge.created_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn chat_bridge_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ChatBridge> {
+    Ok(ChatBridge {
+        chat_id: row.get(0)?,
+        kind: row.get(1)?,
+        endpoint: row.get(2)?,
+        target: row.get(3)?,
+        nick: row.get(4)?,
+        credential: String::new(),
+        enabled: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+pub fn get_chat_bridge(conn: &Connection, chat_id: &str) -> anyhow::Result<Option<ChatBridge>> {
+    let bridge = conn
+        .query_row(
+            "SELECT chat_id, kind, endpoint, target, nick, enabled, created_at
+             FROM chat_bridges WHERE chat_id = ?1",
+            [chat_id],
+            chat_bridge_from_row,
+        )
+        .optional()?;
+    Ok(bridge)
+}
+
+pub fn list_enabled_chat_bridges(conn: &Connection) -> anyhow::Result<Vec<ChatBridge>> {
+    let mut stmt = conn.prepare(
+        "SELECT chat_id, kind, endpoint, target, nick, enabled, created_at
+         FROM chat_bridges WHERE enabled = 1",
+    )?;
+    let rows = stmt.query_map([], chat_bridge_from_row)?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Secrets older versions kept in `chat_bridges.credential`, by chat id.
+/// `bridge::move_legacy_credentials` moves them to the encrypted config.
+pub fn legacy_bridge_credentials(conn: &Connection) -> anyhow::Result<Vec<(String, String)>> {
+    let mut stmt =
+        conn.prepare("SELECT chat_id, credential FROM chat_bridges WHERE credential != ''")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub fn clear_bridge_credential(conn: &Connection, chat_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "UPDATE chat_bridges SET credential = '' WHERE chat_id = ?1",
+        [chat_id],
+    )?;
+    Ok(())
+}
+
+pub fn set_chat_bridge_enabled(
+    conn: &Connection,
+    chat_id: &str,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    let updated = conn.execute(
+        "UPDATE chat_bridges SET enabled = ?1 WHERE chat_id = ?2",
+        rusqlite::params![enabled, chat_id],
+    )?;
+    if updated == 0 {
+        return Err(anyhow::anyhow!(
+            "No bridge configured for chat: {}",
+            chat_id
+        ));
+    }
+    Ok(())
+}
+
+pub fn delete_chat_bridge(conn: &Connection, chat_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "DELETE FROM bridge_identities WHERE chat_id = ?1",
+        [chat_id],
+    )?;
+    conn.execute("DELETE FROM bridge_messages WHERE chat_id = ?1", [chat_id])?;
+    conn.execute("DELETE FROM bridge_nicks WHERE chat_id = ?1", [chat_id])?;
+    conn.execute("DELETE FROM chat_bridges WHERE chat_id = ?1", [chat_id])?;
+    Ok(())
+}
+
+pub fn upsert_bridge_identity(
+    conn: &Connection,
+    chat_id: &str,
+    remote_user: &str,
+    display_name: &str,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO bridge_identities (chat_id, remote_user, display_name)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(chat_id, remote_user) DO UPDATE SET
+             display_name = excluded.display_name",
+        (chat_id, remote_user, display_name),
+    )?;
+    Ok(())
+}
+
+pub fn get_bridge_identity(
+    conn: &Connection,
+    chat_id: &str,
+    remote_user: &str,
+) -> anyhow::Result<Option<String>> {
+    let name = conn
+        .query_row(
+            "SELECT display_name FROM bridge_identities WHERE chat_id = ?1 AND remote_user = ?2",
+            [chat_id, remote_user],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(name)
+}
+
+pub fn list_bridge_identities(
+    conn: &Connection,
+    chat_id: &str,
+) -> anyhow::Result<Vec<BridgeIdentity>> {
+    let mut stmt = conn.prepare(
+        "SELECT chat_id, remote_user, display_name
+         FROM bridge_identities WHERE chat_id = ?1
+         ORDER BY remote_user ASC",
+    )?;
+    let rows = stmt.query_map([chat_id], |row| {
+        Ok(BridgeIdentity {
+            chat_id: row.get(0)?,
+            remote_user: row.get(1)?,
+            display_name: row.get(2)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Remember which remote event a bridged message corresponds to. `direction`
+/// is `in` for messages that came from the remote network, `out` for ours.
+pub fn record_bridge_message(
+    conn: &Connection,
+    chat_id: &str,
+    local_id: &str,
+    remote_id: &str,
+    direction: &str,
+    created_at: i64,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO bridge_messages (chat_id, local_id, remote_id, direction, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![chat_id, local_id, remote_id, direction, created_at],
+    )?;
+    Ok(())
+}
+
+/// The rchat message bridged as `remote_id`, if any.
+pub fn bridge_message_local_id(
+    conn: &Connection,
+    chat_id: &str,
+    remote_id: &str,
+) -> anyhow::Result<Option<String>> {
+    let local_id = conn
+        .query_row(
+            "SELECT local_id FROM bridge_messages WHERE chat_id = ?1 AND remote_id = ?2",
+            [chat_id, remote_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(local_id)
+}
+
+/// Whether `local_id` came in from the remote network, so it must not be sent back.
+pub fn is_inbound_bridge_message(conn: &Connection, chat_id: &str, local_id: &str) -> bool {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM bridge_messages
+         WHERE chat_id = ?1 AND local_id = ?2 AND direction = 'in')",
+        [chat_id, local_id],
+        |row| row.get(0),
+    )
+    .unwrap_or(false)
+}
+
+fn bridge_nick(conn: &Connection, chat_id: &str, peer_id: &str) -> anyhow::Result<Option<String>> {
+    let nick = conn
+        .query_row(
+            "SELECT nick FROM bridge_nicks WHERE chat_id = ?1 AND peer_id = ?2",
+            [chat_id, peer_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(nick)
+}
+
+/// The nick `peer_id` uses on the remote side, assigned on first use: `wanted`,
+/// or `wanted` with a number appended when someone already has it.
+pub fn bridge_nick_for_peer(
+    conn: &Connection,
+    chat_id: &str,
+    peer_id: &str,
+    wanted: &str,
+) -> anyhow::Result<String> {
+    if let Some(nick) = bridge_nick(conn, chat_id, peer_id)? {
+        return Ok(nick);
+    }
+    for n in 1..1000 {
+        let candidate = if n == 1 {
+            wanted.to_string()
+        } else {
+            format!("{}{}", wanted, n)
+        };
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO bridge_nicks (chat_id, peer_id, nick) VALUES (?1, ?2, ?3)",
+            (chat_id, peer_id, &candidate),
+        )?;
+        if inserted == 1 {
+            return Ok(candidate);
+        }
+    }
+    Err(anyhow::anyhow!("No free bridge nick for {}", wanted))
+}
+
+/// Remember a remote user's nick, unless it is already taken.
+pub fn record_bridge_nick(
+    conn: &Connection,
+    chat_id: &str,
+    peer_id: &str,
+    nick: &str,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO bridge_nicks (chat_id, peer_id, nick) VALUES (?1, ?2, ?3)",
+        (chat_id, peer_id, nick),
+    )?;
+    Ok(())
+}
+
+pub fn list_bridge_nicks(conn: &Connection, chat_id: &str) -> anyhow::Result<Vec<BridgeNick>> {
+    let mut stmt = conn.prepare(
+        "SELECT chat_id, peer_id, nick FROM bridge_nicks WHERE chat_id = ?1 ORDER BY nick ASC",
+    )?;
+    let rows = stmt.query_map([chat_id], |row| {
+        Ok(BridgeNick {
+            chat_id: row.get(0)?,
+            peer_id: row.get(1)?,
+            nick: row.get(2)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+// --- Journal Opt-in ---
+
+pub fn set_chat_journaling(conn: &Connection, chat_id: &str, enabled: bool) -> anyhow::Result<()> {
+    if enabled {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        conn.execute(
+            "INSERT OR IGNORE INTO journal_chats (chat_id, enabled_at) VALUES (?1, ?2)",
+            (chat_id, now),
+        )?;
+    } else {
+        conn.execute("DELETE FROM journal_chats WHERE chat_id = ?1", [chat_id])?;
+    }
+    Ok(())
+}
+
+pub fn is_chat_journaled(conn: &Connection, chat_id: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM journal_chats WHERE chat_id = ?1",
+        [chat_id],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+pub fn list_journaled_chats(conn: &Connection) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT chat_id FROM journal_chats ORDER BY enabled_at ASC")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+// --- Chat Settings ---
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChatSettings {
+    pub chat_id: String,
+    pub custom_sound_hash: Option<String>,
+    pub priority: String,           // 'low', 'normal', 'high', 'urgent'
+    pub mute_until: Option<i64>,    // unix secs; MUTE_FOREVER mutes indefinitely
+    pub notification_level: String, // 'all', 'mentions', 'off'
+    pub send_translated: bool,
+    pub translate_to: Option<String>, // peer's preferred language code
+    pub hidden: bool,
+    pub restrict_media_export: bool,
+    pub auto_download: Option<String>, // JSON {media type: policy}; unset types use the global setting
+    pub history_share_days: Option<i64>, // group history shared with new members; None shares none
+}
+
+pub const MUTE_FOREVER: i64 = i64::MAX;
+
+impl ChatSettings {
+    pub fn defaults(chat_id: &str) -> Self {
+        Self {
+            chat_id: chat_id.to_string(),
+            custom_sound_hash: None,
+            priority: "normal".to_string(),
+            mute_until: None,
+            notification_level: "all".to_string(),
+            send_translated: false,
+            translate_to: None,
+            hidden: false,
+            restrict_media_export: false,
+            auto_download: None,
+            history_share_days: None,
+        }
+    }
+
+    pub fn is_muted(&self, now: i64) -> bool {
+        self.mute_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Settings for `chat_id`, falling back to defaults when none were saved.
+pub fn get_chat_settings(conn: &Connection, chat_id: &str) -> anyhow::Result<ChatSettings> {
+    let mut stmt = conn.prepare(
+        "SELECT chat_id, custom_sound_hash, priority, mute_until, notification_level,
+                send_translated, translate_to, hidden, restrict_media_export, auto_download,
+                history_share_days
+         FROM chat_settings WHERE chat_id = ?1",
+    )?;
+    let mut rows = stmt.query([chat_id])?;
+    match rows.next()? {
+        Some(row) => Ok(ChatSettings {
+            chat_id: row.get(0)?,
+            custom_sound_hash: row.get(1)?,
+            priority: row.get(2)?,
+            mute_until: row.get(3)?,
+            notification_level: row.get(4)?,
+            send_translated: row.get(5)?,
+            translate_to: row.get(6)?,
+            hidden: row.get(7)?,
+            restrict_media_export: row.get(8)?,
+            auto_download: row.get(9)?,
+            history_share_days: row.get(10)?,
+        }),
+        None => Ok(ChatSettings::defaults(chat_id)),
+    }
+}
+
+pub fn upsert_chat_settings(conn: &Connection, settings: &ChatSettings) -> anyhow::Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    conn.execute(
+        "INSERT INTO chat_settings
+            (chat_id, custom_sound_hash, priority, mute_until, notification_level,
+             send_translated, translate_to, hidden, restrict_media_export, auto_download,
+             history_share_days, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+         ON CONFLICT(chat_id) DO UPDATE SET
+            custom_sound_hash = excluded.custom_sound_hash,
+            priority = excluded.priority,
+            mute_until = excluded.mute_until,
+            notification_level = excluded.notification_level,
+            send_translated = excluded.send_translated,
+            translate_to = excluded.translate_to,
+            hidden = excluded.hidden,
+            restrict_media_export = excluded.restrict_media_export,
+            auto_download = excluded.auto_download,
+            history_share_days = excluded.history_share_days,
+            updated_at = excluded.updated_at",
+        (
+            &settings.chat_id,
+            &settings.custom_sound_hash,
+            &settings.priority,
+            settings.mute_until,
+            &settings.notification_level,
+            settings.send_translated,
+            &settings.translate_to,
+            settings.hidden,
+            settings.restrict_media_export,
+            &settings.auto_download,
+            settings.history_share_days,
+            now,
+        ),
+    )?;
+    Ok(())
+}
+
+/// Whether `file_hash` was received in a chat that restricts media export.
+pub fn is_media_export_restricted(conn: &Connection, file_hash: &str) -> anyhow::Result<bool> {
+    let restricted = conn
+        .prepare(
+            "SELECT 1 FROM messages m
+             JOIN chat_settings s ON s.chat_id = m.chat_id
+             WHERE m.file_hash = ?1 AND m.peer_id != 'Me' AND s.restrict_media_export = 1
+             LIMIT 1",
+        )?
+        .exists([file_hash])?;
+    Ok(restricted)
+}
+
+pub fn get_hidden_chat_ids(conn: &Connection) -> anyhow::Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT chat_id FROM chat_settings WHERE hidden = 1")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut out = HashSet::new();
+    for row in rows {
+        out.insert(row?);
+    }
+    Ok(out)
+}
+
+// --- Drafts ---
+
+/// Store the draft for `chat_id`; blank text removes it.
+pub fn save_draft(conn: &Connection, chat_id: &str, text: &str) -> anyhow::Result<()> {
+    if text.trim().is_empty() {
+        conn.execute("DELETE FROM drafts WHERE chat_id = ?1", [chat_id])?;
+        return Ok(());
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    conn.execute(
+        "INSERT INTO drafts (chat_id, text, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(chat_id) DO UPDATE SET
+            text = excluded.text,
+            updated_at = excluded.updated_at",
+        (chat_id, text, now),
+    )?;
+    Ok(())
+}
+
+pub fn get_draft(conn: &Connection, chat_id: &str) -> anyhow::Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT text FROM drafts WHERE chat_id = ?1",
+            [chat_id],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+// --- Starred Messages ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StarredMessage {
+    pub message: Message,
+    pub chat_name: String,
+    pub is_group: bool,
+    pub starred_at: i64,
+}
+
+/// Star or unstar a message; returns false if no such message exists.
+pub fn set_message_starred(
+    conn: &Connection,
+    msg_id: &str,
+    starred: bool,
+    now: i64,
+) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE messages SET
+            starred = ?2,
+            starred_at = CASE WHEN ?2 THEN COALESCE(starred_at, ?3) ELSE NULL END
+         WHERE id = ?1",
+        (msg_id, starred, now),
+    )?;
+    Ok(updated > 0)
+}
+
+/// Starred messages across all chats, most recently starred first.
+pub fn get_starred_messages(conn: &Connection) -> anyhow::Result<Vec<StarredMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.id, m.chat_id, m.peer_id, m.timestamp, m.content_type, m.text_content, m.file_hash,
+                COALESCE(m.status, 'delivered'), m.content_metadata, m.sender_alias, m.logical,
+                COALESCE(c.name, m.chat_id), COALESCE(c.is_group, 0), COALESCE(m.starred_at, m.timestamp)
+         FROM messages m
+         LEFT JOIN chats c ON c.id = m.chat_id
+         WHERE m.starred = 1
+         ORDER BY m.starred_at DESC, m.timestamp DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(StarredMessage {
+            message: message_from_row(row)?,
+            chat_name: row.get(11)?,
+            is_group: row.get(12)?,
+            starred_at: row.get(13)?,
+        })
+    })?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+// --- Peer Reputation ---
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeerReputation {
+    pub peer_id: String,
+    pub score: f64, // 0 (neutral) down to -100; see network::reputation
+    pub updated_at: i64,
+    pub invalid_messages: i64,
+    pub rate_limit_trips: i64,
+    pub failed_deliveries: i64,
+}
+
+fn peer_reputation_from_row(row: &rusqlite::Row) -> rusqlite::Result<PeerReputation> {
+    Ok(PeerReputation {
+        peer_id: row.get(0)?,
+        score: row.get(1)?,
+        updated_at: row.get(2)?,
+        invalid_messages: row.get(3)?,
+        rate_limit_trips: row.get(4)?,
+        failed_deliveries: row.get(5)?,
+    })
+}
+
+pub fn get_peer_reputation(
+    conn: &Connection,
+    peer_id: &str,
+) -> anyhow::Result<Option<PeerReputation>> {
+    Ok(conn
+        .query_row(
+            "SELECT id, reputation, reputation_updated_at, invalid_messages, rate_limit_trips, failed_deliveries
+             FROM peers WHERE id = ?1",
+            [peer_id],
+            peer_reputation_from_row,
+        )
+        .optional()?)
+}
+
+/// Reputation rows for peers that have misbehaved at least once.
+pub fn list_peer_reputations(conn: &Connection) -> anyhow::Result<Vec<PeerReputation>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, reputation, reputation_updated_at, invalid_messages, rate_limit_trips, failed_deliveries
+         FROM peers WHERE reputation_updated_at > 0",
+    )?;
+    let rows = stmt.query_map([], peer_reputation_from_row)?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Only peers already in the table are updated; unknown peers stay in memory.
+pub fn save_peer_reputation(conn: &Connection, rep: &PeerReputation) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE peers SET
+            reputation = ?2,
+            reputation_updated_at = ?3,
+            invalid_messages = ?4,
+            rate_limit_trips = ?5,
+            failed_deliveries = ?6
+         WHERE id = ?1",
+        (
+            &rep.peer_id,
+            rep.score,
+            rep.updated_at,
+            rep.invalid_messages,
+            rep.rate_limit_trips,
+            rep.failed_deliveries,
+        ),
+    )?;
+    Ok(updated > 0)
+}
+
+// --- Peer Identity ---
+
+/// Set or clear the local nickname for a peer. A local alias wins over the one
+/// the peer announces; clearing it lets the next identity update apply again.
+pub fn set_peer_alias(
+    conn: &Connection,
+    peer_id: &str,
+    alias: Option<&str>,
+) -> anyhow::Result<bool> {
+    let updated = match alias {
+        Some(alias) => conn.execute(
+            "UPDATE peers SET alias = ?2, alias_is_local = 1 WHERE id = ?1",
+            (peer_id, alias),
+        )?,
+        None => conn.execute(
+            "UPDATE peers SET alias_is_local = 0 WHERE id = ?1",
+            [peer_id],
+        )?,
+    };
+    Ok(updated > 0)
+}
+
+/// Set or clear a locally chosen avatar; same precedence rules as aliases.
+pub fn set_peer_avatar(
+    conn: &Connection,
+    peer_id: &str,
+    avatar_hash: Option<&str>,
+) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE peers SET avatar_hash = ?2, avatar_is_local = ?3 WHERE id = ?1",
+        (peer_id, avatar_hash, avatar_hash.is_some()),
+    )?;
+    Ok(updated > 0)
+}
+
+/// Apply the alias a peer announces for itself, unless the user set one locally.
+pub fn apply_peer_alias(conn: &Connection, peer_id: &str, alias: &str) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE peers SET alias = ?2 WHERE id = ?1 AND alias_is_local = 0",
+        (peer_id, alias),
+    )?;
+    Ok(updated > 0)
+}
+
+/// Apply the avatar a peer announces (`None` when they removed it), unless
+/// the user set one locally.
+pub fn apply_peer_avatar(
+    conn: &Connection,
+    peer_id: &str,
+    avatar_hash: Option<&str>,
+) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE peers SET avatar_hash = ?2 WHERE id = ?1 AND avatar_is_local = 0",
+        (peer_id, avatar_hash),
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn get_peer_avatar_hash(conn: &Connection, peer_id: &str) -> anyhow::Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT avatar_hash FROM peers WHERE id = ?1",
+            [peer_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+/// Remember the key a friend uses for anonymous mDNS advertisements.
+pub fn set_peer_discovery_key(
+    conn: &Connection,
+    peer_id: &str,
+    discovery_key: &str,
+    now: i64,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO peer_discovery_keys (peer_id, discovery_key, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(peer_id) DO UPDATE SET discovery_key = excluded.discovery_key, updated_at = excluded.updated_at",
+        (peer_id, discovery_key, now),
+    )?;
+    Ok(())
+}
+
+/// `(peer_id, discovery_key)` for every friend that shared one.
+pub fn get_peer_discovery_keys(conn: &Connection) -> anyhow::Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT peer_id, discovery_key FROM peer_discovery_keys")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+// --- Connect Handshakes ---
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeState {
+    pub peer_id: String,
+    /// `outgoing` (we pressed Connect) or `incoming` (they did).
+    pub direction: String,
+    pub created_at: i64,
+}
+
+pub fn save_handshake_state(conn: &Connection, state: &HandshakeState) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO handshake_state (peer_id, direction, created_at) VALUES (?1, ?2, ?3)",
+        (&state.peer_id, &state.direction, state.created_at),
+    )?;
+    Ok(())
+}
+
+pub fn delete_handshake_state(conn: &Connection, peer_id: &str) -> anyhow::Result<()> {
+    conn.execute("DELETE FROM handshake_state WHERE peer_id = ?1", [peer_id])?;
+    Ok(())
+}
+
+pub fn list_handshake_states(conn: &Connection) -> anyhow::Result<Vec<HandshakeState>> {
+    let mut stmt = conn.prepare("SELECT peer_id, direction, created_at FROM handshake_state")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(HandshakeState {
+                peer_id: row.get(0)?,
+                direction: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Delete handshakes created before `cutoff`; returns their peer ids.
+pub fn expire_handshake_states(conn: &Connection, cutoff: i64) -> anyhow::Result<Vec<String>> {
+    let tx = conn.unchecked_transaction()?;
+    let expired = {
+        let mut stmt = tx.prepare("SELECT peer_id FROM handshake_state WHERE created_at < ?1")?;
+        let rows = stmt
+            .query_map([cutoff], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        rows
+    };
+    tx.execute(
+        "DELETE FROM handshake_state WHERE created_at < ?1",
+        [cutoff],
+    )?;
+    tx.commit()?;
+    Ok(expired)
+}
+
+// --- Note to Self ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTask {
+    pub message: Message,
+    pub done: bool,
+    pub done_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: i64,
+    pub message_id: String,
+    pub remind_at: i64,
+    pub created_at: i64,
+    pub fired_at: Option<i64>,
+    /// Text of the message, for the notification body.
+    pub text: Option<String>,
+}
+
+/// Insert a `task` message into the self chat together with its done state.
+pub fn insert_self_task(conn: &Connection, msg: &Message) -> anyhow::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    insert_message(&tx, msg)?;
+    tx.execute(
+        "INSERT INTO self_tasks (message_id, done) VALUES (?1, 0)",
+        [&msg.id],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn set_task_done(
+    conn: &Connection,
+    message_id: &str,
+    done: bool,
+    now: i64,
+) -> anyhow::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE self_tasks SET done = ?2, done_at = CASE WHEN ?2 THEN ?3 ELSE NULL END
+         WHERE message_id = ?1",
+        (message_id, done, now),
+    )?;
+    Ok(updated > 0)
+}
+
+/// Tasks in the self chat, open ones first, oldest first within each group.
+pub fn get_self_tasks(conn: &Connection, include_done: bool) -> anyhow::Result<Vec<SelfTask>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.id, m.chat_id, m.peer_id, m.timestamp, m.content_type, m.text_content, m.file_hash,
+                COALESCE(m.status, 'delivered'), m.content_metadata, m.sender_alias, m.logical,
+                t.done, t.done_at
+         FROM self_tasks t
+         JOIN messages m ON m.id = t.message_id
+         WHERE ?1 OR t.done = 0
+         ORDER BY t.done ASC, m.timestamp ASC",
+    )?;
+    let rows = stmt.query_map([include_done], |row| {
+        Ok(SelfTask {
+            message: message_from_row(row)?,
+            done: row.get(11)?,
+            done_at: row.get(12)?,
+        })
+    })?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Schedule a reminder for a self-chat message; returns `None` if the message
+/// is not in the self chat.
+pub fn add_reminder(
+    conn: &Connection,
+    message_id: &str,
+    remind_at: i64,
+    now: i64,
+) -> anyhow::Result<Option<i64>> {
+    let inserted = conn.execute(
+        "INSERT INTO reminders (message_id, remind_at, created_at)
+         SELECT id, ?2, ?3 FROM messages WHERE id = ?1 AND chat_id = 'self'",
+        (message_id, remind_at, now),
+    )?;
+    Ok((inserted > 0).then(|| conn.last_insert_rowid()))
+}
+
+pub fn cancel_reminder(conn: &Connection, reminder_id: i64) -> anyhow::Result<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM reminders WHERE id = ?1 AND fired_at IS NULL",
+        [reminder_id],
+    )?;
+    Ok(deleted > 0)
+}
+
+fn reminder_from_row(row: &rusqlite::Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        remind_at: row.get(2)?,
+        created_at: row.get(3)?,
+        fired_at: row.get(4)?,
+        text: row.get(5)?,
+    })
+}
+
+/// Reminders that have not fired yet, soonest first.
+pub fn get_pending_reminders(conn: &Connection) -> anyhow::Result<Vec<Reminder>> {
+    let mut stmt = conn.prepare(
+        "SELECT r.id, r.message_id, r.remind_at, r.created_at, r.fired_at, m.text_content
+         FROM reminders r
+         JOIN messages m ON m.id = r.message_id
+         WHERE r.fired_at IS NULL
+         ORDER BY r.remind_at ASC",
+    )?;
+    let rows = stmt.query_map([], reminder_from_row)?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Mark every reminder due at `now` as fired and return them. Reminders that
+/// came due while the app was closed are returned on the next call.
+pub fn take_due_reminders(conn: &Connection, now: i64) -> anyhow::Result<Vec<Reminder>> {
+    let due: Vec<Reminder> = get_pending_reminders(conn)?
+        .into_iter()
+        .take_while(|r| r.remind_at <= now)
+        .collect();
+    for reminder in &due {
+        conn.execute(
+            "UPDATE reminders SET fired_at = ?2 WHERE id = ?1",
+            (reminder.id, now),
+        )?;
+    }
+    Ok(due
+        .into_iter()
+        .map(|r| Reminder {
+            fired_at: Some(now),
+            ..r
+        })
+        .collect())
+}
+
+// --- Scheduled Messages ---
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduledMessage {
+    pub id: i64,
+    pub chat_id: String,
+    pub text: String,
+    pub send_at: i64,
+    pub created_at: i64,
+    /// 'pending', 'sent' or 'failed'.
+    pub status: String,
+    /// Id of the message it became once sent.
+    pub message_id: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn add_scheduled_message(
+    conn: &Connection,
+    chat_id: &str,
+    text: &str,
+    send_at: i64,
+    now: i64,
+) -> anyhow::Result<ScheduledMessage> {
+    conn.execute(
+        "INSERT INTO scheduled_messages (chat_id, text, send_at, created_at) VALUES (?1, ?2, ?3, ?4)",
+        (chat_id, text, send_at, now),
+    )?;
+    Ok(ScheduledMessage {
+        id: conn.last_insert_rowid(),
+        chat_id: chat_id.to_string(),
+        text: text.to_string(),
+        send_at,
+        created_at: now,
+        status: "pending".to_string(),
+        message_id: None,
+        error: None,
+    })
+}
+
+/// Cancel a message that has not been sent yet.
+pub fn cancel_scheduled_message(conn: &Connection, id: i64) -> anyhow::Result<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM scheduled_messages WHERE id = ?1 AND status = 'pending'",
+        [id],
+    )?;
+    Ok(deleted > 0)
+}
+
+/// Pending messages (of `chat_id`, if given), soonest first.
+pub fn list_scheduled_messages(
+    conn: &Connection,
+    chat_id: Option<&str>,
+) -> anyhow::Result<Vec<ScheduledMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, chat_id, text, send_at, created_at, status, message_id, error
+         FROM scheduled_messages
+         WHERE status = 'pending' AND (?1 IS NULL OR chat_id = ?1)
+         ORDER BY send_at ASC, id ASC",
+    )?;
+    let rows = stmt
+        .query_map([chat_id], |row| {
+            Ok(ScheduledMessage {
+                id: row.get(0)?,
+                chat_id: row.get(1)?,
+                text: row.get(2)?,
+                send_at: row.get(3)?,
+                created_at: row.get(4)?,
+                status: row.get(5)?,
+                message_id: row.get(6)?,
+                error: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Pending messages due at `now`, soonest first. Messages that came due while
+/// the app was closed are included.
+pub fn due_scheduled_messages(
+    conn: &Connection,
+    now: i64,
+) -> anyhow::Result<Vec<ScheduledMessage>> {
+    Ok(list_scheduled_messages(conn, None)?
+        .into_iter()
+        .take_while(|m| m.send_at <= now)
+        .collect())
+}
+
+/// Record the outcome of sending a scheduled message.
+pub fn finish_scheduled_message(
+    conn: &Connection,
+    id: i64,
+    outcome: Result<&str, &str>,
+) -> anyhow::Result<()> {
+    let (status, message_id, error) = match outcome {
+        Ok(message_id) => ("sent", Some(message_id), None),
+        Err(error) => ("failed", None, Some(error)),
+    };
+    conn.execute(
+        "UPDATE scheduled_messages SET status = ?2, message_id = ?3, error = ?4 WHERE id = ?1",
+        (id, status, message_id, error),
+    )?;
+    Ok(())
+}
+
+// --- Usage Metrics ---
+
+/// Counter row holding the time metrics were last reset.
+const METRICS_SINCE_KEY: &str = "_since";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageMetrics {
+    /// Unix secs of the first recorded metric or the last reset.
+    pub since: Option<i64>,
+    pub counters: std::collections::BTreeMap<String, i64>,
+    /// `error.*` counters keyed by error type.
+    pub errors: std::collections::BTreeMap<String, i64>,
+}
+
+pub fn add_usage_metrics(
+    conn: &Connection,
+    increments: &std::collections::BTreeMap<String, i64>,
+) -> anyhow::Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT OR IGNORE INTO usage_metrics (metric, count, updated_at) VALUES (?1, ?2, ?2)",
+        (METRICS_SINCE_KEY, now),
+    )?;
+    for (metric, count) in increments {
+        tx.execute(
+            "INSERT INTO usage_metrics (metric, count, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(metric) DO UPDATE SET
+                count = count + excluded.count,
+                updated_at = excluded.updated_at",
+            (metric, count, now),
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn get_usage_metrics(conn: &Connection) -> anyhow::Result<UsageMetrics> {
+    let mut stmt = conn.prepare("SELECT metric, count FROM usage_metrics")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    let mut metrics = UsageMetrics::default();
+    for row in rows {
+        let (metric, count) = row?;
+        if metric == METRICS_SINCE_KEY {
+            metrics.since = Some(count);
+        } else if let Some(kind) = metric.strip_prefix("error.") {
+            metrics.errors.insert(kind.to_string(), count);
+        } else {
+            metrics.counters.insert(metric, count);
+        }
+    }
+    Ok(metrics)
+}
+
+pub fn reset_usage_metrics(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute("DELETE FROM usage_metrics", [])?;
+    Ok(())
+}
+
+// --- Bandwidth ---
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BandwidthStat {
+    pub day: String,
+    pub peer_id: String,
+    pub protocol: String,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
+}
+
+pub fn add_bandwidth_usage(conn: &Connection, usage: &[BandwidthStat]) -> anyhow::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for stat in usage {
+        tx.execute(
+            "INSERT INTO bandwidth_stats (day, peer_id, protocol, bytes_sent, bytes_received)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(day, peer_id, protocol) DO UPDATE SET
+                bytes_sent = bytes_sent + excluded.bytes_sent,
+                bytes_received = bytes_received + excluded.bytes_received",
+            (
+                &stat.day,
+                &stat.peer_id,
+                &stat.protocol,
+                stat.bytes_sent,
+                stat.bytes_received,
+            ),
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Daily rows from `since_day` (inclusive) on, newest first.
+pub fn list_bandwidth_stats(
+    conn: &Connection,
+    since_day: &str,
+) -> anyhow::Result<Vec<BandwidthStat>> {
+    let mut stmt = conn.prepare(
+        "SELECT day, peer_id, protocol, bytes_sent, bytes_received FROM bandwidth_stats
+         WHERE day >= ?1 ORDER BY day DESC, peer_id, protocol",
+    )?;
+    let rows = stmt
+        .query_map([since_day], |row| {
+            Ok(BandwidthStat {
+                day: row.get(0)?,
+                peer_id: row.get(1)?,
+                protocol: row.get(2)?,
+                bytes_sent: row.get(3)?,
+                bytes_received: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Bytes sent plus received from `since_day` (inclusive) on.
+pub fn bandwidth_total_since(conn: &Connection, since_day: &str) -> anyhow::Result<i64> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(SUM(bytes_sent + bytes_received), 0) FROM bandwidth_stats WHERE day >= ?1",
+        [since_day],
+        |row| row.get(0),
+    )?)
+}
+
+// --- Peer Addresses ---
+
+/// Most recent addresses kept per peer.
+pub const PEER_ADDRESSES_PER_PEER: i64 = 8;
+
+/// Remember a successful dial of `peer_id` at `address`, keeping only the
+/// peer's most recent [`PEER_ADDRESSES_PER_PEER`] addresses.
+pub fn record_peer_address(
+    conn: &Connection,
+    peer_id: &str,
+    address: &str,
+    now: i64,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO peer_addresses (peer_id, address, last_success_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(peer_id, address) DO UPDATE SET last_success_at = excluded.last_success_at",
+        (peer_id, address, now),
+    )?;
+    conn.execute(
+        "DELETE FROM peer_addresses WHERE peer_id = ?1 AND address NOT IN (
+            SELECT address FROM peer_addresses WHERE peer_id = ?1
+            ORDER BY last_success_at DESC, address LIMIT ?2
+         )",
+        (peer_id, PEER_ADDRESSES_PER_PEER),
+    )?;
+    Ok(())
+}
+
+/// `(peer_id, address)` rows, oldest success first.
+pub fn list_peer_addresses(conn: &Connection) -> anyhow::Result<Vec<(String, String)>> {
+    let mut stmt = conn
+        .prepare("SELECT peer_id, address FROM peer_addresses ORDER BY last_success_at, address")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+// --- Auto Replies ---
+
+/// Record an auto reply to `chat_id` unless one was sent within `window_secs`.
+/// Returns whether the caller may send it.
+pub fn claim_auto_reply(
+    conn: &Connection,
+    chat_id: &str,
+    now: i64,
+    window_secs: i64,
+) -> anyhow::Result<bool> {
+    let changed = conn.execute(
+        "INSERT INTO auto_replies (chat_id, last_replied_at) VALUES (?1, ?2)
+         ON CONFLICT(chat_id) DO UPDATE SET last_replied_at = excluded.last_replied_at
+         WHERE auto_replies.last_replied_at <= ?2 - ?3",
+        (chat_id, now, window_secs),
+    )?;
+    Ok(changed > 0)
+}
+
+/// Forget past auto replies so everyone gets one again, e.g. when away mode is re-enabled.
+pub fn clear_auto_replies(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute("DELETE FROM auto_replies", [])?;
+    Ok(())
+}
+
+// --- Message Request Scores ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRequestScore {
+    pub chat_id: String,
+    pub peer_id: String,
+    pub msg_id: String,
+    pub score: u32,
+    pub level: String,
+    pub reasons: Vec<String>,
+    pub scored_at: i64,
+}
+
+/// True if we have sent at least one message in `chat_id`.
+pub fn has_outgoing_messages(conn: &Connection, chat_id: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM messages WHERE chat_id = ?1 AND peer_id = 'Me' LIMIT 1",
+        [chat_id],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+/// Keeps the highest score seen for a chat so a follow-up "hi" cannot mask an earlier scam.
+pub fn upsert_message_request_score(
+    conn: &Connection,
+    score: &MessageRequestScore,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO message_request_scores (chat_id, peer_id, msg_id, score, level, reasons, scored_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(chat_id) DO UPDATE SET
+            peer_id = excluded.peer_id,
+            msg_id = excluded.msg_id,
+            score = excluded.score,
+            level = excluded.level,
+            reasons = excluded.reasons,
+            scored_at = excluded.scored_at
+         WHERE excluded.score >= message_request_scores.score",
+        (
+            &score.chat_id,
+            &score.peer_id,
+            &score.msg_id,
+            score.score,
+            &score.level,
+            serde_json::to_string(&score.reasons)?,
+            score.scored_at,
+        ),
+    )?;
+    Ok(())
+}
+
+/// Scores for chats that are still message requests (no reply sent yet).
+pub fn list_message_request_scores(conn: &Connection) -> anyhow::Result<Vec<MessageRequestScore>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.chat_id, s.peer_id, s.msg_id, s.score, s.level, s.reasons, s.scored_at
+         FROM message_request_scores s
+         WHERE NOT EXISTS (
+            SELECT 1 FROM messages m WHERE m.chat_id = s.chat_id AND m.peer_id = 'Me'
+         )
+         ORDER BY s.score DESC, s.scored_at DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let reasons: String = row.get(5)?;
+        Ok(MessageRequestScore {
+            chat_id: row.get(0)?,
+            peer_id: row.get(1)?,
+            msg_id: row.get(2)?,
+            score: row.get(3)?,
+            level: row.get(4)?,
+            reasons: serde_json::from_str(&reasons).unwrap_or_default(),
+            scored_at: row.get(6)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_general_rows_are_removed() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+
+        conn.execute(
+            "INSERT OR REPLACE INTO peers (id, alias, last_seen, public_key, method) VALUES ('General', 'General', 0, ?1, 'legacy')",
+            [vec![0u8; 32]],
+        )
+        .expect("insert peer");
+        conn.execute(
+            "INSERT OR REPLACE INTO chats (id, name, is_group, encryption_key) VALUES ('General', 'General', 0, ?1)",
+            [vec![0u8; 32]],
+        )
+        .expect("insert chat");
+        conn.execute(
+            "INSERT OR REPLACE INTO messages (id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, status) VALUES ('m1', 'General', 'General', 1, 'text', 'hello', NULL, 'delivered')",
+            [],
+        )
+        .expect("insert message");
+
+        conn.execute(
+            "INSERT OR REPLACE INTO envelopes (id, name, icon) VALUES ('env1', 'Env', NULL)",
+            [],
+        )
+        .expect("insert envelope");
+        conn.execute(
+            "INSERT OR REPLACE INTO chat_envelopes (chat_id, envelope_id) VALUES ('General', 'env1')",
+            [],
+        )
+        .expect("insert chat envelope");
+
+        remove_legacy_general_data(&conn).expect("cleanup");
+
+        let chat_exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM chats WHERE id='General')",
+                [],
+                |row| row.get(0),
+            )
+            .expect("check chat");
+        let msg_exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM messages WHERE chat_id='General' OR peer_id='General')",
+                [],
+                |row| row.get(0),
+            )
+            .expect("check messages");
+        assert!(!chat_exists);
+        assert!(!msg_exists);
+    }
+
+    #[test]
+    fn messages_store_typed_content_in_flat_columns() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let msg = Message {
+            id: "m1".to_string(),
+            chat_id: "chat-a".to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: 1,
+            content: MessageContent::Document {
+                file_hash: "doc-hash".to_string(),
+                file_name: "spec.pdf".to_string(),
+                metadata: crate::chat::message::ContentMetadata {
+                    size_bytes: Some(42),
+                    ..Default::default()
+                },
+            },
+            status: "sent".to_string(),
+            content_metadata: Some("{\"size_bytes\":42}".to_string()),
+            sender_alias: None,
+            logical: 0,
+        };
+        insert_message(&conn, &msg).expect("insert");
+
+        let columns: (String, Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT content_type, text_content, file_hash FROM messages WHERE id = 'm1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .expect("columns");
+        assert_eq!(
+            columns,
+            (
+                "document".to_string(),
+                Some("spec.pdf".to_string()),
+                Some("doc-hash".to_string())
+            )
+        );
+        let stored = get_message(&conn, "m1").expect("read").expect("stored");
+        assert_eq!(stored.content, msg.content);
+
+        let json = serde_json::to_value(&stored).expect("json");
+        assert_eq!(json["content_type"], "document");
+        assert_eq!(json["text_content"], "spec.pdf");
+        assert_eq!(json["file_hash"], "doc-hash");
+        let back: Message = serde_json::from_value(json).expect("from json");
+        assert_eq!(back.content, msg.content);
+    }
+
+    #[test]
+    fn unread_counts_skip_muted_and_silenced_chats() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+
+        for (i, chat_id) in ["chat-a", "chat-b", "chat-c"].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO messages (id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, status) VALUES (?1, ?2, 'peer-x', 1, 'text', 'hi', NULL, 'delivered')",
+                (format!("m{}", i), chat_id),
+            )
+            .expect("insert message");
+        }
+
+        let mut muted = ChatSettings::defaults("chat-a");
+        muted.mute_until = Some(MUTE_FOREVER);
+        upsert_chat_settings(&conn, &muted).expect("mute chat-a");
+        let mut silenced = ChatSettings::defaults("chat-b");
+        silenced.notification_level = "off".to_string();
+        upsert_chat_settings(&conn, &silenced).expect("silence chat-b");
+
+        let counts = get_unread_counts(&conn, "me").expect("unread counts");
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.get("chat-c"), Some(&1));
+
+        muted.mute_until = Some(1);
+        upsert_chat_settings(&conn, &muted).expect("expired mute");
+        assert!(!get_chat_settings(&conn, "chat-a")
+            .expect("read")
+            .is_muted(2));
+        assert_eq!(get_unread_counts(&conn, "me").expect("unread").len(), 2);
+    }
+
+    #[test]
+    fn drafts_round_trip_and_clear_on_blank_text() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+
+        assert_eq!(get_draft(&conn, "chat-a").expect("empty"), None);
+        save_draft(&conn, "chat-a", "half a thought").expect("save");
+        save_draft(&conn, "chat-a", "half a thought, finished").expect("overwrite");
+        assert_eq!(
+            get_draft(&conn, "chat-a").expect("read").as_deref(),
+            Some("half a thought, finished")
+        );
+
+        save_draft(&conn, "chat-a", "   ").expect("clear");
+        assert_eq!(get_draft(&conn, "chat-a").expect("cleared"), None);
+    }
+
+    #[test]
+    fn deleting_a_message_releases_its_file() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        conn.execute(
+            "INSERT INTO files (file_hash, file_name, mime_type, size_bytes, is_complete)
+             VALUES ('hash', 'scan.pdf', 'application/pdf', 3, 1)",
+            [],
+        )
+        .expect("file");
+        let note = Message {
+            id: "n1".to_string(),
+            chat_id: "self".to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: 1,
+            content: MessageContent::Document {
+                file_hash: "hash".to_string(),
+                file_name: "scan.pdf".to_string(),
+                metadata: Default::default(),
+            },
+            status: "read".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+        insert_message(&conn, &note).expect("insert");
+        assert!(is_file_referenced(&conn, "hash").expect("referenced"));
+
+        assert!(!delete_chat_message(&conn, "other", "n1").expect("wrong chat"));
+        assert!(delete_chat_message(&conn, "self", "n1").expect("delete"));
+        assert!(!is_file_referenced(&conn, "hash").expect("released"));
+    }
+
+    #[test]
+    fn nested_envelopes_aggregate_unread_counts() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        create_envelope(&conn, "work", "Work", None).expect("work");
+        create_nested_envelope(&conn, "team", "Team", None, Some("work")).expect("team");
+        create_envelope(&conn, "family", "Family", None).expect("family");
+        add_peer(&conn, "peer-a", Some("Ada"), None, "local").expect("peer");
+        create_chat(&conn, "peer-a", "Ada", false).expect("chat");
+        assign_chat_to_envelope(&conn, "peer-a", Some("team")).expect("assign");
+        conn.execute(
+            "INSERT INTO messages (id, chat_id, peer_id, timestamp, content_type, text_content, status)
+             VALUES ('m1', 'peer-a', 'peer-a', 5, 'text', 'hi', 'delivered'),
+                    ('m2', 'peer-a', 'peer-a', 9, 'text', 'hi', 'delivered'),
+                    ('m3', 'peer-a', 'Me', 12, 'text', 'yo', 'delivered')",
+            [],
+        )
+        .expect("messages");
+
+        let summaries = get_envelope_summaries(&conn, "Me", str::to_string).expect("summaries");
+        let summary = |id: &str| summaries.iter().find(|s| s.envelope_id == id).unwrap();
+        assert_eq!(summary("work").unread_count, 2);
+        assert_eq!(summary("work").latest_activity, Some(12));
+        assert_eq!(summary("team").chat_count, 1);
+        assert_eq!(summary("family").latest_activity, None);
+
+        assert!(reorder_envelopes(&conn, Some("team"), &["work".to_string()]).is_err());
+        reorder_envelopes(&conn, None, &["family".to_string(), "work".to_string()])
+            .expect("reorder");
+        let order: Vec<String> = get_envelopes(&conn)
+            .expect("envelopes")
+            .into_iter()
+            .filter(|e| e.parent_id.is_none())
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(order, vec!["family", "work"]);
+
+        delete_envelope(&conn, "work").expect("delete");
+        let team = get_envelopes(&conn)
+            .expect("envelopes")
+            .into_iter()
+            .find(|e| e.id == "team")
+            .unwrap();
+        assert_eq!(team.parent_id, None);
+    }
+
+    #[test]
+    fn chats_can_carry_several_envelope_tags() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        create_envelope(&conn, "work", "Work", None).expect("work");
+        create_nested_envelope(&conn, "team", "Team", None, Some("work")).expect("team");
+        create_envelope(&conn, "starred", "Starred", None).expect("starred");
+
+        add_chat_tag(&conn, "a", "team").expect("tag");
+        add_chat_tag(&conn, "a", "starred").expect("tag");
+        add_chat_tag(&conn, "a", "starred").expect("tag twice");
+        add_chat_tag(&conn, "b", "work").expect("tag");
+        assert_eq!(get_chat_assignments(&conn).expect("assignments").len(), 3);
+
+        let ids = |tags: &[&str], all: bool| {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            let mut ids: Vec<String> = get_chat_ids_with_tags(&conn, &tags, all)
+                .expect("filter")
+                .into_iter()
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&["work"], false), vec!["a", "b"]);
+        assert_eq!(ids(&["work", "starred"], true), vec!["a"]);
+
+        remove_chat_tag(&conn, "a", "starred").expect("untag");
+        assert!(ids(&["starred"], false).is_empty());
+        assign_chat_to_envelope(&conn, "b", Some("starred")).expect("move");
+        assert_eq!(ids(&["work"], false), vec!["a"]);
+    }
+
+    #[test]
+    fn message_batches_are_all_or_nothing() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let msg = |id: &str| Message {
+            id: id.to_string(),
+            chat_id: "self".to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: 1,
+            content: MessageContent::Text {
+                text: id.to_string(),
+            },
+            status: "read".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+
+        insert_messages_batch(&conn, &[msg("a"), msg("b")]).expect("batch");
+        // "b" already exists, so "c" is rolled back too.
+        assert!(insert_messages_batch(&conn, &[msg("c"), msg("b")]).is_err());
+        let ids: Vec<String> = get_messages(&conn, "self")
+            .expect("messages")
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn messages_are_ordered_by_logical_clock_over_wall_clock() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let msg = |id: &str, timestamp: i64, logical: i64| Message {
+            id: id.to_string(),
+            chat_id: "c1".to_string(),
+            peer_id: "peer".to_string(),
+            timestamp,
+            content: MessageContent::Text {
+                text: String::new(),
+            },
+            status: "delivered".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical,
+        };
+
+        insert_message(&conn, &msg("mine", 100, 0)).expect("local");
+        // A peer whose clock runs an hour behind answers with logical 7.
+        insert_message(&conn, &msg("reply", 100 - 3600, 7)).expect("remote");
+        insert_message(&conn, &msg("next", 101, 0)).expect("local");
+        insert_message(&conn, &msg("bogus", 102, i64::MAX)).expect("remote");
+
+        let messages = get_messages(&conn, "c1").expect("messages");
+        let order: Vec<(&str, i64)> = messages
+            .iter()
+            .map(|m| (m.id.as_str(), m.logical))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("mine", 1),
+                ("reply", 7),
+                ("next", 8),
+                ("bogus", 9 + MAX_LOGICAL_JUMP)
+            ]
+        );
+        assert_eq!(message_logical(&conn, "next").expect("logical"), Some(8));
+    }
+
+    #[test]
+    fn message_status_only_moves_forward() {
+        use MessageStatus::{Delivered, Failed, Pending, Read, Sent};
+        assert!(status_transition_allowed(Pending, Sent));
+        assert!(status_transition_allowed(Sent, Failed));
+        assert!(status_transition_allowed(Failed, Pending));
+        assert!(!status_transition_allowed(Read, Delivered));
+        assert!(!status_transition_allowed(Delivered, Failed));
+        assert!(!status_transition_allowed(Delivered, Delivered));
+
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        insert_message(
+            &conn,
+            &Message {
+                id: "m1".to_string(),
+                chat_id: "c1".to_string(),
+                peer_id: "Me".to_string(),
+                timestamp: 1,
+                content: MessageContent::Text {
+                    text: String::new(),
+                },
+                status: "pending".to_string(),
+                content_metadata: None,
+                sender_alias: None,
+                logical: 0,
+            },
+        )
+        .expect("insert");
+        let status = |conn: &Connection| get_message(conn, "m1").unwrap().unwrap().status;
+
+        assert!(update_message_status(&conn, "m1", "sent").expect("sent"));
+        // The read receipt overtakes the delivery receipt.
+        assert!(update_message_status(&conn, "m1", "read").expect("read"));
+        assert!(!update_message_status(&conn, "m1", "delivered").expect("late receipt"));
+        assert!(!update_message_status(&conn, "m1", "failed").expect("late failure"));
+        assert_eq!(status(&conn), "read");
+
+        assert!(!update_message_status(&conn, "missing", "read").expect("unknown message"));
+        assert!(update_message_status(&conn, "m1", "bogus").is_err());
+    }
+
+    #[test]
+    fn member_roles_and_group_avatar_can_be_changed() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        upsert_chat(&conn, "group:g", "Group", true).unwrap();
+        add_chat_member(&conn, "group:g", "Me", "admin").unwrap();
+        add_chat_member(&conn, "group:g", "peer-b", "member").unwrap();
+
+        assert_eq!(
+            chat_member_role(&conn, "group:g", "peer-b")
+                .unwrap()
+                .as_deref(),
+            Some("member")
+        );
+        assert!(set_chat_member_role(&conn, "group:g", "peer-b", "admin").unwrap());
+        assert_eq!(
+            chat_member_role(&conn, "group:g", "peer-b")
+                .unwrap()
+                .as_deref(),
+            Some("admin")
+        );
+        assert!(!set_chat_member_role(&conn, "group:g", "peer-c", "admin").unwrap());
+        assert_eq!(chat_member_role(&conn, "group:g", "peer-c").unwrap(), None);
+
+        assert_eq!(get_chat_avatar(&conn, "group:g").unwrap(), None);
+        set_chat_avatar(&conn, "group:g", Some("hash")).unwrap();
+        assert_eq!(
+            get_chat_avatar(&conn, "group:g").unwrap().as_deref(),
+            Some("hash")
+        );
+        set_chat_avatar(&conn, "group:g", None).unwrap();
+        assert_eq!(get_chat_avatar(&conn, "group:g").unwrap(), None);
+    }
+
+    #[test]
+    fn group_keys_are_kept_per_epoch_with_their_admin() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        assert_eq!(latest_group_key(&conn, "group:g").unwrap(), None);
+
+        assert!(store_group_key(&conn, "group:g", 1, &[1; 32], "admin").unwrap());
+        assert!(store_group_key(&conn, "group:g", 2, &[2; 32], "admin").unwrap());
+        assert!(!store_group_key(&conn, "group:g", 2, &[3; 32], "other").unwrap());
+        assert_eq!(
+            latest_group_key(&conn, "group:g").unwrap(),
+            Some((2, [2; 32]))
+        );
+        assert_eq!(group_key(&conn, "group:g", 1).unwrap(), Some([1; 32]));
+        assert_eq!(
+            group_key_admin(&conn, "group:g").unwrap().as_deref(),
+            Some("admin")
+        );
+
+        delete_group_chat(&conn, "group:g").unwrap();
+        assert_eq!(group_key(&conn, "group:g", 1).unwrap(), None);
+    }
+
+    #[test]
+    fn first_group_key_is_only_taken_from_an_admin() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        upsert_chat(&conn, "group:g", "Group", true).unwrap();
+        add_chat_member(&conn, "group:g", "creator", "admin").unwrap();
+        add_chat_member(&conn, "group:g", "peer-b", "member").unwrap();
+
+        assert!(!accepts_group_key_from(&conn, "group:g", "peer-b").unwrap());
+        assert!(!accepts_group_key_from(&conn, "group:g", "stranger").unwrap());
+        assert!(accepts_group_key_from(&conn, "group:g", "creator").unwrap());
+
+        store_group_key(&conn, "group:g", 1, &[1; 32], "creator").unwrap();
+        set_chat_member_role(&conn, "group:g", "peer-b", "admin").unwrap();
+        assert!(!accepts_group_key_from(&conn, "group:g", "peer-b").unwrap());
+        assert!(accepts_group_key_from(&conn, "group:g", "creator").unwrap());
+    }
+
+    #[test]
+    fn message_pages_follow_the_cursor_within_the_window() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        for (i, timestamp) in [10, 20, 30, 40, 50].into_iter().enumerate() {
+            insert_message(
+                &conn,
+                &Message {
+                    id: format!("m{}", i),
+                    chat_id: "group:g".to_string(),
+                    peer_id: "peer-b".to_string(),
+                    timestamp,
+                    content: MessageContent::Text {
+                        text: format!("hi {}", i),
+                    },
+                    status: "delivered".to_string(),
+                    content_metadata: None,
+                    sender_alias: None,
+                    logical: 0,
+                },
+            )
+            .expect("insert");
+        }
+        let ids = |page: &[Message]| page.iter().map(|m| m.id.clone()).collect::<Vec<_>>();
+
+        let first = get_messages_page(&conn, "group:g", 20, None, 2).unwrap();
+        assert_eq!(ids(&first), ["m1", "m2"]);
+        let last = first.last().unwrap();
+        let second =
+            get_messages_page(&conn, "group:g", 20, Some((last.logical, &last.id)), 2).unwrap();
+        assert_eq!(ids(&second), ["m3", "m4"]);
+        let last = second.last().unwrap();
+        assert!(
+            get_messages_page(&conn, "group:g", 20, Some((last.logical, &last.id)), 2)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(get_messages_page(&conn, "group:h", 0, None, 2)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn chats_are_listed_with_their_members() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        add_peer(&conn, "peer-b", Some("Bob"), None, "local").expect("peer");
+        create_chat(&conn, "group:a", "Book club", true).expect("group chat");
+        add_chat_member(&conn, "group:a", "Me", "admin").expect("admin");
+        add_chat_member(&conn, "group:a", "peer-b", "member").expect("member");
+
+        let chats = list_chats(&conn).expect("list");
+        assert_eq!(chats.len(), 1);
+        assert!(chats[0].is_group);
+        let mut roles: Vec<_> = get_chat_members(&conn, "group:a")
+            .expect("members")
+            .into_iter()
+            .map(|m| (m.peer_id, m.role))
+            .collect();
+        roles.sort();
+        assert_eq!(
+            roles,
+            vec![
+                ("Me".to_string(), "admin".to_string()),
+                ("peer-b".to_string(), "member".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn starred_messages_are_listed_across_chats() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        create_chat(&conn, "group:a", "Book club", true).expect("group chat");
+        create_chat(&conn, "lh:bob:peer-b", "Bob", false).expect("direct chat");
+        for (id, chat_id) in [
+            ("m1", "group:a"),
+            ("m2", "lh:bob:peer-b"),
+            ("m3", "group:a"),
+        ] {
+            insert_message(
+                &conn,
+                &Message {
+                    id: id.to_string(),
+                    chat_id: chat_id.to_string(),
+                    peer_id: "Me".to_string(),
+                    timestamp: 1,
+                    content: MessageContent::Text {
+                        text: id.to_string(),
+                    },
+                    status: "delivered".to_string(),
+                    content_metadata: None,
+                    sender_alias: None,
+                    logical: 0,
+                },
+            )
+            .expect("insert");
+        }
+
+        assert!(set_message_starred(&conn, "m1", true, 10).expect("star m1"));
+        assert!(set_message_starred(&conn, "m2", true, 20).expect("star m2"));
+        assert!(!set_message_starred(&conn, "missing", true, 30).expect("missing"));
+
+        let starred = get_starred_messages(&conn).expect("list");
+        let ids: Vec<&str> = starred.iter().map(|s| s.message.id.as_str()).collect();
+        assert_eq!(ids, vec!["m2", "m1"]);
+        assert_eq!(starred[1].chat_name, "Book club");
+        assert!(starred[1].is_group);
+        assert_eq!(starred[0].chat_name, "Bob");
+        assert!(!starred[0].is_group);
+
+        set_message_starred(&conn, "m2", false, 40).expect("unstar");
+        assert_eq!(get_starred_messages(&conn).expect("list").len(), 1);
+    }
+
+    #[test]
+    fn peer_reputation_persists_only_for_known_peers() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        add_peer(&conn, "peer-a", Some("Alice"), None, "local").expect("peer");
+
+        let rep = PeerReputation {
+            peer_id: "peer-a".to_string(),
+            score: -15.0,
+            updated_at: 42,
+            invalid_messages: 1,
+            rate_limit_trips: 1,
+            failed_deliveries: 0,
+        };
+        assert!(save_peer_reputation(&conn, &rep).expect("save"));
+        let stranger = PeerReputation {
+            peer_id: "stranger".to_string(),
+            ..rep.clone()
+        };
+        assert!(!save_peer_reputation(&conn, &stranger).expect("save unknown"));
+
+        let stored = get_peer_reputation(&conn, "peer-a")
+            .expect("read")
+            .expect("row");
+        assert_eq!(stored.score, -15.0);
+        assert_eq!(stored.rate_limit_trips, 1);
+        let listed = list_peer_reputations(&conn).expect("list");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].peer_id, "peer-a");
+    }
+
+    #[test]
+    fn local_alias_and_avatar_override_announced_identity() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        add_peer(&conn, "peer-a", Some("alice"), None, "local").expect("peer");
+
+        assert!(apply_peer_alias(&conn, "peer-a", "Alice A.").expect("apply"));
+        assert!(apply_peer_avatar(&conn, "peer-a", Some("h1")).expect("apply"));
+        assert_eq!(
+            get_peer_alias(&conn, "peer-a").unwrap().as_deref(),
+            Some("Alice A.")
+        );
+
+        assert!(set_peer_alias(&conn, "peer-a", Some("Mom")).expect("alias"));
+        assert!(set_peer_avatar(&conn, "peer-a", Some("local")).expect("avatar"));
+        assert!(!apply_peer_alias(&conn, "peer-a", "Alice B.").expect("apply"));
+        assert!(!apply_peer_avatar(&conn, "peer-a", Some("h2")).expect("apply"));
+        assert_eq!(
+            get_peer_alias(&conn, "peer-a").unwrap().as_deref(),
+            Some("Mom")
+        );
+        assert_eq!(
+            get_peer_avatar_hash(&conn, "peer-a").unwrap().as_deref(),
+            Some("local")
+        );
+
+        set_peer_alias(&conn, "peer-a", None).expect("clear alias");
+        apply_peer_alias(&conn, "peer-a", "Alice B.").expect("apply");
+        assert_eq!(
+            get_peer_alias(&conn, "peer-a").unwrap().as_deref(),
+            Some("Alice B.")
+        );
+        assert!(!set_peer_alias(&conn, "stranger", Some("x")).expect("unknown"));
+    }
+
+    #[test]
+    fn scheduled_messages_come_due_once() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let later = add_scheduled_message(&conn, "chat-a", "later", 200, 1).expect("later");
+        let soon = add_scheduled_message(&conn, "chat-b", "soon", 100, 1).expect("soon");
+        let dropped = add_scheduled_message(&conn, "chat-a", "never", 50, 1).expect("dropped");
+        assert!(cancel_scheduled_message(&conn, dropped.id).expect("cancel"));
+
+        assert_eq!(
+            list_scheduled_messages(&conn, Some("chat-a")).expect("list"),
+            vec![later.clone()]
+        );
+        assert_eq!(
+            due_scheduled_messages(&conn, 150).expect("due"),
+            vec![soon.clone()]
+        );
+
+        finish_scheduled_message(&conn, soon.id, Ok("msg-1")).expect("sent");
+        finish_scheduled_message(&conn, later.id, Err("offline")).expect("failed");
+        assert!(due_scheduled_messages(&conn, 300).expect("due").is_empty());
+        assert!(!cancel_scheduled_message(&conn, soon.id).expect("already sent"));
+    }
+
+    #[test]
+    fn self_tasks_and_reminders() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let task = Message {
+            id: "t1".to_string(),
+            chat_id: "self".to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: 10,
+            content: MessageContent::Task {
+                text: "buy milk".to_string(),
+            },
+            status: "read".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+        insert_self_task(&conn, &task).expect("task");
+        assert!(set_task_done(&conn, "t1", true, 20).expect("done"));
+        assert!(get_self_tasks(&conn, false).expect("open").is_empty());
+        assert_eq!(
+            get_self_tasks(&conn, true).expect("all")[0].done_at,
+            Some(20)
+        );
+
+        let id = add_reminder(&conn, "t1", 100, 20)
+            .expect("add")
+            .expect("id");
+        assert_eq!(add_reminder(&conn, "missing", 100, 20).expect("add"), None);
+        assert!(take_due_reminders(&conn, 99).expect("early").is_empty());
+        let due = take_due_reminders(&conn, 150).expect("due");
+        assert_eq!((due[0].id, due[0].text.as_deref()), (id, Some("buy milk")));
+        assert!(take_due_reminders(&conn, 200).expect("again").is_empty());
+        assert!(!cancel_reminder(&conn, id).expect("fired"));
+    }
+
+    #[test]
+    fn media_export_restriction_applies_to_received_files_only() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        conn.execute(
+            "INSERT INTO messages (id, chat_id, peer_id, timestamp, content_type, text_content, file_hash, status)
+             VALUES ('in', 'peer-a', 'peer-a', 1, 'image', NULL, 'h-in', 'delivered'),
+                    ('out', 'peer-a', 'Me', 2, 'image', NULL, 'h-out', 'delivered')",
+            [],
+        )
+        .expect("insert messages");
+        assert!(!is_media_export_restricted(&conn, "h-in").expect("check"));
+
+        let mut settings = ChatSettings::defaults("peer-a");
+        settings.restrict_media_export = true;
+        upsert_chat_settings(&conn, &settings).expect("settings");
+        assert!(is_media_export_restricted(&conn, "h-in").expect("check"));
+        assert!(!is_media_export_restricted(&conn, "h-out").expect("check"));
+    }
+
+    #[test]
+    fn auto_replies_are_rate_limited_per_chat() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        assert!(claim_auto_reply(&conn, "peer-a", 100, 60).expect("claim"));
+        assert!(!claim_auto_reply(&conn, "peer-a", 159, 60).expect("claim"));
+        assert!(claim_auto_reply(&conn, "peer-b", 159, 60).expect("claim"));
+        assert!(claim_auto_reply(&conn, "peer-a", 160, 60).expect("claim"));
+
+        clear_auto_replies(&conn).expect("clear");
+        assert!(claim_auto_reply(&conn, "peer-a", 161, 60).expect("claim"));
+    }
+
+    #[test]
+    fn usage_metrics_accumulate_and_reset() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let mut batch = std::collections::BTreeMap::new();
+        batch.insert("messages_sent".to_string(), 2);
+        batch.insert("error.dm_outbound".to_string(), 1);
+        add_usage_metrics(&conn, &batch).expect("add");
+        add_usage_metrics(&conn, &batch).expect("add again");
+
+        let metrics = get_usage_metrics(&conn).expect("read");
+        assert!(metrics.since.is_some());
+        assert_eq!(metrics.counters.get("messages_sent"), Some(&4));
+        assert_eq!(metrics.errors.get("dm_outbound"), Some(&2));
+
+        reset_usage_metrics(&conn).expect("reset");
+        let metrics = get_usage_metrics(&conn).expect("read");
+        assert!(metrics.since.is_none() && metrics.counters.is_empty());
+    }
+
+    #[test]
+    fn connection_stats_increment_only_after_first_connect() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+
+        record_chat_connection_established(&conn, "peer-a", 10).expect("first connect");
+        let first = get_chat_connection_stats(&conn, "peer-a").expect("read first");
+        assert_eq!(first.first_connected_at, Some(10));
+        assert_eq!(first.last_connected_at, Some(10));
+        assert_eq!(first.reconnect_count, 0);
+
+        record_chat_connection_established(&conn, "peer-a", 20).expect("reconnect");
+        let second = get_chat_connection_stats(&conn, "peer-a").expect("read second");
+        assert_eq!(second.first_connected_at, Some(10));
+        assert_eq!(second.last_connected_at, Some(20));
+        assert_eq!(second.reconnect_count, 1);
+    }
+
+    #[test]
+    fn handshake_states_expire_by_age() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        for (peer_id, direction, created_at) in [("old", "incoming", 100), ("new", "outgoing", 500)]
+        {
+            save_handshake_state(
+                &conn,
+                &HandshakeState {
+                    peer_id: peer_id.to_string(),
+                    direction: direction.to_string(),
+                    created_at,
+                },
+            )
+            .expect("save");
+        }
+
+        assert_eq!(
+            expire_handshake_states(&conn, 300).expect("expire"),
+            vec!["old".to_string()]
+        );
+        let left = list_handshake_states(&conn).expect("list");
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].direction, "outgoing");
+
+        delete_handshake_state(&conn, "new").expect("delete");
+        assert!(list_handshake_states(&conn).expect("list").is_empty());
+    }
+
+    #[test]
+    fn peer_addresses_keep_the_most_recent() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        for i in 0..PEER_ADDRESSES_PER_PEER + 2 {
+            record_peer_address(&conn, "a", &format!("/ip4/10.0.0.{}/tcp/1", i), i)
+                .expect("record");
+        }
+        record_peer_address(&conn, "a", "/ip4/10.0.0.0/tcp/1", 100).expect("refresh");
+        record_peer_address(&conn, "b", "/ip4/10.0.1.1/tcp/1", 50).expect("record");
+
+        let rows = list_peer_addresses(&conn).expect("list");
+        let for_a: Vec<_> = rows.iter().filter(|(peer, _)| peer == "a").collect();
+        assert_eq!(for_a.len() as i64, PEER_ADDRESSES_PER_PEER);
+        assert!(!for_a.iter().any(|(_, addr)| addr == "/ip4/10.0.0.1/tcp/1"));
+        assert_eq!(
+            rows.last(),
+            Some(&("a".to_string(), "/ip4/10.0.0.0/tcp/1".to_string()))
+        );
+    }
+
+    #[test]
+    fn bridge_secrets_never_come_back_from_the_db() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        // A row written before secrets moved to the config.
+        conn.execute(
+            "INSERT INTO chat_bridges (chat_id, kind, endpoint, target, credential, enabled, created_at)
+             VALUES ('g', 'matrix', 'https://hs', '!room', 'token', 1, 1)",
+            [],
+        )
+        .expect("legacy row");
+
+        let bridge = get_chat_bridge(&conn, "g").expect("get").expect("bridge");
+        assert!(bridge.credential.is_empty());
+        assert_eq!(
+            legacy_bridge_credentials(&conn).expect("legacy"),
+            vec![("g".to_string(), "token".to_string())]
+        );
+
+        clear_bridge_credential(&conn, "g").expect("clear");
+        assert!(legacy_bridge_credentials(&conn).expect("legacy").is_empty());
+
+        upsert_chat_bridge(
+            &conn,
+            &ChatBridge {
+                chat_id: "i".to_string(),
+                kind: "irc".to_string(),
+                endpoint: "irc.example.org:6697".to_string(),
+                target: "#rchat".to_string(),
+                nick: "rchat-bot".to_string(),
+                credential: "ignored".to_string(),
+                enabled: true,
+                created_at: 2,
+            },
+        )
+        .expect("upsert");
+        let irc = get_chat_bridge(&conn, "i").expect("get").expect("bridge");
+        assert_eq!(irc.nick, "rchat-bot");
+        assert!(legacy_bridge_credentials(&conn).expect("legacy").is_empty());
+    }
+
+    #[test]
+    fn bridge_message_ids_map_both_ways() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        record_bridge_message(&conn, "g", "m1", "$ev1", "in", 1).expect("record");
+        record_bridge_message(&conn, "g", "m2", "$ev2", "out", 2).expect("record");
+        // Replays of the same remote event are ignored.
+        record_bridge_message(&conn, "g", "m3", "$ev1", "in", 3).expect("replay");
+
+        assert_eq!(
+            bridge_message_local_id(&conn, "g", "$ev1").expect("lookup"),
+            Some("m1".to_string())
+        );
+        assert_eq!(
+            bridge_message_local_id(&conn, "other", "$ev1").expect("lookup"),
+            None
+        );
+        assert!(is_inbound_bridge_message(&conn, "g", "m1"));
+        assert!(!is_inbound_bridge_message(&conn, "g", "m2"));
+        assert!(!is_inbound_bridge_message(&conn, "g", "m3"));
+    }
+
+    #[test]
+    fn bridge_nicks_stay_stable_and_unique() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        record_bridge_nick(&conn, "g", "bridge:irc:alice", "alice").expect("record");
+        assert_eq!(
+            bridge_nick_for_peer(&conn, "g", "peer-a", "alice").expect("assign"),
+            "alice2"
+        );
+        assert_eq!(
+            bridge_nick_for_peer(&conn, "g", "peer-a", "renamed").expect("again"),
+            "alice2"
+        );
+        assert_eq!(
+            bridge_nick_for_peer(&conn, "other", "peer-a", "alice").expect("other chat"),
+            "alice"
+        );
+        let nicks: Vec<String> = list_bridge_nicks(&conn, "g")
+            .expect("list")
+            .into_iter()
+            .map(|n| n.nick)
+            .collect();
+        assert_eq!(nicks, vec!["alice", "alice2"]);
+    }
+
+    #[test]
+    fn bandwidth_usage_accumulates_per_day() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+        let stat = |day: &str, sent, received| BandwidthStat {
+            day: day.to_string(),
+            peer_id: "peer".to_string(),
+            protocol: "direct_message".to_string(),
+            bytes_sent: sent,
+            bytes_received: received,
+        };
+        add_bandwidth_usage(&conn, &[stat("2026-09-30", 500, 0)]).expect("add");
+        add_bandwidth_usage(&conn, &[stat("2026-10-01", 10, 20)]).expect("add");
+        add_bandwidth_usage(&conn, &[stat("2026-10-01", 5, 0)]).expect("add");
+
+        assert_eq!(
+            list_bandwidth_stats(&conn, "2026-10-01").expect("list"),
+            vec![stat("2026-10-01", 15, 20)]
+        );
+        assert_eq!(
+            bandwidth_total_since(&conn, "2026-10-01").expect("total"),
+            35
+        );
+        assert_eq!(
+            bandwidth_total_since(&conn, "2026-01-01").expect("total"),
+            535
+        );
+    }
+
+    #[test]
+    fn migrates_legacy_github_chat_id_to_canonical_format() {
+        let mut conn = Connection::open_in_memory().expect("in-memory db");
+        create_tables(&conn).expect("schema");
+
+        let legacy_chat_id = "gh:professional-tester";
+        let peer_id = "12D3KooWLk1GoEB3MbHbRLHTxXrvNGSxC2UALaCuKAgKuYXkXazU";
+        let canonical_chat_id =
+            crate::chat_identity::build_github_chat_id("professional-tester", peer_id);
+
+        add_peer(
+            &conn,
+            legacy_chat_id,
+            Some("professional-tester"),
+            None,
+            "github",
+        )
+        .expect("legacy peer");
+        create_chat(&conn, legacy_chat_id, "professional-tester", false).expect("legacy chat");
+
+        let msg = Message {
+            id: "msg-1".to_string(),
+            chat_id: legacy_chat_id.to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: 1,
+            content: MessageContent::Text {
+                text: "hello".to_string(),
+            },
+            status: "delivered".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+        insert_message(&conn, &msg).expect("legacy message");
+
+        let mapping = std::collections::HashMap::from([(
+            "professional-tester".to_string(),
+            peer_id.to_string(),
+        )]);
+        migrate_legacy_github_chat_ids(&mut conn, &mapping).expect("migration");
+
+        assert!(!chat_exists(&conn, legacy_chat_id));
+        assert!(chat_exists(&conn, &canonical_chat_id));
+        assert!(is_peer(&conn, &canonical_chat_id));
+        let migrated_messages = get_messages(&conn, &canonical_chat_id).expect("messages");
+        assert_eq!(migrated_messages.len(), 1);
+        assert_eq!(migrated_messages[0].id, "msg-1");
+    }
+}
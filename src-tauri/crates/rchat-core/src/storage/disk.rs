@@ -0,0 +1,144 @@
+//! Free-space guard for chunk and database writes.
+//!
+//! The app's background monitor samples free space on the data volume. Below
+//! [`LOW_SPACE_BYTES`] the app enters degraded mode: new media is rejected with
+//! a `STORAGE_LOW` error, incoming media is no longer auto-downloaded and the UI
+//! gets a `storage-low` event suggesting `run_storage_gc`. Text messages are
+//! still stored until free space drops under [`CRITICAL_SPACE_BYTES`].
+
+use directories::ProjectDirs;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+pub const LOW_SPACE_BYTES: u64 = 512 * 1024 * 1024;
+/// Leave degraded mode only with some headroom, so we don't flap at the edge.
+pub const RECOVERED_SPACE_BYTES: u64 = 768 * 1024 * 1024;
+pub const CRITICAL_SPACE_BYTES: u64 = 64 * 1024 * 1024;
+pub const STORAGE_LOW_CODE: &str = "STORAGE_LOW";
+/// How often the app's monitor samples free space.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+static DEGRADED: AtomicBool = AtomicBool::new(false);
+/// Last sampled free bytes; `u64::MAX` until the first sample.
+static AVAILABLE_BYTES: AtomicU64 = AtomicU64::new(u64::MAX);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStatus {
+    pub degraded: bool,
+    pub available_bytes: Option<u64>,
+    pub threshold_bytes: u64,
+    pub suggestion: Option<String>,
+}
+
+fn data_dir() -> Option<PathBuf> {
+    ProjectDirs::from("io.github", "ata-sesli", "RChat").map(|d| d.data_dir().to_path_buf())
+}
+
+/// Degraded-mode transition for a new sample, with hysteresis.
+pub fn next_degraded(degraded: bool, available: u64) -> bool {
+    if degraded {
+        available < RECOVERED_SPACE_BYTES
+    } else {
+        available < LOW_SPACE_BYTES
+    }
+}
+
+pub fn is_degraded() -> bool {
+    DEGRADED.load(Ordering::SeqCst)
+}
+
+pub fn status() -> StorageStatus {
+    let degraded = is_degraded();
+    let available = AVAILABLE_BYTES.load(Ordering::SeqCst);
+    StorageStatus {
+        degraded,
+        available_bytes: (available != u64::MAX).then_some(available),
+        threshold_bytes: LOW_SPACE_BYTES,
+        suggestion: degraded.then(|| {
+            "Free up disk space: run storage cleanup (run_storage_gc) or delete old media"
+                .to_string()
+        }),
+    }
+}
+
+/// A write refused for lack of disk space; the app reports it as
+/// [`STORAGE_LOW_CODE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageLow(&'static str);
+
+impl std::fmt::Display for StorageLow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for StorageLow {}
+
+impl From<StorageLow> for String {
+    fn from(error: StorageLow) -> Self {
+        error.to_string()
+    }
+}
+
+/// Gate for chunk writes (outgoing media and incoming transfers).
+pub fn ensure_media_writable() -> Result<(), StorageLow> {
+    if is_degraded() {
+        return Err(StorageLow(
+            "Not enough free disk space for new media. Run storage cleanup or free up space.",
+        ));
+    }
+    Ok(())
+}
+
+/// Gate for message rows; only refuses when the disk is nearly full.
+pub fn ensure_db_writable() -> Result<(), StorageLow> {
+    if AVAILABLE_BYTES.load(Ordering::SeqCst) < CRITICAL_SPACE_BYTES {
+        return Err(StorageLow("Disk is almost full; message was not saved."));
+    }
+    Ok(())
+}
+
+/// Sample free space now; returns the new mode if it changed.
+pub fn refresh() -> Option<bool> {
+    let dir = data_dir()?;
+    let probe = if dir.exists() {
+        dir
+    } else {
+        std::env::temp_dir()
+    };
+    let available = match fs2::available_space(&probe) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!(
+                "[Storage] ⚠️ Failed to read free space for {:?}: {}",
+                probe, e
+            );
+            return None;
+        }
+    };
+    AVAILABLE_BYTES.store(available, Ordering::SeqCst);
+
+    let was = is_degraded();
+    let now = next_degraded(was, available);
+    if now == was {
+        return None;
+    }
+    DEGRADED.store(now, Ordering::SeqCst);
+    Some(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degraded_mode_uses_hysteresis() {
+        assert!(!next_degraded(false, LOW_SPACE_BYTES));
+        assert!(next_degraded(false, LOW_SPACE_BYTES - 1));
+        // Stays degraded until there is real headroom again.
+        assert!(next_degraded(true, LOW_SPACE_BYTES + 1));
+        assert!(!next_degraded(true, RECOVERED_SPACE_BYTES));
+    }
+}
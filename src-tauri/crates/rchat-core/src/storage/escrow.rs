@@ -0,0 +1,112 @@
+//! Recovery-phrase escrow of the vault key.
+//!
+//! The MEK normally only comes out of `rchat.keystore` with the vault
+//! password. When the user opts in, a 24-word BIP39 phrase is generated and a
+//! second copy of the MEK is sealed under an Argon2 key derived from the
+//! phrase's entropy (`rchat.recovery`). The phrase itself is shown once and
+//! never stored. With it, `ConfigManager::recover` can open the config and
+//! rebuild the keystore under a new password.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bip39::Mnemonic;
+use rand::RngCore;
+use rvault_core::crypto;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+/// 32 bytes of entropy, i.e. 24 words.
+const PHRASE_ENTROPY_BYTES: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EscrowFile {
+    salt: String,
+    ciphertext: String,
+    nonce: String,
+}
+
+pub fn escrow_path(app_dir: &Path) -> PathBuf {
+    app_dir.join("rchat.recovery")
+}
+
+/// A fresh phrase, space separated.
+pub fn generate_phrase() -> String {
+    let mut entropy = [0u8; PHRASE_ENTROPY_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut entropy);
+    let phrase = Mnemonic::from_entropy(&entropy)
+        .expect("32 bytes is a valid BIP39 entropy length")
+        .to_string();
+    entropy.zeroize();
+    phrase
+}
+
+/// Entropy of a typed phrase; case and extra whitespace are ignored, the
+/// checksum word is verified.
+fn phrase_entropy(phrase: &str) -> Result<Vec<u8>> {
+    let normalized = phrase
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mnemonic = Mnemonic::parse_normalized(&normalized)
+        .map_err(|e| anyhow!("Invalid recovery phrase: {}", e))?;
+    Ok(mnemonic.to_entropy())
+}
+
+pub fn seal(phrase: &str, mek: &[u8; 32]) -> Result<EscrowFile> {
+    let mut entropy = phrase_entropy(phrase)?;
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let key =
+        crypto::derive_key(&entropy, &salt).map_err(|e| anyhow!("Key derivation failed: {}", e));
+    entropy.zeroize();
+    let key = key?;
+
+    // The cipher takes text, so the MEK travels base64 encoded.
+    let (ciphertext, nonce) = crypto::encrypt_with_key(&key, BASE64.encode(mek).as_bytes())
+        .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+    Ok(EscrowFile {
+        salt: BASE64.encode(salt),
+        ciphertext,
+        nonce,
+    })
+}
+
+pub fn open(phrase: &str, file: &EscrowFile) -> Result<[u8; 32]> {
+    let salt: [u8; 16] = BASE64
+        .decode(&file.salt)?
+        .try_into()
+        .map_err(|_| anyhow!("Salt must be 16 bytes"))?;
+    let mut entropy = phrase_entropy(phrase)?;
+    let key =
+        crypto::derive_key(&entropy, &salt).map_err(|e| anyhow!("Key derivation failed: {}", e));
+    entropy.zeroize();
+    let key = key?;
+
+    let plain = crypto::decrypt_with_key(&key, &file.ciphertext, &file.nonce)
+        .map_err(|_| anyhow!("Recovery phrase does not match this vault"))?;
+    BASE64
+        .decode(plain.trim())?
+        .try_into()
+        .map_err(|_| anyhow!("Escrowed key must be 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrase_unseals_only_its_own_escrow() {
+        let phrase = generate_phrase();
+        assert_eq!(phrase.split(' ').count(), 24);
+
+        let mek = [7u8; 32];
+        let sealed = seal(&phrase, &mek).expect("seal");
+        let sloppy = format!("  {}\n", phrase.to_uppercase().replace(' ', "   "));
+        assert_eq!(open(&sloppy, &sealed).expect("open"), mek);
+
+        assert!(open(&generate_phrase(), &sealed).is_err());
+        assert!(open("not a recovery phrase", &sealed).is_err());
+    }
+}
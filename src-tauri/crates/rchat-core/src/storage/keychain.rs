@@ -0,0 +1,131 @@
+//! Opt-in unlock through the OS keychain (Keychain, Credential Manager,
+//! Secret Service).
+//!
+//! Instead of an rvault session file, the MEK is kept in the keychain under
+//! the app data directory's fingerprint. It is wrapped with a key derived
+//! from the vault's password hash, so an entry left behind by a reset vault
+//! or a recovered password simply fails to open and the user is asked for the
+//! password again. Mobile targets have no backend; there the mode is off.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+const SERVICE: &str = "rchat";
+
+#[derive(Serialize, Deserialize)]
+struct WrappedKey {
+    ciphertext: String,
+    nonce: String,
+}
+
+/// One entry per data directory, so separate profiles do not clash.
+#[cfg_attr(any(target_os = "android", target_os = "ios"), allow(dead_code))]
+fn account(app_dir: &Path) -> String {
+    let digest = Sha256::digest(app_dir.to_string_lossy().as_bytes());
+    format!("vault-{}", &hex::encode(digest)[..16])
+}
+
+fn wrapping_key(master_hash: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rchat-keychain-v1");
+    hasher.update(master_hash.as_bytes());
+    hasher.finalize().into()
+}
+
+fn wrap(key: &[u8; 32], master_hash: &str) -> Result<String> {
+    let (ciphertext, nonce) = rvault_core::crypto::encrypt_with_key(
+        &wrapping_key(master_hash),
+        BASE64.encode(key).as_bytes(),
+    )
+    .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+    Ok(serde_json::to_string(&WrappedKey { ciphertext, nonce })?)
+}
+
+fn unwrap(secret: &str, master_hash: &str) -> Result<[u8; 32]> {
+    let wrapped: WrappedKey = serde_json::from_str(secret)?;
+    let plain = rvault_core::crypto::decrypt_with_key(
+        &wrapping_key(master_hash),
+        &wrapped.ciphertext,
+        &wrapped.nonce,
+    )
+    .map_err(|_| anyhow!("Keychain entry belongs to another vault"))?;
+    BASE64
+        .decode(plain.trim())?
+        .try_into()
+        .map_err(|_| anyhow!("Keychain entry must hold 32 bytes"))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn entry(app_dir: &Path) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, &account(app_dir)).map_err(|e| anyhow!("Keychain: {}", e))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn store(app_dir: &Path, key: &[u8; 32], master_hash: &str) -> Result<()> {
+    entry(app_dir)?
+        .set_password(&wrap(key, master_hash)?)
+        .map_err(|e| anyhow!("Keychain: {}", e))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn load(app_dir: &Path, master_hash: &str) -> Result<Option<[u8; 32]>> {
+    match entry(app_dir)?.get_password() {
+        Ok(secret) => unwrap(&secret, master_hash).map(Some),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!("Keychain: {}", e)),
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn is_enabled(app_dir: &Path) -> bool {
+    entry(app_dir).is_ok_and(|entry| entry.get_password().is_ok())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn clear(app_dir: &Path) -> Result<()> {
+    match entry(app_dir)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(anyhow!("Keychain: {}", e)),
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+pub fn store(_app_dir: &Path, _key: &[u8; 32], _master_hash: &str) -> Result<()> {
+    Err(anyhow!("Keychain unlock is not supported on this platform"))
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+pub fn load(_app_dir: &Path, _master_hash: &str) -> Result<Option<[u8; 32]>> {
+    Ok(None)
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+pub fn is_enabled(_app_dir: &Path) -> bool {
+    false
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+pub fn clear(_app_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_key_only_opens_for_same_password_hash() {
+        let key = [9u8; 32];
+        let secret = wrap(&key, "$argon2id$hash-a").expect("wrap");
+        assert_eq!(unwrap(&secret, "$argon2id$hash-a").expect("unwrap"), key);
+        assert!(unwrap(&secret, "$argon2id$hash-b").is_err());
+        assert_ne!(
+            account(Path::new("/home/a/rchat")),
+            account(Path::new("/home/b/rchat"))
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Versioned schema migrations.
+//!
+//! `schema_migrations` records every migration applied to the database. At
+//! startup [`run`] applies the missing ones from [`MIGRATIONS`] in order, each
+//! in its own transaction together with its `schema_migrations` row, so a
+//! failing migration leaves the database at the previous version and startup
+//! fails with the migration's number and name. Migration 1 is the schema from
+//! before versioning; it is idempotent, so unversioned databases of any age
+//! start there. New schema changes get a new entry at the end of the list;
+//! applied migrations are never edited.
+
+use anyhow::Context;
+use rusqlite::Connection;
+use serde::Serialize;
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub up: fn(&Connection) -> anyhow::Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        up: super::db::baseline_schema,
+    },
+    Migration {
+        version: 2,
+        name: "files_last_accessed_at",
+        // Last read, for evicting least recently used cached media.
+        up: |conn| add_column_if_missing(conn, "files", "last_accessed_at", "INTEGER"),
+    },
+    Migration {
+        version: 3,
+        name: "chat_settings_auto_download",
+        // Per-chat media auto-download overrides (JSON {media type: policy}).
+        up: |conn| add_column_if_missing(conn, "chat_settings", "auto_download", "TEXT"),
+    },
+    Migration {
+        version: 4,
+        name: "envelope_order_and_nesting",
+        // Manual envelope order and nesting under a parent envelope.
+        up: |conn| {
+            add_column_if_missing(
+                conn,
+                "envelopes",
+                "sort_order",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+            add_column_if_missing(conn, "envelopes", "parent_id", "TEXT")?;
+            // Keep the order envelopes happened to be listed in so far.
+            conn.execute("UPDATE envelopes SET sort_order = rowid", [])?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 5,
+        name: "chat_envelopes_many_to_many",
+        // A chat can be tagged with several envelopes.
+        up: chat_envelopes_many_to_many,
+    },
+    Migration {
+        version: 6,
+        name: "messages_chat_timestamp_id",
+        // New message ids are UUIDv7 and sort by time; they break timestamp
+        // ties when listing a chat. Existing ids are kept as they are.
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_messages_chat_timestamp_id
+                     ON messages(chat_id, timestamp, id)",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 7,
+        name: "messages_logical_clock",
+        // Per-chat Lamport clock for ordering across devices with skewed
+        // clocks. Existing messages are numbered in the order they were shown.
+        up: messages_logical_clock,
+    },
+    Migration {
+        version: 8,
+        name: "group_keys",
+        // Group chat keys by epoch and the admin that made them; older
+        // epochs open envelopes sent before a rekey.
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS group_keys (
+                     group_id TEXT NOT NULL,
+                     epoch INTEGER NOT NULL,
+                     key BLOB NOT NULL,
+                     admin_id TEXT NOT NULL,
+                     created_at INTEGER NOT NULL,
+                     PRIMARY KEY (group_id, epoch)
+                 )",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 9,
+        name: "chats_avatar_hash",
+        // Group avatars set by an admin (storage::object file hash).
+        up: |conn| add_column_if_missing(conn, "chats", "avatar_hash", "TEXT"),
+    },
+    Migration {
+        version: 10,
+        name: "chat_settings_history_share_days",
+        // Days of group history an admin shares with new members; NULL shares none.
+        up: |conn| add_column_if_missing(conn, "chat_settings", "history_share_days", "INTEGER"),
+    },
+    Migration {
+        version: 11,
+        name: "bridge_messages",
+        // Bridged message ids: rchat message <-> remote event ('in' or 'out'),
+        // so bridges skip duplicates and don't echo their own posts.
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS bridge_messages (
+                     chat_id TEXT NOT NULL,
+                     local_id TEXT NOT NULL,
+                     remote_id TEXT NOT NULL,
+                     direction TEXT NOT NULL,
+                     created_at INTEGER NOT NULL,
+                     PRIMARY KEY (chat_id, local_id),
+                     UNIQUE (chat_id, remote_id)
+                 )",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 12,
+        name: "bridge_nicks",
+        // Nicknames on a bridge's remote side, for rchat peers and remote users.
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS bridge_nicks (
+                     chat_id TEXT NOT NULL,
+                     peer_id TEXT NOT NULL,
+                     nick TEXT NOT NULL,
+                     PRIMARY KEY (chat_id, peer_id),
+                     UNIQUE (chat_id, nick)
+                 )",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 13,
+        name: "chat_bridges_nick",
+        // IRC bridges kept their nick in `credential`; it gets its own column.
+        // The secrets other bridges kept there move to the encrypted config
+        // once the vault is open (`bridge::move_legacy_credentials`).
+        up: |conn| {
+            add_column_if_missing(conn, "chat_bridges", "nick", "TEXT NOT NULL DEFAULT ''")?;
+            conn.execute(
+                "UPDATE chat_bridges SET nick = credential, credential = '' WHERE kind = 'irc'",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 14,
+        name: "messages_canonical_content_type",
+        // `content_type` holds `MessageContent::content_type()`; early builds
+        // stored photos as `photo`.
+        up: |conn| {
+            conn.execute(
+                "UPDATE messages SET content_type = 'image' WHERE content_type = 'photo'",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+];
+
+/// Rebuild `chat_envelopes` keyed by (chat, envelope) instead of by chat.
+fn chat_envelopes_many_to_many(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE chat_envelopes_v5 (
+             chat_id TEXT NOT NULL,
+             envelope_id TEXT NOT NULL,
+             PRIMARY KEY (chat_id, envelope_id),
+             FOREIGN KEY (envelope_id) REFERENCES envelopes(id) ON DELETE CASCADE
+         );
+         INSERT INTO chat_envelopes_v5 (chat_id, envelope_id)
+             SELECT chat_id, envelope_id FROM chat_envelopes;
+         DROP TABLE chat_envelopes;
+         ALTER TABLE chat_envelopes_v5 RENAME TO chat_envelopes;
+         CREATE INDEX IF NOT EXISTS idx_chat_envelopes_envelope
+             ON chat_envelopes(envelope_id);",
+    )?;
+    Ok(())
+}
+
+fn messages_logical_clock(conn: &Connection) -> anyhow::Result<()> {
+    add_column_if_missing(conn, "messages", "logical", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute_batch(
+        "UPDATE messages SET logical = numbered.n
+         FROM (
+             SELECT id, ROW_NUMBER() OVER (PARTITION BY chat_id ORDER BY timestamp, id) AS n
+             FROM messages
+         ) AS numbered
+         WHERE numbered.id = messages.id;
+         CREATE INDEX IF NOT EXISTS idx_messages_chat_logical
+             ON messages(chat_id, logical, timestamp);",
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub applied_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaVersion {
+    pub version: u32,
+    /// Newest migration this build knows.
+    pub latest: u32,
+    pub applied: Vec<AppliedMigration>,
+}
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// `ALTER TABLE ... ADD COLUMN` unless `table` already has `column`, so
+/// migrations can run against databases that gained it before versioning.
+pub fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> anyhow::Result<()> {
+    let exists: bool = conn.query_row(
+        &format!(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1)",
+            table
+        ),
+        [column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )
+        .with_context(|| format!("Failed to add column {}.{}", table, column))?;
+    }
+    Ok(())
+}
+
+fn ensure_migrations_table(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER NOT NULL PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
+            )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn current_version(conn: &Connection) -> anyhow::Result<u32> {
+    ensure_migrations_table(conn)?;
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// Apply pending migrations. Returns the resulting schema version.
+pub fn run(conn: &Connection) -> anyhow::Result<u32> {
+    run_migrations(conn, MIGRATIONS)
+}
+
+fn run_migrations(conn: &Connection, migrations: &[Migration]) -> anyhow::Result<u32> {
+    let mut version = current_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if version > latest {
+        anyhow::bail!(
+            "Database schema version {} is newer than this app supports ({}); update RChat",
+            version,
+            latest
+        );
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)
+            .and_then(|()| {
+                tx.execute(
+                    "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![migration.version, migration.name, now],
+                )?;
+                Ok(())
+            })
+            .and_then(|()| Ok(tx.commit()?))
+            .with_context(|| {
+                format!(
+                    "Database migration {} ({}) failed; the database was left at version {}",
+                    migration.version, migration.name, version
+                )
+            })?;
+        println!(
+            "[DB] ✅ Applied migration {} ({})",
+            migration.version, migration.name
+        );
+        version = migration.version;
+    }
+    Ok(version)
+}
+
+pub fn schema_version(conn: &Connection) -> anyhow::Result<SchemaVersion> {
+    let version = current_version(conn)?;
+    let mut stmt =
+        conn.prepare("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")?;
+    let applied = stmt
+        .query_map([], |row| {
+            Ok(AppliedMigration {
+                version: row.get(0)?,
+                name: row.get(1)?,
+                applied_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(SchemaVersion {
+        version,
+        latest: latest_version(),
+        applied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_pending_migrations_once_and_stops_at_a_failure() {
+        let conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                name: "create",
+                up: |conn| Ok(conn.execute_batch("CREATE TABLE t (a INTEGER)")?),
+            },
+            Migration {
+                version: 2,
+                name: "add_b",
+                up: |conn| add_column_if_missing(conn, "t", "b", "TEXT"),
+            },
+        ];
+        assert_eq!(run_migrations(&conn, &migrations).unwrap(), 2);
+        assert_eq!(run_migrations(&conn, &migrations).unwrap(), 2);
+
+        let broken = [Migration {
+            version: 3,
+            name: "half_done",
+            up: |conn| {
+                conn.execute_batch("CREATE TABLE u (a INTEGER)")?;
+                anyhow::bail!("boom")
+            },
+        }];
+        let all: Vec<Migration> = migrations.into_iter().chain(broken).collect();
+        let err = run_migrations(&conn, &all).unwrap_err();
+        assert!(format!("{:#}", err).contains("migration 3 (half_done)"));
+        assert_eq!(current_version(&conn).unwrap(), 2);
+        // Rolled back together with the version row.
+        assert!(conn.prepare("SELECT * FROM u").is_err());
+
+        assert!(run_migrations(&conn, &all[..1]).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+pub mod bandwidth;
+pub mod calendar;
+pub mod config;
+pub mod config_migration;
+pub mod db;
+pub mod disk;
+pub mod escrow;
+pub mod keychain;
+pub mod migrations;
+pub mod object;
+pub mod recovery;
+pub mod theme;
@@ -0,0 +1,521 @@
+//! Object storage with FastCDC content-defined chunking.
+//!
+//! This module provides functions to store, load, and delete objects (files)
+//! using content-defined chunking for deduplication.
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use fastcdc::v2020::FastCDC;
+
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+
+// Chunk size parameters (in bytes)
+const MIN_CHUNK_SIZE: u32 = 2 * 1024; // 2 KB
+const AVG_CHUNK_SIZE: u32 = 8 * 1024; // 8 KB
+const MAX_CHUNK_SIZE: u32 = 64 * 1024; // 64 KB
+
+/// Get the chunks directory path.
+pub fn get_chunks_dir(root_dir: Option<PathBuf>) -> Result<PathBuf> {
+    let base_dir = if let Some(d) = root_dir {
+        d
+    } else {
+        let project_dirs = ProjectDirs::from("io.github", "ata-sesli", "RChat")
+            .context("Failed to determine project directories")?;
+        project_dirs.data_dir().to_path_buf()
+    };
+
+    let chunks_dir = base_dir.join("chunks");
+    fs::create_dir_all(&chunks_dir).context("Failed to create chunks directory")?;
+    Ok(chunks_dir)
+}
+
+/// Calculate SHA256 hash and return as hex string.
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    let result = hasher.finalize();
+    hex::encode(result)
+}
+
+/// Store an object (file) using content-defined chunking.
+///
+/// Returns the file hash (SHA256 of the complete file).
+pub fn create(
+    conn: &Connection,
+    data: &[u8],
+    file_name: Option<&str>,
+    mime_type: Option<&str>,
+    root_dir: Option<PathBuf>,
+) -> Result<String> {
+    let file_hash = sha256_hex(data);
+    let size_bytes = data.len() as i64;
+
+    // Check if file already exists
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM files WHERE file_hash = ?1)",
+        [&file_hash],
+        |row| row.get(0),
+    )?;
+
+    if exists {
+        return Ok(file_hash);
+    }
+
+    crate::storage::disk::ensure_media_writable()?;
+
+    let chunks_dir = get_chunks_dir(root_dir)?;
+
+    // Chunk the data using FastCDC
+    let chunker = FastCDC::new(data, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE);
+    let mut chunk_order: i64 = 0;
+    let mut chunk_records: Vec<(String, i64, i64)> = Vec::new(); // (chunk_hash, chunk_order, chunk_size)
+
+    for chunk in chunker {
+        let chunk_data = &data[chunk.offset..chunk.offset + chunk.length];
+        let chunk_hash = sha256_hex(chunk_data);
+        let chunk_size = chunk.length as i64;
+
+        // Store chunk to disk if it doesn't exist (deduplication)
+        let chunk_path = chunks_dir.join(&chunk_hash);
+        if !chunk_path.exists() {
+            crate::storage::recovery::write_atomic(&chunk_path, chunk_data)
+                .with_context(|| format!("Failed to write chunk {}", chunk_hash))?;
+        }
+
+        chunk_records.push((chunk_hash, chunk_order, chunk_size));
+        chunk_order += 1;
+    }
+
+    // Begin transaction
+    let tx = conn.unchecked_transaction()?;
+
+    // Insert into files table
+    tx.execute(
+        "INSERT INTO files (file_hash, file_name, mime_type, size_bytes, is_complete) VALUES (?1, ?2, ?3, ?4, 1)",
+        (
+            &file_hash,
+            file_name,
+            mime_type,
+            size_bytes,
+        ),
+    )?;
+
+    // Insert into file_chunks table
+    for (chunk_hash, order, size) in &chunk_records {
+        tx.execute(
+            "INSERT INTO file_chunks (file_hash, chunk_order, chunk_hash, chunk_size) VALUES (?1, ?2, ?3, ?4)",
+            (&file_hash, order, chunk_hash, size),
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(file_hash)
+}
+
+/// Record a read for the media cache's LRU order (see `storage::quota`).
+fn touch(conn: &Connection, file_hash: &str) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let _ = conn.execute(
+        "UPDATE files SET last_accessed_at = ?2 WHERE file_hash = ?1",
+        rusqlite::params![file_hash, now],
+    );
+}
+
+/// Load an object (file) by reassembling its chunks.
+///
+/// Returns the complete file data.
+pub fn load(conn: &Connection, file_hash: &str, root_dir: Option<PathBuf>) -> Result<Vec<u8>> {
+    // Verify file exists
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM files WHERE file_hash = ?1)",
+        [file_hash],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        anyhow::bail!("File not found: {}", file_hash);
+    }
+    touch(conn, file_hash);
+
+    let chunks_dir = get_chunks_dir(root_dir)?;
+
+    // Get chunks in order
+    let mut stmt = conn.prepare(
+        "SELECT chunk_hash FROM file_chunks WHERE file_hash = ?1 ORDER BY chunk_order ASC",
+    )?;
+
+    let chunk_hashes: Vec<String> = stmt
+        .query_map([file_hash], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Read and concatenate chunks
+    let mut result = Vec::new();
+    for chunk_hash in chunk_hashes {
+        let chunk_path = chunks_dir.join(&chunk_hash);
+        let chunk_data = fs::read(&chunk_path)
+            .with_context(|| format!("Failed to read chunk {}", chunk_hash))?;
+        result.extend_from_slice(&chunk_data);
+    }
+
+    Ok(result)
+}
+
+/// Load `len` bytes starting at `offset`, reading only the chunks that
+/// overlap the range. Works on incomplete objects as long as those chunks have
+/// arrived; the result is shorter than `len` at the end of the object.
+pub fn load_range(
+    conn: &Connection,
+    file_hash: &str,
+    offset: u64,
+    len: u64,
+    root_dir: Option<PathBuf>,
+) -> Result<Vec<u8>> {
+    touch(conn, file_hash);
+    let chunks_dir = get_chunks_dir(root_dir)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT chunk_hash, chunk_size FROM file_chunks WHERE file_hash = ?1 ORDER BY chunk_order ASC",
+    )?;
+    let chunks: Vec<(String, i64)> = stmt
+        .query_map([file_hash], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if chunks.is_empty() {
+        anyhow::bail!("File not found: {}", file_hash);
+    }
+
+    let end = offset.saturating_add(len);
+    let mut result = Vec::new();
+    let mut chunk_start: u64 = 0;
+    for (chunk_hash, chunk_size) in chunks {
+        let chunk_end = chunk_start + chunk_size.max(0) as u64;
+        if chunk_end > offset && chunk_start < end {
+            let chunk_data = fs::read(chunks_dir.join(&chunk_hash))
+                .with_context(|| format!("Chunk {} not available yet", chunk_hash))?;
+            let from = offset.saturating_sub(chunk_start) as usize;
+            let to = (end.min(chunk_end) - chunk_start) as usize;
+            result.extend_from_slice(
+                &chunk_data[from.min(chunk_data.len())..to.min(chunk_data.len())],
+            );
+        }
+        if chunk_end >= end {
+            break;
+        }
+        chunk_start = chunk_end;
+    }
+
+    Ok(result)
+}
+
+/// Stored attributes of an object, as recorded in the `files` table.
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub size_bytes: i64,
+    pub is_complete: bool,
+}
+
+/// Look up an object's name, MIME type and completeness without loading its chunks.
+pub fn info(conn: &Connection, file_hash: &str) -> Result<Option<ObjectInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT file_name, mime_type, COALESCE(size_bytes, 0), COALESCE(is_complete, 0)
+         FROM files WHERE file_hash = ?1",
+    )?;
+    let mut rows = stmt.query([file_hash])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(ObjectInfo {
+            file_name: row.get(0)?,
+            mime_type: row.get(1)?,
+            size_bytes: row.get(2)?,
+            is_complete: row.get(3)?,
+        })),
+        None => Ok(None),
+    }
+}
+
+/// Result of [`collect_garbage`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct GcReport {
+    pub removed_chunks: usize,
+    pub freed_bytes: u64,
+}
+
+/// Remove chunk files on disk that no `file_chunks` row references anymore.
+pub fn collect_garbage(conn: &Connection, root_dir: Option<PathBuf>) -> Result<GcReport> {
+    let chunks_dir = get_chunks_dir(root_dir)?;
+    let mut stmt = conn.prepare("SELECT DISTINCT chunk_hash FROM file_chunks")?;
+    let referenced: std::collections::HashSet<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    let mut report = GcReport::default();
+    for entry in fs::read_dir(&chunks_dir).context("Failed to list chunks directory")? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        // `.tmp` files may be writes in progress; startup recovery handles them.
+        if referenced.contains(&name) || name.ends_with(".tmp") || !entry.file_type()?.is_file() {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        fs::remove_file(entry.path())
+            .with_context(|| format!("Failed to remove orphaned chunk {}", name))?;
+        report.removed_chunks += 1;
+        report.freed_bytes += size;
+    }
+    Ok(report)
+}
+
+/// Result of [`repair_chunks`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ChunkRepairReport {
+    pub temp_files_removed: usize,
+    pub torn_chunks_removed: usize,
+    pub files_marked_incomplete: usize,
+}
+
+/// Startup pass over the chunk store: drop leftover temp files from
+/// interrupted writes and chunks whose size disagrees with the manifest. Files
+/// that lost a chunk are marked incomplete so the transfer fetches it again.
+pub fn repair_chunks(conn: &Connection, root_dir: Option<PathBuf>) -> Result<ChunkRepairReport> {
+    let chunks_dir = get_chunks_dir(root_dir)?;
+    let mut report = ChunkRepairReport::default();
+
+    for entry in fs::read_dir(&chunks_dir).context("Failed to list chunks directory")? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "tmp") && fs::remove_file(&path).is_ok() {
+            report.temp_files_removed += 1;
+        }
+    }
+
+    let mut stmt = conn.prepare("SELECT DISTINCT chunk_hash, chunk_size FROM file_chunks")?;
+    let chunks: Vec<(String, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    for (chunk_hash, chunk_size) in chunks {
+        let path = chunks_dir.join(&chunk_hash);
+        let Ok(meta) = fs::metadata(&path) else {
+            continue;
+        };
+        if meta.len() == chunk_size as u64 {
+            continue;
+        }
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove torn chunk {}", chunk_hash))?;
+        report.torn_chunks_removed += 1;
+        report.files_marked_incomplete += conn.execute(
+            "UPDATE files SET is_complete = 0
+             WHERE is_complete = 1
+               AND file_hash IN (SELECT file_hash FROM file_chunks WHERE chunk_hash = ?1)",
+            [&chunk_hash],
+        )?;
+    }
+    Ok(report)
+}
+
+/// Delete an object (file) from the database.
+///
+/// Note: Chunks are NOT deleted from disk to avoid race conditions with deduplication.
+/// A separate garbage collection process can clean up orphaned chunks.
+pub fn delete(conn: &Connection, file_hash: &str) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    // Delete from file_chunks first (foreign key constraint)
+    tx.execute("DELETE FROM file_chunks WHERE file_hash = ?1", [file_hash])?;
+
+    // Delete from files
+    let rows_deleted = tx.execute("DELETE FROM files WHERE file_hash = ?1", [file_hash])?;
+
+    tx.commit()?;
+
+    if rows_deleted == 0 {
+        anyhow::bail!("File not found: {}", file_hash);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use tempfile::tempdir;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Create tables
+        conn.execute(
+            "CREATE TABLE files (
+                file_hash TEXT PRIMARY KEY,
+                file_name TEXT,
+                mime_type TEXT,
+                size_bytes INTEGER,
+                is_complete BOOLEAN DEFAULT 0
+            )",
+            [],
+        )
+        .unwrap();
+
+        conn.execute(
+            "CREATE TABLE file_chunks (
+                file_hash TEXT NOT NULL,
+                chunk_order INTEGER NOT NULL,
+                chunk_hash TEXT NOT NULL,
+                chunk_size INTEGER NOT NULL,
+                PRIMARY KEY (file_hash, chunk_order),
+                FOREIGN KEY (file_hash) REFERENCES files(file_hash)
+            )",
+            [],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn test_create_and_load() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        // Create test data (larger than chunk size to ensure multiple chunks)
+        let test_data: Vec<u8> = (0..100_000).map(|i| (i % 256) as u8).collect();
+
+        // Create object
+        let file_hash = create(
+            &conn,
+            &test_data,
+            Some("test.bin"),
+            Some("application/octet-stream"),
+            root.clone(),
+        )
+        .expect("Failed to create object");
+
+        // Load object
+        let loaded_data = load(&conn, &file_hash, root).expect("Failed to load object");
+
+        // Verify
+        assert_eq!(test_data, loaded_data);
+    }
+
+    #[test]
+    fn test_load_range_spans_chunks() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        let test_data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let file_hash = create(&conn, &test_data, None, None, root.clone()).unwrap();
+
+        let range = load_range(&conn, &file_hash, 30_000, 40_000, root.clone()).unwrap();
+        assert_eq!(range, test_data[30_000..70_000]);
+
+        // Clamped at the end of the object.
+        let tail = load_range(&conn, &file_hash, 99_990, 1_000, root.clone()).unwrap();
+        assert_eq!(tail, test_data[99_990..]);
+        assert!(load_range(&conn, &file_hash, 200_000, 10, root.clone())
+            .unwrap()
+            .is_empty());
+        assert!(load_range(&conn, "missing", 0, 10, root).is_err());
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        let test_data = b"Hello, World! This is a test file.".to_vec();
+
+        // Create same object twice
+        let hash1 = create(&conn, &test_data, Some("file1.txt"), None, root.clone()).unwrap();
+        let hash2 = create(&conn, &test_data, Some("file2.txt"), None, root).unwrap();
+
+        // Hashes should be identical
+        assert_eq!(hash1, hash2);
+
+        // Only one file record should exist
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_delete() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        let test_data = b"Data to be deleted".to_vec();
+
+        let file_hash = create(&conn, &test_data, None, None, root.clone()).unwrap();
+
+        // Verify exists
+        assert!(load(&conn, &file_hash, root.clone()).is_ok());
+
+        // Delete
+        delete(&conn, &file_hash).unwrap();
+
+        // Verify load fails
+        assert!(load(&conn, &file_hash, root).is_err());
+    }
+
+    #[test]
+    fn test_collect_garbage_removes_only_orphaned_chunks() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        let kept = create(&conn, b"keep me around", None, None, root.clone()).unwrap();
+        let dropped = create(&conn, b"this one goes away", None, None, root.clone()).unwrap();
+        delete(&conn, &dropped).unwrap();
+
+        let report = collect_garbage(&conn, root.clone()).unwrap();
+        assert_eq!(report.removed_chunks, 1);
+        assert_eq!(report.freed_bytes, b"this one goes away".len() as u64);
+        assert!(load(&conn, &kept, root.clone()).is_ok());
+        assert_eq!(collect_garbage(&conn, root).unwrap().removed_chunks, 0);
+    }
+
+    #[test]
+    fn test_repair_chunks_drops_temp_and_torn_chunks() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        let file_hash = create(&conn, b"some chunked content", None, None, root.clone()).unwrap();
+        let chunks_dir = temp.path().join("chunks");
+        let chunk_hash: String = conn
+            .query_row(
+                "SELECT chunk_hash FROM file_chunks WHERE file_hash = ?1",
+                [&file_hash],
+                |row| row.get(0),
+            )
+            .unwrap();
+        fs::write(chunks_dir.join(&chunk_hash), b"torn").unwrap();
+        fs::write(chunks_dir.join("abc.tmp"), b"partial").unwrap();
+
+        let report = repair_chunks(&conn, root).unwrap();
+        assert_eq!(report.temp_files_removed, 1);
+        assert_eq!(report.torn_chunks_removed, 1);
+        assert_eq!(report.files_marked_incomplete, 1);
+        assert!(!chunks_dir.join(&chunk_hash).exists());
+    }
+
+    #[test]
+    fn test_delete_nonexistent() {
+        let conn = setup_test_db();
+
+        // Deleting non-existent file should error
+        assert!(delete(&conn, "nonexistent_hash").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Crash safety for on-disk state.
+//!
+//! Config and chunk files are written to a sibling `.tmp` file, fsynced and
+//! renamed over the target, so a crash leaves either the old or the new
+//! content, never a torn file. At startup [`recover_config`] promotes a
+//! finished temp file or the last backup if the config is missing or
+//! unreadable; `object::repair_chunks` and [`db_quick_check`] cover the chunk
+//! store and the database. Everything repaired ends up in a [`RecoveryReport`].
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+pub fn temp_path(path: &Path) -> PathBuf {
+    sibling(path, ".tmp")
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+/// Write `data` to `path` via temp file + fsync + rename.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp = temp_path(path);
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    // Persist the rename itself; not supported (or needed) on Windows.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecoveryReport {
+    /// Human-readable description of each repair, empty on a clean start.
+    pub repairs: Vec<String>,
+    /// Result of SQLite's `quick_check` ("ok" when healthy).
+    pub db_integrity: String,
+}
+
+/// Make sure `path` holds a readable config, restoring it from a completed
+/// temp file or the backup if needed. Returns the repairs performed.
+pub fn recover_config(path: &Path, is_valid: impl Fn(&[u8]) -> bool) -> Vec<String> {
+    let mut repairs = Vec::new();
+    let tmp = temp_path(path);
+    let main_ok = fs::read(path).map(|d| is_valid(&d)).unwrap_or(false);
+
+    // A temp file that parses means the crash hit between fsync and rename.
+    let replacement = if main_ok {
+        None
+    } else {
+        [tmp.clone(), backup_path(path)]
+            .into_iter()
+            .find_map(|candidate| {
+                let data = fs::read(&candidate).ok().filter(|d| is_valid(d))?;
+                Some((candidate, data))
+            })
+    };
+
+    let restoring_from_tmp = replacement.as_ref().is_some_and(|(from, _)| *from == tmp);
+    if tmp.exists() && fs::remove_file(&tmp).is_ok() && !restoring_from_tmp {
+        repairs.push(format!("Removed interrupted write {}", tmp.display()));
+    }
+
+    match replacement {
+        Some((from, data)) => match write_atomic(path, &data) {
+            Ok(()) => repairs.push(format!(
+                "Restored {} from {}",
+                path.display(),
+                from.display()
+            )),
+            Err(e) => repairs.push(format!("Failed to restore {}: {}", path.display(), e)),
+        },
+        None if !main_ok && path.exists() => repairs.push(format!(
+            "{} is unreadable and no valid backup was found",
+            path.display()
+        )),
+        None => {}
+    }
+    repairs
+}
+
+pub fn db_quick_check(conn: &Connection) -> String {
+    conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0))
+        .unwrap_or_else(|e| format!("check failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn is_json(data: &[u8]) -> bool {
+        serde_json::from_slice::<serde_json::Value>(data).is_ok()
+    }
+
+    #[test]
+    fn atomic_write_replaces_without_leaving_temp_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rchat.config");
+        write_atomic(&path, b"{\"v\":1}").unwrap();
+        write_atomic(&path, b"{\"v\":2}").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"{\"v\":2}");
+        assert!(!temp_path(&path).exists());
+        assert!(recover_config(&path, is_json).is_empty());
+    }
+
+    #[test]
+    fn torn_config_is_restored_from_backup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rchat.config");
+        fs::write(backup_path(&path), b"{\"v\":1}").unwrap();
+        fs::write(temp_path(&path), b"{\"v\":2").unwrap(); // crashed mid-write
+        fs::write(&path, b"{\"v\"").unwrap();
+
+        let repairs = recover_config(&path, is_json);
+        assert_eq!(repairs.len(), 2);
+        assert_eq!(fs::read(&path).unwrap(), b"{\"v\":1}");
+        assert!(!temp_path(&path).exists());
+    }
+}
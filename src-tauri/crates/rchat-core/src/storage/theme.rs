@@ -52,6 +52,26 @@ pub struct ThemeConfig {
     pub warning: AccentColors,
 }
 
+/// Light/dark preference. `System` follows the OS setting.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    Light,
+    Dark,
+    System,
+}
+
+impl Appearance {
+    /// Whether to render light, given the OS preference.
+    pub fn is_light(self, system_is_light: bool) -> bool {
+        match self {
+            Self::Light => true,
+            Self::Dark => false,
+            Self::System => system_is_light,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CustomThemeEntry {
     pub key: String,
@@ -311,6 +331,64 @@ pub fn validate_and_normalize_theme(theme: &ThemeConfig) -> Result<ThemeConfig>
     })
 }
 
+// ============================================================================
+// Shareable Theme Files (.rchat-theme)
+// ============================================================================
+
+pub const THEME_FILE_EXTENSION: &str = "rchat-theme";
+pub const THEME_FILE_MIME: &str = "application/vnd.rchat.theme+json";
+pub const THEME_FILE_VERSION: u32 = 1;
+/// Theme files are a few hundred bytes; anything this large is not one.
+pub const MAX_THEME_FILE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThemeFile {
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub theme: ThemeConfig,
+}
+
+pub fn encode_theme_file(
+    name: &str,
+    description: Option<String>,
+    theme: &ThemeConfig,
+) -> Result<Vec<u8>> {
+    let file = ThemeFile {
+        version: THEME_FILE_VERSION,
+        name: name.to_string(),
+        description,
+        theme: validate_and_normalize_theme(theme)?,
+    };
+    Ok(serde_json::to_vec_pretty(&file)?)
+}
+
+/// Parse a theme file from a friend, rejecting unknown versions and bad colors.
+pub fn decode_theme_file(data: &[u8]) -> Result<ThemeFile> {
+    if data.len() > MAX_THEME_FILE_BYTES {
+        return Err(anyhow::anyhow!("Theme file is too large"));
+    }
+    let file: ThemeFile = serde_json::from_slice(data)
+        .map_err(|e| anyhow::anyhow!("Not a valid theme file: {}", e))?;
+    if file.version != THEME_FILE_VERSION {
+        return Err(anyhow::anyhow!(
+            "Unsupported theme file version {}",
+            file.version
+        ));
+    }
+    let name = file.name.trim();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Theme file has no name"));
+    }
+    Ok(ThemeFile {
+        version: file.version,
+        name: name.to_string(),
+        description: file.description,
+        theme: validate_and_normalize_theme(&file.theme)?,
+    })
+}
+
 // ============================================================================
 // Theme Preset (simplified JSON schema)
 // ============================================================================
@@ -392,6 +470,11 @@ impl ThemeManager {
 
     /// Load a preset by name and convert to full ThemeConfig
     pub fn load_preset(&self, name: &str) -> Result<ThemeConfig> {
+        self.load_preset_for(name, None)
+    }
+
+    /// Load a preset in its light or dark variant; `None` keeps it as authored.
+    pub fn load_preset_for(&self, name: &str, light: Option<bool>) -> Result<ThemeConfig> {
         let json = EMBEDDED_THEMES
             .iter()
             .find(|(n, _)| *n == name)
@@ -399,18 +482,38 @@ impl ThemeManager {
             .ok_or_else(|| anyhow::anyhow!("Theme preset '{}' not found", name))?;
 
         let preset: ThemePreset = serde_json::from_str(json)?;
-        Ok(self.preset_to_config(&preset))
+        Ok(self.preset_to_config(&preset, light))
     }
 
-    /// Convert simplified preset to full ThemeConfig using interpolation
-    fn preset_to_config(&self, preset: &ThemePreset) -> ThemeConfig {
+    /// Convert simplified preset to full ThemeConfig using interpolation,
+    /// mirroring its base palette when the other light/dark variant is asked for.
+    fn preset_to_config(&self, preset: &ThemePreset, light: Option<bool>) -> ThemeConfig {
+        let preset_is_light = relative_luminance(&preset.background) > 0.5;
+        let (background, chat_panel, text_muted, text_primary) = match light {
+            Some(true) if !preset_is_light => {
+                let bg = self.interpolate_color(&preset.text_primary, "#ffffff", 0.9);
+                let panel = self.interpolate_color(&bg, &preset.secondary_accent, 0.08);
+                let text = preset.background.clone();
+                let muted = self.interpolate_color(&text, "#94a3b8", 0.55);
+                (bg, panel, muted, text)
+            }
+            Some(false) if preset_is_light => {
+                let bg = self.interpolate_color(&preset.text_primary, "#020617", 0.92);
+                let panel = self.interpolate_color(&bg, &preset.secondary_accent, 0.12);
+                let text = preset.background.clone();
+                let muted = self.interpolate_color(&text, "#64748b", 0.35);
+                (bg, panel, muted, text)
+            }
+            _ => (
+                preset.background.clone(),
+                preset.chat_panel.clone(),
+                preset.text_muted.clone(),
+                preset.text_primary.clone(),
+            ),
+        };
+
         // Generate base colors via interpolation
-        let base = self.generate_base_colors(
-            &preset.background,
-            &preset.chat_panel,
-            &preset.text_muted,
-            &preset.text_primary,
-        );
+        let base = self.generate_base_colors(&background, &chat_panel, &text_muted, &text_primary);
 
         // Generate accent shades from primary color
         let primary = self.generate_accent_shades(&preset.primary_accent);
@@ -539,11 +642,46 @@ mod tests {
         assert!(relative_luminance(&theme.base.c950) > 0.8);
     }
 
+    #[test]
+    fn presets_get_light_and_dark_variants() {
+        let manager = ThemeManager;
+        let dark = manager.load_preset("midnight_neon").expect("preset");
+        assert!(relative_luminance(&dark.base.c950) < 0.2);
+        let light = manager
+            .load_preset_for("midnight_neon", Some(true))
+            .expect("light variant");
+        assert!(relative_luminance(&light.base.c950) > 0.7);
+        assert_eq!(light.primary.c500, dark.primary.c500);
+
+        let arctic = manager
+            .load_preset_for("arctic_ice", Some(true))
+            .expect("already light");
+        assert_eq!(arctic.base.c950, "#F0F9FF");
+        let arctic_dark = manager
+            .load_preset_for("arctic_ice", Some(false))
+            .expect("dark variant");
+        assert!(relative_luminance(&arctic_dark.base.c950) < 0.2);
+    }
+
     #[test]
     fn normalize_hex_accepts_short_form() {
         assert_eq!(normalize_hex("#abc").expect("hex"), "#aabbcc");
     }
 
+    #[test]
+    fn theme_file_round_trips_and_rejects_bad_colors() {
+        let mut theme = ThemeConfig::default();
+        theme.primary.c500 = "#ABC".to_string();
+        let data = encode_theme_file("Teal", None, &theme).expect("encode");
+        let file = decode_theme_file(&data).expect("decode");
+        assert_eq!(file.name, "Teal");
+        assert_eq!(file.theme.primary.c500, "#aabbcc");
+
+        let mut json: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        json["theme"]["base"]["950"] = serde_json::json!("javascript:alert(1)");
+        assert!(decode_theme_file(&serde_json::to_vec(&json).unwrap()).is_err());
+    }
+
     #[test]
     fn validate_theme_rejects_bad_hex() {
         let mut theme = ThemeConfig::default();
@@ -138,6 +138,15 @@ pub struct ChatConnectionRuntime {
     pub last_connected_at: Option<i64>,
 }
 
+/// A GitHub user who dialed us and asked to be a friend; see
+/// `accept_friend_request`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FriendRequest {
+    pub github_username: String,
+    pub peer_id: String,
+    pub received_at: i64,
+}
+
 // This struct holds the Sender channel.
 // We wrap it in Mutex so multiple UI threads can use it safely.
 pub struct NetworkState {
@@ -153,10 +162,57 @@ pub struct NetworkState {
     pub voice_call_state: Mutex<VoiceCallState>, // Runtime voice-call state for UI polling
     pub broadcast_state: Mutex<BroadcastState>,  // Runtime DM broadcast state for UI polling
     pub connectivity: Mutex<crate::storage::config::ConnectivitySettings>, // Runtime connectivity controls
+    pub network_stats: Mutex<crate::network::diagnostics::NetworkStats>, // Maintained by NetworkManager for diagnostics
+    pub friend_requests: Mutex<HashMap<String, FriendRequest>>, // Pending friend requests by peer id
+}
+
+impl NetworkState {
+    /// Fresh runtime state for a node; STUN results are filled in by the caller.
+    pub fn new(
+        sender: mpsc::Sender<NetworkCommand>,
+        local_peer_id: String,
+        connectivity: crate::storage::config::ConnectivitySettings,
+    ) -> Self {
+        Self {
+            sender: Mutex::new(sender),
+            local_peer_id: Mutex::new(Some(local_peer_id)),
+            listening_addresses: Mutex::new(Vec::new()),
+            public_address_v6: Mutex::new(None),
+            public_address_v4: Mutex::new(None),
+            stun_external_port: Mutex::new(None),
+            temporary_state: Mutex::new(TemporaryRuntimeState::default()),
+            connected_chat_ids: Mutex::new(HashSet::new()),
+            chat_connections: Mutex::new(HashMap::new()),
+            voice_call_state: Mutex::new(VoiceCallState::default()),
+            broadcast_state: Mutex::new(BroadcastState::default()),
+            connectivity: Mutex::new(connectivity),
+            network_stats: Mutex::new(crate::network::diagnostics::NetworkStats::default()),
+            friend_requests: Mutex::new(HashMap::new()),
+        }
+    }
 }
 
 pub struct AppState {
     pub config_manager: tokio::sync::Mutex<ConfigManager>,
     pub db_conn: std::sync::Mutex<rusqlite::Connection>,
     pub app_dir: std::path::PathBuf,
+    pub chunks_dir: std::path::PathBuf, // Chunk store read and written by file transfers
+    pub journal: crate::storage::journal::JournalWriter, // Opt-in encrypted message journal
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State over an in-memory database, with the app files under `app_dir`.
+    pub fn for_tests(app_dir: std::path::PathBuf) -> Self {
+        Self {
+            config_manager: tokio::sync::Mutex::new(ConfigManager::new(app_dir.clone())),
+            db_conn: std::sync::Mutex::new(
+                crate::storage::db::connect_in_memory().expect("in-memory db"),
+            ),
+            chunks_dir: crate::storage::object::get_chunks_dir(Some(app_dir.clone()))
+                .expect("chunks dir"),
+            journal: crate::storage::journal::JournalWriter::new(&app_dir),
+            app_dir,
+        }
+    }
 }
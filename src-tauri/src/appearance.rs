@@ -0,0 +1,121 @@
+//! Light/dark appearance.
+//!
+//! The OS color scheme is read from the main window at startup and tracked
+//! through `WindowEvent::ThemeChanged`. With `Appearance::System` selected, a
+//! change re-renders the active built-in preset in the matching variant and the
+//! UI gets a `system-theme-changed` event.
+
+use crate::AppHandle;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{Emitter, Manager};
+
+use crate::storage::config::{Appearance, Config, ThemeConfig};
+use crate::storage::theme::ThemeManager;
+
+static SYSTEM_IS_LIGHT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppearanceState {
+    /// `None` until the user picks one; presets then render as authored.
+    pub appearance: Option<Appearance>,
+    pub system: &'static str,
+    pub effective: &'static str,
+    pub theme: ThemeConfig,
+}
+
+fn scheme_name(light: bool) -> &'static str {
+    if light {
+        "light"
+    } else {
+        "dark"
+    }
+}
+
+pub fn system_is_light() -> bool {
+    SYSTEM_IS_LIGHT.load(Ordering::SeqCst)
+}
+
+pub fn set_system_theme(theme: tauri::Theme) {
+    SYSTEM_IS_LIGHT.store(matches!(theme, tauri::Theme::Light), Ordering::SeqCst);
+}
+
+/// Variant to render built-in presets in, or `None` to keep them as authored.
+pub fn preset_variant(config: &Config) -> Option<bool> {
+    config
+        .user
+        .appearance
+        .map(|appearance| appearance.is_light(system_is_light()))
+}
+
+pub fn state_for(config: &Config) -> AppearanceState {
+    let system = system_is_light();
+    let effective = config
+        .user
+        .appearance
+        .map(|appearance| appearance.is_light(system))
+        .unwrap_or(!config.user.dark_mode);
+    AppearanceState {
+        appearance: config.user.appearance,
+        system: scheme_name(system),
+        effective: scheme_name(effective),
+        theme: config.user.theme.clone(),
+    }
+}
+
+/// Re-render the selected built-in preset for the current appearance.
+/// Custom themes are left alone. Returns whether the theme changed.
+pub fn rerender_preset(config: &mut Config, theme_manager: &ThemeManager) -> bool {
+    let Some(light) = preset_variant(config) else {
+        return false;
+    };
+    config.user.dark_mode = !light;
+    let Some(key) = config
+        .user
+        .selected_preset
+        .clone()
+        .filter(|key| !key.starts_with("custom:"))
+    else {
+        return false;
+    };
+    match theme_manager.load_preset_for(&key, Some(light)) {
+        Ok(theme) => {
+            config.user.theme = theme;
+            true
+        }
+        Err(e) => {
+            eprintln!("[Theme] ⚠️ Failed to render preset {}: {}", key, e);
+            false
+        }
+    }
+}
+
+pub fn on_system_theme_changed(app_handle: &AppHandle, theme: tauri::Theme) {
+    let light = matches!(theme, tauri::Theme::Light);
+    if SYSTEM_IS_LIGHT.swap(light, Ordering::SeqCst) == light {
+        return;
+    }
+    println!("[Theme] 🌓 System theme changed to {}", scheme_name(light));
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        let Ok(mut config) = mgr.load().await else {
+            // Locked vault: nothing to re-render yet, but the UI still follows.
+            let _ = app_handle.emit(
+                "system-theme-changed",
+                serde_json::json!({ "system": scheme_name(light) }),
+            );
+            return;
+        };
+        if config.user.appearance == Some(Appearance::System)
+            && rerender_preset(&mut config, &ThemeManager::new(&state.app_dir))
+        {
+            if let Err(e) = mgr.save(&config).await {
+                eprintln!("[Theme] ❌ Failed to save re-rendered theme: {}", e);
+            }
+        }
+        let _ = app_handle.emit("system-theme-changed", state_for(&config));
+    });
+}
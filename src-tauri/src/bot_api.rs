@@ -0,0 +1,770 @@
+//! Local API for bots and integrations (`UserConfig::bot_api_enabled`).
+//!
+//! Programs on this machine connect to a unix socket (`bot/bot.sock` in the
+//! app directory) or, on Windows, the `\\.\pipe\rchat-bot-api` named pipe, and
+//! exchange one JSON object per line. The first request must be `auth` with
+//! the token from `bot_api.token`; after that a client can send messages, list
+//! chats, read history and subscribe to events:
+//!
+//! ```text
+//! > {"id":1,"method":"auth","params":{"token":"..."}}
+//! < {"id":1,"ok":true,"result":null}
+//! > {"id":2,"method":"subscribe","params":{"events":["message-received"]}}
+//! < {"id":2,"ok":true,"result":["message-received"]}
+//! < {"event":"message-received","payload":{...}}
+//! > {"id":3,"method":"send_message","params":{"chat_id":"...","text":"hi"}}
+//! < {"id":3,"ok":true,"result":"<message id>"}
+//! ```
+//!
+//! Requests go through the same commands as the UI, so sends are subject to
+//! the usual checks. Hidden chats are left out of chat lists, history and
+//! events, and can't be sent to, unless they are revealed in the app. The
+//! server runs while the network is up.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::AppHandle;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::{EventId, Listener, Manager};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+use crate::bridge::RateLimiter;
+use crate::error::RChatError;
+
+/// Events a client can subscribe to.
+pub const EVENTS: &[&str] = &[
+    "message-received",
+    "message-status-updated",
+    "scheduled-message-sent",
+    "mentioned",
+    "peer-connected",
+];
+
+const TOKEN_FILE: &str = "bot_api.token";
+/// Holds the socket; only the current user can enter it.
+#[cfg(unix)]
+const SOCKET_DIR: &str = "bot";
+#[cfg(unix)]
+const SOCKET_FILE: &str = "bot.sock";
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\rchat-bot-api";
+const EVENT_QUEUE_CAPACITY: usize = 256;
+const OUTBOUND_QUEUE_CAPACITY: usize = 64;
+/// Longer request lines end the connection.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+struct BotEvent {
+    name: &'static str,
+    payload: Value,
+}
+
+struct Server {
+    task: tauri::async_runtime::JoinHandle<()>,
+    listeners: Vec<EventId>,
+}
+
+/// The running server, if any. Managed as Tauri state.
+#[derive(Default)]
+pub struct BotApiRuntime {
+    server: Mutex<Option<Server>>,
+}
+
+/// Where clients connect.
+pub fn endpoint(app_dir: &Path) -> String {
+    #[cfg(unix)]
+    {
+        socket_path(app_dir).display().to_string()
+    }
+    #[cfg(windows)]
+    {
+        let _ = app_dir;
+        PIPE_NAME.to_string()
+    }
+}
+
+#[cfg(unix)]
+fn socket_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SOCKET_DIR).join(SOCKET_FILE)
+}
+
+fn token_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(TOKEN_FILE)
+}
+
+/// The client token, created on first use. Only the current user can read it.
+pub fn load_or_create_token(app_dir: &Path) -> std::io::Result<String> {
+    load_or_create_token_file(&token_path(app_dir))
+}
+
+/// Replace the client token. Running servers must be restarted to use it.
+pub fn rotate_token(app_dir: &Path) -> std::io::Result<String> {
+    write_new_token(&token_path(app_dir))
+}
+
+/// The token stored at `path`, created on first use. Shared with `http_api`.
+pub(crate) fn load_or_create_token_file(path: &Path) -> std::io::Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_string()),
+        _ => write_new_token(path),
+    }
+}
+
+/// Store a fresh random token at `path`, readable only by the current user.
+pub(crate) fn write_new_token(path: &Path) -> std::io::Result<String> {
+    use rand::RngCore;
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // `mode` only applies to new files; tighten one that was already there.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::io::Write::write_all(&mut file, token.as_bytes())?;
+    Ok(token)
+}
+
+/// Start or stop the server to match the setting. Called once the network is
+/// up and whenever the setting changes.
+pub async fn apply(app_handle: &AppHandle, enabled: bool) {
+    stop(app_handle).await;
+    if !enabled || app_handle.try_state::<crate::NetworkState>().is_none() {
+        return;
+    }
+    if let Err(e) = start(app_handle).await {
+        eprintln!("[BotApi] ❌ Failed to start: {}", e);
+    }
+}
+
+/// Start the server if the setting is on.
+pub async fn start_if_enabled(app_handle: &AppHandle) {
+    let enabled = {
+        let state = app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        mgr.load()
+            .await
+            .map(|config| config.user.bot_api_enabled)
+            .unwrap_or(false)
+    };
+    apply(app_handle, enabled).await;
+}
+
+async fn start(app_handle: &AppHandle) -> Result<(), String> {
+    let Some(runtime) = app_handle.try_state::<BotApiRuntime>() else {
+        return Err("Bot API runtime not initialized".to_string());
+    };
+    let app_dir = app_handle.state::<crate::AppState>().app_dir.clone();
+    let token = load_or_create_token(&app_dir).map_err(|e| e.to_string())?;
+
+    let (events_tx, _) = broadcast::channel(EVENT_QUEUE_CAPACITY);
+    let listeners = EVENTS
+        .iter()
+        .map(|name| {
+            let events_tx = events_tx.clone();
+            app_handle.listen_any(*name, move |event| {
+                let payload = serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+                let _ = events_tx.send(BotEvent {
+                    name: *name,
+                    payload,
+                });
+            })
+        })
+        .collect();
+
+    let task = {
+        let endpoint = endpoint(&app_dir);
+        let accept = accept_loop(app_handle.clone(), endpoint.clone(), token, events_tx);
+        println!("[BotApi] ✅ Listening on {}", endpoint);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = accept.await {
+                eprintln!("[BotApi] ❌ Server stopped: {}", e);
+            }
+        })
+    };
+
+    *runtime.server.lock().await = Some(Server { task, listeners });
+    Ok(())
+}
+
+pub async fn stop(app_handle: &AppHandle) {
+    let Some(runtime) = app_handle.try_state::<BotApiRuntime>() else {
+        return;
+    };
+    if let Some(server) = runtime.server.lock().await.take() {
+        server.task.abort();
+        for listener in server.listeners {
+            app_handle.unlisten(listener);
+        }
+        #[cfg(unix)]
+        {
+            let app_dir = &app_handle.state::<crate::AppState>().app_dir;
+            let _ = std::fs::remove_file(socket_path(app_dir));
+        }
+        println!("[BotApi] Stopped");
+    }
+}
+
+#[cfg(unix)]
+async fn accept_loop(
+    app_handle: AppHandle,
+    endpoint: String,
+    token: String,
+    events: broadcast::Sender<BotEvent>,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    // Bind inside a private directory, so the socket is never reachable by
+    // others before its own mode is set.
+    if let Some(dir) = Path::new(&endpoint).parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    // A socket left behind by a crash would make bind fail.
+    let _ = std::fs::remove_file(&endpoint);
+    let listener = tokio::net::UnixListener::bind(&endpoint)?;
+    std::fs::set_permissions(&endpoint, std::fs::Permissions::from_mode(0o600))?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tauri::async_runtime::spawn(serve_client(
+            app_handle.clone(),
+            stream,
+            token.clone(),
+            events.clone(),
+        ));
+    }
+}
+
+#[cfg(windows)]
+async fn accept_loop(
+    app_handle: AppHandle,
+    endpoint: String,
+    token: String,
+    events: broadcast::Sender<BotEvent>,
+) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(&endpoint)?;
+    loop {
+        server.connect().await?;
+        let client = std::mem::replace(
+            &mut server,
+            ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(&endpoint)?,
+        );
+        tauri::async_runtime::spawn(serve_client(
+            app_handle.clone(),
+            client,
+            token.clone(),
+            events.clone(),
+        ));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, PartialEq)]
+enum Call {
+    Auth {
+        token: String,
+    },
+    SendMessage {
+        chat_id: String,
+        text: String,
+    },
+    ListChats,
+    GetMessages {
+        chat_id: String,
+        limit: Option<usize>,
+    },
+    Subscribe {
+        events: Vec<String>,
+    },
+}
+
+impl Call {
+    fn parse(method: &str, params: Value) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Auth {
+            token: String,
+        }
+        #[derive(Deserialize)]
+        struct SendMessage {
+            chat_id: String,
+            text: String,
+        }
+        #[derive(Deserialize)]
+        struct GetMessages {
+            chat_id: String,
+            #[serde(default)]
+            limit: Option<usize>,
+        }
+        #[derive(Deserialize)]
+        struct Subscribe {
+            #[serde(default)]
+            events: Vec<String>,
+        }
+
+        fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, String> {
+            let params = if params.is_null() { json!({}) } else { params };
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {}", e))
+        }
+
+        Ok(match method {
+            "auth" => {
+                let Auth { token } = params(params)?;
+                Call::Auth { token }
+            }
+            "send_message" => {
+                let SendMessage { chat_id, text } = params(params)?;
+                Call::SendMessage { chat_id, text }
+            }
+            "list_chats" => Call::ListChats,
+            "get_messages" => {
+                let GetMessages { chat_id, limit } = params(params)?;
+                Call::GetMessages { chat_id, limit }
+            }
+            "subscribe" => {
+                let Subscribe { events } = params(params)?;
+                Call::Subscribe { events }
+            }
+            other => return Err(format!("Unknown method: {}", other)),
+        })
+    }
+}
+
+/// The events a subscription asks for; none means all of them.
+fn subscription(requested: &[String]) -> Result<HashSet<&'static str>, String> {
+    if requested.is_empty() {
+        return Ok(EVENTS.iter().copied().collect());
+    }
+    requested
+        .iter()
+        .map(|name| {
+            EVENTS
+                .iter()
+                .copied()
+                .find(|event| event == name)
+                .ok_or_else(|| format!("Unknown event: {}", name))
+        })
+        .collect()
+}
+
+/// Compare without leaking how much of the token matched.
+pub(crate) fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn response(id: Value, result: Result<Value, String>) -> Value {
+    match result {
+        Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+        Err(error) => json!({ "id": id, "ok": false, "error": error }),
+    }
+}
+
+/// Hidden chats the APIs must not show right now. Also used by
+/// [`crate::http_api`].
+pub(crate) fn concealed_chat_ids(app_handle: &AppHandle) -> Result<HashSet<String>, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let state = app_handle.state::<crate::AppState>();
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    crate::chat::hidden::concealed_chat_ids(&conn, now).map_err(|e| e.to_string())
+}
+
+/// Fail as not found when `chat_id` is concealed, as given or as the direct
+/// chat a peer id resolves to. Also used by [`crate::http_api`].
+pub(crate) async fn ensure_chat_visible(
+    app_handle: &AppHandle,
+    chat_id: &str,
+) -> Result<(), RChatError> {
+    let hidden = concealed_chat_ids(app_handle)?;
+    let resolved =
+        crate::commands::chat::canonical_direct_chat_id_for_target(&app_handle.state(), chat_id)
+            .await;
+    if hidden.contains(chat_id) || hidden.contains(&resolved) {
+        return Err(RChatError::NotFound("Chat not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Whether an event concerns a concealed chat. Failed lookups count as yes.
+fn is_concealed_event(app_handle: &AppHandle, event: &BotEvent) -> bool {
+    let Some(chat_id) = event.payload.get("chat_id").and_then(Value::as_str) else {
+        return false;
+    };
+    concealed_chat_ids(app_handle).map_or(true, |hidden| hidden.contains(chat_id))
+}
+
+/// Read one line of at most [`MAX_LINE_BYTES`], without the line ending.
+/// `Ok(None)` at the end of the stream; longer lines are an error.
+async fn read_line<R>(reader: &mut R, buf: &mut Vec<u8>) -> std::io::Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    buf.clear();
+    let read = (&mut *reader)
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_until(b'\n', buf)
+        .await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    if buf.len() > MAX_LINE_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "request line too long",
+        ));
+    }
+    String::from_utf8(std::mem::take(buf))
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+struct Session {
+    authenticated: bool,
+    limiter: RateLimiter,
+    subscription: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+async fn serve_client<S>(
+    app_handle: AppHandle,
+    stream: S,
+    token: String,
+    events: broadcast::Sender<BotEvent>,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let (out_tx, mut out_rx) = mpsc::channel::<Value>(OUTBOUND_QUEUE_CAPACITY);
+    let writer_task = tauri::async_runtime::spawn(async move {
+        while let Some(message) = out_rx.recv().await {
+            let mut line = message.to_string();
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session {
+        authenticated: false,
+        limiter: RateLimiter::default(),
+        subscription: None,
+    };
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    while let Ok(Some(line)) = read_line(&mut reader, &mut buf).await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, result) = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let result = match Call::parse(&request.method, request.params) {
+                    Ok(call) => {
+                        handle(&app_handle, &token, &events, &out_tx, &mut session, call).await
+                    }
+                    Err(e) => Err(e),
+                };
+                (request.id, result)
+            }
+            Err(e) => (Value::Null, Err(format!("Invalid request: {}", e))),
+        };
+        let rejected = !session.authenticated;
+        if out_tx.send(response(id, result)).await.is_err() || rejected {
+            break;
+        }
+    }
+
+    if let Some(subscription) = session.subscription.take() {
+        subscription.abort();
+    }
+    drop(out_tx);
+    let _ = writer_task.await;
+}
+
+async fn handle(
+    app_handle: &AppHandle,
+    token: &str,
+    events: &broadcast::Sender<BotEvent>,
+    out_tx: &mpsc::Sender<Value>,
+    session: &mut Session,
+    call: Call,
+) -> Result<Value, String> {
+    let call = match call {
+        Call::Auth { token: given } => {
+            if !token_matches(token, &given) {
+                eprintln!("[BotApi] ⚠️ Rejected a client with a wrong token");
+                return Err("Invalid token".to_string());
+            }
+            session.authenticated = true;
+            println!("[BotApi] 🤖 Client connected");
+            return Ok(Value::Null);
+        }
+        _ if !session.authenticated => return Err("Authenticate first".to_string()),
+        call => call,
+    };
+
+    match call {
+        Call::Auth { .. } => unreachable!("handled above"),
+        Call::SendMessage { chat_id, text } => {
+            if text.trim().is_empty() {
+                return Err("Message text is empty".to_string());
+            }
+            ensure_chat_visible(app_handle, &chat_id).await?;
+            if !session.limiter.try_acquire() {
+                return Err("Rate limited".to_string());
+            }
+            let msg_id = crate::commands::chat::send_message(
+                chat_id,
+                text,
+                app_handle.state(),
+                app_handle.state(),
+                app_handle.clone(),
+            )
+            .await
+            .map_err(String::from)?;
+            Ok(Value::String(msg_id))
+        }
+        Call::ListChats => {
+            let chats = crate::commands::chat::get_chats(app_handle.state())
+                .await
+                .map_err(String::from)?;
+            serde_json::to_value(chats).map_err(|e| e.to_string())
+        }
+        Call::GetMessages { chat_id, limit } => {
+            ensure_chat_visible(app_handle, &chat_id).await?;
+            let mut messages = crate::commands::chat::get_chat_history(
+                chat_id,
+                app_handle.state(),
+                app_handle.state(),
+            )
+            .await
+            .map_err(String::from)?;
+            if let Some(limit) = limit {
+                messages.drain(..messages.len().saturating_sub(limit));
+            }
+            serde_json::to_value(messages).map_err(|e| e.to_string())
+        }
+        Call::Subscribe { events: requested } => {
+            let wanted = subscription(&requested)?;
+            let mut names: Vec<&str> = wanted.iter().copied().collect();
+            names.sort_unstable();
+            if let Some(previous) = session.subscription.take() {
+                previous.abort();
+            }
+            let mut rx = events.subscribe();
+            let out_tx = out_tx.clone();
+            let app_handle = app_handle.clone();
+            session.subscription = Some(tauri::async_runtime::spawn(async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(event)
+                            if wanted.contains(event.name)
+                                && !is_concealed_event(&app_handle, &event) =>
+                        {
+                            let message = json!({ "event": event.name, "payload": event.payload });
+                            if out_tx.send(message).await.is_err() {
+                                break;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            eprintln!("[BotApi] ⚠️ Slow client missed {} event(s)", missed);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            }));
+            Ok(json!(names))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Call, String> {
+        let request: Request = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        Call::parse(&request.method, request.params)
+    }
+
+    #[test]
+    fn requests_parse_with_and_without_params() {
+        assert_eq!(
+            parse(r#"{"id":1,"method":"auth","params":{"token":"abc"}}"#),
+            Ok(Call::Auth {
+                token: "abc".to_string()
+            })
+        );
+        assert_eq!(parse(r#"{"method":"list_chats"}"#), Ok(Call::ListChats));
+        assert_eq!(
+            parse(r#"{"id":"x","method":"get_messages","params":{"chat_id":"c"}}"#),
+            Ok(Call::GetMessages {
+                chat_id: "c".to_string(),
+                limit: None
+            })
+        );
+        assert_eq!(
+            parse(r#"{"method":"subscribe"}"#),
+            Ok(Call::Subscribe { events: vec![] })
+        );
+        assert!(parse(r#"{"method":"send_message","params":{"chat_id":"c"}}"#).is_err());
+        assert!(parse(r#"{"method":"delete_everything"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn request_lines_are_bounded() {
+        let mut input = b"{\"method\":\"list_chats\"}\r\nlast".to_vec();
+        let mut reader = BufReader::new(&input[..]);
+        let mut buf = Vec::new();
+        assert_eq!(
+            read_line(&mut reader, &mut buf)
+                .await
+                .expect("line")
+                .as_deref(),
+            Some(r#"{"method":"list_chats"}"#)
+        );
+        assert_eq!(
+            read_line(&mut reader, &mut buf)
+                .await
+                .expect("last")
+                .as_deref(),
+            Some("last")
+        );
+        assert_eq!(read_line(&mut reader, &mut buf).await.expect("eof"), None);
+
+        input = vec![b'x'; MAX_LINE_BYTES + 1];
+        input.push(b'\n');
+        let mut reader = BufReader::new(&input[..]);
+        assert!(read_line(&mut reader, &mut buf).await.is_err());
+    }
+
+    #[test]
+    fn subscriptions_only_cover_known_events() {
+        assert_eq!(subscription(&[]).expect("all").len(), EVENTS.len());
+        let only = subscription(&["message-received".to_string()]).expect("one");
+        assert!(only.contains("message-received") && only.len() == 1);
+        assert!(subscription(&["auth-status".to_string()]).is_err());
+    }
+
+    #[test]
+    fn token_is_private_and_checked_exactly() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let token = load_or_create_token(dir.path()).expect("token");
+        assert_eq!(load_or_create_token(dir.path()).expect("again"), token);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(token_path(dir.path()))
+                .expect("metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(token_matches(&token, &token));
+        assert!(!token_matches(&token, &token[1..]));
+        assert!(!token_matches(&token, &format!("{}0", &token[1..])));
+        let rotated = rotate_token(dir.path()).expect("rotate");
+        assert_ne!(rotated, token);
+
+        // A token file someone loosened is tightened again on the next write.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = token_path(dir.path());
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))
+                .expect("loosen");
+            rotate_token(dir.path()).expect("rotate again");
+            let mode = std::fs::metadata(&path)
+                .expect("metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn hidden_chats_cannot_be_sent_to_or_read() {
+        let files = tempfile::tempdir().expect("tempdir");
+        let app = tauri::test::mock_app();
+        app.manage(crate::AppState::for_tests(files.path().to_path_buf()));
+        let peer_id = libp2p::PeerId::random().to_string();
+        let chat_id = crate::chat_identity::build_local_chat_id("bob", &peer_id);
+        {
+            let state = app.state::<crate::AppState>();
+            let conn = state.db_conn.lock().expect("db lock");
+            crate::storage::db::upsert_chat(&conn, &chat_id, "bob", false).expect("chat");
+            let mut settings = crate::storage::db::ChatSettings::defaults(&chat_id);
+            settings.hidden = true;
+            crate::storage::db::upsert_chat_settings(&conn, &settings).expect("settings");
+        }
+
+        let (events, _) = broadcast::channel(1);
+        let (out_tx, _out_rx) = mpsc::channel(1);
+        let mut session = Session {
+            authenticated: true,
+            limiter: RateLimiter::default(),
+            subscription: None,
+        };
+        // The peer id resolves to the hidden direct chat.
+        for target in [chat_id.clone(), peer_id] {
+            let send = Call::SendMessage {
+                chat_id: target.clone(),
+                text: "hi".to_string(),
+            };
+            let result = handle(app.handle(), "t", &events, &out_tx, &mut session, send).await;
+            assert_eq!(result, Err("Chat not found".to_string()));
+            let read = Call::GetMessages {
+                chat_id: target,
+                limit: None,
+            };
+            let result = handle(app.handle(), "t", &events, &out_tx, &mut session, read).await;
+            assert_eq!(result, Err("Chat not found".to_string()));
+        }
+    }
+}
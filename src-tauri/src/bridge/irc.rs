@@ -0,0 +1,236 @@
+//! Minimal plain-TCP IRC connector: registers a nick, joins the bridged channel,
+//! relays PRIVMSGs both ways and answers server PINGs.
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use super::{ConnectorEvent, InboundBridgeMessage, OutboundBridgeMessage, RateLimiter};
+use crate::storage::db::ChatBridge;
+
+/// Keep lines comfortably below the 512-byte IRC limit once the prefix is added.
+const MAX_LINE_BYTES: usize = 400;
+
+/// Drop mIRC formatting (bold, colors, italics, ...), which rchat has no way to show.
+pub fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Color: \x03 followed by up to two digits, optionally ",bg" with up to two more.
+            '\u{3}' => {
+                for _ in 0..2 {
+                    chars.next_if(char::is_ascii_digit);
+                }
+                if chars.peek() == Some(&',') {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    if lookahead.peek().is_some_and(char::is_ascii_digit) {
+                        chars.next();
+                        for _ in 0..2 {
+                            chars.next_if(char::is_ascii_digit);
+                        }
+                    }
+                }
+            }
+            '\u{2}' | '\u{f}' | '\u{11}' | '\u{16}' | '\u{1d}' | '\u{1e}' | '\u{1f}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parse a `:nick!user@host PRIVMSG #channel :text` line addressed to `channel`.
+pub fn parse_privmsg(line: &str, channel: &str) -> Option<InboundBridgeMessage> {
+    let rest = line.strip_prefix(':')?;
+    let (prefix, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix("PRIVMSG ")?;
+    let (target, text) = rest.split_once(" :")?;
+    if !target.eq_ignore_ascii_case(channel) {
+        return None;
+    }
+    let nick = prefix.split('!').next().unwrap_or(prefix);
+    let text = text.trim();
+    if nick.is_empty() || text.is_empty() {
+        return None;
+    }
+    // CTCP ACTION (/me) arrives wrapped in \x01.
+    let text = match text
+        .strip_prefix("\u{1}ACTION ")
+        .and_then(|t| t.strip_suffix('\u{1}'))
+    {
+        Some(action) => format!("* {} {}", nick, action),
+        None => text.to_string(),
+    };
+    let text = strip_formatting(&text);
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some(InboundBridgeMessage {
+        remote_id: None,
+        remote_user: nick.to_string(),
+        text,
+    })
+}
+
+/// Split outbound text into IRC-safe lines (no CR/LF, bounded length).
+pub fn split_outbound_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for raw in text.lines().map(str::trim_end).filter(|l| !l.is_empty()) {
+        let mut current = String::new();
+        for ch in raw.chars() {
+            if current.len() + ch.len_utf8() > MAX_LINE_BYTES {
+                lines.push(std::mem::take(&mut current));
+            }
+            current.push(ch);
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+    }
+    lines
+}
+
+async fn run_session(
+    bridge: &ChatBridge,
+    outbound_rx: &mut mpsc::Receiver<OutboundBridgeMessage>,
+    inbound_tx: &mpsc::Sender<ConnectorEvent>,
+) -> Result<(), String> {
+    let stream = TcpStream::connect(bridge.endpoint.trim())
+        .await
+        .map_err(|e| format!("connect {}: {}", bridge.endpoint, e))?;
+    let (read_half, mut write_half) = stream.into_split();
+    let mut reader = BufReader::new(read_half).lines();
+
+    let nick = bridge.nick.trim();
+    let channel = bridge.target.trim();
+    write_half
+        .write_all(format!("NICK {}\r\nUSER {} 0 * :rchat bridge\r\n", nick, nick).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut limiter = RateLimiter::default();
+    let mut joined = false;
+
+    loop {
+        tokio::select! {
+            line = reader.next_line() => {
+                let Some(line) = line.map_err(|e| e.to_string())? else {
+                    return Err("server closed connection".to_string());
+                };
+
+                if let Some(token) = line.strip_prefix("PING ") {
+                    write_half
+                        .write_all(format!("PONG {}\r\n", token).as_bytes())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    continue;
+                }
+
+                // 001 = RPL_WELCOME, registration finished.
+                if !joined && line.split(' ').nth(1) == Some("001") {
+                    write_half
+                        .write_all(format!("JOIN {}\r\n", channel).as_bytes())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    joined = true;
+                    println!("[Bridge] ✅ IRC connected as {} in {}", nick, channel);
+                    continue;
+                }
+
+                if let Some(msg) = parse_privmsg(&line, channel) {
+                    if msg.remote_user.eq_ignore_ascii_case(nick) {
+                        continue;
+                    }
+                    if inbound_tx.send(ConnectorEvent::Received(msg)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            outbound = outbound_rx.recv() => {
+                let Some(msg) = outbound else {
+                    let _ = write_half.write_all(b"QUIT :bridge stopped\r\n").await;
+                    return Ok(());
+                };
+                if !joined {
+                    continue;
+                }
+                let body = super::format_outbound_text(&msg.sender_name, &msg.text);
+                for line in split_outbound_lines(&body) {
+                    limiter.acquire().await;
+                    write_half
+                        .write_all(format!("PRIVMSG {} :{}\r\n", channel, line).as_bytes())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+    }
+}
+
+pub async fn run(
+    bridge: ChatBridge,
+    mut outbound_rx: mpsc::Receiver<OutboundBridgeMessage>,
+    inbound_tx: mpsc::Sender<ConnectorEvent>,
+) {
+    let mut attempts: u32 = 0;
+
+    loop {
+        match run_session(&bridge, &mut outbound_rx, &inbound_tx).await {
+            Ok(()) => return,
+            Err(e) => {
+                let delay = super::reconnect_delay(attempts);
+                attempts = attempts.saturating_add(1);
+                eprintln!(
+                    "[Bridge] ❌ IRC session for {} failed: {} (retrying in {}s)",
+                    bridge.chat_id,
+                    e,
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_channel_privmsg_and_actions() {
+        let msg = parse_privmsg(":alice!a@host PRIVMSG #rchat :hello all", "#rchat").expect("msg");
+        assert_eq!(msg.remote_user, "alice");
+        assert_eq!(msg.text, "hello all");
+
+        let action = parse_privmsg(
+            ":bob!b@host PRIVMSG #RChat :\u{1}ACTION waves\u{1}",
+            "#rchat",
+        )
+        .expect("action");
+        assert_eq!(action.text, "* bob waves");
+
+        assert!(parse_privmsg(":alice!a@host PRIVMSG other :hi", "#rchat").is_none());
+        assert!(parse_privmsg("PING :server", "#rchat").is_none());
+    }
+
+    #[test]
+    fn strips_mirc_formatting() {
+        assert_eq!(
+            strip_formatting("\u{2}bold\u{2} \u{3}4,12red\u{3} \u{3}3,x \u{1d}it\u{f}"),
+            "bold red ,x it"
+        );
+        let msg = parse_privmsg(":alice!a@host PRIVMSG #rchat :\u{3}04hi", "#rchat").expect("msg");
+        assert_eq!(msg.text, "hi");
+    }
+
+    #[test]
+    fn splits_long_and_multiline_text() {
+        let long = "x".repeat(MAX_LINE_BYTES + 10);
+        let lines = split_outbound_lines(&format!("first\r\n\n{}", long));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "first");
+        assert_eq!(lines[1].len(), MAX_LINE_BYTES);
+        assert_eq!(lines[2].len(), 10);
+    }
+}
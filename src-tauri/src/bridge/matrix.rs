@@ -0,0 +1,320 @@
+//! Matrix client-server connector: long-polls `/sync` for the bridged room and sends
+//! `m.text` events for outbound messages.
+//!
+//! Outbound events carry the rchat message id under [`RCHAT_MESSAGE_ID_KEY`]; events with
+//! that key were posted by an rchat bridge (ours or another member's) and are never
+//! relayed back in.
+
+use reqwest::Url;
+use tokio::sync::mpsc;
+
+use super::{ConnectorEvent, InboundBridgeMessage, OutboundBridgeMessage, RateLimiter};
+use crate::storage::config::MatrixAccount;
+use crate::storage::db::ChatBridge;
+
+const SYNC_TIMEOUT_MS: u64 = 30_000;
+pub const RCHAT_MESSAGE_ID_KEY: &str = "io.github.ata-sesli.rchat.message_id";
+
+fn api_url(homeserver: &str, segments: &[&str]) -> Result<Url, String> {
+    let mut url =
+        Url::parse(homeserver.trim()).map_err(|e| format!("Invalid homeserver: {}", e))?;
+    url.path_segments_mut()
+        .map_err(|_| "Homeserver URL cannot be a base".to_string())?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+async fn whoami(
+    client: &reqwest::Client,
+    homeserver: &str,
+    access_token: &str,
+) -> Result<String, String> {
+    let url = api_url(
+        homeserver,
+        &["_matrix", "client", "v3", "account", "whoami"],
+    )?;
+    let body: serde_json::Value = client
+        .get(url)
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    body.get("user_id")
+        .and_then(|v| v.as_str())
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| "whoami response missing user_id".to_string())
+}
+
+/// Check the account's token; returns its Matrix user id.
+pub async fn validate_account(account: &MatrixAccount) -> Result<String, String> {
+    whoami(
+        &reqwest::Client::new(),
+        &account.homeserver,
+        &account.access_token,
+    )
+    .await
+}
+
+async fn sync(
+    client: &reqwest::Client,
+    bridge: &ChatBridge,
+    since: Option<&str>,
+) -> Result<serde_json::Value, String> {
+    let mut url = api_url(&bridge.endpoint, &["_matrix", "client", "v3", "sync"])?;
+    let filter = serde_json::json!({
+        "room": {
+            "rooms": [bridge.target],
+            "timeline": { "limit": 20 },
+        },
+        "presence": { "types": [] },
+        "account_data": { "types": [] },
+    });
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("filter", &filter.to_string());
+        if let Some(since) = since {
+            query.append_pair("since", since);
+            query.append_pair("timeout", &SYNC_TIMEOUT_MS.to_string());
+        } else {
+            query.append_pair("timeout", "0");
+        }
+    }
+
+    client
+        .get(url)
+        .bearer_auth(&bridge.credential)
+        .timeout(std::time::Duration::from_millis(SYNC_TIMEOUT_MS + 10_000))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Post `body` for rchat message `local_id`; returns the event id.
+async fn send_text(
+    client: &reqwest::Client,
+    bridge: &ChatBridge,
+    local_id: &str,
+    body: &str,
+) -> Result<String, String> {
+    // Derived from the message id, so a retried send is deduplicated by the homeserver.
+    let txn_id = format!("rchat-{}", local_id);
+    let url = api_url(
+        &bridge.endpoint,
+        &[
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            &bridge.target,
+            "send",
+            "m.room.message",
+            &txn_id,
+        ],
+    )?;
+    let response: serde_json::Value = client
+        .put(url)
+        .bearer_auth(&bridge.credential)
+        .json(&serde_json::json!({
+            "msgtype": "m.text",
+            "body": body,
+            RCHAT_MESSAGE_ID_KEY: local_id,
+        }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    response
+        .get("event_id")
+        .and_then(|v| v.as_str())
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| "send response missing event_id".to_string())
+}
+
+/// Extract text messages for `room_id` from a `/sync` response, skipping our own echoes
+/// and anything an rchat bridge posted.
+pub fn parse_sync_messages(
+    body: &serde_json::Value,
+    room_id: &str,
+    own_user_id: &str,
+) -> Vec<InboundBridgeMessage> {
+    let Some(events) = body
+        .pointer("/rooms/join")
+        .and_then(|join| join.get(room_id))
+        .and_then(|room| room.pointer("/timeline/events"))
+        .and_then(|events| events.as_array())
+    else {
+        return Vec::new();
+    };
+
+    events
+        .iter()
+        .filter(|event| event.get("type").and_then(|t| t.as_str()) == Some("m.room.message"))
+        .filter_map(|event| {
+            let sender = event.get("sender")?.as_str()?;
+            if sender == own_user_id {
+                return None;
+            }
+            let content = event.get("content")?;
+            if content.get(RCHAT_MESSAGE_ID_KEY).is_some() {
+                return None;
+            }
+            let msgtype = content.get("msgtype")?.as_str()?;
+            if msgtype != "m.text" && msgtype != "m.emote" && msgtype != "m.notice" {
+                return None;
+            }
+            let text = content.get("body")?.as_str()?.trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(InboundBridgeMessage {
+                remote_id: event
+                    .get("event_id")
+                    .and_then(|id| id.as_str())
+                    .map(ToOwned::to_owned),
+                remote_user: sender.to_string(),
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+async fn run_session(
+    client: &reqwest::Client,
+    bridge: &ChatBridge,
+    outbound_rx: &mut mpsc::Receiver<OutboundBridgeMessage>,
+    inbound_tx: &mpsc::Sender<ConnectorEvent>,
+) -> Result<(), String> {
+    let own_user_id = whoami(client, &bridge.endpoint, &bridge.credential).await?;
+    // Initial sync only establishes the position; history is not replayed into the group.
+    let initial = sync(client, bridge, None).await?;
+    let mut since = initial
+        .get("next_batch")
+        .and_then(|v| v.as_str())
+        .map(ToOwned::to_owned);
+    println!(
+        "[Bridge] ✅ Matrix connected as {} in {}",
+        own_user_id, bridge.target
+    );
+
+    let mut limiter = RateLimiter::default();
+
+    loop {
+        tokio::select! {
+            outbound = outbound_rx.recv() => {
+                let Some(msg) = outbound else {
+                    return Ok(());
+                };
+                limiter.acquire().await;
+                let body = super::format_outbound_text(&msg.sender_name, &msg.text);
+                let remote_id = send_text(client, bridge, &msg.local_id, &body).await?;
+                let sent = ConnectorEvent::Sent {
+                    local_id: msg.local_id,
+                    remote_id,
+                };
+                if inbound_tx.send(sent).await.is_err() {
+                    return Ok(());
+                }
+            }
+            response = sync(client, bridge, since.as_deref()) => {
+                let response = response?;
+                for msg in parse_sync_messages(&response, &bridge.target, &own_user_id) {
+                    if inbound_tx.send(ConnectorEvent::Received(msg)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                if let Some(next) = response.get("next_batch").and_then(|v| v.as_str()) {
+                    since = Some(next.to_string());
+                }
+            }
+        }
+    }
+}
+
+pub async fn run(
+    bridge: ChatBridge,
+    mut outbound_rx: mpsc::Receiver<OutboundBridgeMessage>,
+    inbound_tx: mpsc::Sender<ConnectorEvent>,
+) {
+    let client = reqwest::Client::new();
+    let mut attempts: u32 = 0;
+
+    loop {
+        match run_session(&client, &bridge, &mut outbound_rx, &inbound_tx).await {
+            Ok(()) => return,
+            Err(e) => {
+                let delay = super::reconnect_delay(attempts);
+                attempts = attempts.saturating_add(1);
+                eprintln!(
+                    "[Bridge] ❌ Matrix session for {} failed: {} (retrying in {}s)",
+                    bridge.chat_id,
+                    e,
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_events_and_skips_own_and_bridged_echoes() {
+        let body = serde_json::json!({
+            "next_batch": "s2",
+            "rooms": { "join": { "!room:example.org": { "timeline": { "events": [
+                { "type": "m.room.message", "sender": "@alice:example.org", "event_id": "$a1",
+                  "content": { "msgtype": "m.text", "body": "hi there" } },
+                { "type": "m.room.message", "sender": "@carol-bridge:example.org",
+                  "content": { "msgtype": "m.text", "body": "<carol> looped",
+                               "io.github.ata-sesli.rchat.message_id": "m9" } },
+                { "type": "m.room.message", "sender": "@bridge:example.org",
+                  "content": { "msgtype": "m.text", "body": "<me> echo" } },
+                { "type": "m.room.member", "sender": "@carol:example.org",
+                  "content": { "membership": "join" } },
+                { "type": "m.room.message", "sender": "@dave:example.org",
+                  "content": { "msgtype": "m.image", "body": "cat.png" } }
+            ] } } } }
+        });
+
+        let msgs = parse_sync_messages(&body, "!room:example.org", "@bridge:example.org");
+        assert_eq!(
+            msgs,
+            vec![InboundBridgeMessage {
+                remote_id: Some("$a1".to_string()),
+                remote_user: "@alice:example.org".to_string(),
+                text: "hi there".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn api_url_appends_room_segments() {
+        let url = api_url(
+            "https://matrix.example.org/",
+            &["_matrix", "client", "v3", "rooms", "!abc:example.org"],
+        )
+        .expect("url");
+        assert_eq!(
+            url.as_str(),
+            "https://matrix.example.org/_matrix/client/v3/rooms/!abc:example.org"
+        );
+    }
+}
@@ -0,0 +1,648 @@
+//! Relays selected group chats to external networks (Matrix rooms, IRC channels, XMPP
+//! MUCs) and back.
+//!
+//! Each enabled `chat_bridges` row gets a connector task. Group messages seen locally are
+//! pushed to the connector; remote messages are stored, shown in the UI and republished
+//! to the group on behalf of the remote user.
+//!
+//! Bridged messages are kept from looping back: `bridge_messages` maps rchat message ids to
+//! remote event ids, republished messages carry `GroupMessageEnvelope::bridged` so other
+//! members' bridges skip them, and connectors drop remote events they sent themselves.
+//!
+//! IRC and XMPP have no per-message sender, so rchat senders appear as `<nick> text`.
+//! `bridge_nicks` keeps each peer's nick stable and distinct from remote users' nicks.
+//!
+//! Bridge secrets (Matrix tokens, XMPP logins) live in the encrypted config, in
+//! `SystemConfig::bridge_secrets`, not in the database; the UI only learns whether
+//! a bridge has one.
+
+pub mod irc;
+pub mod matrix;
+pub mod xmpp;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::AppHandle;
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::network::command::NetworkCommand;
+use crate::network::gossip::{GroupContentType, GroupMessageEnvelope};
+use crate::storage::db::ChatBridge;
+
+const OUTBOUND_QUEUE_CAPACITY: usize = 64;
+const INBOUND_QUEUE_CAPACITY: usize = 64;
+const RATE_LIMIT_BURST: u32 = 5;
+const RATE_LIMIT_PER_SEC: f64 = 1.0;
+const MAX_RECONNECT_DELAY_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeKind {
+    Matrix,
+    Irc,
+    Xmpp,
+}
+
+impl BridgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Matrix => "matrix",
+            Self::Irc => "irc",
+            Self::Xmpp => "xmpp",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "matrix" => Some(Self::Matrix),
+            "irc" => Some(Self::Irc),
+            "xmpp" => Some(Self::Xmpp),
+            _ => None,
+        }
+    }
+}
+
+/// A group message leaving rchat for the remote network.
+#[derive(Debug, Clone)]
+pub struct OutboundBridgeMessage {
+    pub local_id: String,
+    pub sender_name: String,
+    pub text: String,
+}
+
+/// A message arriving from the remote network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundBridgeMessage {
+    /// Event id on networks that have them (Matrix).
+    pub remote_id: Option<String>,
+    pub remote_user: String,
+    pub text: String,
+}
+
+/// What a connector reports back to the relay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectorEvent {
+    Received(InboundBridgeMessage),
+    /// An outbound message was posted as `remote_id`.
+    Sent {
+        local_id: String,
+        remote_id: String,
+    },
+}
+
+/// Token bucket used to keep relays under the remote network's flood limits.
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, refill_per_sec: f64) -> Self {
+        Self {
+            capacity: burst as f64,
+            refill_per_sec,
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    pub fn try_acquire_at(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Wait until a token is available, then take it.
+    pub async fn acquire(&mut self) {
+        while !self.try_acquire() {
+            let missing = (1.0 - self.tokens).max(0.0);
+            let wait = missing / self.refill_per_sec.max(f64::EPSILON);
+            tokio::time::sleep(Duration::from_secs_f64(wait.max(0.01))).await;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RATE_LIMIT_BURST, RATE_LIMIT_PER_SEC)
+    }
+}
+
+/// Exponential reconnect delay shared by the connectors.
+pub(crate) fn reconnect_delay(attempts: u32) -> Duration {
+    let secs = (1u64 << attempts.min(6)).min(MAX_RECONNECT_DELAY_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Text as it appears on the remote side, prefixed with the rchat sender.
+pub fn format_outbound_text(sender_name: &str, text: &str) -> String {
+    format!("<{}> {}", sender_name, text)
+}
+
+/// A nick the remote side accepts, from an rchat display name: ASCII letters,
+/// digits, `-` and `_`, at most 16 characters.
+pub fn remote_nick(name: &str) -> String {
+    let nick: String = name
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(16)
+        .collect();
+    if nick.trim_matches('_').is_empty() {
+        "rchat".to_string()
+    } else {
+        nick
+    }
+}
+
+/// Synthetic peer id used to attribute bridged messages in the local DB.
+pub fn bridge_peer_id(kind: BridgeKind, remote_user: &str) -> String {
+    format!("bridge:{}:{}", kind.as_str(), remote_user)
+}
+
+/// Fallback display name when no identity mapping is configured.
+pub fn default_display_name(kind: BridgeKind, remote_user: &str) -> String {
+    let short = match kind {
+        // @alice:example.org -> alice
+        BridgeKind::Matrix => remote_user
+            .trim_start_matches('@')
+            .split(':')
+            .next()
+            .unwrap_or(remote_user),
+        BridgeKind::Irc | BridgeKind::Xmpp => remote_user,
+    };
+    format!("{} ({})", short, kind.as_str())
+}
+
+struct BridgeHandle {
+    kind: BridgeKind,
+    outbound_tx: mpsc::Sender<OutboundBridgeMessage>,
+    tasks: Vec<tauri::async_runtime::JoinHandle<()>>,
+}
+
+/// Running connectors, keyed by group chat id. Managed as Tauri state.
+#[derive(Default)]
+pub struct BridgeRuntime {
+    active: Mutex<HashMap<String, BridgeHandle>>,
+}
+
+/// Start (or restart) the connector for a bridge row.
+pub async fn start_bridge(app_handle: &AppHandle, bridge: ChatBridge) -> Result<(), String> {
+    let Some(kind) = BridgeKind::parse(&bridge.kind) else {
+        return Err(format!("Unsupported bridge kind: {}", bridge.kind));
+    };
+    let Some(runtime) = app_handle.try_state::<BridgeRuntime>() else {
+        return Err("Bridge runtime not initialized".to_string());
+    };
+    let bridge = with_credential(app_handle, kind, bridge).await?;
+
+    stop_bridge(app_handle, &bridge.chat_id).await;
+
+    let (outbound_tx, outbound_rx) = mpsc::channel(OUTBOUND_QUEUE_CAPACITY);
+    let (inbound_tx, mut inbound_rx) = mpsc::channel(INBOUND_QUEUE_CAPACITY);
+
+    let connector = {
+        let bridge = bridge.clone();
+        tauri::async_runtime::spawn(async move {
+            match kind {
+                BridgeKind::Matrix => matrix::run(bridge, outbound_rx, inbound_tx).await,
+                BridgeKind::Irc => irc::run(bridge, outbound_rx, inbound_tx).await,
+                BridgeKind::Xmpp => xmpp::run(bridge, outbound_rx, inbound_tx).await,
+            }
+        })
+    };
+
+    let relay = {
+        let app_handle = app_handle.clone();
+        let chat_id = bridge.chat_id.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut limiter = RateLimiter::default();
+            while let Some(event) = inbound_rx.recv().await {
+                let msg = match event {
+                    ConnectorEvent::Received(msg) => msg,
+                    ConnectorEvent::Sent {
+                        local_id,
+                        remote_id,
+                    } => {
+                        record_mapping(&app_handle, &chat_id, &local_id, &remote_id, "out");
+                        continue;
+                    }
+                };
+                if !limiter.try_acquire() {
+                    eprintln!(
+                        "[Bridge] ⚠️ Dropping inbound {} message for {}: rate limited",
+                        kind.as_str(),
+                        chat_id
+                    );
+                    continue;
+                }
+                if let Err(e) = relay_inbound(&app_handle, &chat_id, kind, msg).await {
+                    eprintln!("[Bridge] ❌ Failed to relay inbound message: {}", e);
+                }
+            }
+        })
+    };
+
+    println!(
+        "[Bridge] ✅ Started {} bridge for {} -> {}",
+        kind.as_str(),
+        bridge.chat_id,
+        bridge.target
+    );
+    runtime.active.lock().await.insert(
+        bridge.chat_id.clone(),
+        BridgeHandle {
+            kind,
+            outbound_tx,
+            tasks: vec![connector, relay],
+        },
+    );
+    Ok(())
+}
+
+/// Fill in the bridge's secret from `SystemConfig`, or the Matrix account when a Matrix
+/// bridge has no login of its own.
+async fn with_credential(
+    app_handle: &AppHandle,
+    kind: BridgeKind,
+    mut bridge: ChatBridge,
+) -> Result<ChatBridge, String> {
+    if kind == BridgeKind::Irc {
+        return Ok(bridge);
+    }
+    let system = {
+        let state = app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await.map_err(|e| e.to_string())?.system
+    };
+    bridge.credential = system
+        .bridge_secrets
+        .get(&bridge.chat_id)
+        .cloned()
+        .unwrap_or_default();
+    if kind == BridgeKind::Xmpp
+        || (!bridge.endpoint.trim().is_empty() && !bridge.credential.trim().is_empty())
+    {
+        return Ok(bridge);
+    }
+    let account = system
+        .matrix
+        .ok_or_else(|| "No Matrix account configured".to_string())?;
+    bridge.endpoint = account.homeserver;
+    bridge.credential = account.access_token;
+    Ok(bridge)
+}
+
+/// Store (`Some`) or forget (`None`) a bridge's secret in the encrypted config.
+pub async fn set_bridge_secret(
+    app_handle: &AppHandle,
+    chat_id: &str,
+    secret: Option<String>,
+) -> Result<(), String> {
+    let state = app_handle.state::<crate::AppState>();
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let changed = match secret {
+        Some(secret) => {
+            config
+                .system
+                .bridge_secrets
+                .insert(chat_id.to_string(), secret.clone())
+                != Some(secret)
+        }
+        None => config.system.bridge_secrets.remove(chat_id).is_some(),
+    };
+    if changed {
+        mgr.save(&config).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Whether a bridge has a secret of its own in the encrypted config.
+pub async fn has_bridge_secret(app_handle: &AppHandle, chat_id: &str) -> Result<bool, String> {
+    let state = app_handle.state::<crate::AppState>();
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await.map_err(|e| e.to_string())?;
+    Ok(config.system.bridge_secrets.contains_key(chat_id))
+}
+
+/// Move secrets older versions stored in `chat_bridges.credential` into the encrypted
+/// config. Needs the vault to be unlocked; runs before bridges start.
+pub async fn move_legacy_credentials(app_handle: &AppHandle) {
+    let legacy = {
+        let state = app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        crate::storage::db::legacy_bridge_credentials(&conn).unwrap_or_default()
+    };
+    if legacy.is_empty() {
+        return;
+    }
+    {
+        let state = app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        let saved = match mgr.load().await {
+            Ok(mut config) => {
+                for (chat_id, secret) in &legacy {
+                    config
+                        .system
+                        .bridge_secrets
+                        .entry(chat_id.clone())
+                        .or_insert_with(|| secret.clone());
+                }
+                mgr.save(&config).await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            eprintln!(
+                "[Bridge] ❌ Failed to move bridge secrets to the vault: {}",
+                e
+            );
+            return;
+        }
+    }
+    let state = app_handle.state::<crate::AppState>();
+    let Ok(conn) = state.db_conn.lock() else {
+        return;
+    };
+    for (chat_id, _) in &legacy {
+        if let Err(e) = crate::storage::db::clear_bridge_credential(&conn, chat_id) {
+            eprintln!(
+                "[Bridge] ❌ Failed to clear stored secret of {}: {}",
+                chat_id, e
+            );
+        }
+    }
+    println!(
+        "[Bridge] 🔒 Moved {} bridge secret(s) into the vault",
+        legacy.len()
+    );
+}
+
+fn record_mapping(
+    app_handle: &AppHandle,
+    chat_id: &str,
+    local_id: &str,
+    remote_id: &str,
+    direction: &str,
+) {
+    let state = app_handle.state::<crate::AppState>();
+    let Ok(conn) = state.db_conn.lock() else {
+        return;
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    if let Err(e) = crate::storage::db::record_bridge_message(
+        &conn, chat_id, local_id, remote_id, direction, now,
+    ) {
+        eprintln!("[Bridge] ⚠️ Failed to record message mapping: {}", e);
+    }
+}
+
+pub async fn stop_bridge(app_handle: &AppHandle, chat_id: &str) {
+    let Some(runtime) = app_handle.try_state::<BridgeRuntime>() else {
+        return;
+    };
+    if let Some(handle) = runtime.active.lock().await.remove(chat_id) {
+        for task in handle.tasks {
+            task.abort();
+        }
+        println!("[Bridge] Stopped bridge for {}", chat_id);
+    }
+}
+
+/// Start connectors for every enabled bridge. Called once the network is up.
+pub async fn start_enabled_bridges(app_handle: &AppHandle) {
+    move_legacy_credentials(app_handle).await;
+    let bridges = {
+        let state = app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        crate::storage::db::list_enabled_chat_bridges(&conn).unwrap_or_default()
+    };
+
+    for bridge in bridges {
+        let chat_id = bridge.chat_id.clone();
+        if let Err(e) = start_bridge(app_handle, bridge).await {
+            eprintln!("[Bridge] ❌ Failed to start bridge for {}: {}", chat_id, e);
+        }
+    }
+}
+
+/// Forward a group message to the chat's bridge, if one is running. Messages that came in
+/// through a bridge are not sent back out.
+pub async fn relay_outbound(
+    app_handle: &AppHandle,
+    chat_id: &str,
+    msg_id: &str,
+    sender_id: &str,
+    sender_name: &str,
+    content_type: GroupContentType,
+    text: Option<&str>,
+) {
+    let Some(runtime) = app_handle.try_state::<BridgeRuntime>() else {
+        return;
+    };
+    let active = runtime.active.lock().await;
+    let Some(handle) = active.get(chat_id) else {
+        return;
+    };
+    let sender_name = {
+        let state = app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        if crate::storage::db::is_inbound_bridge_message(&conn, chat_id, msg_id) {
+            return;
+        }
+        match handle.kind {
+            BridgeKind::Matrix => sender_name.to_string(),
+            BridgeKind::Irc | BridgeKind::Xmpp => crate::storage::db::bridge_nick_for_peer(
+                &conn,
+                chat_id,
+                sender_id,
+                &remote_nick(sender_name),
+            )
+            .unwrap_or_else(|_| remote_nick(sender_name)),
+        }
+    };
+
+    let text = match content_type {
+        GroupContentType::Text => match text.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(t) => t.to_string(),
+            None => return,
+        },
+        other => format!("[{}]", other.as_str()),
+    };
+
+    if let Err(e) = handle.outbound_tx.try_send(OutboundBridgeMessage {
+        local_id: msg_id.to_string(),
+        sender_name,
+        text,
+    }) {
+        eprintln!(
+            "[Bridge] ⚠️ Dropping outbound message for {}: {}",
+            chat_id, e
+        );
+    }
+}
+
+async fn relay_inbound(
+    app_handle: &AppHandle,
+    chat_id: &str,
+    kind: BridgeKind,
+    msg: InboundBridgeMessage,
+) -> Result<(), String> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let msg_id = crate::chat::message_id::new_message_id();
+    let peer_id = bridge_peer_id(kind, &msg.remote_user);
+
+    let db_msg = {
+        let state = app_handle.state::<crate::AppState>();
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        if let Some(remote_id) = &msg.remote_id {
+            if crate::storage::db::bridge_message_local_id(&conn, chat_id, remote_id)
+                .map_err(|e| e.to_string())?
+                .is_some()
+            {
+                // Already relayed, e.g. replayed after a reconnect.
+                return Ok(());
+            }
+        }
+        let display_name =
+            crate::storage::db::get_bridge_identity(&conn, chat_id, &msg.remote_user)
+                .map_err(|e| e.to_string())?
+                .unwrap_or_else(|| default_display_name(kind, &msg.remote_user));
+
+        crate::storage::db::add_peer(&conn, &peer_id, Some(&display_name), None, "bridge")
+            .map_err(|e| e.to_string())?;
+        if kind != BridgeKind::Matrix {
+            crate::storage::db::record_bridge_nick(&conn, chat_id, &peer_id, &msg.remote_user)
+                .map_err(|e| e.to_string())?;
+        }
+
+        let db_msg = crate::storage::db::Message {
+            id: msg_id.clone(),
+            chat_id: chat_id.to_string(),
+            peer_id,
+            timestamp,
+            content: crate::chat::message::MessageContent::Text {
+                text: msg.text.clone(),
+            },
+            status: "delivered".to_string(),
+            content_metadata: None,
+            sender_alias: Some(display_name),
+            logical: 0,
+        };
+        crate::storage::db::insert_message(&conn, &db_msg).map_err(|e| e.to_string())?;
+        if let Some(remote_id) = &msg.remote_id {
+            crate::storage::db::record_bridge_message(
+                &conn, chat_id, &msg_id, remote_id, "in", timestamp,
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        crate::storage::journal::record_message(&state.journal, &conn, &db_msg);
+        db_msg
+    };
+
+    crate::chat::notify::dispatch(app_handle, &db_msg);
+    crate::chat::suggest::on_message_received(app_handle, &db_msg);
+    let _ = app_handle.emit("message-received", db_msg.clone());
+
+    // Republish so the rest of the group sees the bridged message.
+    if let Some(net_state) = app_handle.try_state::<crate::NetworkState>() {
+        let envelope = GroupMessageEnvelope {
+            id: msg_id,
+            group_id: chat_id.to_string(),
+            sender_id: "Me".to_string(),
+            sender_alias: db_msg.sender_alias.clone(),
+            timestamp,
+            content_type: GroupContentType::Text,
+            text_content: Some(msg.text),
+            file_hash: None,
+            signature: None,
+            logical: None,
+            bridged: Some(kind.as_str().to_string()),
+            content: None,
+        };
+        let tx = net_state.sender.lock().await;
+        tx.send(NetworkCommand::PublishGroup { envelope })
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_allows_burst_then_refills() {
+        let mut limiter = RateLimiter::new(2, 1.0);
+        let start = limiter.last_refill;
+        assert!(limiter.try_acquire_at(start));
+        assert!(limiter.try_acquire_at(start));
+        assert!(!limiter.try_acquire_at(start));
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(1100)));
+    }
+
+    #[test]
+    fn default_display_name_shortens_matrix_ids() {
+        assert_eq!(
+            default_display_name(BridgeKind::Matrix, "@alice:example.org"),
+            "alice (matrix)"
+        );
+        assert_eq!(default_display_name(BridgeKind::Irc, "bob"), "bob (irc)");
+        assert_eq!(
+            bridge_peer_id(BridgeKind::Irc, "bob"),
+            "bridge:irc:bob".to_string()
+        );
+    }
+
+    #[test]
+    fn parses_bridge_kind_case_insensitively() {
+        assert_eq!(BridgeKind::parse("Matrix"), Some(BridgeKind::Matrix));
+        assert_eq!(BridgeKind::parse(" irc "), Some(BridgeKind::Irc));
+        assert_eq!(BridgeKind::parse("XMPP"), Some(BridgeKind::Xmpp));
+        assert_eq!(BridgeKind::parse("slack"), None);
+    }
+
+    #[test]
+    fn remote_nicks_are_ascii_and_short() {
+        assert_eq!(remote_nick("Alice Smith"), "Alice_Smith");
+        assert_eq!(remote_nick("Zoë"), "Zo");
+        assert_eq!(remote_nick("日本"), "rchat");
+        assert_eq!(remote_nick(&"x".repeat(40)).len(), 16);
+    }
+}
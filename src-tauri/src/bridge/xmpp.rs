@@ -0,0 +1,531 @@
+//! Minimal plain-TCP XMPP connector for interop demos: logs in with SASL PLAIN, joins the
+//! bridged MUC and relays groupchat messages both ways. There is no TLS, so it is meant for
+//! local or test servers that allow unencrypted logins.
+//!
+//! The bridge row holds `host:port` as the endpoint, `room@conference.example.org` (optionally
+//! `/nick`) as the target and `user@example.org:password` as the credential.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use super::{ConnectorEvent, InboundBridgeMessage, OutboundBridgeMessage, RateLimiter};
+use crate::storage::db::ChatBridge;
+
+/// Stanzas larger than this end the session.
+const MAX_STANZA_BYTES: usize = 256 * 1024;
+/// Ids of messages sent by an rchat bridge start with this, so other bridges can skip them.
+const RCHAT_ID_PREFIX: &str = "rchat-";
+
+struct Login {
+    domain: String,
+    username: String,
+    password: String,
+    room: String,
+    nick: String,
+}
+
+impl Login {
+    fn from_bridge(bridge: &ChatBridge) -> Result<Self, String> {
+        let (jid, password) = bridge
+            .credential
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| "XMPP credential must be user@domain:password".to_string())?;
+        let (username, domain) = jid
+            .split_once('@')
+            .ok_or_else(|| "XMPP credential must be user@domain:password".to_string())?;
+        let (room, nick) = match bridge.target.trim().split_once('/') {
+            Some((room, nick)) => (room, nick),
+            None => (bridge.target.trim(), username),
+        };
+        if username.is_empty() || domain.is_empty() || room.is_empty() || nick.is_empty() {
+            return Err("XMPP room and credential must not be empty".to_string());
+        }
+        Ok(Self {
+            domain: domain.to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+            room: room.to_string(),
+            nick: nick.to_string(),
+        })
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\'' => out.push_str("&apos;"),
+            '"' => out.push_str("&quot;"),
+            // Other control characters are not allowed in XML.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "apos" => Some('\''),
+            "quot" => Some('"'),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Splits the incoming byte stream into top-level stanzas. The stream header and XML
+/// declaration are dropped; the closing `</stream:stream>` is returned as is.
+#[derive(Default)]
+pub struct StanzaReader {
+    buf: Vec<u8>,
+}
+
+impl StanzaReader {
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn next_stanza(&mut self) -> Option<String> {
+        loop {
+            // Whitespace between stanzas is a keepalive.
+            let start = self.buf.iter().position(|b| !b.is_ascii_whitespace())?;
+            self.buf.drain(..start);
+
+            let rest = &self.buf[..];
+            let header = rest.starts_with(b"<?") || rest.starts_with(b"<stream:stream");
+            if header {
+                let end = rest.iter().position(|&b| b == b'>')?;
+                self.buf.drain(..=end);
+                continue;
+            }
+            let end = element_end(rest)?;
+            let stanza = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+            self.buf.drain(..end);
+            return Some(stanza);
+        }
+    }
+}
+
+/// Length of the complete element at the start of `data`, if it has fully arrived.
+fn element_end(data: &[u8]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut pos = 0;
+    loop {
+        let open = pos + data[pos..].iter().position(|&b| b == b'<')?;
+        let close = open + data[open..].iter().position(|&b| b == b'>')?;
+        let tag = &data[open..=close];
+        if tag.starts_with(b"</") {
+            depth = depth.saturating_sub(1);
+        } else if !tag.ends_with(b"/>") && !tag.starts_with(b"<?") && !tag.starts_with(b"<!") {
+            depth += 1;
+        }
+        pos = close + 1;
+        if depth == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+fn tag_name(stanza: &str) -> &str {
+    let tag = stanza.trim_start_matches('<');
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+/// Attribute of the stanza's opening tag.
+fn attr(stanza: &str, name: &str) -> Option<String> {
+    let open_tag = &stanza[..stanza.find('>')?];
+    for quote in ['\'', '"'] {
+        let needle = format!(" {}={}", name, quote);
+        if let Some(start) = open_tag.find(&needle) {
+            let value = &open_tag[start + needle.len()..];
+            return Some(unescape(&value[..value.find(quote)?]));
+        }
+    }
+    None
+}
+
+/// Text of the first `<name>` child, unescaped.
+fn child_text(stanza: &str, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let start = stanza
+        .match_indices(&open)
+        .map(|(i, _)| i)
+        .find(|&i| matches!(stanza.as_bytes().get(i + open.len()), Some(b'>' | b' ')))?;
+    let content_start = start + stanza[start..].find('>')? + 1;
+    let content_end = content_start + stanza[content_start..].find(&format!("</{}>", name))?;
+    Some(unescape(&stanza[content_start..content_end]))
+}
+
+/// A groupchat message for `room`, unless it is ours, replayed history or posted by an
+/// rchat bridge.
+pub fn parse_groupchat(stanza: &str, room: &str, own_nick: &str) -> Option<InboundBridgeMessage> {
+    if tag_name(stanza) != "message" || attr(stanza, "type").as_deref() != Some("groupchat") {
+        return None;
+    }
+    let from = attr(stanza, "from")?;
+    let (from_room, nick) = from.split_once('/')?;
+    if !from_room.eq_ignore_ascii_case(room) || nick.is_empty() || nick == own_nick {
+        return None;
+    }
+    if stanza.contains("<delay ") {
+        return None;
+    }
+    let id = attr(stanza, "id");
+    if id
+        .as_deref()
+        .is_some_and(|id| id.starts_with(RCHAT_ID_PREFIX))
+    {
+        return None;
+    }
+    let text = child_text(stanza, "body")?;
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    // `/me` arrives as plain text.
+    let text = match text.strip_prefix("/me ") {
+        Some(action) => format!("* {} {}", nick, action),
+        None => text.to_string(),
+    };
+    let stanza_id = stanza
+        .find("<stanza-id ")
+        .and_then(|start| attr(&stanza[start..], "id"));
+    Some(InboundBridgeMessage {
+        remote_id: stanza_id.or(id),
+        remote_user: nick.to_string(),
+        text,
+    })
+}
+
+const BIND_REQUEST: &str = "<iq type='set' id='bind'>\
+    <bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'><resource>rchat</resource></bind>\
+    </iq>";
+
+fn stream_header(domain: &str) -> String {
+    format!(
+        "<?xml version='1.0'?><stream:stream to='{}' version='1.0' xmlns='jabber:client' \
+         xmlns:stream='http://etherx.jabber.org/streams'>",
+        escape(domain)
+    )
+}
+
+/// Join `room` as `nick` without replaying its history.
+fn join_presence(room: &str, nick: &str) -> String {
+    format!(
+        "<presence to='{}/{}'><x xmlns='http://jabber.org/protocol/muc'>\
+         <history maxstanzas='0'/></x></presence>",
+        escape(room),
+        escape(nick)
+    )
+}
+
+fn groupchat_message(room: &str, id: &str, body: &str) -> String {
+    format!(
+        "<message to='{}' type='groupchat' id='{}'><body>{}</body></message>",
+        escape(room),
+        escape(id),
+        escape(body)
+    )
+}
+
+async fn send(write_half: &mut OwnedWriteHalf, data: &str) -> Result<(), String> {
+    write_half
+        .write_all(data.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn read_stanza(
+    read_half: &mut OwnedReadHalf,
+    reader: &mut StanzaReader,
+) -> Result<String, String> {
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(stanza) = reader.next_stanza() {
+            if stanza.starts_with("</stream:stream") || stanza.starts_with("<stream:error") {
+                return Err(format!("server closed the stream: {}", stanza));
+            }
+            return Ok(stanza);
+        }
+        if reader.buffered() > MAX_STANZA_BYTES {
+            return Err("stanza too large".to_string());
+        }
+        let n = read_half
+            .read(&mut chunk)
+            .await
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("server closed connection".to_string());
+        }
+        reader.push(&chunk[..n]);
+    }
+}
+
+async fn read_features(
+    read_half: &mut OwnedReadHalf,
+    reader: &mut StanzaReader,
+) -> Result<String, String> {
+    loop {
+        let stanza = read_stanza(read_half, reader).await?;
+        if tag_name(&stanza) == "stream:features" {
+            return Ok(stanza);
+        }
+    }
+}
+
+async fn run_session(
+    login: &Login,
+    bridge: &ChatBridge,
+    outbound_rx: &mut mpsc::Receiver<OutboundBridgeMessage>,
+    inbound_tx: &mpsc::Sender<ConnectorEvent>,
+) -> Result<(), String> {
+    let stream = TcpStream::connect(bridge.endpoint.trim())
+        .await
+        .map_err(|e| format!("connect {}: {}", bridge.endpoint, e))?;
+    let (mut read_half, mut write_half) = stream.into_split();
+    let mut reader = StanzaReader::default();
+
+    send(&mut write_half, &stream_header(&login.domain)).await?;
+    let features = read_features(&mut read_half, &mut reader).await?;
+    if !features.contains("<mechanism>PLAIN</mechanism>") {
+        return Err("server offers no plain login; TLS is not supported".to_string());
+    }
+    let token = BASE64.encode(format!("\0{}\0{}", login.username, login.password));
+    send(
+        &mut write_half,
+        &format!(
+            "<auth xmlns='urn:ietf:params:xml:ns:xmpp-sasl' mechanism='PLAIN'>{}</auth>",
+            token
+        ),
+    )
+    .await?;
+    let outcome = read_stanza(&mut read_half, &mut reader).await?;
+    if tag_name(&outcome) != "success" {
+        return Err("login failed".to_string());
+    }
+
+    send(&mut write_half, &stream_header(&login.domain)).await?;
+    read_features(&mut read_half, &mut reader).await?;
+    send(&mut write_half, BIND_REQUEST).await?;
+    loop {
+        let stanza = read_stanza(&mut read_half, &mut reader).await?;
+        if tag_name(&stanza) == "iq" && attr(&stanza, "id").as_deref() == Some("bind") {
+            if attr(&stanza, "type").as_deref() != Some("result") {
+                return Err("resource binding failed".to_string());
+            }
+            break;
+        }
+    }
+
+    send(&mut write_half, &join_presence(&login.room, &login.nick)).await?;
+    println!(
+        "[Bridge] ✅ XMPP connected as {} in {}",
+        login.nick, login.room
+    );
+
+    let mut limiter = RateLimiter::default();
+    loop {
+        tokio::select! {
+            stanza = read_stanza(&mut read_half, &mut reader) => {
+                let stanza = stanza?;
+                match tag_name(&stanza) {
+                    "iq" if matches!(attr(&stanza, "type").as_deref(), Some("get" | "set")) => {
+                        let id = escape(&attr(&stanza, "id").unwrap_or_default());
+                        let to = escape(&attr(&stanza, "from").unwrap_or_default());
+                        let reply = if stanza.contains("<ping ") {
+                            format!("<iq type='result' id='{}' to='{}'/>", id, to)
+                        } else {
+                            format!(
+                                "<iq type='error' id='{}' to='{}'><error type='cancel'>\
+                                 <service-unavailable xmlns='urn:ietf:params:xml:ns:xmpp-stanzas'/>\
+                                 </error></iq>",
+                                id, to
+                            )
+                        };
+                        send(&mut write_half, &reply).await?;
+                    }
+                    "presence" if attr(&stanza, "type").as_deref() == Some("error") => {
+                        return Err(format!("could not join {}", login.room));
+                    }
+                    "message" => {
+                        if let Some(msg) = parse_groupchat(&stanza, &login.room, &login.nick) {
+                            if inbound_tx.send(ConnectorEvent::Received(msg)).await.is_err() {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            outbound = outbound_rx.recv() => {
+                let Some(msg) = outbound else {
+                    let goodbye = "<presence type='unavailable'/></stream:stream>";
+                    let _ = send(&mut write_half, goodbye).await;
+                    return Ok(());
+                };
+                limiter.acquire().await;
+                let id = format!("{}{}", RCHAT_ID_PREFIX, msg.local_id);
+                let body = super::format_outbound_text(&msg.sender_name, &msg.text);
+                send(&mut write_half, &groupchat_message(&login.room, &id, &body)).await?;
+                let sent = ConnectorEvent::Sent {
+                    local_id: msg.local_id,
+                    remote_id: id,
+                };
+                if inbound_tx.send(sent).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+pub async fn run(
+    bridge: ChatBridge,
+    mut outbound_rx: mpsc::Receiver<OutboundBridgeMessage>,
+    inbound_tx: mpsc::Sender<ConnectorEvent>,
+) {
+    let login = match Login::from_bridge(&bridge) {
+        Ok(login) => login,
+        Err(e) => {
+            eprintln!("[Bridge] ❌ XMPP bridge for {}: {}", bridge.chat_id, e);
+            return;
+        }
+    };
+    let mut attempts: u32 = 0;
+
+    loop {
+        match run_session(&login, &bridge, &mut outbound_rx, &inbound_tx).await {
+            Ok(()) => return,
+            Err(e) => {
+                let delay = super::reconnect_delay(attempts);
+                attempts = attempts.saturating_add(1);
+                eprintln!(
+                    "[Bridge] ❌ XMPP session for {} failed: {} (retrying in {}s)",
+                    bridge.chat_id,
+                    e,
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_splits_stanzas_across_chunks() {
+        let mut reader = StanzaReader::default();
+        reader.push(b"<?xml version='1.0'?><stream:stream from='example.org' id='1'>");
+        reader.push(b"<stream:features><mechanisms><mechanism>PLAIN</mecha");
+        assert_eq!(reader.next_stanza(), None);
+        reader.push(b"nism></mechanisms></stream:features> <success xmlns='x'/>");
+        assert_eq!(
+            reader.next_stanza().as_deref(),
+            Some("<stream:features><mechanisms><mechanism>PLAIN</mechanism></mechanisms></stream:features>")
+        );
+        assert_eq!(
+            reader.next_stanza().as_deref(),
+            Some("<success xmlns='x'/>")
+        );
+        assert_eq!(reader.next_stanza(), None);
+    }
+
+    #[test]
+    fn parses_groupchat_and_skips_echoes() {
+        let room = "rchat@conference.example.org";
+        let msg = parse_groupchat(
+            "<message from='rchat@conference.example.org/alice' type='groupchat' id='a1'><body>1 &lt; 2 &amp; caf&#233;</body><stanza-id xmlns='urn:xmpp:sid:0' id='s1' by='rchat@conference.example.org'/></message>",
+            room,
+            "bridge",
+        )
+        .expect("msg");
+        assert_eq!(msg.remote_user, "alice");
+        assert_eq!(msg.text, "1 < 2 & café");
+        assert_eq!(msg.remote_id.as_deref(), Some("s1"));
+
+        let own = "<message from='rchat@conference.example.org/bridge' type='groupchat'><body>x</body></message>";
+        assert!(parse_groupchat(own, room, "bridge").is_none());
+        let bridged = "<message from='rchat@conference.example.org/other' type='groupchat' id='rchat-m1'><body>&lt;bob&gt; hi</body></message>";
+        assert!(parse_groupchat(bridged, room, "bridge").is_none());
+        let history = "<message from='rchat@conference.example.org/carol' type='groupchat'><body>old</body><delay xmlns='urn:xmpp:delay' stamp='2020-01-01T00:00:00Z'/></message>";
+        assert!(parse_groupchat(history, room, "bridge").is_none());
+        let subject = "<message from='rchat@conference.example.org/carol' type='groupchat'><subject>topic</subject></message>";
+        assert!(parse_groupchat(subject, room, "bridge").is_none());
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        let text = "<b>\"it's\" & \u{1}done</b>";
+        assert_eq!(unescape(&escape(text)), "<b>\"it's\" & done</b>");
+        assert_eq!(unescape("&unknown; &#x41;"), "&unknown; A");
+    }
+
+    #[test]
+    fn login_comes_from_credential_and_target() {
+        let mut bridge = ChatBridge {
+            chat_id: "group:x".to_string(),
+            kind: "xmpp".to_string(),
+            endpoint: "localhost:5222".to_string(),
+            target: "room@conference.example.org".to_string(),
+            nick: String::new(),
+            credential: "bot@example.org:pa:ss".to_string(),
+            enabled: true,
+            created_at: 0,
+        };
+        let login = Login::from_bridge(&bridge).expect("login");
+        assert_eq!(login.username, "bot");
+        assert_eq!(login.domain, "example.org");
+        assert_eq!(login.password, "pa:ss");
+        assert_eq!(login.nick, "bot");
+
+        bridge.target = "room@conference.example.org/rchat".to_string();
+        assert_eq!(Login::from_bridge(&bridge).expect("login").nick, "rchat");
+        bridge.credential = "no-password".to_string();
+        assert!(Login::from_bridge(&bridge).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Automatic download of incoming media.
+//!
+//! Each media type has a global [`AutoDownloadPolicy`] in
+//! `UserConfig::auto_download`, which chats can override per type in their
+//! `chat_settings.auto_download`. When the policy says no, only the message
+//! and its file offer are stored; the chunks are fetched when the user opens
+//! the placeholder (`refetch_media`). The frontend reports whether the current
+//! connection is Wi-Fi (or otherwise unmetered) through `set_network_metered`;
+//! until it does, the connection counts as unmetered.
+
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::storage::config::{AutoDownloadPolicy, AutoDownloadSettings};
+use crate::storage::db;
+
+static DEFAULTS: Mutex<Option<AutoDownloadSettings>> = Mutex::new(None);
+static METERED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_defaults(settings: AutoDownloadSettings) {
+    if let Ok(mut defaults) = DEFAULTS.lock() {
+        *defaults = Some(settings);
+    }
+}
+
+fn defaults() -> AutoDownloadSettings {
+    DEFAULTS
+        .lock()
+        .ok()
+        .and_then(|defaults| *defaults)
+        .unwrap_or_default()
+}
+
+pub fn set_metered(metered: bool) {
+    METERED.store(metered, Ordering::SeqCst);
+}
+
+pub fn is_metered() -> bool {
+    METERED.load(Ordering::SeqCst)
+}
+
+/// Per-type overrides stored in `chat_settings.auto_download`.
+pub fn parse_overrides(raw: Option<&str>) -> BTreeMap<String, AutoDownloadPolicy> {
+    raw.and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default()
+}
+
+/// The chat's override for `content_type`, else the global policy.
+pub fn resolve(
+    defaults: &AutoDownloadSettings,
+    overrides: &BTreeMap<String, AutoDownloadPolicy>,
+    content_type: &str,
+) -> Option<AutoDownloadPolicy> {
+    let global = defaults.policy_for(content_type)?;
+    Some(overrides.get(content_type).copied().unwrap_or(global))
+}
+
+pub fn allows(policy: AutoDownloadPolicy, metered: bool) -> bool {
+    match policy {
+        AutoDownloadPolicy::Always => true,
+        AutoDownloadPolicy::WifiOnly => !metered,
+        AutoDownloadPolicy::Never => false,
+    }
+}
+
+/// Whether media of `content_type` received in `chat_id` is fetched right away.
+pub fn should_download(conn: &Connection, chat_id: &str, content_type: &str) -> bool {
+    let overrides = db::get_chat_settings(conn, chat_id)
+        .map(|settings| parse_overrides(settings.auto_download.as_deref()))
+        .unwrap_or_default();
+    resolve(&defaults(), &overrides, content_type).is_none_or(|policy| allows(policy, is_metered()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_overrides_win_and_wifi_only_waits_for_unmetered() {
+        let defaults = AutoDownloadSettings::default();
+        let overrides = parse_overrides(Some(r#"{"image":"never","video":"always"}"#));
+
+        assert_eq!(
+            resolve(&defaults, &overrides, "image"),
+            Some(AutoDownloadPolicy::Never)
+        );
+        assert_eq!(
+            resolve(&defaults, &overrides, "video"),
+            Some(AutoDownloadPolicy::Always)
+        );
+        assert_eq!(
+            resolve(&defaults, &overrides, "document"),
+            Some(AutoDownloadPolicy::WifiOnly)
+        );
+        assert_eq!(resolve(&defaults, &overrides, "text"), None);
+        assert!(parse_overrides(Some("not json")).is_empty());
+
+        assert!(allows(AutoDownloadPolicy::WifiOnly, false));
+        assert!(!allows(AutoDownloadPolicy::WifiOnly, true));
+        assert!(allows(AutoDownloadPolicy::Always, true));
+        assert!(!allows(AutoDownloadPolicy::Never, false));
+    }
+}
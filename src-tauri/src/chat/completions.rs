@@ -0,0 +1,68 @@
+//! Client for a user-configured OpenAI-compatible `chat/completions` endpoint,
+//! shared by reply suggestions and translation.
+
+use std::time::Duration;
+
+pub struct Client {
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    max_tokens: Option<u32>,
+    http: reqwest::Client,
+}
+
+impl Client {
+    pub fn new(
+        endpoint: String,
+        model: String,
+        api_key: Option<String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            endpoint,
+            model,
+            api_key,
+            timeout,
+            max_tokens: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// The model's reply to `user` under the `system` prompt.
+    pub async fn complete(&self, system: &str, user: &str) -> Result<String, String> {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": user }
+            ]
+        });
+        if let Some(max_tokens) = self.max_tokens {
+            body["max_tokens"] = max_tokens.into();
+        }
+
+        let mut request = self
+            .http
+            .post(&self.endpoint)
+            .timeout(self.timeout)
+            .json(&body);
+        if let Some(key) = self.api_key.as_deref().filter(|k| !k.is_empty()) {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("model endpoint returned {}", response.status()));
+        }
+        let json: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+        json["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "model response has no content".to_string())
+    }
+}
@@ -0,0 +1,384 @@
+//! Text message formatting.
+//!
+//! Every text message goes through [`process`] on the sending and on the
+//! receiving side, so all peers render it the same way without trusting a
+//! pre-rendered copy: line endings and trailing whitespace are normalized,
+//! `:shortcode:` emoji are replaced and basic markdown (bold, italic, strike,
+//! inline code, links, code blocks, quotes and list items) is parsed into
+//! [`Block`]s. The blocks are stored under `formatted` in the message's
+//! `content_metadata`, next to the raw text; plain messages store nothing.
+
+use serde::{Deserialize, Serialize};
+
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("blush", "😊"),
+    ("broken_heart", "💔"),
+    ("clap", "👏"),
+    ("cry", "😢"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("grin", "😁"),
+    ("heart", "❤️"),
+    ("joy", "😂"),
+    ("laughing", "😆"),
+    ("ok_hand", "👌"),
+    ("party", "🥳"),
+    ("pray", "🙏"),
+    ("rage", "😡"),
+    ("rocket", "🚀"),
+    ("sad", "😞"),
+    ("shrug", "🤷"),
+    ("smile", "😄"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("wave", "👋"),
+    ("wink", "😉"),
+    ("x", "❌"),
+    ("white_check_mark", "✅"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Span {
+    Text { text: String },
+    Bold { text: String },
+    Italic { text: String },
+    Strike { text: String },
+    Code { text: String },
+    Link { text: String, url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Block {
+    Paragraph {
+        spans: Vec<Span>,
+    },
+    Quote {
+        spans: Vec<Span>,
+    },
+    ListItem {
+        spans: Vec<Span>,
+    },
+    CodeBlock {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+        code: String,
+    },
+}
+
+/// CRLF to LF, no trailing whitespace per line, at most one blank line in a
+/// row, and no leading or trailing blank lines.
+pub fn normalize_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.replace("\r\n", "\n").replace('\r', "\n").lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_run > 0 { "\n\n" } else { "\n" });
+        }
+        blank_run = 0;
+        out.push_str(line);
+    }
+    out
+}
+
+/// Replace known `:shortcode:`s with their emoji.
+pub fn replace_shortcodes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let emoji = after.find(':').and_then(|end| {
+            SHORTCODES
+                .iter()
+                .find(|(code, _)| *code == &after[..end])
+                .map(|(_, emoji)| (end, *emoji))
+        });
+        match emoji {
+            Some((end, emoji)) => {
+                out.push_str(emoji);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parse inline markdown. Markers without a closing counterpart stay literal;
+/// `_` only opens italics at the start of a word, so `snake_case` is left alone.
+pub fn parse_spans(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let at_word_start = text[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        let parsed = if let Some(inner) = rest.strip_prefix('`') {
+            inner.find('`').map(|end| {
+                (
+                    Span::Code {
+                        text: inner[..end].to_string(),
+                    },
+                    end + 2,
+                )
+            })
+        } else if let Some(inner) = rest.strip_prefix("**") {
+            enclosed(inner, "**").map(|end| {
+                (
+                    Span::Bold {
+                        text: replace_shortcodes(&inner[..end]),
+                    },
+                    end + 4,
+                )
+            })
+        } else if let Some(inner) = rest.strip_prefix("~~") {
+            enclosed(inner, "~~").map(|end| {
+                (
+                    Span::Strike {
+                        text: replace_shortcodes(&inner[..end]),
+                    },
+                    end + 4,
+                )
+            })
+        } else if let Some(inner) = rest.strip_prefix('*') {
+            enclosed(inner, "*").map(|end| {
+                (
+                    Span::Italic {
+                        text: replace_shortcodes(&inner[..end]),
+                    },
+                    end + 2,
+                )
+            })
+        } else if let Some(inner) = rest.strip_prefix('_').filter(|_| at_word_start) {
+            enclosed(inner, "_").map(|end| {
+                (
+                    Span::Italic {
+                        text: replace_shortcodes(&inner[..end]),
+                    },
+                    end + 2,
+                )
+            })
+        } else if rest.starts_with('[') {
+            parse_link(rest)
+        } else {
+            None
+        };
+
+        match parsed {
+            Some((span, len)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::Text {
+                        text: replace_shortcodes(&std::mem::take(&mut plain)),
+                    });
+                }
+                spans.push(span);
+                i += len;
+            }
+            None => {
+                let c = rest.chars().next().unwrap_or_default();
+                plain.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::Text {
+            text: replace_shortcodes(&plain),
+        });
+    }
+    spans
+}
+
+/// Offset of the `marker` closing a non-empty, unpadded run at the start of `inner`.
+fn enclosed(inner: &str, marker: &str) -> Option<usize> {
+    if inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = inner.find(marker)?;
+    (end > 0 && !inner[..end].ends_with(char::is_whitespace) && !inner[..end].contains('\n'))
+        .then_some(end)
+}
+
+/// `[text](http(s)://url)` at the start of `rest`, with its length.
+fn parse_link(rest: &str) -> Option<(Span, usize)> {
+    let close = rest.find("](")?;
+    let text = &rest[1..close];
+    let after = &rest[close + 2..];
+    let end = after.find(')')?;
+    let url = &after[..end];
+    let is_web = url.starts_with("https://") || url.starts_with("http://");
+    if text.is_empty() || text.contains('\n') || !is_web || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((
+        Span::Link {
+            text: replace_shortcodes(text),
+            url: url.to_string(),
+        },
+        close + 2 + end + 1,
+    ))
+}
+
+/// Normalize and parse `text` into blocks.
+pub fn parse(text: &str) -> Vec<Block> {
+    let text = normalize_whitespace(text);
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = text.lines();
+
+    fn flush(paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph {
+                spans: parse_spans(&paragraph.join("\n")),
+            });
+            paragraph.clear();
+        }
+    }
+
+    while let Some(line) = lines.next() {
+        if let Some(language) = line.strip_prefix("```") {
+            flush(&mut paragraph, &mut blocks);
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim_start() == "```" {
+                    break;
+                }
+                code.push(line);
+            }
+            let language = language.trim();
+            blocks.push(Block::CodeBlock {
+                language: (!language.is_empty()).then(|| language.to_string()),
+                code: code.join("\n"),
+            });
+        } else if let Some(quote) = line.strip_prefix("> ") {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Quote {
+                spans: parse_spans(quote),
+            });
+        } else if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::ListItem {
+                spans: parse_spans(item),
+            });
+        } else if line.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+/// Blocks for `raw`, or `None` if it renders as the raw text itself.
+pub fn process(raw: &str) -> Option<Vec<Block>> {
+    let blocks = parse(raw);
+    let plain = match blocks.as_slice() {
+        [] => true,
+        [Block::Paragraph { spans }] => {
+            matches!(spans.as_slice(), [Span::Text { text }] if text == raw)
+        }
+        _ => false,
+    };
+    (!plain).then_some(blocks)
+}
+
+/// `content_metadata` with `formatted` set for `raw`; unchanged for plain text.
+pub fn with_formatting(metadata: Option<String>, raw: &str) -> Option<String> {
+    let Some(blocks) = process(raw) else {
+        return metadata;
+    };
+    let mut value = metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+    value["formatted"] = serde_json::json!(blocks);
+    Some(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(t: &str) -> Span {
+        Span::Text {
+            text: t.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_markdown_and_shortcodes() {
+        assert_eq!(process("just words"), None);
+        assert_eq!(normalize_whitespace("a  \r\n\r\n\r\n\nb \n"), "a\n\nb");
+        assert_eq!(
+            replace_shortcodes("ok :+1: :nope: 10:30"),
+            "ok 👍 :nope: 10:30"
+        );
+
+        assert_eq!(
+            parse_spans("**hi** :wave: `:wave:` my_var _x_ [site](https://a.b) *open"),
+            vec![
+                Span::Bold {
+                    text: "hi".to_string()
+                },
+                text(" 👋 "),
+                Span::Code {
+                    text: ":wave:".to_string()
+                },
+                text(" my_var "),
+                Span::Italic {
+                    text: "x".to_string()
+                },
+                text(" "),
+                Span::Link {
+                    text: "site".to_string(),
+                    url: "https://a.b".to_string()
+                },
+                text(" *open"),
+            ]
+        );
+
+        let blocks = process("> quoted\n- item\n```rust\nlet x = 1;\n```\nbye :tada:").unwrap();
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Quote {
+                    spans: vec![text("quoted")]
+                },
+                Block::ListItem {
+                    spans: vec![text("item")]
+                },
+                Block::CodeBlock {
+                    language: Some("rust".to_string()),
+                    code: "let x = 1;".to_string()
+                },
+                Block::Paragraph {
+                    spans: vec![text("bye 🎉")]
+                },
+            ]
+        );
+    }
+}
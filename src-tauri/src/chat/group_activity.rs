@@ -0,0 +1,141 @@
+//! Group typing and presence, aggregated per chat.
+//!
+//! Members publish a [`GroupActivitySignal`](crate::network::gossip::GroupActivitySignal)
+//! while they type. Instead of forwarding each one, the manager records them
+//! here together with the number of members online (peers subscribed to the
+//! group topic) and, on a short tick, emits a single `group-activity` event per
+//! group whose state actually changed. Bursts of signals within one tick
+//! collapse into one event; a typing flag nobody refreshes expires after
+//! [`TYPING_TTL`].
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Senders repeat `typing: true` at this interval while the user keeps typing.
+pub const TYPING_REFRESH: Duration = Duration::from_secs(3);
+pub const TYPING_TTL: Duration = Duration::from_secs(6);
+/// Changes are coalesced and emitted at most this often.
+pub const EMIT_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypingMember {
+    pub peer_id: String,
+    pub alias: Option<String>,
+}
+
+/// Payload of the `group-activity` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupActivity {
+    pub group_id: String,
+    pub typing: Vec<TypingMember>,
+    pub online_count: usize,
+}
+
+#[derive(Debug, Default)]
+struct GroupState {
+    /// peer id -> (alias, expires at)
+    typing: BTreeMap<String, (Option<String>, Instant)>,
+    online_count: usize,
+    last_emitted: Option<GroupActivity>,
+}
+
+#[derive(Debug, Default)]
+pub struct GroupActivityTracker {
+    groups: HashMap<String, GroupState>,
+}
+
+impl GroupActivityTracker {
+    pub fn note_typing(
+        &mut self,
+        group_id: &str,
+        peer_id: &str,
+        alias: Option<String>,
+        typing: bool,
+        now: Instant,
+    ) {
+        let state = self.groups.entry(group_id.to_string()).or_default();
+        if typing {
+            state
+                .typing
+                .insert(peer_id.to_string(), (alias, now + TYPING_TTL));
+        } else {
+            state.typing.remove(peer_id);
+        }
+    }
+
+    /// A member who just sent a message is no longer typing it.
+    pub fn note_message(&mut self, group_id: &str, peer_id: &str) {
+        if let Some(state) = self.groups.get_mut(group_id) {
+            state.typing.remove(peer_id);
+        }
+    }
+
+    pub fn set_online_count(&mut self, group_id: &str, count: usize) {
+        self.groups
+            .entry(group_id.to_string())
+            .or_default()
+            .online_count = count;
+    }
+
+    pub fn forget(&mut self, group_id: &str) {
+        self.groups.remove(group_id);
+    }
+
+    /// Expire stale typing flags and return the groups whose aggregate state
+    /// differs from what was last emitted.
+    pub fn take_changes(&mut self, now: Instant) -> Vec<GroupActivity> {
+        let mut changes = Vec::new();
+        for (group_id, state) in &mut self.groups {
+            state.typing.retain(|_, (_, expires_at)| *expires_at > now);
+            let current = GroupActivity {
+                group_id: group_id.clone(),
+                typing: state
+                    .typing
+                    .iter()
+                    .map(|(peer_id, (alias, _))| TypingMember {
+                        peer_id: peer_id.clone(),
+                        alias: alias.clone(),
+                    })
+                    .collect(),
+                online_count: state.online_count,
+            };
+            if state.last_emitted.as_ref() != Some(&current) {
+                state.last_emitted = Some(current.clone());
+                changes.push(current);
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP: &str = "group:550e8400-e29b-41d4-a716-446655440000";
+
+    #[test]
+    fn signals_are_coalesced_and_expire() {
+        let mut tracker = GroupActivityTracker::default();
+        let now = Instant::now();
+        tracker.set_online_count(GROUP, 3);
+        tracker.note_typing(GROUP, "peer-a", Some("Ada".to_string()), true, now);
+        tracker.note_typing(GROUP, "peer-b", None, true, now);
+        tracker.note_typing(GROUP, "peer-b", None, true, now);
+
+        let changes = tracker.take_changes(now);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].typing.len(), 2);
+        assert_eq!(changes[0].online_count, 3);
+        // Nothing new: no event.
+        assert!(tracker.take_changes(now).is_empty());
+
+        tracker.note_message(GROUP, "peer-a");
+        let changes = tracker.take_changes(now);
+        assert_eq!(changes[0].typing[0].peer_id, "peer-b");
+
+        let changes = tracker.take_changes(now + TYPING_TTL);
+        assert!(changes[0].typing.is_empty());
+    }
+}
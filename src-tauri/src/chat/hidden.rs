@@ -0,0 +1,79 @@
+//! Hidden chats.
+//!
+//! A chat marked hidden in its settings is left out of the chat list and its
+//! notifications are masked. `reveal_hidden_chats` re-verifies the vault
+//! password and shows them for [`REVEAL_WINDOW_SECS`] or until
+//! `conceal_hidden_chats` is called.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use rusqlite::Connection;
+
+pub const REVEAL_WINDOW_SECS: i64 = 5 * 60;
+pub const MASKED_TITLE: &str = "RChat";
+pub const MASKED_BODY: &str = "New message";
+
+/// Unix secs until which hidden chats are shown; 0 when concealed.
+#[derive(Default)]
+pub struct RevealWindow(AtomicI64);
+
+impl RevealWindow {
+    pub const fn new() -> Self {
+        Self(AtomicI64::new(0))
+    }
+
+    pub fn reveal(&self, now: i64) -> i64 {
+        let until = now.saturating_add(REVEAL_WINDOW_SECS);
+        self.0.store(until, Ordering::SeqCst);
+        until
+    }
+
+    pub fn conceal(&self) {
+        self.0.store(0, Ordering::SeqCst);
+    }
+
+    pub fn is_revealed(&self, now: i64) -> bool {
+        self.0.load(Ordering::SeqCst) > now
+    }
+}
+
+/// The app's window, shared by every surface.
+static REVEALED: RevealWindow = RevealWindow::new();
+
+pub fn reveal(now: i64) -> i64 {
+    REVEALED.reveal(now)
+}
+
+pub fn conceal() {
+    REVEALED.conceal();
+}
+
+pub fn is_revealed(now: i64) -> bool {
+    REVEALED.is_revealed(now)
+}
+
+/// Hidden chats that are not revealed at `now`, for surfaces that have to leave
+/// them out; empty while they are revealed.
+pub fn concealed_chat_ids(conn: &Connection, now: i64) -> anyhow::Result<HashSet<String>> {
+    if is_revealed(now) {
+        return Ok(HashSet::new());
+    }
+    crate::storage::db::get_hidden_chat_ids(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reveal_expires_and_can_be_concealed() {
+        let window = RevealWindow::new();
+        assert!(!window.is_revealed(100));
+        let until = window.reveal(100);
+        assert!(window.is_revealed(100) && window.is_revealed(until - 1));
+        assert!(!window.is_revealed(until));
+        window.conceal();
+        assert!(!window.is_revealed(100));
+    }
+}
@@ -0,0 +1,779 @@
+//! Chat history from other apps: Telegram's JSON export (`result.json` of a
+//! single chat) and WhatsApp's "Export chat" text file.
+//!
+//! An export becomes a read-only archived chat in the "Imported" envelope.
+//! Senders are mapped to peers by the caller's choice, then by alias; anyone
+//! else gets a placeholder peer (`import:<source>:<hash>`), so importing the
+//! same people again lands on the same placeholders. Attachments are read from
+//! next to the export file into the object store. Neither format records a
+//! time zone, so local times are shifted by the caller's UTC offset.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
+use std::sync::Mutex;
+
+use crate::chat::message::MessageContent;
+use crate::storage::{self, db::Message};
+
+/// Messages inserted (and progress reported) per database transaction.
+const BATCH_SIZE: usize = 500;
+const IMPORTED_ENVELOPE_ID: &str = "imported";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Telegram,
+    Whatsapp,
+}
+
+impl ImportSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Telegram => "telegram",
+            Self::Whatsapp => "whatsapp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImportOptions {
+    /// Sender name as it appears in the export -> peer id (or "Me").
+    #[serde(default)]
+    pub sender_map: HashMap<String, String>,
+    /// The user's own name in the export.
+    pub self_name: Option<String>,
+    /// Minutes east of UTC the export's local times were written in.
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Overrides the chat name from the export.
+    pub chat_name: Option<String>,
+}
+
+/// One message as read from an export, before it is mapped to peers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedMessage {
+    pub source_id: String,
+    pub timestamp: i64,
+    pub sender_name: String,
+    /// Telegram's `from_id` where present, else the name.
+    pub sender_key: String,
+    pub text: Option<String>,
+    /// Relative to the export's directory.
+    pub attachment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedChat {
+    pub name: String,
+    pub messages: Vec<ParsedMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSender {
+    pub name: String,
+    pub message_count: usize,
+    /// The peer the sender would be mapped to; `None` means a placeholder.
+    pub peer_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub name: String,
+    pub message_count: usize,
+    pub senders: Vec<ImportSender>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub chat_id: String,
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub chat_id: String,
+    pub name: String,
+    pub message_count: usize,
+    pub media_count: usize,
+    pub missing_media: usize,
+    pub placeholder_peers: usize,
+}
+
+// --- Telegram ---
+
+#[derive(Debug, Deserialize)]
+struct TelegramExport {
+    name: Option<String>,
+    #[serde(default)]
+    messages: Vec<TelegramMessage>,
+    /// Present in whole-account exports, which we don't take.
+    chats: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TelegramMessage {
+    id: i64,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    date: String,
+    date_unixtime: Option<String>,
+    from: Option<String>,
+    from_id: Option<String>,
+    #[serde(default)]
+    text: serde_json::Value,
+    photo: Option<String>,
+    file: Option<String>,
+}
+
+/// Telegram text is a string, or a list of strings and `{type, text}` entities.
+fn telegram_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(parts) => parts
+            .iter()
+            .map(|part| match part {
+                serde_json::Value::String(text) => text.as_str(),
+                other => other
+                    .get("text")
+                    .and_then(|text| text.as_str())
+                    .unwrap_or_default(),
+            })
+            .collect(),
+        _ => String::new(),
+    }
+}
+
+pub fn parse_telegram(json: &str, utc_offset_minutes: i32) -> Result<ParsedChat> {
+    let export: TelegramExport =
+        serde_json::from_str(json).context("Not a Telegram chat export")?;
+    if export.chats.is_some() {
+        bail!("This is a whole-account export; export a single chat instead");
+    }
+    let mut messages = Vec::new();
+    for msg in export.messages {
+        if msg.kind != "message" {
+            continue;
+        }
+        let timestamp = match msg.date_unixtime.as_deref().map(str::parse::<i64>) {
+            Some(Ok(unix)) => unix,
+            _ => parse_iso_local(&msg.date)
+                .map(|local| local - i64::from(utc_offset_minutes) * 60)
+                .with_context(|| format!("Bad date on message {}", msg.id))?,
+        };
+        let sender_name = msg.from.unwrap_or_else(|| "Deleted Account".to_string());
+        // Files left out of the export come as "(File not included. ...)".
+        let attachment = msg.photo.or(msg.file).filter(|path| !path.starts_with('('));
+        let text = telegram_text(&msg.text);
+        messages.push(ParsedMessage {
+            source_id: msg.id.to_string(),
+            timestamp,
+            sender_key: msg.from_id.unwrap_or_else(|| sender_name.clone()),
+            sender_name,
+            text: (!text.is_empty()).then_some(text),
+            attachment,
+        });
+    }
+    Ok(ParsedChat {
+        name: export.name.unwrap_or_else(|| "Telegram chat".to_string()),
+        messages,
+    })
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` as seconds, read as if it were UTC.
+fn parse_iso_local(date: &str) -> Option<i64> {
+    let (day, time) = date.split_once('T')?;
+    let mut ymd = day.split('-').map(str::parse::<i64>);
+    let (year, month, day) = (ymd.next()?.ok()?, ymd.next()?.ok()?, ymd.next()?.ok()?);
+    let mut hms = time.split(':').map(str::parse::<i64>);
+    let (hour, minute, second) = (hms.next()?.ok()?, hms.next()?.ok()?, hms.next()?.ok()?);
+    Some(days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Days since the Unix epoch for a civil date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// --- WhatsApp ---
+
+/// A message's first line: `31/12/2023, 21:15 - Alice: hi` (Android) or
+/// `[31/12/2023, 21:15:03] Alice: hi` (iOS), in any locale's date order.
+struct WhatsappHeader<'a> {
+    date: (i64, i64, i64),
+    seconds_of_day: i64,
+    /// `Name: text`, or a system notice without a name.
+    rest: &'a str,
+}
+
+fn parse_whatsapp_header(line: &str) -> Option<WhatsappHeader<'_>> {
+    let line = line.trim_start_matches(['\u{200e}', '\u{feff}']);
+    let (line, closing) = match line.strip_prefix('[') {
+        Some(line) => (line, "] "),
+        None => (line, " - "),
+    };
+    let (stamp, rest) = line.split_once(closing)?;
+    let (date, time) = stamp.split_once(", ").or_else(|| stamp.split_once(' '))?;
+
+    let mut parts = date.split(['/', '.', '-']).map(|p| p.trim().parse::<i64>());
+    let date = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    if parts.next().is_some() {
+        return None;
+    }
+
+    // "9:15 PM", with a plain or narrow no-break space before the suffix.
+    let time = time.trim();
+    let (clock, suffix) = match time.rsplit_once([' ', '\u{202f}', '\u{a0}']) {
+        Some((clock, suffix)) => (clock, Some(suffix.to_ascii_uppercase().replace('.', ""))),
+        None => (time, None),
+    };
+    let mut hms = clock.split(':').map(str::parse::<i64>);
+    let mut hour = hms.next()?.ok()?;
+    let minute = hms.next()?.ok()?;
+    let second = match hms.next() {
+        Some(second) => second.ok()?,
+        None => 0,
+    };
+    match suffix.as_deref() {
+        Some("PM") if hour < 12 => hour += 12,
+        Some("AM") if hour == 12 => hour = 0,
+        Some("AM" | "PM") | None => {}
+        Some(_) => return None,
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(WhatsappHeader {
+        date,
+        seconds_of_day: hour * 3_600 + minute * 60 + second,
+        rest,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// Settle the locale's date order from every date in the file: a first field
+/// above 12 can only be a day, a second field above 12 only a day after a month.
+fn whatsapp_date_order<'a>(dates: impl Iterator<Item = &'a (i64, i64, i64)>) -> DateOrder {
+    let mut order = DateOrder::DayMonthYear;
+    for &(a, b, _) in dates {
+        if a > 31 {
+            return DateOrder::YearMonthDay;
+        }
+        if a > 12 {
+            return DateOrder::DayMonthYear;
+        }
+        if b > 12 {
+            order = DateOrder::MonthDayYear;
+        }
+    }
+    order
+}
+
+/// An attachment line: `IMG-20231231-WA0001.jpg (file attached)` on Android,
+/// `<attached: 00000012-PHOTO-2023-12-31-21-15-03.jpg>` on iOS.
+fn whatsapp_attachment(line: &str) -> Option<&str> {
+    let line = line.trim().trim_start_matches('\u{200e}');
+    if let Some(name) = line.strip_suffix(" (file attached)") {
+        return Some(name.trim());
+    }
+    line.strip_prefix("<attached: ")?
+        .strip_suffix('>')
+        .map(str::trim)
+}
+
+pub fn parse_whatsapp(text: &str, chat_name: &str, utc_offset_minutes: i32) -> Result<ParsedChat> {
+    // (header, sender, body lines); notices without a sender are dropped.
+    let mut raw: Vec<(WhatsappHeader<'_>, &str, Vec<&str>)> = Vec::new();
+    let mut in_notice = false;
+    for line in text.lines() {
+        if let Some(header) = parse_whatsapp_header(line) {
+            in_notice = true;
+            if let Some((sender, body)) = header.rest.split_once(": ") {
+                in_notice = false;
+                raw.push((header, sender.trim_start_matches('\u{200e}'), vec![body]));
+            }
+        } else if !in_notice {
+            if let Some((_, _, body)) = raw.last_mut() {
+                body.push(line);
+            }
+        }
+    }
+    if raw.is_empty() {
+        bail!("No messages found; is this a WhatsApp chat export?");
+    }
+
+    let order = whatsapp_date_order(raw.iter().map(|(header, _, _)| &header.date));
+    let mut messages = Vec::with_capacity(raw.len());
+    for (index, (header, sender, body)) in raw.into_iter().enumerate() {
+        let (a, b, c) = header.date;
+        let (year, month, day) = match order {
+            DateOrder::DayMonthYear => (c, b, a),
+            DateOrder::MonthDayYear => (c, a, b),
+            DateOrder::YearMonthDay => (a, b, c),
+        };
+        let year = if year < 100 { year + 2000 } else { year };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            bail!("Unreadable date on message {}", index + 1);
+        }
+        let local = days_from_civil(year, month, day) * 86_400 + header.seconds_of_day;
+
+        let mut lines = body.into_iter();
+        let first = lines.next().unwrap_or_default();
+        let (attachment, mut text_lines) = match whatsapp_attachment(first) {
+            Some(name) => (Some(name.to_string()), Vec::new()),
+            None => (None, vec![first]),
+        };
+        text_lines.extend(lines);
+        let text = text_lines.join("\n").trim().to_string();
+        messages.push(ParsedMessage {
+            source_id: (index + 1).to_string(),
+            timestamp: local - i64::from(utc_offset_minutes) * 60,
+            sender_name: sender.to_string(),
+            sender_key: sender.to_string(),
+            text: (!text.is_empty()).then_some(text),
+            attachment,
+        });
+    }
+    Ok(ParsedChat {
+        name: chat_name.to_string(),
+        messages,
+    })
+}
+
+/// Read and parse the export at `path`.
+pub fn parse_export(
+    source: ImportSource,
+    path: &Path,
+    utc_offset_minutes: i32,
+) -> Result<ParsedChat> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    match source {
+        ImportSource::Telegram => parse_telegram(&content, utc_offset_minutes),
+        ImportSource::Whatsapp => {
+            // "WhatsApp Chat with Alice.txt" -> "Alice"
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "WhatsApp chat".to_string());
+            let name = stem
+                .strip_prefix("WhatsApp Chat with ")
+                .or_else(|| stem.strip_prefix("WhatsApp Chat - "))
+                .unwrap_or(&stem);
+            parse_whatsapp(&content, name, utc_offset_minutes)
+        }
+    }
+}
+
+// --- Mapping and storing ---
+
+struct SenderResolver<'a> {
+    source: ImportSource,
+    options: &'a ImportOptions,
+    /// Lowercased alias -> peer id, `None` when several peers share it.
+    peers_by_alias: HashMap<String, Option<String>>,
+    resolved: HashMap<String, String>,
+}
+
+impl<'a> SenderResolver<'a> {
+    fn new(conn: &Connection, source: ImportSource, options: &'a ImportOptions) -> Result<Self> {
+        for peer_id in options.sender_map.values() {
+            if peer_id != "Me" && !storage::db::is_peer(conn, peer_id) {
+                bail!("Unknown peer {}", peer_id);
+            }
+        }
+        let mut peers_by_alias: HashMap<String, Option<String>> = HashMap::new();
+        for peer in storage::db::get_all_peers(conn)? {
+            if peer.id == "Me" || peer.method == "import" {
+                continue;
+            }
+            peers_by_alias
+                .entry(peer.alias.to_lowercase())
+                .and_modify(|id| *id = None)
+                .or_insert(Some(peer.id));
+        }
+        Ok(Self {
+            source,
+            options,
+            peers_by_alias,
+            resolved: HashMap::new(),
+        })
+    }
+
+    /// The existing peer `msg`'s sender maps to, if any.
+    fn existing(&self, msg: &ParsedMessage) -> Option<String> {
+        if let Some(peer_id) = self.options.sender_map.get(&msg.sender_name) {
+            return Some(peer_id.clone());
+        }
+        if self.options.self_name.as_deref() == Some(msg.sender_name.as_str()) {
+            return Some("Me".to_string());
+        }
+        self.peers_by_alias
+            .get(&msg.sender_name.to_lowercase())
+            .cloned()
+            .flatten()
+    }
+
+    fn placeholder_id(&self, msg: &ParsedMessage) -> String {
+        let hash = storage::object::sha256_hex(msg.sender_key.as_bytes());
+        format!("import:{}:{}", self.source.as_str(), &hash[..12])
+    }
+
+    /// The peer id for `msg`'s sender, creating a placeholder peer if needed.
+    /// Returns whether a placeholder was created.
+    fn resolve(&mut self, conn: &Connection, msg: &ParsedMessage) -> Result<(String, bool)> {
+        if let Some(peer_id) = self.resolved.get(&msg.sender_key) {
+            return Ok((peer_id.clone(), false));
+        }
+        let (peer_id, created) = match self.existing(msg) {
+            Some(peer_id) => (peer_id, false),
+            None => {
+                let peer_id = self.placeholder_id(msg);
+                let created = !storage::db::is_peer(conn, &peer_id);
+                storage::db::add_peer(conn, &peer_id, Some(&msg.sender_name), None, "import")?;
+                (peer_id, created)
+            }
+        };
+        self.resolved
+            .insert(msg.sender_key.clone(), peer_id.clone());
+        Ok((peer_id, created))
+    }
+}
+
+/// Who sent what in an export, and where each sender would land.
+pub fn preview(
+    conn: &Connection,
+    source: ImportSource,
+    chat: &ParsedChat,
+    options: &ImportOptions,
+) -> Result<ImportPreview> {
+    let resolver = SenderResolver::new(conn, source, options)?;
+    let mut senders: Vec<ImportSender> = Vec::new();
+    let mut index_by_name = HashMap::new();
+    for msg in &chat.messages {
+        let index = *index_by_name
+            .entry(msg.sender_name.clone())
+            .or_insert_with(|| {
+                senders.push(ImportSender {
+                    name: msg.sender_name.clone(),
+                    message_count: 0,
+                    peer_id: resolver.existing(msg),
+                });
+                senders.len() - 1
+            });
+        senders[index].message_count += 1;
+    }
+    Ok(ImportPreview {
+        name: chat.name.clone(),
+        message_count: chat.messages.len(),
+        senders,
+    })
+}
+
+fn mime_type(file_name: &str) -> &'static str {
+    let ext = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    match ext.as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("mov") => "video/quicktime",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg" | "opus") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("m4a") => "audio/mp4",
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("csv") => "text/csv",
+        _ => "application/octet-stream",
+    }
+}
+
+fn content_type(mime_type: &str) -> &'static str {
+    match mime_type.split('/').next() {
+        Some("image") => "image",
+        Some("video") => "video",
+        Some("audio") => "audio",
+        _ => "document",
+    }
+}
+
+/// The attachment's bytes, if it is a plain relative path that exists.
+fn read_attachment(base_dir: &Path, relative: &str) -> Option<Vec<u8>> {
+    let relative = Path::new(relative);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    std::fs::read(base_dir.join(relative)).ok()
+}
+
+/// Store `chat` as a new archived chat, `BATCH_SIZE` messages per transaction,
+/// calling `on_progress` after each batch. The database lock is released
+/// between batches so the app stays usable during large imports.
+pub fn import_chat(
+    db: &Mutex<Connection>,
+    source: ImportSource,
+    chat: ParsedChat,
+    base_dir: &Path,
+    options: &ImportOptions,
+    mut on_progress: impl FnMut(&ImportProgress),
+) -> Result<ImportSummary> {
+    let lock = || db.lock().map_err(|e| anyhow::anyhow!(e.to_string()));
+    let name = options
+        .chat_name
+        .clone()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or(chat.name);
+    let chat_id = crate::chat_kind::generate_imported_chat_id(source.as_str());
+    let mut messages = chat.messages;
+    messages.sort_by_key(|msg| msg.timestamp);
+
+    let mut resolver = {
+        let conn = lock()?;
+        let resolver = SenderResolver::new(&conn, source, options)?;
+        let senders: HashSet<&str> = messages
+            .iter()
+            .filter(|msg| resolver.existing(msg).as_deref() != Some("Me"))
+            .map(|msg| msg.sender_key.as_str())
+            .collect();
+        storage::db::create_chat(&conn, &chat_id, &name, senders.len() > 1)?;
+        storage::db::add_chat_member(&conn, &chat_id, "Me", "member")?;
+        if conn
+            .query_row(
+                "SELECT 1 FROM envelopes WHERE id = ?1",
+                [IMPORTED_ENVELOPE_ID],
+                |_| Ok(()),
+            )
+            .is_err()
+        {
+            storage::db::create_envelope(&conn, IMPORTED_ENVELOPE_ID, "Imported", None)?;
+        }
+        storage::db::assign_chat_to_envelope(&conn, &chat_id, Some(IMPORTED_ENVELOPE_ID))?;
+        resolver
+    };
+
+    let total = messages.len();
+    let mut summary = ImportSummary {
+        chat_id: chat_id.clone(),
+        name,
+        message_count: 0,
+        media_count: 0,
+        missing_media: 0,
+        placeholder_peers: 0,
+    };
+    let mut members = HashSet::new();
+    for (batch_index, batch) in messages.chunks(BATCH_SIZE).enumerate() {
+        // Files are read before taking the lock.
+        let attachments: Vec<Option<Vec<u8>>> = batch
+            .iter()
+            .map(|msg| {
+                msg.attachment
+                    .as_deref()
+                    .and_then(|path| read_attachment(base_dir, path))
+            })
+            .collect();
+
+        let conn = lock()?;
+        let mut rows = Vec::with_capacity(batch.len());
+        for (msg, data) in batch.iter().zip(attachments) {
+            let (peer_id, created) = resolver.resolve(&conn, msg)?;
+            if created {
+                summary.placeholder_peers += 1;
+            }
+            if peer_id != "Me" && members.insert(peer_id.clone()) {
+                storage::db::add_chat_member(&conn, &chat_id, &peer_id, "member")?;
+            }
+            let id_base =
+                storage::object::sha256_hex(format!("{}\n{}", chat_id, msg.source_id).as_bytes());
+            let row = |suffix: &str,
+                       content_type: &str,
+                       text: Option<String>,
+                       file_hash: Option<String>| Message {
+                id: format!("import-{}{}", &id_base[..32], suffix),
+                chat_id: chat_id.clone(),
+                peer_id: peer_id.clone(),
+                timestamp: msg.timestamp,
+                content: MessageContent::from_parts(
+                    content_type,
+                    text,
+                    file_hash,
+                    Default::default(),
+                ),
+                status: "read".to_string(),
+                content_metadata: None,
+                sender_alias: Some(msg.sender_name.clone()),
+                logical: 0,
+            };
+
+            let Some(file_name) = msg.attachment.as_deref() else {
+                rows.push(row("", "text", msg.text.clone(), None));
+                continue;
+            };
+            let Some(data) = data else {
+                summary.missing_media += 1;
+                let note = format!("[Missing attachment: {}]", file_name);
+                let text = match &msg.text {
+                    Some(text) => format!("{}\n{}", note, text),
+                    None => note,
+                };
+                rows.push(row("", "text", Some(text), None));
+                continue;
+            };
+            let display_name = Path::new(file_name)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| file_name.to_string());
+            let mime = mime_type(&display_name);
+            let file_hash =
+                storage::object::create(&conn, &data, Some(&display_name), Some(mime), None)?;
+            summary.media_count += 1;
+            match content_type(mime) {
+                kind @ ("image" | "video") => {
+                    rows.push(row("", kind, msg.text.clone(), Some(file_hash)));
+                }
+                kind => {
+                    // Documents carry their file name and audio nothing, so a
+                    // caption becomes a message of its own.
+                    let text = (kind == "document").then(|| display_name.clone());
+                    rows.push(row("", kind, text, Some(file_hash)));
+                    if let Some(caption) = &msg.text {
+                        rows.push(row("-caption", "text", Some(caption.clone()), None));
+                    }
+                }
+            }
+        }
+        storage::db::insert_messages_batch(&conn, &rows)?;
+        drop(conn);
+
+        summary.message_count += rows.len();
+        on_progress(&ImportProgress {
+            chat_id: chat_id.clone(),
+            processed: (batch_index * BATCH_SIZE + batch.len()).min(total),
+            total,
+        });
+    }
+
+    println!(
+        "[Import] ✅ {} message(s) from {} into {}",
+        summary.message_count,
+        source.as_str(),
+        chat_id
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_telegram_text_entities_and_media() {
+        let json = r#"{
+            "name": "Alice",
+            "type": "personal_chat",
+            "messages": [
+                {"id": 1, "type": "service", "date": "2024-01-01T10:00:00", "text": ""},
+                {"id": 2, "type": "message", "date": "2024-01-01T10:00:00",
+                 "date_unixtime": "1704103200", "from": "Alice", "from_id": "user42",
+                 "text": ["see ", {"type": "bold", "text": "this"}]},
+                {"id": 3, "type": "message", "date": "2024-01-01T10:05:00",
+                 "from": "Bob", "from_id": "user7", "text": "",
+                 "photo": "photos/photo_1.jpg"},
+                {"id": 4, "type": "message", "date": "2024-01-01T10:06:00",
+                 "from": "Bob", "from_id": "user7", "text": "doc",
+                 "file": "(File not included. Change data exporting settings to download.)"}
+            ]
+        }"#;
+        let chat = parse_telegram(json, 60).expect("parse");
+        assert_eq!(chat.name, "Alice");
+        assert_eq!(chat.messages.len(), 3);
+        assert_eq!(chat.messages[0].text.as_deref(), Some("see this"));
+        assert_eq!(chat.messages[0].timestamp, 1_704_103_200);
+        assert_eq!(chat.messages[0].sender_key, "user42");
+        // No date_unixtime: local 10:05 at UTC+1 is 09:05 UTC.
+        assert_eq!(chat.messages[1].timestamp, 1_704_103_200 - 3_600 + 300);
+        assert_eq!(
+            chat.messages[1].attachment.as_deref(),
+            Some("photos/photo_1.jpg")
+        );
+        assert_eq!(chat.messages[1].text, None);
+        assert_eq!(chat.messages[2].attachment, None);
+
+        assert!(parse_telegram(r#"{"chats": {"list": []}}"#, 0).is_err());
+    }
+
+    #[test]
+    fn parses_android_whatsapp_with_continuations_and_notices() {
+        let text = "\
+12/31/23, 9:15 PM - Messages and calls are end-to-end encrypted.
+12/31/23, 9:15 PM - Alice: first line
+second line
+1/1/24, 12:05 AM - Bob: IMG-20240101-WA0001.jpg (file attached)
+happy new year";
+        let chat = parse_whatsapp(text, "Alice", 0).expect("parse");
+        assert_eq!(chat.messages.len(), 2);
+        let first = &chat.messages[0];
+        assert_eq!(first.sender_name, "Alice");
+        assert_eq!(first.text.as_deref(), Some("first line\nsecond line"));
+        // 2023-12-31 21:15 UTC
+        assert_eq!(first.timestamp, 1_704_057_300);
+        let second = &chat.messages[1];
+        assert_eq!(
+            second.attachment.as_deref(),
+            Some("IMG-20240101-WA0001.jpg")
+        );
+        assert_eq!(second.text.as_deref(), Some("happy new year"));
+        assert_eq!(second.timestamp, 1_704_067_500);
+    }
+
+    #[test]
+    fn parses_ios_whatsapp_day_first() {
+        let text = "\
+[31/12/2023, 21:15:03] Alice: hi
+\u{200e}[01/01/2024, 00:00:00] Bob: \u{200e}<attached: 00000012-PHOTO-2024-01-01-00-00-00.jpg>";
+        let chat = parse_whatsapp(text, "Alice", 0).expect("parse");
+        assert_eq!(chat.messages[0].timestamp, 1_704_057_303);
+        assert_eq!(chat.messages[1].timestamp, 1_704_067_200);
+        assert_eq!(
+            chat.messages[1].attachment.as_deref(),
+            Some("00000012-PHOTO-2024-01-01-00-00-00.jpg")
+        );
+
+        assert!(parse_whatsapp("just some text", "x", 0).is_err());
+    }
+
+    #[test]
+    fn attachments_stay_inside_the_export() {
+        let dir = std::env::temp_dir();
+        assert_eq!(read_attachment(&dir, "../etc/passwd"), None);
+        assert_eq!(read_attachment(&dir, "/etc/passwd"), None);
+    }
+}
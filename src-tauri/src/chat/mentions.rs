@@ -0,0 +1,112 @@
+//! `@alias` mentions in group chats.
+//!
+//! A mention is `@` at the start of the text or after whitespace, followed by
+//! a member's alias (case-insensitive, spaces allowed) that isn't directly
+//! followed by another letter or digit. The longest matching alias wins, so
+//! `@Ada Lovelace` picks "Ada Lovelace" over "Ada". Resolved peer ids are
+//! stored under `mentions` in the message's `content_metadata`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mention {
+    pub peer_id: String,
+    pub alias: String,
+}
+
+/// Mentions of `members` (`(peer_id, alias)`) in `text`, in order of first
+/// appearance and without duplicates.
+pub fn parse(text: &str, members: &[(String, String)]) -> Vec<Mention> {
+    let mut candidates: Vec<(&str, String)> = members
+        .iter()
+        .map(|(peer_id, alias)| (peer_id.as_str(), alias.trim().to_lowercase()))
+        .filter(|(_, alias)| !alias.is_empty())
+        .collect();
+    candidates.sort_by_key(|(_, alias)| std::cmp::Reverse(alias.len()));
+
+    let lower = text.to_lowercase();
+    let mut mentions: Vec<Mention> = Vec::new();
+    for (at, _) in lower.match_indices('@') {
+        if lower[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_whitespace())
+        {
+            continue;
+        }
+        let rest = &lower[at + 1..];
+        let found = candidates.iter().find(|(_, alias)| {
+            rest.starts_with(alias.as_str())
+                && !rest[alias.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_alphanumeric)
+        });
+        if let Some((peer_id, _)) = found {
+            if !mentions.iter().any(|m| m.peer_id == *peer_id) {
+                let alias = members
+                    .iter()
+                    .find(|(id, _)| id == peer_id)
+                    .map(|(_, alias)| alias.trim().to_string())
+                    .unwrap_or_default();
+                mentions.push(Mention {
+                    peer_id: peer_id.to_string(),
+                    alias,
+                });
+            }
+        }
+    }
+    mentions
+}
+
+/// `content_metadata` with `mentions` set; unchanged when there are none.
+pub fn with_mentions(metadata: Option<String>, mentions: &[Mention]) -> Option<String> {
+    if mentions.is_empty() {
+        return metadata;
+    }
+    let mut value = metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+    value["mentions"] = serde_json::json!(mentions);
+    Some(value.to_string())
+}
+
+/// Whether `text` mentions `alias`.
+pub fn mentions_alias(text: &str, alias: &str) -> bool {
+    !parse(text, &[(String::new(), alias.to_string())]).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_alias_wins_and_words_must_end() {
+        let members = vec![
+            ("p1".to_string(), "Ada".to_string()),
+            ("p2".to_string(), "Ada Lovelace".to_string()),
+            ("p3".to_string(), "bob".to_string()),
+        ];
+        let ids = |text: &str| -> Vec<String> {
+            parse(text, &members)
+                .into_iter()
+                .map(|m| m.peer_id)
+                .collect()
+        };
+        assert_eq!(ids("hi @ada lovelace and @Bob!"), vec!["p2", "p3"]);
+        assert_eq!(ids("@ada, @ADA"), vec!["p1"]);
+        assert!(ids("mail bob@ada.dev or @bobby").is_empty());
+
+        let metadata = with_mentions(
+            Some("{\"translation\":\"x\"}".to_string()),
+            &parse("@bob", &members),
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&metadata).unwrap();
+        assert_eq!(value["translation"], "x");
+        assert_eq!(value["mentions"][0]["peer_id"], "p3");
+        assert_eq!(with_mentions(None, &[]), None);
+    }
+}
@@ -1 +1,15 @@
-pub mod message;
+pub mod auto_download;
+pub mod completions;
+pub mod format;
+pub mod group_activity;
+pub mod hidden;
+pub mod import;
+pub mod mentions;
+pub mod notify;
+pub mod reminders;
+pub mod scheduled;
+pub mod spam;
+pub mod suggest;
+pub mod translate;
+
+pub use rchat_core::chat::{export, media_guard, message, message_id};
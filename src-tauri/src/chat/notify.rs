@@ -0,0 +1,312 @@
+//! Notification dispatch for incoming messages.
+//!
+//! The backend decides *what* to alert about and with which sound/priority; the
+//! frontend turns the `notification` event into an OS notification and plays the
+//! custom sound (loaded from the object store by hash) if one is set. Muted
+//! chats and chats whose notification level filters the message stay silent;
+//! hidden chats alert without sender, content or custom sound. A group message
+//! that @-mentions the local alias additionally emits `mentioned` with the same
+//! payload.
+
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+use crate::chat::message::MessageContent;
+use crate::chat::{hidden, mentions};
+use crate::chat_kind::{self, ChatKind};
+use crate::storage::db::{self, Message};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationPriority {
+    Low,
+    Normal,
+    High,
+    Urgent,
+}
+
+impl NotificationPriority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "high" => Some(Self::High),
+            "urgent" => Some(Self::Urgent),
+            _ => None,
+        }
+    }
+
+    /// Whether the UI should flash the window / taskbar (the desktop "LED").
+    pub fn requests_attention(self) -> bool {
+        self >= Self::High
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    All,
+    /// Direct messages, plus group messages that @-mention the local alias.
+    Mentions,
+    Off,
+}
+
+impl NotificationLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Mentions => "mentions",
+            Self::Off => "off",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "all" => Some(Self::All),
+            "mentions" => Some(Self::Mentions),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationPayload {
+    pub chat_id: String,
+    pub msg_id: String,
+    pub title: String,
+    pub body: String,
+    pub priority: NotificationPriority,
+    pub request_attention: bool,
+    pub sound_hash: Option<String>,
+}
+
+fn preview_body(msg: &Message) -> String {
+    match &msg.content {
+        MessageContent::Text { text } => text.clone(),
+        MessageContent::Task { text } => format!("☑️ {}", text),
+        MessageContent::Photo { .. } => "📷 Photo".to_string(),
+        MessageContent::Sticker { .. } => "Sticker".to_string(),
+        MessageContent::Video { .. } => "🎬 Video".to_string(),
+        MessageContent::Audio { .. } => "🎵 Audio".to_string(),
+        MessageContent::Document { .. } => "📄 Document".to_string(),
+    }
+}
+
+/// Whether `msg` is a group message that mentions `my_alias`.
+pub fn mentions_me(msg: &Message, my_alias: Option<&str>) -> bool {
+    matches!(
+        chat_kind::parse_chat_kind(&msg.chat_id),
+        ChatKind::Group | ChatKind::TemporaryGroup
+    ) && my_alias
+        .zip(msg.content.text())
+        .is_some_and(|(alias, text)| mentions::mentions_alias(text, alias))
+}
+
+/// Whether `msg` may alert under `settings` at `now` (unix secs).
+pub fn should_alert(
+    settings: &db::ChatSettings,
+    msg: &Message,
+    my_alias: Option<&str>,
+    now: i64,
+) -> bool {
+    if settings.is_muted(now) {
+        return false;
+    }
+    match NotificationLevel::parse(&settings.notification_level).unwrap_or(NotificationLevel::All) {
+        NotificationLevel::All => true,
+        NotificationLevel::Off => false,
+        NotificationLevel::Mentions => {
+            let is_group = matches!(
+                chat_kind::parse_chat_kind(&msg.chat_id),
+                ChatKind::Group | ChatKind::TemporaryGroup
+            );
+            !is_group || mentions_me(msg, my_alias)
+        }
+    }
+}
+
+/// Build the notification for `msg` from its chat's settings, or `None` when
+/// the chat is muted or its notification level filters the message out.
+pub fn build_notification(
+    conn: &rusqlite::Connection,
+    msg: &Message,
+    my_alias: Option<&str>,
+    now: i64,
+) -> anyhow::Result<Option<NotificationPayload>> {
+    let settings = db::get_chat_settings(conn, &msg.chat_id)?;
+    if !should_alert(&settings, msg, my_alias, now) {
+        return Ok(None);
+    }
+    let priority =
+        NotificationPriority::parse(&settings.priority).unwrap_or(NotificationPriority::Normal);
+    let title = msg
+        .sender_alias
+        .clone()
+        .filter(|alias| !alias.trim().is_empty())
+        .or_else(|| db::get_peer_alias(conn, &msg.peer_id).ok().flatten())
+        .unwrap_or_else(|| msg.peer_id.chars().take(12).collect());
+
+    let payload = NotificationPayload {
+        chat_id: msg.chat_id.clone(),
+        msg_id: msg.id.clone(),
+        title,
+        body: preview_body(msg),
+        priority,
+        request_attention: priority.requests_attention(),
+        sound_hash: settings.custom_sound_hash,
+    };
+    Ok(Some(if settings.hidden {
+        mask_hidden(payload)
+    } else {
+        payload
+    }))
+}
+
+/// Strip sender, content and custom sound so a hidden chat doesn't give itself away.
+pub fn mask_hidden(payload: NotificationPayload) -> NotificationPayload {
+    NotificationPayload {
+        title: hidden::MASKED_TITLE.to_string(),
+        body: hidden::MASKED_BODY.to_string(),
+        sound_hash: None,
+        ..payload
+    }
+}
+
+/// Emit a `notification` event (and `mentioned`, if we were @-mentioned) for an
+/// incoming message. Own messages are ignored.
+pub fn dispatch(app_handle: &crate::AppHandle, msg: &Message) {
+    if msg.peer_id == "Me" {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let msg = msg.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<crate::AppState>();
+        let my_alias = {
+            let mgr = state.config_manager.lock().await;
+            mgr.load()
+                .await
+                .ok()
+                .and_then(|config| config.user.profile.alias)
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let payload = {
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            build_notification(&conn, &msg, my_alias.as_deref(), now)
+        };
+        match payload {
+            Ok(Some(payload)) => {
+                if mentions_me(&msg, my_alias.as_deref()) {
+                    let _ = app_handle.emit("mentioned", payload.clone());
+                }
+                let _ = app_handle.emit("notification", payload);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!(
+                "[Notify] ❌ Failed to build notification for {}: {}",
+                msg.id, e
+            ),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_parses_and_orders() {
+        assert_eq!(
+            NotificationPriority::parse(" High "),
+            Some(NotificationPriority::High)
+        );
+        assert_eq!(NotificationPriority::parse("loud"), None);
+        assert!(NotificationPriority::Urgent.requests_attention());
+        assert!(!NotificationPriority::Normal.requests_attention());
+    }
+
+    #[test]
+    fn mute_and_level_gate_alerts() {
+        let group_id = chat_kind::generate_group_chat_id();
+        let msg = |chat_id: &str, text: &str| Message {
+            id: "m1".to_string(),
+            chat_id: chat_id.to_string(),
+            peer_id: "peer-x".to_string(),
+            timestamp: 1,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            status: "delivered".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+
+        let mut settings = db::ChatSettings::defaults(&group_id);
+        assert!(should_alert(&settings, &msg(&group_id, "hi all"), None, 10));
+
+        settings.mute_until = Some(20);
+        assert!(!should_alert(
+            &settings,
+            &msg(&group_id, "hi all"),
+            None,
+            10
+        ));
+        assert!(should_alert(&settings, &msg(&group_id, "hi all"), None, 20));
+
+        settings.mute_until = None;
+        settings.notification_level = "mentions".to_string();
+        assert!(!should_alert(
+            &settings,
+            &msg(&group_id, "hi all"),
+            Some("Ada"),
+            10
+        ));
+        assert!(should_alert(
+            &settings,
+            &msg(&group_id, "ping @ada"),
+            Some("Ada"),
+            10
+        ));
+        assert!(should_alert(
+            &settings,
+            &msg("peer-x", "hi"),
+            Some("Ada"),
+            10
+        ));
+
+        settings.notification_level = "off".to_string();
+        assert!(!should_alert(&settings, &msg("peer-x", "hi"), None, 10));
+    }
+
+    #[test]
+    fn hidden_chat_notifications_are_masked() {
+        let payload = mask_hidden(NotificationPayload {
+            chat_id: "peer-x".to_string(),
+            msg_id: "m1".to_string(),
+            title: "Bob".to_string(),
+            body: "secret plans".to_string(),
+            priority: NotificationPriority::High,
+            request_attention: true,
+            sound_hash: Some("abc".to_string()),
+        });
+        assert_eq!(payload.title, hidden::MASKED_TITLE);
+        assert_eq!(payload.body, hidden::MASKED_BODY);
+        assert!(payload.sound_hash.is_none() && payload.request_attention);
+    }
+}
@@ -0,0 +1,68 @@
+//! Reminders for Note to Self messages.
+//!
+//! Reminders live in the `reminders` table, so they survive restarts; a
+//! background loop fires everything that is due as a `reminder-due` event plus a
+//! regular `notification`. Reminders that came due while the app was closed
+//! fire right after startup.
+
+use crate::AppHandle;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use super::notify::{NotificationPayload, NotificationPriority};
+use crate::storage::db::{self, Reminder};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+fn notification_for(reminder: &Reminder) -> NotificationPayload {
+    NotificationPayload {
+        chat_id: "self".to_string(),
+        msg_id: reminder.message_id.clone(),
+        title: "⏰ Reminder".to_string(),
+        body: reminder
+            .text
+            .clone()
+            .filter(|text| !text.trim().is_empty())
+            .unwrap_or_else(|| "Note to Self".to_string()),
+        priority: NotificationPriority::High,
+        request_attention: NotificationPriority::High.requests_attention(),
+        sound_hash: None,
+    }
+}
+
+fn fire_due(app_handle: &AppHandle) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let due = {
+        let state = app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        match db::take_due_reminders(&conn, now) {
+            Ok(due) => due,
+            Err(e) => {
+                eprintln!("[Reminders] ❌ Failed to read due reminders: {}", e);
+                return;
+            }
+        }
+    };
+    for reminder in due {
+        println!(
+            "[Reminders] ⏰ Reminder {} for {}",
+            reminder.id, reminder.message_id
+        );
+        let _ = app_handle.emit("notification", notification_for(&reminder));
+        let _ = app_handle.emit("reminder-due", reminder);
+    }
+}
+
+pub fn spawn_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            fire_due(&app_handle);
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
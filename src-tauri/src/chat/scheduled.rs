@@ -0,0 +1,92 @@
+//! Messages scheduled to be sent later.
+//!
+//! Scheduled messages live in the `scheduled_messages` table, so they survive
+//! restarts. A background loop hands every due message to `send_message`,
+//! exactly as if the user had pressed send, and reports the outcome as a
+//! `scheduled-message-sent` event. Messages that came due while the app was
+//! closed go out once the network is up again.
+
+use crate::AppHandle;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::storage::db::{self, ScheduledMessage};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+async fn send_due(app_handle: &AppHandle) {
+    // Sending needs the network manager, which starts after unlock.
+    if app_handle.try_state::<crate::NetworkState>().is_none() {
+        return;
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let due = {
+        let state = app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        match db::due_scheduled_messages(&conn, now) {
+            Ok(due) => due,
+            Err(e) => {
+                eprintln!("[Scheduled] ❌ Failed to read due messages: {}", e);
+                return;
+            }
+        }
+    };
+
+    for scheduled in due {
+        let result = crate::commands::chat::send_message(
+            scheduled.chat_id.clone(),
+            scheduled.text.clone(),
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.clone(),
+        )
+        .await
+        .map_err(String::from);
+        match &result {
+            Ok(msg_id) => println!(
+                "[Scheduled] 📤 Sent scheduled message {} as {}",
+                scheduled.id, msg_id
+            ),
+            Err(e) => eprintln!(
+                "[Scheduled] ❌ Scheduled message {} failed: {}",
+                scheduled.id, e
+            ),
+        }
+
+        let state = app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            continue;
+        };
+        let outcome = result.as_deref().map_err(String::as_str);
+        if let Err(e) = db::finish_scheduled_message(&conn, scheduled.id, outcome) {
+            eprintln!("[Scheduled] ❌ Failed to record outcome: {}", e);
+        }
+        let (status, message_id, error) = match result {
+            Ok(msg_id) => ("sent", Some(msg_id), None),
+            Err(e) => ("failed", None, Some(e)),
+        };
+        let _ = app_handle.emit(
+            "scheduled-message-sent",
+            ScheduledMessage {
+                status: status.to_string(),
+                message_id,
+                error,
+                ..scheduled
+            },
+        );
+    }
+}
+
+pub fn spawn_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            send_due(&app_handle).await;
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,230 @@
+//! Local spam/scam heuristics for first-contact messages.
+//!
+//! Nothing here leaves the device: the score is computed from the message text
+//! plus a short-lived in-memory record of identical texts seen across chats and
+//! group topics, so mass-broadcast scams stand out.
+
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+const REPEAT_WINDOW: Duration = Duration::from_secs(600);
+const MIN_TRACKED_TEXT_CHARS: usize = 12;
+const TRACKER_PRUNE_THRESHOLD: usize = 2048;
+
+const URL_SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "tinyurl.com",
+    "t.co/",
+    "goo.gl",
+    "cutt.ly",
+    "is.gd",
+    "rb.gy",
+    "t.me/",
+];
+
+/// (pattern, weight, reason). Patterns are matched against lowercased text.
+const SCAM_PATTERNS: &[(&str, u32, &str)] = &[
+    ("seed phrase", 35, "asks for a seed phrase"),
+    ("recovery phrase", 35, "asks for a recovery phrase"),
+    ("private key", 30, "mentions private keys"),
+    ("double your", 30, "promises to double money"),
+    ("guaranteed profit", 30, "promises guaranteed profit"),
+    ("guaranteed return", 30, "promises guaranteed returns"),
+    ("verify your account", 25, "asks to verify an account"),
+    ("you have won", 25, "claims you won a prize"),
+    ("gift card", 25, "mentions gift cards"),
+    ("claim your", 20, "urges you to claim something"),
+    ("giveaway", 20, "mentions a giveaway"),
+    ("investment opportunity", 20, "pitches an investment"),
+    ("wire transfer", 20, "asks for a wire transfer"),
+    ("click here", 15, "urges you to click a link"),
+    ("bitcoin", 10, "mentions cryptocurrency"),
+    ("usdt", 10, "mentions cryptocurrency"),
+    ("crypto", 10, "mentions cryptocurrency"),
+    ("urgent", 10, "creates urgency"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpamLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl SpamLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    fn from_score(score: u32) -> Self {
+        match score {
+            0..=29 => Self::Low,
+            30..=59 => Self::Medium,
+            _ => Self::High,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpamScore {
+    pub score: u32, // 0-100
+    pub level: SpamLevel,
+    pub reasons: Vec<String>,
+}
+
+fn is_link(token: &str) -> bool {
+    let token = token.trim_matches(|c: char| !c.is_alphanumeric() && c != '/' && c != '.');
+    token.starts_with("http://")
+        || token.starts_with("https://")
+        || token.starts_with("www.")
+        || URL_SHORTENERS.iter().any(|s| token.contains(s))
+}
+
+/// Score `text`; `repeated_targets` is how many distinct chats/topics recently
+/// received the exact same text (see [`RepeatTracker`]).
+pub fn score_text(text: &str, repeated_targets: usize) -> SpamScore {
+    let lower = text.to_lowercase();
+    let mut score = 0u32;
+    let mut reasons = Vec::new();
+
+    let words = lower.split_whitespace().count().max(1);
+    let links = lower.split_whitespace().filter(|t| is_link(t)).count();
+    if links > 0 {
+        score += 15;
+        reasons.push(format!("contains {} link(s)", links));
+        if links * 10 >= words * 3 {
+            score += 20;
+            reasons.push("mostly links".to_string());
+        }
+        if URL_SHORTENERS.iter().any(|s| lower.contains(s)) {
+            score += 15;
+            reasons.push("uses a link shortener".to_string());
+        }
+    }
+
+    let mut pattern_score = 0u32;
+    for (pattern, weight, reason) in SCAM_PATTERNS {
+        if lower.contains(pattern) {
+            pattern_score += weight;
+            if !reasons.iter().any(|r| r == reason) {
+                reasons.push(reason.to_string());
+            }
+        }
+    }
+    score += pattern_score.min(60);
+
+    if repeated_targets >= 6 {
+        score += 45;
+    } else if repeated_targets >= 3 {
+        score += 30;
+    }
+    if repeated_targets >= 3 {
+        reasons.push(format!(
+            "same text sent to {} chats recently",
+            repeated_targets
+        ));
+    }
+
+    let score = score.min(100);
+    SpamScore {
+        score,
+        level: SpamLevel::from_score(score),
+        reasons,
+    }
+}
+
+fn fingerprint(text: &str) -> Option<u64> {
+    let normalized = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if normalized.chars().count() < MIN_TRACKED_TEXT_CHARS {
+        return None;
+    }
+    let mut hasher = DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Remembers which chats/topics recently received each distinct text.
+#[derive(Default)]
+pub struct RepeatTracker {
+    seen: HashMap<u64, Vec<(String, Instant)>>,
+}
+
+impl RepeatTracker {
+    /// Record `text` arriving in `target` and return how many distinct targets
+    /// received it within the window (including this one).
+    pub fn observe(&mut self, text: &str, target: &str) -> usize {
+        self.observe_at(text, target, Instant::now())
+    }
+
+    fn observe_at(&mut self, text: &str, target: &str, now: Instant) -> usize {
+        let Some(key) = fingerprint(text) else {
+            return 1;
+        };
+        if self.seen.len() > TRACKER_PRUNE_THRESHOLD {
+            self.seen.retain(|_, targets| {
+                targets.retain(|(_, at)| now.duration_since(*at) <= REPEAT_WINDOW);
+                !targets.is_empty()
+            });
+        }
+
+        let targets = self.seen.entry(key).or_default();
+        targets.retain(|(_, at)| now.duration_since(*at) <= REPEAT_WINDOW);
+        match targets.iter_mut().find(|(t, _)| t == target) {
+            Some(entry) => entry.1 = now,
+            None => targets.push((target.to_string(), now)),
+        }
+        targets.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_greeting_scores_low() {
+        let score = score_text("Hey, it's Sam from the meetup yesterday!", 1);
+        assert_eq!(score.level, SpamLevel::Low);
+        assert!(score.reasons.is_empty());
+    }
+
+    #[test]
+    fn scam_text_with_links_scores_high() {
+        let score = score_text(
+            "URGENT giveaway! Double your bitcoin, claim your prize: https://bit.ly/x",
+            1,
+        );
+        assert_eq!(score.level, SpamLevel::High);
+        assert!(score.reasons.iter().any(|r| r.contains("link shortener")));
+    }
+
+    #[test]
+    fn tracker_counts_distinct_targets_in_window() {
+        let mut tracker = RepeatTracker::default();
+        let now = Instant::now();
+        let text = "Join my exclusive trading group today";
+        assert_eq!(tracker.observe_at(text, "group:a", now), 1);
+        assert_eq!(tracker.observe_at(text, "group:a", now), 1);
+        assert_eq!(
+            tracker.observe_at("join my  EXCLUSIVE trading group today", "peer-b", now),
+            2
+        );
+        assert_eq!(
+            tracker.observe_at(text, "peer-c", now + REPEAT_WINDOW * 2),
+            1
+        );
+        assert_eq!(tracker.observe_at("hi", "peer-d", now), 1);
+    }
+}
@@ -0,0 +1,309 @@
+//! Smart reply suggestions.
+//!
+//! On receipt of a text message a [`SuggestionProvider`] computes up to
+//! [`MAX_SUGGESTIONS`] short replies. The built-in provider is a local heuristic;
+//! users can point the app at an OpenAI-compatible model endpoint instead.
+//! Results are cached per message id and pushed to the UI via `reply-suggestions`.
+
+use crate::AppHandle;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use super::completions;
+use crate::storage::config::ReplySuggestionSettings;
+use crate::storage::db::Message;
+
+pub const MAX_SUGGESTIONS: usize = 3;
+const MAX_SUGGESTION_CHARS: usize = 80;
+const CACHE_CAPACITY: usize = 256;
+const MODEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type SuggestionFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<String>, String>> + Send + 'a>>;
+
+pub trait SuggestionProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn suggest<'a>(&'a self, msg: &'a Message) -> SuggestionFuture<'a>;
+}
+
+/// Keyword rules over the incoming text; never leaves the device.
+pub struct HeuristicProvider;
+
+impl SuggestionProvider for HeuristicProvider {
+    fn name(&self) -> &'static str {
+        "heuristic"
+    }
+
+    fn suggest<'a>(&'a self, msg: &'a Message) -> SuggestionFuture<'a> {
+        let text = msg.content.text().unwrap_or_default().to_string();
+        Box::pin(async move { Ok(heuristic_suggestions(&text)) })
+    }
+}
+
+fn owned(replies: &[&str]) -> Vec<String> {
+    replies.iter().map(|r| r.to_string()).collect()
+}
+
+pub fn heuristic_suggestions(text: &str) -> Vec<String> {
+    let lower = text.trim().to_lowercase();
+    if lower.is_empty() {
+        return Vec::new();
+    }
+    let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| lower.starts_with(p));
+    let contains_any = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+
+    if lower.ends_with('?') {
+        if contains_any(&["how are you", "how's it going", "how are things"]) {
+            return owned(&["Good, thanks! You?", "All good!", "Not bad"]);
+        }
+        if starts_with_any(&["can you", "could you", "will you", "would you"]) {
+            return owned(&["Sure!", "Sorry, I can't", "Let me check"]);
+        }
+        if starts_with_any(&["what", "when", "where", "why", "who", "how", "which"]) {
+            return owned(&["Let me check", "Not sure yet", "I'll get back to you"]);
+        }
+        return owned(&["Yes", "No", "Maybe"]);
+    }
+    if contains_any(&["thank", "thx", "cheers"]) {
+        return owned(&["You're welcome!", "Anytime!", "No problem"]);
+    }
+    if contains_any(&["sorry", "my bad", "apolog"]) {
+        return owned(&["No worries", "It's okay", "All good"]);
+    }
+    if contains_any(&["bye", "good night", "see you", "later!"]) {
+        return owned(&["Bye!", "See you!", "Take care"]);
+    }
+    if starts_with_any(&["hi", "hello", "hey", "good morning", "yo"]) && lower.len() <= 24 {
+        return owned(&["Hey!", "Hi! How are you?", "Hello 👋"]);
+    }
+    owned(&["👍", "Okay", "Sounds good"])
+}
+
+/// Asks the configured model endpoint for replies.
+pub struct ModelProvider {
+    client: completions::Client,
+}
+
+impl ModelProvider {
+    pub fn new(endpoint: String, model: String, api_key: Option<String>) -> Self {
+        Self {
+            client: completions::Client::new(endpoint, model, api_key, MODEL_TIMEOUT)
+                .with_max_tokens(60),
+        }
+    }
+}
+
+impl SuggestionProvider for ModelProvider {
+    fn name(&self) -> &'static str {
+        "model"
+    }
+
+    fn suggest<'a>(&'a self, msg: &'a Message) -> SuggestionFuture<'a> {
+        Box::pin(async move {
+            let text = msg.content.text().unwrap_or_default();
+            let content = self
+                .client
+                .complete(
+                    "Suggest up to three short replies (at most six words each) to the user's chat message. One reply per line, no numbering, no commentary.",
+                    text,
+                )
+                .await?;
+            Ok(clean_model_suggestions(&content))
+        })
+    }
+}
+
+/// Normalize free-form model output into at most [`MAX_SUGGESTIONS`] replies.
+pub fn clean_model_suggestions(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(|c: char| {
+                    c.is_ascii_digit() || matches!(c, '-' | '*' | '•' | '.' | ')')
+                })
+                .trim()
+                .trim_matches('"')
+                .trim()
+        })
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().take(MAX_SUGGESTION_CHARS).collect::<String>())
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+pub fn provider_from_settings(
+    settings: &ReplySuggestionSettings,
+) -> Option<Box<dyn SuggestionProvider>> {
+    if !settings.enabled {
+        return None;
+    }
+    let endpoint = settings
+        .model_endpoint
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty());
+    let model = settings
+        .model_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty());
+    match (endpoint, model) {
+        (Some(endpoint), Some(model)) => Some(Box::new(ModelProvider::new(
+            endpoint.to_string(),
+            model.to_string(),
+            settings.model_api_key.clone(),
+        ))),
+        _ => Some(Box::new(HeuristicProvider)),
+    }
+}
+
+#[derive(Default)]
+struct SuggestionCache {
+    entries: HashMap<String, Vec<String>>,
+    order: VecDeque<String>,
+}
+
+impl SuggestionCache {
+    fn insert(&mut self, msg_id: String, suggestions: Vec<String>) {
+        if self.entries.insert(msg_id.clone(), suggestions).is_none() {
+            self.order.push_back(msg_id);
+        }
+        while self.order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Per-message suggestion cache, managed as Tauri state.
+#[derive(Default)]
+pub struct ReplySuggestions {
+    cache: Mutex<SuggestionCache>,
+}
+
+impl ReplySuggestions {
+    pub fn get(&self, msg_id: &str) -> Option<Vec<String>> {
+        self.cache.lock().ok()?.entries.get(msg_id).cloned()
+    }
+
+    pub fn insert(&self, msg_id: String, suggestions: Vec<String>) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(msg_id, suggestions);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReplySuggestionsEvent {
+    msg_id: String,
+    chat_id: String,
+    suggestions: Vec<String>,
+}
+
+pub async fn load_settings(app_handle: &AppHandle) -> Option<ReplySuggestionSettings> {
+    let state = app_handle.state::<crate::AppState>();
+    let mgr = state.config_manager.lock().await;
+    mgr.load()
+        .await
+        .ok()
+        .map(|config| config.user.reply_suggestions)
+}
+
+/// Run the configured provider, falling back to the heuristic on model errors.
+pub async fn compute(settings: &ReplySuggestionSettings, msg: &Message) -> Vec<String> {
+    let Some(provider) = provider_from_settings(settings) else {
+        return Vec::new();
+    };
+    match provider.suggest(msg).await {
+        Ok(mut suggestions) => {
+            suggestions.truncate(MAX_SUGGESTIONS);
+            suggestions
+        }
+        Err(e) => {
+            eprintln!(
+                "[Suggest] ⚠️ {} provider failed for {}: {}",
+                provider.name(),
+                msg.id,
+                e
+            );
+            heuristic_suggestions(msg.content.text().unwrap_or_default())
+        }
+    }
+}
+
+fn wants_suggestions(msg: &Message) -> bool {
+    msg.peer_id != "Me"
+        && matches!(
+            &msg.content,
+            crate::chat::message::MessageContent::Text { text } if !text.trim().is_empty()
+        )
+}
+
+/// Hook for incoming messages: compute suggestions in the background if enabled.
+pub fn on_message_received(app_handle: &AppHandle, msg: &Message) {
+    if !wants_suggestions(msg) {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let msg = msg.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(settings) = load_settings(&app_handle).await else {
+            return;
+        };
+        let suggestions = compute(&settings, &msg).await;
+        if suggestions.is_empty() {
+            return;
+        }
+        app_handle
+            .state::<ReplySuggestions>()
+            .insert(msg.id.clone(), suggestions.clone());
+        let _ = app_handle.emit(
+            "reply-suggestions",
+            ReplySuggestionsEvent {
+                msg_id: msg.id,
+                chat_id: msg.chat_id,
+                suggestions,
+            },
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristic_matches_common_intents() {
+        assert_eq!(
+            heuristic_suggestions("Can you send the file?"),
+            vec!["Sure!", "Sorry, I can't", "Let me check"]
+        );
+        assert_eq!(heuristic_suggestions("Is it done?")[0], "Yes");
+        assert_eq!(heuristic_suggestions("thanks a lot")[0], "You're welcome!");
+        assert!(heuristic_suggestions("   ").is_empty());
+    }
+
+    #[test]
+    fn model_output_is_cleaned_and_capped() {
+        let cleaned =
+            clean_model_suggestions("1. \"Sure thing\"\n- On my way\n\n* Later\n4) Extra");
+        assert_eq!(cleaned, vec!["Sure thing", "On my way", "Later"]);
+    }
+
+    #[test]
+    fn cache_evicts_oldest_entries() {
+        let mut cache = SuggestionCache::default();
+        for i in 0..=CACHE_CAPACITY {
+            cache.insert(format!("m{}", i), vec!["ok".to_string()]);
+        }
+        assert!(!cache.entries.contains_key("m0"));
+        assert!(cache.entries.contains_key(&format!("m{}", CACHE_CAPACITY)));
+    }
+}
@@ -0,0 +1,140 @@
+//! Outgoing message translation.
+//!
+//! Direct chats can turn on "send translated": the composed text is run through
+//! a [`TranslationProvider`] into the peer's preferred language and the
+//! envelope carries both the original and the translation. Both sides keep the
+//! translation in the message's `content_metadata` under `"translation"`.
+//! Without a configured provider, or when it fails, only the original is sent.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use super::completions;
+use crate::network::direct_message::MessageTranslation;
+use crate::storage::config::TranslationSettings;
+
+const MODEL_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_LANG_CODE_CHARS: usize = 16;
+
+pub type TranslationFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
+
+pub trait TranslationProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn translate<'a>(&'a self, text: &'a str, target_lang: &'a str) -> TranslationFuture<'a>;
+}
+
+/// Translates through the configured model endpoint.
+pub struct ModelTranslator {
+    client: completions::Client,
+}
+
+impl ModelTranslator {
+    pub fn new(endpoint: String, model: String, api_key: Option<String>) -> Self {
+        Self {
+            client: completions::Client::new(endpoint, model, api_key, MODEL_TIMEOUT),
+        }
+    }
+}
+
+impl TranslationProvider for ModelTranslator {
+    fn name(&self) -> &'static str {
+        "model"
+    }
+
+    fn translate<'a>(&'a self, text: &'a str, target_lang: &'a str) -> TranslationFuture<'a> {
+        Box::pin(async move {
+            let system = format!(
+                "Translate the user's chat message into the language with code '{}'. Reply with the translation only, keeping emoji, names and formatting.",
+                target_lang
+            );
+            let content = self.client.complete(&system, text).await?;
+            Ok(content.trim().to_string())
+        })
+    }
+}
+
+pub fn provider_from_settings(
+    settings: &TranslationSettings,
+) -> Option<Box<dyn TranslationProvider>> {
+    let endpoint = settings
+        .model_endpoint
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())?;
+    let model = settings
+        .model_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty())?;
+    Some(Box::new(ModelTranslator::new(
+        endpoint.to_string(),
+        model.to_string(),
+        settings.model_api_key.clone(),
+    )))
+}
+
+/// Normalize a user-entered language code ("DE", " pt-BR ") or reject it.
+pub fn normalize_lang(lang: &str) -> Option<String> {
+    let lang = lang.trim();
+    let valid = !lang.is_empty()
+        && lang.len() <= MAX_LANG_CODE_CHARS
+        && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then(|| lang.to_ascii_lowercase())
+}
+
+/// `content_metadata` JSON for a message carrying a translation.
+pub fn translation_metadata(translation: &MessageTranslation) -> String {
+    serde_json::json!({ "translation": translation }).to_string()
+}
+
+/// Translate `text` for sending, or `None` if it is blank, no provider is
+/// configured or the provider fails (the message then goes out untranslated).
+pub async fn translate_outgoing(
+    settings: &TranslationSettings,
+    text: &str,
+    target_lang: &str,
+) -> Option<MessageTranslation> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let provider = provider_from_settings(settings)?;
+    match provider.translate(text, target_lang).await {
+        Ok(translated) if !translated.is_empty() && translated != text => {
+            Some(MessageTranslation {
+                lang: target_lang.to_string(),
+                text: translated,
+            })
+        }
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!(
+                "[Translate] ⚠️ {} provider failed, sending original only: {}",
+                provider.name(),
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_codes_are_normalized_and_metadata_round_trips() {
+        assert_eq!(normalize_lang(" pt-BR ").as_deref(), Some("pt-br"));
+        assert_eq!(normalize_lang("de; drop"), None);
+        assert_eq!(normalize_lang(""), None);
+
+        let translation = MessageTranslation {
+            lang: "de".to_string(),
+            text: "Hallo".to_string(),
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&translation_metadata(&translation)).expect("json");
+        assert_eq!(json["translation"]["text"], "Hallo");
+        assert!(provider_from_settings(&TranslationSettings::default()).is_none());
+    }
+}
@@ -1,7 +1,15 @@
 use tauri::{Emitter, Manager, State};
 
-use crate::storage::config::{Config, ConnectivityMode, ConnectivitySettings};
+use crate::error::RChatError;
+use crate::network::hks::KeyRotation;
+use crate::storage::config::{
+    Config, ConnectivityMode, ConnectivitySettings, DiscoveryBackendKind, GitlabDiscovery,
+};
 use crate::{network, oauth, AppState, NetworkState};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
 #[derive(serde::Serialize)]
 pub struct AuthStatus {
@@ -10,9 +18,12 @@ pub struct AuthStatus {
     is_github_connected: bool,
     is_online: bool,
     connectivity: ConnectivitySettings,
+    /// Only set by `init_vault` when a phrase was requested; shown once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recovery_phrase: Option<String>,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct ConnectivitySettingsPatch {
     pub mdns_enabled: Option<bool>,
     pub github_sync_enabled: Option<bool>,
@@ -24,6 +35,25 @@ fn normalize_connectivity(settings: ConnectivitySettings) -> ConnectivitySetting
     settings.with_derived_mode()
 }
 
+pub(crate) fn apply_connectivity_patch(
+    mut next: ConnectivitySettings,
+    patch: &ConnectivitySettingsPatch,
+) -> ConnectivitySettings {
+    if let Some(v) = patch.mdns_enabled {
+        next.mdns_enabled = v;
+    }
+    if let Some(v) = patch.github_sync_enabled {
+        next.github_sync_enabled = v;
+    }
+    if let Some(v) = patch.nat_keepalive_enabled {
+        next.nat_keepalive_enabled = v;
+    }
+    if let Some(v) = patch.punch_assist_enabled {
+        next.punch_assist_enabled = v;
+    }
+    normalize_connectivity(next)
+}
+
 fn unlocked_auth_status(config: &Config) -> AuthStatus {
     let connectivity = normalize_connectivity(config.user.connectivity.clone());
     AuthStatus {
@@ -32,10 +62,14 @@ fn unlocked_auth_status(config: &Config) -> AuthStatus {
         is_github_connected: config.system.github_token.is_some(),
         is_online: connectivity.github_sync_enabled,
         connectivity,
+        recovery_phrase: None,
     }
 }
 
-async fn sync_runtime_connectivity(app_handle: &tauri::AppHandle, settings: &ConnectivitySettings) {
+pub(crate) async fn sync_runtime_connectivity(
+    app_handle: &crate::AppHandle,
+    settings: &ConnectivitySettings,
+) {
     if let Some(network_state) = app_handle.try_state::<NetworkState>() {
         let mut runtime = network_state.connectivity.lock().await;
         *runtime = settings.clone();
@@ -43,36 +77,38 @@ async fn sync_runtime_connectivity(app_handle: &tauri::AppHandle, settings: &Con
 }
 
 #[tauri::command]
-pub async fn save_api_token(token: String, state: State<'_, AppState>) -> Result<(), String> {
-    // Fetch username from GitHub API using octocrab
-    let octocrab = octocrab::Octocrab::builder()
-        .personal_token(token.clone())
-        .build()
-        .map_err(|e| format!("Failed to build octocrab client: {}", e))?;
-
-    let user: octocrab::models::Author = octocrab
-        .get("/user", None::<&()>)
+pub async fn save_api_token(token: String, state: State<'_, AppState>) -> Result<(), RChatError> {
+    let token = token.trim().to_string();
+    let info = network::gist::validate_token(&token)
         .await
-        .map_err(|e| format!("Failed to fetch GitHub user: {}", e))?;
-
-    let username = user.login;
-    println!("[Backend] GitHub username fetched: {}", username);
+        .map_err(|e| format!("GitHub token check failed: {}", e))?;
+    let username = info.username;
+    println!(
+        "[Backend] GitHub username fetched: {} (scopes: {})",
+        username,
+        if info.scopes.is_empty() {
+            "fine-grained".to_string()
+        } else {
+            info.scopes.join(", ")
+        }
+    );
 
     // Save both token and username
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
     config.system.github_token = Some(token);
     config.system.github_username = Some(username);
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn check_auth_status(state: State<'_, AppState>) -> Result<AuthStatus, String> {
+pub async fn check_auth_status(state: State<'_, AppState>) -> Result<AuthStatus, RChatError> {
     let mgr = state.config_manager.lock().await;
 
     let connectivity = if mgr.is_unlocked() {
         if let Ok(config) = mgr.load().await {
+            network::proxy::set_proxy(config.system.socks5_proxy.clone());
             normalize_connectivity(config.user.connectivity)
         } else {
             ConnectivitySettings::default()
@@ -87,22 +123,14 @@ pub async fn check_auth_status(state: State<'_, AppState>) -> Result<AuthStatus,
         if let Ok(config) = mgr.load().await {
             if config.system.github_token.is_some() && config.system.github_username.is_none() {
                 if let Some(ref token) = config.system.github_token {
-                    if let Ok(octocrab) = octocrab::Octocrab::builder()
-                        .personal_token(token.clone())
-                        .build()
-                    {
-                        if let Ok(user) = octocrab
-                            .get::<octocrab::models::Author, _, _>("/user", None::<&()>)
-                            .await
-                        {
-                            println!(
-                                "[Backend] Migrating: fetched GitHub username {}",
-                                user.login
-                            );
-                            let mut updated_config = config.clone();
-                            updated_config.system.github_username = Some(user.login);
-                            let _ = mgr.save(&updated_config).await;
-                        }
+                    if let Ok(info) = network::gist::validate_token(token).await {
+                        println!(
+                            "[Backend] Migrating: fetched GitHub username {}",
+                            info.username
+                        );
+                        let mut updated_config = config.clone();
+                        updated_config.system.github_username = Some(info.username);
+                        let _ = mgr.save(&updated_config).await;
                     }
                 }
             }
@@ -115,6 +143,7 @@ pub async fn check_auth_status(state: State<'_, AppState>) -> Result<AuthStatus,
         is_github_connected: mgr.has_token().await,
         is_online,
         connectivity,
+        recovery_phrase: None,
     })
 }
 
@@ -122,8 +151,8 @@ pub async fn check_auth_status(state: State<'_, AppState>) -> Result<AuthStatus,
 pub async fn toggle_online_status(
     online: bool,
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
     // Compatibility wrapper for legacy clients.
     let mapped = if online {
         ConnectivitySettings::from_mode(ConnectivityMode::Reachable)
@@ -132,10 +161,10 @@ pub async fn toggle_online_status(
     };
 
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
     config.user.connectivity = mapped.clone();
     config.user.is_online = mapped.github_sync_enabled;
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
     drop(mgr);
 
     sync_runtime_connectivity(&app_handle, &mapped).await;
@@ -145,9 +174,9 @@ pub async fn toggle_online_status(
 #[tauri::command]
 pub async fn get_connectivity_settings(
     state: State<'_, AppState>,
-) -> Result<ConnectivitySettings, String> {
+) -> Result<ConnectivitySettings, RChatError> {
     let mgr = state.config_manager.lock().await;
-    let config = mgr.load().await.map_err(|e| e.to_string())?;
+    let config = mgr.load().await?;
     Ok(normalize_connectivity(config.user.connectivity))
 }
 
@@ -155,10 +184,10 @@ pub async fn get_connectivity_settings(
 pub async fn set_connectivity_mode(
     mode: ConnectivityMode,
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<ConnectivitySettings, String> {
+    app_handle: crate::AppHandle,
+) -> Result<ConnectivitySettings, RChatError> {
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
 
     let next = match mode {
         ConnectivityMode::Invisible => ConnectivitySettings::from_mode(ConnectivityMode::Invisible),
@@ -169,7 +198,7 @@ pub async fn set_connectivity_mode(
 
     config.user.connectivity = next.clone();
     config.user.is_online = next.github_sync_enabled;
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
     drop(mgr);
 
     sync_runtime_connectivity(&app_handle, &next).await;
@@ -180,29 +209,15 @@ pub async fn set_connectivity_mode(
 pub async fn update_connectivity_settings(
     patch: ConnectivitySettingsPatch,
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<ConnectivitySettings, String> {
+    app_handle: crate::AppHandle,
+) -> Result<ConnectivitySettings, RChatError> {
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
-    let mut next = config.user.connectivity.clone();
-
-    if let Some(v) = patch.mdns_enabled {
-        next.mdns_enabled = v;
-    }
-    if let Some(v) = patch.github_sync_enabled {
-        next.github_sync_enabled = v;
-    }
-    if let Some(v) = patch.nat_keepalive_enabled {
-        next.nat_keepalive_enabled = v;
-    }
-    if let Some(v) = patch.punch_assist_enabled {
-        next.punch_assist_enabled = v;
-    }
-    next = normalize_connectivity(next);
+    let mut config = mgr.load().await?;
+    let next = apply_connectivity_patch(config.user.connectivity.clone(), &patch);
 
     config.user.connectivity = next.clone();
     config.user.is_online = next.github_sync_enabled;
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
     drop(mgr);
 
     sync_runtime_connectivity(&app_handle, &next).await;
@@ -212,10 +227,45 @@ pub async fn update_connectivity_settings(
 #[tauri::command]
 pub async fn init_vault(
     password: String,
+    recovery_phrase: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<AuthStatus, String> {
+) -> Result<AuthStatus, RChatError> {
     let mut mgr = state.config_manager.lock().await;
     let config = mgr.init(password.trim()).await.map_err(|e| e.to_string())?;
+    state.journal.set_key(mgr.journal_key());
+    let mut status = unlocked_auth_status(&config);
+    if recovery_phrase.unwrap_or(false) {
+        status.recovery_phrase = Some(
+            mgr.create_recovery_phrase()
+                .await
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    Ok(status)
+}
+
+/// Unlock with the recovery phrase from `init_vault` and set a new password.
+#[tauri::command]
+pub async fn recover_vault(
+    phrase: String,
+    new_password: String,
+    state: State<'_, AppState>,
+) -> Result<AuthStatus, RChatError> {
+    let mut mgr = state.config_manager.lock().await;
+    let (config, old_journal_key) =
+        mgr.recover(&phrase, new_password.trim())
+            .await
+            .map_err(|e| {
+                eprintln!("[Backend] Recovery failed: {}", e);
+                e.to_string()
+            })?;
+    state.journal.set_key(mgr.journal_key());
+    match state.journal.rekey(&old_journal_key) {
+        Ok(days) if days > 0 => println!("[Journal] 🔑 Re-encrypted {} journal day(s)", days),
+        Ok(_) => {}
+        Err(e) => eprintln!("[Journal] ❌ Failed to re-encrypt journal: {}", e),
+    }
+    network::proxy::set_proxy(config.system.socks5_proxy.clone());
     Ok(unlocked_auth_status(&config))
 }
 
@@ -223,28 +273,26 @@ pub async fn init_vault(
 pub async fn unlock_vault(
     password: String,
     state: State<'_, AppState>,
-) -> Result<AuthStatus, String> {
-    println!(
-        "[Backend] unlock_vault called. Password len: {}",
-        password.len()
-    );
+) -> Result<AuthStatus, RChatError> {
+    println!("[Backend] unlock_vault called");
     let mut mgr = state.config_manager.lock().await;
-    println!("[Backend] Password trimmed len: {}", password.trim().len());
     let config = mgr
         .unlock_with_password(password.trim())
         .await
         .map_err(|e| {
             eprintln!("[Backend] Unlock failed: {}", e);
-            e.to_string()
+            RChatError::Crypto(e.to_string())
         })?;
     println!("[Backend] Vault unlocked successfully.");
+    state.journal.set_key(mgr.journal_key());
+    network::proxy::set_proxy(config.system.socks5_proxy.clone());
     Ok(unlocked_auth_status(&config))
 }
 
 /// Start the P2P network - call this AFTER vault is unlocked
 /// This ensures the persisted keypair can be loaded from the encrypted config
 #[tauri::command]
-pub async fn start_network(app_handle: tauri::AppHandle) -> Result<(), String> {
+pub async fn start_network(app_handle: crate::AppHandle) -> Result<(), RChatError> {
     println!("[Backend] start_network called (post-unlock)");
 
     // Check if network is already running
@@ -257,42 +305,223 @@ pub async fn start_network(app_handle: tauri::AppHandle) -> Result<(), String> {
         let app_state = app_handle.state::<AppState>();
         let github_peer_mapping = {
             let mgr = app_state.config_manager.lock().await;
-            let config = mgr.load().await.map_err(|e| e.to_string())?;
+            let config = mgr.load().await?;
             config.user.github_peer_mapping
         };
         let mut conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
-        crate::storage::db::migrate_legacy_github_chat_ids(&mut conn, &github_peer_mapping)
-            .map_err(|e| e.to_string())?;
+        crate::storage::db::migrate_legacy_github_chat_ids(&mut conn, &github_peer_mapping)?;
     }
 
     match network::init(app_handle.clone()).await {
         Ok(_) => {
             println!("[Backend] Network started successfully!");
+            crate::bridge::start_enabled_bridges(&app_handle).await;
+            crate::bot_api::start_if_enabled(&app_handle).await;
+            crate::http_api::start_if_enabled(&app_handle).await;
             let _ = app_handle.emit("auth-status", serde_json::json!({"unlocked": true}));
             Ok(())
         }
         Err(e) => {
             eprintln!("[Backend] Failed to start network: {}", e);
-            Err(e.to_string())
+            Err(RChatError::Network(e.to_string()))
         }
     }
 }
 
 #[tauri::command]
-pub async fn start_github_auth() -> Result<oauth::AuthState, String> {
-    oauth::start_device_flow().await.map_err(|e| e.to_string())
+pub async fn start_github_auth() -> Result<oauth::AuthState, RChatError> {
+    oauth::start_device_flow()
+        .await
+        .map_err(|e| RChatError::Network(e.to_string()))
 }
 
 #[tauri::command]
-pub async fn poll_github_auth(device_code: String) -> Result<String, String> {
+pub async fn poll_github_auth(device_code: String) -> Result<String, RChatError> {
     oauth::poll_for_token(&device_code)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| RChatError::Network(e.to_string()))
+}
+
+/// Forget the stored GitHub token and username; gist sync stops until a new
+/// token is saved.
+#[tauri::command]
+pub async fn disconnect_github(state: State<'_, AppState>) -> Result<(), RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    config.system.github_token = None;
+    config.system.github_username = None;
+    mgr.save(&config).await?;
+    println!("[Backend] GitHub disconnected");
+    Ok(())
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct GitlabDiscoveryInput {
+    /// Defaults to `https://gitlab.com`.
+    pub base_url: Option<String>,
+    pub token: String,
+    pub project: String,
+}
+
+/// Choose where peer info is published. GitLab settings are checked against
+/// the instance first; returns our username there.
+#[tauri::command]
+pub async fn set_discovery_backend(
+    backend: DiscoveryBackendKind,
+    gitlab: Option<GitlabDiscoveryInput>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, RChatError> {
+    let gitlab = match gitlab {
+        Some(input) => {
+            let mut settings = GitlabDiscovery {
+                base_url: input
+                    .base_url
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .unwrap_or_else(|| "https://gitlab.com".to_string()),
+                token: input.token.trim().to_string(),
+                project: input.project.trim().trim_matches('/').to_string(),
+                username: None,
+            };
+            let username = network::discovery_backend::GitlabSnippetBackend::validate(&settings)
+                .await
+                .map_err(|e| format!("GitLab check failed: {}", e))?;
+            settings.username = Some(username);
+            Some(settings)
+        }
+        None => None,
+    };
+
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    if gitlab.is_some() {
+        config.system.gitlab = gitlab;
+    }
+    if backend == DiscoveryBackendKind::Gitlab && config.system.gitlab.is_none() {
+        return Err(RChatError::InvalidInput(
+            "GitLab token and project are required".to_string(),
+        ));
+    }
+    config.system.discovery_backend = backend;
+    mgr.save(&config).await?;
+    crate::network::discovery::forget_last_published();
+    Ok(config.system.discovery_username().map(ToOwned::to_owned))
+}
+
+/// Route discovery, GitHub sign-in and outgoing libp2p TCP through a SOCKS5
+/// proxy (`host:port`), or stop with `None`. HTTP switches immediately; the
+/// libp2p transport on the next network start.
+#[tauri::command]
+pub async fn set_socks5_proxy(
+    proxy: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let proxy = proxy
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty());
+    if let Some(addr) = &proxy {
+        network::proxy::validate_proxy_addr(addr)?;
+    }
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    config.system.socks5_proxy = proxy.clone();
+    mgr.save(&config).await?;
+    network::proxy::set_proxy(proxy);
+    Ok(())
+}
+
+/// Replace our Ed25519/X25519 keys. The new keys are signed with the old
+/// identity key and the rotation is published right away, so friends polling
+/// our blob verify it and switch over without a new invite.
+#[tauri::command]
+pub async fn rotate_identity_keys(
+    app: crate::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let backend = {
+        let mgr = state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        let old_key = config
+            .user
+            .identity_private_key
+            .as_deref()
+            .and_then(|key| BASE64.decode(key).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(|bytes| SigningKey::from_bytes(&bytes))
+            .ok_or("Identity key not set")?;
+
+        let mut csprng = OsRng;
+        let new_identity = SigningKey::generate(&mut csprng);
+        let new_encryption = StaticSecret::random_from_rng(&mut csprng);
+        let rotated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let rotation = KeyRotation::new(
+            &old_key,
+            &new_identity.verifying_key(),
+            &X25519PublicKey::from(&new_encryption),
+            rotated_at,
+        );
+
+        config
+            .user
+            .key_rotations
+            .push(serde_json::to_string(&rotation).map_err(|e| e.to_string())?);
+        config.user.identity_private_key = Some(BASE64.encode(new_identity.to_bytes()));
+        config.user.identity_public_key =
+            Some(BASE64.encode(new_identity.verifying_key().to_bytes()));
+        config.user.encryption_private_key = Some(BASE64.encode(new_encryption.to_bytes()));
+        mgr.save(&config).await?;
+        network::discovery_backend::Backend::from_system(&config.system)
+    };
+    println!("[Auth] 🔑 Identity keys rotated");
+
+    let net_state = app.state::<NetworkState>();
+    let _ = net_state
+        .sender
+        .lock()
+        .await
+        .send(network::command::NetworkCommand::ReloadIdentityKeys)
+        .await;
+
+    if backend.can_publish() {
+        let addrs = net_state.listening_addresses.lock().await.clone();
+        network::discovery::publish_peer_info(&backend, addrs, app.clone())
+            .await
+            .map_err(|e| {
+                network::gist::report_auth_failure(&app, &e);
+                format!("Keys rotated, but publishing them failed: {}", e)
+            })?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_keychain_unlock(state: State<'_, AppState>) -> Result<bool, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    Ok(mgr.keychain_unlock_enabled())
+}
+
+/// Opt in/out of unlocking from the OS keychain on this machine.
+#[tauri::command]
+pub async fn set_keychain_unlock(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<bool, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    if enabled {
+        mgr.enable_keychain_unlock().map_err(|e| e.to_string())?;
+    } else {
+        mgr.disable_keychain_unlock().map_err(|e| e.to_string())?;
+    }
+    Ok(mgr.keychain_unlock_enabled())
 }
 
 #[tauri::command]
-pub async fn reset_vault(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn reset_vault(state: State<'_, AppState>) -> Result<(), RChatError> {
     let mut mgr = state.config_manager.lock().await;
     mgr.reset().await.map_err(|e| e.to_string())?;
+    state.journal.set_key(None);
     Ok(())
 }
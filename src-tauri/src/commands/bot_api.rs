@@ -0,0 +1,46 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::RChatError;
+use crate::AppState;
+
+/// What a bot needs to connect; see [`crate::bot_api`].
+#[derive(Debug, Clone, Serialize)]
+pub struct BotApiInfo {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub token: String,
+}
+
+#[tauri::command]
+pub async fn get_bot_api_info(state: State<'_, AppState>) -> Result<BotApiInfo, RChatError> {
+    let enabled = {
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await?.user.bot_api_enabled
+    };
+    let token = crate::bot_api::load_or_create_token(&state.app_dir)?;
+    Ok(BotApiInfo {
+        enabled,
+        endpoint: crate::bot_api::endpoint(&state.app_dir),
+        token,
+    })
+}
+
+/// Issue a new token and disconnect every client holding the old one.
+#[tauri::command]
+pub async fn rotate_bot_api_token(
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<BotApiInfo, RChatError> {
+    let token = crate::bot_api::rotate_token(&state.app_dir)?;
+    let enabled = {
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await?.user.bot_api_enabled
+    };
+    crate::bot_api::apply(&app_handle, enabled).await;
+    Ok(BotApiInfo {
+        enabled,
+        endpoint: crate::bot_api::endpoint(&state.app_dir),
+        token,
+    })
+}
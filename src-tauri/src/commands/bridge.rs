@@ -0,0 +1,303 @@
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::bridge::{self, BridgeKind};
+use crate::chat_kind;
+use crate::error::RChatError;
+use crate::storage;
+use crate::storage::config::MatrixAccount;
+use crate::{AppState, NetworkState};
+
+/// The Matrix account bridges use, without its token.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixAccountInfo {
+    pub homeserver: String,
+    pub user_id: Option<String>,
+}
+
+/// A chat's bridge as the UI sees it: the secret stays in the vault, only its presence shows.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatBridgeInfo {
+    pub chat_id: String,
+    pub kind: String,
+    pub endpoint: String,
+    pub target: String,
+    pub nick: String,
+    pub has_credential: bool,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+impl ChatBridgeInfo {
+    fn new(bridge: storage::db::ChatBridge, has_credential: bool) -> Self {
+        Self {
+            chat_id: bridge.chat_id,
+            kind: bridge.kind,
+            endpoint: bridge.endpoint,
+            target: bridge.target,
+            nick: bridge.nick,
+            has_credential,
+            enabled: bridge.enabled,
+            created_at: bridge.created_at,
+        }
+    }
+}
+
+/// Attach or update a group's bridge. `credential` is the Matrix access token or the
+/// XMPP `user@domain:password` login and goes into the encrypted config; leave it
+/// empty to keep the stored one. `nick` is the IRC nickname.
+#[tauri::command]
+pub async fn configure_chat_bridge(
+    chat_id: String,
+    kind: String,
+    endpoint: String,
+    target: String,
+    credential: String,
+    nick: Option<String>,
+    enabled: bool,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<ChatBridgeInfo, RChatError> {
+    if !chat_kind::is_group_chat_id(&chat_id) {
+        return Err(RChatError::InvalidInput(
+            "Bridges can only be attached to saved group chats".to_string(),
+        ));
+    }
+    let kind = BridgeKind::parse(&kind)
+        .ok_or_else(|| RChatError::InvalidInput(format!("Unsupported bridge kind: {}", kind)))?;
+    let endpoint = endpoint.trim().to_string();
+    let target = target.trim().to_string();
+    let credential = credential.trim().to_string();
+    let nick = nick.unwrap_or_default().trim().to_string();
+    if target.is_empty() || (endpoint.is_empty() && kind != BridgeKind::Matrix) {
+        return Err(RChatError::InvalidInput(
+            "Bridge endpoint and target are required".to_string(),
+        ));
+    }
+
+    // IRC has no secret, and Matrix bridges without a homeserver of their own use the
+    // account from `set_matrix_account`.
+    let keeps_secret = match kind {
+        BridgeKind::Irc => false,
+        BridgeKind::Matrix => !endpoint.is_empty(),
+        BridgeKind::Xmpp => true,
+    };
+    if kind == BridgeKind::Irc && nick.is_empty() {
+        return Err(RChatError::InvalidInput(
+            "IRC bridges need a nickname".to_string(),
+        ));
+    }
+    if keeps_secret
+        && credential.is_empty()
+        && !bridge::has_bridge_secret(&app_handle, &chat_id).await?
+    {
+        return Err(RChatError::InvalidInput(
+            "Bridge credential is required".to_string(),
+        ));
+    }
+    if !keeps_secret {
+        bridge::set_bridge_secret(&app_handle, &chat_id, None).await?;
+    } else if !credential.is_empty() {
+        bridge::set_bridge_secret(&app_handle, &chat_id, Some(credential)).await?;
+    }
+
+    let bridge = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        let created_at = storage::db::get_chat_bridge(&conn, &chat_id)?
+            .map(|existing| existing.created_at)
+            .unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0)
+            });
+        let bridge = storage::db::ChatBridge {
+            chat_id: chat_id.clone(),
+            kind: kind.as_str().to_string(),
+            endpoint,
+            target,
+            nick: if kind == BridgeKind::Irc {
+                nick
+            } else {
+                String::new()
+            },
+            credential: String::new(),
+            enabled,
+            created_at,
+        };
+        storage::db::upsert_chat_bridge(&conn, &bridge)?;
+        bridge
+    };
+
+    if enabled && app_handle.try_state::<NetworkState>().is_some() {
+        bridge::start_bridge(&app_handle, bridge.clone()).await?;
+    } else {
+        bridge::stop_bridge(&app_handle, &chat_id).await;
+    }
+
+    Ok(ChatBridgeInfo::new(bridge, keeps_secret))
+}
+
+#[tauri::command]
+pub async fn get_chat_bridge(
+    chat_id: String,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<Option<ChatBridgeInfo>, RChatError> {
+    let bridge = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::get_chat_bridge(&conn, &chat_id)?
+    };
+    let Some(bridge) = bridge else {
+        return Ok(None);
+    };
+    let has_credential = bridge::has_bridge_secret(&app_handle, &chat_id).await?;
+    Ok(Some(ChatBridgeInfo::new(bridge, has_credential)))
+}
+
+#[tauri::command]
+pub async fn set_chat_bridge_enabled(
+    chat_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
+    let bridge = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::set_chat_bridge_enabled(&conn, &chat_id, enabled)?;
+        storage::db::get_chat_bridge(&conn, &chat_id)?
+    };
+
+    match bridge {
+        Some(bridge) if enabled && app_handle.try_state::<NetworkState>().is_some() => {
+            bridge::start_bridge(&app_handle, bridge)
+                .await
+                .map_err(RChatError::from)
+        }
+        _ => {
+            bridge::stop_bridge(&app_handle, &chat_id).await;
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_chat_bridge(
+    chat_id: String,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
+    bridge::stop_bridge(&app_handle, &chat_id).await;
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::delete_chat_bridge(&conn, &chat_id)?;
+    }
+    bridge::set_bridge_secret(&app_handle, &chat_id, None)
+        .await
+        .map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn set_bridge_identity(
+    chat_id: String,
+    remote_user: String,
+    display_name: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let display_name = display_name.trim();
+    if display_name.is_empty() {
+        return Err(RChatError::InvalidInput(
+            "Display name cannot be empty".to_string(),
+        ));
+    }
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    if storage::db::get_chat_bridge(&conn, &chat_id)?.is_none() {
+        return Err(RChatError::NotFound(format!(
+            "No bridge configured for chat: {}",
+            chat_id
+        )));
+    }
+    storage::db::upsert_bridge_identity(&conn, &chat_id, remote_user.trim(), display_name)
+        .map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn list_bridge_identities(
+    chat_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::BridgeIdentity>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::list_bridge_identities(&conn, &chat_id).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn get_matrix_account(
+    state: State<'_, AppState>,
+) -> Result<Option<MatrixAccountInfo>, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await?;
+    Ok(config.system.matrix.map(|account| MatrixAccountInfo {
+        homeserver: account.homeserver,
+        user_id: account.user_id,
+    }))
+}
+
+/// Log the bridge into a Matrix account (checked against the homeserver), or log it out
+/// with `None`. Running bridges pick up the change.
+#[tauri::command]
+pub async fn set_matrix_account(
+    homeserver: Option<String>,
+    access_token: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<Option<MatrixAccountInfo>, RChatError> {
+    let account = match (homeserver, access_token) {
+        (Some(homeserver), Some(access_token)) => {
+            let mut account = MatrixAccount {
+                homeserver: homeserver.trim().to_string(),
+                access_token: access_token.trim().to_string(),
+                user_id: None,
+            };
+            if account.homeserver.is_empty() || account.access_token.is_empty() {
+                return Err(RChatError::InvalidInput(
+                    "Matrix homeserver and access token are required".to_string(),
+                ));
+            }
+            let user_id = bridge::matrix::validate_account(&account)
+                .await
+                .map_err(|e| format!("Matrix check failed: {}", e))?;
+            account.user_id = Some(user_id);
+            Some(account)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(RChatError::InvalidInput(
+                "Matrix homeserver and access token are required".to_string(),
+            ))
+        }
+    };
+
+    {
+        let mgr = state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        config.system.matrix = account.clone();
+        mgr.save(&config).await?;
+    }
+    if app_handle.try_state::<NetworkState>().is_some() {
+        bridge::start_enabled_bridges(&app_handle).await;
+    }
+    Ok(account.map(|account| MatrixAccountInfo {
+        homeserver: account.homeserver,
+        user_id: account.user_id,
+    }))
+}
+
+/// Nicknames rchat peers and remote users go by on an IRC or XMPP bridge.
+#[tauri::command]
+pub async fn list_bridge_nicks(
+    chat_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::BridgeNick>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::list_bridge_nicks(&conn, &chat_id).map_err(RChatError::from)
+}
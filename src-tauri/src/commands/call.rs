@@ -2,6 +2,7 @@ use tauri::State;
 
 use crate::chat_identity;
 use crate::chat_kind::{self, ChatKind};
+use crate::error::RChatError;
 use crate::network::command::NetworkCommand;
 use crate::NetworkState;
 use std::collections::HashSet;
@@ -56,16 +57,18 @@ fn validate_dm_call_target(
     peer_id: &str,
     connected: &HashSet<String>,
     media_label: &str,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     if !matches!(chat_kind::parse_chat_kind(peer_id), ChatKind::Direct) {
-        return Err(format!(
+        return Err(RChatError::InvalidInput(format!(
             "{} calls are only available for regular DM chats",
             media_label
-        ));
+        )));
     }
 
     if !connected_ids_contain_direct_peer(peer_id, connected) {
-        return Err("Peer is not currently connected".to_string());
+        return Err(RChatError::PeerOffline(
+            "Peer is not currently connected".to_string(),
+        ));
     }
 
     Ok(())
@@ -75,7 +78,7 @@ async fn ensure_dm_connected(
     peer_id: &str,
     state: &State<'_, NetworkState>,
     media_label: &str,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let connected = {
         let connected = state.connected_chat_ids.lock().await;
         connected.clone()
@@ -87,47 +90,50 @@ async fn ensure_dm_connected(
 pub async fn start_voice_call(
     peer_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     ensure_dm_connected(&peer_id, &state, "Voice").await?;
 
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::StartVoiceCall { peer_id })
         .await
-        .map_err(|e| format!("Failed to start voice call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to start voice call: {}", e)))
 }
 
 #[tauri::command]
 pub async fn accept_voice_call(
     call_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::AcceptVoiceCall { call_id })
         .await
-        .map_err(|e| format!("Failed to accept voice call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to accept voice call: {}", e)))
 }
 
 #[tauri::command]
 pub async fn reject_voice_call(
     call_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::RejectVoiceCall { call_id })
         .await
-        .map_err(|e| format!("Failed to reject voice call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to reject voice call: {}", e)))
 }
 
 #[tauri::command]
-pub async fn end_voice_call(call_id: String, state: State<'_, NetworkState>) -> Result<(), String> {
+pub async fn end_voice_call(
+    call_id: String,
+    state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::EndVoiceCall { call_id })
         .await
-        .map_err(|e| format!("Failed to end voice call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to end voice call: {}", e)))
 }
 
 #[tauri::command]
@@ -135,59 +141,62 @@ pub async fn set_voice_call_muted(
     call_id: String,
     muted: bool,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::SetVoiceCallMuted { call_id, muted })
         .await
-        .map_err(|e| format!("Failed to update mute state: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to update mute state: {}", e)))
 }
 
 #[tauri::command]
 pub async fn start_video_call(
     peer_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     ensure_dm_connected(&peer_id, &state, "Video").await?;
 
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::StartVideoCall { peer_id })
         .await
-        .map_err(|e| format!("Failed to start video call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to start video call: {}", e)))
 }
 
 #[tauri::command]
 pub async fn accept_video_call(
     call_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::AcceptVideoCall { call_id })
         .await
-        .map_err(|e| format!("Failed to accept video call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to accept video call: {}", e)))
 }
 
 #[tauri::command]
 pub async fn reject_video_call(
     call_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::RejectVideoCall { call_id })
         .await
-        .map_err(|e| format!("Failed to reject video call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to reject video call: {}", e)))
 }
 
 #[tauri::command]
-pub async fn end_video_call(call_id: String, state: State<'_, NetworkState>) -> Result<(), String> {
+pub async fn end_video_call(
+    call_id: String,
+    state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::EndVideoCall { call_id })
         .await
-        .map_err(|e| format!("Failed to end video call: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to end video call: {}", e)))
 }
 
 #[tauri::command]
@@ -195,12 +204,12 @@ pub async fn set_video_call_muted(
     call_id: String,
     muted: bool,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::SetVideoCallMuted { call_id, muted })
         .await
-        .map_err(|e| format!("Failed to update video mute state: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to update video mute state: {}", e)))
 }
 
 #[tauri::command]
@@ -208,12 +217,12 @@ pub async fn set_video_call_camera_enabled(
     call_id: String,
     enabled: bool,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::SetVideoCallCameraEnabled { call_id, enabled })
         .await
-        .map_err(|e| format!("Failed to update camera state: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to update camera state: {}", e)))
 }
 
 #[tauri::command]
@@ -226,7 +235,7 @@ pub async fn send_video_call_chunk(
     chunk_type: String,
     payload: Vec<u8>,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::SendVideoCallChunk {
@@ -239,7 +248,7 @@ pub async fn send_video_call_chunk(
             payload,
         })
         .await
-        .map_err(|e| format!("Failed to send video chunk: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to send video chunk: {}", e)))
 }
 
 #[tauri::command]
@@ -251,7 +260,7 @@ pub async fn submit_video_call_i420_frame(
     profile: String,
     data: Vec<u8>,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     match sender.try_send(NetworkCommand::SubmitVideoCallI420Frame {
         call_id,
@@ -263,7 +272,10 @@ pub async fn submit_video_call_i420_frame(
     }) {
         Ok(()) => Ok(()),
         Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => Ok(()),
-        Err(e) => Err(format!("Failed to submit video frame: {}", e)),
+        Err(e) => Err(RChatError::Network(format!(
+            "Failed to submit video frame: {}",
+            e
+        ))),
     }
 }
 
@@ -272,12 +284,12 @@ pub async fn set_video_call_quality(
     call_id: String,
     mode: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::SetVideoCallQuality { call_id, mode })
         .await
-        .map_err(|e| format!("Failed to update video quality: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to update video quality: {}", e)))
 }
 
 #[tauri::command]
@@ -285,7 +297,7 @@ pub async fn report_video_call_render_stats(
     call_id: String,
     stats: VideoRenderStatsInput,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::ReportVideoCallRenderStats {
@@ -297,11 +309,11 @@ pub async fn report_video_call_render_stats(
             window_seconds: stats.window_seconds,
         })
         .await
-        .map_err(|e| format!("Failed to report video render stats: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to report video render stats: {}", e)))
 }
 
 #[tauri::command]
-pub async fn get_video_capture_support() -> Result<VideoCaptureSupport, String> {
+pub async fn get_video_capture_support() -> Result<VideoCaptureSupport, RChatError> {
     match rchat_video_capture::list_devices() {
         Ok(devices) => {
             let devices = devices.into_iter().map(video_capture_device_info).collect();
@@ -320,7 +332,7 @@ pub async fn get_video_capture_support() -> Result<VideoCaptureSupport, String>
 }
 
 #[tauri::command]
-pub async fn get_screen_capture_support() -> Result<ScreenCaptureSupport, String> {
+pub async fn get_screen_capture_support() -> Result<ScreenCaptureSupport, RChatError> {
     let support = rchat_screen_capture::screen_capture_support().await;
     Ok(ScreenCaptureSupport {
         supported: support.supported,
@@ -330,10 +342,10 @@ pub async fn get_screen_capture_support() -> Result<ScreenCaptureSupport, String
 }
 
 #[tauri::command]
-pub async fn get_video_capture_devices() -> Result<Vec<VideoCaptureDeviceInfo>, String> {
+pub async fn get_video_capture_devices() -> Result<Vec<VideoCaptureDeviceInfo>, RChatError> {
     rchat_video_capture::list_devices()
         .map(|devices| devices.into_iter().map(video_capture_device_info).collect())
-        .map_err(|error| error.to_string())
+        .map_err(|error| error.to_string().into())
 }
 
 fn video_capture_device_info(
@@ -353,70 +365,74 @@ pub async fn start_screen_broadcast(
     peer_id: String,
     profile: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     ensure_dm_connected(&peer_id, &state, "Screen broadcast").await?;
-    let profile = rchat_screen_capture::ScreenCaptureProfile::from_label(&profile)
-        .ok_or_else(|| format!("Unsupported screen broadcast profile: {}", profile))?;
+    let profile =
+        rchat_screen_capture::ScreenCaptureProfile::from_label(&profile).ok_or_else(|| {
+            RChatError::InvalidInput(format!("Unsupported screen broadcast profile: {}", profile))
+        })?;
 
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::StartScreenBroadcast { peer_id, profile })
         .await
-        .map_err(|e| format!("Failed to start screen broadcast: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to start screen broadcast: {}", e)))
 }
 
 #[tauri::command]
 pub async fn accept_screen_broadcast(
     session_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::AcceptScreenBroadcast { session_id })
         .await
-        .map_err(|e| format!("Failed to accept screen broadcast: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to accept screen broadcast: {}", e)))
 }
 
 #[tauri::command]
 pub async fn reject_screen_broadcast(
     session_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::RejectScreenBroadcast { session_id })
         .await
-        .map_err(|e| format!("Failed to reject screen broadcast: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to reject screen broadcast: {}", e)))
 }
 
 #[tauri::command]
 pub async fn end_screen_broadcast(
     session_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::EndScreenBroadcast { session_id })
         .await
-        .map_err(|e| format!("Failed to end screen broadcast: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to end screen broadcast: {}", e)))
 }
 
 #[tauri::command]
 pub async fn get_voice_call_state(
     state: State<'_, NetworkState>,
-) -> Result<crate::app_state::VoiceCallState, String> {
+) -> Result<crate::app_state::VoiceCallState, RChatError> {
     Ok(state.voice_call_state.lock().await.clone())
 }
 
 #[tauri::command]
 pub async fn get_broadcast_state(
     state: State<'_, NetworkState>,
-) -> Result<crate::app_state::BroadcastState, String> {
+) -> Result<crate::app_state::BroadcastState, RChatError> {
     Ok(state.broadcast_state.lock().await.clone())
 }
 
 #[tauri::command]
-pub async fn get_connected_chat_ids(state: State<'_, NetworkState>) -> Result<Vec<String>, String> {
+pub async fn get_connected_chat_ids(
+    state: State<'_, NetworkState>,
+) -> Result<Vec<String>, RChatError> {
     let connected = state.connected_chat_ids.lock().await;
     Ok(connected.iter().cloned().collect())
 }
@@ -462,7 +478,9 @@ mod tests {
 
         assert_eq!(
             validate_dm_call_target(&chat_id, &connected(&[OTHER_PEER_ID]), "Voice"),
-            Err("Peer is not currently connected".to_string())
+            Err(RChatError::PeerOffline(
+                "Peer is not currently connected".to_string()
+            ))
         );
     }
 
@@ -478,7 +496,9 @@ mod tests {
         for chat_id in non_dm_ids {
             assert_eq!(
                 validate_dm_call_target(chat_id, &connected_ids, "Voice"),
-                Err("Voice calls are only available for regular DM chats".to_string())
+                Err(RChatError::InvalidInput(
+                    "Voice calls are only available for regular DM chats".to_string()
+                ))
             );
         }
     }
@@ -2,7 +2,8 @@ use tauri::{Manager, State};
 
 use crate::chat;
 use crate::chat_kind::{self, ChatKind};
-use crate::network::command::NetworkCommand;
+use crate::error::RChatError;
+use crate::network::command::{DirectMediaKind, NetworkCommand};
 use crate::network::gossip::{GroupContentType, GroupMessageEnvelope};
 use crate::storage;
 use crate::{AppState, NetworkState};
@@ -18,6 +19,26 @@ async fn mapped_github_chat_id_for_peer(
     crate::chat_identity::github_chat_id_for_peer_id(peer_id, &config.user.github_peer_mapping)
 }
 
+/// Peer id to GitHub chat id for every mapped contact, so counts keyed by the
+/// raw peer chat can be reported under the chat id the UI shows.
+pub(crate) async fn mapped_chat_ids_by_peer(
+    app_state: &State<'_, AppState>,
+) -> std::collections::HashMap<String, String> {
+    let mgr = app_state.config_manager.lock().await;
+    match mgr.load().await {
+        Ok(config) => config
+            .user
+            .github_peer_mapping
+            .into_iter()
+            .map(|(github, peer_id)| {
+                let canonical = crate::chat_identity::build_github_chat_id(&github, &peer_id);
+                (peer_id, canonical)
+            })
+            .collect(),
+        Err(_) => std::collections::HashMap::new(),
+    }
+}
+
 async fn resolve_peer_id_for_chat(
     _app_state: &State<'_, AppState>,
     chat_id: &str,
@@ -25,7 +46,7 @@ async fn resolve_peer_id_for_chat(
     crate::chat_identity::resolve_peer_id_for_direct_chat_id(chat_id)
 }
 
-async fn canonical_direct_chat_id_for_target(
+pub(crate) async fn canonical_direct_chat_id_for_target(
     app_state: &State<'_, AppState>,
     direct_id: &str,
 ) -> String {
@@ -86,10 +107,10 @@ pub struct ArchivedChatResult {
 pub async fn get_chat_latest_times(
     state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<std::collections::HashMap<String, i64>, String> {
+) -> Result<std::collections::HashMap<String, i64>, RChatError> {
     let mut result = {
         let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-        storage::db::get_chat_latest_times(&conn).map_err(|e| e.to_string())?
+        storage::db::get_chat_latest_times(&conn)?
     };
 
     let temp_state = net_state.temporary_state.lock().await;
@@ -99,21 +120,7 @@ pub async fn get_chat_latest_times(
         }
     }
 
-    let mapped_chat_ids_by_peer: std::collections::HashMap<String, String> = {
-        let mgr = state.config_manager.lock().await;
-        match mgr.load().await {
-            Ok(config) => config
-                .user
-                .github_peer_mapping
-                .into_iter()
-                .flat_map(|(github, peer_id)| {
-                    let canonical = crate::chat_identity::build_github_chat_id(&github, &peer_id);
-                    vec![(peer_id, canonical)]
-                })
-                .collect(),
-            Err(_) => std::collections::HashMap::new(),
-        }
-    };
+    let mapped_chat_ids_by_peer = mapped_chat_ids_by_peer(&state).await;
     let mut canonical = std::collections::HashMap::new();
     for (chat_id, ts) in result {
         let key = mapped_chat_ids_by_peer
@@ -137,10 +144,15 @@ pub async fn get_chat_latest_times(
 pub async fn get_chat_list(
     state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<Vec<storage::db::ChatListItem>, String> {
-    let mut items = {
+) -> Result<Vec<storage::db::ChatListItem>, RChatError> {
+    let now_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (mut items, hidden_chat_ids) = {
         let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-        storage::db::get_chat_list(&conn).map_err(|e| e.to_string())?
+        let hidden_chat_ids = crate::chat::hidden::concealed_chat_ids(&conn, now_secs)?;
+        (storage::db::get_chat_list(&conn)?, hidden_chat_ids)
     };
 
     let mut seen: std::collections::HashSet<String> =
@@ -182,21 +194,7 @@ pub async fn get_chat_list(
         seen.insert(chat_id.clone());
     }
 
-    let mapped_chat_ids_by_peer: std::collections::HashMap<String, String> = {
-        let mgr = state.config_manager.lock().await;
-        match mgr.load().await {
-            Ok(config) => config
-                .user
-                .github_peer_mapping
-                .into_iter()
-                .flat_map(|(github, peer_id)| {
-                    let canonical = crate::chat_identity::build_github_chat_id(&github, &peer_id);
-                    vec![(peer_id, canonical)]
-                })
-                .collect(),
-            Err(_) => std::collections::HashMap::new(),
-        }
-    };
+    let mapped_chat_ids_by_peer = mapped_chat_ids_by_peer(&state).await;
     let mut deduped: Vec<storage::db::ChatListItem> = Vec::with_capacity(items.len());
     let mut by_id: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
     for mut item in items {
@@ -222,6 +220,7 @@ pub async fn get_chat_list(
         by_id.insert(item.id.clone(), deduped.len());
         deduped.push(item);
     }
+    deduped.retain(|item| !hidden_chat_ids.contains(&item.id));
 
     Ok(deduped)
 }
@@ -230,8 +229,8 @@ pub async fn get_chat_list(
 pub async fn create_group_chat(
     name: Option<String>,
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<GroupChatResult, String> {
+    app_handle: crate::AppHandle,
+) -> Result<GroupChatResult, RChatError> {
     let chat_id = chat_kind::generate_group_chat_id();
     let resolved_name = name
         .map(|n| n.trim().to_string())
@@ -240,9 +239,8 @@ pub async fn create_group_chat(
 
     {
         let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-        storage::db::upsert_chat(&conn, &chat_id, &resolved_name, true)
-            .map_err(|e| e.to_string())?;
-        storage::db::add_chat_member(&conn, &chat_id, "Me", "admin").map_err(|e| e.to_string())?;
+        storage::db::upsert_chat(&conn, &chat_id, &resolved_name, true)?;
+        storage::db::add_chat_member(&conn, &chat_id, "Me", "admin")?;
     }
 
     if let Some(net_state) = app_handle.try_state::<NetworkState>() {
@@ -252,6 +250,11 @@ pub async fn create_group_chat(
                 group_id: chat_id.clone(),
             })
             .await;
+        let _ = tx
+            .send(NetworkCommand::RekeyGroup {
+                group_id: chat_id.clone(),
+            })
+            .await;
     }
 
     Ok(GroupChatResult {
@@ -265,10 +268,12 @@ pub async fn join_group_chat(
     chat_id: String,
     name: Option<String>,
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<GroupChatResult, String> {
+    app_handle: crate::AppHandle,
+) -> Result<GroupChatResult, RChatError> {
     if !chat_kind::is_group_chat_id(&chat_id) {
-        return Err("Invalid group id. Expected format group:<uuid>".to_string());
+        return Err(RChatError::InvalidInput(
+            "Invalid group id. Expected format group:<uuid>".to_string(),
+        ));
     }
 
     let resolved_name = name
@@ -278,9 +283,8 @@ pub async fn join_group_chat(
 
     {
         let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-        storage::db::upsert_chat(&conn, &chat_id, &resolved_name, true)
-            .map_err(|e| e.to_string())?;
-        storage::db::add_chat_member(&conn, &chat_id, "Me", "member").map_err(|e| e.to_string())?;
+        storage::db::upsert_chat(&conn, &chat_id, &resolved_name, true)?;
+        storage::db::add_chat_member(&conn, &chat_id, "Me", "member")?;
     }
 
     if let Some(net_state) = app_handle.try_state::<NetworkState>() {
@@ -302,16 +306,18 @@ pub async fn join_group_chat(
 pub async fn leave_group_chat(
     chat_id: String,
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
     if !chat_kind::is_group_chat_id(&chat_id) {
-        return Err("Invalid group id. Expected format group:<uuid>".to_string());
+        return Err(RChatError::InvalidInput(
+            "Invalid group id. Expected format group:<uuid>".to_string(),
+        ));
     }
 
     {
         let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
         let _ = storage::db::remove_chat_member(&conn, &chat_id, "Me");
-        storage::db::delete_group_chat(&conn, &chat_id).map_err(|e| e.to_string())?;
+        storage::db::delete_group_chat(&conn, &chat_id)?;
     }
 
     if let Some(net_state) = app_handle.try_state::<NetworkState>() {
@@ -326,12 +332,227 @@ pub async fn leave_group_chat(
     Ok(())
 }
 
+#[derive(serde::Serialize)]
+pub struct ChatInfo {
+    pub id: String,
+    pub name: String,
+    pub is_group: bool,
+    pub participants: Vec<storage::db::ChatMember>,
+    pub settings: storage::db::ChatSettings,
+    /// Group avatar set by an admin (file hash).
+    pub avatar_hash: Option<String>,
+}
+
+fn chat_info(
+    conn: &rusqlite::Connection,
+    chat: storage::db::ChatListItem,
+) -> anyhow::Result<ChatInfo> {
+    let mut participants = storage::db::get_chat_members(conn, &chat.id)?;
+    if participants.is_empty() && !chat.is_group {
+        // Direct chats don't record members; the peer is part of the id.
+        if let Some(peer_id) = crate::chat_identity::resolve_peer_id_for_direct_chat_id(&chat.id) {
+            participants.push(storage::db::ChatMember {
+                peer_id,
+                role: "member".to_string(),
+                joined_at: 0,
+            });
+        }
+    }
+    let settings = storage::db::get_chat_settings(conn, &chat.id)?;
+    let avatar_hash = storage::db::get_chat_avatar(conn, &chat.id)?;
+    Ok(ChatInfo {
+        id: chat.id,
+        name: chat.name,
+        is_group: chat.is_group,
+        participants,
+        settings,
+        avatar_hash,
+    })
+}
+
+/// Start an empty chat. A direct chat takes exactly one peer and reuses the
+/// existing chat with that peer, if any; a group gets a new id with us as
+/// admin and `peer_ids` as members.
+#[tauri::command]
+pub async fn create_chat(
+    peer_ids: Vec<String>,
+    name: Option<String>,
+    is_group: bool,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<ChatInfo, RChatError> {
+    let mut peers: Vec<String> = Vec::new();
+    for peer_id in peer_ids.iter().map(|p| p.trim()) {
+        if !peer_id.is_empty() && peer_id != "Me" && !peers.iter().any(|p| p == peer_id) {
+            peers.push(peer_id.to_string());
+        }
+    }
+    let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        if let Some(unknown) = peers.iter().find(|p| !storage::db::is_peer(&conn, p)) {
+            return Err(RChatError::NotFound(format!("Unknown peer {}", unknown)));
+        }
+    }
+
+    if !is_group {
+        let [peer_id] = peers.as_slice() else {
+            return Err(RChatError::InvalidInput(
+                "A direct chat needs exactly one peer".to_string(),
+            ));
+        };
+        let chat_id = canonical_direct_chat_id_for_target(&state, peer_id).await;
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        if !storage::db::chat_exists(&conn, &chat_id) {
+            storage::db::create_chat(&conn, &chat_id, &default_direct_chat_name(&chat_id), false)?;
+        }
+        if let Some(name) = &name {
+            storage::db::upsert_chat(&conn, &chat_id, name, false)?;
+        }
+        let chat = storage::db::list_chats(&conn)?
+            .into_iter()
+            .find(|chat| chat.id == chat_id)
+            .ok_or_else(|| format!("Chat {} was not created", chat_id))?;
+        return chat_info(&conn, chat).map_err(RChatError::from);
+    }
+
+    let chat_id = chat_kind::generate_group_chat_id();
+    let resolved_name = name.unwrap_or_else(|| chat_kind::default_group_name(&chat_id));
+    let info = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::upsert_chat(&conn, &chat_id, &resolved_name, true)?;
+        storage::db::add_chat_member(&conn, &chat_id, "Me", "admin")?;
+        for peer_id in &peers {
+            storage::db::add_chat_member(&conn, &chat_id, peer_id, "member")?;
+        }
+        chat_info(
+            &conn,
+            storage::db::ChatListItem {
+                id: chat_id.clone(),
+                name: resolved_name,
+                is_group: true,
+            },
+        )
+        .map_err(|e| e.to_string())?
+    };
+
+    if let Some(net_state) = app_handle.try_state::<NetworkState>() {
+        let tx = net_state.sender.lock().await;
+        let _ = tx
+            .send(NetworkCommand::SubscribeGroup {
+                group_id: chat_id.clone(),
+            })
+            .await;
+        let _ = tx
+            .send(NetworkCommand::RekeyGroup {
+                group_id: chat_id.clone(),
+            })
+            .await;
+    }
+
+    Ok(info)
+}
+
+/// Every stored chat with its participants and settings. Hidden chats are
+/// left out unless they are currently revealed.
+#[tauri::command]
+pub async fn get_chats(state: State<'_, AppState>) -> Result<Vec<ChatInfo>, RChatError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let hidden_chat_ids = crate::chat::hidden::concealed_chat_ids(&conn, now)?;
+    storage::db::list_chats(&conn)?
+        .into_iter()
+        .filter(|chat| !hidden_chat_ids.contains(&chat.id))
+        .map(|chat| chat_info(&conn, chat).map_err(RChatError::from))
+        .collect()
+}
+
+/// Chats tagged with any of `envelope_ids`, or with all of them when
+/// `match_all` is set. Tags on nested envelopes count for their parents.
+#[tauri::command]
+pub async fn get_chats_with_tags(
+    envelope_ids: Vec<String>,
+    match_all: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<ChatInfo>, RChatError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let hidden_chat_ids = crate::chat::hidden::concealed_chat_ids(&conn, now)?;
+    let tagged = storage::db::get_chat_ids_with_tags(&conn, &envelope_ids, match_all)?;
+    storage::db::list_chats(&conn)?
+        .into_iter()
+        .filter(|chat| tagged.contains(&chat.id) && !hidden_chat_ids.contains(&chat.id))
+        .map(|chat| chat_info(&conn, chat).map_err(RChatError::from))
+        .collect()
+}
+
+/// Tell the group we started or stopped typing. Call with `typing: true` on
+/// keystrokes (the backend throttles it) and `false` when the draft is cleared;
+/// members see the aggregate as a `group-activity` event.
+#[tauri::command]
+pub async fn set_group_typing(
+    chat_id: String,
+    typing: bool,
+    state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
+    if !chat_kind::is_group_chat_id(&chat_id) && !chat_kind::is_temp_group_chat_id(&chat_id) {
+        return Err(RChatError::InvalidInput(
+            "Typing indicators are only sent to group chats".to_string(),
+        ));
+    }
+    let sender_alias = {
+        let mgr = state.config_manager.lock().await;
+        let config = mgr.load().await?;
+        config.user.profile.alias
+    };
+    let tx = net_state.sender.lock().await;
+    tx.send(NetworkCommand::SendGroupTyping {
+        group_id: chat_id,
+        sender_alias,
+        typing,
+    })
+    .await
+    .map_err(|e| RChatError::Network(e.to_string()))
+}
+
 #[tauri::command]
 pub async fn send_message_to_self(
     message: String,
+    file_path: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    println!("[Backend] send_message_to_self: {}", message);
+) -> Result<storage::db::Message, RChatError> {
+    println!("[Backend] send_message_to_self");
+
+    // Attachments go through object storage, like media sent to peers.
+    let attachment = match file_path {
+        Some(file_path) => {
+            storage::disk::ensure_media_writable()?;
+            let data =
+                std::fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+            let path = std::path::Path::new(&file_path);
+            let file_name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("file")
+                .to_string();
+            let mime_type = note_mime_type(path);
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            let file_hash =
+                storage::object::create(&conn, &data, Some(&file_name), Some(mime_type), None)
+                    .map_err(|e| format!("Failed to store file: {}", e))?;
+            Some((file_hash, file_name, mime_type, data.len()))
+        }
+        None => None,
+    };
+
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
 
     let timestamp = std::time::SystemTime::now()
@@ -339,42 +560,174 @@ pub async fn send_message_to_self(
         .unwrap()
         .as_secs() as i64;
 
-    let id_suffix: u32 = rand::random();
-    let msg_id = format!("{}-{}", timestamp, id_suffix);
-
-    let msg = storage::db::Message {
-        id: msg_id,
-        chat_id: "self".to_string(),
-        peer_id: "Me".to_string(),
-        timestamp,
-        content_type: "text".to_string(),
-        text_content: Some(message),
-        file_hash: None,
-        status: "read".to_string(),
-        content_metadata: None,
-        sender_alias: None,
+    let msg_id = crate::chat::message_id::new_message_id();
+
+    let msg = match attachment {
+        Some((file_hash, file_name, mime_type, size)) => {
+            let content_metadata = format!("{{\"size_bytes\":{}}}", size);
+            storage::db::Message {
+                id: msg_id,
+                chat_id: "self".to_string(),
+                peer_id: "Me".to_string(),
+                timestamp,
+                content: crate::chat::message::MessageContent::from_columns(
+                    note_content_type(mime_type),
+                    Some(if message.trim().is_empty() {
+                        file_name
+                    } else {
+                        message
+                    }),
+                    Some(file_hash),
+                    Some(&content_metadata),
+                ),
+                status: "read".to_string(),
+                content_metadata: Some(content_metadata),
+                sender_alias: None,
+                logical: 0,
+            }
+        }
+        None => storage::db::Message {
+            id: msg_id,
+            chat_id: "self".to_string(),
+            peer_id: "Me".to_string(),
+            timestamp,
+            content: crate::chat::message::MessageContent::Text { text: message },
+            status: "read".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        },
     };
 
     match storage::db::insert_message(&conn, &msg) {
         Ok(_) => {
+            storage::journal::record_message(&state.journal, &conn, &msg);
             println!("[Backend] Note saved successfully");
-            Ok(())
+            Ok(msg)
         }
         Err(e) => {
             eprintln!("[Backend] Failed to save note: {}", e);
-            Err(e.to_string())
+            Err(e.into())
         }
     }
 }
 
+fn note_mime_type(path: &std::path::Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    match ext.as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("mov") => "video/quicktime",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("m4a") => "audio/mp4",
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("csv") => "text/csv",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Message content type for an attachment, matching what peer media uses.
+fn note_content_type(mime_type: &str) -> &'static str {
+    match mime_type.split('/').next() {
+        Some("image") => "image",
+        Some("video") => "video",
+        Some("audio") => "audio",
+        _ => "document",
+    }
+}
+
+/// Files saved to Note to Self, newest first. `filter` is as for
+/// `list_chat_files` ("all", "image", "video", "audio", "document").
+#[tauri::command]
+pub async fn get_self_files(
+    filter: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::ChatFileRow>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::list_chat_files(
+        &conn,
+        "self",
+        filter.as_deref().unwrap_or("all"),
+        limit.unwrap_or(50),
+        offset.unwrap_or(0),
+    )
+    .map_err(RChatError::from)
+}
+
+/// Write a file saved to Note to Self to `target_path`.
+#[tauri::command]
+pub async fn save_self_file(
+    file_hash: String,
+    target_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let is_note: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM messages WHERE chat_id = 'self' AND file_hash = ?1)",
+            [&file_hash],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !is_note {
+        return Err(RChatError::InvalidInput(
+            "File is not in Note to Self".to_string(),
+        ));
+    }
+    let data = storage::object::load(&conn, &file_hash, None)
+        .map_err(|e| format!("Failed to load file: {}", e))?;
+    std::fs::write(&target_path, &data).map_err(|e| format!("Failed to save file: {}", e))?;
+    Ok(())
+}
+
+/// Delete a note, and its file once nothing else uses it.
+#[tauri::command]
+pub async fn delete_self_note(
+    msg_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let file_hash = storage::db::get_message(&conn, &msg_id)?
+        .filter(|msg| msg.chat_id == "self")
+        .ok_or_else(|| RChatError::NotFound("Note not found".to_string()))?
+        .content
+        .file_hash()
+        .map(str::to_string);
+    storage::db::delete_chat_message(&conn, "self", &msg_id)?;
+    if let Some(file_hash) = file_hash {
+        if !storage::db::is_file_referenced(&conn, &file_hash)? {
+            // Chunks are left to garbage collection.
+            storage::object::delete(&conn, &file_hash).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn send_message(
     peer_id: String,
     message: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<String, String> {
-    println!("[Backend] send_message to {}: {}", peer_id, message);
+    app_handle: crate::AppHandle,
+) -> Result<String, RChatError> {
+    println!(
+        "[Backend] send_message to {}",
+        crate::logging::peer(&peer_id)
+    );
+    storage::disk::ensure_db_writable()?;
 
     let canonical_peer_id = if matches!(chat_kind::parse_chat_kind(&peer_id), ChatKind::Direct) {
         canonical_direct_chat_id_for_target(&app_state, &peer_id).await
@@ -391,10 +744,27 @@ pub async fn send_message(
             canonical_peer_id.clone()
         };
 
-    let my_alias = {
+    let (my_alias, translation_settings) = {
         let mgr = app_state.config_manager.lock().await;
-        let config = mgr.load().await.map_err(|e| e.to_string())?;
-        config.user.profile.alias.clone()
+        let config = mgr.load().await?;
+        (config.user.profile.alias.clone(), config.user.translation)
+    };
+
+    let translate_to = if matches!(chat_kind, ChatKind::Direct | ChatKind::TemporaryDirect) {
+        let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
+        let settings = storage::db::get_chat_settings(&conn, &canonical_peer_id)?;
+        settings
+            .send_translated
+            .then_some(settings.translate_to)
+            .flatten()
+    } else {
+        None
+    };
+    let translation = match translate_to {
+        Some(lang) => {
+            chat::translate::translate_outgoing(&translation_settings, &message, &lang).await
+        }
+        None => None,
     };
 
     let is_temporary = matches!(
@@ -403,7 +773,9 @@ pub async fn send_message(
     );
     let is_archived = matches!(chat_kind, ChatKind::Archived);
     if is_archived {
-        return Err("Archived chats are read-only".to_string());
+        return Err(RChatError::InvalidInput(
+            "Archived chats are read-only".to_string(),
+        ));
     }
 
     let (msg_id, timestamp, outgoing_msg) = {
@@ -412,8 +784,7 @@ pub async fn send_message(
             .unwrap()
             .as_secs() as i64;
 
-        let id_suffix: u32 = rand::random();
-        let msg_id = format!("{}-{}", timestamp, id_suffix);
+        let msg_id = crate::chat::message_id::new_message_id();
 
         let status = match chat_kind {
             ChatKind::SelfChat => "read",
@@ -428,17 +799,34 @@ pub async fn send_message(
             canonical_peer_id.clone()
         };
 
+        let mentions = if matches!(chat_kind, ChatKind::Group | ChatKind::TemporaryGroup) {
+            let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
+            let members = storage::db::get_chat_member_aliases(&conn, &canonical_peer_id)?;
+            chat::mentions::parse(&message, &members)
+        } else {
+            Vec::new()
+        };
+
         let msg = storage::db::Message {
             id: msg_id.clone(),
             chat_id,
             peer_id: "Me".to_string(),
             timestamp,
-            content_type: "text".to_string(),
-            text_content: Some(message.clone()),
-            file_hash: None,
+            content: crate::chat::message::MessageContent::Text {
+                text: message.clone(),
+            },
             status: status.to_string(),
-            content_metadata: None,
+            content_metadata: chat::format::with_formatting(
+                chat::mentions::with_mentions(
+                    translation
+                        .as_ref()
+                        .map(chat::translate::translation_metadata),
+                    &mentions,
+                ),
+                &message,
+            ),
             sender_alias: my_alias.clone(),
+            logical: 0,
         };
 
         if !is_temporary {
@@ -494,10 +882,8 @@ pub async fn send_message(
                             &canonical_peer_id,
                             &chat_kind::default_group_name(&canonical_peer_id),
                             true,
-                        )
-                        .map_err(|e| e.to_string())?;
-                        storage::db::add_chat_member(&conn, &canonical_peer_id, "Me", "member")
-                            .map_err(|e| e.to_string())?;
+                        )?;
+                        storage::db::add_chat_member(&conn, &canonical_peer_id, "Me", "member")?;
                     }
                 }
                 ChatKind::SelfChat
@@ -508,8 +894,9 @@ pub async fn send_message(
 
             if let Err(e) = storage::db::insert_message(&conn, &msg) {
                 eprintln!("[Backend] Failed to save outgoing message: {}", e);
-                return Err(e.to_string());
+                return Err(e.into());
             }
+            storage::journal::record_message(&app_state.journal, &conn, &msg);
         }
 
         (msg_id, timestamp, msg)
@@ -542,11 +929,24 @@ pub async fn send_message(
                 timestamp,
                 sender_alias: my_alias,
                 content: message,
+                translation,
             })
             .await
             .map_err(|e| e.to_string())?;
         }
         ChatKind::Group | ChatKind::TemporaryGroup => {
+            if matches!(chat_kind, ChatKind::Group) {
+                crate::bridge::relay_outbound(
+                    &app_handle,
+                    &canonical_peer_id,
+                    &msg_id,
+                    "Me",
+                    my_alias.as_deref().unwrap_or("Me"),
+                    GroupContentType::Text,
+                    Some(&message),
+                )
+                .await;
+            }
             let envelope = GroupMessageEnvelope {
                 id: msg_id.clone(),
                 group_id: canonical_peer_id.clone(),
@@ -556,6 +956,10 @@ pub async fn send_message(
                 content_type: GroupContentType::Text,
                 text_content: Some(message),
                 file_hash: None,
+                signature: None,
+                logical: None,
+                bridged: None,
+                content: None,
             };
             tx.send(NetworkCommand::PublishGroup { envelope })
                 .await
@@ -564,15 +968,139 @@ pub async fn send_message(
         ChatKind::Archived => {}
     }
 
+    storage::metrics::record(storage::metrics::MESSAGES_SENT);
     Ok(msg_id)
 }
 
+/// Send a direct message again after the outbox gave up on it
+/// (`message-send-failed`).
+#[tauri::command]
+pub async fn retry_message(
+    msg_id: String,
+    app_state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
+    let msg = {
+        let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::get_message(&conn, &msg_id)?
+            .ok_or_else(|| RChatError::NotFound("Message not found".to_string()))?
+    };
+    if msg.peer_id != "Me" || msg.status != "failed" {
+        return Err(RChatError::InvalidInput(
+            "Only failed outgoing messages can be retried".to_string(),
+        ));
+    }
+    if !matches!(chat_kind::parse_chat_kind(&msg.chat_id), ChatKind::Direct) {
+        return Err(RChatError::InvalidInput(
+            "Only direct messages can be retried".to_string(),
+        ));
+    }
+    let target_peer_id = resolve_peer_id_for_chat(&app_state, &msg.chat_id)
+        .await
+        .unwrap_or_else(|| msg.chat_id.clone());
+
+    let command = match msg.content.content_type() {
+        "text" => NetworkCommand::SendDirectText {
+            target_peer_id,
+            msg_id: msg.id.clone(),
+            timestamp: msg.timestamp,
+            sender_alias: msg.sender_alias.clone(),
+            content: msg.content.text_content().unwrap_or_default().to_string(),
+            translation: msg
+                .content_metadata
+                .as_deref()
+                .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+                .and_then(|metadata| serde_json::from_value(metadata["translation"].clone()).ok()),
+        },
+        content_type => {
+            let kind = match content_type {
+                "image" => DirectMediaKind::Image,
+                "sticker" => DirectMediaKind::Sticker,
+                "document" => DirectMediaKind::Document,
+                "video" => DirectMediaKind::Video,
+                "audio" => DirectMediaKind::Audio,
+                _ => {
+                    return Err(RChatError::InvalidInput(format!(
+                        "Can't resend {} messages",
+                        content_type
+                    )))
+                }
+            };
+            NetworkCommand::SendDirectMedia {
+                kind,
+                target_peer_id,
+                file_hash: msg
+                    .content
+                    .file_hash()
+                    .filter(|hash| !hash.is_empty())
+                    .map(str::to_string)
+                    .ok_or_else(|| RChatError::NotFound("Message has no file".to_string()))?,
+                file_name: msg.content.text_content().map(str::to_string),
+                msg_id: msg.id.clone(),
+                timestamp: msg.timestamp,
+            }
+        }
+    };
+
+    {
+        let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::update_message_status(&conn, &msg.id, "pending")?;
+    }
+    let tx = net_state.sender.lock().await;
+    tx.send(command)
+        .await
+        .map_err(|e| RChatError::Network(e.to_string()))
+}
+
+/// Send `text` to `chat_id` at `send_at` (unix secs) through the normal send
+/// path. A time in the past sends on the next scheduler tick.
+#[tauri::command]
+pub async fn schedule_message(
+    chat_id: String,
+    text: String,
+    send_at: i64,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ScheduledMessage, RChatError> {
+    if text.trim().is_empty() {
+        return Err(RChatError::InvalidInput(
+            "Message text is required".to_string(),
+        ));
+    }
+    storage::disk::ensure_db_writable()?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::add_scheduled_message(&conn, &chat_id, &text, send_at, now)
+        .map_err(RChatError::from)
+}
+
+/// Messages still waiting to be sent, of `chat_id` or of every chat.
+#[tauri::command]
+pub async fn list_scheduled_messages(
+    chat_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::ScheduledMessage>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::list_scheduled_messages(&conn, chat_id.as_deref()).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_scheduled_message(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<bool, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::cancel_scheduled_message(&conn, id).map_err(RChatError::from)
+}
+
 #[tauri::command]
 pub async fn get_chat_history(
     chat_id: String,
     state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<Vec<storage::db::Message>, String> {
+) -> Result<Vec<storage::db::Message>, RChatError> {
     println!("[Backend] get_chat_history for: {}", chat_id);
 
     let resolved_chat_id = if matches!(chat_kind::parse_chat_kind(&chat_id), ChatKind::Direct) {
@@ -595,17 +1123,23 @@ pub async fn get_chat_history(
     }
 
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    let mut messages =
-        storage::db::get_messages(&conn, &resolved_chat_id).map_err(|e| e.to_string())?;
+    let mut messages = storage::db::get_messages(&conn, &resolved_chat_id)?;
 
     for db_msg in &mut messages {
-        if (db_msg.content_type == "photo" || db_msg.content_type == "image")
-            && db_msg.content_metadata.is_none()
-            && db_msg.file_hash.is_some()
+        let mut rich_msg = chat::message::Message::from_db_row(db_msg);
+        if matches!(
+            rich_msg.content,
+            chat::message::MessageContent::Photo { .. }
+                | chat::message::MessageContent::Video { .. }
+        ) && db_msg.content_metadata.is_none()
+            && db_msg
+                .content
+                .file_hash()
+                .is_some_and(|hash| !hash.is_empty())
         {
-            let mut rich_msg = chat::message::Message::from_db_row(db_msg);
             if rich_msg.hydrate(&conn) {
                 let updated = rich_msg.to_db_row();
+                db_msg.content = updated.content;
                 db_msg.content_metadata = updated.content_metadata;
             }
         }
@@ -615,12 +1149,112 @@ pub async fn get_chat_history(
     Ok(messages)
 }
 
+#[tauri::command]
+pub async fn export_chat(
+    chat_id: String,
+    format: chat::export::ExportFormat,
+    include_media: chat::export::MediaExportMode,
+    path: String,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<chat::export::ExportSummary, RChatError> {
+    println!("[Backend] export_chat {} -> {}", chat_id, path);
+
+    let resolved_chat_id = if matches!(chat_kind::parse_chat_kind(&chat_id), ChatKind::Direct) {
+        canonical_direct_chat_id_for_target(&state, &chat_id).await
+    } else {
+        chat_id.clone()
+    };
+    if matches!(
+        chat_kind::parse_chat_kind(&resolved_chat_id),
+        ChatKind::TemporaryDirect | ChatKind::TemporaryGroup
+    ) {
+        return Err(RChatError::InvalidInput(
+            "Temporary chats must be archived before exporting".to_string(),
+        ));
+    }
+    if path.trim().is_empty() {
+        return Err(RChatError::InvalidInput(
+            "Export path is required".to_string(),
+        ));
+    }
+
+    if include_media != chat::export::MediaExportMode::None {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        chat::media_guard::ensure_chat_media_exportable(&conn, &resolved_chat_id)?;
+    }
+
+    // Writing the transcript (and copying media) is slow file I/O; keep it off
+    // the async runtime and let other commands use the database meanwhile.
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<AppState>();
+        chat::export::export_chat(
+            &state.db_conn,
+            &resolved_chat_id,
+            format,
+            include_media,
+            std::path::Path::new(path.trim()),
+        )
+        .map_err(RChatError::from)
+    })
+    .await
+    .map_err(|e| RChatError::Internal(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn set_message_starred(
+    msg_id: String,
+    starred: bool,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let found = storage::db::set_message_starred(&conn, &msg_id, starred, now)?;
+    if !found {
+        return Err(RChatError::NotFound(format!(
+            "Message not found: {}",
+            msg_id
+        )));
+    }
+    Ok(())
+}
+
+/// Starred messages from every chat, with the chat name for context. Messages
+/// in hidden chats are left out unless they are currently revealed.
+#[tauri::command]
+pub async fn get_starred_messages(
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::StarredMessage>, RChatError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let hidden_chat_ids = crate::chat::hidden::concealed_chat_ids(&conn, now)?;
+    Ok(storage::db::get_starred_messages(&conn)?
+        .into_iter()
+        .filter(|starred| !hidden_chat_ids.contains(&starred.message.chat_id))
+        .collect())
+}
+
+/// Spam/scam scores for chats from unknown peers that we have not replied to yet.
+#[tauri::command]
+pub async fn get_message_request_scores(
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::MessageRequestScore>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::list_message_request_scores(&conn).map_err(RChatError::from)
+}
+
 #[tauri::command]
 pub async fn mark_messages_read(
     chat_id: String,
     state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, RChatError> {
     println!("[Backend] mark_messages_read for chat: {}", chat_id);
 
     let resolved_chat_id = if matches!(chat_kind::parse_chat_kind(&chat_id), ChatKind::Direct) {
@@ -652,16 +1286,14 @@ pub async fn mark_messages_read(
             match chat_kind {
                 ChatKind::Group => {
                     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-                    storage::db::mark_group_messages_read(&conn, &resolved_chat_id)
-                        .map_err(|e| e.to_string())?
+                    storage::db::mark_group_messages_read(&conn, &resolved_chat_id)?
                 }
                 _ => {
                     let sender_id = resolve_peer_id_for_chat(&state, &resolved_chat_id)
                         .await
                         .unwrap_or_else(|| resolved_chat_id.clone());
                     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-                    storage::db::mark_messages_read(&conn, &resolved_chat_id, &sender_id)
-                        .map_err(|e| e.to_string())?
+                    storage::db::mark_messages_read(&conn, &resolved_chat_id, &sender_id)?
                 }
             }
         }
@@ -697,27 +1329,13 @@ pub async fn mark_messages_read(
 pub async fn get_unread_counts(
     my_peer_id: String,
     state: State<'_, AppState>,
-) -> Result<std::collections::HashMap<String, i64>, String> {
+) -> Result<std::collections::HashMap<String, i64>, RChatError> {
     let counts = {
         let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-        storage::db::get_unread_counts(&conn, &my_peer_id).map_err(|e| e.to_string())?
+        storage::db::get_unread_counts(&conn, &my_peer_id)?
     };
 
-    let mapped_chat_ids_by_peer: std::collections::HashMap<String, String> = {
-        let mgr = state.config_manager.lock().await;
-        match mgr.load().await {
-            Ok(config) => config
-                .user
-                .github_peer_mapping
-                .into_iter()
-                .flat_map(|(github, peer_id)| {
-                    let canonical = crate::chat_identity::build_github_chat_id(&github, &peer_id);
-                    vec![(peer_id, canonical)]
-                })
-                .collect(),
-            Err(_) => std::collections::HashMap::new(),
-        }
-    };
+    let mapped_chat_ids_by_peer = mapped_chat_ids_by_peer(&state).await;
 
     let mut canonical = std::collections::HashMap::new();
     for (chat_id, count) in counts {
@@ -735,9 +1353,11 @@ pub async fn save_temporary_chat_to_archive(
     chat_id: String,
     state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<ArchivedChatResult, String> {
+) -> Result<ArchivedChatResult, RChatError> {
     if !chat_kind::is_temporary_chat_id(&chat_id) {
-        return Err("Only temporary chats can be archived".to_string());
+        return Err(RChatError::InvalidInput(
+            "Only temporary chats can be archived".to_string(),
+        ));
     }
 
     let now = std::time::SystemTime::now()
@@ -749,7 +1369,7 @@ pub async fn save_temporary_chat_to_archive(
     let (session, messages) = {
         let mut temp_state = net_state.temporary_state.lock().await;
         let Some(session) = temp_state.chats.get(&chat_id).cloned() else {
-            return Err("Temporary chat not found".to_string());
+            return Err(RChatError::NotFound("Temporary chat not found".to_string()));
         };
         let messages = temp_state
             .messages
@@ -757,7 +1377,9 @@ pub async fn save_temporary_chat_to_archive(
             .cloned()
             .unwrap_or_default();
         if messages.is_empty() {
-            return Err("No temporary messages to archive".to_string());
+            return Err(RChatError::InvalidInput(
+                "No temporary messages to archive".to_string(),
+            ));
         }
         temp_state.chats.remove(&chat_id);
         temp_state.messages.remove(&chat_id);
@@ -773,15 +1395,14 @@ pub async fn save_temporary_chat_to_archive(
             })
             .is_err()
         {
-            storage::db::create_envelope(&conn, "archived", "Archived", None)
-                .map_err(|e| e.to_string())?;
+            storage::db::create_envelope(&conn, "archived", "Archived", None)?;
         }
 
         let archived_is_group = matches!(session.kind, crate::app_state::TemporaryChatKind::Group);
-        storage::db::create_chat(&conn, &archive_chat_id, &session.name, archived_is_group)
-            .map_err(|e| e.to_string())?;
+        storage::db::create_chat(&conn, &archive_chat_id, &session.name, archived_is_group)?;
         let _ = storage::db::add_chat_member(&conn, &archive_chat_id, "Me", "member");
 
+        let mut archived = Vec::with_capacity(messages.len());
         for (idx, mut msg) in messages.into_iter().enumerate() {
             msg.id = format!("{}-{}", msg.id, idx);
             msg.chat_id = archive_chat_id.clone();
@@ -791,7 +1412,7 @@ pub async fn save_temporary_chat_to_archive(
                 let _ = storage::db::add_peer(&conn, &msg.peer_id, None, None, "archived");
             }
 
-            if let Some(file_hash) = &msg.file_hash {
+            if let Some(file_hash) = msg.content.file_hash() {
                 let file_exists: bool = conn
                     .query_row(
                         "SELECT 1 FROM files WHERE file_hash = ?1",
@@ -800,20 +1421,21 @@ pub async fn save_temporary_chat_to_archive(
                     )
                     .unwrap_or(false);
                 if !file_exists {
-                    msg.text_content = Some(
-                        msg.text_content
-                            .clone()
-                            .unwrap_or_else(|| "Media unavailable".to_string()),
-                    );
-                    msg.file_hash = None;
+                    msg.content = chat::message::MessageContent::Text {
+                        text: msg
+                            .content
+                            .text_content()
+                            .unwrap_or("Media unavailable")
+                            .to_string(),
+                    };
                 }
             }
 
-            storage::db::insert_message(&conn, &msg).map_err(|e| e.to_string())?;
+            archived.push(msg);
         }
+        storage::db::insert_messages_batch(&conn, &archived)?;
 
-        storage::db::assign_chat_to_envelope(&conn, &archive_chat_id, Some("archived"))
-            .map_err(|e| e.to_string())?;
+        storage::db::assign_chat_to_envelope(&conn, &archive_chat_id, Some("archived"))?;
     }
 
     {
@@ -1,6 +1,7 @@
 use tauri::State;
 
 use crate::chat_kind::{self, ChatKind};
+use crate::error::RChatError;
 use crate::network::command::NetworkCommand;
 use crate::{AppState, NetworkState};
 
@@ -64,7 +65,7 @@ pub async fn get_chat_details_overview(
     chat_id: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<ChatDetailsOverview, String> {
+) -> Result<ChatDetailsOverview, RChatError> {
     ensure_dm_chat(&chat_id)?;
 
     let peer_id = resolve_dm_peer_id(&chat_id, &app_state)
@@ -74,25 +75,21 @@ pub async fn get_chat_details_overview(
     let (peer_name, peer_alias, connection_stats) = {
         let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
 
-        let peer_name = crate::storage::db::get_chat_name(&conn, &chat_id)
-            .map_err(|e| e.to_string())?
+        let peer_name = crate::storage::db::get_chat_name(&conn, &chat_id)?
             .or_else(|| crate::chat_identity::extract_name_from_chat_id(&chat_id))
             .unwrap_or_else(|| chat_id.clone());
 
-        let peer_alias = crate::storage::db::get_peer_alias(&conn, &chat_id)
-            .map_err(|e| e.to_string())?
-            .or_else(|| {
-                if peer_id != chat_id {
-                    crate::storage::db::get_peer_alias(&conn, &peer_id)
-                        .ok()
-                        .flatten()
-                } else {
-                    None
-                }
-            });
+        let peer_alias = crate::storage::db::get_peer_alias(&conn, &chat_id)?.or_else(|| {
+            if peer_id != chat_id {
+                crate::storage::db::get_peer_alias(&conn, &peer_id)
+                    .ok()
+                    .flatten()
+            } else {
+                None
+            }
+        });
 
-        let connection_stats = crate::storage::db::get_chat_connection_stats(&conn, &chat_id)
-            .map_err(|e| e.to_string())?;
+        let connection_stats = crate::storage::db::get_chat_connection_stats(&conn, &chat_id)?;
 
         (peer_name, peer_alias, connection_stats)
     };
@@ -139,15 +136,13 @@ pub async fn get_chat_details_overview(
 pub async fn get_chat_stats(
     chat_id: String,
     app_state: State<'_, AppState>,
-) -> Result<ChatStats, String> {
+) -> Result<ChatStats, RChatError> {
     ensure_dm_chat(&chat_id)?;
 
     let (message_stats, connection_stats) = {
         let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
-        let message_stats = crate::storage::db::get_chat_message_stats(&conn, &chat_id)
-            .map_err(|e| e.to_string())?;
-        let connection_stats = crate::storage::db::get_chat_connection_stats(&conn, &chat_id)
-            .map_err(|e| e.to_string())?;
+        let message_stats = crate::storage::db::get_chat_message_stats(&conn, &chat_id)?;
+        let connection_stats = crate::storage::db::get_chat_connection_stats(&conn, &chat_id)?;
         (message_stats, connection_stats)
     };
 
@@ -167,7 +162,7 @@ pub async fn list_chat_files(
     limit: Option<i64>,
     offset: Option<i64>,
     app_state: State<'_, AppState>,
-) -> Result<Vec<crate::storage::db::ChatFileRow>, String> {
+) -> Result<Vec<crate::storage::db::ChatFileRow>, RChatError> {
     ensure_dm_chat(&chat_id)?;
 
     let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
@@ -178,7 +173,7 @@ pub async fn list_chat_files(
         limit.unwrap_or(50),
         offset.unwrap_or(0),
     )
-    .map_err(|e| e.to_string())
+    .map_err(RChatError::from)
 }
 
 #[tauri::command]
@@ -186,14 +181,14 @@ pub async fn drop_chat_connection(
     chat_id: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let peer_id = resolve_dm_peer_id(&chat_id, &app_state).await?;
 
     let sender = net_state.sender.lock().await;
     sender
         .send(NetworkCommand::DropConnection { peer_id })
         .await
-        .map_err(|e| format!("Failed to drop connection: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to drop connection: {}", e)))
 }
 
 #[tauri::command]
@@ -201,7 +196,7 @@ pub async fn force_chat_reconnect(
     chat_id: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let peer_id = resolve_dm_peer_id(&chat_id, &app_state).await?;
 
     let sender = net_state.sender.lock().await;
@@ -215,5 +210,5 @@ pub async fn force_chat_reconnect(
     sender
         .send(NetworkCommand::RequestConnection { peer_id })
         .await
-        .map_err(|e| format!("Failed to request reconnect: {}", e))
+        .map_err(|e| RChatError::Network(format!("Failed to request reconnect: {}", e)))
 }
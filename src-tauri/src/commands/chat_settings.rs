@@ -0,0 +1,383 @@
+use tauri::State;
+
+use crate::chat::notify::{NotificationLevel, NotificationPriority};
+use crate::chat::translate;
+use crate::chat::{auto_download, hidden, media_guard};
+use crate::chat_kind::{self, ChatKind};
+use crate::commands::media::{detect_audio_mime, detect_audio_mime_from_bytes};
+use crate::error::RChatError;
+use crate::storage;
+use crate::storage::config::{
+    AutoDownloadPolicy, AutoDownloadSettings, AutoReplySettings, TranslationSettings,
+};
+use crate::AppState;
+
+const MAX_NOTIFICATION_SOUND_BYTES: usize = 2_000_000; // 2 MB
+
+fn now_unix_ts() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[tauri::command]
+pub async fn get_chat_settings(
+    chat_id: String,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::get_chat_settings(&conn, &chat_id).map_err(RChatError::from)
+}
+
+/// Set (or clear, with `None`) the custom notification sound for a chat.
+/// The file is copied into the object store so the setting survives the
+/// original file moving or the profile being restored on another machine.
+#[tauri::command]
+pub async fn set_chat_notification_sound(
+    chat_id: String,
+    file_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    println!(
+        "[Backend] set_chat_notification_sound: chat_id={}, file_path={:?}",
+        chat_id, file_path
+    );
+
+    let sound = match file_path.as_deref().map(str::trim) {
+        Some(path) if !path.is_empty() => {
+            let mime_type = detect_audio_mime(path).ok_or_else(|| {
+                RChatError::InvalidInput(
+                    "Unsupported sound format. Allowed: mp3, m4a, wav, ogg, webm, opus".to_string(),
+                )
+            })?;
+            let data = std::fs::read(path).map_err(|e| format!("Failed to read sound: {}", e))?;
+            if data.is_empty() || data.len() > MAX_NOTIFICATION_SOUND_BYTES {
+                return Err(RChatError::InvalidInput(
+                    "Notification sounds must be between 1 byte and 2 MB".to_string(),
+                ));
+            }
+            if detect_audio_mime_from_bytes(&data).is_none() {
+                return Err(RChatError::InvalidInput(
+                    "File does not look like a supported audio file".to_string(),
+                ));
+            }
+            let file_name = std::path::Path::new(path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("notification-sound")
+                .to_string();
+            Some((data, file_name, mime_type))
+        }
+        _ => None,
+    };
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.custom_sound_hash = match sound {
+        Some((data, file_name, mime_type)) => Some(
+            storage::object::create(&conn, &data, Some(&file_name), Some(mime_type), None)
+                .map_err(|e| format!("Failed to store sound: {}", e))?,
+        ),
+        None => None,
+    };
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_chat_notification_priority(
+    chat_id: String,
+    priority: String,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    let priority = NotificationPriority::parse(&priority).ok_or_else(|| {
+        format!(
+            "Invalid priority '{}'. Allowed: low, normal, high, urgent",
+            priority
+        )
+    })?;
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.priority = priority.as_str().to_string();
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Mute a chat for `duration_secs` (indefinitely when `None`), or unmute it
+/// when `muted` is false.
+#[tauri::command]
+pub async fn set_chat_mute(
+    chat_id: String,
+    muted: bool,
+    duration_secs: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    let mute_until = match (muted, duration_secs) {
+        (false, _) => None,
+        (true, None) => Some(storage::db::MUTE_FOREVER),
+        (true, Some(secs)) if secs > 0 => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            Some(now.saturating_add(secs))
+        }
+        (true, Some(_)) => {
+            return Err(RChatError::InvalidInput(
+                "Mute duration must be positive".to_string(),
+            ))
+        }
+    };
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.mute_until = mute_until;
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_chat_notification_level(
+    chat_id: String,
+    level: String,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    let level = NotificationLevel::parse(&level).ok_or_else(|| {
+        format!(
+            "Invalid notification level '{}'. Allowed: all, mentions, off",
+            level
+        )
+    })?;
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.notification_level = level.as_str().to_string();
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Turn "send translated" on or off for a direct chat. `target_lang` is the
+/// peer's preferred language and is required when enabling.
+#[tauri::command]
+pub async fn set_chat_send_translation(
+    chat_id: String,
+    enabled: bool,
+    target_lang: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    if !matches!(
+        chat_kind::parse_chat_kind(&chat_id),
+        ChatKind::Direct | ChatKind::TemporaryDirect
+    ) {
+        return Err(RChatError::InvalidInput(
+            "Send translated is only available in direct chats".to_string(),
+        ));
+    }
+    let target_lang = match target_lang.as_deref() {
+        Some(lang) => Some(translate::normalize_lang(lang).ok_or_else(|| {
+            RChatError::InvalidInput(format!("Invalid language code '{}'", lang))
+        })?),
+        None => None,
+    };
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    if target_lang.is_some() {
+        settings.translate_to = target_lang;
+    }
+    if enabled && settings.translate_to.is_none() {
+        return Err(RChatError::InvalidInput(
+            "Choose the language to translate into".to_string(),
+        ));
+    }
+    settings.send_translated = enabled;
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_chat_hidden(
+    chat_id: String,
+    hidden: bool,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    if matches!(chat_kind::parse_chat_kind(&chat_id), ChatKind::SelfChat) {
+        return Err(RChatError::InvalidInput(
+            "Note to Self cannot be hidden".to_string(),
+        ));
+    }
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.hidden = hidden;
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Show hidden chats in the chat list after re-checking the vault password.
+/// Returns when (unix secs) they are hidden again.
+#[tauri::command]
+pub async fn reveal_hidden_chats(
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<i64, RChatError> {
+    verify_vault_password(&state, &password).await?;
+    Ok(hidden::reveal(now_unix_ts()))
+}
+
+#[tauri::command]
+pub async fn conceal_hidden_chats() -> Result<(), RChatError> {
+    hidden::conceal();
+    Ok(())
+}
+
+async fn verify_vault_password(state: &State<'_, AppState>, password: &str) -> Result<(), String> {
+    let mgr = state.config_manager.lock().await;
+    if mgr
+        .verify_password(password)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Ok(())
+    } else {
+        Err("Invalid password".to_string())
+    }
+}
+
+/// Block or allow saving received media out of a chat. Lifting the
+/// restriction requires the vault password.
+#[tauri::command]
+pub async fn set_chat_media_export_restricted(
+    chat_id: String,
+    restricted: bool,
+    password: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    if !restricted {
+        verify_vault_password(&state, password.as_deref().unwrap_or_default()).await?;
+    }
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.restrict_media_export = restricted;
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Override (or with `None`, reset to the global setting) whether `media_type`
+/// is downloaded automatically in this chat.
+#[tauri::command]
+pub async fn set_chat_auto_download(
+    chat_id: String,
+    media_type: String,
+    policy: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    if AutoDownloadSettings::default()
+        .policy_for(&media_type)
+        .is_none()
+    {
+        return Err(RChatError::InvalidInput(format!(
+            "Invalid media type '{}'. Allowed: image, video, audio, document, sticker",
+            media_type
+        )));
+    }
+    let policy = policy
+        .map(|policy| {
+            AutoDownloadPolicy::parse(&policy).ok_or_else(|| {
+                format!(
+                    "Invalid policy '{}'. Allowed: always, wifi_only, never",
+                    policy
+                )
+            })
+        })
+        .transpose()?;
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    let mut overrides = auto_download::parse_overrides(settings.auto_download.as_deref());
+    match policy {
+        Some(policy) => overrides.insert(media_type, policy),
+        None => overrides.remove(&media_type),
+    };
+    settings.auto_download = if overrides.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&overrides).map_err(|e| e.to_string())?)
+    };
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// The frontend reports whether the current connection is metered (not
+/// Wi-Fi); `wifi_only` media waits while it is.
+#[tauri::command]
+pub fn set_network_metered(metered: bool) -> Result<(), RChatError> {
+    auto_download::set_metered(metered);
+    Ok(())
+}
+
+/// Allow one save of a restricted file after re-entering the vault password.
+#[tauri::command]
+pub async fn authorize_media_export(
+    file_hash: String,
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    verify_vault_password(&state, &password).await?;
+    media_guard::grant_override(&file_hash, now_unix_ts());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_translation_settings(
+    state: State<'_, AppState>,
+) -> Result<TranslationSettings, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await?;
+    Ok(config.user.translation)
+}
+
+#[tauri::command]
+pub async fn set_translation_settings(
+    settings: TranslationSettings,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    settings.validate().map_err(RChatError::InvalidInput)?;
+
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    config.user.translation = settings;
+    mgr.save(&config).await.map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn get_auto_reply_settings(
+    state: State<'_, AppState>,
+) -> Result<AutoReplySettings, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await?;
+    Ok(config.user.auto_reply)
+}
+
+/// Configure away mode. Turning it on starts a fresh round, so every friend
+/// gets the reply again.
+#[tauri::command]
+pub async fn set_auto_reply_settings(
+    settings: AutoReplySettings,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    settings.validate().map_err(RChatError::InvalidInput)?;
+
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    let turning_on = settings.enabled && !config.user.auto_reply.enabled;
+    config.user.auto_reply = settings;
+    mgr.save(&config).await?;
+
+    if turning_on {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::clear_auto_replies(&conn)?;
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::error::RChatError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use std::io::Write;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -28,7 +30,7 @@ fn append_frontend_log_line(path: &Path, message: &str) -> std::io::Result<()> {
 }
 
 #[tauri::command]
-pub fn frontend_log(app_handle: tauri::AppHandle, message: String) -> Result<(), String> {
+pub fn frontend_log(app_handle: crate::AppHandle, message: String) -> Result<(), RChatError> {
     let sanitized = sanitize_frontend_log(&message);
     println!("{}", sanitized);
     let log_path = app_handle
@@ -41,6 +43,43 @@ pub fn frontend_log(app_handle: tauri::AppHandle, message: String) -> Result<(),
     Ok(())
 }
 
+/// Write the discovery (Gist/HKS) state to `debug/discovery-<timestamp>.json`
+/// in the app data directory and return its path. The file stays local; see
+/// `crate::network::discovery_debug` for what it holds.
+#[tauri::command]
+pub async fn debug_export_discovery_state(
+    app_handle: crate::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<String, RChatError> {
+    let config = {
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await?
+    };
+    let my_x25519_pubkey = config
+        .user
+        .encryption_private_key
+        .as_deref()
+        .and_then(|s| BASE64.decode(s).ok())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(|bytes| {
+            let secret = x25519_dalek::StaticSecret::from(bytes);
+            BASE64.encode(x25519_dalek::PublicKey::from(&secret).as_bytes())
+        });
+    let snapshot = crate::network::discovery_debug::snapshot(&config, my_x25519_pubkey);
+    let json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to resolve app data dir: {}", e))?
+        .join("debug");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("discovery-{}.json", snapshot.generated_at));
+    std::fs::write(&path, json).map_err(|e| format!("failed to write export: {}", e))?;
+    println!("[Debug] 🧾 Exported discovery state to {}", path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,26 @@
+use tauri::State;
+
+use crate::error::RChatError;
+use crate::storage;
+use crate::AppState;
+
+/// Persist the unsent text for a chat. Saving blank text clears the draft, so
+/// the frontend can call this after a message is sent.
+#[tauri::command]
+pub async fn save_draft(
+    chat_id: String,
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::save_draft(&conn, &chat_id, &text).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn get_draft(
+    chat_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::get_draft(&conn, &chat_id).map_err(RChatError::from)
+}
@@ -1,5 +1,6 @@
 use tauri::State;
 
+use crate::error::RChatError;
 use crate::storage;
 use crate::AppState;
 
@@ -8,15 +9,17 @@ pub async fn create_envelope(
     id: String,
     name: String,
     icon: Option<String>,
+    parent_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     println!(
-        "[Backend] create_envelope call: {}, {}, icon: {:?}",
-        id, name, icon
+        "[Backend] create_envelope call: {}, {}, icon: {:?}, parent: {:?}",
+        id, name, icon, parent_id
     );
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
 
-    storage::db::create_envelope(&conn, &id, &name, icon.as_deref()).map_err(|e| e.to_string())
+    storage::db::create_nested_envelope(&conn, &id, &name, icon.as_deref(), parent_id.as_deref())
+        .map_err(RChatError::from)
 }
 
 #[tauri::command]
@@ -25,23 +28,55 @@ pub async fn update_envelope(
     name: String,
     icon: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::update_envelope(&conn, &id, &name, icon.as_deref()).map_err(|e| e.to_string())
+    storage::db::update_envelope(&conn, &id, &name, icon.as_deref()).map_err(RChatError::from)
 }
 
 #[tauri::command]
-pub async fn delete_envelope(id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_envelope(id: String, state: State<'_, AppState>) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::delete_envelope(&conn, &id).map_err(|e| e.to_string())
+    storage::db::delete_envelope(&conn, &id).map_err(RChatError::from)
 }
 
 #[tauri::command]
 pub async fn get_envelopes(
     state: State<'_, AppState>,
-) -> Result<Vec<storage::db::Envelope>, String> {
+) -> Result<Vec<storage::db::Envelope>, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::get_envelopes(&conn).map_err(|e| e.to_string())
+    storage::db::get_envelopes(&conn).map_err(RChatError::from)
+}
+
+/// Move `envelope_ids` into `parent_id` (top level for `None`) in this order.
+#[tauri::command]
+pub async fn reorder_envelopes(
+    parent_id: Option<String>,
+    envelope_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    println!(
+        "[Backend] reorder_envelopes: parent={:?}, ids={:?}",
+        parent_id, envelope_ids
+    );
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::reorder_envelopes(&conn, parent_id.as_deref(), &envelope_ids)
+        .map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn get_envelope_summaries(
+    my_peer_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::EnvelopeSummary>, RChatError> {
+    let mapped = crate::commands::chat::mapped_chat_ids_by_peer(&state).await;
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::get_envelope_summaries(&conn, &my_peer_id, |chat_id| {
+        mapped
+            .get(chat_id)
+            .cloned()
+            .unwrap_or_else(|| chat_id.to_string())
+    })
+    .map_err(RChatError::from)
 }
 
 #[tauri::command]
@@ -49,20 +84,42 @@ pub async fn move_chat_to_envelope(
     chat_id: String,
     envelope_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     println!(
         "[Backend] move_chat_to_envelope: chat_id={}, envelope_id={:?}",
         chat_id, envelope_id
     );
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
     storage::db::assign_chat_to_envelope(&conn, &chat_id, envelope_id.as_deref())
-        .map_err(|e| e.to_string())
+        .map_err(RChatError::from)
+}
+
+/// Tag a chat with an envelope in addition to the ones it already has.
+#[tauri::command]
+pub async fn add_chat_tag(
+    chat_id: String,
+    envelope_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::add_chat_tag(&conn, &chat_id, &envelope_id).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn remove_chat_tag(
+    chat_id: String,
+    envelope_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::remove_chat_tag(&conn, &chat_id, &envelope_id).map_err(RChatError::from)
 }
 
+/// One row per (chat, envelope) tag.
 #[tauri::command]
 pub async fn get_envelope_assignments(
     state: State<'_, AppState>,
-) -> Result<Vec<storage::db::ChatAssignment>, String> {
+) -> Result<Vec<storage::db::ChatAssignment>, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::get_chat_assignments(&conn).map_err(|e| e.to_string())
+    storage::db::get_chat_assignments(&conn).map_err(RChatError::from)
 }
@@ -0,0 +1,181 @@
+//! Moderation commands for group admins.
+//!
+//! Each command checks that we are an admin of the group (our `chat_peers`
+//! role), applies the change locally and publishes it as a signed
+//! [`GroupControlMessage`](crate::network::gossip::GroupControlMessage).
+//! Members apply it only after checking the signature and that the sender is
+//! an admin on their side too.
+
+use tauri::{Manager, State};
+
+use crate::chat_kind;
+use crate::error::RChatError;
+use crate::network::command::NetworkCommand;
+use crate::network::gossip::GroupAdminAction;
+use crate::storage;
+use crate::{AppState, NetworkState};
+
+pub(crate) fn require_admin(conn: &rusqlite::Connection, chat_id: &str) -> Result<(), RChatError> {
+    if !chat_kind::is_group_chat_id(chat_id) {
+        return Err(RChatError::InvalidInput(
+            "Invalid group id. Expected format group:<uuid>".to_string(),
+        ));
+    }
+    match storage::db::chat_member_role(conn, chat_id, "Me")? {
+        Some(role) if role == "admin" => Ok(()),
+        Some(_) => Err(RChatError::InvalidInput(
+            "Only group admins can do this".to_string(),
+        )),
+        None => Err(RChatError::NotFound(format!("Not a member of {}", chat_id))),
+    }
+}
+
+fn require_member(
+    conn: &rusqlite::Connection,
+    chat_id: &str,
+    peer_id: &str,
+) -> Result<(), RChatError> {
+    if peer_id == "Me" || storage::db::chat_member_role(conn, chat_id, peer_id)?.is_none() {
+        return Err(RChatError::NotFound(format!(
+            "{} is not a member of {}",
+            peer_id, chat_id
+        )));
+    }
+    Ok(())
+}
+
+async fn publish(
+    app_handle: &crate::AppHandle,
+    chat_id: &str,
+    action: GroupAdminAction,
+    rekey: bool,
+) {
+    let Some(net_state) = app_handle.try_state::<NetworkState>() else {
+        return;
+    };
+    let tx = net_state.sender.lock().await;
+    let _ = tx
+        .send(NetworkCommand::PublishGroupControl {
+            group_id: chat_id.to_string(),
+            action,
+        })
+        .await;
+    if rekey {
+        let _ = tx
+            .send(NetworkCommand::RekeyGroup {
+                group_id: chat_id.to_string(),
+            })
+            .await;
+    }
+}
+
+/// Remove a member from the group. The group gets a new key they can't read.
+#[tauri::command]
+pub async fn kick_member(
+    chat_id: String,
+    peer_id: String,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        require_admin(&conn, &chat_id)?;
+        require_member(&conn, &chat_id, &peer_id)?;
+        if storage::db::group_key_admin(&conn, &chat_id)?.as_deref() == Some(peer_id.as_str()) {
+            return Err(RChatError::InvalidInput(
+                "The group's creator can't be removed".to_string(),
+            ));
+        }
+        storage::db::remove_chat_member(&conn, &chat_id, &peer_id)?;
+    }
+    // The kick goes out under the old key, so the member learns about it.
+    publish(
+        &app_handle,
+        &chat_id,
+        GroupAdminAction::Kick { peer_id },
+        true,
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn promote_member(
+    chat_id: String,
+    peer_id: String,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        require_admin(&conn, &chat_id)?;
+        require_member(&conn, &chat_id, &peer_id)?;
+        storage::db::set_chat_member_role(&conn, &chat_id, &peer_id, "admin")?;
+    }
+    publish(
+        &app_handle,
+        &chat_id,
+        GroupAdminAction::Promote { peer_id },
+        false,
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_group_name(
+    chat_id: String,
+    name: String,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(RChatError::InvalidInput(
+            "Group name can't be empty".to_string(),
+        ));
+    }
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        require_admin(&conn, &chat_id)?;
+        storage::db::upsert_chat(&conn, &chat_id, &name, true)?;
+    }
+    publish(
+        &app_handle,
+        &chat_id,
+        GroupAdminAction::SetName { name },
+        false,
+    )
+    .await;
+    Ok(())
+}
+
+/// Set the group's avatar to a stored file, or clear it with `None`.
+/// Members fetch the file from us.
+#[tauri::command]
+pub async fn set_group_avatar(
+    chat_id: String,
+    file_hash: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<(), RChatError> {
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        require_admin(&conn, &chat_id)?;
+        if let Some(hash) = &file_hash {
+            let complete = storage::object::info(&conn, hash)?.is_some_and(|info| info.is_complete);
+            if !complete {
+                return Err(RChatError::NotFound(format!("File {} not found", hash)));
+            }
+        }
+        storage::db::set_chat_avatar(&conn, &chat_id, file_hash.as_deref())?;
+    }
+    publish(
+        &app_handle,
+        &chat_id,
+        GroupAdminAction::SetAvatar { file_hash },
+        false,
+    )
+    .await;
+    Ok(())
+}
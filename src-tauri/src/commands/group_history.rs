@@ -0,0 +1,82 @@
+//! Group history backfill.
+//!
+//! Admins choose how many days of a group's history they share with new
+//! members (none by default); members ask an admin for it with
+//! `request_group_history` and receive it page by page
+//! ("group-history-backfilled").
+
+use tauri::State;
+
+use crate::commands::group_admin::require_admin;
+use crate::error::RChatError;
+use crate::network::command::NetworkCommand;
+use crate::network::group_history::MAX_HISTORY_SHARE_DAYS;
+use crate::storage;
+use crate::{AppState, NetworkState};
+
+/// Share the last `days` of the group's history with members who ask, or
+/// nothing with `None`.
+#[tauri::command]
+pub async fn set_group_history_sharing(
+    chat_id: String,
+    days: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<storage::db::ChatSettings, RChatError> {
+    if days.is_some_and(|days| !(1..=MAX_HISTORY_SHARE_DAYS).contains(&days)) {
+        return Err(RChatError::InvalidInput(format!(
+            "History can be shared for 1 to {} days",
+            MAX_HISTORY_SHARE_DAYS
+        )));
+    }
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    require_admin(&conn, &chat_id)?;
+    let mut settings = storage::db::get_chat_settings(&conn, &chat_id)?;
+    settings.history_share_days = days;
+    storage::db::upsert_chat_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Ask an admin of the group for its earlier history: `peer_id`, or the
+/// group's creator when unset. The admin must be online and share history.
+#[tauri::command]
+pub async fn request_group_history(
+    chat_id: String,
+    peer_id: Option<String>,
+    state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
+    let admin = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        if storage::db::chat_member_role(&conn, &chat_id, "Me")?.is_none() {
+            return Err(RChatError::NotFound(format!("Not a member of {}", chat_id)));
+        }
+        match peer_id {
+            Some(peer_id) => {
+                if storage::db::chat_member_role(&conn, &chat_id, &peer_id)?.as_deref()
+                    != Some("admin")
+                {
+                    return Err(RChatError::InvalidInput(format!(
+                        "{} is not an admin of {}",
+                        peer_id, chat_id
+                    )));
+                }
+                peer_id
+            }
+            None => storage::db::group_key_admin(&conn, &chat_id)?
+                .ok_or_else(|| RChatError::NotFound(format!("No known admin for {}", chat_id)))?,
+        }
+    };
+    let peer_id = admin
+        .parse::<libp2p::PeerId>()
+        .map_err(|e| RChatError::InvalidInput(format!("Invalid peer id: {}", e)))?;
+
+    let sender = net_state.sender.lock().await;
+    sender
+        .send(NetworkCommand::RequestGroupHistory {
+            group_id: chat_id,
+            peer_id,
+        })
+        .await
+        .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Group invite codes.
+//!
+//! An admin creates a signed `rchat://group/...` code (see
+//! [`GroupInvite`]); whoever redeems it with `join_group` is let in by the
+//! inviter, who must be online, and receives the members, the group key and
+//! recent history.
+
+use tauri::State;
+
+use crate::commands::group_admin::require_admin;
+use crate::commands::invite::resolve_current_public_address;
+use crate::error::RChatError;
+use crate::network::command::NetworkCommand;
+use crate::network::group_invite::GroupInvite;
+use crate::storage;
+use crate::{AppState, NetworkState};
+
+#[derive(serde::Serialize)]
+pub struct GroupInviteView {
+    pub code: String,
+    pub group_id: String,
+    pub expires_at: i64,
+}
+
+fn now_unix_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Create an invite code for a group we administer. Valid for
+/// `GROUP_INVITE_TTL_SECS` while this session runs.
+#[tauri::command]
+pub async fn create_group_invite(
+    chat_id: String,
+    state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<GroupInviteView, RChatError> {
+    let group_name = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        require_admin(&conn, &chat_id)?;
+        storage::db::get_chat_name(&conn, &chat_id)?.unwrap_or_else(|| chat_id.clone())
+    };
+    let inviter_peer_id = net_state
+        .local_peer_id
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| RChatError::PeerOffline("Network is not started yet".to_string()))?;
+    let inviter_addr = resolve_current_public_address(&net_state).await?;
+    let invite = GroupInvite::new(
+        &chat_id,
+        &group_name,
+        &inviter_peer_id,
+        &inviter_addr,
+        now_unix_secs(),
+    );
+    let expires_at = invite.expires_at;
+
+    let (reply, rx) = tokio::sync::oneshot::channel();
+    {
+        let sender = net_state.sender.lock().await;
+        sender
+            .send(NetworkCommand::CreateGroupInvite { invite, reply })
+            .await
+            .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    }
+    let code = tokio::time::timeout(std::time::Duration::from_secs(5), rx)
+        .await
+        .map_err(|_| RChatError::Network("Timed out waiting for the network manager".to_string()))?
+        .map_err(|_| RChatError::Network("Network manager dropped the request".to_string()))?
+        .map_err(RChatError::Crypto)?;
+
+    Ok(GroupInviteView {
+        code,
+        group_id: chat_id,
+        expires_at,
+    })
+}
+
+/// Redeem a group invite code. The group shows up once the inviter has let
+/// us in ("group-joined").
+#[tauri::command]
+pub async fn join_group(
+    code: String,
+    state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<String, RChatError> {
+    let invite = GroupInvite::decode(&code).map_err(|e| RChatError::InvalidInput(e.to_string()))?;
+    invite
+        .check(now_unix_secs())
+        .map_err(|e| RChatError::InvalidInput(e.to_string()))?;
+    let local_peer_id = net_state
+        .local_peer_id
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| RChatError::PeerOffline("Network is not started yet".to_string()))?;
+    if invite.inviter_peer_id == local_peer_id {
+        return Err(RChatError::InvalidInput(
+            "This is your own invite".to_string(),
+        ));
+    }
+    {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        if storage::db::chat_member_role(&conn, &invite.group_id, "Me")?.is_some() {
+            return Err(RChatError::InvalidInput(format!(
+                "Already a member of {}",
+                invite.group_name
+            )));
+        }
+    }
+
+    let group_id = invite.group_id.clone();
+    let sender = net_state.sender.lock().await;
+    sender
+        .send(NetworkCommand::JoinGroup { invite })
+        .await
+        .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    Ok(group_id)
+}
@@ -0,0 +1,46 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::RChatError;
+use crate::AppState;
+
+/// What a script needs to call the API; see [`crate::http_api`].
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpApiInfo {
+    pub enabled: bool,
+    pub url: String,
+    pub token: String,
+}
+
+#[tauri::command]
+pub async fn get_http_api_info(state: State<'_, AppState>) -> Result<HttpApiInfo, RChatError> {
+    let settings = {
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await?.user.http_api
+    };
+    let token = crate::http_api::load_or_create_token(&state.app_dir)?;
+    Ok(HttpApiInfo {
+        enabled: settings.enabled,
+        url: crate::http_api::base_url(&settings),
+        token,
+    })
+}
+
+/// Issue a new token; requests with the old one are refused from now on.
+#[tauri::command]
+pub async fn rotate_http_api_token(
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<HttpApiInfo, RChatError> {
+    let token = crate::http_api::rotate_token(&state.app_dir)?;
+    let settings = {
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await?.user.http_api
+    };
+    crate::http_api::apply(&app_handle, &settings).await;
+    Ok(HttpApiInfo {
+        enabled: settings.enabled,
+        url: crate::http_api::base_url(&settings),
+        token,
+    })
+}
@@ -0,0 +1,77 @@
+use tauri::{Emitter, Manager, State};
+
+use crate::chat::import::{ImportOptions, ImportPreview, ImportSource, ImportSummary};
+use crate::error::RChatError;
+use crate::AppState;
+
+fn export_path(path: &str) -> Result<std::path::PathBuf, RChatError> {
+    if path.trim().is_empty() {
+        return Err(RChatError::InvalidInput(
+            "Import path is required".to_string(),
+        ));
+    }
+    Ok(std::path::PathBuf::from(path.trim()))
+}
+
+/// Chat name, message count and senders of an export, with the peer each
+/// sender would be mapped to, so the user can adjust the mapping first.
+#[tauri::command]
+pub async fn preview_chat_import(
+    source: ImportSource,
+    path: String,
+    options: Option<ImportOptions>,
+    state: State<'_, AppState>,
+) -> Result<ImportPreview, RChatError> {
+    let path = export_path(&path)?;
+    let options = options.unwrap_or_default();
+    let chat = crate::chat::import::parse_export(source, &path, options.utc_offset_minutes)
+        .map_err(|e| RChatError::InvalidInput(e.to_string()))?;
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    crate::chat::import::preview(&conn, source, &chat, &options).map_err(RChatError::from)
+}
+
+/// Import a Telegram or WhatsApp export as a new archived chat. Emits
+/// `chat-import-progress` after each batch of messages.
+#[tauri::command]
+pub async fn import_chat_history(
+    source: ImportSource,
+    path: String,
+    options: Option<ImportOptions>,
+    app_handle: crate::AppHandle,
+) -> Result<ImportSummary, RChatError> {
+    println!(
+        "[Backend] import_chat_history {} <- {}",
+        source.as_str(),
+        path
+    );
+    let path = export_path(&path)?;
+    let options = options.unwrap_or_default();
+    let chat = crate::chat::import::parse_export(source, &path, options.utc_offset_minutes)
+        .map_err(|e| RChatError::InvalidInput(e.to_string()))?;
+    if chat.messages.is_empty() {
+        return Err(RChatError::InvalidInput(
+            "The export has no messages".to_string(),
+        ));
+    }
+    let base_dir = path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<AppState>();
+        crate::chat::import::import_chat(
+            &state.db_conn,
+            source,
+            chat,
+            &base_dir,
+            &options,
+            |progress| {
+                let _ = app_handle.emit("chat-import-progress", progress);
+            },
+        )
+        .map_err(RChatError::from)
+    })
+    .await
+    .map_err(|e| RChatError::Internal(e.to_string()))?
+}
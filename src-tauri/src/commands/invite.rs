@@ -1,9 +1,12 @@
 use tauri::{Emitter, Manager, State};
 
 use crate::app_state::{
-    ActiveTemporaryInvite, TemporaryChatKind, TemporaryChatSession, TemporaryInvitePayload,
+    ActiveTemporaryInvite, FriendRequest, TemporaryChatKind, TemporaryChatSession,
+    TemporaryInvitePayload,
 };
+use crate::error::RChatError;
 use crate::network::command::NetworkCommand;
+use crate::network::discovery_backend::Backend;
 use crate::storage;
 use crate::{AppState, NetworkState};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
@@ -93,7 +96,9 @@ fn extract_temporary_payload_token(input: &str) -> Result<String, String> {
     Ok(trimmed.to_string())
 }
 
-async fn resolve_current_public_address(net_state: &NetworkState) -> Result<String, String> {
+pub(crate) async fn resolve_current_public_address(
+    net_state: &NetworkState,
+) -> Result<String, String> {
     let v4_stun = net_state.public_address_v4.lock().await.clone();
     let stun_port = *net_state.stun_external_port.lock().await;
 
@@ -130,7 +135,7 @@ fn canonical_temp_dm_chat_id(a: &str, b: &str) -> String {
 
 /// Generate a 14-character password for invitations
 #[tauri::command]
-pub async fn generate_invite_password() -> Result<String, String> {
+pub async fn generate_invite_password() -> Result<String, RChatError> {
     Ok(rvault_core::crypto::generate_password(14, false))
 }
 
@@ -140,26 +145,25 @@ pub async fn create_invite(
     invitee: String,
     password: String,
     app_state: State<'_, AppState>,
-    app: tauri::AppHandle,
-) -> Result<(), String> {
+    app: crate::AppHandle,
+) -> Result<(), RChatError> {
     use crate::network::discovery;
     use crate::network::gist;
     use crate::network::invite;
 
-    let (my_username, token) = {
+    let (my_username, backend, my_keys) = {
         let mgr = app_state.config_manager.lock().await;
-        let config = mgr.load().await.map_err(|e| e.to_string())?;
+        let config = mgr.load().await?;
         let username = config
             .system
-            .github_username
-            .clone()
-            .ok_or("GitHub username not set")?;
-        let tok = config
-            .system
-            .github_token
-            .clone()
-            .ok_or("GitHub token not set")?;
-        (username, tok)
+            .discovery_username()
+            .map(ToOwned::to_owned)
+            .ok_or("Discovery username not set")?;
+        let backend = Backend::from_system(&config.system);
+        if !backend.can_publish() {
+            return Err(RChatError::InvalidInput("GitHub token not set".to_string()));
+        }
+        (username, backend, config.user.identity_keys())
     };
 
     let net_state = app.state::<NetworkState>();
@@ -168,7 +172,11 @@ pub async fn create_invite(
         .lock()
         .await
         .clone()
-        .ok_or("Network peer id not available. Is the network started?")?;
+        .ok_or_else(|| {
+            RChatError::PeerOffline(
+                "Network peer id not available. Is the network started?".to_string(),
+            )
+        })?;
     let my_address = {
         let v4_stun = net_state.public_address_v4.lock().await.clone();
         let stun_port = net_state.stun_external_port.lock().await.clone();
@@ -194,7 +202,11 @@ pub async fn create_invite(
                 })
                 .or_else(|| addrs.first())
                 .cloned()
-                .ok_or("No listening address available. Is the network started?")?
+                .ok_or_else(|| {
+                    RChatError::PeerOffline(
+                        "No listening address available. Is the network started?".to_string(),
+                    )
+                })?
         }
     };
 
@@ -204,33 +216,38 @@ pub async fn create_invite(
         &invitee,
         &my_address,
         &local_peer_id,
+        my_keys,
         120,
     )
     .map_err(|e| format!("Failed to generate invite: {}", e))?;
 
-    let tracked = gist::track_invite(encrypted_invite);
+    let pending = gist::PendingInvitation {
+        invite: gist::track_invite(encrypted_invite),
+        invitee: Some(invitee.clone()),
+    };
 
     {
         let mgr = app_state.config_manager.lock().await;
-        let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+        let mut config = mgr.load().await?;
 
-        if config.user.pending_invitations.is_none() {
-            config.user.pending_invitations = Some(Vec::new());
-        }
-
-        if let Some(ref mut invites) = config.user.pending_invitations {
-            let invite_json = serde_json::to_string(&tracked)
-                .map_err(|e| format!("Failed to serialize invite: {}", e))?;
-            invites.push(invite_json);
-        }
+        let invite_json = serde_json::to_string(&pending)
+            .map_err(|e| format!("Failed to serialize invite: {}", e))?;
+        config
+            .user
+            .pending_invitations
+            .get_or_insert_with(Vec::new)
+            .push(invite_json);
 
-        mgr.save(&config).await.map_err(|e| e.to_string())?;
+        mgr.save(&config).await?;
     }
 
     println!("[Backend] Publishing invite to Gist immediately...");
-    discovery::publish_peer_info(&token, vec![], app.clone())
+    discovery::publish_peer_info(&backend, vec![], app.clone())
         .await
-        .map_err(|e| format!("Failed to publish invite: {}", e))?;
+        .map_err(|e| {
+            gist::report_auth_failure(&app, &e);
+            format!("Failed to publish invite: {}", e)
+        })?;
 
     println!("[Backend] Published invite to Gist");
 
@@ -254,35 +271,128 @@ pub async fn create_invite(
     Ok(())
 }
 
+#[derive(serde::Serialize)]
+pub struct PendingInvitationView {
+    pub invitee: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+/// Invitations we published that are still waiting to be redeemed.
+#[tauri::command]
+pub async fn get_pending_invitations(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<PendingInvitationView>, RChatError> {
+    use crate::network::gist;
+
+    let mgr = app_state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    if gist::prune_pending_invitations(&mut config.user.pending_invitations, now_unix_secs(), None)
+        > 0
+    {
+        mgr.save(&config).await?;
+    }
+    Ok(
+        gist::parse_pending_invitations(config.user.pending_invitations.as_deref())
+            .into_iter()
+            .map(|pending| PendingInvitationView {
+                expires_at: pending.expires_at(),
+                created_at: pending.invite.created_at,
+                invitee: pending.invitee,
+            })
+            .collect(),
+    )
+}
+
+/// Withdraw our invitation for `invitee` and republish without it.
+#[tauri::command]
+pub async fn cancel_invite(
+    invitee: String,
+    app_state: State<'_, AppState>,
+    app: crate::AppHandle,
+) -> Result<(), RChatError> {
+    use crate::network::discovery;
+    use crate::network::gist;
+
+    let backend = {
+        let mgr = app_state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        let removed = gist::prune_pending_invitations(
+            &mut config.user.pending_invitations,
+            now_unix_secs(),
+            Some(&invitee),
+        );
+        if removed == 0 {
+            return Err(RChatError::NotFound(format!(
+                "No pending invitation for {}",
+                invitee
+            )));
+        }
+        mgr.save(&config).await?;
+        Backend::from_system(&config.system)
+    };
+
+    {
+        let net_state = app.state::<NetworkState>();
+        let tx = net_state.sender.lock().await;
+        if let Err(e) = tx
+            .send(NetworkCommand::CancelShadowPoll {
+                invitee: invitee.clone(),
+            })
+            .await
+        {
+            println!("[Backend] Failed to cancel shadow poll: {}", e);
+        }
+    }
+
+    if backend.can_publish() {
+        discovery::publish_peer_info(&backend, vec![], app.clone())
+            .await
+            .map_err(|e| {
+                gist::report_auth_failure(&app, &e);
+                format!("Failed to publish without invite: {}", e)
+            })?;
+    }
+
+    println!("[Backend] Canceled invite for {}", invitee);
+    Ok(())
+}
+
 /// Complete invitation redemption with friend persistence and auto-message
 #[tauri::command]
 pub async fn redeem_and_connect(
-    handle: tauri::AppHandle,
+    handle: crate::AppHandle,
     inviter: String,
     password: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<String, String> {
+) -> Result<String, RChatError> {
     use crate::network::gist;
     use crate::network::invite;
-    use crate::storage::config::FriendConfig;
 
-    let my_username = {
+    let (my_username, backend, my_keys) = {
         let mgr = app_state.config_manager.lock().await;
-        let config = mgr.load().await.map_err(|e| e.to_string())?;
-        config
+        let config = mgr.load().await?;
+        let username = config
             .system
-            .github_username
-            .clone()
-            .ok_or("GitHub username not set")?
+            .discovery_username()
+            .map(ToOwned::to_owned)
+            .ok_or("Discovery username not set")?;
+        (
+            username,
+            Backend::from_system(&config.system),
+            config.user.identity_keys(),
+        )
     };
 
-    let encrypted_invites = gist::get_friend_invitations(&inviter)
+    let encrypted_invites = gist::get_friend_invitations(&backend, &inviter)
         .await
         .map_err(|e| format!("Failed to fetch invitations: {}", e))?;
 
     if encrypted_invites.is_empty() {
-        return Err("No invitations found from this user".to_string());
+        return Err(RChatError::NotFound(
+            "No invitations found from this user".to_string(),
+        ));
     }
 
     let result = invite::process_invites(&encrypted_invites, &password, &inviter, &my_username)
@@ -309,32 +419,23 @@ pub async fn redeem_and_connect(
                 }
             });
             let resolved_peer_id = existing_peer_id.or(invite_peer_id).ok_or_else(|| {
-                "Invitation is missing inviter peer id. Ask the inviter to generate a new invite."
-                    .to_string()
+                RChatError::InvalidInput(
+                    "Invitation is missing inviter peer id. Ask the inviter to generate a new invite."
+                        .to_string(),
+                )
             })?;
             let chat_id =
                 crate::chat_identity::build_github_chat_id(&github_username, &resolved_peer_id);
 
             {
                 let mgr = app_state.config_manager.lock().await;
-                let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+                let mut config = mgr.load().await?;
 
-                if !config
+                if config
                     .user
-                    .friends
-                    .iter()
-                    .any(|f| f.username == github_username)
+                    .remember_friend(&github_username, payload.inviter_keys.as_ref())
                 {
-                    config.user.friends.push(FriendConfig {
-                        username: github_username.clone(),
-                        alias: None,
-                        x25519_pubkey: None,
-                        ed25519_pubkey: None,
-                        leaf_index: 0,
-                        encrypted_leaf_key: None,
-                        nonce: None,
-                    });
-                    mgr.save(&config).await.map_err(|e| e.to_string())?;
+                    mgr.save(&config).await?;
                 }
             }
 
@@ -342,13 +443,11 @@ pub async fn redeem_and_connect(
                 let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
 
                 if !storage::db::is_peer(&conn, &chat_id) {
-                    storage::db::add_peer(&conn, &chat_id, Some(&github_username), None, "github")
-                        .map_err(|e| e.to_string())?;
+                    storage::db::add_peer(&conn, &chat_id, Some(&github_username), None, "github")?;
                 }
 
                 if !storage::db::chat_exists(&conn, &chat_id) {
-                    storage::db::create_chat(&conn, &chat_id, &github_username, false)
-                        .map_err(|e| e.to_string())?;
+                    storage::db::create_chat(&conn, &chat_id, &github_username, false)?;
                 }
             }
 
@@ -359,23 +458,23 @@ pub async fn redeem_and_connect(
 
             {
                 let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
-                let id_suffix: u32 = rand::random();
-                let msg_id = format!("{}-{}", timestamp, id_suffix);
+                let msg_id = crate::chat::message_id::new_message_id();
 
                 let msg = storage::db::Message {
                     id: msg_id.clone(),
                     chat_id: chat_id.clone(),
                     peer_id: "Me".to_string(),
                     timestamp,
-                    content_type: "text".to_string(),
-                    text_content: Some("Hi!".to_string()),
-                    file_hash: None,
+                    content: crate::chat::message::MessageContent::Text {
+                        text: "Hi!".to_string(),
+                    },
                     status: "delivered".to_string(),
                     content_metadata: None,
                     sender_alias: None,
+                    logical: 0,
                 };
 
-                storage::db::insert_message(&conn, &msg).map_err(|e| e.to_string())?;
+                storage::db::insert_message(&conn, &msg)?;
             }
 
             {
@@ -399,23 +498,19 @@ pub async fn redeem_and_connect(
                     }
                 };
 
-                let github_token = {
-                    let mgr = app_state.config_manager.lock().await;
-                    let config = mgr.load().await.map_err(|e| e.to_string())?;
-                    config.system.github_token.clone()
-                };
-
-                if let Some(token) = github_token {
+                if backend.can_publish() {
                     match invite::generate_shadow_invite(
                         &password,
                         &inviter,
                         &my_username,
                         &my_address,
                         "pending",
+                        my_keys,
                     ) {
                         Ok(shadow) => {
-                            if let Err(e) = gist::publish_shadow_invite(&token, shadow).await {
+                            if let Err(e) = gist::publish_shadow_invite(&backend, shadow).await {
                                 eprintln!("[Shadow] Failed to publish: {}", e);
+                                gist::report_auth_failure(&handle, &e);
                             } else {
                                 println!("[Shadow] ✅ Published to Gist for {}", inviter);
 
@@ -466,17 +561,102 @@ pub async fn redeem_and_connect(
 
             Ok(chat_id)
         }
-        None => Err("No valid invitation found for you. Check password and usernames.".to_string()),
+        None => Err(RChatError::NotFound(
+            "No valid invitation found for you. Check password and usernames.".to_string(),
+        )),
     }
 }
 
+/// Friend requests waiting for the user, oldest first.
+#[tauri::command]
+pub async fn get_friend_requests(
+    net_state: State<'_, NetworkState>,
+) -> Result<Vec<FriendRequest>, RChatError> {
+    let mut requests: Vec<FriendRequest> = net_state
+        .friend_requests
+        .lock()
+        .await
+        .values()
+        .cloned()
+        .collect();
+    requests.sort_by_key(|request| request.received_at);
+    Ok(requests)
+}
+
+/// Add the requester to our friends and open a chat with them.
+#[tauri::command]
+pub async fn accept_friend_request(
+    peer_id: String,
+    app_state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<String, RChatError> {
+    use crate::storage::config::FriendConfig;
+
+    let request = net_state
+        .friend_requests
+        .lock()
+        .await
+        .remove(&peer_id)
+        .ok_or_else(|| {
+            RChatError::NotFound("No pending friend request from this peer".to_string())
+        })?;
+
+    {
+        let mgr = app_state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        if !config
+            .user
+            .friends
+            .iter()
+            .any(|f| f.username == request.github_username)
+        {
+            config.user.friends.push(FriendConfig {
+                username: request.github_username.clone(),
+                alias: None,
+                x25519_pubkey: None,
+                ed25519_pubkey: None,
+                leaf_index: 0,
+                encrypted_leaf_key: None,
+                nonce: None,
+            });
+            mgr.save(&config).await?;
+        }
+    }
+
+    let tx = net_state.sender.lock().await;
+    tx.send(NetworkCommand::AcceptFriendRequest {
+        github_username: request.github_username.clone(),
+        peer_id: request.peer_id.clone(),
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    println!(
+        "[Backend] Accepted friend request from {}",
+        request.github_username
+    );
+    Ok(crate::chat_identity::build_github_chat_id(
+        &request.github_username,
+        &request.peer_id,
+    ))
+}
+
+#[tauri::command]
+pub async fn decline_friend_request(
+    peer_id: String,
+    net_state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
+    net_state.friend_requests.lock().await.remove(&peer_id);
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn create_temporary_invite(
     kind: String,
     name: Option<String>,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<TemporaryInviteView, String> {
+) -> Result<TemporaryInviteView, RChatError> {
     let temp_kind = parse_temp_kind(&kind)?;
     let chat_id = match temp_kind {
         TemporaryChatKind::Dm => crate::chat_kind::generate_temp_direct_chat_id(),
@@ -497,11 +677,11 @@ pub async fn create_temporary_invite(
         .lock()
         .await
         .clone()
-        .ok_or("Network is not started yet")?;
+        .ok_or_else(|| RChatError::PeerOffline("Network is not started yet".to_string()))?;
     let inviter_addr = resolve_current_public_address(&net_state).await?;
     let inviter_username = {
         let mgr = app_state.config_manager.lock().await;
-        let config = mgr.load().await.map_err(|e| e.to_string())?;
+        let config = mgr.load().await?;
         config
             .system
             .github_username
@@ -555,7 +735,7 @@ pub async fn create_temporary_invite(
 #[tauri::command]
 pub async fn get_active_temporary_invite(
     net_state: State<'_, NetworkState>,
-) -> Result<Option<TemporaryInviteView>, String> {
+) -> Result<Option<TemporaryInviteView>, RChatError> {
     let now = now_unix_secs();
     let mut temp_state = net_state.temporary_state.lock().await;
 
@@ -577,7 +757,7 @@ pub async fn get_active_temporary_invite(
 }
 
 #[tauri::command]
-pub async fn cancel_temporary_invite(net_state: State<'_, NetworkState>) -> Result<(), String> {
+pub async fn cancel_temporary_invite(net_state: State<'_, NetworkState>) -> Result<(), RChatError> {
     let mut temp_state = net_state.temporary_state.lock().await;
     if let Some(active) = temp_state.active_invite.take() {
         if let Some(session) = temp_state.chats.get(&active.payload.chat_id).cloned() {
@@ -599,31 +779,39 @@ pub async fn cancel_temporary_invite(net_state: State<'_, NetworkState>) -> Resu
 pub async fn redeem_temporary_invite(
     deep_link: String,
     net_state: State<'_, NetworkState>,
-) -> Result<TemporaryChatResult, String> {
+) -> Result<TemporaryChatResult, RChatError> {
     let token = extract_temporary_payload_token(&deep_link)?;
     let payload = decode_temporary_payload(&token)?;
     if payload.version != TEMP_INVITE_VERSION {
-        return Err(format!(
+        return Err(RChatError::InvalidInput(format!(
             "Unsupported temporary invite version: {}",
             payload.version
-        ));
+        )));
     }
 
     let now = now_unix_secs();
     if payload.expires_at <= now {
-        return Err("Temporary invite has expired".to_string());
+        return Err(RChatError::InvalidInput(
+            "Temporary invite has expired".to_string(),
+        ));
     }
 
     let mut temp_state = net_state.temporary_state.lock().await;
     let Some(local_active) = temp_state.active_invite.clone() else {
-        return Err("Create a temporary invite first before redeeming one".to_string());
+        return Err(RChatError::InvalidInput(
+            "Create a temporary invite first before redeeming one".to_string(),
+        ));
     };
     if local_active.payload.expires_at <= now {
         temp_state.active_invite = None;
-        return Err("Your temporary invite has expired. Create a new one first".to_string());
+        return Err(RChatError::InvalidInput(
+            "Your temporary invite has expired. Create a new one first".to_string(),
+        ));
     }
     if local_active.payload.kind != payload.kind {
-        return Err("Temporary invite kind mismatch (dm/group)".to_string());
+        return Err(RChatError::InvalidInput(
+            "Temporary invite kind mismatch (dm/group)".to_string(),
+        ));
     }
 
     let is_group = matches!(payload.kind, TemporaryChatKind::Group);
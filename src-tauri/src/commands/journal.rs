@@ -0,0 +1,38 @@
+use tauri::State;
+
+use crate::error::RChatError;
+use crate::storage;
+use crate::AppState;
+
+#[tauri::command]
+pub async fn set_chat_journaling(
+    chat_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    println!(
+        "[Backend] set_chat_journaling: chat_id={}, enabled={}",
+        chat_id, enabled
+    );
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::set_chat_journaling(&conn, &chat_id, enabled).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn get_journaled_chats(state: State<'_, AppState>) -> Result<Vec<String>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::list_journaled_chats(&conn).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn list_journal_days(state: State<'_, AppState>) -> Result<Vec<String>, RChatError> {
+    state.journal.list_days().map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn read_journal_day(
+    day: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::journal::JournalEntry>, RChatError> {
+    state.journal.read_day(&day).map_err(RChatError::from)
+}
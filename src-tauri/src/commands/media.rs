@@ -1,6 +1,7 @@
 use tauri::State;
 
 use crate::chat_kind::{self, ChatKind};
+use crate::error::RChatError;
 use crate::network::command::{DirectMediaKind, NetworkCommand};
 use crate::network::gossip::{GroupContentType, GroupMessageEnvelope};
 use crate::storage;
@@ -13,6 +14,7 @@ use std::path::Path;
 const MAX_STICKER_SIZE_BYTES: usize = 1_000_000; // 1 MB
 const MIN_STICKER_SIDE_PX: u32 = 96;
 const MAX_STICKER_SCALE_ATTEMPTS: u32 = 16;
+const MAX_STREAM_READ_BYTES: u64 = 8 * 1024 * 1024; // 8 MB
 
 #[derive(serde::Serialize)]
 pub struct SentMediaResult {
@@ -50,6 +52,13 @@ struct PreparedSticker {
     converted: bool,
 }
 
+fn now_unix_ts() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
 fn encode_webp_lossless(image: &DynamicImage) -> Result<Vec<u8>, String> {
     let rgba = image.to_rgba8();
     let (width, height) = rgba.dimensions();
@@ -138,7 +147,7 @@ fn prepare_sticker_for_import(file_path: &str) -> Result<PreparedSticker, String
     }
 }
 
-fn detect_audio_mime(file_path: &str) -> Option<&'static str> {
+pub(crate) fn detect_audio_mime(file_path: &str) -> Option<&'static str> {
     match file_path
         .rsplit('.')
         .next()
@@ -164,7 +173,7 @@ fn detect_image_mime_from_bytes(data: &[u8]) -> Option<&'static str> {
     }
 }
 
-fn detect_audio_mime_from_bytes(data: &[u8]) -> Option<&'static str> {
+pub(crate) fn detect_audio_mime_from_bytes(data: &[u8]) -> Option<&'static str> {
     if data.len() >= 12 {
         if &data[0..4] == b"RIFF" && &data[8..12] == b"WAVE" {
             return Some("audio/wav");
@@ -317,11 +326,13 @@ pub async fn send_image_message(
     file_path: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<SentMediaResult, String> {
+) -> Result<SentMediaResult, RChatError> {
     println!(
         "[Backend] send_image_message: to {} from {}",
-        peer_id, file_path
+        crate::logging::peer(&peer_id),
+        file_path
     );
+    storage::disk::ensure_media_writable()?;
     let canonical_peer_id = canonical_direct_chat_id(&app_state, &peer_id).await;
 
     let file_data = std::fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
@@ -360,8 +371,7 @@ pub async fn send_image_message(
         .unwrap()
         .as_secs() as i64;
 
-    let id_suffix: u32 = rand::random();
-    let msg_id = format!("{}-{}", timestamp, id_suffix);
+    let msg_id = crate::chat::message_id::new_message_id();
 
     let chat_kind = chat_kind::parse_chat_kind(&canonical_peer_id);
     let is_temporary = matches!(
@@ -379,12 +389,15 @@ pub async fn send_image_message(
         chat_id: chat_id.clone(),
         peer_id: "Me".to_string(),
         timestamp,
-        content_type: "image".to_string(),
-        text_content: None,
-        file_hash: Some(file_hash.clone()),
+        content: crate::chat::message::MessageContent::Photo {
+            file_hash: file_hash.clone(),
+            caption: None,
+            metadata: Default::default(),
+        },
         status: status.to_string(),
         content_metadata: None,
         sender_alias: None,
+        logical: 0,
     };
 
     if is_temporary {
@@ -394,8 +407,9 @@ pub async fn send_image_message(
         ensure_persisted_outgoing_chat(&conn, chat_kind, &canonical_peer_id)?;
         if let Err(e) = storage::db::insert_message(&conn, &message) {
             eprintln!("[Backend] Failed to save image message: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
+        storage::journal::record_message(&app_state.journal, &conn, &message);
     }
 
     if !matches!(chat_kind, ChatKind::SelfChat) {
@@ -426,6 +440,10 @@ pub async fn send_image_message(
                     content_type: GroupContentType::Image,
                     text_content: None,
                     file_hash: Some(file_hash.clone()),
+                    signature: None,
+                    logical: None,
+                    bridged: None,
+                    content: None,
                 };
                 tx.send(NetworkCommand::PublishGroup { envelope })
                     .await
@@ -436,6 +454,7 @@ pub async fn send_image_message(
     }
 
     println!("[Backend] Image message sent: hash={}", file_hash);
+    storage::metrics::record(storage::metrics::MESSAGES_SENT);
     Ok(SentMediaResult {
         msg_id,
         file_hash,
@@ -447,7 +466,7 @@ pub async fn send_image_message(
 pub async fn get_image_data(
     file_hash: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
 
     let data = storage::object::load(&conn, &file_hash, None)
@@ -482,7 +501,7 @@ pub async fn get_image_data(
 }
 
 #[tauri::command]
-pub async fn get_image_from_path(file_path: String) -> Result<String, String> {
+pub async fn get_image_from_path(file_path: String) -> Result<String, RChatError> {
     let data =
         std::fs::read(&file_path).map_err(|e| format!("Failed to read image file: {}", e))?;
 
@@ -510,8 +529,9 @@ pub async fn save_image_to_file(
     file_hash: String,
     target_path: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    crate::chat::media_guard::ensure_exportable(&conn, &file_hash, now_unix_ts())?;
 
     let data = storage::object::load(&conn, &file_hash, None)
         .map_err(|e| format!("Failed to load image: {}", e))?;
@@ -528,8 +548,13 @@ pub async fn send_document_message(
     file_path: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<SentMediaResult, String> {
-    println!("[Backend] Sending document to {}: {}", peer_id, file_path);
+) -> Result<SentMediaResult, RChatError> {
+    println!(
+        "[Backend] Sending document to {}: {}",
+        crate::logging::peer(&peer_id),
+        file_path
+    );
+    storage::disk::ensure_media_writable()?;
     let canonical_peer_id = canonical_direct_chat_id(&app_state, &peer_id).await;
     let chat_kind = chat_kind::parse_chat_kind(&canonical_peer_id);
 
@@ -552,6 +577,7 @@ pub async fn send_document_message(
         Some("ppt") => "application/vnd.ms-powerpoint",
         Some("pptx") => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
         Some("csv") => "text/csv",
+        Some(storage::theme::THEME_FILE_EXTENSION) => storage::theme::THEME_FILE_MIME,
         _ => "application/octet-stream",
     };
 
@@ -566,8 +592,7 @@ pub async fn send_document_message(
         .unwrap()
         .as_secs() as i64;
 
-    let id_suffix: u32 = rand::random();
-    let msg_id = format!("{}-{}", timestamp, id_suffix);
+    let msg_id = crate::chat::message_id::new_message_id();
 
     let is_temporary = matches!(
         chat_kind,
@@ -584,12 +609,18 @@ pub async fn send_document_message(
         chat_id: chat_id.clone(),
         peer_id: "Me".to_string(),
         timestamp,
-        content_type: "document".to_string(),
-        text_content: Some(file_name.clone()),
-        file_hash: Some(file_hash.clone()),
+        content: crate::chat::message::MessageContent::Document {
+            file_hash: file_hash.clone(),
+            file_name: file_name.clone(),
+            metadata: crate::chat::message::ContentMetadata {
+                size_bytes: Some(file_data.len() as i64),
+                ..Default::default()
+            },
+        },
         status: status.to_string(),
         content_metadata: Some(format!("{{\"size_bytes\":{}}}", file_data.len())),
         sender_alias: None,
+        logical: 0,
     };
 
     if is_temporary {
@@ -600,8 +631,9 @@ pub async fn send_document_message(
 
         if let Err(e) = storage::db::insert_message(&conn, &message) {
             eprintln!("[Backend] Failed to save document message: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
+        storage::journal::record_message(&app_state.journal, &conn, &message);
     }
 
     if !matches!(chat_kind, ChatKind::SelfChat) {
@@ -632,6 +664,10 @@ pub async fn send_document_message(
                     content_type: GroupContentType::Document,
                     text_content: Some(file_name.clone()),
                     file_hash: Some(file_hash.clone()),
+                    signature: None,
+                    logical: None,
+                    bridged: None,
+                    content: None,
                 };
                 tx.send(NetworkCommand::PublishGroup { envelope })
                     .await
@@ -645,6 +681,7 @@ pub async fn send_document_message(
         "[Backend] Document message sent: hash={}, name={}",
         file_hash, file_name
     );
+    storage::metrics::record(storage::metrics::MESSAGES_SENT);
     Ok(SentMediaResult {
         msg_id,
         file_hash,
@@ -657,8 +694,9 @@ pub async fn save_document_to_file(
     file_hash: String,
     target_path: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    crate::chat::media_guard::ensure_exportable(&conn, &file_hash, now_unix_ts())?;
 
     let data = storage::object::load(&conn, &file_hash, None)
         .map_err(|e| format!("Failed to load document: {}", e))?;
@@ -675,8 +713,13 @@ pub async fn send_video_message(
     file_path: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<SentMediaResult, String> {
-    println!("[Backend] Sending video to {}: {}", peer_id, file_path);
+) -> Result<SentMediaResult, RChatError> {
+    println!(
+        "[Backend] Sending video to {}: {}",
+        crate::logging::peer(&peer_id),
+        file_path
+    );
+    storage::disk::ensure_media_writable()?;
     let canonical_peer_id = canonical_direct_chat_id(&app_state, &peer_id).await;
     let chat_kind = chat_kind::parse_chat_kind(&canonical_peer_id);
 
@@ -698,10 +741,23 @@ pub async fn send_video_message(
         _ => "video/mp4",
     };
 
-    let file_hash = {
+    let poster = crate::chat::message::video_poster(Path::new(&file_path));
+    let (file_hash, thumbnail_hash) = {
         let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
-        storage::object::create(&conn, &file_data, Some(&file_name), Some(mime_type), None)
-            .map_err(|e| format!("Failed to store video: {}", e))?
+        let file_hash =
+            storage::object::create(&conn, &file_data, Some(&file_name), Some(mime_type), None)
+                .map_err(|e| format!("Failed to store video: {}", e))?;
+        let thumbnail_hash = poster.and_then(|poster| {
+            storage::object::create(&conn, &poster, Some("poster.jpg"), Some("image/jpeg"), None)
+                .map_err(|e| eprintln!("[Backend] Failed to store video poster: {}", e))
+                .ok()
+        });
+        (file_hash, thumbnail_hash)
+    };
+    let metadata = crate::chat::message::ContentMetadata {
+        size_bytes: Some(file_data.len() as i64),
+        thumbnail_hash,
+        ..Default::default()
     };
 
     let timestamp = std::time::SystemTime::now()
@@ -709,8 +765,7 @@ pub async fn send_video_message(
         .unwrap()
         .as_secs() as i64;
 
-    let id_suffix: u32 = rand::random();
-    let msg_id = format!("{}-{}", timestamp, id_suffix);
+    let msg_id = crate::chat::message_id::new_message_id();
 
     let is_temporary = matches!(
         chat_kind,
@@ -727,12 +782,15 @@ pub async fn send_video_message(
         chat_id: chat_id.clone(),
         peer_id: "Me".to_string(),
         timestamp,
-        content_type: "video".to_string(),
-        text_content: Some(file_name.clone()),
-        file_hash: Some(file_hash.clone()),
+        content: crate::chat::message::MessageContent::Video {
+            file_hash: file_hash.clone(),
+            file_name: file_name.clone(),
+            metadata: metadata.clone(),
+        },
         status: status.to_string(),
-        content_metadata: Some(format!("{{\"size_bytes\":{}}}", file_data.len())),
+        content_metadata: serde_json::to_string(&metadata).ok(),
         sender_alias: None,
+        logical: 0,
     };
 
     if is_temporary {
@@ -743,8 +801,9 @@ pub async fn send_video_message(
 
         if let Err(e) = storage::db::insert_message(&conn, &message) {
             eprintln!("[Backend] Failed to save video message: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
+        storage::journal::record_message(&app_state.journal, &conn, &message);
     }
 
     if !matches!(chat_kind, ChatKind::SelfChat) {
@@ -775,6 +834,10 @@ pub async fn send_video_message(
                     content_type: GroupContentType::Video,
                     text_content: Some(file_name.clone()),
                     file_hash: Some(file_hash.clone()),
+                    signature: None,
+                    logical: None,
+                    bridged: None,
+                    content: None,
                 };
                 tx.send(NetworkCommand::PublishGroup { envelope })
                     .await
@@ -788,6 +851,7 @@ pub async fn send_video_message(
         "[Backend] Video message sent: hash={}, name={}",
         file_hash, file_name
     );
+    storage::metrics::record(storage::metrics::MESSAGES_SENT);
     Ok(SentMediaResult {
         msg_id,
         file_hash,
@@ -799,7 +863,7 @@ pub async fn send_video_message(
 pub async fn get_video_data(
     file_hash: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
 
     let data = storage::object::load(&conn, &file_hash, None)
@@ -820,14 +884,42 @@ pub async fn get_video_data(
     Ok(data_url)
 }
 
+/// Raw bytes `offset..offset + len` of a stored file, for streaming playback
+/// of videos without loading and base64-encoding the whole file. Reads are
+/// capped at [`MAX_STREAM_READ_BYTES`]; a short read means the end of the file.
+/// Ranges whose chunks are still being downloaded fail and can be retried.
+#[tauri::command]
+pub async fn get_file_stream(
+    file_hash: String,
+    offset: u64,
+    len: u64,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let data = storage::object::load_range(
+        &conn,
+        &file_hash,
+        offset,
+        len.min(MAX_STREAM_READ_BYTES),
+        None,
+    )
+    .map_err(|e| format!("Failed to read file range: {}", e))?;
+    Ok(tauri::ipc::Response::new(data))
+}
+
 #[tauri::command]
 pub async fn send_audio_message(
     peer_id: String,
     file_path: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<SentMediaResult, String> {
-    println!("[Backend] Sending audio to {}: {}", peer_id, file_path);
+) -> Result<SentMediaResult, RChatError> {
+    println!(
+        "[Backend] Sending audio to {}: {}",
+        crate::logging::peer(&peer_id),
+        file_path
+    );
+    storage::disk::ensure_media_writable()?;
     let canonical_peer_id = canonical_direct_chat_id(&app_state, &peer_id).await;
     let chat_kind = chat_kind::parse_chat_kind(&canonical_peer_id);
 
@@ -841,7 +933,9 @@ pub async fn send_audio_message(
         .unwrap_or_else(|| "audio".to_string());
 
     let mime_type = detect_audio_mime(&file_path).ok_or_else(|| {
-        "Unsupported audio format. Allowed: mp3, m4a, wav, ogg, webm, opus".to_string()
+        RChatError::InvalidInput(
+            "Unsupported audio format. Allowed: mp3, m4a, wav, ogg, webm, opus".to_string(),
+        )
     })?;
 
     let file_hash = {
@@ -855,8 +949,7 @@ pub async fn send_audio_message(
         .unwrap()
         .as_secs() as i64;
 
-    let id_suffix: u32 = rand::random();
-    let msg_id = format!("{}-{}", timestamp, id_suffix);
+    let msg_id = crate::chat::message_id::new_message_id();
 
     let is_temporary = matches!(
         chat_kind,
@@ -873,12 +966,18 @@ pub async fn send_audio_message(
         chat_id: chat_id.clone(),
         peer_id: "Me".to_string(),
         timestamp,
-        content_type: "audio".to_string(),
-        text_content: Some(file_name.clone()),
-        file_hash: Some(file_hash.clone()),
+        content: crate::chat::message::MessageContent::Audio {
+            file_hash: file_hash.clone(),
+            file_name: file_name.clone(),
+            metadata: crate::chat::message::ContentMetadata {
+                size_bytes: Some(file_data.len() as i64),
+                ..Default::default()
+            },
+        },
         status: status.to_string(),
         content_metadata: Some(format!("{{\"size_bytes\":{}}}", file_data.len())),
         sender_alias: None,
+        logical: 0,
     };
 
     if is_temporary {
@@ -889,8 +988,9 @@ pub async fn send_audio_message(
 
         if let Err(e) = storage::db::insert_message(&conn, &message) {
             eprintln!("[Backend] Failed to save audio message: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
+        storage::journal::record_message(&app_state.journal, &conn, &message);
     }
 
     if !matches!(chat_kind, ChatKind::SelfChat) {
@@ -921,6 +1021,10 @@ pub async fn send_audio_message(
                     content_type: GroupContentType::Audio,
                     text_content: Some(file_name.clone()),
                     file_hash: Some(file_hash.clone()),
+                    signature: None,
+                    logical: None,
+                    bridged: None,
+                    content: None,
                 };
                 tx.send(NetworkCommand::PublishGroup { envelope })
                     .await
@@ -934,6 +1038,7 @@ pub async fn send_audio_message(
         "[Backend] Audio message sent: hash={}, name={}",
         file_hash, file_name
     );
+    storage::metrics::record(storage::metrics::MESSAGES_SENT);
     Ok(SentMediaResult {
         msg_id,
         file_hash,
@@ -945,7 +1050,7 @@ pub async fn send_audio_message(
 pub async fn get_audio_data(
     file_hash: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
 
     let data = storage::object::load(&conn, &file_hash, None)
@@ -984,8 +1089,9 @@ pub async fn save_audio_to_file(
     file_hash: String,
     target_path: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    crate::chat::media_guard::ensure_exportable(&conn, &file_hash, now_unix_ts())?;
 
     let data = storage::object::load(&conn, &file_hash, None)
         .map_err(|e| format!("Failed to load audio: {}", e))?;
@@ -999,16 +1105,16 @@ pub async fn save_audio_to_file(
 #[tauri::command]
 pub async fn list_stickers(
     state: State<'_, AppState>,
-) -> Result<Vec<storage::db::Sticker>, String> {
+) -> Result<Vec<storage::db::Sticker>, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::list_stickers(&conn).map_err(|e| e.to_string())
+    storage::db::list_stickers(&conn).map_err(RChatError::from)
 }
 
 #[tauri::command]
 pub async fn add_sticker(
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<AddStickerResult, String> {
+) -> Result<AddStickerResult, RChatError> {
     let prepared = prepare_sticker_for_import(&file_path)?;
 
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
@@ -1037,7 +1143,7 @@ pub async fn add_sticker(
 pub async fn add_stickers_batch(
     file_paths: Vec<String>,
     state: State<'_, AppState>,
-) -> Result<StickerBatchImportResult, String> {
+) -> Result<StickerBatchImportResult, RChatError> {
     let mut results = Vec::with_capacity(file_paths.len());
     let mut success_count = 0usize;
     let mut failure_count = 0usize;
@@ -1107,16 +1213,19 @@ pub async fn add_stickers_batch(
 }
 
 #[tauri::command]
-pub async fn delete_sticker(file_hash: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_sticker(
+    file_hash: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::delete_sticker(&conn, &file_hash).map_err(|e| e.to_string())
+    storage::db::delete_sticker(&conn, &file_hash).map_err(RChatError::from)
 }
 
 #[tauri::command]
 pub async fn save_sticker_from_message(
     file_hash: String,
     state: State<'_, AppState>,
-) -> Result<AddStickerResult, String> {
+) -> Result<AddStickerResult, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
 
     let exists_in_files: bool = conn
@@ -1128,7 +1237,9 @@ pub async fn save_sticker_from_message(
         .map_err(|e| format!("Failed to check sticker file: {}", e))?;
 
     if !exists_in_files {
-        return Err("Sticker file is not available locally yet".to_string());
+        return Err(RChatError::NotFound(
+            "Sticker file is not available locally yet".to_string(),
+        ));
     }
 
     let name: String = conn
@@ -1159,7 +1270,7 @@ pub async fn send_sticker_message(
     file_hash: String,
     app_state: State<'_, AppState>,
     net_state: State<'_, NetworkState>,
-) -> Result<SentMediaResult, String> {
+) -> Result<SentMediaResult, RChatError> {
     let canonical_peer_id = canonical_direct_chat_id(&app_state, &peer_id).await;
     let chat_kind = chat_kind::parse_chat_kind(&canonical_peer_id);
 
@@ -1167,8 +1278,7 @@ pub async fn send_sticker_message(
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_secs() as i64;
-    let id_suffix: u32 = rand::random();
-    let msg_id = format!("{}-{}", timestamp, id_suffix);
+    let msg_id = crate::chat::message_id::new_message_id();
     let is_temporary = matches!(
         chat_kind,
         ChatKind::TemporaryDirect | ChatKind::TemporaryGroup
@@ -1179,7 +1289,9 @@ pub async fn send_sticker_message(
         let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
 
         if !storage::db::sticker_exists(&conn, &file_hash) {
-            return Err("Sticker not found in local library".to_string());
+            return Err(RChatError::NotFound(
+                "Sticker not found in local library".to_string(),
+            ));
         }
 
         let file_exists: bool = conn
@@ -1190,7 +1302,9 @@ pub async fn send_sticker_message(
             )
             .map_err(|e| format!("Failed to check sticker file: {}", e))?;
         if !file_exists {
-            return Err("Sticker file is missing from local storage".to_string());
+            return Err(RChatError::NotFound(
+                "Sticker file is missing from local storage".to_string(),
+            ));
         }
 
         let file_name: Option<String> = conn
@@ -1219,12 +1333,14 @@ pub async fn send_sticker_message(
         chat_id: chat_id.clone(),
         peer_id: "Me".to_string(),
         timestamp,
-        content_type: "sticker".to_string(),
-        text_content: None,
-        file_hash: Some(file_hash.clone()),
+        content: crate::chat::message::MessageContent::Sticker {
+            file_hash: file_hash.clone(),
+            metadata: Default::default(),
+        },
         status: status.to_string(),
         content_metadata: None,
         sender_alias: None,
+        logical: 0,
     };
 
     if is_temporary {
@@ -1233,6 +1349,7 @@ pub async fn send_sticker_message(
         let conn = app_state.db_conn.lock().map_err(|e| e.to_string())?;
         storage::db::insert_message(&conn, &message)
             .map_err(|e| format!("Failed to save sticker message: {}", e))?;
+        storage::journal::record_message(&app_state.journal, &conn, &message);
     }
 
     if !matches!(chat_kind, ChatKind::SelfChat) {
@@ -1263,6 +1380,10 @@ pub async fn send_sticker_message(
                     content_type: GroupContentType::Sticker,
                     text_content: None,
                     file_hash: Some(file_hash.clone()),
+                    signature: None,
+                    logical: None,
+                    bridged: None,
+                    content: None,
                 };
                 tx.send(NetworkCommand::PublishGroup { envelope })
                     .await
@@ -1272,6 +1393,7 @@ pub async fn send_sticker_message(
         }
     }
 
+    storage::metrics::record(storage::metrics::MESSAGES_SENT);
     Ok(SentMediaResult {
         msg_id,
         file_hash,
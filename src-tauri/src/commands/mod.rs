@@ -1,10 +1,24 @@
 pub mod auth;
+pub mod bot_api;
+pub mod bridge;
 pub mod call;
 pub mod chat;
 pub mod chat_details;
+pub mod chat_settings;
 pub mod debug;
+pub mod drafts;
 pub mod envelopes;
+pub mod group_admin;
+pub mod group_history;
+pub mod group_invite;
+pub mod http_api;
+pub mod import;
 pub mod invite;
+pub mod journal;
 pub mod media;
 pub mod network_control;
+pub mod notes;
 pub mod peer_profile;
+pub mod settings;
+pub mod storage;
+pub mod suggestions;
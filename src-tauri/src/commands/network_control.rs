@@ -1,5 +1,6 @@
 use tauri::State;
 
+use crate::error::RChatError;
 use crate::network;
 use crate::network::command::NetworkCommand;
 use crate::NetworkState;
@@ -9,14 +10,17 @@ use crate::NetworkState;
 pub async fn request_connection(
     peer_id: String,
     state: State<'_, NetworkState>,
-) -> Result<(), String> {
-    println!("[Backend] request_connection called for: {}", peer_id);
+) -> Result<(), RChatError> {
+    println!(
+        "[Backend] request_connection called for: {}",
+        crate::logging::peer(&peer_id)
+    );
 
     let sender = state.sender.lock().await;
     sender
         .send(NetworkCommand::RequestConnection { peer_id })
         .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
+        .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
 
     Ok(())
 }
@@ -30,3 +34,132 @@ pub fn set_fast_discovery(enabled: bool) {
         network::mdns::disable_fast_discovery();
     }
 }
+
+/// Snapshot of listeners, STUN results, peer/mesh counts and recent dial failures
+#[tauri::command]
+pub async fn get_network_diagnostics(
+    state: State<'_, NetworkState>,
+) -> Result<network::diagnostics::NetworkDiagnostics, RChatError> {
+    Ok(network::diagnostics::snapshot(&state).await)
+}
+
+/// Which path (LAN, direct, relay) the connection to `peer_id` currently uses
+#[tauri::command]
+pub async fn get_connection_path(
+    peer_id: String,
+    state: State<'_, NetworkState>,
+) -> Result<network::path::PathReport, RChatError> {
+    let peer_id = peer_id
+        .parse::<libp2p::PeerId>()
+        .map_err(|e| RChatError::InvalidInput(format!("Invalid peer id: {}", e)))?;
+    let (reply, rx) = tokio::sync::oneshot::channel();
+    {
+        let sender = state.sender.lock().await;
+        sender
+            .send(NetworkCommand::GetConnectionPath { peer_id, reply })
+            .await
+            .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    }
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), rx)
+        .await
+        .map_err(|_| RChatError::Network("Timed out waiting for the network manager".to_string()))?
+        .map_err(|_| RChatError::Network("Network manager dropped the request".to_string()))
+}
+
+/// Dial every address we know for `peer_id` (stored, mDNS, relay) and report
+/// which ones connect and how fast
+#[tauri::command]
+pub async fn test_peer_connection(
+    peer_id: String,
+    state: State<'_, NetworkState>,
+) -> Result<network::probe::PeerConnectionTest, RChatError> {
+    let peer_id = peer_id
+        .parse::<libp2p::PeerId>()
+        .map_err(|e| RChatError::InvalidInput(format!("Invalid peer id: {}", e)))?;
+    let (reply, rx) = tokio::sync::oneshot::channel();
+    {
+        let sender = state.sender.lock().await;
+        sender
+            .send(NetworkCommand::TestPeerConnection { peer_id, reply })
+            .await
+            .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    }
+
+    // Unfinished dials are reported on the manager heartbeat after PROBE_TIMEOUT.
+    let wait = network::probe::PROBE_TIMEOUT + std::time::Duration::from_secs(15);
+    tokio::time::timeout(wait, rx)
+        .await
+        .map_err(|_| RChatError::Network("Timed out waiting for the network manager".to_string()))?
+        .map_err(|_| RChatError::Network("Network manager dropped the request".to_string()))
+}
+
+/// Current mDNS-discovered peers, queried from the network manager on demand
+#[tauri::command]
+pub async fn get_local_peers(
+    state: State<'_, NetworkState>,
+) -> Result<Vec<network::command::LocalPeerInfo>, RChatError> {
+    let (reply, rx) = tokio::sync::oneshot::channel();
+    {
+        let sender = state.sender.lock().await;
+        sender
+            .send(NetworkCommand::GetLocalPeers { reply })
+            .await
+            .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    }
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), rx)
+        .await
+        .map_err(|_| RChatError::Network("Timed out waiting for the network manager".to_string()))?
+        .map_err(|_| RChatError::Network("Network manager dropped the request".to_string()))
+}
+
+/// Round-trip time, jitter and ping loss to `peer_id` over the recent pings;
+/// `None` until a ping completed
+#[tauri::command]
+pub async fn get_peer_connection_quality(
+    peer_id: String,
+    state: State<'_, NetworkState>,
+) -> Result<Option<network::quality::PeerQuality>, RChatError> {
+    let peer_id = peer_id
+        .parse::<libp2p::PeerId>()
+        .map_err(|e| RChatError::InvalidInput(format!("Invalid peer id: {}", e)))?;
+    let (reply, rx) = tokio::sync::oneshot::channel();
+    {
+        let sender = state.sender.lock().await;
+        sender
+            .send(NetworkCommand::GetPeerQuality { peer_id, reply })
+            .await
+            .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    }
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), rx)
+        .await
+        .map_err(|_| RChatError::Network("Timed out waiting for the network manager".to_string()))?
+        .map_err(|_| RChatError::Network("Network manager dropped the request".to_string()))
+}
+
+/// Optional features `peer_id` advertised (e2ee-v2, blob-stream, ...);
+/// `None` until the peer was identified
+#[tauri::command]
+pub async fn get_peer_capabilities(
+    peer_id: String,
+    state: State<'_, NetworkState>,
+) -> Result<Option<Vec<network::capabilities::Capability>>, RChatError> {
+    let peer_id = peer_id
+        .parse::<libp2p::PeerId>()
+        .map_err(|e| RChatError::InvalidInput(format!("Invalid peer id: {}", e)))?;
+    let (reply, rx) = tokio::sync::oneshot::channel();
+    {
+        let sender = state.sender.lock().await;
+        sender
+            .send(NetworkCommand::GetPeerCapabilities { peer_id, reply })
+            .await
+            .map_err(|e| RChatError::Network(format!("Failed to send request: {}", e)))?;
+    }
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), rx)
+        .await
+        .map_err(|_| RChatError::Network("Timed out waiting for the network manager".to_string()))?
+        .map_err(|_| RChatError::Network("Network manager dropped the request".to_string()))
+}
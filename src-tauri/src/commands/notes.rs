@@ -0,0 +1,111 @@
+use tauri::{Emitter, State};
+
+use crate::error::RChatError;
+use crate::storage;
+use crate::AppState;
+
+fn now_unix_ts() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Add a task item to Note to Self.
+#[tauri::command]
+pub async fn add_self_task(
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<storage::db::Message, RChatError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(RChatError::InvalidInput(
+            "Task text is required".to_string(),
+        ));
+    }
+    storage::disk::ensure_db_writable()?;
+
+    let timestamp = now_unix_ts();
+    let msg = storage::db::Message {
+        id: crate::chat::message_id::new_message_id(),
+        chat_id: "self".to_string(),
+        peer_id: "Me".to_string(),
+        timestamp,
+        content: crate::chat::message::MessageContent::Task {
+            text: text.to_string(),
+        },
+        status: "read".to_string(),
+        content_metadata: None,
+        sender_alias: None,
+        logical: 0,
+    };
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::insert_self_task(&conn, &msg)?;
+    storage::journal::record_message(&state.journal, &conn, &msg);
+    Ok(msg)
+}
+
+#[tauri::command]
+pub async fn set_task_done(
+    message_id: String,
+    done: bool,
+    app_handle: crate::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let updated = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::set_task_done(&conn, &message_id, done, now_unix_ts())?
+    };
+    if !updated {
+        return Err(RChatError::InvalidInput(format!(
+            "Not a task: {}",
+            message_id
+        )));
+    }
+    let _ = app_handle.emit(
+        "task-updated",
+        serde_json::json!({ "message_id": message_id, "done": done }),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_self_tasks(
+    include_done: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::SelfTask>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::get_self_tasks(&conn, include_done.unwrap_or(true)).map_err(RChatError::from)
+}
+
+/// Schedule a reminder for a Note to Self message at `remind_at` (unix secs).
+/// A time in the past fires on the next scheduler tick.
+#[tauri::command]
+pub async fn schedule_reminder(
+    message_id: String,
+    remind_at: i64,
+    state: State<'_, AppState>,
+) -> Result<i64, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::add_reminder(&conn, &message_id, remind_at, now_unix_ts())?.ok_or_else(|| {
+        RChatError::InvalidInput("Reminders can only be set on Note to Self messages".to_string())
+    })
+}
+
+#[tauri::command]
+pub async fn cancel_reminder(
+    reminder_id: i64,
+    state: State<'_, AppState>,
+) -> Result<bool, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::cancel_reminder(&conn, reminder_id).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn get_reminders(
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::db::Reminder>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::get_pending_reminders(&conn).map_err(RChatError::from)
+}
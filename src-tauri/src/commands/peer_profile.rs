@@ -1,9 +1,11 @@
 use rand::RngCore;
 use tauri::State;
 
+use crate::error::RChatError;
+use crate::network::command::NetworkCommand;
 use crate::storage;
 use crate::storage::config::{CustomThemeEntry, FriendConfig, ThemeConfig, UserProfile};
-use crate::AppState;
+use crate::{AppState, NetworkState};
 
 #[derive(serde::Serialize, Clone)]
 pub struct PresetInfo {
@@ -85,31 +87,31 @@ fn custom_entry_to_preset(entry: &CustomThemeEntry) -> PresetInfo {
 }
 
 #[tauri::command]
-pub async fn get_trusted_peers(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+pub async fn get_trusted_peers(state: State<'_, AppState>) -> Result<Vec<String>, RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    let peers = crate::storage::db::get_all_peers(&conn).map_err(|e| e.to_string())?;
+    let peers = crate::storage::db::get_all_peers(&conn)?;
 
     let peer_ids: Vec<String> = peers.into_iter().map(|p| p.id).collect();
     Ok(peer_ids)
 }
 
 #[tauri::command]
-pub async fn delete_peer(peer_id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_peer(peer_id: String, state: State<'_, AppState>) -> Result<(), RChatError> {
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    crate::storage::db::delete_peer(&conn, &peer_id).map_err(|e| e.to_string())?;
-    println!("[Backend] Deleted peer: {}", peer_id);
+    crate::storage::db::delete_peer(&conn, &peer_id)?;
+    println!("[Backend] Deleted peer: {}", crate::logging::peer(&peer_id));
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_friends(state: State<'_, AppState>) -> Result<Vec<FriendConfig>, String> {
+pub async fn get_friends(state: State<'_, AppState>) -> Result<Vec<FriendConfig>, RChatError> {
     println!("[Backend] get_friends called");
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
         Ok(config) => Ok(config.user.friends.clone()),
         Err(e) => {
             eprintln!("[Backend] Error loading friends: {}", e);
-            Err(e.to_string())
+            Err(e.into())
         }
     }
 }
@@ -117,10 +119,90 @@ pub async fn get_friends(state: State<'_, AppState>) -> Result<Vec<FriendConfig>
 #[tauri::command]
 pub async fn get_peer_aliases(
     state: State<'_, AppState>,
-) -> Result<std::collections::HashMap<String, String>, String> {
+) -> Result<std::collections::HashMap<String, String>, RChatError> {
     println!("[Backend] get_peer_aliases called");
     let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
-    storage::db::get_peer_aliases(&conn).map_err(|e| e.to_string())
+    storage::db::get_peer_aliases(&conn).map_err(RChatError::from)
+}
+
+/// Reputation and standing of a peer as seen by this device.
+#[tauri::command]
+pub async fn get_peer_security_info(
+    peer_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::network::reputation::PeerSecurityInfo, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let known = storage::db::is_peer(&conn, &peer_id);
+    let reputation = storage::db::get_peer_reputation(&conn, &peer_id)?;
+    Ok(crate::network::reputation::PeerSecurityInfo::new(
+        &peer_id,
+        known,
+        reputation,
+        now_unix_ts(),
+    ))
+}
+
+/// Set a local nickname for a peer; an empty alias goes back to the one the
+/// peer announces.
+#[tauri::command]
+pub async fn set_peer_alias(
+    peer_id: String,
+    alias: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    let alias = alias.trim();
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let updated =
+        storage::db::set_peer_alias(&conn, &peer_id, (!alias.is_empty()).then_some(alias))?;
+    if !updated {
+        return Err(RChatError::NotFound(format!("Unknown peer: {}", peer_id)));
+    }
+    Ok(())
+}
+
+/// Set a local avatar for a peer from an image file, or clear it with `None`.
+/// Returns the stored object hash.
+#[tauri::command]
+pub async fn set_peer_avatar(
+    peer_id: String,
+    image_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, RChatError> {
+    let image = match image_path {
+        Some(path) => {
+            storage::disk::ensure_media_writable()?;
+            let data = std::fs::read(&path).map_err(|e| format!("Failed to read image: {}", e))?;
+            let file_name = std::path::Path::new(&path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|s| s.to_string());
+            Some((data, file_name))
+        }
+        None => None,
+    };
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    let avatar_hash = match image {
+        Some((data, file_name)) => Some(
+            storage::object::create(&conn, &data, file_name.as_deref(), None, None)
+                .map_err(|e| e.to_string())?,
+        ),
+        None => None,
+    };
+    let updated = storage::db::set_peer_avatar(&conn, &peer_id, avatar_hash.as_deref())?;
+    if !updated {
+        return Err(RChatError::NotFound(format!("Unknown peer: {}", peer_id)));
+    }
+    Ok(avatar_hash)
+}
+
+#[tauri::command]
+pub async fn get_peer_avatar(
+    peer_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::db::get_peer_avatar_hash(&conn, &peer_id).map_err(RChatError::from)
 }
 
 #[tauri::command]
@@ -129,7 +211,7 @@ pub async fn add_friend(
     x25519_key: Option<String>,
     ed25519_key: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), RChatError> {
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
         Ok(mut config) => {
@@ -143,29 +225,43 @@ pub async fn add_friend(
                     encrypted_leaf_key: None,
                     nonce: None,
                 });
-                mgr.save(&config).await.map_err(|e| e.to_string())?;
+                mgr.save(&config).await?;
             }
             Ok(())
         }
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
 #[tauri::command]
-pub async fn remove_friend(username: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn remove_friend(username: String, state: State<'_, AppState>) -> Result<(), RChatError> {
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
         Ok(mut config) => {
+            let removed = config
+                .user
+                .friends
+                .iter()
+                .find(|f| f.username == username)
+                .cloned();
+            if let Some(friend) = removed {
+                // Rekey their leaf path so they can't read what we publish next.
+                if let Err(e) =
+                    crate::network::discovery::rekey_removed_friend(&mut config, &friend)
+                {
+                    eprintln!("[Backend] Failed to rekey HKS tree for {}: {}", username, e);
+                }
+            }
             config.user.friends.retain(|f| f.username != username);
-            mgr.save(&config).await.map_err(|e| e.to_string())?;
+            mgr.save(&config).await?;
             Ok(())
         }
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
 #[tauri::command]
-pub async fn get_user_profile(state: State<'_, AppState>) -> Result<UserProfile, String> {
+pub async fn get_user_profile(state: State<'_, AppState>) -> Result<UserProfile, RChatError> {
     println!("[Backend] get_user_profile called");
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
@@ -185,34 +281,49 @@ pub async fn update_user_profile(
     alias: Option<String>,
     avatar_path: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let mgr = state.config_manager.lock().await;
-    match mgr.load().await {
-        Ok(mut config) => {
-            if let Some(a) = alias {
-                config.user.profile.alias = Some(a);
-            }
-            if let Some(p) = avatar_path {
-                config.user.profile.avatar_path = Some(p);
-            }
-            mgr.save(&config).await.map_err(|e| e.to_string())?;
-            Ok(())
+    net_state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
+    let alias_changed = {
+        let mgr = state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        let alias_changed = alias.is_some() && alias != config.user.profile.alias;
+        if let Some(a) = alias {
+            config.user.profile.alias = Some(a);
         }
-        Err(e) => Err(e.to_string()),
+        if let Some(p) = avatar_path {
+            config.user.profile.avatar_path = Some(p);
+        }
+        mgr.save(&config).await?;
+        alias_changed.then(|| config.user.profile.alias.clone())
+    };
+
+    // Friends learn about the change now; others get it on their next connect.
+    let tx = net_state.sender.lock().await;
+    if let Err(e) = tx.send(NetworkCommand::BroadcastIdentity).await {
+        eprintln!("[Backend] Failed to broadcast identity update: {}", e);
     }
+    if let Some(alias) = alias_changed {
+        if let Err(e) = tx.send(NetworkCommand::UpdateMdnsAlias { alias }).await {
+            eprintln!("[Backend] Failed to update mDNS alias: {}", e);
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn get_pinned_peers(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+pub async fn get_pinned_peers(state: State<'_, AppState>) -> Result<Vec<String>, RChatError> {
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
         Ok(config) => Ok(config.user.pinned_peers.clone()),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
 #[tauri::command]
-pub async fn toggle_pin_peer(username: String, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn toggle_pin_peer(
+    username: String,
+    state: State<'_, AppState>,
+) -> Result<bool, RChatError> {
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
         Ok(mut config) => {
@@ -223,15 +334,15 @@ pub async fn toggle_pin_peer(username: String, state: State<'_, AppState>) -> Re
                 config.user.pinned_peers.push(username);
                 is_pinned = true;
             }
-            mgr.save(&config).await.map_err(|e| e.to_string())?;
+            mgr.save(&config).await?;
             Ok(is_pinned)
         }
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
 #[tauri::command]
-pub async fn get_theme(state: State<'_, AppState>) -> Result<ThemeConfig, String> {
+pub async fn get_theme(state: State<'_, AppState>) -> Result<ThemeConfig, RChatError> {
     println!("[Backend] get_theme called");
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
@@ -244,16 +355,19 @@ pub async fn get_theme(state: State<'_, AppState>) -> Result<ThemeConfig, String
 }
 
 #[tauri::command]
-pub async fn update_theme(theme: ThemeConfig, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn update_theme(
+    theme: ThemeConfig,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
     println!("[Backend] update_theme called");
     let normalized_theme =
         storage::theme::validate_and_normalize_theme(&theme).map_err(|e| e.to_string())?;
 
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
     config.user.theme = normalized_theme;
     config.user.selected_preset = None;
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
     println!("[Backend] Theme updated successfully");
     Ok(())
 }
@@ -263,16 +377,16 @@ pub async fn generate_simple_theme(
     primary: String,
     secondary: String,
     text: String,
-) -> Result<ThemeConfig, String> {
-    storage::theme::generate_simple_theme(&primary, &secondary, &text).map_err(|e| e.to_string())
+) -> Result<ThemeConfig, RChatError> {
+    storage::theme::generate_simple_theme(&primary, &secondary, &text).map_err(RChatError::from)
 }
 
 #[tauri::command]
-pub async fn list_theme_presets(state: State<'_, AppState>) -> Result<Vec<PresetInfo>, String> {
+pub async fn list_theme_presets(state: State<'_, AppState>) -> Result<Vec<PresetInfo>, RChatError> {
     println!("[Backend] list_theme_presets called");
 
     let mgr = state.config_manager.lock().await;
-    let config = mgr.load().await.map_err(|e| e.to_string())?;
+    let config = mgr.load().await?;
 
     let theme_manager = storage::theme::ThemeManager::new(&state.app_dir);
 
@@ -304,12 +418,15 @@ pub async fn list_theme_presets(state: State<'_, AppState>) -> Result<Vec<Preset
 }
 
 #[tauri::command]
-pub async fn apply_preset(name: String, state: State<'_, AppState>) -> Result<ThemeConfig, String> {
+pub async fn apply_preset(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<ThemeConfig, RChatError> {
     println!("[Backend] apply_preset called with: {}", name);
 
     let theme_manager = storage::theme::ThemeManager::new(&state.app_dir);
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
 
     let theme = if name.starts_with("custom:") {
         config
@@ -318,16 +435,16 @@ pub async fn apply_preset(name: String, state: State<'_, AppState>) -> Result<Th
             .iter()
             .find(|entry| entry.key == name)
             .map(|entry| entry.theme.clone())
-            .ok_or_else(|| format!("Custom theme '{}' not found", name))?
+            .ok_or_else(|| RChatError::NotFound(format!("Custom theme '{}' not found", name)))?
     } else {
         theme_manager
-            .load_preset(&name)
+            .load_preset_for(&name, crate::appearance::preset_variant(&config))
             .map_err(|e| e.to_string())?
     };
 
     config.user.theme = theme.clone();
     config.user.selected_preset = Some(name.clone());
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
 
     println!("[Backend] Preset {} applied successfully", name);
     Ok(theme)
@@ -339,7 +456,7 @@ pub async fn create_custom_theme(
     description: Option<String>,
     theme: ThemeConfig,
     state: State<'_, AppState>,
-) -> Result<PresetInfo, String> {
+) -> Result<PresetInfo, RChatError> {
     let normalized_name = validate_theme_name(&name)?;
     let normalized_description = trim_optional_description(description);
     let normalized_theme =
@@ -356,13 +473,13 @@ pub async fn create_custom_theme(
     };
 
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
 
     config.user.custom_themes.push(entry.clone());
     config.user.theme = normalized_theme;
     config.user.selected_preset = Some(entry.key.clone());
 
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
 
     Ok(custom_entry_to_preset(&entry))
 }
@@ -374,9 +491,11 @@ pub async fn update_custom_theme(
     description: Option<String>,
     theme: ThemeConfig,
     state: State<'_, AppState>,
-) -> Result<PresetInfo, String> {
+) -> Result<PresetInfo, RChatError> {
     if !key.starts_with("custom:") {
-        return Err("Only custom themes can be updated".to_string());
+        return Err(RChatError::InvalidInput(
+            "Only custom themes can be updated".to_string(),
+        ));
     }
 
     let normalized_name = validate_theme_name(&name)?;
@@ -385,7 +504,7 @@ pub async fn update_custom_theme(
         storage::theme::validate_and_normalize_theme(&theme).map_err(|e| e.to_string())?;
 
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
 
     let Some(index) = config
         .user
@@ -393,7 +512,7 @@ pub async fn update_custom_theme(
         .iter()
         .position(|entry| entry.key == key)
     else {
-        return Err("Custom theme not found".to_string());
+        return Err(RChatError::NotFound("Custom theme not found".to_string()));
     };
 
     let updated_at = now_unix_ts();
@@ -407,40 +526,201 @@ pub async fn update_custom_theme(
     config.user.theme = normalized_theme;
     config.user.selected_preset = Some(entry.key.clone());
 
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
 
     Ok(custom_entry_to_preset(&entry))
 }
 
 #[tauri::command]
-pub async fn delete_custom_theme(key: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_custom_theme(
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
     if !key.starts_with("custom:") {
-        return Err("Only custom themes can be deleted".to_string());
+        return Err(RChatError::InvalidInput(
+            "Only custom themes can be deleted".to_string(),
+        ));
     }
 
     let mgr = state.config_manager.lock().await;
-    let mut config = mgr.load().await.map_err(|e| e.to_string())?;
+    let mut config = mgr.load().await?;
 
     let before = config.user.custom_themes.len();
     config.user.custom_themes.retain(|entry| entry.key != key);
 
     if config.user.custom_themes.len() == before {
-        return Err("Custom theme not found".to_string());
+        return Err(RChatError::NotFound("Custom theme not found".to_string()));
     }
 
     if config.user.selected_preset.as_deref() == Some(&key) {
         config.user.selected_preset = None;
     }
 
-    mgr.save(&config).await.map_err(|e| e.to_string())?;
+    mgr.save(&config).await?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_selected_preset(state: State<'_, AppState>) -> Result<Option<String>, String> {
+pub async fn get_selected_preset(state: State<'_, AppState>) -> Result<Option<String>, RChatError> {
     let mgr = state.config_manager.lock().await;
     match mgr.load().await {
         Ok(config) => Ok(config.user.selected_preset),
         Err(_) => Ok(None),
     }
 }
+
+/// Name and description of the active theme, for exporting it.
+fn active_theme_label(
+    config: &storage::config::Config,
+    theme_manager: &storage::theme::ThemeManager,
+) -> (String, Option<String>) {
+    let selected = config.user.selected_preset.as_deref();
+    if let Some(entry) = config
+        .user
+        .custom_themes
+        .iter()
+        .find(|entry| Some(entry.key.as_str()) == selected)
+    {
+        return (entry.name.clone(), entry.description.clone());
+    }
+    theme_manager
+        .list_presets_info()
+        .into_iter()
+        .find(|(key, _, _)| Some(key.as_str()) == selected)
+        .map(|(_, name, description)| (name, Some(description)))
+        .unwrap_or_else(|| ("My theme".to_string(), None))
+}
+
+fn active_theme_file(
+    config: &storage::config::Config,
+    theme_manager: &storage::theme::ThemeManager,
+) -> Result<(String, Vec<u8>), String> {
+    let (name, description) = active_theme_label(config, theme_manager);
+    let data = storage::theme::encode_theme_file(&name, description, &config.user.theme)
+        .map_err(|e| e.to_string())?;
+    Ok((name, data))
+}
+
+/// File name for a theme: its name with anything unsafe replaced.
+fn theme_file_name(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.{}", stem, storage::theme::THEME_FILE_EXTENSION)
+}
+
+/// Write the active theme to `file_path` as a `.rchat-theme` file.
+#[tauri::command]
+pub async fn export_theme(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<String, RChatError> {
+    let mut path = std::path::PathBuf::from(file_path);
+    if path.extension().and_then(|e| e.to_str()) != Some(storage::theme::THEME_FILE_EXTENSION) {
+        path.set_extension(storage::theme::THEME_FILE_EXTENSION);
+    }
+
+    let (name, data) = {
+        let mgr = state.config_manager.lock().await;
+        let config = mgr.load().await?;
+        active_theme_file(&config, &storage::theme::ThemeManager::new(&state.app_dir))?
+    };
+    std::fs::write(&path, data).map_err(|e| format!("Failed to write theme file: {}", e))?;
+
+    println!("[Backend] Exported theme '{}' to {}", name, path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Import a `.rchat-theme` file (e.g. one received from a friend) as a custom
+/// theme. It is added to the list but not applied.
+#[tauri::command]
+pub async fn import_theme(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<PresetInfo, RChatError> {
+    let data = std::fs::read(&file_path).map_err(|e| format!("Failed to read theme: {}", e))?;
+    let file = storage::theme::decode_theme_file(&data).map_err(|e| e.to_string())?;
+
+    let now = now_unix_ts();
+    let entry = CustomThemeEntry {
+        key: generate_custom_theme_key(),
+        name: file.name,
+        description: trim_optional_description(file.description),
+        theme: file.theme,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    config.user.custom_themes.push(entry.clone());
+    mgr.save(&config).await?;
+
+    println!("[Backend] Imported theme '{}'", entry.name);
+    Ok(custom_entry_to_preset(&entry))
+}
+
+/// Send the active theme to a chat as a `.rchat-theme` document.
+#[tauri::command]
+pub async fn send_theme_to_peer(
+    peer_id: String,
+    state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<crate::commands::media::SentMediaResult, RChatError> {
+    let (name, data) = {
+        let mgr = state.config_manager.lock().await;
+        let config = mgr.load().await?;
+        active_theme_file(&config, &storage::theme::ThemeManager::new(&state.app_dir))?
+    };
+
+    // The document path copies the file into the object store, so a scratch
+    // file is enough here.
+    let dir = std::env::temp_dir().join("rchat-themes");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(theme_file_name(&name));
+    std::fs::write(&path, data).map_err(|e| format!("Failed to write theme file: {}", e))?;
+
+    let result = crate::commands::media::send_document_message(
+        peer_id,
+        path.to_string_lossy().to_string(),
+        state,
+        net_state,
+    )
+    .await;
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+#[tauri::command]
+pub async fn get_appearance(
+    state: State<'_, AppState>,
+) -> Result<crate::appearance::AppearanceState, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await?;
+    Ok(crate::appearance::state_for(&config))
+}
+
+/// Switch between light, dark and system appearance. The selected built-in
+/// preset is re-rendered in the matching variant.
+#[tauri::command]
+pub async fn set_appearance(
+    appearance: storage::config::Appearance,
+    state: State<'_, AppState>,
+) -> Result<crate::appearance::AppearanceState, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    config.user.appearance = Some(appearance);
+    crate::appearance::rerender_preset(
+        &mut config,
+        &storage::theme::ThemeManager::new(&state.app_dir),
+    );
+    mgr.save(&config).await?;
+    Ok(crate::appearance::state_for(&config))
+}
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::auth::{
+    apply_connectivity_patch, sync_runtime_connectivity, ConnectivitySettingsPatch,
+};
+use crate::error::RChatError;
+use crate::storage;
+use crate::storage::config::{
+    Appearance, AutoDownloadSettings, AutoReplySettings, ConnectivitySettings,
+    FloodProtectionSettings, HttpApiSettings, MdnsPrivacy, ReplySuggestionSettings,
+    TranslationSettings, UserConfig, WebsocketSettings,
+};
+use crate::AppState;
+
+/// User preferences managed through [`get_settings`] / [`update_settings`].
+/// Keys, friends and the profile keep their own commands.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSettings {
+    pub dark_mode: bool,
+    pub timeout: u16,
+    /// Derived from `connectivity`; read-only here.
+    pub is_online: bool,
+    pub appearance: Option<Appearance>,
+    pub connectivity: ConnectivitySettings,
+    pub reply_suggestions: ReplySuggestionSettings,
+    pub translation: TranslationSettings,
+    pub auto_reply: AutoReplySettings,
+    pub mdns_privacy: MdnsPrivacy,
+    pub handshake_ttl_secs: i64,
+    pub flood_protection: FloodProtectionSettings,
+    pub auto_download: AutoDownloadSettings,
+    /// Applied on the next network start.
+    pub websocket: WebsocketSettings,
+    /// Applied on the next network start.
+    pub listen_port: Option<u16>,
+    pub bot_api_enabled: bool,
+    pub http_api: HttpApiSettings,
+}
+
+impl AppSettings {
+    fn from_config(user: &UserConfig) -> Self {
+        Self {
+            dark_mode: user.dark_mode,
+            timeout: user.timeout,
+            is_online: user.is_online,
+            appearance: user.appearance,
+            connectivity: user.connectivity.clone().with_derived_mode(),
+            reply_suggestions: user.reply_suggestions.clone(),
+            translation: user.translation.clone(),
+            auto_reply: user.auto_reply.clone(),
+            mdns_privacy: user.mdns_privacy,
+            handshake_ttl_secs: user.handshake_ttl_secs,
+            flood_protection: user.flood_protection,
+            auto_download: user.auto_download,
+            websocket: user.websocket.clone(),
+            listen_port: user.listen_port,
+            bot_api_enabled: user.bot_api_enabled,
+            http_api: user.http_api.clone(),
+        }
+    }
+}
+
+/// Partial update; omitted fields are left unchanged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsPatch {
+    pub dark_mode: Option<bool>,
+    pub timeout: Option<u16>,
+    pub appearance: Option<Appearance>,
+    pub connectivity: Option<ConnectivitySettingsPatch>,
+    pub reply_suggestions: Option<ReplySuggestionSettings>,
+    pub translation: Option<TranslationSettings>,
+    pub auto_reply: Option<AutoReplySettings>,
+    pub mdns_privacy: Option<MdnsPrivacy>,
+    pub handshake_ttl_secs: Option<i64>,
+    pub flood_protection: Option<FloodProtectionSettings>,
+    pub auto_download: Option<AutoDownloadSettings>,
+    pub websocket: Option<WebsocketSettings>,
+    /// 0 goes back to random ports.
+    pub listen_port: Option<u16>,
+    pub bot_api_enabled: Option<bool>,
+    pub http_api: Option<HttpApiSettings>,
+}
+
+/// Side effects the caller has to run after saving a patched config.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PatchEffects {
+    connectivity_changed: bool,
+    appearance_changed: bool,
+    auto_reply_turned_on: bool,
+    mdns_privacy_changed: bool,
+    flood_protection_changed: bool,
+    auto_download_changed: bool,
+    bot_api_changed: bool,
+    http_api_changed: bool,
+}
+
+/// Validate the whole patch first, then apply it, so a bad field changes nothing.
+fn apply_patch(user: &mut UserConfig, patch: SettingsPatch) -> Result<PatchEffects, RChatError> {
+    if let Some(settings) = &patch.reply_suggestions {
+        settings.validate().map_err(RChatError::InvalidInput)?;
+    }
+    if let Some(settings) = &patch.translation {
+        settings.validate().map_err(RChatError::InvalidInput)?;
+    }
+    if let Some(settings) = &patch.auto_reply {
+        settings.validate().map_err(RChatError::InvalidInput)?;
+    }
+    if patch.handshake_ttl_secs.is_some_and(|ttl| ttl <= 0) {
+        return Err(RChatError::InvalidInput(
+            "Handshake TTL must be positive".to_string(),
+        ));
+    }
+    if let Some(settings) = &patch.flood_protection {
+        settings.validate().map_err(RChatError::InvalidInput)?;
+    }
+    if let Some(settings) = &patch.websocket {
+        settings.validate().map_err(RChatError::InvalidInput)?;
+    }
+    if let Some(settings) = &patch.http_api {
+        settings.validate().map_err(RChatError::InvalidInput)?;
+    }
+
+    let mut effects = PatchEffects::default();
+    if let Some(dark_mode) = patch.dark_mode {
+        user.dark_mode = dark_mode;
+    }
+    if let Some(timeout) = patch.timeout {
+        user.timeout = timeout;
+    }
+    if let Some(appearance) = patch.appearance {
+        effects.appearance_changed = user.appearance != Some(appearance);
+        user.appearance = Some(appearance);
+    }
+    if let Some(connectivity) = &patch.connectivity {
+        let next = apply_connectivity_patch(user.connectivity.clone(), connectivity);
+        effects.connectivity_changed = true;
+        user.is_online = next.github_sync_enabled;
+        user.connectivity = next;
+    }
+    if let Some(settings) = patch.reply_suggestions {
+        user.reply_suggestions = settings;
+    }
+    if let Some(settings) = patch.translation {
+        user.translation = settings;
+    }
+    if let Some(settings) = patch.auto_reply {
+        effects.auto_reply_turned_on = settings.enabled && !user.auto_reply.enabled;
+        user.auto_reply = settings;
+    }
+    if let Some(level) = patch.mdns_privacy {
+        effects.mdns_privacy_changed = user.mdns_privacy != level;
+        user.mdns_privacy = level;
+        if level == MdnsPrivacy::Anonymous {
+            // Friends learn the key with our next identity update.
+            user.ensure_mdns_discovery_key();
+        }
+    }
+    if let Some(ttl) = patch.handshake_ttl_secs {
+        user.handshake_ttl_secs = ttl;
+    }
+    if let Some(settings) = patch.flood_protection {
+        effects.flood_protection_changed = user.flood_protection != settings;
+        user.flood_protection = settings;
+    }
+    if let Some(settings) = patch.auto_download {
+        effects.auto_download_changed = user.auto_download != settings;
+        user.auto_download = settings;
+    }
+    if let Some(settings) = patch.websocket {
+        user.websocket = settings;
+    }
+    if let Some(port) = patch.listen_port {
+        user.listen_port = (port != 0).then_some(port);
+    }
+    if let Some(enabled) = patch.bot_api_enabled {
+        effects.bot_api_changed = user.bot_api_enabled != enabled;
+        user.bot_api_enabled = enabled;
+    }
+    if let Some(settings) = patch.http_api {
+        effects.http_api_changed = user.http_api != settings;
+        user.http_api = settings;
+    }
+    Ok(effects)
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await?;
+    Ok(AppSettings::from_config(&config.user))
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    patch: SettingsPatch,
+    state: State<'_, AppState>,
+    app_handle: crate::AppHandle,
+) -> Result<AppSettings, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    let effects = apply_patch(&mut config.user, patch)?;
+    if effects.appearance_changed {
+        crate::appearance::rerender_preset(
+            &mut config,
+            &storage::theme::ThemeManager::new(&state.app_dir),
+        );
+    }
+    mgr.save(&config).await?;
+    drop(mgr);
+
+    if effects.auto_reply_turned_on {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::clear_auto_replies(&conn)?;
+    }
+    if effects.connectivity_changed {
+        sync_runtime_connectivity(&app_handle, &config.user.connectivity).await;
+    }
+    if effects.mdns_privacy_changed {
+        // The network manager re-registers the service on its next mDNS check.
+        crate::network::mdns::set_privacy(config.user.mdns_privacy);
+    }
+    if effects.flood_protection_changed {
+        crate::network::flood::set_limits(config.user.flood_protection);
+    }
+    if effects.auto_download_changed {
+        crate::chat::auto_download::set_defaults(config.user.auto_download);
+    }
+    if effects.bot_api_changed {
+        crate::bot_api::apply(&app_handle, config.user.bot_api_enabled).await;
+    }
+    if effects.http_api_changed {
+        crate::http_api::apply(&app_handle, &config.user.http_api).await;
+    }
+    Ok(AppSettings::from_config(&config.user))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_patch_changes_nothing() {
+        let mut user = UserConfig::default();
+        let patch: SettingsPatch = serde_json::from_value(serde_json::json!({
+            "dark_mode": false,
+            "auto_reply": { "enabled": true, "message": "  " }
+        }))
+        .expect("patch");
+        assert!(matches!(
+            apply_patch(&mut user, patch),
+            Err(RChatError::InvalidInput(_))
+        ));
+        assert!(user.dark_mode);
+
+        let patch: SettingsPatch = serde_json::from_value(serde_json::json!({
+            "dark_mode": false,
+            "timeout": 15
+        }))
+        .expect("patch");
+        assert_eq!(
+            apply_patch(&mut user, patch).expect("apply"),
+            PatchEffects::default()
+        );
+        assert!(!user.dark_mode);
+        assert_eq!(user.timeout, 15);
+
+        assert!(serde_json::from_value::<SettingsPatch>(
+            serde_json::json!({ "identity_private_key": "x" })
+        )
+        .is_err());
+    }
+}
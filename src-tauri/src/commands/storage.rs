@@ -0,0 +1,214 @@
+use tauri::State;
+
+use crate::error::RChatError;
+use crate::network::command::NetworkCommand;
+use crate::storage;
+use crate::{AppState, NetworkState};
+
+#[tauri::command]
+pub async fn get_storage_status() -> Result<storage::disk::StorageStatus, RChatError> {
+    Ok(storage::disk::status())
+}
+
+/// Delete chunk files no stored file references, then re-check free space.
+#[tauri::command]
+pub async fn run_storage_gc(
+    state: State<'_, AppState>,
+) -> Result<storage::object::GcReport, RChatError> {
+    let report = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::object::collect_garbage(&conn, None).map_err(|e| e.to_string())?
+    };
+    println!(
+        "[Storage] 🧹 GC removed {} chunk(s), freed {} bytes",
+        report.removed_chunks, report.freed_bytes
+    );
+    storage::disk::refresh();
+    Ok(report)
+}
+
+/// Database, chunk store and per-chat media sizes.
+#[tauri::command]
+pub async fn get_storage_usage(
+    state: State<'_, AppState>,
+) -> Result<storage::quota::StorageUsage, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::quota::usage(&conn, None).map_err(RChatError::from)
+}
+
+/// Set or clear (`None`) the received-media cache cap and apply it right away.
+#[tauri::command]
+pub async fn set_media_cache_cap(
+    cap_mb: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<storage::quota::EvictionReport, RChatError> {
+    {
+        let mgr = state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        config.user.media_cache_cap_mb = cap_mb;
+        mgr.save(&config).await?;
+    }
+    storage::quota::set_cache_cap_mb(cap_mb);
+    let report = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::quota::enforce_cache_cap(&conn).map_err(|e| e.to_string())?
+    };
+    if report.evicted_files > 0 {
+        println!(
+            "[Storage] 🧹 Evicted {} cached file(s), freed {} bytes",
+            report.evicted_files, report.freed_bytes
+        );
+        storage::disk::refresh();
+    }
+    Ok(report)
+}
+
+/// Download the attachment of `message_id` from its sender: media that was
+/// evicted from the cache, or a placeholder auto-download skipped.
+#[tauri::command]
+pub async fn refetch_media(
+    message_id: String,
+    state: State<'_, AppState>,
+    net_state: State<'_, NetworkState>,
+) -> Result<(), RChatError> {
+    let (peer_id, file_hash) = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT peer_id, file_hash FROM messages WHERE id = ?1",
+            [&message_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+        )
+        .map_err(|e| e.to_string())?
+    };
+    let file_hash = file_hash
+        .ok_or_else(|| RChatError::InvalidInput("Message has no attachment".to_string()))?;
+    if peer_id == "Me" {
+        return Err(RChatError::InvalidInput(
+            "Own media is never evicted".to_string(),
+        ));
+    }
+    let tx = net_state.sender.lock().await;
+    tx.send(NetworkCommand::FetchFile { peer_id, file_hash })
+        .await
+        .map_err(|e| RChatError::Network(e.to_string()))
+}
+
+/// Applied schema migrations and the newest one this build knows.
+#[tauri::command]
+pub async fn get_db_schema_version(
+    state: State<'_, AppState>,
+) -> Result<storage::migrations::SchemaVersion, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::migrations::schema_version(&conn).map_err(RChatError::from)
+}
+
+/// Full integrity check plus file and WAL sizes.
+#[tauri::command]
+pub async fn get_database_health(
+    state: State<'_, AppState>,
+) -> Result<storage::maintenance::DbHealth, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::maintenance::health(&conn).map_err(RChatError::from)
+}
+
+/// Checkpoint the WAL, VACUUM and ANALYZE. Blocks other database access
+/// while it runs.
+#[tauri::command]
+pub async fn optimize_database(
+    state: State<'_, AppState>,
+) -> Result<storage::maintenance::OptimizeReport, RChatError> {
+    let report = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::maintenance::optimize(&conn).map_err(|e| e.to_string())?
+    };
+    println!(
+        "[DB] 🧹 Optimized database: {} -> {} bytes in {} ms",
+        report.size_before, report.size_after, report.duration_ms
+    );
+    storage::disk::refresh();
+    Ok(report)
+}
+
+/// What the startup consistency pass repaired after an unclean shutdown.
+#[tauri::command]
+pub async fn get_startup_recovery_report(
+    report: State<'_, storage::recovery::RecoveryReport>,
+) -> Result<storage::recovery::RecoveryReport, RChatError> {
+    Ok(report.inner().clone())
+}
+
+/// Local usage counters. They never leave this device.
+#[tauri::command]
+pub async fn get_usage_metrics(
+    state: State<'_, AppState>,
+) -> Result<storage::db::UsageMetrics, RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::metrics::flush(&conn).map_err(|e| e.to_string())?;
+    storage::db::get_usage_metrics(&conn).map_err(RChatError::from)
+}
+
+#[tauri::command]
+pub async fn reset_usage_metrics(state: State<'_, AppState>) -> Result<(), RChatError> {
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::metrics::discard_pending();
+    storage::db::reset_usage_metrics(&conn)?;
+    println!("[Metrics] 🧹 Usage metrics reset");
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct BandwidthReport {
+    /// Daily rows per peer and protocol, newest first.
+    pub days: Vec<storage::db::BandwidthStat>,
+    pub month_total_bytes: i64,
+    pub monthly_cap_bytes: Option<i64>,
+    pub cap_exceeded: bool,
+}
+
+/// Bytes exchanged over the last `days` days (30 by default) and this month.
+#[tauri::command]
+pub async fn get_bandwidth_stats(
+    days: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<BandwidthReport, RChatError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let since = now - i64::from(days.unwrap_or(30).saturating_sub(1)) * 24 * 60 * 60;
+
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::bandwidth::flush(&conn)?;
+    Ok(BandwidthReport {
+        days: storage::db::list_bandwidth_stats(&conn, &storage::bandwidth::utc_day(since))?,
+        month_total_bytes: storage::db::bandwidth_total_since(
+            &conn,
+            &storage::bandwidth::month_start(now),
+        )?,
+        monthly_cap_bytes: storage::bandwidth::monthly_cap_bytes(),
+        cap_exceeded: storage::bandwidth::cap_exceeded(),
+    })
+}
+
+/// Set or clear (`None`) the monthly cap. Over the cap, large file downloads
+/// wait until the next month or a higher cap.
+#[tauri::command]
+pub async fn set_bandwidth_cap(
+    monthly_cap_mb: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    if monthly_cap_mb == Some(0) {
+        return Err(RChatError::InvalidInput(
+            "Bandwidth cap must be at least 1 MB".to_string(),
+        ));
+    }
+    {
+        let mgr = state.config_manager.lock().await;
+        let mut config = mgr.load().await?;
+        config.user.bandwidth_monthly_cap_mb = monthly_cap_mb;
+        mgr.save(&config).await?;
+    }
+    storage::bandwidth::set_monthly_cap_mb(monthly_cap_mb);
+    let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+    storage::bandwidth::flush(&conn).map_err(RChatError::from)
+}
@@ -0,0 +1,62 @@
+use tauri::State;
+
+use crate::chat::suggest::{self, ReplySuggestions};
+use crate::error::RChatError;
+use crate::storage;
+use crate::storage::config::ReplySuggestionSettings;
+use crate::AppState;
+
+/// Cached suggestions for `msg_id`, computing them on demand if the message
+/// arrived before suggestions were enabled.
+#[tauri::command]
+pub async fn get_reply_suggestions(
+    msg_id: String,
+    state: State<'_, AppState>,
+    suggestions: State<'_, ReplySuggestions>,
+) -> Result<Vec<String>, RChatError> {
+    if let Some(cached) = suggestions.get(&msg_id) {
+        return Ok(cached);
+    }
+
+    let settings = {
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await?.user.reply_suggestions
+    };
+    if !settings.enabled {
+        return Ok(Vec::new());
+    }
+
+    let msg = {
+        let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+        storage::db::get_message(&conn, &msg_id)?
+    };
+    let Some(msg) = msg.filter(|m| m.peer_id != "Me" && m.content.content_type() == "text") else {
+        return Ok(Vec::new());
+    };
+
+    let computed = suggest::compute(&settings, &msg).await;
+    suggestions.insert(msg_id, computed.clone());
+    Ok(computed)
+}
+
+#[tauri::command]
+pub async fn get_reply_suggestion_settings(
+    state: State<'_, AppState>,
+) -> Result<ReplySuggestionSettings, RChatError> {
+    let mgr = state.config_manager.lock().await;
+    let config = mgr.load().await?;
+    Ok(config.user.reply_suggestions)
+}
+
+#[tauri::command]
+pub async fn set_reply_suggestion_settings(
+    settings: ReplySuggestionSettings,
+    state: State<'_, AppState>,
+) -> Result<(), RChatError> {
+    settings.validate().map_err(RChatError::InvalidInput)?;
+
+    let mgr = state.config_manager.lock().await;
+    let mut config = mgr.load().await?;
+    config.user.reply_suggestions = settings;
+    mgr.save(&config).await.map_err(RChatError::from)
+}
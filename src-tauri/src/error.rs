@@ -0,0 +1,207 @@
+//! Errors returned by Tauri commands.
+//!
+//! Commands fail with an [`RChatError`], which reaches the frontend as
+//! `{ "code": "VAULT_LOCKED", "message": "Vault is locked" }`. The codes are
+//! stable so the UI can translate them; the message is English and meant for
+//! logs or as a fallback. Plain `String` errors still convert (as `INTERNAL`),
+//! so helpers returning `Result<T, String>` can be used with `?`.
+//!
+//! Lower layers can raise an `RChatError` through `anyhow` as well: the
+//! conversion from `anyhow::Error` recovers it, and classifies database and
+//! I/O errors found in the chain.
+
+use rchat_core::chat::media_guard::ExportRestricted;
+use rchat_core::storage::config::VaultLocked;
+use rchat_core::storage::disk::StorageLow;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RChatError {
+    /// The vault has to be unlocked first.
+    VaultLocked,
+    NotFound(String),
+    InvalidInput(String),
+    /// The peer isn't connected, or the network isn't running.
+    PeerOffline(String),
+    Network(String),
+    Crypto(String),
+    Io(String),
+    Database(String),
+    /// Free disk space is too low for the write.
+    StorageLow(String),
+    /// The chat doesn't allow saving its media out of the app.
+    ExportRestricted(String),
+    Internal(String),
+}
+
+impl RChatError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RChatError::VaultLocked => "VAULT_LOCKED",
+            RChatError::NotFound(_) => "NOT_FOUND",
+            RChatError::InvalidInput(_) => "INVALID_INPUT",
+            RChatError::PeerOffline(_) => "PEER_OFFLINE",
+            RChatError::Network(_) => "NETWORK",
+            RChatError::Crypto(_) => "CRYPTO",
+            RChatError::Io(_) => "IO",
+            RChatError::Database(_) => "DATABASE",
+            RChatError::StorageLow(_) => rchat_core::storage::disk::STORAGE_LOW_CODE,
+            RChatError::ExportRestricted(_) => {
+                rchat_core::chat::media_guard::EXPORT_RESTRICTED_CODE
+            }
+            RChatError::Internal(_) => "INTERNAL",
+        }
+    }
+}
+
+impl fmt::Display for RChatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RChatError::VaultLocked => f.write_str("Vault is locked"),
+            RChatError::NotFound(message)
+            | RChatError::InvalidInput(message)
+            | RChatError::PeerOffline(message)
+            | RChatError::Network(message)
+            | RChatError::Crypto(message)
+            | RChatError::Io(message)
+            | RChatError::Database(message)
+            | RChatError::StorageLow(message)
+            | RChatError::ExportRestricted(message)
+            | RChatError::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for RChatError {}
+
+impl Serialize for RChatError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("RChatError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+impl From<String> for RChatError {
+    fn from(message: String) -> Self {
+        RChatError::Internal(message)
+    }
+}
+
+impl From<&str> for RChatError {
+    fn from(message: &str) -> Self {
+        RChatError::Internal(message.to_string())
+    }
+}
+
+impl From<RChatError> for String {
+    fn from(error: RChatError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<anyhow::Error> for RChatError {
+    fn from(error: anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(rchat) = cause.downcast_ref::<RChatError>() {
+                return rchat.clone();
+            }
+            if cause.is::<VaultLocked>() {
+                return RChatError::VaultLocked;
+            }
+            if let Some(low) = cause.downcast_ref::<StorageLow>() {
+                return (*low).into();
+            }
+            if let Some(restricted) = cause.downcast_ref::<ExportRestricted>() {
+                return (*restricted).into();
+            }
+            if cause.is::<rusqlite::Error>() {
+                return RChatError::Database(error.to_string());
+            }
+            if cause.is::<std::io::Error>() {
+                return RChatError::Io(error.to_string());
+            }
+        }
+        RChatError::Internal(error.to_string())
+    }
+}
+
+impl From<VaultLocked> for RChatError {
+    fn from(_: VaultLocked) -> Self {
+        RChatError::VaultLocked
+    }
+}
+
+impl From<StorageLow> for RChatError {
+    fn from(error: StorageLow) -> Self {
+        RChatError::StorageLow(error.to_string())
+    }
+}
+
+impl From<ExportRestricted> for RChatError {
+    fn from(error: ExportRestricted) -> Self {
+        RChatError::ExportRestricted(error.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for RChatError {
+    fn from(error: rusqlite::Error) -> Self {
+        RChatError::Database(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for RChatError {
+    fn from(error: std::io::Error) -> Self {
+        RChatError::Io(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn serializes_code_and_message_and_classifies_anyhow_errors() {
+        assert_eq!(
+            serde_json::to_value(RChatError::VaultLocked).unwrap(),
+            serde_json::json!({ "code": "VAULT_LOCKED", "message": "Vault is locked" })
+        );
+        assert_eq!(
+            serde_json::to_value(RChatError::NotFound("Note not found".to_string())).unwrap(),
+            serde_json::json!({ "code": "NOT_FOUND", "message": "Note not found" })
+        );
+        assert_eq!(RChatError::from("oops".to_string()).code(), "INTERNAL");
+
+        let locked: anyhow::Result<()> = Err(RChatError::VaultLocked).context("Failed to load");
+        assert_eq!(
+            RChatError::from(locked.unwrap_err()),
+            RChatError::VaultLocked
+        );
+
+        let locked = anyhow::Error::from(VaultLocked).context("Failed to save");
+        assert_eq!(RChatError::from(locked), RChatError::VaultLocked);
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let db: anyhow::Result<()> = conn
+            .execute("SELECT * FROM missing", [])
+            .map(|_| ())
+            .context("Failed to read");
+        let error = RChatError::from(db.unwrap_err());
+        assert_eq!(error.code(), "DATABASE");
+        assert_eq!(error.to_string(), "Failed to read");
+
+        let io = std::fs::read("/nonexistent/rchat").map_err(anyhow::Error::from);
+        assert_eq!(RChatError::from(io.unwrap_err()).code(), "IO");
+
+        let restricted = anyhow::Error::from(ExportRestricted).context("Failed to export");
+        assert_eq!(RChatError::from(restricted).code(), "EXPORT_RESTRICTED");
+        assert_eq!(
+            RChatError::StorageLow("Disk is almost full".to_string()).code(),
+            "STORAGE_LOW"
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Opt-in REST API on localhost (`UserConfig::http_api`) for scripts and other
+//! apps on this machine.
+//!
+//! Every request needs `Authorization: Bearer <token>`, with the token from
+//! `http_api.token` in the app directory. Endpoints:
+//!
+//! - `GET /chats`: chats, as in the chat list
+//! - `GET /messages?chat_id=<id>&limit=<n>`: a chat's history, oldest first
+//! - `POST /send` with `{"chat_id": "...", "text": "..."}`: returns `{"message_id": "..."}`
+//! - `GET /peers`: known peers
+//!
+//! Failures answer `{"code": ..., "message": ...}` like the app's commands;
+//! sends over the shared rate limit get `429` with code `RATE_LIMITED`.
+//! Requests go through the same commands as the UI, hidden chats can't be
+//! read or sent to unless they are revealed in the app, and the server runs
+//! while the network is up.
+
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::AppHandle;
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::sync::Mutex;
+
+use crate::bridge::RateLimiter;
+use crate::error::RChatError;
+use crate::storage::config::HttpApiSettings;
+
+pub const DEFAULT_PORT: u16 = 48_080;
+const TOKEN_FILE: &str = "http_api.token";
+
+/// The running server, if any. Managed as Tauri state.
+#[derive(Default)]
+pub struct HttpApiRuntime {
+    server: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+fn token_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(TOKEN_FILE)
+}
+
+/// The client token, created on first use. Only the current user can read it.
+pub fn load_or_create_token(app_dir: &Path) -> std::io::Result<String> {
+    crate::bot_api::load_or_create_token_file(&token_path(app_dir))
+}
+
+/// Replace the client token. Running servers must be restarted to use it.
+pub fn rotate_token(app_dir: &Path) -> std::io::Result<String> {
+    crate::bot_api::write_new_token(&token_path(app_dir))
+}
+
+pub fn base_url(settings: &HttpApiSettings) -> String {
+    format!("http://127.0.0.1:{}", settings.port.unwrap_or(DEFAULT_PORT))
+}
+
+/// Start or stop the server to match `settings`. Called once the network is up
+/// and whenever the settings change.
+pub async fn apply(app_handle: &AppHandle, settings: &HttpApiSettings) {
+    stop(app_handle).await;
+    if !settings.enabled || app_handle.try_state::<crate::NetworkState>().is_none() {
+        return;
+    }
+    if let Err(e) = start(app_handle, settings).await {
+        eprintln!("[HttpApi] ❌ Failed to start: {}", e);
+    }
+}
+
+/// Start the server if it is enabled.
+pub async fn start_if_enabled(app_handle: &AppHandle) {
+    let settings = {
+        let state = app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        mgr.load()
+            .await
+            .map(|config| config.user.http_api)
+            .unwrap_or_default()
+    };
+    apply(app_handle, &settings).await;
+}
+
+async fn start(app_handle: &AppHandle, settings: &HttpApiSettings) -> Result<(), String> {
+    let Some(runtime) = app_handle.try_state::<HttpApiRuntime>() else {
+        return Err("HTTP API runtime not initialized".to_string());
+    };
+    let app_dir = app_handle.state::<crate::AppState>().app_dir.clone();
+    let token = load_or_create_token(&app_dir).map_err(|e| e.to_string())?;
+    let port = settings.port.unwrap_or(DEFAULT_PORT);
+    // Loopback only: the API is for this machine.
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .await
+        .map_err(|e| format!("bind 127.0.0.1:{}: {}", port, e))?;
+
+    let router = router(ApiState {
+        app_handle: app_handle.clone(),
+        token: token.into(),
+        limiter: Arc::new(Mutex::new(RateLimiter::default())),
+    });
+    let task = tauri::async_runtime::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            eprintln!("[HttpApi] ❌ Server stopped: {}", e);
+        }
+    });
+    println!("[HttpApi] ✅ Listening on {}", base_url(settings));
+    *runtime.server.lock().await = Some(task);
+    Ok(())
+}
+
+pub async fn stop(app_handle: &AppHandle) {
+    let Some(runtime) = app_handle.try_state::<HttpApiRuntime>() else {
+        return;
+    };
+    if let Some(task) = runtime.server.lock().await.take() {
+        task.abort();
+        println!("[HttpApi] Stopped");
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app_handle: AppHandle,
+    token: Arc<str>,
+    /// Shared by all clients, like a bridge's.
+    limiter: Arc<Mutex<RateLimiter>>,
+}
+
+fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/chats", get(chats))
+        .route("/messages", get(messages))
+        .route("/send", post(send))
+        .route("/peers", get(peers))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+enum ApiError {
+    Command(RChatError),
+    RateLimited,
+}
+
+impl From<RChatError> for ApiError {
+    fn from(error: RChatError) -> Self {
+        Self::Command(error)
+    }
+}
+
+fn status_for(error: &RChatError) -> StatusCode {
+    match error {
+        RChatError::NotFound(_) => StatusCode::NOT_FOUND,
+        RChatError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+        RChatError::VaultLocked | RChatError::PeerOffline(_) | RChatError::Network(_) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        RChatError::StorageLow(_) => StatusCode::INSUFFICIENT_STORAGE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::Command(error) => (status_for(&error), Json(error)).into_response(),
+            ApiError::RateLimited => {
+                let body = serde_json::json!({
+                    "code": "RATE_LIMITED",
+                    "message": "Rate limited",
+                });
+                (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let authorized = bearer_token(request.headers())
+        .is_some_and(|given| crate::bot_api::token_matches(&state.token, given));
+    if !authorized {
+        let body = serde_json::json!({
+            "code": "UNAUTHORIZED",
+            "message": "Missing or wrong token",
+        });
+        return (StatusCode::UNAUTHORIZED, Json(body)).into_response();
+    }
+    next.run(request).await
+}
+
+async fn chats(
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<crate::commands::chat::ChatInfo>>, ApiError> {
+    let chats = crate::commands::chat::get_chats(state.app_handle.state()).await?;
+    Ok(Json(chats))
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesQuery {
+    chat_id: String,
+    limit: Option<usize>,
+}
+
+async fn messages(
+    State(state): State<ApiState>,
+    Query(query): Query<MessagesQuery>,
+) -> Result<Json<Vec<crate::storage::db::Message>>, ApiError> {
+    crate::bot_api::ensure_chat_visible(&state.app_handle, &query.chat_id).await?;
+    let mut messages = crate::commands::chat::get_chat_history(
+        query.chat_id,
+        state.app_handle.state(),
+        state.app_handle.state(),
+    )
+    .await?;
+    if let Some(limit) = query.limit {
+        messages.drain(..messages.len().saturating_sub(limit));
+    }
+    Ok(Json(messages))
+}
+
+#[derive(Debug, Deserialize)]
+struct SendRequest {
+    chat_id: String,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SendResponse {
+    message_id: String,
+}
+
+async fn send(
+    State(state): State<ApiState>,
+    Json(request): Json<SendRequest>,
+) -> Result<Json<SendResponse>, ApiError> {
+    if request.text.trim().is_empty() {
+        return Err(RChatError::InvalidInput("Message text is empty".to_string()).into());
+    }
+    crate::bot_api::ensure_chat_visible(&state.app_handle, &request.chat_id).await?;
+    if !state.limiter.lock().await.try_acquire() {
+        return Err(ApiError::RateLimited);
+    }
+    let message_id = crate::commands::chat::send_message(
+        request.chat_id,
+        request.text,
+        state.app_handle.state(),
+        state.app_handle.state(),
+        state.app_handle.clone(),
+    )
+    .await?;
+    Ok(Json(SendResponse { message_id }))
+}
+
+/// A peer as `/peers` lists it; keys stay inside the app.
+#[derive(Debug, Serialize)]
+struct PeerSummary {
+    id: String,
+    alias: String,
+    last_seen: i64,
+    method: String,
+}
+
+async fn peers(State(state): State<ApiState>) -> Result<Json<Vec<PeerSummary>>, ApiError> {
+    let app_state = state.app_handle.state::<crate::AppState>();
+    let conn = app_state
+        .db_conn
+        .lock()
+        .map_err(|e| RChatError::Internal(e.to_string()))?;
+    let peers = crate::storage::db::get_all_peers(&conn)
+        .map_err(RChatError::from)?
+        .into_iter()
+        .filter(|peer| peer.id != "Me")
+        .map(|peer| PeerSummary {
+            id: peer.id,
+            alias: peer.alias,
+            last_seen: peer.last_seen,
+            method: peer.method,
+        })
+        .collect();
+    Ok(Json(peers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bearer_token_needs_the_scheme() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().expect("header"));
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(
+            header::AUTHORIZATION,
+            "Bearer abc ".parse().expect("header"),
+        );
+        assert_eq!(bearer_token(&headers), Some("abc"));
+    }
+
+    #[test]
+    fn errors_map_to_http_statuses() {
+        assert_eq!(
+            status_for(&RChatError::NotFound("x".to_string())),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status_for(&RChatError::InvalidInput("x".to_string())),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status_for(&RChatError::VaultLocked),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            status_for(&RChatError::Database("x".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            ApiError::RateLimited.into_response().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[test]
+    fn settings_default_to_off_on_the_default_port() {
+        let settings = HttpApiSettings::default();
+        assert!(!settings.enabled);
+        assert_eq!(base_url(&settings), "http://127.0.0.1:48080");
+        assert!(HttpApiSettings {
+            enabled: true,
+            port: Some(0)
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_to_hidden_chats_are_not_found() {
+        let files = tempfile::tempdir().expect("tempdir");
+        let app = tauri::test::mock_app();
+        app.manage(crate::AppState::for_tests(files.path().to_path_buf()));
+        {
+            let state = app.state::<crate::AppState>();
+            let conn = state.db_conn.lock().expect("db lock");
+            crate::storage::db::upsert_chat(&conn, "group:g", "Group", true).expect("chat");
+            let mut settings = crate::storage::db::ChatSettings::defaults("group:g");
+            settings.hidden = true;
+            crate::storage::db::upsert_chat_settings(&conn, &settings).expect("settings");
+        }
+        let state = ApiState {
+            app_handle: app.handle().clone(),
+            token: "t".into(),
+            limiter: Arc::new(Mutex::new(RateLimiter::default())),
+        };
+
+        let request = SendRequest {
+            chat_id: "group:g".to_string(),
+            text: "hi".to_string(),
+        };
+        let response = send(State(state), Json(request)).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
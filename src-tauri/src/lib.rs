@@ -1,19 +1,39 @@
 mod app_state;
+mod appearance;
+mod bot_api;
+mod bridge;
 mod chat;
-mod chat_identity;
-mod chat_kind;
 mod commands;
+mod error;
+mod http_api;
 mod live;
 mod network;
 mod oauth;
 mod storage;
 
 pub use app_state::{AppState, NetworkState};
+use rchat_core::{chat_identity, chat_kind, logging};
+
+/// The Tauri runtime the backend is built for. Unit tests swap in the mock
+/// runtime so the network manager and its workers can run without a window.
+#[cfg(not(test))]
+pub type Runtime = tauri::Wry;
+#[cfg(test)]
+pub type Runtime = tauri::test::MockRuntime;
+pub type AppHandle = tauri::AppHandle<Runtime>;
 
 use crate::commands::auth::{
-    check_auth_status, get_connectivity_settings, init_vault, poll_github_auth, reset_vault,
-    save_api_token, set_connectivity_mode, start_github_auth, start_network, toggle_online_status,
-    unlock_vault, update_connectivity_settings,
+    check_auth_status, disconnect_github, get_connectivity_settings, get_keychain_unlock,
+    init_vault, poll_github_auth, recover_vault, reset_vault, rotate_identity_keys, save_api_token,
+    set_connectivity_mode, set_discovery_backend, set_keychain_unlock, set_socks5_proxy,
+    start_github_auth, start_network, toggle_online_status, unlock_vault,
+    update_connectivity_settings,
+};
+use crate::commands::bot_api::{get_bot_api_info, rotate_bot_api_token};
+use crate::commands::bridge::{
+    configure_chat_bridge, get_chat_bridge, get_matrix_account, list_bridge_identities,
+    list_bridge_nicks, remove_chat_bridge, set_bridge_identity, set_chat_bridge_enabled,
+    set_matrix_account,
 };
 use crate::commands::call::{
     accept_screen_broadcast, accept_video_call, accept_voice_call, end_screen_broadcast,
@@ -25,35 +45,74 @@ use crate::commands::call::{
     start_video_call, start_voice_call, submit_video_call_i420_frame,
 };
 use crate::commands::chat::{
-    create_group_chat, get_chat_history, get_chat_latest_times, get_chat_list, get_unread_counts,
-    join_group_chat, leave_group_chat, mark_messages_read, save_temporary_chat_to_archive,
-    send_message, send_message_to_self,
+    cancel_scheduled_message, create_chat, create_group_chat, delete_self_note, export_chat,
+    get_chat_history, get_chat_latest_times, get_chat_list, get_chats, get_chats_with_tags,
+    get_message_request_scores, get_self_files, get_starred_messages, get_unread_counts,
+    join_group_chat, leave_group_chat, list_scheduled_messages, mark_messages_read, retry_message,
+    save_self_file, save_temporary_chat_to_archive, schedule_message, send_message,
+    send_message_to_self, set_group_typing, set_message_starred,
 };
 use crate::commands::chat_details::{
     drop_chat_connection, force_chat_reconnect, get_chat_details_overview, get_chat_stats,
     list_chat_files,
 };
-use crate::commands::debug::frontend_log;
+use crate::commands::chat_settings::{
+    authorize_media_export, conceal_hidden_chats, get_auto_reply_settings, get_chat_settings,
+    get_translation_settings, reveal_hidden_chats, set_chat_auto_download, set_chat_hidden,
+    set_chat_media_export_restricted, set_chat_mute, set_chat_notification_level,
+    set_chat_notification_priority, set_chat_notification_sound, set_chat_send_translation,
+    set_network_metered, set_translation_settings,
+};
+use crate::commands::debug::{debug_export_discovery_state, frontend_log};
+use crate::commands::drafts::{get_draft, save_draft};
 use crate::commands::envelopes::{
-    create_envelope, delete_envelope, get_envelope_assignments, get_envelopes,
-    move_chat_to_envelope, update_envelope,
+    add_chat_tag, create_envelope, delete_envelope, get_envelope_assignments,
+    get_envelope_summaries, get_envelopes, move_chat_to_envelope, remove_chat_tag,
+    reorder_envelopes, update_envelope,
 };
+use crate::commands::group_admin::{kick_member, promote_member, set_group_avatar, set_group_name};
+use crate::commands::group_history::{request_group_history, set_group_history_sharing};
+use crate::commands::group_invite::{create_group_invite, join_group};
+use crate::commands::http_api::{get_http_api_info, rotate_http_api_token};
+use crate::commands::import::{import_chat_history, preview_chat_import};
 use crate::commands::invite::{
-    cancel_temporary_invite, create_invite, create_temporary_invite, generate_invite_password,
-    get_active_temporary_invite, redeem_and_connect, redeem_temporary_invite,
+    accept_friend_request, cancel_invite, cancel_temporary_invite, create_invite,
+    create_temporary_invite, decline_friend_request, generate_invite_password,
+    get_active_temporary_invite, get_friend_requests, get_pending_invitations, redeem_and_connect,
+    redeem_temporary_invite,
+};
+use crate::commands::journal::{
+    get_journaled_chats, list_journal_days, read_journal_day, set_chat_journaling,
 };
 use crate::commands::media::{
-    add_sticker, add_stickers_batch, delete_sticker, get_audio_data, get_image_data,
-    get_image_from_path, get_video_data, list_stickers, save_audio_to_file, save_document_to_file,
-    save_image_to_file, save_sticker_from_message, send_audio_message, send_document_message,
-    send_image_message, send_sticker_message, send_video_message,
+    add_sticker, add_stickers_batch, delete_sticker, get_audio_data, get_file_stream,
+    get_image_data, get_image_from_path, get_video_data, list_stickers, save_audio_to_file,
+    save_document_to_file, save_image_to_file, save_sticker_from_message, send_audio_message,
+    send_document_message, send_image_message, send_sticker_message, send_video_message,
+};
+use crate::commands::network_control::{
+    get_connection_path, get_local_peers, get_network_diagnostics, get_peer_capabilities,
+    get_peer_connection_quality, request_connection, set_fast_discovery, test_peer_connection,
+};
+use crate::commands::notes::{
+    add_self_task, cancel_reminder, get_reminders, get_self_tasks, schedule_reminder, set_task_done,
 };
-use crate::commands::network_control::{request_connection, set_fast_discovery};
 use crate::commands::peer_profile::{
-    add_friend, apply_preset, create_custom_theme, delete_custom_theme, delete_peer,
-    generate_simple_theme, get_friends, get_peer_aliases, get_pinned_peers, get_selected_preset,
-    get_theme, get_trusted_peers, get_user_profile, list_theme_presets, remove_friend,
-    toggle_pin_peer, update_custom_theme, update_theme, update_user_profile,
+    add_friend, apply_preset, create_custom_theme, delete_custom_theme, delete_peer, export_theme,
+    generate_simple_theme, get_appearance, get_friends, get_peer_aliases, get_peer_avatar,
+    get_peer_security_info, get_pinned_peers, get_selected_preset, get_theme, get_trusted_peers,
+    get_user_profile, import_theme, list_theme_presets, remove_friend, send_theme_to_peer,
+    set_appearance, set_peer_alias, set_peer_avatar, toggle_pin_peer, update_custom_theme,
+    update_theme, update_user_profile,
+};
+use crate::commands::settings::{get_settings, update_settings};
+use crate::commands::storage::{
+    get_bandwidth_stats, get_database_health, get_db_schema_version, get_startup_recovery_report,
+    get_storage_status, get_storage_usage, get_usage_metrics, optimize_database, refetch_media,
+    reset_usage_metrics, run_storage_gc, set_bandwidth_cap, set_media_cache_cap,
+};
+use crate::commands::suggestions::{
+    get_reply_suggestion_settings, get_reply_suggestions, set_reply_suggestion_settings,
 };
 use crate::storage::config::ConfigManager;
 use tauri::{Emitter, Manager};
@@ -108,9 +167,10 @@ fn configure_linux_webcodecs_gstreamer_rank() {}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
     configure_linux_webcodecs_gstreamer_rank();
 
-    tauri::Builder::default()
+    tauri::Builder::<Runtime>::new()
         .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
             // Bring existing window to front when a second instance is invoked.
             if let Some(window) = app.get_webview_window("main") {
@@ -140,6 +200,10 @@ pub fn run() {
                 .expect("failed to get app data dir");
             std::fs::create_dir_all(&app_dir).expect("failed to create app data dir");
             let mut config_manager = ConfigManager::new(app_dir.clone());
+            let mut recovery_report = storage::recovery::RecoveryReport {
+                repairs: config_manager.recover_interrupted_save(),
+                ..Default::default()
+            };
 
             if config_manager.try_restore_session() {
                 println!("Session restored successfully. Vault unlocked.");
@@ -149,16 +213,75 @@ pub fn run() {
 
             let db_connection =
                 storage::db::connect_to_db().expect("Failed to initialize database");
+            let chunks_dir =
+                storage::object::get_chunks_dir(None).expect("Failed to create chunks directory");
+
+            recovery_report.db_integrity = storage::recovery::db_quick_check(&db_connection);
+            match storage::object::repair_chunks(&db_connection, None) {
+                Ok(chunks) => {
+                    if chunks.temp_files_removed > 0 {
+                        recovery_report.repairs.push(format!(
+                            "Removed {} interrupted chunk write(s)",
+                            chunks.temp_files_removed
+                        ));
+                    }
+                    if chunks.torn_chunks_removed > 0 {
+                        recovery_report.repairs.push(format!(
+                            "Removed {} torn chunk(s); {} file(s) will be fetched again",
+                            chunks.torn_chunks_removed, chunks.files_marked_incomplete
+                        ));
+                    }
+                }
+                Err(e) => recovery_report
+                    .repairs
+                    .push(format!("Chunk store check failed: {}", e)),
+            }
+            for repair in &recovery_report.repairs {
+                println!("[Recovery] 🩹 {}", repair);
+            }
+            if recovery_report.db_integrity != "ok" {
+                eprintln!(
+                    "[Recovery] ⚠️ Database integrity check: {}",
+                    recovery_report.db_integrity
+                );
+            }
+
+            let journal = storage::journal::JournalWriter::new(&app_dir);
+            journal.set_key(config_manager.journal_key());
 
             app.manage(AppState {
                 config_manager: tokio::sync::Mutex::new(config_manager),
                 db_conn: std::sync::Mutex::new(db_connection),
                 app_dir: app_dir.clone(),
+                chunks_dir,
+                journal,
             });
+            app.manage(bot_api::BotApiRuntime::default());
+            app.manage(http_api::HttpApiRuntime::default());
+            app.manage(bridge::BridgeRuntime::default());
+            app.manage(chat::suggest::ReplySuggestions::default());
+            app.manage(recovery_report);
+            storage::disk::spawn_monitor(app.handle().clone());
+            chat::reminders::spawn_scheduler(app.handle().clone());
+            chat::scheduled::spawn_scheduler(app.handle().clone());
+            storage::metrics::spawn_flusher(app.handle().clone());
+            storage::bandwidth::spawn_flusher(app.handle().clone());
+            storage::maintenance::spawn_checkpointer(app.handle().clone());
+            if let Some(theme) = app
+                .get_webview_window("main")
+                .and_then(|window| window.theme().ok())
+            {
+                appearance::set_system_theme(theme);
+            }
 
             println!("[Backend] Setup hook returning Ok");
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::ThemeChanged(theme) = event {
+                appearance::on_system_theme_changed(window.app_handle(), *theme);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             save_api_token,
             check_auth_status,
@@ -167,7 +290,15 @@ pub fn run() {
             update_connectivity_settings,
             toggle_online_status,
             frontend_log,
+            debug_export_discovery_state,
             init_vault,
+            recover_vault,
+            disconnect_github,
+            set_discovery_backend,
+            set_socks5_proxy,
+            rotate_identity_keys,
+            get_keychain_unlock,
+            set_keychain_unlock,
             unlock_vault,
             start_network,
             start_github_auth,
@@ -175,6 +306,10 @@ pub fn run() {
             reset_vault,
             get_friends,
             get_peer_aliases,
+            get_peer_security_info,
+            set_peer_alias,
+            set_peer_avatar,
+            get_peer_avatar,
             get_trusted_peers,
             add_friend,
             delete_peer,
@@ -189,22 +324,54 @@ pub fn run() {
             create_custom_theme,
             update_custom_theme,
             delete_custom_theme,
+            export_theme,
+            import_theme,
+            send_theme_to_peer,
+            get_appearance,
+            set_appearance,
             update_user_profile,
             get_pinned_peers,
             toggle_pin_peer,
             send_message_to_self,
+            get_self_files,
+            schedule_message,
+            list_scheduled_messages,
+            cancel_scheduled_message,
+            save_self_file,
+            delete_self_note,
             send_message,
+            retry_message,
+            set_group_typing,
             get_chat_history,
+            export_chat,
+            preview_chat_import,
+            import_chat_history,
+            get_message_request_scores,
+            set_message_starred,
+            get_starred_messages,
             create_envelope,
             update_envelope,
             delete_envelope,
             get_envelopes,
             move_chat_to_envelope,
             get_envelope_assignments,
+            reorder_envelopes,
+            get_envelope_summaries,
+            add_chat_tag,
+            remove_chat_tag,
             request_connection,
             set_fast_discovery,
+            get_network_diagnostics,
+            get_local_peers,
+            get_connection_path,
+            get_peer_connection_quality,
+            get_peer_capabilities,
+            test_peer_connection,
             get_chat_latest_times,
             get_chat_list,
+            get_chats,
+            get_chats_with_tags,
+            create_chat,
             get_chat_details_overview,
             get_chat_stats,
             list_chat_files,
@@ -220,6 +387,7 @@ pub fn run() {
             save_document_to_file,
             send_video_message,
             get_video_data,
+            get_file_stream,
             send_audio_message,
             get_audio_data,
             save_audio_to_file,
@@ -231,7 +399,12 @@ pub fn run() {
             save_sticker_from_message,
             generate_invite_password,
             create_invite,
+            get_pending_invitations,
+            cancel_invite,
             redeem_and_connect,
+            get_friend_requests,
+            accept_friend_request,
+            decline_friend_request,
             create_temporary_invite,
             redeem_temporary_invite,
             get_active_temporary_invite,
@@ -239,6 +412,14 @@ pub fn run() {
             create_group_chat,
             join_group_chat,
             leave_group_chat,
+            kick_member,
+            promote_member,
+            set_group_name,
+            set_group_avatar,
+            create_group_invite,
+            join_group,
+            set_group_history_sharing,
+            request_group_history,
             save_temporary_chat_to_archive,
             start_voice_call,
             accept_voice_call,
@@ -265,6 +446,66 @@ pub fn run() {
             end_screen_broadcast,
             get_broadcast_state,
             get_connected_chat_ids,
+            get_bot_api_info,
+            rotate_bot_api_token,
+            get_http_api_info,
+            rotate_http_api_token,
+            configure_chat_bridge,
+            get_chat_bridge,
+            set_chat_bridge_enabled,
+            remove_chat_bridge,
+            set_bridge_identity,
+            list_bridge_identities,
+            list_bridge_nicks,
+            get_matrix_account,
+            set_matrix_account,
+            set_chat_journaling,
+            get_journaled_chats,
+            list_journal_days,
+            read_journal_day,
+            get_chat_settings,
+            set_chat_notification_sound,
+            set_chat_notification_priority,
+            set_chat_mute,
+            set_chat_notification_level,
+            set_chat_send_translation,
+            set_chat_hidden,
+            reveal_hidden_chats,
+            conceal_hidden_chats,
+            set_chat_media_export_restricted,
+            set_chat_auto_download,
+            set_network_metered,
+            authorize_media_export,
+            get_auto_reply_settings,
+            set_auto_reply_settings,
+            get_settings,
+            update_settings,
+            get_translation_settings,
+            set_translation_settings,
+            save_draft,
+            get_draft,
+            get_storage_status,
+            run_storage_gc,
+            get_storage_usage,
+            set_media_cache_cap,
+            refetch_media,
+            get_startup_recovery_report,
+            get_db_schema_version,
+            get_database_health,
+            optimize_database,
+            get_usage_metrics,
+            reset_usage_metrics,
+            get_bandwidth_stats,
+            set_bandwidth_cap,
+            add_self_task,
+            set_task_done,
+            get_self_tasks,
+            schedule_reminder,
+            cancel_reminder,
+            get_reminders,
+            get_reply_suggestions,
+            get_reply_suggestion_settings,
+            set_reply_suggestion_settings,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
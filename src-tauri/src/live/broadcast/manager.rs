@@ -489,7 +489,10 @@ pub(super) fn start_screen_broadcast_stream_accept_loop(
         while let Some((peer, mut stream)) = incoming.next().await {
             let event_tx = event_tx.clone();
             tauri::async_runtime::spawn(async move {
-                eprintln!("[Broadcast][Stream] inbound stream accepted peer={}", peer);
+                eprintln!(
+                    "[Broadcast][Stream] inbound stream accepted peer={}",
+                    crate::logging::peer(&peer)
+                );
                 let session_id = match read_broadcast_stream_header(&mut stream).await {
                     Ok(session_id) => session_id,
                     Err(error) => {
@@ -505,7 +508,8 @@ pub(super) fn start_screen_broadcast_stream_accept_loop(
                 };
                 eprintln!(
                     "[Broadcast][Stream] inbound header read peer={} session_id={}",
-                    peer, session_id
+                    crate::logging::peer(&peer),
+                    session_id
                 );
 
                 let mut first_frame_read = false;
@@ -516,7 +520,7 @@ pub(super) fn start_screen_broadcast_stream_accept_loop(
                             if !first_frame_read {
                                 eprintln!(
                                     "[Broadcast][Stream] inbound first frame read peer={} session_id={} seq={} bytes={} kind={:?} profile={}",
-                                    peer,
+                                    crate::logging::peer(&peer),
                                     session_id,
                                     frame.seq,
                                     frame.payload.len(),
@@ -575,8 +579,13 @@ impl NetworkManager {
             timestamp: now,
             chunk_hash: None,
             chunk_data: None,
+            chunk_nonce: None,
             chunk_list: None,
             sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
         };
         self.swarm
             .behaviour_mut()
@@ -688,7 +697,7 @@ impl NetworkManager {
         println!(
             "[Broadcast][Screen][{}] peer={}, backend={}, source='{}', profile={}, actual_width={}, actual_height={}, actual_fps={}, format={}, target_kbps={}, actual_kbps={:.1}, captured_frames={}, captured_fps={:.1}, encode_fps={:.1}, encode_p95_ms={:.1}, capture_drops={}, preview_drops={}, conversion_errors={}, preview_frames={}, sample_counts=raw:{},screen:{},complete:{},started:{},idle:{},blank:{},suspended:{},stopped:{},unknown:{},non_screen:{},no_image:{}, converted_frames={}, skipped_frames={}, encoded_frames={}, keyframes={}, delta_frames={}, outbound_bytes={}, encode_errors={}, worker_event_drops={}, stream_queue_drops={}, outbound_failures={}, inbound_failures={}, rejected_responses={}",
             label,
-            session.remote_peer_id,
+            crate::logging::peer(&session.remote_peer_id),
             backend,
             source,
             session.profile.label(),
@@ -750,7 +759,9 @@ impl NetworkManager {
     async fn fail_active_screen_capture(&mut self, session: &ActiveBroadcast, message: String) {
         eprintln!(
             "[Broadcast][Screen] capture failure session={} peer={}: {}",
-            session.session_id, session.remote_peer_id, message
+            session.session_id,
+            crate::logging::peer(&session.remote_peer_id),
+            message
         );
         let _ = self.app_handle.emit(
             "screen-broadcast-capture-error",
@@ -778,7 +789,7 @@ impl NetworkManager {
         let Some(connection_id) = self.voice_quic_connection_id(&peer) else {
             eprintln!(
                 "[Broadcast][QUIC] No QUIC connection id available for screen stream: peer={}",
-                peer
+                crate::logging::peer(&peer)
             );
             return false;
         };
@@ -791,7 +802,7 @@ impl NetworkManager {
 
         eprintln!(
             "[Broadcast][Stream] selected outbound QUIC connection peer={} session_id={} connection_id={:?}",
-            peer, session_id, connection_id
+            crate::logging::peer(&peer), session_id, connection_id
         );
 
         let (tx, mut rx) = tokio::sync::mpsc::channel::<BroadcastStreamRecord>(
@@ -807,7 +818,9 @@ impl NetworkManager {
             Err(error) => {
                 eprintln!(
                     "[Broadcast][QUIC] Failed to queue screen stream on {} for {}: {}",
-                    connection_id, peer, error
+                    connection_id,
+                    crate::logging::peer(&peer),
+                    error
                 );
                 return false;
             }
@@ -819,7 +832,7 @@ impl NetworkManager {
                 Ok(Ok(Ok(stream))) => {
                     eprintln!(
                             "[Broadcast][Stream] outbound stream opened peer={} session_id={} connection_id={:?}",
-                            peer, writer_session_id, connection_id
+                            crate::logging::peer(&peer), writer_session_id, connection_id
                         );
                     stream
                 }
@@ -868,7 +881,7 @@ impl NetworkManager {
             }
             eprintln!(
                 "[Broadcast][Stream] outbound header written peer={} session_id={} connection_id={:?}",
-                peer, writer_session_id, connection_id
+                crate::logging::peer(&peer), writer_session_id, connection_id
             );
 
             let mut first_frame_written = false;
@@ -895,7 +908,7 @@ impl NetworkManager {
                     if !first_frame_written {
                         eprintln!(
                             "[Broadcast][Stream] outbound first frame written peer={} session_id={} seq={} bytes={} kind={:?} profile={} connection_id={:?}",
-                            peer,
+                            crate::logging::peer(&peer),
                             writer_session_id,
                             seq,
                             bytes,
@@ -970,7 +983,7 @@ impl NetworkManager {
         eprintln!(
             "[Broadcast][Screen] starting capture worker session={} peer={} profile={}",
             session.session_id,
-            session.remote_peer_id,
+            crate::logging::peer(&session.remote_peer_id),
             session.profile.label()
         );
         let (control_tx, control_rx) = tokio::sync::mpsc::channel(16);
@@ -1070,8 +1083,13 @@ impl NetworkManager {
             timestamp: now,
             chunk_hash: None,
             chunk_data: None,
+            chunk_nonce: None,
             chunk_list: None,
             sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
         };
         self.swarm
             .behaviour_mut()
@@ -1458,7 +1476,8 @@ impl NetworkManager {
             } => {
                 eprintln!(
                     "[Broadcast][Stream] inbound failure from {}: {}",
-                    peer, error
+                    crate::logging::peer(&peer),
+                    error
                 );
                 self.screen_broadcast_stats.inbound_failures = self
                     .screen_broadcast_stats
@@ -1485,7 +1504,8 @@ impl NetworkManager {
             } => {
                 eprintln!(
                     "[Broadcast][Stream] outbound failure to {}: {}",
-                    peer, error
+                    crate::logging::peer(&peer),
+                    error
                 );
                 self.screen_broadcast_stats.outbound_failures = self
                     .screen_broadcast_stats
@@ -1579,7 +1599,8 @@ impl NetworkManager {
                     .saturating_add(1);
                 eprintln!(
                     "[Broadcast] Legacy outbound frame failure to {}: {:?}",
-                    peer, error
+                    crate::logging::peer(&peer),
+                    error
                 );
             }
             Event::InboundFailure { peer, error, .. } => {
@@ -1589,7 +1610,8 @@ impl NetworkManager {
                     .saturating_add(1);
                 eprintln!(
                     "[Broadcast] Legacy inbound frame failure from {}: {:?}",
-                    peer, error
+                    crate::logging::peer(&peer),
+                    error
                 );
             }
             Event::ResponseSent { .. } => {}
@@ -579,7 +579,7 @@ impl NetworkManager {
         let Some(connection_id) = self.voice_quic_connection_id(&peer) else {
             eprintln!(
                 "[Video][QUIC] No QUIC connection id available for video stream: peer={}",
-                peer
+                crate::logging::peer(&peer)
             );
             return false;
         };
@@ -592,7 +592,7 @@ impl NetworkManager {
 
         eprintln!(
             "[Video][Stream] selected outbound QUIC connection peer={} call_id={} connection_id={:?}",
-            peer, call_id, connection_id
+            crate::logging::peer(&peer), call_id, connection_id
         );
 
         let (tx, mut rx) =
@@ -607,7 +607,9 @@ impl NetworkManager {
             Err(e) => {
                 eprintln!(
                     "[Video][QUIC] Failed to queue video stream on {} for {}: {}",
-                    connection_id, peer, e
+                    connection_id,
+                    crate::logging::peer(&peer),
+                    e
                 );
                 return false;
             }
@@ -624,7 +626,7 @@ impl NetworkManager {
                 Ok(Ok(Ok(stream))) => {
                     eprintln!(
                             "[Video][Stream] outbound stream opened peer={} call_id={} connection_id={:?}",
-                            peer, writer_call_id, connection_id
+                            crate::logging::peer(&peer), writer_call_id, connection_id
                         );
                     stream
                 }
@@ -672,7 +674,9 @@ impl NetworkManager {
             }
             eprintln!(
                 "[Video][Stream] outbound header written peer={} call_id={} connection_id={:?}",
-                peer, writer_call_id, connection_id
+                crate::logging::peer(&peer),
+                writer_call_id,
+                connection_id
             );
 
             let mut first_frame_written = false;
@@ -697,7 +701,7 @@ impl NetworkManager {
                     if !first_frame_written {
                         eprintln!(
                             "[Video][Stream] outbound first frame written peer={} call_id={} seq={} bytes={} kind={:?} connection_id={:?}",
-                            peer, writer_call_id, seq, bytes, chunk_type, connection_id
+                            crate::logging::peer(&peer), writer_call_id, seq, bytes, chunk_type, connection_id
                         );
                         first_frame_written = true;
                     }
@@ -883,8 +887,13 @@ impl NetworkManager {
             timestamp: now,
             chunk_hash: None,
             chunk_data: None,
+            chunk_nonce: None,
             chunk_list: None,
             sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
         };
         self.swarm
             .behaviour_mut()
@@ -1446,7 +1455,7 @@ impl NetworkManager {
                     }
                     eprintln!(
                         "[Video][RemoteQuality] peer={} call_id={} profile={} reason={}",
-                        peer,
+                        crate::logging::peer(&peer),
                         call_id,
                         change.profile.label(),
                         change.reason
@@ -1458,7 +1467,11 @@ impl NetworkManager {
                 call_id,
                 error,
             } => {
-                eprintln!("[Video] Inbound stream failure from {}: {}", peer, error);
+                eprintln!(
+                    "[Video] Inbound stream failure from {}: {}",
+                    crate::logging::peer(&peer),
+                    error
+                );
                 self.video_network_stats.inbound_failures += 1;
                 if self
                     .active_call
@@ -1480,7 +1493,11 @@ impl NetworkManager {
                 call_id,
                 error,
             } => {
-                eprintln!("[Video] Outbound stream failure to {}: {}", peer, error);
+                eprintln!(
+                    "[Video] Outbound stream failure to {}: {}",
+                    crate::logging::peer(&peer),
+                    error
+                );
                 self.video_network_stats.outbound_failures += 1;
                 if self.video_stream_call_id.as_deref() == Some(call_id.as_str()) {
                     self.video_stream_tx = None;
@@ -1568,7 +1585,7 @@ impl NetworkManager {
         eprintln!(
             "[Video][Network][{}] peer={}, quic_connections={}, tcp_connections={}, profile={}, local_profile={}, remote_requested_profile={}, effective_profile={}, inbound_decode_mode={}, target_kbps={}, actual_kbps={:.1}, encoded_actual={}, capture_backend={}, capture_device='{}', capture_requested_profile={}, capture_actual={}, capture_format={}, captured_frames={}, captured_fps={:.1}, capture_dropped_i420={}, capture_dropped_preview={}, capture_conversion_errors={}, capture_preview_frames={}, capture_start_failures={}, submitted_frames={}, raw_frames_dropped={}, encoded_frames={}, keyframes={}, delta_frames={}, inbound_frames={}, inbound_seq_gaps={}, inbound_out_of_order_frames={}, outbound_failures={}, inbound_failures={}, encode_errors={}, encoded_queue_drops={}, local_rendered_frames={}, local_dropped_frames={}, local_decode_errors={}, receiver_received_frames={}, receiver_rendered_frames={}, receiver_dropped_frames={}, receiver_decode_errors={}, quality_changes={}, outbound_bytes={}, inbound_bytes={}, avg_out_bytes={:.1}, avg_in_bytes={:.1}, encode_p95_ms={:.1}",
             label,
-            peer_id,
+            crate::logging::peer(&peer_id),
             quic_count,
             tcp_count,
             effective_profile.label(),
@@ -1629,7 +1646,10 @@ pub(super) fn start_video_stream_accept_loop(
         while let Some((peer, mut stream)) = incoming.next().await {
             let event_tx = event_tx.clone();
             tauri::async_runtime::spawn(async move {
-                eprintln!("[Video][Stream] inbound stream accepted peer={}", peer);
+                eprintln!(
+                    "[Video][Stream] inbound stream accepted peer={}",
+                    crate::logging::peer(&peer)
+                );
                 let call_id = match read_video_stream_header(&mut stream).await {
                     Ok(call_id) => call_id,
                     Err(e) => {
@@ -1645,7 +1665,8 @@ pub(super) fn start_video_stream_accept_loop(
                 };
                 eprintln!(
                     "[Video][Stream] inbound header read peer={} call_id={}",
-                    peer, call_id
+                    crate::logging::peer(&peer),
+                    call_id
                 );
 
                 let mut first_frame_read = false;
@@ -1656,7 +1677,7 @@ pub(super) fn start_video_stream_accept_loop(
                                 if !first_frame_read {
                                     eprintln!(
                                         "[Video][Stream] inbound first frame read peer={} call_id={} seq={} bytes={} kind={:?}",
-                                        peer,
+                                        crate::logging::peer(&peer),
                                         call_id,
                                         frame.seq,
                                         frame.payload.len(),
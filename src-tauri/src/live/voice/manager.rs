@@ -100,7 +100,7 @@ impl NetworkManager {
         let Some(connection_id) = self.voice_quic_connection_id(&peer) else {
             eprintln!(
                 "[Voice][QUIC] No QUIC connection id available for voice stream: peer={}",
-                peer
+                crate::logging::peer(&peer)
             );
             return false;
         };
@@ -113,7 +113,7 @@ impl NetworkManager {
 
         eprintln!(
             "[Voice][Stream] selected outbound QUIC connection peer={} call_id={} connection_id={:?}",
-            peer, call_id, connection_id
+            crate::logging::peer(&peer), call_id, connection_id
         );
 
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<VoiceFrameRequest>();
@@ -127,7 +127,9 @@ impl NetworkManager {
             Err(e) => {
                 eprintln!(
                     "[Voice][QUIC] Failed to queue voice stream on {} for {}: {}",
-                    connection_id, peer, e
+                    connection_id,
+                    crate::logging::peer(&peer),
+                    e
                 );
                 return false;
             }
@@ -144,7 +146,7 @@ impl NetworkManager {
                 Ok(Ok(Ok(stream))) => {
                     eprintln!(
                         "[Voice][Stream] outbound stream opened peer={} call_id={} connection_id={:?}",
-                        peer, writer_call_id, connection_id
+                        crate::logging::peer(&peer), writer_call_id, connection_id
                     );
                     stream
                 }
@@ -192,7 +194,9 @@ impl NetworkManager {
             }
             eprintln!(
                 "[Voice][Stream] outbound header written peer={} call_id={} connection_id={:?}",
-                peer, writer_call_id, connection_id
+                crate::logging::peer(&peer),
+                writer_call_id,
+                connection_id
             );
 
             let mut first_frame_written = false;
@@ -217,7 +221,7 @@ impl NetworkManager {
                 if !first_frame_written {
                     eprintln!(
                         "[Voice][Stream] outbound first frame written peer={} call_id={} seq={} bytes={} connection_id={:?}",
-                        peer,
+                        crate::logging::peer(&peer),
                         frame.call_id,
                         frame.seq,
                         frame.payload.len(),
@@ -259,8 +263,13 @@ impl NetworkManager {
             timestamp: now,
             chunk_hash: None,
             chunk_data: None,
+            chunk_nonce: None,
             chunk_list: None,
             sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
         };
         self.swarm
             .behaviour_mut()
@@ -334,8 +343,13 @@ impl NetworkManager {
             timestamp: now,
             chunk_hash: None,
             chunk_data: None,
+            chunk_nonce: None,
             chunk_list: None,
             sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
         };
         self.swarm
             .behaviour_mut()
@@ -806,7 +820,11 @@ impl NetworkManager {
                 call_id,
                 error,
             } => {
-                eprintln!("[Voice] Inbound stream failure from {}: {}", peer, error);
+                eprintln!(
+                    "[Voice] Inbound stream failure from {}: {}",
+                    crate::logging::peer(&peer),
+                    error
+                );
                 self.voice_network_stats.inbound_failures += 1;
                 if self
                     .active_call
@@ -827,7 +845,11 @@ impl NetworkManager {
                 call_id,
                 error,
             } => {
-                eprintln!("[Voice] Outbound stream failure to {}: {}", peer, error);
+                eprintln!(
+                    "[Voice] Outbound stream failure to {}: {}",
+                    crate::logging::peer(&peer),
+                    error
+                );
                 self.voice_network_stats.outbound_failures += 1;
                 if self.voice_stream_call_id.as_deref() == Some(call_id.as_str()) {
                     self.voice_stream_tx = None;
@@ -861,7 +883,10 @@ pub(super) fn start_voice_stream_accept_loop(
         while let Some((peer, mut stream)) = incoming.next().await {
             let event_tx = event_tx.clone();
             tauri::async_runtime::spawn(async move {
-                eprintln!("[Voice][Stream] inbound stream accepted peer={}", peer);
+                eprintln!(
+                    "[Voice][Stream] inbound stream accepted peer={}",
+                    crate::logging::peer(&peer)
+                );
                 let call_id = match read_voice_stream_header(&mut stream).await {
                     Ok(call_id) => call_id,
                     Err(e) => {
@@ -877,7 +902,8 @@ pub(super) fn start_voice_stream_accept_loop(
                 };
                 eprintln!(
                     "[Voice][Stream] inbound header read peer={} call_id={}",
-                    peer, call_id
+                    crate::logging::peer(&peer),
+                    call_id
                 );
 
                 let mut first_frame_read = false;
@@ -887,7 +913,7 @@ pub(super) fn start_voice_stream_accept_loop(
                             if !first_frame_read {
                                 eprintln!(
                                     "[Voice][Stream] inbound first frame read peer={} call_id={} seq={} bytes={}",
-                                    peer,
+                                    crate::logging::peer(&peer),
                                     call_id,
                                     frame.seq,
                                     frame.payload.len()
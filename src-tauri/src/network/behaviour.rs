@@ -1,6 +1,9 @@
 use libp2p::{
-    dcutr, gossipsub, identify, identity::Keypair, kad, ping, relay, request_response,
-    swarm::NetworkBehaviour,
+    dcutr, gossipsub, identify,
+    identity::Keypair,
+    kad, ping, relay, request_response,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
+    upnp,
 };
 
 use super::direct_message::{DirectMessageRequest, DirectMessageResponse};
@@ -32,36 +35,48 @@ pub struct RChatBehaviour {
     pub broadcast_stream: crate::network::voice_stream::Behaviour,
     // Dedicated DM screen-broadcast media transport
     pub broadcast: request_response::cbor::Behaviour<BroadcastFrameRequest, BroadcastFrameResponse>,
+    // Chunk streams for large files
+    pub blob_stream: crate::network::voice_stream::Behaviour,
 
     // Circuit Relay Client - for NAT traversal via public relays
     pub relay_client: relay::client::Behaviour,
 
     // DCUtR - Direct Connection Upgrade through Relay (hole punching)
     pub dcutr: dcutr::Behaviour,
+
+    // UPnP - Port mapping on the router, only with a fixed listen port
+    pub upnp: Toggle<upnp::tokio::Behaviour>,
 }
 
 impl RChatBehaviour {
-    pub fn new(key: Keypair, relay_client: relay::client::Behaviour) -> Self {
+    pub fn new(key: Keypair, relay_client: relay::client::Behaviour, upnp: bool) -> Self {
         let peer_id = key.public().to_peer_id();
 
         // 1. Gossipsub (Group Chat)
         let gossipsub_config = gossipsub::Config::default();
-        let gossipsub = gossipsub::Behaviour::new(
+        let mut gossipsub = gossipsub::Behaviour::new(
             gossipsub::MessageAuthenticity::Signed(key.clone()),
             gossipsub_config,
         )
         .expect("Invalid gossipsub config");
+        // Peer scoring; the app-specific part is our persisted peer reputation.
+        let (score_params, score_thresholds) = crate::network::reputation::gossipsub_scoring();
+        gossipsub
+            .with_peer_score(score_params, score_thresholds)
+            .expect("Invalid gossipsub peer score params");
 
         // 2. Kademlia (Discovery)
         let store = kad::store::MemoryStore::new(peer_id);
         let kademlia = kad::Behaviour::new(peer_id, store);
 
-        // 3. MDNS (Local Discovery) - REPLACED by native mdns-sd (see network/mdns_sd.rs)
+        // 3. MDNS (Local Discovery) - handled outside the swarm by network/mdns.rs
         // We use native OS mDNS service to avoid UDP port 5353 conflicts and VPN routing issues.
 
         // 4. Identify (Handshake)
-        let identify =
-            identify::Behaviour::new(identify::Config::new("rchat/1.0.0".into(), key.public()));
+        let identify = identify::Behaviour::new(
+            identify::Config::new("rchat/1.0.0".into(), key.public())
+                .with_agent_version(crate::network::capabilities::local_agent_version()),
+        );
 
         // 5. Ping (Health)
         let ping = ping::Behaviour::default();
@@ -69,7 +84,7 @@ impl RChatBehaviour {
         // 6. Request-Response (Direct 1:1 Messages)
         let direct_message = request_response::cbor::Behaviour::new(
             [(
-                libp2p::StreamProtocol::new("/rchat/dm/1.0.0"),
+                libp2p::StreamProtocol::new(super::direct_message::DM_PROTOCOL),
                 request_response::ProtocolSupport::Full,
             )],
             request_response::Config::default(),
@@ -99,9 +114,17 @@ impl RChatBehaviour {
             request_response::Config::default(),
         );
 
+        // 6f. Stream protocol (Large File Chunks)
+        let blob_stream = crate::network::voice_stream::Behaviour::new(
+            libp2p::StreamProtocol::new(crate::network::blob_stream::BLOB_PROTOCOL),
+        );
+
         // 7. DCUtR (Hole Punching)
         let dcutr = dcutr::Behaviour::new(peer_id);
 
+        // 8. UPnP (Port Forwarding)
+        let upnp = Toggle::from(upnp.then(upnp::tokio::Behaviour::default));
+
         Self {
             gossipsub,
             kademlia,
@@ -112,8 +135,10 @@ impl RChatBehaviour {
             video_call,
             broadcast_stream,
             broadcast,
+            blob_stream,
             relay_client,
             dcutr,
+            upnp,
         }
     }
 }
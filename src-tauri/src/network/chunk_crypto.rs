@@ -0,0 +1,103 @@
+//! End-to-end encryption for file-transfer chunk payloads.
+//!
+//! Noise only protects a single libp2p connection; chunks forwarded through a
+//! relay (or over a future transport) must stay opaque to everything but the two
+//! peers. Each side converts its ed25519 identity to X25519 and runs DH against
+//! the other side's identity key, recovered from its `PeerId`, so the per-peer
+//! session key needs no extra handshake.
+
+use libp2p::{identity, PeerId};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use x25519_dalek::StaticSecret;
+
+use super::keys;
+
+const KEY_CONTEXT: &[u8] = b"rchat-chunk-v1";
+
+fn derive_key(secret: &StaticSecret, peer: &PeerId) -> Option<[u8; 32]> {
+    let shared = secret.diffie_hellman(&keys::x25519_public(peer)?);
+    if !shared.was_contributory() {
+        return None;
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_CONTEXT);
+    hasher.update(shared.as_bytes());
+    Some(hasher.finalize().into())
+}
+
+/// Per-peer chunk keys derived from the local identity, cached by `PeerId`.
+pub struct ChunkKeyring {
+    secret: Option<StaticSecret>,
+    keys: HashMap<PeerId, [u8; 32]>,
+}
+
+impl ChunkKeyring {
+    pub fn new(local: &identity::Keypair) -> Self {
+        let secret = keys::x25519_secret(local);
+        if secret.is_none() {
+            eprintln!(
+                "[ChunkTransfer] ⚠️ Local identity is not ed25519; chunk encryption unavailable"
+            );
+        }
+        Self {
+            secret,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Session key shared with `peer`, or `None` if its identity key can't be
+    /// recovered from the `PeerId`.
+    pub fn key_for(&mut self, peer: &PeerId) -> Option<[u8; 32]> {
+        if let Some(key) = self.keys.get(peer) {
+            return Some(*key);
+        }
+        let key = derive_key(self.secret.as_ref()?, peer)?;
+        self.keys.insert(*peer, key);
+        Some(key)
+    }
+}
+
+/// Encrypt a chunk; returns `(ciphertext_b64, nonce_b64)`.
+pub fn seal_chunk(key: &[u8; 32], chunk: &[u8]) -> Result<(String, String), String> {
+    keys::seal(key, chunk).map_err(|e| format!("chunk encryption failed: {}", e))
+}
+
+pub fn open_chunk(
+    key: &[u8; 32],
+    ciphertext_b64: &str,
+    nonce_b64: &str,
+) -> Result<Vec<u8>, String> {
+    keys::open(key, ciphertext_b64, nonce_b64)
+        .map_err(|e| format!("chunk decryption failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_peers_derive_the_same_key() {
+        let alice = identity::Keypair::generate_ed25519();
+        let bob = identity::Keypair::generate_ed25519();
+        let mut alice_ring = ChunkKeyring::new(&alice);
+        let mut bob_ring = ChunkKeyring::new(&bob);
+
+        let alice_key = alice_ring.key_for(&bob.public().to_peer_id());
+        let bob_key = bob_ring.key_for(&alice.public().to_peer_id());
+        assert!(alice_key.is_some());
+        assert_eq!(alice_key, bob_key);
+
+        let carol = identity::Keypair::generate_ed25519();
+        assert_ne!(alice_ring.key_for(&carol.public().to_peer_id()), alice_key);
+    }
+
+    #[test]
+    fn sealed_chunk_round_trips_only_with_the_right_key() {
+        let chunk = vec![0u8, 159, 146, 150, 255, 1, 2, 3];
+        let key = [7u8; 32];
+        let (ciphertext, nonce) = seal_chunk(&key, &chunk).expect("seal");
+        assert_eq!(open_chunk(&key, &ciphertext, &nonce).expect("open"), chunk);
+        assert!(open_chunk(&[8u8; 32], &ciphertext, &nonce).is_err());
+    }
+}
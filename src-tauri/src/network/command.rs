@@ -1,4 +1,4 @@
-use crate::network::gossip::GroupMessageEnvelope;
+use crate::network::gossip::{GroupAdminAction, GroupMessageEnvelope};
 
 #[derive(Debug, Clone)]
 pub enum DirectMediaKind {
@@ -9,7 +9,16 @@ pub enum DirectMediaKind {
     Audio,
 }
 
-#[derive(Debug, Clone)]
+/// An mDNS-discovered peer as reported by `get_local_peers`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LocalPeerInfo {
+    pub peer_id: String,
+    pub addresses: Vec<String>,
+    pub alias: Option<String>,
+    pub connected: bool,
+}
+
+#[derive(Debug)]
 pub enum NetworkCommand {
     StartPunch {
         multiaddr: String,
@@ -27,6 +36,17 @@ pub enum NetworkCommand {
         password: String,
         my_username: String,
     },
+    /// The user canceled their invite for `invitee`; stop polling for a shadow.
+    CancelShadowPoll {
+        invitee: String,
+    },
+    /// The user accepted a pending friend request.
+    AcceptFriendRequest {
+        github_username: String,
+        peer_id: String,
+    },
+    /// Identity keys changed on disk (key rotation); re-read them.
+    ReloadIdentityKeys,
     RegisterTemporarySession {
         chat_id: String,
         peer_id: String,
@@ -42,20 +62,56 @@ pub enum NetworkCommand {
     UnsubscribeGroup {
         group_id: String,
     },
+    /// Start a new key epoch for a group we administer, after its members changed.
+    RekeyGroup {
+        group_id: String,
+    },
+    /// Tell a group's members about an admin action we applied locally.
+    PublishGroupControl {
+        group_id: String,
+        action: GroupAdminAction,
+    },
+    /// Sign a group invite and accept it until it expires; replies with the
+    /// code.
+    CreateGroupInvite {
+        invite: crate::network::group_invite::GroupInvite,
+        reply: tokio::sync::oneshot::Sender<Result<String, String>>,
+    },
+    /// Redeem a verified group invite with its inviter.
+    JoinGroup {
+        invite: crate::network::group_invite::GroupInvite,
+    },
+    /// Backfill a group's history from one of its admins.
+    RequestGroupHistory {
+        group_id: String,
+        peer_id: libp2p::PeerId,
+    },
     PublishGroup {
         envelope: GroupMessageEnvelope,
     },
+    SendGroupTyping {
+        group_id: String,
+        sender_alias: Option<String>,
+        typing: bool,
+    },
     SendDirectText {
         target_peer_id: String,
         msg_id: String,
         timestamp: i64,
         sender_alias: Option<String>,
         content: String,
+        translation: Option<crate::network::direct_message::MessageTranslation>,
     },
     SendReadReceipt {
         target_peer_id: String,
         msg_ids: Vec<String>,
     },
+    /// Send our profile alias/avatar to connected friends after it changed.
+    BroadcastIdentity,
+    /// Our alias changed; re-register the mDNS service with it.
+    UpdateMdnsAlias {
+        alias: Option<String>,
+    },
     SendDirectMedia {
         kind: DirectMediaKind,
         target_peer_id: String,
@@ -64,6 +120,11 @@ pub enum NetworkCommand {
         msg_id: String,
         timestamp: i64,
     },
+    /// Download a file we have metadata for (e.g. evicted media) from `peer_id`.
+    FetchFile {
+        peer_id: String,
+        file_hash: String,
+    },
     StartVoiceCall {
         peer_id: String,
     },
@@ -142,4 +203,23 @@ pub enum NetworkCommand {
     EndScreenBroadcast {
         session_id: String,
     },
+    GetLocalPeers {
+        reply: tokio::sync::oneshot::Sender<Vec<LocalPeerInfo>>,
+    },
+    GetConnectionPath {
+        peer_id: libp2p::PeerId,
+        reply: tokio::sync::oneshot::Sender<crate::network::path::PathReport>,
+    },
+    TestPeerConnection {
+        peer_id: libp2p::PeerId,
+        reply: tokio::sync::oneshot::Sender<crate::network::probe::PeerConnectionTest>,
+    },
+    GetPeerQuality {
+        peer_id: libp2p::PeerId,
+        reply: tokio::sync::oneshot::Sender<Option<crate::network::quality::PeerQuality>>,
+    },
+    GetPeerCapabilities {
+        peer_id: libp2p::PeerId,
+        reply: tokio::sync::oneshot::Sender<Option<Vec<crate::network::capabilities::Capability>>>,
+    },
 }
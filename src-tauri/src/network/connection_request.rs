@@ -0,0 +1,120 @@
+//! Signed connect requests ("user pressed Connect"), sent to the target over
+//! the direct-message protocol.
+//!
+//! The sender signs `(from, to, timestamp)` with its libp2p ed25519 identity.
+//! The receiver recovers the key from the sender's `PeerId`, as
+//! `chunk_crypto` does, and rejects bad signatures, requests meant for someone
+//! else and stale timestamps before telling the UI.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use libp2p::{identity, PeerId};
+use serde::{Deserialize, Serialize};
+
+const SIGNING_CONTEXT: &str = "rchat-connection-request-v1";
+/// Multihash code for "identity": the public key is inlined in the `PeerId`.
+const IDENTITY_MULTIHASH_CODE: u64 = 0x00;
+/// Requests further than this from our clock are refused, limiting replays.
+pub const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// Carried as JSON in `DirectMessageRequest::text_content`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedConnectionRequest {
+    pub from_peer_id: String,
+    pub to_peer_id: String,
+    pub timestamp: i64,
+    /// Base64 ed25519 signature over [`signing_payload`].
+    pub signature: String,
+}
+
+fn signing_payload(from: &str, to: &str, timestamp: i64) -> Vec<u8> {
+    format!("{}\n{}\n{}\n{}", SIGNING_CONTEXT, from, to, timestamp).into_bytes()
+}
+
+pub fn sign(
+    local: &identity::Keypair,
+    to: &PeerId,
+    timestamp: i64,
+) -> Result<SignedConnectionRequest, String> {
+    let from_peer_id = local.public().to_peer_id().to_string();
+    let to_peer_id = to.to_string();
+    let signature = local
+        .sign(&signing_payload(&from_peer_id, &to_peer_id, timestamp))
+        .map_err(|e| format!("Failed to sign connection request: {}", e))?;
+    Ok(SignedConnectionRequest {
+        from_peer_id,
+        to_peer_id,
+        timestamp,
+        signature: BASE64.encode(signature),
+    })
+}
+
+/// Check a request that arrived from `sender` (the authenticated connection
+/// peer) for us (`local`).
+pub fn verify(
+    request: &SignedConnectionRequest,
+    sender: &PeerId,
+    local: &PeerId,
+    now: i64,
+) -> Result<(), String> {
+    if request.from_peer_id != sender.to_string() {
+        return Err("connection request sender does not match the connection".to_string());
+    }
+    if request.to_peer_id != local.to_string() {
+        return Err("connection request is for another peer".to_string());
+    }
+    if (now - request.timestamp).abs() > MAX_CLOCK_SKEW_SECS {
+        return Err("connection request timestamp out of range".to_string());
+    }
+
+    let multihash = sender.as_ref();
+    if multihash.code() != IDENTITY_MULTIHASH_CODE {
+        return Err("sender key is not recoverable from its peer id".to_string());
+    }
+    let public = identity::PublicKey::try_decode_protobuf(multihash.digest())
+        .map_err(|e| format!("invalid sender key: {}", e))?;
+    let signature = BASE64
+        .decode(&request.signature)
+        .map_err(|_| "connection request signature is not base64".to_string())?;
+    let payload = signing_payload(
+        &request.from_peer_id,
+        &request.to_peer_id,
+        request.timestamp,
+    );
+    if !public.verify(&payload, &signature) {
+        return Err("invalid connection request signature".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_untampered_requests_for_us_verify() {
+        let alice = identity::Keypair::generate_ed25519();
+        let bob = identity::Keypair::generate_ed25519();
+        let alice_id = alice.public().to_peer_id();
+        let bob_id = bob.public().to_peer_id();
+
+        let request = sign(&alice, &bob_id, 1_000).expect("sign");
+        assert!(verify(&request, &alice_id, &bob_id, 1_010).is_ok());
+
+        // Replayed much later, or claimed by someone else.
+        assert!(verify(
+            &request,
+            &alice_id,
+            &bob_id,
+            1_000 + MAX_CLOCK_SKEW_SECS + 1
+        )
+        .is_err());
+        assert!(verify(&request, &bob_id, &bob_id, 1_010).is_err());
+
+        let mut forged = request.clone();
+        forged.timestamp = 1_005;
+        assert!(verify(&forged, &alice_id, &bob_id, 1_010).is_err());
+
+        let carol = identity::Keypair::generate_ed25519().public().to_peer_id();
+        assert!(verify(&request, &alice_id, &carol, 1_010).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Network diagnostics snapshot for the settings/debug panel.
+//!
+//! `NetworkManager` keeps a [`NetworkStats`] in `NetworkState` up to date (peer
+//! counts and gossipsub mesh sizes on every heartbeat, dial failures as they
+//! happen); `get_network_diagnostics` combines it with the listener and STUN
+//! results already stored there.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+
+const MAX_RECENT_DIAL_FAILURES: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DialFailure {
+    pub at: i64,
+    pub peer_id: Option<String>,
+    pub source: String,
+    pub address: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkStats {
+    pub connected_peers: usize,
+    pub discovered_local_peers: usize,
+    pub mesh_sizes: BTreeMap<String, usize>, // gossipsub topic -> mesh peer count
+    pub recent_dial_failures: VecDeque<DialFailure>, // newest last
+    pub updated_at: i64,
+}
+
+impl NetworkStats {
+    pub fn record_dial_failure(&mut self, failure: DialFailure) {
+        self.recent_dial_failures.push_back(failure);
+        while self.recent_dial_failures.len() > MAX_RECENT_DIAL_FAILURES {
+            self.recent_dial_failures.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NatStatus {
+    /// STUN did not answer; reachability is unknown.
+    Unknown,
+    /// A listener is bound directly on the STUN-reported public address.
+    Public,
+    /// STUN found a public mapping that differs from every local listener.
+    BehindNat,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkDiagnostics {
+    pub listen_addresses: Vec<String>,
+    pub external_addresses: Vec<String>,
+    pub stun_external_port: Option<u16>,
+    pub connected_peers: usize,
+    pub discovered_local_peers: usize,
+    pub mesh_sizes: BTreeMap<String, usize>,
+    pub nat_status: NatStatus,
+    pub recent_dial_failures: Vec<DialFailure>,
+    pub stats_updated_at: i64,
+}
+
+pub fn classify_nat(listen_addresses: &[String], external_ips: &[String]) -> NatStatus {
+    if external_ips.is_empty() {
+        return NatStatus::Unknown;
+    }
+    let bound_publicly = external_ips.iter().any(|ip| {
+        listen_addresses.iter().any(|addr| {
+            addr.split('/')
+                .collect::<Vec<_>>()
+                .windows(2)
+                .any(|w| matches!(w[0], "ip4" | "ip6") && w[1] == ip)
+        })
+    });
+    if bound_publicly {
+        NatStatus::Public
+    } else {
+        NatStatus::BehindNat
+    }
+}
+
+/// Human-readable name for a gossipsub topic (group id when it maps to one).
+pub fn topic_label(topic: &str) -> String {
+    crate::network::gossip::group_id_from_topic(topic).unwrap_or_else(|| topic.to_string())
+}
+
+pub async fn snapshot(state: &crate::NetworkState) -> NetworkDiagnostics {
+    let listen_addresses = state.listening_addresses.lock().await.clone();
+    let external_ips: Vec<String> = [
+        state.public_address_v4.lock().await.clone(),
+        state.public_address_v6.lock().await.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let stun_external_port = *state.stun_external_port.lock().await;
+    let stats = state.network_stats.lock().await.clone();
+
+    let external_addresses = external_ips
+        .iter()
+        .map(|ip| match stun_external_port {
+            Some(port) if ip.contains(':') => format!("[{}]:{}", ip, port),
+            Some(port) => format!("{}:{}", ip, port),
+            None => ip.clone(),
+        })
+        .collect();
+
+    NetworkDiagnostics {
+        nat_status: classify_nat(&listen_addresses, &external_ips),
+        listen_addresses,
+        external_addresses,
+        stun_external_port,
+        connected_peers: stats.connected_peers,
+        discovered_local_peers: stats.discovered_local_peers,
+        mesh_sizes: stats.mesh_sizes,
+        recent_dial_failures: stats.recent_dial_failures.into_iter().collect(),
+        stats_updated_at: stats.updated_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_nat_from_listeners_and_stun() {
+        let listeners = vec![
+            "/ip4/192.168.1.20/udp/4001/quic-v1".to_string(),
+            "/ip6/2001:db8::5/tcp/4001".to_string(),
+        ];
+        assert_eq!(classify_nat(&listeners, &[]), NatStatus::Unknown);
+        assert_eq!(
+            classify_nat(&listeners, &["203.0.113.9".to_string()]),
+            NatStatus::BehindNat
+        );
+        assert_eq!(
+            classify_nat(&listeners, &["2001:db8::5".to_string()]),
+            NatStatus::Public
+        );
+    }
+
+    #[test]
+    fn keeps_only_recent_dial_failures() {
+        let mut stats = NetworkStats::default();
+        for i in 0..(MAX_RECENT_DIAL_FAILURES + 5) {
+            stats.record_dial_failure(DialFailure {
+                at: i as i64,
+                peer_id: None,
+                source: "unknown".to_string(),
+                address: None,
+                error: "timeout".to_string(),
+            });
+        }
+        assert_eq!(stats.recent_dial_failures.len(), MAX_RECENT_DIAL_FAILURES);
+        assert_eq!(stats.recent_dial_failures.front().map(|f| f.at), Some(5));
+    }
+}
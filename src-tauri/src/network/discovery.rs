@@ -1,24 +1,29 @@
-use std::time::Duration;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 // use reqwest::Client; // Removed
 use libp2p::Multiaddr;
 use tokio::sync::mpsc::Sender;
 // use serde::{Deserialize, Serialize}; // Unused
+use crate::network::discovery_backend::{Backend, DiscoveryBackend};
 use crate::network::gist; // Import new module
-use crate::network::hks::{HksTree, TrackedInvite};
+use crate::network::hks::{self, HksTree, KeyRotation, TrackedInvite};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use ed25519_dalek::{SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
+use crate::storage::config::{Config, FriendConfig};
 use crate::AppState;
 use tauri::Manager;
 
-pub async fn discover_peers(sender: Sender<Multiaddr>, app: tauri::AppHandle) {
+pub async fn discover_peers(sender: Sender<Multiaddr>, app: crate::AppHandle) {
     let mut interval = tokio::time::interval(Duration::from_secs(120));
     loop {
         interval.tick().await;
 
         // 1. Fetch Config (Friends + My Keys)
-        let (friends, my_secret, my_pubkey_b64, github_sync_enabled) = {
+        let (friends, my_secret, my_pubkey_b64, github_sync_enabled, backend) = {
             let state = app.state::<AppState>();
             let mgr = state.config_manager.lock().await;
             if let Ok(config) = mgr.load().await {
@@ -49,9 +54,10 @@ pub async fn discover_peers(sender: Sender<Multiaddr>, app: tauri::AppHandle) {
                     secret,
                     pubkey_b64,
                     config.user.connectivity.github_sync_enabled,
+                    Some(Backend::from_system(&config.system)),
                 )
             } else {
-                (vec![], None, None, false)
+                (vec![], None, None, false, None)
             }
         };
 
@@ -59,6 +65,9 @@ pub async fn discover_peers(sender: Sender<Multiaddr>, app: tauri::AppHandle) {
             continue;
         }
 
+        let Some(backend) = backend else {
+            continue;
+        };
         if friends.is_empty() || my_secret.is_none() || my_pubkey_b64.is_none() {
             continue;
         }
@@ -75,7 +84,8 @@ pub async fn discover_peers(sender: Sender<Multiaddr>, app: tauri::AppHandle) {
                     if let Ok(friend_verifying_key) =
                         VerifyingKey::from_bytes(&friend_ed_key_bytes.try_into().unwrap())
                     {
-                        if let Ok(addrs) = fetch_friend_peers(
+                        if let Ok((addrs, rotation)) = fetch_friend_peers(
+                            &backend,
                             &friend.username,
                             &friend_verifying_key,
                             &my_secret,
@@ -83,6 +93,11 @@ pub async fn discover_peers(sender: Sender<Multiaddr>, app: tauri::AppHandle) {
                         )
                         .await
                         {
+                            if let Some(rotation) = rotation {
+                                adopt_friend_rotation(&app, &friend.username, &rotation).await;
+                            }
+                            let mut addrs = addrs;
+                            crate::network::path::rank_addresses(&mut addrs);
                             for addr in addrs {
                                 let _ = sender.send(addr).await;
                             }
@@ -94,21 +109,261 @@ pub async fn discover_peers(sender: Sender<Multiaddr>, app: tauri::AppHandle) {
     }
 }
 
+/// Republish an unchanged blob after this long anyway, so invitations that
+/// expired in the meantime are dropped from it.
+const REPUBLISH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Fingerprint of the inputs of the last successful publish. The blob itself
+/// differs on every export, so it cannot be compared directly.
+static LAST_PUBLISHED: Mutex<Option<([u8; 32], Instant)>> = Mutex::new(None);
+
+/// Publish on the next attempt even if nothing changed, e.g. after the
+/// backend was switched.
+pub fn forget_last_published() {
+    if let Ok(mut last) = LAST_PUBLISHED.lock() {
+        *last = None;
+    }
+}
+
+/// The HKS tree of the last publish, with its encrypted links, and the X25519
+/// key its roster was sealed with. Its node keys are also kept in `hks_nodes`
+/// in the config, and each friend's leaf in their `leaf_index`.
+static HKS_TREE: Mutex<Option<([u8; 32], HksTree)>> = Mutex::new(None);
+
+/// The cached tree, unless our encryption key changed since it was built.
+fn take_cached_tree(my_secret: &StaticSecret) -> Option<HksTree> {
+    let mine = X25519PublicKey::from(my_secret).to_bytes();
+    HKS_TREE
+        .lock()
+        .ok()
+        .and_then(|mut cached| cached.take())
+        .filter(|(owner, _)| *owner == mine)
+        .map(|(_, tree)| tree)
+}
+
+fn store_cached_tree(my_secret: &StaticSecret, tree: HksTree) {
+    if let Ok(mut cached) = HKS_TREE.lock() {
+        *cached = Some((X25519PublicKey::from(my_secret).to_bytes(), tree));
+    }
+}
+
+/// Bring the tree's roster in line with the friend list. Friends no longer
+/// listed, or listed under a rotated key, are dropped and their path to the
+/// root rekeyed; new ones go on their stored leaf if it has room. Returns
+/// whether `config` changed and needs saving.
+fn sync_hks_tree(
+    cached: Option<HksTree>,
+    config: &mut Config,
+    my_secret: &StaticSecret,
+) -> (HksTree, bool) {
+    let mut changed = false;
+    let mut tree = match cached {
+        Some(tree) => tree,
+        None => HksTree::from_node_keys(&config.user.hks_nodes).unwrap_or_else(|_| {
+            changed = true;
+            HksTree::new()
+        }),
+    };
+
+    let listed: HashSet<&str> = config
+        .user
+        .friends
+        .iter()
+        .filter_map(|friend| friend.x25519_pubkey.as_deref())
+        .collect();
+    let gone: Vec<String> = tree
+        .roster
+        .keys()
+        .filter(|key| !listed.contains(key.as_str()))
+        .cloned()
+        .collect();
+    for key in gone {
+        match tree.remove_friend(&key, my_secret) {
+            Ok(_) => changed = true,
+            Err(e) => eprintln!("[Discovery] Failed to rekey removed friend: {}", e),
+        }
+    }
+
+    for friend in config.user.friends.iter_mut() {
+        let Some(key) = friend.x25519_pubkey.as_deref() else {
+            continue;
+        };
+        if tree.roster.contains_key(key) {
+            continue;
+        }
+        let on_stored_leaf = HksTree::is_leaf(friend.leaf_index)
+            && tree
+                .add_friend_to_leaf(&friend.username, key, my_secret, friend.leaf_index)
+                .is_ok();
+        if on_stored_leaf {
+            continue;
+        }
+        match tree.add_friend(&friend.username, key, my_secret) {
+            Ok(leaf_index) => {
+                friend.leaf_index = leaf_index;
+                changed = true;
+            }
+            Err(e) => eprintln!(
+                "Failed to add friend {} to HKS tree: {}",
+                friend.username, e
+            ),
+        }
+    }
+
+    if changed {
+        config.user.hks_nodes = tree.node_keys_b64();
+    }
+    (tree, changed)
+}
+
+/// Rekey a removed friend's leaf path in the stored tree, so the keys they
+/// hold stop working for what we publish next. Call before saving `config`.
+pub fn rekey_removed_friend(config: &mut Config, friend: &FriendConfig) -> anyhow::Result<()> {
+    let Some(my_secret) = config
+        .user
+        .encryption_private_key
+        .as_deref()
+        .and_then(|s| BASE64.decode(s).ok())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(StaticSecret::from)
+    else {
+        return Ok(());
+    };
+    let mut tree = match take_cached_tree(&my_secret) {
+        Some(tree) => tree,
+        None => match HksTree::from_node_keys(&config.user.hks_nodes) {
+            Ok(tree) => tree,
+            // No stored tree: the next publish starts from fresh keys anyway.
+            Err(_) => return Ok(()),
+        },
+    };
+
+    let removed = match friend.x25519_pubkey.as_deref() {
+        Some(key) => tree.remove_friend(key, &my_secret),
+        None => Ok(None),
+    };
+    let result = match removed {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) if HksTree::is_leaf(friend.leaf_index) => {
+            tree.rekey_path(friend.leaf_index, &my_secret).map(|_| ())
+        }
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    config.user.hks_nodes = tree.node_keys_b64();
+    store_cached_tree(&my_secret, tree);
+    forget_last_published();
+    result
+}
+
+fn publish_fingerprint(addrs: &[String], config: &crate::storage::config::Config) -> [u8; 32] {
+    let mut addrs = addrs.to_vec();
+    addrs.sort();
+    let mut hasher = Sha256::new();
+    for addr in &addrs {
+        hasher.update(addr.as_bytes());
+        hasher.update([0]);
+    }
+    for friend in &config.user.friends {
+        hasher.update(friend.username.as_bytes());
+        hasher.update([0]);
+        hasher.update(friend.x25519_pubkey.as_deref().unwrap_or("").as_bytes());
+        hasher.update([0]);
+    }
+    for invite in config.user.pending_invitations.iter().flatten() {
+        hasher.update(invite.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(
+        config
+            .user
+            .identity_public_key
+            .as_deref()
+            .unwrap_or("")
+            .as_bytes(),
+    );
+    for rotation in &config.user.key_rotations {
+        hasher.update([0]);
+        hasher.update(rotation.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+fn unchanged_since_last_publish(fingerprint: &[u8; 32]) -> bool {
+    LAST_PUBLISHED.lock().is_ok_and(|last| {
+        last.is_some_and(|(previous, at)| {
+            previous == *fingerprint && at.elapsed() < REPUBLISH_INTERVAL
+        })
+    })
+}
+
+/// Add our STUN-discovered public IPv6 address on the port of every global
+/// IPv6 listener. IPv6 is rarely behind NAT, so friends can dial it directly.
+fn with_public_ipv6(mut addrs: Vec<String>, public_v6: Option<&str>) -> Vec<String> {
+    use libp2p::multiaddr::Protocol;
+
+    let Some(public_v6) = public_v6.and_then(|ip| ip.parse::<std::net::Ipv6Addr>().ok()) else {
+        return addrs;
+    };
+    let extra: Vec<String> = addrs
+        .iter()
+        .filter_map(|addr| addr.parse::<Multiaddr>().ok())
+        .filter(|addr| {
+            matches!(addr.iter().next(), Some(Protocol::Ip6(ip))
+                if crate::network::path::classify(addr) == crate::network::path::ConnectionPath::Direct
+                    && !ip.is_unspecified())
+        })
+        .map(|addr| {
+            addr.replace(0, |_| Some(Protocol::Ip6(public_v6)))
+                .unwrap_or(addr)
+                .to_string()
+        })
+        .collect();
+    for addr in extra {
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+    addrs
+}
+
 pub async fn publish_peer_info(
-    token: &str,
+    backend: &impl DiscoveryBackend,
     addrs: Vec<String>,
-    app: tauri::AppHandle,
+    app: crate::AppHandle,
 ) -> anyhow::Result<()> {
+    let public_v6 = match app.try_state::<crate::NetworkState>() {
+        Some(net_state) => net_state.public_address_v6.lock().await.clone(),
+        None => None,
+    };
+    let addrs = with_public_ipv6(addrs, public_v6.as_deref());
+
     // 1. Prepare Content (HKS Blob) and extract pending invitations
-    let (blob_content, pending_invites) = {
+    let (blob_content, pending_invites, fingerprint) = {
         let state = app.state::<AppState>();
         let mgr = state.config_manager.lock().await;
         // Load config to access keys and friends
-        let config = mgr
+        let mut config = mgr
             .load()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to read config: {}", e))?;
 
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if gist::prune_pending_invitations(&mut config.user.pending_invitations, now, None) > 0 {
+            if let Err(e) = mgr.save(&config).await {
+                eprintln!("[Discovery] Failed to drop expired invitations: {}", e);
+            }
+        }
+
+        let fingerprint = publish_fingerprint(&addrs, &config);
+        if unchanged_since_last_publish(&fingerprint) {
+            println!("[Discovery] Addresses and friends unchanged, skipping publish");
+            return Ok(());
+        }
+
         // Get Identity Keys
         let identity_priv_b64 = config
             .user
@@ -128,42 +383,51 @@ pub async fn publish_peer_info(
         let encryption_secret_bytes = BASE64.decode(encryption_priv_b64)?;
         let encryption_secret =
             StaticSecret::from(encryption_secret_bytes.try_into().unwrap_or([0; 32]));
-        let encryption_pubkey = X25519PublicKey::from(&encryption_secret);
-
-        // Build Tree
-        let mut tree = HksTree::new();
-
-        // Add Friends
-        for friend in &config.user.friends {
-            if let Some(friend_x25519_b64) = &friend.x25519_pubkey {
-                // Add friend
-                if let Err(e) =
-                    tree.add_friend(&friend.username, friend_x25519_b64, &encryption_secret)
-                {
-                    eprintln!(
-                        "Failed to add friend {} to HKS tree: {}",
-                        friend.username, e
-                    );
-                }
-            }
-        }
 
-        // Export
+        // Export, announcing any key rotations so friends can follow them
+        let rotations: Vec<KeyRotation> = config
+            .user
+            .key_rotations
+            .iter()
+            .filter_map(|s| serde_json::from_str(s).ok())
+            .collect();
         let payload = addrs.join("\n");
-        let blob = tree.export(&payload, &signing_key, &encryption_pubkey)?;
+
+        // Reuse the tree between publishes; only rekeyed links are re-encrypted.
+        // The crypto runs on a blocking thread so it doesn't stall the runtime.
+        let cached = take_cached_tree(&encryption_secret);
+        let secret = encryption_secret.clone();
+        let started = Instant::now();
+        let (tree, tree_changed, synced_config, blob) =
+            tauri::async_runtime::spawn_blocking(move || {
+                let (mut tree, changed) = sync_hks_tree(cached, &mut config, &secret);
+                let blob = tree.export(&payload, &signing_key, &secret, &rotations);
+                (tree, changed, config, blob)
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("HKS export task failed: {}", e))?;
+        let config = synced_config;
+        println!(
+            "[Discovery] Built HKS blob for {} friends in {:?}",
+            tree.roster.len(),
+            started.elapsed()
+        );
+        store_cached_tree(&encryption_secret, tree);
+        if tree_changed {
+            if let Err(e) = mgr.save(&config).await {
+                eprintln!("[Discovery] Failed to store HKS tree: {}", e);
+            }
+        }
+        let blob = blob?;
 
         // Parse pending invitations from config
         let invites: Vec<TrackedInvite> =
-            if let Some(ref inv_list) = config.user.pending_invitations {
-                inv_list
-                    .iter()
-                    .filter_map(|s| serde_json::from_str(s).ok())
-                    .collect()
-            } else {
-                vec![]
-            };
+            gist::parse_pending_invitations(config.user.pending_invitations.as_deref())
+                .into_iter()
+                .map(|pending| pending.invite)
+                .collect();
 
-        (blob, invites)
+        (blob, invites, fingerprint)
     };
 
     // 2. Inject pending invitations into blob
@@ -184,43 +448,125 @@ pub async fn publish_peer_info(
         blob_content
     };
 
-    // 3. Check for existing Gist
-    let existing_gist = gist::find_rchat_gist(token).await?;
-
-    if let Some(existing) = existing_gist {
-        // Update
-        let _ = gist::update_peer_info(token, &existing.id, final_blob_content).await?;
-    } else {
-        // Create
-        let _ = gist::create_peer_info(token, final_blob_content).await?;
+    // 3. Create or update the published blob
+    backend.publish(final_blob_content.clone()).await?;
+    crate::network::discovery_debug::record_published(&final_blob_content);
+    if let Ok(mut last) = LAST_PUBLISHED.lock() {
+        *last = Some((fingerprint, Instant::now()));
     }
-
     Ok(())
 }
 
+/// Fetch a friend's addresses. If the friend rotated keys since we stored
+/// theirs, the verified rotation is returned too and the blob is checked
+/// against the new key.
 pub async fn fetch_friend_peers(
+    backend: &impl DiscoveryBackend,
     username: &str,
     friend_verifying_key: &VerifyingKey,
     my_secret: &StaticSecret,
     my_pubkey_b64: &str,
-) -> anyhow::Result<Vec<Multiaddr>> {
+) -> anyhow::Result<(Vec<Multiaddr>, Option<KeyRotation>)> {
     // Use gist module to fetch content
-    if let Some(blob_b64) = gist::get_friend_content(username).await? {
+    if let Some(blob_b64) = backend.fetch_friend(username).await? {
+        let known_key_b64 = BASE64.encode(friend_verifying_key.to_bytes());
+        let rotation = gist::parse_blob(&blob_b64)
+            .ok()
+            .and_then(|blob| hks::follow_rotations(&known_key_b64, &blob.rotations).cloned());
+        let verifying_key = match &rotation {
+            Some(rotation) => {
+                let new_key_bytes = BASE64.decode(&rotation.new_ed25519)?;
+                VerifyingKey::from_bytes(
+                    &new_key_bytes
+                        .try_into()
+                        .map_err(|_| anyhow::anyhow!("Rotated key must be 32 bytes"))?,
+                )?
+            }
+            None => *friend_verifying_key,
+        };
+
         // Decrypt using HKS Import
-        if let Ok(payload_json) =
-            HksTree::import(&blob_b64, my_pubkey_b64, my_secret, friend_verifying_key)
-        {
-            let mut peers = Vec::new();
-            for line in payload_json.lines() {
-                if let Ok(addr) = line.trim().parse::<Multiaddr>() {
-                    peers.push(addr);
+        match HksTree::import(&blob_b64, my_pubkey_b64, my_secret, &verifying_key) {
+            Ok(payload_json) => {
+                let mut peers = Vec::new();
+                for line in payload_json.lines() {
+                    if let Ok(addr) = line.trim().parse::<Multiaddr>() {
+                        peers.push(addr);
+                    }
                 }
+                crate::network::discovery_debug::record_fetched(
+                    username,
+                    &blob_b64,
+                    format!("decrypted {} addresses", peers.len()),
+                );
+                return Ok((peers, rotation));
+            }
+            Err(e) => {
+                println!("Failed to decrypt blob from friend {}", username);
+                crate::network::discovery_debug::record_fetched(
+                    username,
+                    &blob_b64,
+                    format!("decrypt failed: {}", e),
+                );
             }
-            return Ok(peers);
-        } else {
-            println!("Failed to decrypt blob from friend {}", username);
         }
+        // Not in their roster under our key yet, but the rotation is still valid.
+        return Ok((vec![], rotation));
     }
 
-    Ok(vec![])
+    Ok((vec![], None))
+}
+
+/// Store a friend's rotated keys. Our next publish lists them under the new
+/// X25519 key.
+async fn adopt_friend_rotation(app: &crate::AppHandle, username: &str, rotation: &KeyRotation) {
+    let state = app.state::<AppState>();
+    let mgr = state.config_manager.lock().await;
+    let Ok(mut config) = mgr.load().await else {
+        return;
+    };
+    let Some(friend) = config
+        .user
+        .friends
+        .iter_mut()
+        .find(|friend| friend.username == username)
+    else {
+        return;
+    };
+    friend.ed25519_pubkey = Some(rotation.new_ed25519.clone());
+    friend.x25519_pubkey = Some(rotation.new_x25519.clone());
+    match mgr.save(&config).await {
+        Ok(()) => println!("[Discovery] 🔑 {} rotated their identity keys", username),
+        Err(e) => eprintln!(
+            "[Discovery] Failed to store rotated keys for {}: {}",
+            username, e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_ipv6_is_added_on_global_ipv6_ports() {
+        let addrs = vec![
+            "/ip4/192.168.1.20/udp/4001/quic-v1".to_string(),
+            "/ip6/fe80::1/udp/4001/quic-v1".to_string(),
+            "/ip6/2001:db8::5/tcp/4002".to_string(),
+        ];
+        let published = with_public_ipv6(addrs.clone(), Some("2001:db8::9"));
+        assert_eq!(published.len(), 4);
+        assert_eq!(published[3], "/ip6/2001:db8::9/tcp/4002");
+
+        assert_eq!(with_public_ipv6(addrs.clone(), None), addrs);
+        assert_eq!(
+            with_public_ipv6(
+                vec!["/ip6/2001:db8::9/tcp/4002".to_string()],
+                Some("2001:db8::9")
+            )
+            .len(),
+            1
+        );
+    }
 }
@@ -0,0 +1,295 @@
+//! Where the encrypted peer-info blob is published and fetched.
+//!
+//! GitHub Gists are the default. Users without GitHub can publish a public
+//! snippet in a GitLab project instead (`SystemConfig::gitlab`). Friends on
+//! GitLab are looked up in `<username>/<project name>`, with the project name
+//! taken from our own setting, so everyone on an instance is expected to use
+//! the same name ([`DEFAULT_GITLAB_PROJECT`] unless agreed otherwise). The
+//! blob format is the same on every backend; see `network::gist`.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tauri::Manager;
+
+use crate::storage::config::{DiscoveryBackendKind, GitlabDiscovery, SystemConfig};
+
+use super::{gist, proxy};
+
+pub const DEFAULT_GITLAB_PROJECT: &str = "rchat";
+
+pub trait DiscoveryBackend {
+    /// Our own published blob, if any.
+    async fn fetch_own(&self) -> Result<Option<String>>;
+    /// Create or replace our blob.
+    async fn publish(&self, content: String) -> Result<()>;
+    async fn fetch_friend(&self, username: &str) -> Result<Option<String>>;
+}
+
+pub struct GistBackend {
+    token: Option<String>,
+}
+
+impl GistBackend {
+    fn token(&self) -> Result<&str> {
+        self.token
+            .as_deref()
+            .ok_or_else(|| anyhow!("GitHub token not set"))
+    }
+}
+
+impl DiscoveryBackend for GistBackend {
+    async fn fetch_own(&self) -> Result<Option<String>> {
+        let Some(gist) = gist::find_rchat_gist(self.token()?).await? else {
+            return Ok(None);
+        };
+        let Some(file) = gist.files.get(gist::RCHAT_FILE_NAME) else {
+            return Ok(None);
+        };
+        let resp = proxy::http_client().get(&file.raw_url).send().await?;
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+        Ok(Some(resp.text().await?))
+    }
+
+    async fn publish(&self, content: String) -> Result<()> {
+        let token = self.token()?;
+        if let Some(existing) = gist::find_rchat_gist(token).await? {
+            gist::update_peer_info(token, &existing.id, content).await?;
+        } else {
+            gist::create_peer_info(token, content).await?;
+        }
+        Ok(())
+    }
+
+    async fn fetch_friend(&self, username: &str) -> Result<Option<String>> {
+        gist::get_friend_content(username, self.token.as_deref()).await
+    }
+}
+
+pub struct GitlabSnippetBackend {
+    settings: GitlabDiscovery,
+}
+
+#[derive(Deserialize)]
+struct Snippet {
+    id: u64,
+    title: String,
+}
+
+/// Project path as a URL path segment (`alice/rchat` -> `alice%2Frchat`).
+fn project_id(path: &str) -> String {
+    path.trim_matches('/').replace('/', "%2F")
+}
+
+/// Project that holds `username`'s snippet, named like our own.
+fn friend_project(own_project: &str, username: &str) -> String {
+    let name = own_project
+        .trim_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_GITLAB_PROJECT);
+    format!("{}/{}", username, name)
+}
+
+impl GitlabSnippetBackend {
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/api/v4{}",
+            self.settings.base_url.trim_end_matches('/'),
+            path
+        )
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        proxy::http_client()
+            .request(method, self.url(path))
+            .header("PRIVATE-TOKEN", &self.settings.token)
+    }
+
+    async fn find_snippet(&self, project: &str) -> Result<Option<u64>> {
+        let resp = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/snippets", project_id(project)),
+            )
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let snippets: Vec<Snippet> = resp.error_for_status()?.json().await?;
+        Ok(snippets
+            .into_iter()
+            .find(|snippet| snippet.title == gist::RCHAT_GIST_DESC)
+            .map(|snippet| snippet.id))
+    }
+
+    async fn fetch_snippet(&self, project: &str) -> Result<Option<String>> {
+        let Some(id) = self.find_snippet(project).await? else {
+            return Ok(None);
+        };
+        let resp = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/snippets/{}/raw", project_id(project), id),
+            )
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(Some(resp.text().await?))
+    }
+
+    /// Check the token and project; returns the token's username.
+    pub async fn validate(settings: &GitlabDiscovery) -> Result<String> {
+        #[derive(Deserialize)]
+        struct User {
+            username: String,
+        }
+        let backend = Self {
+            settings: settings.clone(),
+        };
+        let user: User = backend
+            .request(reqwest::Method::GET, "/user")
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| anyhow!("GitLab rejected the token: {}", e))?
+            .json()
+            .await?;
+        backend
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}", project_id(&settings.project)),
+            )
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| anyhow!("GitLab project {} not accessible: {}", settings.project, e))?;
+        Ok(user.username)
+    }
+}
+
+impl DiscoveryBackend for GitlabSnippetBackend {
+    async fn fetch_own(&self) -> Result<Option<String>> {
+        self.fetch_snippet(&self.settings.project).await
+    }
+
+    async fn publish(&self, content: String) -> Result<()> {
+        let project = project_id(&self.settings.project);
+        let request = match self.find_snippet(&self.settings.project).await? {
+            Some(id) => self
+                .request(
+                    reqwest::Method::PUT,
+                    &format!("/projects/{}/snippets/{}", project, id),
+                )
+                .json(&serde_json::json!({
+                    "files": [{
+                        "action": "update",
+                        "file_path": gist::RCHAT_FILE_NAME,
+                        "content": content,
+                    }],
+                })),
+            None => self
+                .request(
+                    reqwest::Method::POST,
+                    &format!("/projects/{}/snippets", project),
+                )
+                .json(&serde_json::json!({
+                    "title": gist::RCHAT_GIST_DESC,
+                    "visibility": "public",
+                    "files": [{
+                        "file_path": gist::RCHAT_FILE_NAME,
+                        "content": content,
+                    }],
+                })),
+        };
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    async fn fetch_friend(&self, username: &str) -> Result<Option<String>> {
+        self.fetch_snippet(&friend_project(&self.settings.project, username))
+            .await
+    }
+}
+
+/// The backend selected in the config; GitHub unless GitLab is fully set up.
+pub enum Backend {
+    Gist(GistBackend),
+    GitlabSnippets(GitlabSnippetBackend),
+}
+
+impl Backend {
+    pub fn from_system(system: &SystemConfig) -> Self {
+        match (system.discovery_backend, &system.gitlab) {
+            (DiscoveryBackendKind::Gitlab, Some(settings)) => {
+                Self::GitlabSnippets(GitlabSnippetBackend {
+                    settings: settings.clone(),
+                })
+            }
+            _ => Self::Gist(GistBackend {
+                token: system.github_token.clone(),
+            }),
+        }
+    }
+
+    /// Backend of the unlocked vault's config.
+    pub async fn from_app(app: &crate::AppHandle) -> Result<Self> {
+        let state = app.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        let config = mgr.load().await?;
+        Ok(Self::from_system(&config.system))
+    }
+
+    /// Whether we have credentials to publish, not just read.
+    pub fn can_publish(&self) -> bool {
+        match self {
+            Self::Gist(backend) => backend.token.is_some(),
+            Self::GitlabSnippets(_) => true,
+        }
+    }
+}
+
+impl DiscoveryBackend for Backend {
+    async fn fetch_own(&self) -> Result<Option<String>> {
+        match self {
+            Self::Gist(backend) => backend.fetch_own().await,
+            Self::GitlabSnippets(backend) => backend.fetch_own().await,
+        }
+    }
+
+    async fn publish(&self, content: String) -> Result<()> {
+        match self {
+            Self::Gist(backend) => backend.publish(content).await,
+            Self::GitlabSnippets(backend) => backend.publish(content).await,
+        }
+    }
+
+    async fn fetch_friend(&self, username: &str) -> Result<Option<String>> {
+        match self {
+            Self::Gist(backend) => backend.fetch_friend(username).await,
+            Self::GitlabSnippets(backend) => backend.fetch_friend(username).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gitlab_projects_are_encoded_and_mirrored_for_friends() {
+        assert_eq!(project_id("/alice/tools/rchat/"), "alice%2Ftools%2Frchat");
+        assert_eq!(friend_project("alice/peers", "bob"), "bob/peers");
+        assert_eq!(friend_project("", "bob"), "bob/rchat");
+
+        let system = SystemConfig {
+            discovery_backend: DiscoveryBackendKind::Gitlab,
+            ..Default::default()
+        };
+        // Selected but not configured: stay on GitHub.
+        assert!(matches!(Backend::from_system(&system), Backend::Gist(_)));
+    }
+}
@@ -0,0 +1,270 @@
+//! Local snapshot of the discovery (Gist/HKS) state for debugging.
+//!
+//! When a friend can't decrypt our blob, the cause is usually a roster entry
+//! under a stale X25519 key or a friend missing from the roster altogether.
+//! [`snapshot`] puts what we last published next to what we last fetched from
+//! every friend and the keys we have on file for them. It holds only public
+//! material (the blobs are public anyway); no private keys are included. The
+//! snapshot is written to disk by the caller and never uploaded.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::network::gist;
+use crate::network::hks::PublishedBlob;
+use crate::storage::config::Config;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedBlob {
+    pub blob: String,
+    pub recorded_at: u64,
+    /// For fetched blobs: what happened when we tried to read it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+}
+
+static LAST_PUBLISHED_BLOB: Mutex<Option<RecordedBlob>> = Mutex::new(None);
+static LAST_FETCHED_BLOBS: Mutex<Option<HashMap<String, RecordedBlob>>> = Mutex::new(None);
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn record_published(blob: &str) {
+    if let Ok(mut last) = LAST_PUBLISHED_BLOB.lock() {
+        *last = Some(RecordedBlob {
+            blob: blob.to_string(),
+            recorded_at: now_secs(),
+            outcome: None,
+        });
+    }
+}
+
+pub fn record_fetched(username: &str, blob: &str, outcome: impl Into<String>) {
+    if let Ok(mut fetched) = LAST_FETCHED_BLOBS.lock() {
+        fetched.get_or_insert_with(HashMap::new).insert(
+            username.to_string(),
+            RecordedBlob {
+                blob: blob.to_string(),
+                recorded_at: now_secs(),
+                outcome: Some(outcome.into()),
+            },
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RosterEntry {
+    pub name: String,
+    pub x25519_pubkey: String,
+    pub leaf_index: usize,
+}
+
+/// The readable parts of a published blob.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobSummary {
+    pub sender_x25519_pubkey: String,
+    pub roster: Vec<RosterEntry>,
+    /// X25519 keys the payload is sealed for directly (small rosters).
+    pub direct_recipients: Vec<String>,
+    pub tree_links: usize,
+    pub invitations: usize,
+    pub shadow_invites: usize,
+    pub rotations: usize,
+}
+
+impl BlobSummary {
+    fn of(blob: &PublishedBlob) -> Self {
+        let mut roster: Vec<RosterEntry> = blob
+            .roster
+            .values()
+            .map(|entry| RosterEntry {
+                name: entry.name.clone(),
+                x25519_pubkey: entry.x25519_pubkey.clone(),
+                leaf_index: entry.leaf_index,
+            })
+            .collect();
+        roster.sort_by_key(|entry| entry.leaf_index);
+        Self {
+            sender_x25519_pubkey: blob.sender_x25519_pubkey.clone(),
+            roster,
+            direct_recipients: blob.direct_payloads.keys().cloned().collect(),
+            tree_links: blob.tree_links.len(),
+            invitations: blob.invitations.len(),
+            shadow_invites: blob.shadow_invites.len(),
+            rotations: blob.rotations.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobDebug {
+    #[serde(flatten)]
+    pub recorded: RecordedBlob,
+    pub summary: Option<BlobSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_error: Option<String>,
+}
+
+impl BlobDebug {
+    fn of(recorded: RecordedBlob) -> Self {
+        match gist::parse_blob(&recorded.blob) {
+            Ok(blob) => Self {
+                summary: Some(BlobSummary::of(&blob)),
+                parse_error: None,
+                recorded,
+            },
+            Err(e) => Self {
+                summary: None,
+                parse_error: Some(e.to_string()),
+                recorded,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FriendDebug {
+    pub username: String,
+    pub alias: Option<String>,
+    pub x25519_pubkey: Option<String>,
+    pub ed25519_pubkey: Option<String>,
+    pub leaf_index: usize,
+    /// Our roster entry for them, if our last published blob has one.
+    pub my_roster_entry: Option<RosterEntry>,
+    /// Whether that entry uses the X25519 key we have on file for them. A
+    /// mismatch means they can't decrypt our blob until we republish.
+    pub my_roster_key_matches: Option<bool>,
+    /// Whether our last blob seals the payload directly for the X25519 key we
+    /// have on file for them.
+    pub in_my_direct_recipients: bool,
+    /// Whether their last blob lists our current X25519 key, in the roster or
+    /// as a direct recipient.
+    pub listed_in_their_roster: Option<bool>,
+    pub last_fetched: Option<BlobDebug>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveryDebugState {
+    pub generated_at: u64,
+    pub backend: String,
+    pub username: Option<String>,
+    pub my_ed25519_pubkey: Option<String>,
+    pub my_x25519_pubkey: Option<String>,
+    pub published: Option<BlobDebug>,
+    pub friends: Vec<FriendDebug>,
+}
+
+pub fn snapshot(config: &Config, my_x25519_pubkey: Option<String>) -> DiscoveryDebugState {
+    let published = LAST_PUBLISHED_BLOB
+        .lock()
+        .ok()
+        .and_then(|last| last.clone())
+        .map(BlobDebug::of);
+    let fetched = LAST_FETCHED_BLOBS
+        .lock()
+        .ok()
+        .and_then(|fetched| fetched.clone())
+        .unwrap_or_default();
+
+    let friends = config
+        .user
+        .friends
+        .iter()
+        .map(|friend| {
+            let my_roster_entry = published
+                .as_ref()
+                .and_then(|p| p.summary.as_ref())
+                .and_then(|s| s.roster.iter().find(|e| e.name == friend.username))
+                .cloned();
+            let my_roster_key_matches = my_roster_entry
+                .as_ref()
+                .map(|entry| friend.x25519_pubkey.as_deref() == Some(&entry.x25519_pubkey));
+            let in_my_direct_recipients = published
+                .as_ref()
+                .and_then(|p| p.summary.as_ref())
+                .zip(friend.x25519_pubkey.as_deref())
+                .is_some_and(|(s, key)| s.direct_recipients.iter().any(|k| k == key));
+            let last_fetched = fetched.get(&friend.username).cloned().map(BlobDebug::of);
+            let listed_in_their_roster = last_fetched
+                .as_ref()
+                .and_then(|b| b.summary.as_ref())
+                .zip(my_x25519_pubkey.as_deref())
+                .map(|(summary, mine)| {
+                    summary.roster.iter().any(|e| e.x25519_pubkey == mine)
+                        || summary.direct_recipients.iter().any(|key| key == mine)
+                });
+            FriendDebug {
+                username: friend.username.clone(),
+                alias: friend.alias.clone(),
+                x25519_pubkey: friend.x25519_pubkey.clone(),
+                ed25519_pubkey: friend.ed25519_pubkey.clone(),
+                leaf_index: friend.leaf_index,
+                my_roster_entry,
+                my_roster_key_matches,
+                in_my_direct_recipients,
+                listed_in_their_roster,
+                last_fetched,
+            }
+        })
+        .collect();
+
+    DiscoveryDebugState {
+        generated_at: now_secs(),
+        backend: format!("{:?}", config.system.discovery_backend).to_lowercase(),
+        username: config.system.discovery_username().map(str::to_string),
+        my_ed25519_pubkey: config.user.identity_public_key.clone(),
+        my_x25519_pubkey,
+        published,
+        friends,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::hks::FriendEntry;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn summary_lists_roster_by_leaf() {
+        let entry = |name: &str, leaf_index| FriendEntry {
+            name: name.to_string(),
+            x25519_pubkey: format!("{}-key", name),
+            encrypted_leaf_key: String::new(),
+            nonce: String::new(),
+            leaf_index,
+        };
+        let blob = PublishedBlob {
+            payload: String::new(),
+            payload_nonce: String::new(),
+            tree_links: BTreeMap::new(),
+            direct_payloads: BTreeMap::new(),
+            roster: BTreeMap::from([
+                ("bob-key".to_string(), entry("bob", 4096)),
+                ("ada-key".to_string(), entry("ada", 4095)),
+            ]),
+            signature: String::new(),
+            sender_x25519_pubkey: "me".to_string(),
+            invitations: vec![],
+            shadow_invites: vec![],
+            rotations: vec![],
+        };
+
+        let summary = BlobSummary::of(&blob);
+        assert_eq!(summary.roster[0].name, "ada");
+        assert_eq!(summary.roster[1].leaf_index, 4096);
+
+        let recorded = RecordedBlob {
+            blob: "not a blob".to_string(),
+            recorded_at: 0,
+            outcome: None,
+        };
+        assert!(BlobDebug::of(recorded).parse_error.is_some());
+    }
+}
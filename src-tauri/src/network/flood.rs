@@ -0,0 +1,193 @@
+//! Per-peer flood protection for incoming gossip and direct messages.
+//!
+//! Every peer gets a token bucket per kind of traffic. Running dry is a trip,
+//! counted once per burst; `trips_before_ban` trips within `ban_secs` ban the
+//! peer for `ban_secs`, during which everything it sends is dropped and its
+//! connections are refused. Bans are kept in memory only.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+use serde::Serialize;
+
+use crate::bridge::RateLimiter;
+use crate::storage::config::FloodProtectionSettings;
+
+/// Limits from the user's settings; defaults until the config is loaded.
+static LIMITS: Mutex<Option<FloodProtectionSettings>> = Mutex::new(None);
+
+pub fn set_limits(settings: FloodProtectionSettings) {
+    if let Ok(mut limits) = LIMITS.lock() {
+        *limits = Some(settings);
+    }
+}
+
+pub fn limits() -> FloodProtectionSettings {
+    LIMITS
+        .lock()
+        .ok()
+        .and_then(|limits| *limits)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Traffic {
+    Gossip,
+    DirectMessage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    /// Dropped; `first` on the first drop of a burst.
+    Limited {
+        first: bool,
+    },
+    /// Dropped because the peer is banned; `new` when this message caused it.
+    Banned {
+        new: bool,
+    },
+}
+
+struct Bucket {
+    limiter: RateLimiter,
+    tripped: bool,
+}
+
+#[derive(Default)]
+pub struct FloodGuard {
+    /// Limits the current buckets were built with.
+    limits: Option<FloodProtectionSettings>,
+    buckets: HashMap<(PeerId, Traffic), Bucket>,
+    trips: HashMap<PeerId, Vec<Instant>>,
+    bans: HashMap<PeerId, Instant>,
+}
+
+impl FloodGuard {
+    pub fn check(&mut self, peer: PeerId, traffic: Traffic) -> Verdict {
+        self.check_at(peer, traffic, &limits(), Instant::now())
+    }
+
+    fn check_at(
+        &mut self,
+        peer: PeerId,
+        traffic: Traffic,
+        limits: &FloodProtectionSettings,
+        now: Instant,
+    ) -> Verdict {
+        if self.limits.as_ref() != Some(limits) {
+            self.limits = Some(*limits);
+            self.buckets.clear();
+        }
+        if self.banned_until(&peer, now).is_some() {
+            return Verdict::Banned { new: false };
+        }
+
+        let (burst, per_sec) = match traffic {
+            Traffic::Gossip => (limits.gossip_burst, limits.gossip_per_sec),
+            Traffic::DirectMessage => (limits.direct_burst, limits.direct_per_sec),
+        };
+        let bucket = self
+            .buckets
+            .entry((peer, traffic))
+            .or_insert_with(|| Bucket {
+                limiter: RateLimiter::new(burst, per_sec),
+                tripped: false,
+            });
+        if bucket.limiter.try_acquire_at(now) {
+            bucket.tripped = false;
+            return Verdict::Allow;
+        }
+        if bucket.tripped {
+            return Verdict::Limited { first: false };
+        }
+        bucket.tripped = true;
+
+        let window = Duration::from_secs(limits.ban_secs);
+        let trips = self.trips.entry(peer).or_default();
+        trips.retain(|at| now.saturating_duration_since(*at) < window);
+        trips.push(now);
+        if trips.len() < limits.trips_before_ban as usize {
+            return Verdict::Limited { first: true };
+        }
+        self.trips.remove(&peer);
+        self.buckets.retain(|(banned, _), _| *banned != peer);
+        self.bans.insert(peer, now + window);
+        Verdict::Banned { new: true }
+    }
+
+    fn banned_until(&mut self, peer: &PeerId, now: Instant) -> Option<Instant> {
+        match self.bans.get(peer) {
+            Some(until) if *until > now => Some(*until),
+            Some(_) => {
+                self.bans.remove(peer);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Time left on the peer's ban, if it is banned.
+    pub fn ban_remaining(&mut self, peer: &PeerId) -> Option<Duration> {
+        let now = Instant::now();
+        self.banned_until(peer, now)
+            .map(|until| until.saturating_duration_since(now))
+    }
+
+    /// Drop buckets of peers that went away and bans that ran out.
+    pub fn retain_connected(&mut self, is_connected: impl Fn(&PeerId) -> bool) {
+        let now = Instant::now();
+        self.buckets.retain(|(peer, _), _| is_connected(peer));
+        self.bans.retain(|_, until| *until > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_trips_ban_the_peer_until_the_ban_runs_out() {
+        let limits = FloodProtectionSettings {
+            gossip_burst: 2,
+            gossip_per_sec: 1.0,
+            trips_before_ban: 2,
+            ban_secs: 60,
+            ..FloodProtectionSettings::default()
+        };
+        let mut guard = FloodGuard::default();
+        let peer = PeerId::random();
+        let start = Instant::now();
+        let check = |guard: &mut FloodGuard, traffic, secs| {
+            guard.check_at(peer, traffic, &limits, start + Duration::from_secs(secs))
+        };
+
+        assert_eq!(check(&mut guard, Traffic::Gossip, 0), Verdict::Allow);
+        assert_eq!(check(&mut guard, Traffic::Gossip, 0), Verdict::Allow);
+        assert_eq!(
+            check(&mut guard, Traffic::Gossip, 0),
+            Verdict::Limited { first: true }
+        );
+        assert_eq!(
+            check(&mut guard, Traffic::Gossip, 0),
+            Verdict::Limited { first: false }
+        );
+        // Direct messages have their own bucket.
+        assert_eq!(check(&mut guard, Traffic::DirectMessage, 0), Verdict::Allow);
+
+        // Refilled, then drained again: second trip within the window.
+        assert_eq!(check(&mut guard, Traffic::Gossip, 1), Verdict::Allow);
+        assert_eq!(
+            check(&mut guard, Traffic::Gossip, 1),
+            Verdict::Banned { new: true }
+        );
+        assert_eq!(
+            check(&mut guard, Traffic::DirectMessage, 30),
+            Verdict::Banned { new: false }
+        );
+        assert_eq!(check(&mut guard, Traffic::Gossip, 61), Verdict::Allow);
+    }
+}
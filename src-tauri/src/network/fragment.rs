@@ -0,0 +1,180 @@
+//! App-layer fragmentation for oversized envelopes.
+//!
+//! Gossipsub rejects messages above its transmit limit and a single huge
+//! request-response message is all-or-nothing on a lossy link. Payloads above
+//! [`FRAGMENT_THRESHOLD`] are split into fragments sized in whole multiples of a
+//! conservative path MTU, each carrying `(fragment_of, seq, total)`, and the
+//! receiver reassembles them in sequence order. Incomplete sets are dropped
+//! after [`REASSEMBLY_TIMEOUT`].
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// QUIC's minimum guaranteed datagram size; safe on every path we use.
+pub const PATH_MTU: usize = 1200;
+/// Raw bytes per fragment; stays under gossipsub's 64 KiB limit after base64.
+pub const FRAGMENT_PAYLOAD_BYTES: usize = 32 * PATH_MTU;
+/// Serialized payloads larger than this are sent as fragments.
+pub const FRAGMENT_THRESHOLD: usize = 40 * PATH_MTU;
+pub const MAX_FRAGMENTS: u32 = 256;
+pub const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_PENDING_MESSAGES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fragment {
+    /// Id of the message this fragment belongs to (unique per sender).
+    pub fragment_of: String,
+    pub seq: u32,
+    pub total: u32,
+    /// Base64 slice of the serialized message.
+    pub data: String,
+}
+
+pub fn needs_fragmenting(payload: &[u8]) -> bool {
+    payload.len() > FRAGMENT_THRESHOLD
+}
+
+/// Split `payload` into ordered fragments; `None` if it would exceed [`MAX_FRAGMENTS`].
+pub fn split(message_id: &str, payload: &[u8]) -> Option<Vec<Fragment>> {
+    let total = payload.len().div_ceil(FRAGMENT_PAYLOAD_BYTES).max(1);
+    if total > MAX_FRAGMENTS as usize {
+        return None;
+    }
+    Some(
+        payload
+            .chunks(FRAGMENT_PAYLOAD_BYTES)
+            .enumerate()
+            .map(|(seq, part)| Fragment {
+                fragment_of: message_id.to_string(),
+                seq: seq as u32,
+                total: total as u32,
+                data: BASE64.encode(part),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug)]
+struct Pending {
+    total: u32,
+    parts: BTreeMap<u32, Vec<u8>>,
+    started: Instant,
+}
+
+/// Collects fragments per `(origin, fragment_of)` until a message is complete.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    pending: HashMap<(String, String), Pending>,
+}
+
+impl Reassembler {
+    /// Add a fragment from `origin`; returns the full payload once every
+    /// fragment has arrived.
+    pub fn accept(
+        &mut self,
+        origin: &str,
+        fragment: Fragment,
+        now: Instant,
+    ) -> Result<Option<Vec<u8>>, String> {
+        if fragment.total == 0 || fragment.total > MAX_FRAGMENTS {
+            return Err(format!("invalid fragment count {}", fragment.total));
+        }
+        if fragment.seq >= fragment.total {
+            return Err(format!(
+                "fragment {} out of range for {}",
+                fragment.seq, fragment.total
+            ));
+        }
+        let part = BASE64
+            .decode(&fragment.data)
+            .map_err(|e| format!("invalid fragment data: {}", e))?;
+        if part.len() > FRAGMENT_PAYLOAD_BYTES {
+            return Err(format!("fragment of {} bytes is too large", part.len()));
+        }
+
+        let key = (origin.to_string(), fragment.fragment_of);
+        if !self.pending.contains_key(&key) && self.pending.len() >= MAX_PENDING_MESSAGES {
+            if let Some(oldest) = self
+                .pending
+                .iter()
+                .min_by_key(|(_, p)| p.started)
+                .map(|(k, _)| k.clone())
+            {
+                self.pending.remove(&oldest);
+            }
+        }
+
+        let pending = self.pending.entry(key.clone()).or_insert_with(|| Pending {
+            total: fragment.total,
+            parts: BTreeMap::new(),
+            started: now,
+        });
+        if pending.total != fragment.total {
+            self.pending.remove(&key);
+            return Err(format!("fragment count changed mid-message for {}", key.1));
+        }
+        pending.parts.entry(fragment.seq).or_insert(part);
+
+        if pending.parts.len() < pending.total as usize {
+            return Ok(None);
+        }
+        let complete = self.pending.remove(&key).map(|p| p.parts);
+        Ok(complete.map(|parts| parts.into_values().flatten().collect()))
+    }
+
+    /// Drop incomplete messages older than [`REASSEMBLY_TIMEOUT`]; returns how many.
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let before = self.pending.len();
+        self.pending
+            .retain(|_, p| now.duration_since(p.started) < REASSEMBLY_TIMEOUT);
+        before - self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassembles_out_of_order_fragments() {
+        let payload: Vec<u8> = (0..FRAGMENT_PAYLOAD_BYTES * 2 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        assert!(needs_fragmenting(&payload));
+        let mut fragments = split("msg-1", &payload).expect("split");
+        assert_eq!(fragments.len(), 3);
+        fragments.reverse();
+
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        let mut result = None;
+        for fragment in fragments {
+            // Duplicates are ignored.
+            reassembler
+                .accept("peer-a", fragment.clone(), now)
+                .expect("accept");
+            result = reassembler.accept("peer-a", fragment, now).expect("accept");
+        }
+        assert_eq!(result, Some(payload));
+    }
+
+    #[test]
+    fn rejects_bad_fragments_and_expires_stale_sets() {
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        let bad = Fragment {
+            fragment_of: "m".to_string(),
+            seq: 3,
+            total: 2,
+            data: String::new(),
+        };
+        assert!(reassembler.accept("peer-a", bad, now).is_err());
+
+        let first = split("m", &vec![1u8; FRAGMENT_PAYLOAD_BYTES + 1]).expect("split")[0].clone();
+        assert_eq!(reassembler.accept("peer-a", first, now), Ok(None));
+        assert_eq!(reassembler.expire(now), 0);
+        assert_eq!(reassembler.expire(now + REASSEMBLY_TIMEOUT), 1);
+    }
+}
@@ -1,3 +1,5 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use libp2p::gossipsub::IdentTopic;
 use serde::{Deserialize, Serialize};
 
@@ -6,15 +8,9 @@ use crate::chat_kind;
 pub const CONTROL_TOPIC: &str = "rchat:control";
 pub const GROUP_TOPIC_PREFIX: &str = "rchat:group:";
 pub const TEMP_GROUP_TOPIC_PREFIX: &str = "rchat:temp-group:";
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum ControlEnvelope {
-    ConnectionRequest {
-        from_peer_id: String,
-        to_peer_id: String,
-    },
-}
+/// Appended to a group topic for typing signals, so clients that predate them
+/// never see (and penalize) the unfamiliar payload.
+pub const ACTIVITY_TOPIC_SUFFIX: &str = "/activity";
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -57,6 +53,125 @@ pub struct GroupMessageEnvelope {
     pub text_content: Option<String>,
     #[serde(default)]
     pub file_hash: Option<String>,
+    /// Base64 Ed25519 signature by the sender's identity key (the one friends
+    /// store as `ed25519_pubkey`) over the envelope without this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Sender's logical clock for the message. Not signed, so peers that
+    /// predate it still verify the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logical: Option<i64>,
+    /// Set (to the bridge kind) on messages relayed in from a bridge, so
+    /// other members' bridges don't send them back out. Not signed either.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridged: Option<String>,
+    /// Typed content with the sender's media metadata, see
+    /// `DirectMessageRequest::content`. Not signed, so receivers only use it
+    /// when it agrees with the signed flat fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<crate::chat::message::MessageContent>,
+}
+
+impl GroupMessageEnvelope {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let unsigned = Self {
+            signature: None,
+            logical: None,
+            bridged: None,
+            content: None,
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    pub fn sign(&mut self, key: &SigningKey) {
+        let signature = key.sign(&self.signing_bytes());
+        self.signature = Some(BASE64.encode(signature.to_bytes()));
+    }
+
+    /// Whether the envelope carries a valid signature by `key`.
+    pub fn verify_signature(&self, key: &VerifyingKey) -> bool {
+        let Some(signature) = self
+            .signature
+            .as_deref()
+            .and_then(|sig| BASE64.decode(sig).ok())
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        else {
+            return false;
+        };
+        key.verify(&self.signing_bytes(), &signature).is_ok()
+    }
+}
+
+/// A change only group admins may make.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum GroupAdminAction {
+    Kick { peer_id: String },
+    Promote { peer_id: String },
+    SetName { name: String },
+    SetAvatar { file_hash: Option<String> },
+}
+
+/// An admin action published on the group's topic for the other members to
+/// apply once they checked the sender is an admin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupControlMessage {
+    pub group_id: String,
+    pub sender_id: String,
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub action: GroupAdminAction,
+    /// Base64 Ed25519 signature by the sender's libp2p identity (recoverable
+    /// from `sender_id`) over the message without this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl GroupControlMessage {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let unsigned = Self {
+            signature: None,
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    pub fn sign(&mut self, key: &SigningKey) {
+        let signature = key.sign(&self.signing_bytes());
+        self.signature = Some(BASE64.encode(signature.to_bytes()));
+    }
+
+    /// Whether the message was signed by the peer it names as sender.
+    pub fn verify_signature(&self) -> bool {
+        let Some(key) = self
+            .sender_id
+            .parse()
+            .ok()
+            .and_then(|peer| crate::network::keys::verifying_key(&peer))
+        else {
+            return false;
+        };
+        let Some(signature) = self
+            .signature
+            .as_deref()
+            .and_then(|sig| BASE64.decode(sig).ok())
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        else {
+            return false;
+        };
+        key.verify(&self.signing_bytes(), &signature).is_ok()
+    }
+}
+
+/// Ephemeral typing state for one member, published on the group's activity topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupActivitySignal {
+    pub group_id: String,
+    pub sender_id: String,
+    #[serde(default)]
+    pub sender_alias: Option<String>,
+    pub typing: bool,
 }
 
 pub fn control_topic() -> IdentTopic {
@@ -76,6 +191,19 @@ pub fn topic_for_group_id(group_id: &str) -> Option<IdentTopic> {
     None
 }
 
+pub fn activity_topic_for_group_id(group_id: &str) -> Option<IdentTopic> {
+    let topic = topic_for_group_id(group_id)?;
+    Some(IdentTopic::new(format!(
+        "{}{}",
+        topic.hash().as_str(),
+        ACTIVITY_TOPIC_SUFFIX
+    )))
+}
+
+pub fn group_id_from_activity_topic(topic: &str) -> Option<String> {
+    group_id_from_topic(topic.strip_suffix(ACTIVITY_TOPIC_SUFFIX)?)
+}
+
 pub fn group_id_from_topic(topic: &str) -> Option<String> {
     if let Some(uuid) = topic.strip_prefix(GROUP_TOPIC_PREFIX) {
         let candidate = format!("group:{}", uuid);
@@ -105,6 +233,82 @@ mod tests {
         assert_eq!(recovered, group_id);
     }
 
+    #[test]
+    fn activity_topic_is_separate_from_message_topic() {
+        let group_id = "group:550e8400-e29b-41d4-a716-446655440000";
+        let topic = activity_topic_for_group_id(group_id).expect("activity topic");
+        assert!(group_id_from_topic(topic.hash().as_str()).is_none());
+        assert_eq!(
+            group_id_from_activity_topic(topic.hash().as_str()).as_deref(),
+            Some(group_id)
+        );
+    }
+
+    #[test]
+    fn signed_envelope_verifies_only_unmodified_and_by_signer() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut envelope = GroupMessageEnvelope {
+            id: "m1".to_string(),
+            group_id: "group:550e8400-e29b-41d4-a716-446655440000".to_string(),
+            sender_id: "12D3KooWAlice".to_string(),
+            sender_alias: None,
+            timestamp: 1,
+            content_type: GroupContentType::Text,
+            text_content: Some("hi".to_string()),
+            file_hash: None,
+            signature: None,
+            logical: None,
+            bridged: None,
+            content: None,
+        };
+        assert!(!envelope.verify_signature(&key.verifying_key()));
+
+        envelope.sign(&key);
+        assert!(envelope.verify_signature(&key.verifying_key()));
+        let other = SigningKey::generate(&mut rand::rngs::OsRng);
+        assert!(!envelope.verify_signature(&other.verifying_key()));
+
+        envelope.sender_id = "12D3KooWMallory".to_string();
+        assert!(!envelope.verify_signature(&key.verifying_key()));
+    }
+
+    #[test]
+    fn control_message_verifies_against_the_sender_peer_id() {
+        let keypair = libp2p::identity::Keypair::generate_ed25519();
+        let key = crate::network::keys::signing_key(&keypair).unwrap();
+        let mut message = GroupControlMessage {
+            group_id: "group:550e8400-e29b-41d4-a716-446655440000".to_string(),
+            sender_id: keypair.public().to_peer_id().to_string(),
+            timestamp: 1,
+            action: GroupAdminAction::Kick {
+                peer_id: "12D3KooWBob".to_string(),
+            },
+            signature: None,
+        };
+        assert!(!message.verify_signature());
+        message.sign(&key);
+        assert!(message.verify_signature());
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["action"], "kick");
+        let parsed: GroupControlMessage = serde_json::from_value(json).unwrap();
+        assert!(parsed.verify_signature());
+        // Control messages and envelopes don't parse as each other.
+        assert!(serde_json::from_slice::<GroupMessageEnvelope>(
+            &serde_json::to_vec(&message).unwrap()
+        )
+        .is_err());
+
+        message.action = GroupAdminAction::Promote {
+            peer_id: "12D3KooWBob".to_string(),
+        };
+        assert!(!message.verify_signature());
+        let other = libp2p::identity::Keypair::generate_ed25519();
+        message.sender_id = other.public().to_peer_id().to_string();
+        message.sign(&key);
+        assert!(!message.verify_signature());
+    }
+
     #[test]
     fn rejects_invalid_group_id_for_topic() {
         assert!(topic_for_group_id("group:not-a-uuid").is_none());
@@ -0,0 +1,149 @@
+//! History backfill for new group members.
+//!
+//! A member asks an admin for the group's past messages with a
+//! [`HistoryRequest`]; the admin answers with [`HistoryPage`]s of at most
+//! [`HISTORY_PAGE_LEN`] messages, oldest first, each naming the cursor to
+//! ask for the next one. Both sides opt in: the admin only shares the last
+//! `history_share_days` of their chat settings (nothing when unset), and the
+//! member only takes pages for a backfill they started. Messages carry their
+//! `file_hash`; media is fetched from the admin with the file protocol.
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::db::Message;
+
+pub const HISTORY_PAGE_LEN: usize = 100;
+pub const MAX_HISTORY_SHARE_DAYS: i64 = 365;
+
+/// Where a page ends: the last message's `(logical, id)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryCursor {
+    pub logical: i64,
+    pub id: String,
+}
+
+impl HistoryCursor {
+    pub fn after(message: &Message) -> Self {
+        Self {
+            logical: message.logical,
+            id: message.id.clone(),
+        }
+    }
+}
+
+/// `text_content` of a `GroupHistoryRequest`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRequest {
+    pub group_id: String,
+    /// `None` for the first page.
+    #[serde(default)]
+    pub after: Option<HistoryCursor>,
+}
+
+/// `text_content` of a `GroupHistoryPage`. Peer ids are the sender's view
+/// with "Me" replaced by their own id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub group_id: String,
+    pub messages: Vec<Message>,
+    /// Hashes of the files the messages refer to.
+    #[serde(default)]
+    pub media: Vec<String>,
+    /// Set when there is more to fetch.
+    #[serde(default)]
+    pub next: Option<HistoryCursor>,
+}
+
+impl HistoryPage {
+    pub fn new(group_id: &str, messages: Vec<Message>) -> Self {
+        let next = (messages.len() == HISTORY_PAGE_LEN)
+            .then(|| messages.last().map(HistoryCursor::after))
+            .flatten();
+        let mut media: Vec<String> = messages
+            .iter()
+            .filter_map(|message| message.content.file_hash().map(str::to_string))
+            .collect();
+        media.sort();
+        media.dedup();
+        Self {
+            group_id: group_id.to_string(),
+            messages,
+            media,
+            next,
+        }
+    }
+}
+
+/// First timestamp shared when sharing the last `days`, or `None` when
+/// sharing is off.
+pub fn share_window_start(days: Option<i64>, now: i64) -> Option<i64> {
+    days.filter(|days| *days > 0)
+        .map(|days| now - days.min(MAX_HISTORY_SHARE_DAYS) * 24 * 60 * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::message::MessageContent;
+
+    fn message(id: &str, logical: i64, file_hash: Option<&str>) -> Message {
+        Message {
+            id: id.to_string(),
+            chat_id: "group:g".to_string(),
+            peer_id: "peer".to_string(),
+            timestamp: 0,
+            content: match file_hash {
+                Some(file_hash) => MessageContent::Sticker {
+                    file_hash: file_hash.to_string(),
+                    metadata: Default::default(),
+                },
+                None => MessageContent::Text {
+                    text: String::new(),
+                },
+            },
+            status: "delivered".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical,
+        }
+    }
+
+    #[test]
+    fn full_pages_point_at_the_next_one() {
+        let short = HistoryPage::new(
+            "group:g",
+            vec![
+                message("a", 1, Some("h1")),
+                message("b", 2, Some("h1")),
+                message("c", 3, None),
+            ],
+        );
+        assert_eq!(short.next, None);
+        assert_eq!(short.media, ["h1"]);
+
+        let full = HistoryPage::new(
+            "group:g",
+            (0..HISTORY_PAGE_LEN as i64)
+                .map(|i| message(&format!("m{}", i), i, None))
+                .collect(),
+        );
+        assert_eq!(
+            full.next,
+            Some(HistoryCursor {
+                logical: HISTORY_PAGE_LEN as i64 - 1,
+                id: format!("m{}", HISTORY_PAGE_LEN - 1),
+            })
+        );
+
+        assert_eq!(share_window_start(None, 1_000_000), None);
+        assert_eq!(share_window_start(Some(0), 1_000_000), None);
+        assert_eq!(
+            share_window_start(Some(1), 1_000_000),
+            Some(1_000_000 - 86_400)
+        );
+        assert_eq!(
+            share_window_start(Some(10_000), 0),
+            Some(-MAX_HISTORY_SHARE_DAYS * 86_400)
+        );
+    }
+}
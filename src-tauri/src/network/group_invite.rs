@@ -0,0 +1,242 @@
+//! Invite codes for groups.
+//!
+//! An admin hands out a [`GroupInvite`] as an `rchat://group/...` code: the
+//! group id, where to reach the inviter, a random welcome key and an expiry,
+//! signed with the inviter's libp2p identity. Whoever redeems the code dials
+//! the inviter and sends a `GroupJoinRequest` proving they hold the welcome
+//! key (a hash bound to their own peer id, so the proof can't be reused by
+//! someone else). The inviter adds them and answers with a `GroupWelcome`
+//! sealed with the welcome key: members, the current group key and, if the
+//! inviter shares history, the last messages (older ones can be backfilled,
+//! see `group_history`). Invites live in the inviter's memory and end with
+//! the session.
+
+use anyhow::{anyhow, Result};
+use base64::{
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD},
+    Engine as _,
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::keys;
+use crate::storage::db::{ChatMember, Message};
+
+pub const GROUP_INVITE_PREFIX: &str = "rchat://group/";
+pub const GROUP_INVITE_TTL_SECS: i64 = 24 * 60 * 60;
+/// Messages a new member gets from before they joined.
+pub const HISTORY_SNAPSHOT_LEN: usize = 50;
+const JOIN_PROOF_CONTEXT: &[u8] = b"rchat-group-join-v1";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupInvite {
+    pub group_id: String,
+    pub group_name: String,
+    pub inviter_peer_id: String,
+    pub inviter_addr: String,
+    /// Base64 random 32 bytes; seals the welcome.
+    pub welcome_key: String,
+    pub expires_at: i64,
+    /// Base64 Ed25519 signature by the inviter's libp2p identity over the
+    /// invite without this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl GroupInvite {
+    pub fn new(
+        group_id: &str,
+        group_name: &str,
+        inviter_peer_id: &str,
+        inviter_addr: &str,
+        now: i64,
+    ) -> Self {
+        Self {
+            group_id: group_id.to_string(),
+            group_name: group_name.to_string(),
+            inviter_peer_id: inviter_peer_id.to_string(),
+            inviter_addr: inviter_addr.to_string(),
+            welcome_key: BASE64.encode(rvault_core::crypto::generate_raw_key()),
+            expires_at: now + GROUP_INVITE_TTL_SECS,
+            signature: None,
+        }
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let unsigned = Self {
+            signature: None,
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    pub fn sign(&mut self, key: &SigningKey) {
+        let signature = key.sign(&self.signing_bytes());
+        self.signature = Some(BASE64.encode(signature.to_bytes()));
+    }
+
+    /// The invite is signed by the inviter it names, for a valid group, and
+    /// still valid at `now`.
+    pub fn check(&self, now: i64) -> Result<()> {
+        if !crate::chat_kind::is_group_chat_id(&self.group_id) {
+            return Err(anyhow!("Invite is not for a group"));
+        }
+        let key = self
+            .inviter_peer_id
+            .parse()
+            .ok()
+            .and_then(|peer| keys::verifying_key(&peer))
+            .ok_or_else(|| anyhow!("Unknown inviter key"))?;
+        let signature = self
+            .signature
+            .as_deref()
+            .and_then(|sig| BASE64.decode(sig).ok())
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| anyhow!("Invite is not signed"))?;
+        key.verify(&self.signing_bytes(), &signature)
+            .map_err(|_| anyhow!("Invalid invite signature"))?;
+        if self.expires_at <= now {
+            return Err(anyhow!("Invite has expired"));
+        }
+        Ok(())
+    }
+
+    pub fn welcome_key(&self) -> Result<[u8; 32]> {
+        BASE64
+            .decode(&self.welcome_key)?
+            .try_into()
+            .map_err(|_| anyhow!("Welcome key must be 32 bytes"))
+    }
+
+    /// What `joiner` sends to show they hold this invite.
+    pub fn join_proof(&self, joiner: &PeerId) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(JOIN_PROOF_CONTEXT);
+        hasher.update(self.welcome_key.as_bytes());
+        hasher.update(joiner.to_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        Ok(format!(
+            "{}{}",
+            GROUP_INVITE_PREFIX,
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(self)?)
+        ))
+    }
+
+    pub fn decode(code: &str) -> Result<Self> {
+        let code = code.trim();
+        let token = code.strip_prefix(GROUP_INVITE_PREFIX).unwrap_or(code);
+        let json = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|e| anyhow!("Invalid group invite code: {}", e))?;
+        serde_json::from_slice(&json).map_err(|e| anyhow!("Invalid group invite code: {}", e))
+    }
+}
+
+/// `text_content` of a `GroupJoinRequest`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupJoinRequest {
+    pub group_id: String,
+    pub proof: String,
+}
+
+/// The group key a new member starts with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WelcomeKey {
+    pub epoch: i64,
+    pub key: String, // Base64
+    pub admin_id: String,
+}
+
+/// Sealed with the welcome key into the `text_content` of a `GroupWelcome`.
+/// Peer ids are the inviter's view with "Me" replaced by their own id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupWelcome {
+    pub group_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub avatar_hash: Option<String>,
+    pub members: Vec<ChatMember>,
+    #[serde(default)]
+    pub key: Option<WelcomeKey>,
+    /// Oldest first.
+    pub history: Vec<Message>,
+}
+
+pub fn seal_welcome(welcome: &GroupWelcome, welcome_key: &[u8; 32]) -> Result<String> {
+    let sealed = super::group_key::seal(
+        &welcome.group_id,
+        0,
+        welcome_key,
+        &serde_json::to_vec(welcome)?,
+    )?;
+    Ok(serde_json::to_string(&sealed)?)
+}
+
+pub fn open_welcome(sealed: &str, welcome_key: &[u8; 32]) -> Result<GroupWelcome> {
+    let sealed: super::group_key::SealedGroupPayload = serde_json::from_str(sealed)?;
+    let welcome: GroupWelcome =
+        serde_json::from_slice(&super::group_key::open(welcome_key, &sealed)?)?;
+    if welcome.group_id != sealed.group_id {
+        return Err(anyhow!("Welcome is for another group"));
+    }
+    Ok(welcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_code_round_trips_and_proofs_are_bound_to_the_joiner() {
+        let inviter = libp2p::identity::Keypair::generate_ed25519();
+        let inviter_id = inviter.public().to_peer_id();
+        let group = "group:550e8400-e29b-41d4-a716-446655440000";
+        let mut invite = GroupInvite::new(
+            group,
+            "Friends",
+            &inviter_id.to_string(),
+            "/ip4/1.2.3.4/udp/4001/quic-v1",
+            100,
+        );
+        assert!(invite.check(100).is_err());
+        invite.sign(&keys::signing_key(&inviter).unwrap());
+
+        let code = invite.encode().unwrap();
+        assert!(code.starts_with(GROUP_INVITE_PREFIX));
+        let decoded = GroupInvite::decode(&code).unwrap();
+        assert_eq!(decoded, invite);
+        assert!(decoded.check(100).is_ok());
+        assert!(decoded.check(100 + GROUP_INVITE_TTL_SECS).is_err());
+
+        let mut tampered = decoded.clone();
+        tampered.group_id = "group:650e8400-e29b-41d4-a716-446655440000".to_string();
+        assert!(tampered.check(100).is_err());
+
+        let ada = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let bob = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        assert_eq!(invite.join_proof(&ada), decoded.join_proof(&ada));
+        assert_ne!(invite.join_proof(&ada), invite.join_proof(&bob));
+
+        let welcome = GroupWelcome {
+            group_id: group.to_string(),
+            name: "Friends".to_string(),
+            avatar_hash: None,
+            members: vec![],
+            key: None,
+            history: vec![],
+        };
+        let key = invite.welcome_key().unwrap();
+        let sealed = seal_welcome(&welcome, &key).unwrap();
+        assert_eq!(open_welcome(&sealed, &key).unwrap().name, "Friends");
+        assert!(open_welcome(&sealed, &[0; 32]).is_err());
+    }
+}
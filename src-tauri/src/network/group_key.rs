@@ -0,0 +1,247 @@
+//! Group chat keys.
+//!
+//! Envelopes on a group topic are sealed with the group's key. The group's
+//! admin makes the key: a random 32-byte key per epoch, handed to the members
+//! in a [`GroupKeyUpdate`]. The update is an HKS blob ([`HksTree::export`])
+//! built for the group alone: every member is a friend on a leaf of a fresh
+//! tree and the blob's payload is the key, so members reach it through their
+//! leaf (small groups get a copy sealed to each member instead). Keys come
+//! from the libp2p identities: the blob is signed with the admin's, and each
+//! member's X25519 key is derived from their `PeerId`.
+//!
+//! When membership changes the admin starts a new epoch with a new key and a
+//! new tree, so a removed member can't read what follows. Keys of earlier
+//! epochs are kept to open envelopes sent before a rekey, but only from
+//! current members. Membership changes through invites and signed admin
+//! control only, and once a group has a key, unsealed envelopes on its topic
+//! are dropped.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::SigningKey;
+use libp2p::{identity, PeerId};
+use rvault_core::crypto;
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use super::hks::HksTree;
+use super::keys;
+use super::wire::{self, Decoded};
+
+/// A group's key for one epoch, published by the admin on the group topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupKeyUpdate {
+    pub group_id: String,
+    pub epoch: i64,
+    pub sender_id: String,
+    /// [`HksTree::export`] blob whose payload is the key.
+    pub key_blob: String,
+}
+
+/// Signed inside the blob, so an update can't be replayed for another group
+/// or epoch.
+#[derive(Serialize, Deserialize)]
+struct KeyPayload {
+    group_id: String,
+    epoch: i64,
+    key: String, // Base64
+}
+
+/// A serialized [`GroupMessageEnvelope`](super::gossip::GroupMessageEnvelope)
+/// sealed with the group key of `epoch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedGroupPayload {
+    pub group_id: String,
+    pub epoch: i64,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// What arrived on a group topic.
+pub enum GroupPayload {
+    KeyUpdate(GroupKeyUpdate),
+    Sealed(SealedGroupPayload),
+    /// Anything else, for the caller to tell apart by its wire type.
+    Plain(Decoded),
+}
+
+pub fn parse_payload(data: Vec<u8>) -> GroupPayload {
+    match wire::decode(data) {
+        Decoded::Typed { kind, body } if kind == wire::kind::GROUP_KEY_UPDATE => {
+            match Decoded::body(body.clone()) {
+                Ok(update) => GroupPayload::KeyUpdate(update),
+                Err(_) => GroupPayload::Plain(Decoded::Typed { kind, body }),
+            }
+        }
+        Decoded::Typed { kind, body } if kind == wire::kind::GROUP_SEALED => {
+            match Decoded::body(body.clone()) {
+                Ok(sealed) => GroupPayload::Sealed(sealed),
+                Err(_) => GroupPayload::Plain(Decoded::Typed { kind, body }),
+            }
+        }
+        Decoded::Legacy(data) => {
+            if let Ok(update) = serde_json::from_slice(&data) {
+                return GroupPayload::KeyUpdate(update);
+            }
+            if let Ok(sealed) = serde_json::from_slice(&data) {
+                return GroupPayload::Sealed(sealed);
+            }
+            GroupPayload::Plain(Decoded::Legacy(data))
+        }
+        other => GroupPayload::Plain(other),
+    }
+}
+
+/// The local identity as used for group keys.
+pub struct GroupIdentity {
+    signing: SigningKey,
+    secret: StaticSecret,
+    public_b64: String,
+}
+
+impl GroupIdentity {
+    /// `None` if the identity is not Ed25519.
+    pub fn new(local: &identity::Keypair) -> Option<Self> {
+        let secret = keys::x25519_secret(local)?;
+        Some(Self {
+            signing: keys::signing_key(local)?,
+            public_b64: BASE64.encode(X25519PublicKey::from(&secret).as_bytes()),
+            secret,
+        })
+    }
+
+    /// Hand `key` to `members`. Members whose key can't be recovered from
+    /// their `PeerId` are left out.
+    pub fn build_update(
+        &self,
+        sender_id: &str,
+        group_id: &str,
+        epoch: i64,
+        key: &[u8; 32],
+        members: &[PeerId],
+    ) -> Result<GroupKeyUpdate> {
+        let mut tree = HksTree::new();
+        for member in members {
+            let Some(public) = keys::x25519_public(member) else {
+                continue;
+            };
+            let public_b64 = BASE64.encode(public.as_bytes());
+            if public_b64 != self.public_b64 {
+                tree.add_friend(&member.to_string(), &public_b64, &self.secret)?;
+            }
+        }
+        let payload = serde_json::to_string(&KeyPayload {
+            group_id: group_id.to_string(),
+            epoch,
+            key: BASE64.encode(key),
+        })?;
+        Ok(GroupKeyUpdate {
+            group_id: group_id.to_string(),
+            epoch,
+            sender_id: sender_id.to_string(),
+            key_blob: tree.export(&payload, &self.signing, &self.secret, &[])?,
+        })
+    }
+
+    /// The key in `update`, if it was signed by `sender` and includes us.
+    pub fn open_update(&self, update: &GroupKeyUpdate, sender: &PeerId) -> Result<[u8; 32]> {
+        let verifying = keys::verifying_key(sender).ok_or_else(|| anyhow!("Sender key unknown"))?;
+        let payload =
+            HksTree::import(&update.key_blob, &self.public_b64, &self.secret, &verifying)?;
+        let payload: KeyPayload = serde_json::from_str(&payload)?;
+        if payload.group_id != update.group_id || payload.epoch != update.epoch {
+            return Err(anyhow!("Key update is for another group or epoch"));
+        }
+        BASE64
+            .decode(payload.key)?
+            .try_into()
+            .map_err(|_| anyhow!("Group key must be 32 bytes"))
+    }
+}
+
+pub fn new_group_key() -> [u8; 32] {
+    crypto::generate_raw_key()
+}
+
+pub fn seal(group_id: &str, epoch: i64, key: &[u8; 32], data: &[u8]) -> Result<SealedGroupPayload> {
+    let (ciphertext, nonce) =
+        keys::seal(key, data).map_err(|e| anyhow!("Group payload encryption failed: {}", e))?;
+    Ok(SealedGroupPayload {
+        group_id: group_id.to_string(),
+        epoch,
+        nonce,
+        ciphertext,
+    })
+}
+
+pub fn open(key: &[u8; 32], sealed: &SealedGroupPayload) -> Result<Vec<u8>> {
+    keys::open(key, &sealed.ciphertext, &sealed.nonce)
+        .map_err(|e| anyhow!("Group payload decrypt failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_open_the_key_and_removed_members_cannot() {
+        let admin = identity::Keypair::generate_ed25519();
+        let ada = identity::Keypair::generate_ed25519();
+        let bob = identity::Keypair::generate_ed25519();
+        let admin_id = admin.public().to_peer_id();
+        let (ada_id, bob_id) = (ada.public().to_peer_id(), bob.public().to_peer_id());
+        let me = GroupIdentity::new(&admin).unwrap();
+        let ada_identity = GroupIdentity::new(&ada).unwrap();
+        let bob_identity = GroupIdentity::new(&bob).unwrap();
+
+        let first = new_group_key();
+        let update = me
+            .build_update(
+                &admin_id.to_string(),
+                "group:g",
+                1,
+                &first,
+                &[admin_id, ada_id, bob_id],
+            )
+            .unwrap();
+        assert_eq!(ada_identity.open_update(&update, &admin_id).unwrap(), first);
+        assert_eq!(bob_identity.open_update(&update, &admin_id).unwrap(), first);
+        // Only the admin's signature is accepted.
+        assert!(ada_identity.open_update(&update, &bob_id).is_err());
+
+        let second = new_group_key();
+        let rekey = me
+            .build_update(&admin_id.to_string(), "group:g", 2, &second, &[ada_id])
+            .unwrap();
+        assert_eq!(ada_identity.open_update(&rekey, &admin_id).unwrap(), second);
+        assert!(bob_identity.open_update(&rekey, &admin_id).is_err());
+        let mut replayed = rekey.clone();
+        replayed.group_id = "group:h".to_string();
+        assert!(ada_identity.open_update(&replayed, &admin_id).is_err());
+
+        let sealed = seal("group:g", 2, &second, b"{\"id\":\"m\"}").unwrap();
+        let bytes = wire::encode(wire::kind::GROUP_SEALED, &sealed).unwrap();
+        let GroupPayload::Sealed(parsed) = parse_payload(bytes) else {
+            panic!("expected a sealed payload");
+        };
+        assert_eq!(open(&second, &parsed).unwrap(), b"{\"id\":\"m\"}");
+        assert!(open(&first, &parsed).is_err());
+        assert!(matches!(
+            parse_payload(wire::encode(wire::kind::GROUP_KEY_UPDATE, &rekey).unwrap()),
+            GroupPayload::KeyUpdate(_)
+        ));
+        // Updates and sealed payloads from before the wire envelope.
+        assert!(matches!(
+            parse_payload(serde_json::to_vec(&rekey).unwrap()),
+            GroupPayload::KeyUpdate(_)
+        ));
+        assert!(matches!(
+            parse_payload(serde_json::to_vec(&sealed).unwrap()),
+            GroupPayload::Sealed(_)
+        ));
+        assert!(matches!(
+            parse_payload(b"{\"id\":\"m\"}".to_vec()),
+            GroupPayload::Plain(Decoded::Legacy(_))
+        ));
+    }
+}
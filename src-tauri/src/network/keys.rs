@@ -0,0 +1,78 @@
+//! Keys derived from the libp2p identity, and sealing with a symmetric key.
+//!
+//! Ed25519 identities sign group invites and admin control; their X25519 form
+//! keys chunk transfers and group key updates. A peer's public key is inlined
+//! in its `PeerId`, so no lookup or handshake is needed to recover it.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use libp2p::{identity, PeerId};
+use rvault_core::crypto;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+/// Multihash code for "identity": the public key is inlined in the `PeerId`.
+const IDENTITY_MULTIHASH_CODE: u64 = 0x00;
+
+/// The local libp2p identity as an Ed25519 signing key.
+pub(crate) fn signing_key(local: &identity::Keypair) -> Option<ed25519_dalek::SigningKey> {
+    let ed = local.clone().try_into_ed25519().ok()?;
+    let seed: [u8; 32] = ed.secret().as_ref().try_into().ok()?;
+    Some(ed25519_dalek::SigningKey::from_bytes(&seed))
+}
+
+/// A peer's Ed25519 identity key, recovered from its `PeerId`.
+pub(crate) fn verifying_key(peer: &PeerId) -> Option<ed25519_dalek::VerifyingKey> {
+    let multihash = peer.as_ref();
+    if multihash.code() != IDENTITY_MULTIHASH_CODE {
+        return None;
+    }
+    let public = identity::PublicKey::try_decode_protobuf(multihash.digest()).ok()?;
+    let ed = public.try_into_ed25519().ok()?;
+    ed25519_dalek::VerifyingKey::from_bytes(&ed.to_bytes()).ok()
+}
+
+pub(crate) fn x25519_secret(local: &identity::Keypair) -> Option<StaticSecret> {
+    Some(StaticSecret::from(signing_key(local)?.to_scalar_bytes()))
+}
+
+pub(crate) fn x25519_public(peer: &PeerId) -> Option<X25519PublicKey> {
+    Some(X25519PublicKey::from(
+        verifying_key(peer)?.to_montgomery().to_bytes(),
+    ))
+}
+
+/// Encrypt `data` with `key`; returns `(ciphertext_b64, nonce_b64)`.
+pub(crate) fn seal(key: &[u8; 32], data: &[u8]) -> Result<(String, String), String> {
+    // rvault_core's AEAD helpers round-trip UTF-8, so seal the base64 form.
+    crypto::encrypt_with_key(key, BASE64.encode(data).as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Decrypt what [`seal`] produced.
+pub(crate) fn open(
+    key: &[u8; 32],
+    ciphertext_b64: &str,
+    nonce_b64: &str,
+) -> Result<Vec<u8>, String> {
+    let encoded =
+        crypto::decrypt_with_key(key, ciphertext_b64, nonce_b64).map_err(|e| e.to_string())?;
+    BASE64
+        .decode(encoded)
+        .map_err(|e| format!("decrypted data is not valid base64: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, Verifier};
+
+    #[test]
+    fn peer_ids_carry_the_identity_key() {
+        let local = identity::Keypair::generate_ed25519();
+        let signature = signing_key(&local).unwrap().sign(b"hello");
+        let verifying = verifying_key(&local.public().to_peer_id()).unwrap();
+        assert!(verifying.verify(b"hello", &signature).is_ok());
+        assert_eq!(
+            x25519_public(&local.public().to_peer_id()),
+            x25519_secret(&local).as_ref().map(X25519PublicKey::from)
+        );
+    }
+}
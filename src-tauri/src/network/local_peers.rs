@@ -0,0 +1,121 @@
+//! Addresses of peers found on the local network.
+//!
+//! mDNS re-announces every peer on each browse cycle, so addresses are kept
+//! once per peer with the time they were last seen. Addresses not seen again
+//! within [`ADDRESS_TTL`] are dropped by [`LocalPeerTable::expire`]; a peer
+//! with none left is forgotten.
+
+use libp2p::{Multiaddr, PeerId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub const ADDRESS_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Default)]
+pub struct LocalPeerTable {
+    /// Addresses in the order they were first seen, with their last sighting.
+    by_peer: HashMap<PeerId, Vec<(Multiaddr, Instant)>>,
+}
+
+impl LocalPeerTable {
+    /// Record a sighting of `addr`; returns whether it was new for `peer`.
+    pub fn observe(&mut self, peer: PeerId, addr: Multiaddr, now: Instant) -> bool {
+        let addrs = self.by_peer.entry(peer).or_default();
+        match addrs.iter_mut().find(|(known, _)| *known == addr) {
+            Some((_, seen)) => {
+                *seen = now;
+                false
+            }
+            None => {
+                addrs.push((addr, now));
+                true
+            }
+        }
+    }
+
+    pub fn addresses(&self, peer: &PeerId) -> Vec<Multiaddr> {
+        self.by_peer
+            .get(peer)
+            .map(|addrs| addrs.iter().map(|(addr, _)| addr.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, peer: &PeerId) -> bool {
+        self.by_peer.contains_key(peer)
+    }
+
+    pub fn remove(&mut self, peer: &PeerId) -> bool {
+        self.by_peer.remove(peer).is_some()
+    }
+
+    /// Forget everything; returns the peers that were known.
+    pub fn clear(&mut self) -> Vec<PeerId> {
+        self.by_peer.drain().map(|(peer, _)| peer).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_peer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_peer.is_empty()
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.by_peer.keys()
+    }
+
+    /// Drop addresses not seen for `ttl`, except those of peers for which
+    /// `keep` holds (e.g. connected ones). Returns the peers forgotten
+    /// because no address was left.
+    pub fn expire(
+        &mut self,
+        now: Instant,
+        ttl: Duration,
+        keep: impl Fn(&PeerId) -> bool,
+    ) -> Vec<PeerId> {
+        let mut expired = Vec::new();
+        self.by_peer.retain(|peer, addrs| {
+            if keep(peer) {
+                return true;
+            }
+            addrs.retain(|(_, seen)| now.saturating_duration_since(*seen) < ttl);
+            if addrs.is_empty() {
+                expired.push(*peer);
+            }
+            !addrs.is_empty()
+        });
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_are_deduplicated_and_expire() {
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let lan: Multiaddr = "/ip4/192.168.1.10/tcp/4001".parse().unwrap();
+        let quic: Multiaddr = "/ip4/192.168.1.10/udp/4001/quic-v1".parse().unwrap();
+        let start = Instant::now();
+        let mut table = LocalPeerTable::default();
+
+        assert!(table.observe(peer, lan.clone(), start));
+        assert!(table.observe(peer, quic.clone(), start));
+        assert!(!table.observe(peer, lan.clone(), start + Duration::from_secs(120)));
+        assert_eq!(table.addresses(&peer), vec![lan.clone(), quic]);
+
+        // Only the re-announced address survives.
+        let later = start + ADDRESS_TTL + Duration::from_secs(1);
+        assert!(table.expire(later, ADDRESS_TTL, |_| false).is_empty());
+        assert_eq!(table.addresses(&peer), vec![lan]);
+
+        let much_later = later + ADDRESS_TTL;
+        assert!(table.expire(much_later, ADDRESS_TTL, |_| true).is_empty());
+        assert_eq!(table.expire(much_later, ADDRESS_TTL, |_| false), vec![peer]);
+        assert!(table.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use super::*;
+use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+
+impl NetworkManager {
+    /// Away mode: answer a friend's direct text with the configured reply,
+    /// at most once per chat per window (tracked in the `auto_replies` table).
+    pub(super) async fn maybe_auto_reply(
+        &mut self,
+        peer: PeerId,
+        chat_id: &str,
+        request: &DirectMessageRequest,
+    ) {
+        if request.msg_type != DirectMessageKind::Text
+            || !matches!(
+                crate::chat_kind::parse_chat_kind(chat_id),
+                crate::chat_kind::ChatKind::Direct
+            )
+            || self.friend_row_id(&peer).is_none()
+        {
+            return;
+        }
+
+        let (settings, my_alias) = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let mgr = state.config_manager.lock().await;
+            let Ok(config) = mgr.load().await else {
+                return;
+            };
+            (config.user.auto_reply, config.user.profile.alias)
+        };
+        if !settings.triggers_on(request.text_content.as_deref().unwrap_or_default()) {
+            return;
+        }
+
+        let now = now_unix_secs();
+        let message = crate::storage::db::Message {
+            id: crate::chat::message_id::new_message_id(),
+            chat_id: chat_id.to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: now,
+            content: crate::chat::message::MessageContent::Text {
+                text: settings.message.trim().to_string(),
+            },
+            status: "pending".to_string(),
+            content_metadata: Some(serde_json::json!({ "auto_reply": true }).to_string()),
+            sender_alias: None,
+            logical: 0,
+        };
+        {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            match crate::storage::db::claim_auto_reply(&conn, chat_id, now, settings.window_secs) {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(e) => {
+                    eprintln!(
+                        "[AutoReply] ❌ Failed to check rate limit for {}: {}",
+                        chat_id, e
+                    );
+                    return;
+                }
+            }
+            if let Err(e) = crate::storage::db::insert_message(&conn, &message) {
+                eprintln!("[AutoReply] ❌ Failed to save auto reply: {}", e);
+                return;
+            }
+            crate::storage::journal::record_message(&state.journal, &conn, &message);
+        }
+
+        let request = DirectMessageRequest {
+            id: message.id.clone(),
+            sender_id: self.swarm.local_peer_id().to_string(),
+            msg_type: DirectMessageKind::Text,
+            text_content: message.content.text_content().map(str::to_string),
+            file_hash: None,
+            timestamp: now,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: my_alias,
+            translation: None,
+            logical: self.stored_logical(&message.id),
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        };
+        self.send_direct_request(&peer, request);
+        println!("[AutoReply] 📤 Sent away reply to {}", chat_id);
+        let _ = self.app_handle.emit("message-received", message);
+    }
+}
@@ -0,0 +1,277 @@
+use super::transfer::{chunks_dir, ReceivedChunk};
+use super::*;
+use crate::network::blob_stream::{
+    read_blob_record, write_blob_record, BlobChunk, BlobRequest, BLOB_STREAM_THRESHOLD,
+};
+use crate::network::chunk_crypto;
+use crate::network::direct_message::ChunkInfo;
+use futures::io::AsyncWriteExt;
+use libp2p::Stream;
+use std::time::Duration;
+
+const BLOB_STREAM_OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Chunks read ahead of the manager; a full queue stops reading the stream.
+pub(super) const BLOB_STREAM_EVENT_CAPACITY: usize = 64;
+
+pub(super) enum BlobStreamEvent {
+    /// A peer asks us to stream chunks of a file.
+    Request {
+        peer: PeerId,
+        request: BlobRequest,
+        stream: Stream,
+    },
+    /// A chunk of a file we are downloading.
+    Chunk {
+        peer: PeerId,
+        file_hash: String,
+        chunk: BlobChunk,
+    },
+    /// The download stream ended early; `remaining` chunks never arrived.
+    Failed {
+        peer: PeerId,
+        file_hash: String,
+        remaining: Vec<ChunkInfo>,
+        error: String,
+    },
+}
+
+pub(super) fn start_blob_stream_accept_loop(
+    incoming: crate::network::voice_stream::IncomingStreams,
+    event_tx: tokio::sync::mpsc::Sender<BlobStreamEvent>,
+) {
+    tauri::async_runtime::spawn(async move {
+        futures::pin_mut!(incoming);
+        while let Some((peer, mut stream)) = incoming.next().await {
+            let event_tx = event_tx.clone();
+            tauri::async_runtime::spawn(async move {
+                match read_blob_record::<_, BlobRequest>(&mut stream).await {
+                    Ok(request) => {
+                        let _ = event_tx
+                            .send(BlobStreamEvent::Request {
+                                peer,
+                                request,
+                                stream,
+                            })
+                            .await;
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "[Blob] ❌ Invalid blob request from {}: {}",
+                            crate::logging::peer(&peer),
+                            e
+                        );
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Send the requested chunks, sealed with `chunk_key`, then close the stream.
+async fn serve_blob_request(
+    peer: PeerId,
+    request: BlobRequest,
+    mut stream: Stream,
+    chunk_key: [u8; 32],
+    dir: std::path::PathBuf,
+) {
+    for chunk_hash in request.chunk_hashes {
+        let chunk_data = match std::fs::read(dir.join(&chunk_hash)) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("[Blob] ❌ Chunk {} not found: {}", chunk_hash, e);
+                break;
+            }
+        };
+        let (data, nonce) = match chunk_crypto::seal_chunk(&chunk_key, &chunk_data) {
+            Ok(sealed) => sealed,
+            Err(e) => {
+                eprintln!("[Blob] ❌ Failed to seal chunk {}: {}", chunk_hash, e);
+                break;
+            }
+        };
+        let record = BlobChunk {
+            chunk_hash,
+            data,
+            nonce,
+        };
+        if let Err(e) = write_blob_record(&mut stream, &record).await {
+            eprintln!(
+                "[Blob] ❌ Stream to {} failed: {}",
+                crate::logging::peer(&peer),
+                e
+            );
+            return;
+        }
+        crate::storage::bandwidth::record_sent(
+            &peer.to_string(),
+            crate::storage::bandwidth::FILE_TRANSFER,
+            record.data.len(),
+        );
+    }
+    let _ = stream.close().await;
+    println!(
+        "[Blob] 📤 Finished streaming {} to {}",
+        request.file_hash,
+        crate::logging::peer(&peer)
+    );
+}
+
+impl NetworkManager {
+    /// Fetch `chunks` of a large file over a blob stream. Returns false when
+    /// the file is too small, the peer doesn't advertise blob streams or
+    /// there's no connection to open one on; the caller then requests the
+    /// chunks one by one.
+    pub(super) fn start_blob_download(
+        &mut self,
+        peer: PeerId,
+        file_hash: &str,
+        chunks: &[ChunkInfo],
+        total_size: i64,
+    ) -> bool {
+        if total_size < BLOB_STREAM_THRESHOLD
+            || !self.peer_supports(&peer, crate::network::capabilities::Capability::BlobStream)
+        {
+            return false;
+        }
+        let Some(connection_id) = self.peer_transport_registry.newest_connection_id(&peer) else {
+            return false;
+        };
+        let stream_rx = match self
+            .swarm
+            .behaviour_mut()
+            .blob_stream
+            .open_stream_on_connection(peer, connection_id)
+        {
+            Ok(stream_rx) => stream_rx,
+            Err(e) => {
+                eprintln!(
+                    "[Blob] ⚠️ Can't open blob stream to {}: {}",
+                    crate::logging::peer(&peer),
+                    e
+                );
+                return false;
+            }
+        };
+
+        println!(
+            "[Blob] 📥 Streaming {} chunks of {} from {}",
+            chunks.len(),
+            file_hash,
+            crate::logging::peer(&peer)
+        );
+        let event_tx = self.blob_stream_event_tx.clone();
+        let file_hash = file_hash.to_string();
+        let chunks = chunks.to_vec();
+        tauri::async_runtime::spawn(async move {
+            let mut received = 0;
+            let error = match tokio::time::timeout(BLOB_STREAM_OPEN_TIMEOUT, stream_rx).await {
+                Ok(Ok(Ok(mut stream))) => {
+                    let request = BlobRequest {
+                        file_hash: file_hash.clone(),
+                        chunk_hashes: chunks.iter().map(|c| c.chunk_hash.clone()).collect(),
+                    };
+                    match write_blob_record(&mut stream, &request).await {
+                        Ok(()) => loop {
+                            if received == chunks.len() {
+                                break None;
+                            }
+                            match read_blob_record::<_, BlobChunk>(&mut stream).await {
+                                Ok(chunk) => {
+                                    received += 1;
+                                    let event = BlobStreamEvent::Chunk {
+                                        peer,
+                                        file_hash: file_hash.clone(),
+                                        chunk,
+                                    };
+                                    if event_tx.send(event).await.is_err() {
+                                        return;
+                                    }
+                                }
+                                Err(e) => break Some(e.to_string()),
+                            }
+                        },
+                        Err(e) => Some(e.to_string()),
+                    }
+                }
+                Ok(Ok(Err(e))) => Some(e.to_string()),
+                Ok(Err(_)) => Some("stream open canceled".to_string()),
+                Err(_) => Some("stream open timed out".to_string()),
+            };
+            if let Some(error) = error {
+                let _ = event_tx
+                    .send(BlobStreamEvent::Failed {
+                        peer,
+                        file_hash,
+                        remaining: chunks[received..].to_vec(),
+                        error,
+                    })
+                    .await;
+            }
+        });
+        true
+    }
+
+    pub(super) async fn handle_blob_stream_event(&mut self, event: BlobStreamEvent) {
+        match event {
+            BlobStreamEvent::Request {
+                peer,
+                request,
+                stream,
+            } => {
+                // Same rule as `ChunkRequest`s: never send chunks unencrypted.
+                let Some(chunk_key) = self.chunk_keyring.key_for(&peer) else {
+                    eprintln!(
+                        "[Blob] ❌ No chunk key for {}; not streaming {}",
+                        crate::logging::peer(&peer),
+                        request.file_hash
+                    );
+                    return;
+                };
+                println!(
+                    "[Blob] 📦 Streaming {} chunks of {} to {}",
+                    request.chunk_hashes.len(),
+                    request.file_hash,
+                    crate::logging::peer(&peer)
+                );
+                let dir = chunks_dir(&self.app_handle);
+                tauri::async_runtime::spawn(serve_blob_request(
+                    peer, request, stream, chunk_key, dir,
+                ));
+            }
+            BlobStreamEvent::Chunk {
+                peer,
+                file_hash,
+                chunk,
+            } => {
+                crate::storage::bandwidth::record_received(
+                    &peer.to_string(),
+                    crate::storage::bandwidth::FILE_TRANSFER,
+                    chunk.data.len(),
+                );
+                let received = ReceivedChunk {
+                    data: chunk.data,
+                    nonce: Some(chunk.nonce),
+                    key: self.chunk_keyring.key_for(&peer),
+                };
+                self.accept_chunk(&file_hash, &chunk.chunk_hash, received, "blob_stream")
+                    .await;
+            }
+            BlobStreamEvent::Failed {
+                peer,
+                file_hash,
+                remaining,
+                error,
+            } => {
+                eprintln!(
+                    "[Blob] ⚠️ Blob stream for {} from {} failed ({}); requesting {} remaining chunks",
+                    file_hash,
+                    crate::logging::peer(&peer),
+                    error,
+                    remaining.len()
+                );
+                self.request_chunks(peer, &file_hash, &remaining);
+            }
+        }
+    }
+}
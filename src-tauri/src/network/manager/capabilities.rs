@@ -0,0 +1,42 @@
+use super::*;
+use crate::network::capabilities::{self, Capability};
+
+impl NetworkManager {
+    pub(super) fn handle_identify_event(&mut self, event: libp2p::identify::Event) {
+        let libp2p::identify::Event::Received { peer_id, info, .. } = event else {
+            return;
+        };
+        let caps = capabilities::from_identify(
+            &info.protocol_version,
+            &info.agent_version,
+            info.protocols.iter().map(|protocol| protocol.as_ref()),
+        );
+        let names: Vec<&str> = caps.iter().map(|cap| cap.as_str()).collect();
+        if self.peer_capabilities.record(peer_id, caps.clone()) {
+            println!(
+                "[Capabilities] 🧩 {} ({}) supports [{}]",
+                crate::logging::peer(&peer_id),
+                info.agent_version,
+                names.join(", ")
+            );
+            let _ = self.app_handle.emit(
+                "peer-capabilities",
+                serde_json::json!({
+                    "peer_id": peer_id.to_string(),
+                    "capabilities": caps,
+                }),
+            );
+        }
+    }
+
+    pub(super) fn peer_supports(&self, peer: &PeerId, cap: Capability) -> bool {
+        self.peer_capabilities.supports(peer, cap)
+    }
+
+    /// What `peer` advertised, or `None` before identify completed.
+    pub(super) fn peer_capability_list(&self, peer: &PeerId) -> Option<Vec<Capability>> {
+        self.peer_capabilities
+            .get(peer)
+            .map(|caps| caps.iter().copied().collect())
+    }
+}
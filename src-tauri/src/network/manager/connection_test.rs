@@ -0,0 +1,86 @@
+use super::*;
+use crate::network::probe::{PeerConnectionTest, ProbeRun};
+
+impl NetworkManager {
+    /// Dial every known address of `peer_id` separately for `test_peer_connection`.
+    pub(super) async fn start_connection_test(
+        &mut self,
+        peer_id: PeerId,
+        reply: tokio::sync::oneshot::Sender<PeerConnectionTest>,
+    ) {
+        use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
+
+        let mut stored = self.path_registry.connected_addresses(&peer_id);
+        {
+            let peer_id_str = peer_id.to_string();
+            let github_chat_id = self
+                .github_by_peer_id
+                .get(&peer_id_str)
+                .map(|username| crate::chat_identity::build_github_chat_id(username, &peer_id_str));
+            let state = self.app_handle.state::<crate::NetworkState>();
+            let runtime = state.chat_connections.lock().await;
+            for chat_id in std::iter::once(&peer_id_str).chain(github_chat_id.as_ref()) {
+                let remembered = runtime
+                    .get(chat_id)
+                    .and_then(|entry| entry.remote_addr.as_deref())
+                    .and_then(|addr| addr.parse::<Multiaddr>().ok());
+                stored.extend(remembered);
+            }
+        }
+        let mdns = self.local_peers.addresses(&peer_id);
+        let candidates = crate::network::probe::candidates(&stored, &mdns);
+
+        let connected_before = self.swarm.is_connected(&peer_id);
+        let mut run = ProbeRun::new(peer_id, reply, now_unix_secs(), connected_before);
+        println!(
+            "[Probe] 🔍 Testing {} address(es) for {}",
+            candidates.len(),
+            crate::logging::peer(&peer_id)
+        );
+        for (source, addr) in candidates {
+            self.record_outgoing_dial(&addr, OutgoingDialSource::ConnectionTest);
+            let opts = DialOpts::peer_id(peer_id)
+                .addresses(vec![addr.clone()])
+                .condition(PeerCondition::Always)
+                .build();
+            let connection_id = opts.connection_id();
+            match self.swarm.dial(opts) {
+                Ok(()) => run.dialing(connection_id, source, addr),
+                Err(e) => run.dial_rejected(source, &addr, e.to_string()),
+            }
+        }
+        self.connection_tests.push(run);
+        self.finish_connection_tests();
+    }
+
+    /// Feed a dial outcome to the test that issued it, if any.
+    pub(super) fn settle_connection_test(
+        &mut self,
+        connection_id: ConnectionId,
+        outcome: Result<(), String>,
+    ) {
+        let settled = self
+            .connection_tests
+            .iter_mut()
+            .any(|run| run.settle(connection_id, outcome.clone()));
+        if settled {
+            self.finish_connection_tests();
+        }
+    }
+
+    /// Answer tests whose dials all settled or that ran out of time.
+    pub(super) fn finish_connection_tests(&mut self) {
+        let now = std::time::Instant::now();
+        let (done, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.connection_tests)
+            .into_iter()
+            .partition(|run| run.is_done(now));
+        self.connection_tests = running;
+        for run in done {
+            println!(
+                "[Probe] ✅ Connection test for {} finished",
+                crate::logging::peer(&run.peer)
+            );
+            run.finish();
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use super::*;
+use crate::chat::group_activity::TYPING_REFRESH;
+use crate::network::gossip::{
+    activity_topic_for_group_id, topic_for_group_id, GroupActivitySignal,
+};
+use crate::network::wire;
+
+impl NetworkManager {
+    pub(super) fn subscribe_group_activity(&mut self, group_id: &str) {
+        let Some(topic) = activity_topic_for_group_id(group_id) else {
+            return;
+        };
+        if let Err(e) = self.swarm.behaviour_mut().gossipsub.subscribe(&topic) {
+            eprintln!(
+                "[Group] ⚠️ Failed to subscribe activity for {}: {:?}",
+                group_id, e
+            );
+        }
+    }
+
+    pub(super) fn unsubscribe_group_activity(&mut self, group_id: &str) {
+        if let Some(topic) = activity_topic_for_group_id(group_id) {
+            self.swarm.behaviour_mut().gossipsub.unsubscribe(&topic);
+        }
+        self.group_activity.forget(group_id);
+        self.typing_sent_at.remove(group_id);
+    }
+
+    /// Publish our typing state; `typing: true` is throttled to one signal per
+    /// `TYPING_REFRESH`, and `false` is only sent if we announced typing.
+    pub(super) fn send_group_typing(
+        &mut self,
+        group_id: String,
+        sender_alias: Option<String>,
+        typing: bool,
+    ) {
+        if !self.subscribed_group_ids.contains(&group_id) {
+            return;
+        }
+        let now = std::time::Instant::now();
+        if typing {
+            if self
+                .typing_sent_at
+                .get(&group_id)
+                .is_some_and(|at| now.duration_since(*at) < TYPING_REFRESH)
+            {
+                return;
+            }
+            self.typing_sent_at.insert(group_id.clone(), now);
+        } else if self.typing_sent_at.remove(&group_id).is_none() {
+            return;
+        }
+
+        let Some(topic) = activity_topic_for_group_id(&group_id) else {
+            return;
+        };
+        let signal = GroupActivitySignal {
+            group_id,
+            sender_id: self.swarm.local_peer_id().to_string(),
+            sender_alias,
+            typing,
+        };
+        let Ok(payload) = wire::encode(wire::kind::GROUP_ACTIVITY, &signal) else {
+            return;
+        };
+        // Nobody else online is the common case; typing is best effort.
+        let _ = self.swarm.behaviour_mut().gossipsub.publish(topic, payload);
+    }
+
+    /// Refresh online counts and emit one `group-activity` event per group
+    /// whose typing/presence changed since the last tick.
+    pub(super) fn tick_group_activity(&mut self) {
+        let mut online: HashMap<libp2p::gossipsub::TopicHash, usize> = HashMap::new();
+        for (_, topics) in self.swarm.behaviour().gossipsub.all_peers() {
+            for topic in topics {
+                *online.entry(topic.clone()).or_default() += 1;
+            }
+        }
+        for group_id in &self.subscribed_group_ids {
+            let count = topic_for_group_id(group_id)
+                .and_then(|topic| online.get(&topic.hash()).copied())
+                .unwrap_or(0);
+            self.group_activity.set_online_count(group_id, count);
+        }
+
+        for activity in self.group_activity.take_changes(std::time::Instant::now()) {
+            let _ = self.app_handle.emit("group-activity", activity);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::*;
+use crate::network::gossip::{GroupAdminAction, GroupControlMessage};
+use crate::network::wire;
+
+impl NetworkManager {
+    /// Whether `peer_id` may moderate `group_id`: the group's key admin (its
+    /// creator) or a member promoted to admin.
+    fn is_remote_group_admin(&self, group_id: &str, peer_id: &str) -> bool {
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return false;
+        };
+        let role = crate::storage::db::chat_member_role(&conn, group_id, peer_id)
+            .ok()
+            .flatten();
+        let key_admin = crate::storage::db::group_key_admin(&conn, group_id)
+            .ok()
+            .flatten();
+        role.as_deref() == Some("admin") || key_admin.as_deref() == Some(peer_id)
+    }
+
+    pub(super) fn publish_group_control(&mut self, group_id: &str, action: GroupAdminAction) {
+        let Some(topic) = crate::network::gossip::topic_for_group_id(group_id) else {
+            eprintln!("[GroupAdmin] ❌ Invalid group id: {}", group_id);
+            return;
+        };
+        let Some(key) = crate::network::keys::signing_key(&self.local_key) else {
+            eprintln!("[GroupAdmin] ⚠️ Identity is not Ed25519; can't sign admin actions");
+            return;
+        };
+        let mut message = GroupControlMessage {
+            group_id: group_id.to_string(),
+            sender_id: self.swarm.local_peer_id().to_string(),
+            timestamp: now_unix_secs(),
+            action,
+            signature: None,
+        };
+        message.sign(&key);
+        let payload = match wire::encode(wire::kind::GROUP_CONTROL, &message) {
+            Ok(payload) => payload,
+            Err(e) => {
+                eprintln!("[GroupAdmin] ❌ Failed to encode admin action: {}", e);
+                return;
+            }
+        };
+        let payload = match self.seal_group_payload(group_id, payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                eprintln!("[GroupAdmin] ❌ Not publishing admin action: {}", e);
+                return;
+            }
+        };
+        let id = format!("control-{}", crate::chat::message_id::new_message_id());
+        self.publish_on_group_topic(&topic, &id, payload);
+    }
+
+    /// Apply an admin action from another member. Returns false if the
+    /// message is forged or its sender is no admin.
+    pub(super) fn handle_group_control(
+        &mut self,
+        source: Option<PeerId>,
+        topic_group_id: &str,
+        message: GroupControlMessage,
+    ) -> bool {
+        let Some(source) = source.filter(|source| source.to_string() == message.sender_id) else {
+            return false;
+        };
+        if message.group_id != topic_group_id || !message.verify_signature() {
+            return false;
+        }
+        let local_peer_id = self.swarm.local_peer_id().to_string();
+        if message.sender_id == local_peer_id {
+            return true;
+        }
+        let group_id = message.group_id.clone();
+        if !self.is_remote_group_admin(&group_id, &message.sender_id) {
+            eprintln!(
+                "[GroupAdmin] ⚠️ Ignoring admin action in {} from {}: not an admin",
+                group_id,
+                crate::logging::peer(&source)
+            );
+            return false;
+        }
+        // Our own membership row is stored as "Me".
+        let member_row = |peer_id: &str| {
+            if peer_id == local_peer_id {
+                "Me".to_string()
+            } else {
+                peer_id.to_string()
+            }
+        };
+
+        let mut fetch_avatar = None;
+        let applied = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return true;
+            };
+            if !crate::storage::db::chat_exists(&conn, &group_id) {
+                return true;
+            }
+            match &message.action {
+                GroupAdminAction::Kick { peer_id } => {
+                    let key_admin = crate::storage::db::group_key_admin(&conn, &group_id)
+                        .ok()
+                        .flatten();
+                    if key_admin.as_deref() == Some(peer_id.as_str()) {
+                        eprintln!("[GroupAdmin] ⚠️ Ignoring kick of {}'s creator", group_id);
+                        return false;
+                    }
+                    crate::storage::db::remove_chat_member(&conn, &group_id, &member_row(peer_id))
+                }
+                GroupAdminAction::Promote { peer_id } => {
+                    let row = member_row(peer_id);
+                    crate::storage::db::add_chat_member(&conn, &group_id, &row, "admin").and_then(
+                        |()| {
+                            crate::storage::db::set_chat_member_role(
+                                &conn, &group_id, &row, "admin",
+                            )
+                            .map(|_| ())
+                        },
+                    )
+                }
+                GroupAdminAction::SetName { name } if !name.trim().is_empty() => {
+                    crate::storage::db::upsert_chat(&conn, &group_id, name.trim(), true)
+                }
+                GroupAdminAction::SetName { .. } => return false,
+                GroupAdminAction::SetAvatar { file_hash } => {
+                    let have_it = match file_hash.as_deref() {
+                        Some(hash) => crate::storage::object::info(&conn, hash)
+                            .ok()
+                            .flatten()
+                            .is_some_and(|info| info.is_complete),
+                        None => true,
+                    };
+                    if !have_it {
+                        fetch_avatar = file_hash.clone();
+                    }
+                    crate::storage::db::set_chat_avatar(&conn, &group_id, file_hash.as_deref())
+                }
+            }
+        };
+        if let Err(e) = applied {
+            eprintln!(
+                "[GroupAdmin] ❌ Failed to apply admin action in {}: {}",
+                group_id, e
+            );
+            return true;
+        }
+        println!(
+            "[GroupAdmin] 🛡️ Applied admin action in {} from {}",
+            group_id,
+            crate::logging::peer(&source)
+        );
+
+        match &message.action {
+            GroupAdminAction::Kick { peer_id } if *peer_id == local_peer_id => {
+                self.unsubscribe_group(&group_id);
+            }
+            // Only the key admin can rekey; for anyone else this does nothing.
+            GroupAdminAction::Kick { .. } => self.rekey_group(&group_id),
+            _ => {}
+        }
+        if let Some(file_hash) = fetch_avatar {
+            if crate::storage::disk::is_degraded() {
+                println!(
+                    "[Storage] ⏸️ Low disk space, not fetching avatar of {}",
+                    group_id
+                );
+            } else {
+                self.request_file_metadata(source, &file_hash);
+            }
+        }
+        let _ = self.app_handle.emit(
+            "group-updated",
+            serde_json::json!({
+                "group_id": group_id,
+                "sender_id": message.sender_id,
+                "action": message.action,
+            }),
+        );
+        true
+    }
+}
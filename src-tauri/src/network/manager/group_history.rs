@@ -0,0 +1,234 @@
+use super::*;
+use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+use crate::network::group_history::{
+    share_window_start, HistoryCursor, HistoryPage, HistoryRequest, HISTORY_PAGE_LEN,
+};
+
+impl NetworkManager {
+    /// Start a history backfill of `group_id` from `peer`, an admin.
+    pub(super) fn request_group_history(&mut self, group_id: &str, peer: PeerId) {
+        if peer == *self.swarm.local_peer_id() {
+            println!(
+                "[GroupHistory] ℹ️ We are {}'s admin; nothing to backfill",
+                group_id
+            );
+            return;
+        }
+        self.pending_history_requests
+            .insert((peer, group_id.to_string()));
+        println!(
+            "[GroupHistory] 📜 Asking {} for history of {}",
+            crate::logging::peer(&peer),
+            group_id
+        );
+        self.send_history_request(peer, group_id, None);
+    }
+
+    fn send_history_request(&mut self, peer: PeerId, group_id: &str, after: Option<HistoryCursor>) {
+        let request = HistoryRequest {
+            group_id: group_id.to_string(),
+            after,
+        };
+        let Ok(text_content) = serde_json::to_string(&request) else {
+            return;
+        };
+        let timestamp = now_unix_secs();
+        let request = DirectMessageRequest {
+            id: format!("history-{}-{}", group_id, timestamp),
+            sender_id: self.swarm.local_peer_id().to_string(),
+            msg_type: DirectMessageKind::GroupHistoryRequest,
+            text_content: Some(text_content),
+            file_hash: None,
+            timestamp,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        };
+        self.send_direct_request(&peer, request);
+    }
+
+    /// A member asked for history of a group we administer. Answered with
+    /// one page if our chat settings share history.
+    pub(super) fn handle_group_history_request(
+        &mut self,
+        peer: PeerId,
+        request: &DirectMessageRequest,
+    ) -> Result<(), String> {
+        let history: HistoryRequest = request
+            .text_content
+            .as_deref()
+            .and_then(|text| serde_json::from_str(text).ok())
+            .ok_or("malformed history request")?;
+        let group_id = history.group_id;
+        if !self.is_group_admin(&group_id) {
+            return Err("not an admin of the group".to_string());
+        }
+        let local_peer_id = self.swarm.local_peer_id().to_string();
+        let messages = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            if crate::storage::db::chat_member_role(&conn, &group_id, &peer.to_string())
+                .map_err(|e| e.to_string())?
+                .is_none()
+            {
+                return Err("not a member of the group".to_string());
+            }
+            let days = crate::storage::db::get_chat_settings(&conn, &group_id)
+                .map_err(|e| e.to_string())?
+                .history_share_days;
+            let since = share_window_start(days, now_unix_secs())
+                .ok_or("history sharing is off for this group")?;
+            let after = history
+                .after
+                .as_ref()
+                .map(|cursor| (cursor.logical, cursor.id.as_str()));
+            crate::storage::db::get_messages_page(&conn, &group_id, since, after, HISTORY_PAGE_LEN)
+                .map_err(|e| e.to_string())?
+        };
+        let messages = messages
+            .into_iter()
+            .map(|mut msg| {
+                if msg.peer_id == "Me" {
+                    msg.peer_id = local_peer_id.clone();
+                }
+                msg.status = "delivered".to_string();
+                msg
+            })
+            .collect();
+        let page = HistoryPage::new(&group_id, messages);
+        println!(
+            "[GroupHistory] 📤 Sharing {} message(s) of {} with {}",
+            page.messages.len(),
+            group_id,
+            crate::logging::peer(&peer)
+        );
+        let text_content = serde_json::to_string(&page).map_err(|e| e.to_string())?;
+        let timestamp = now_unix_secs();
+        let reply = DirectMessageRequest {
+            id: format!("history-page-{}-{}", group_id, timestamp),
+            sender_id: local_peer_id,
+            msg_type: DirectMessageKind::GroupHistoryPage,
+            text_content: Some(text_content),
+            file_hash: None,
+            timestamp,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        };
+        self.send_direct_request(&peer, reply);
+        Ok(())
+    }
+
+    /// A page of a backfill we started: store what we don't have, fetch its
+    /// media and ask for the next page.
+    pub(super) fn handle_group_history_page(
+        &mut self,
+        peer: PeerId,
+        request: &DirectMessageRequest,
+    ) -> Result<(), String> {
+        let page: HistoryPage = request
+            .text_content
+            .as_deref()
+            .and_then(|text| serde_json::from_str(text).ok())
+            .ok_or("malformed history page")?;
+        let group_id = page.group_id.clone();
+        let pending = (peer, group_id.clone());
+        if !self.pending_history_requests.contains(&pending) {
+            return Err("no history requested".to_string());
+        }
+        let local_peer_id = self.swarm.local_peer_id().to_string();
+        let (stored, downloads) = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            if crate::storage::db::chat_member_role(&conn, &group_id, "Me")
+                .map_err(|e| e.to_string())?
+                .is_none()
+            {
+                self.pending_history_requests.remove(&pending);
+                return Err("not a member of the group".to_string());
+            }
+            let mut missing = Vec::new();
+            for mut msg in page.messages {
+                if crate::storage::db::get_message(&conn, &msg.id)
+                    .map_err(|e| e.to_string())?
+                    .is_some()
+                {
+                    continue;
+                }
+                msg.chat_id = group_id.clone();
+                if msg.peer_id == local_peer_id {
+                    msg.peer_id = "Me".to_string();
+                }
+                missing.push(msg);
+            }
+            crate::storage::db::insert_messages_batch(&conn, &missing)
+                .map_err(|e| format!("failed to store history: {}", e))?;
+            let downloads: Vec<String> = missing
+                .iter()
+                .filter_map(|msg| {
+                    let hash = msg.content.file_hash()?;
+                    let complete = crate::storage::object::info(&conn, hash)
+                        .ok()
+                        .flatten()
+                        .is_some_and(|info| info.is_complete);
+                    (!complete
+                        && page.media.iter().any(|media| media == hash)
+                        && crate::chat::auto_download::should_download(
+                            &conn,
+                            &group_id,
+                            msg.content.content_type(),
+                        ))
+                    .then(|| hash.to_string())
+                })
+                .collect();
+            (missing.len(), downloads)
+        };
+
+        if crate::storage::disk::is_degraded() {
+            if !downloads.is_empty() {
+                println!(
+                    "[Storage] ⏸️ Low disk space, not fetching history media of {}",
+                    group_id
+                );
+            }
+        } else {
+            for file_hash in downloads {
+                self.request_file_metadata(peer, &file_hash);
+            }
+        }
+        let done = page.next.is_none();
+        println!(
+            "[GroupHistory] 📥 Stored {} earlier message(s) of {}{}",
+            stored,
+            group_id,
+            if done { ", backfill done" } else { "" }
+        );
+        match page.next {
+            Some(next) => self.send_history_request(peer, &group_id, Some(next)),
+            None => {
+                self.pending_history_requests.remove(&pending);
+            }
+        }
+        let _ = self.app_handle.emit(
+            "group-history-backfilled",
+            serde_json::json!({
+                "group_id": group_id,
+                "stored": stored,
+                "done": done,
+            }),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+use super::*;
+use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+use crate::network::group_history::share_window_start;
+use crate::network::group_invite::{
+    self, GroupInvite, GroupJoinRequest, GroupWelcome, WelcomeKey, HISTORY_SNAPSHOT_LEN,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+fn group_invite_request(
+    sender_id: String,
+    msg_type: DirectMessageKind,
+    text_content: String,
+) -> DirectMessageRequest {
+    let timestamp = now_unix_secs();
+    DirectMessageRequest {
+        id: format!("{}-{}", msg_type.as_str(), timestamp),
+        sender_id,
+        msg_type,
+        text_content: Some(text_content),
+        file_hash: None,
+        timestamp,
+        chunk_hash: None,
+        chunk_data: None,
+        chunk_nonce: None,
+        chunk_list: None,
+        sender_alias: None,
+        translation: None,
+        logical: None,
+        content: None,
+        wire: crate::network::wire::WireVersion::CURRENT,
+    }
+}
+
+impl NetworkManager {
+    /// Sign `invite` and accept it from now until it expires.
+    pub(super) fn create_group_invite(
+        &mut self,
+        mut invite: GroupInvite,
+    ) -> Result<String, String> {
+        let key = crate::network::keys::signing_key(&self.local_key)
+            .ok_or("Identity is not Ed25519; can't sign invites")?;
+        invite.sign(&key);
+        let code = invite.encode().map_err(|e| e.to_string())?;
+        let now = now_unix_secs();
+        self.group_invites.retain(|invite| invite.expires_at > now);
+        println!(
+            "[GroupInvite] 🎟️ Invite for {} valid until {}",
+            invite.group_id, invite.expires_at
+        );
+        self.group_invites.push(invite);
+        Ok(code)
+    }
+
+    /// Reach the inviter of a verified invite and ask to join.
+    pub(super) fn join_group(&mut self, invite: GroupInvite) {
+        let Ok(inviter) = invite.inviter_peer_id.parse::<PeerId>() else {
+            eprintln!(
+                "[GroupInvite] ❌ Invalid inviter in invite for {}",
+                invite.group_id
+            );
+            return;
+        };
+        if inviter == *self.swarm.local_peer_id() {
+            eprintln!(
+                "[GroupInvite] ⚠️ Ignoring our own invite for {}",
+                invite.group_id
+            );
+            return;
+        }
+        let addr = invite.inviter_addr.parse::<Multiaddr>();
+        self.pending_group_joins.insert(inviter, invite);
+        if self.swarm.is_connected(&inviter) {
+            self.send_pending_group_join(inviter);
+            return;
+        }
+        match addr {
+            Ok(addr) => {
+                println!(
+                    "[GroupInvite] 📞 Dialing inviter {}",
+                    crate::logging::peer(&inviter)
+                );
+                self.record_outgoing_dial(&addr, OutgoingDialSource::GroupInvite);
+                if let Err(e) = self.swarm.dial(addr) {
+                    eprintln!("[GroupInvite] ❌ Failed to dial inviter: {:?}", e);
+                }
+            }
+            Err(e) => eprintln!("[GroupInvite] ❌ Invalid inviter address: {}", e),
+        }
+    }
+
+    /// Send the join request for an invite from `peer`, if we are redeeming
+    /// one. Called again on every new connection until the welcome arrives.
+    pub(super) fn send_pending_group_join(&mut self, peer: PeerId) {
+        let Some(invite) = self.pending_group_joins.get(&peer) else {
+            return;
+        };
+        if invite.expires_at <= now_unix_secs() {
+            println!("[GroupInvite] ⏰ Invite for {} expired", invite.group_id);
+            self.pending_group_joins.remove(&peer);
+            return;
+        }
+        let local_peer_id = *self.swarm.local_peer_id();
+        let join = GroupJoinRequest {
+            group_id: invite.group_id.clone(),
+            proof: invite.join_proof(&local_peer_id),
+        };
+        let Ok(text_content) = serde_json::to_string(&join) else {
+            return;
+        };
+        println!(
+            "[GroupInvite] 🙋 Asking {} to join {}",
+            crate::logging::peer(&peer),
+            join.group_id
+        );
+        let request = group_invite_request(
+            local_peer_id.to_string(),
+            DirectMessageKind::GroupJoinRequest,
+            text_content,
+        );
+        self.send_direct_request(&peer, request);
+    }
+
+    /// A peer redeemed one of our invites: add them, rekey and send them the
+    /// group.
+    pub(super) fn handle_group_join_request(
+        &mut self,
+        peer: PeerId,
+        request: &DirectMessageRequest,
+    ) -> Result<(), String> {
+        let join: GroupJoinRequest = request
+            .text_content
+            .as_deref()
+            .and_then(|text| serde_json::from_str(text).ok())
+            .ok_or("malformed join request")?;
+        let now = now_unix_secs();
+        self.group_invites.retain(|invite| invite.expires_at > now);
+        let invite = self
+            .group_invites
+            .iter()
+            .find(|invite| {
+                invite.group_id == join.group_id && invite.join_proof(&peer) == join.proof
+            })
+            .cloned()
+            .ok_or("unknown or expired invite")?;
+        let group_id = invite.group_id.clone();
+        if !self.is_group_admin(&group_id) {
+            return Err("inviter is no longer an admin of the group".to_string());
+        }
+        {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            crate::storage::db::add_chat_member(&conn, &group_id, &peer.to_string(), "member")
+                .map_err(|e| e.to_string())?;
+        }
+        println!(
+            "[GroupInvite] 👋 {} joined {} by invite",
+            crate::logging::peer(&peer),
+            group_id
+        );
+        // The new epoch includes them, and the welcome carries its key.
+        self.rekey_group(&group_id);
+
+        let welcome = self.group_welcome(&group_id).map_err(|e| e.to_string())?;
+        let welcome_key = invite.welcome_key().map_err(|e| e.to_string())?;
+        let sealed =
+            group_invite::seal_welcome(&welcome, &welcome_key).map_err(|e| e.to_string())?;
+        let reply = group_invite_request(
+            self.swarm.local_peer_id().to_string(),
+            DirectMessageKind::GroupWelcome,
+            sealed,
+        );
+        self.send_direct_request(&peer, reply);
+        let _ = self.app_handle.emit(
+            "group-member-joined",
+            serde_json::json!({
+                "group_id": group_id,
+                "peer_id": peer.to_string(),
+            }),
+        );
+        Ok(())
+    }
+
+    /// The group as we see it, with "Me" replaced by our peer id.
+    fn group_welcome(&self, group_id: &str) -> anyhow::Result<GroupWelcome> {
+        let local_peer_id = self.swarm.local_peer_id().to_string();
+        let peer_row = |peer_id: String| {
+            if peer_id == "Me" {
+                local_peer_id.clone()
+            } else {
+                peer_id
+            }
+        };
+        let state = self.app_handle.state::<crate::AppState>();
+        let conn = state.db_conn.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let members = crate::storage::db::get_chat_members(&conn, group_id)?
+            .into_iter()
+            .map(|mut member| {
+                member.peer_id = peer_row(member.peer_id);
+                member
+            })
+            .collect();
+        let key = match crate::storage::db::latest_group_key(&conn, group_id)? {
+            Some((epoch, key)) => {
+                crate::storage::db::group_key_admin(&conn, group_id)?.map(|admin_id| WelcomeKey {
+                    epoch,
+                    key: BASE64.encode(key),
+                    admin_id,
+                })
+            }
+            None => None,
+        };
+        // Only what our history sharing setting allows.
+        let days = crate::storage::db::get_chat_settings(&conn, group_id)?.history_share_days;
+        let mut history = match share_window_start(days, now_unix_secs()) {
+            Some(since) => crate::storage::db::get_messages(&conn, group_id)?
+                .into_iter()
+                .filter(|msg| msg.timestamp >= since)
+                .collect(),
+            None => Vec::new(),
+        };
+        let skip = history.len().saturating_sub(HISTORY_SNAPSHOT_LEN);
+        let history = history
+            .drain(skip..)
+            .map(|mut msg| {
+                msg.peer_id = peer_row(msg.peer_id);
+                msg.status = "delivered".to_string();
+                msg
+            })
+            .collect();
+        Ok(GroupWelcome {
+            group_id: group_id.to_string(),
+            name: crate::storage::db::get_chat_name(&conn, group_id)?
+                .unwrap_or_else(|| group_id.to_string()),
+            avatar_hash: crate::storage::db::get_chat_avatar(&conn, group_id)?,
+            members,
+            key,
+            history,
+        })
+    }
+
+    /// The inviter let us in: store the group, its key and history, and
+    /// subscribe.
+    pub(super) fn handle_group_welcome(
+        &mut self,
+        peer: PeerId,
+        request: &DirectMessageRequest,
+    ) -> Result<(), String> {
+        let invite = self
+            .pending_group_joins
+            .get(&peer)
+            .ok_or("no pending invite from this peer")?;
+        let welcome_key = invite.welcome_key().map_err(|e| e.to_string())?;
+        let welcome = request
+            .text_content
+            .as_deref()
+            .ok_or("empty welcome")
+            .and_then(|sealed| {
+                group_invite::open_welcome(sealed, &welcome_key)
+                    .map_err(|_| "welcome could not be opened")
+            })?;
+        if welcome.group_id != invite.group_id {
+            return Err("welcome is for another group".to_string());
+        }
+        self.pending_group_joins.remove(&peer);
+
+        let group_id = welcome.group_id.clone();
+        let local_peer_id = self.swarm.local_peer_id().to_string();
+        let member_row = |peer_id: String| {
+            if peer_id == local_peer_id {
+                "Me".to_string()
+            } else {
+                peer_id
+            }
+        };
+        let mut fetch_avatar = None;
+        {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            let stored = (|| -> anyhow::Result<usize> {
+                crate::storage::db::upsert_chat(&conn, &group_id, &welcome.name, true)?;
+                crate::storage::db::add_chat_member(&conn, &group_id, "Me", "member")?;
+                for member in &welcome.members {
+                    crate::storage::db::add_chat_member(
+                        &conn,
+                        &group_id,
+                        &member_row(member.peer_id.clone()),
+                        &member.role,
+                    )?;
+                }
+                if let Some(key) = &welcome.key {
+                    let bytes: [u8; 32] = BASE64
+                        .decode(&key.key)?
+                        .try_into()
+                        .map_err(|_| anyhow::anyhow!("Group key must be 32 bytes"))?;
+                    crate::storage::db::store_group_key(
+                        &conn,
+                        &group_id,
+                        key.epoch,
+                        &bytes,
+                        &key.admin_id,
+                    )?;
+                }
+                let mut missing = Vec::new();
+                for mut msg in welcome.history.clone() {
+                    if crate::storage::db::get_message(&conn, &msg.id)?.is_some() {
+                        continue;
+                    }
+                    msg.chat_id = group_id.clone();
+                    msg.peer_id = member_row(msg.peer_id);
+                    missing.push(msg);
+                }
+                crate::storage::db::insert_messages_batch(&conn, &missing)?;
+                if let Some(hash) = &welcome.avatar_hash {
+                    let have_it = crate::storage::object::info(&conn, hash)?
+                        .is_some_and(|info| info.is_complete);
+                    if !have_it {
+                        fetch_avatar = Some(hash.clone());
+                    }
+                }
+                crate::storage::db::set_chat_avatar(
+                    &conn,
+                    &group_id,
+                    welcome.avatar_hash.as_deref(),
+                )?;
+                Ok(missing.len())
+            })();
+            match stored {
+                Ok(history) => println!(
+                    "[GroupInvite] ✅ Joined {} with {} member(s) and {} earlier message(s)",
+                    group_id,
+                    welcome.members.len(),
+                    history
+                ),
+                Err(e) => return Err(format!("failed to store group: {}", e)),
+            }
+        }
+
+        self.subscribe_group(&group_id);
+        if let Some(file_hash) = fetch_avatar {
+            if crate::storage::disk::is_degraded() {
+                println!(
+                    "[Storage] ⏸️ Low disk space, not fetching avatar of {}",
+                    group_id
+                );
+            } else {
+                self.request_file_metadata(peer, &file_hash);
+            }
+        }
+        let _ = self.app_handle.emit(
+            "group-joined",
+            serde_json::json!({
+                "group_id": group_id,
+                "name": welcome.name,
+            }),
+        );
+        Ok(())
+    }
+}
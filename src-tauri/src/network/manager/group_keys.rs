@@ -0,0 +1,312 @@
+use super::*;
+use crate::network::group_key::{self, GroupKeyUpdate, GroupPayload};
+use crate::network::wire::{self, Decoded};
+
+/// A group topic payload after opening.
+pub(super) struct OpenedGroupPayload {
+    pub decoded: Decoded,
+    /// Sealed with the newest key we hold. Only members who were handed that
+    /// key can produce it; an older epoch may come from someone since removed.
+    pub current_epoch: bool,
+    /// The group has a key, so membership is enforced.
+    pub keyed: bool,
+}
+
+impl NetworkManager {
+    pub(super) fn is_group_admin(&self, group_id: &str) -> bool {
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return false;
+        };
+        crate::storage::db::chat_member_role(&conn, group_id, "Me")
+            .ok()
+            .flatten()
+            .is_some_and(|role| role == "admin")
+    }
+
+    /// Seal a serialized envelope with the group's newest key. Groups without
+    /// a key get it as it is; any other failure fails the send rather than
+    /// publishing plaintext.
+    pub(super) fn seal_group_payload(
+        &self,
+        group_id: &str,
+        payload: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let latest = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            crate::storage::db::latest_group_key(&conn, group_id)
+                .map_err(|e| format!("Failed to read key of {}: {}", group_id, e))?
+        };
+        let Some((epoch, key)) = latest else {
+            return Ok(payload);
+        };
+        group_key::seal(group_id, epoch, &key, &payload)
+            .and_then(|sealed| Ok(wire::encode(wire::kind::GROUP_SEALED, &sealed)?))
+            .map_err(|e| format!("Failed to seal for {}: {}", group_id, e))
+    }
+
+    /// The payload from a group topic with its wire type: sealed payloads
+    /// are opened and key updates are taken in (`None`). Once a group has a
+    /// key, unsealed payloads on its topic are dropped.
+    pub(super) fn open_group_payload(
+        &mut self,
+        source: Option<PeerId>,
+        topic_group_id: &str,
+        data: Vec<u8>,
+    ) -> Option<OpenedGroupPayload> {
+        match group_key::parse_payload(data) {
+            GroupPayload::Plain(decoded) => {
+                let keyed = {
+                    let state = self.app_handle.state::<crate::AppState>();
+                    let conn = state.db_conn.lock().ok()?;
+                    crate::storage::db::latest_group_key(&conn, topic_group_id)
+                        .map(|latest| latest.is_some())
+                        // Fail closed: unknown means it may be keyed.
+                        .unwrap_or(true)
+                };
+                if keyed {
+                    println!(
+                        "[GroupKey] 🔒 Dropping unsealed payload on keyed {} from {}",
+                        topic_group_id,
+                        source
+                            .as_ref()
+                            .map_or_else(|| "?".to_string(), crate::logging::peer)
+                    );
+                    return None;
+                }
+                Some(OpenedGroupPayload {
+                    decoded,
+                    current_epoch: false,
+                    keyed: false,
+                })
+            }
+            GroupPayload::KeyUpdate(update) => {
+                self.handle_group_key_update(source, topic_group_id, update);
+                None
+            }
+            GroupPayload::Sealed(sealed) => {
+                if sealed.group_id != topic_group_id {
+                    return None;
+                }
+                let (key, latest_epoch) = {
+                    let state = self.app_handle.state::<crate::AppState>();
+                    let conn = state.db_conn.lock().ok()?;
+                    let key = crate::storage::db::group_key(&conn, &sealed.group_id, sealed.epoch)
+                        .ok()
+                        .flatten();
+                    let latest_epoch =
+                        crate::storage::db::latest_group_key(&conn, &sealed.group_id)
+                            .ok()
+                            .flatten()
+                            .map(|(epoch, _)| epoch);
+                    (key, latest_epoch)
+                };
+                let Some(key) = key else {
+                    println!(
+                        "[GroupKey] 🔒 No key for epoch {} of {}, dropping envelope",
+                        sealed.epoch, sealed.group_id
+                    );
+                    return None;
+                };
+                match group_key::open(&key, &sealed) {
+                    Ok(data) => Some(OpenedGroupPayload {
+                        decoded: wire::decode(data),
+                        current_epoch: latest_epoch == Some(sealed.epoch),
+                        keyed: true,
+                    }),
+                    Err(e) => {
+                        eprintln!("[GroupKey] ❌ {}: {}", sealed.group_id, e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `peer_id` has a membership row in `group_id`.
+    pub(super) fn is_group_member(&self, group_id: &str, peer_id: &str) -> bool {
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return false;
+        };
+        crate::storage::db::chat_member_role(&conn, group_id, peer_id)
+            .ok()
+            .flatten()
+            .is_some()
+    }
+
+    fn handle_group_key_update(
+        &mut self,
+        source: Option<PeerId>,
+        topic_group_id: &str,
+        update: GroupKeyUpdate,
+    ) {
+        // The update is signed by its sender, who must be the gossip source.
+        let Some(source) = source.filter(|source| source.to_string() == update.sender_id) else {
+            return;
+        };
+        if update.group_id != topic_group_id || source == *self.swarm.local_peer_id() {
+            return;
+        }
+        let Some(identity) = &self.group_identity else {
+            return;
+        };
+        let key = match identity.open_update(&update, &source) {
+            Ok(key) => key,
+            Err(e) => {
+                // Updates that leave us out are expected after we were removed.
+                println!(
+                    "[GroupKey] 🔒 Key update {} of {} not for us: {}",
+                    update.epoch, update.group_id, e
+                );
+                return;
+            }
+        };
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        let accepted =
+            crate::storage::db::accepts_group_key_from(&conn, &update.group_id, &update.sender_id);
+        match accepted {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!(
+                    "[GroupKey] ⚠️ Ignoring key update for {} from {}: not its admin",
+                    update.group_id,
+                    crate::logging::peer(&source)
+                );
+                return;
+            }
+            Err(e) => {
+                eprintln!(
+                    "[GroupKey] ❌ Failed to read admin of {}: {}",
+                    update.group_id, e
+                );
+                return;
+            }
+        }
+        match crate::storage::db::store_group_key(
+            &conn,
+            &update.group_id,
+            update.epoch,
+            &key,
+            &update.sender_id,
+        ) {
+            Ok(true) => println!(
+                "[GroupKey] 🔑 Got key {} of {} from {}",
+                update.epoch,
+                update.group_id,
+                crate::logging::peer(&source)
+            ),
+            Ok(false) => {}
+            Err(e) => eprintln!("[GroupKey] ❌ Failed to store key: {}", e),
+        }
+    }
+
+    /// Start a new epoch for a group we administer and hand its key to the
+    /// current members.
+    pub(super) fn rekey_group(&mut self, group_id: &str) {
+        if !crate::chat_kind::is_group_chat_id(group_id) || !self.is_group_admin(group_id) {
+            return;
+        }
+        let Some(identity) = &self.group_identity else {
+            eprintln!(
+                "[GroupKey] ⚠️ Identity is not Ed25519; {} stays unsealed",
+                group_id
+            );
+            return;
+        };
+        let local_peer_id = self.swarm.local_peer_id().to_string();
+        let (epoch, members) = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            // Promoted admins moderate, but keys only ever come from the creator.
+            match crate::storage::db::group_key_admin(&conn, group_id) {
+                Ok(Some(admin)) if admin != local_peer_id => return,
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("[GroupKey] ❌ Failed to read admin of {}: {}", group_id, e);
+                    return;
+                }
+            }
+            let epoch = match crate::storage::db::latest_group_key(&conn, group_id) {
+                Ok(latest) => latest.map_or(1, |(epoch, _)| epoch + 1),
+                Err(e) => {
+                    eprintln!("[GroupKey] ❌ Failed to read key of {}: {}", group_id, e);
+                    return;
+                }
+            };
+            let members: Vec<PeerId> = crate::storage::db::get_chat_members(&conn, group_id)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|member| member.peer_id.parse().ok())
+                .collect();
+            (epoch, members)
+        };
+
+        let key = group_key::new_group_key();
+        let update = match identity
+            .build_update(&local_peer_id, group_id, epoch, &key, &members)
+            .and_then(|update| Ok(wire::encode(wire::kind::GROUP_KEY_UPDATE, &update)?))
+        {
+            Ok(update) => update,
+            Err(e) => {
+                eprintln!("[GroupKey] ❌ Failed to build key update: {}", e);
+                return;
+            }
+        };
+        {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            if let Err(e) =
+                crate::storage::db::store_group_key(&conn, group_id, epoch, &key, &local_peer_id)
+            {
+                eprintln!("[GroupKey] ❌ Failed to store key: {}", e);
+                return;
+            }
+        }
+        println!(
+            "[GroupKey] 🔑 Rekeyed {} to epoch {} for {} member(s)",
+            group_id,
+            epoch,
+            members.len()
+        );
+        self.group_key_updates
+            .insert(group_id.to_string(), update.clone());
+        self.publish_group_key_update(group_id, epoch, update);
+    }
+
+    fn publish_group_key_update(&mut self, group_id: &str, epoch: i64, update: Vec<u8>) {
+        let Some(topic) = crate::network::gossip::topic_for_group_id(group_id) else {
+            return;
+        };
+        self.publish_on_group_topic(&topic, &format!("{}:key:{}", group_id, epoch), update);
+    }
+
+    /// A peer joined the topic of a group we administer: republish the
+    /// current key, which they may have missed while offline.
+    pub(super) fn handle_group_subscription(&mut self, topic: &libp2p::gossipsub::TopicHash) {
+        let Some(group_id) = crate::network::gossip::group_id_from_topic(topic.as_str()) else {
+            return;
+        };
+        if !self.is_group_admin(&group_id) {
+            return;
+        }
+        let Some(update) = self.group_key_updates.get(&group_id).cloned() else {
+            // Nothing published since startup: start a fresh epoch instead.
+            self.rekey_group(&group_id);
+            return;
+        };
+        let epoch = match group_key::parse_payload(update.clone()) {
+            GroupPayload::KeyUpdate(update) => update.epoch,
+            _ => 0,
+        };
+        self.publish_group_key_update(&group_id, epoch, update);
+    }
+}
@@ -0,0 +1,133 @@
+//! Connect handshakes that survive restarts.
+//!
+//! Every change to `pending_requests` (we pressed Connect) and
+//! `incoming_requests` (they did) is mirrored into the `handshake_state`
+//! table and reloaded on startup. Requests older than
+//! `UserConfig::handshake_ttl_secs` are dropped, both on load and by the
+//! periodic cleanup.
+
+use super::*;
+use crate::storage::db::HandshakeState;
+
+const OUTGOING: &str = "outgoing";
+const INCOMING: &str = "incoming";
+
+impl NetworkManager {
+    async fn handshake_cutoff(&self) -> i64 {
+        let ttl = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let mgr = state.config_manager.lock().await;
+            mgr.load()
+                .await
+                .map(|config| config.user.handshake_ttl_secs)
+                .unwrap_or_else(|_| {
+                    crate::storage::config::UserConfig::default().handshake_ttl_secs
+                })
+        };
+        now_unix_secs().saturating_sub(ttl)
+    }
+
+    /// Reload unexpired handshakes saved by a previous run.
+    pub(super) async fn restore_handshakes(&mut self) {
+        let cutoff = self.handshake_cutoff().await;
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        if let Err(e) = crate::storage::db::expire_handshake_states(&conn, cutoff) {
+            eprintln!("[Handshake] Failed to expire saved handshakes: {}", e);
+        }
+        let saved = match crate::storage::db::list_handshake_states(&conn) {
+            Ok(saved) => saved,
+            Err(e) => {
+                eprintln!("[Handshake] Failed to load saved handshakes: {}", e);
+                return;
+            }
+        };
+        for handshake in saved {
+            let Ok(peer_id) = handshake.peer_id.parse::<PeerId>() else {
+                continue;
+            };
+            match handshake.direction.as_str() {
+                OUTGOING => self.pending_requests.insert(peer_id),
+                INCOMING => self.incoming_requests.insert(peer_id),
+                _ => continue,
+            };
+        }
+        println!(
+            "[Handshake] Restored {} outgoing / {} incoming request(s)",
+            self.pending_requests.len(),
+            self.incoming_requests.len()
+        );
+    }
+
+    fn save_handshake(&self, peer_id: PeerId, direction: &str) {
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        let handshake = HandshakeState {
+            peer_id: peer_id.to_string(),
+            direction: direction.to_string(),
+            created_at: now_unix_secs(),
+        };
+        if let Err(e) = crate::storage::db::save_handshake_state(&conn, &handshake) {
+            eprintln!(
+                "[Handshake] Failed to save request for {}: {}",
+                crate::logging::peer(&peer_id),
+                e
+            );
+        }
+    }
+
+    pub(super) fn track_outgoing_request(&mut self, peer_id: PeerId) {
+        self.pending_requests.insert(peer_id);
+        self.save_handshake(peer_id, OUTGOING);
+    }
+
+    pub(super) fn track_incoming_request(&mut self, peer_id: PeerId) {
+        self.incoming_requests.insert(peer_id);
+        self.save_handshake(peer_id, INCOMING);
+    }
+
+    pub(super) fn forget_handshake(&mut self, peer_id: &PeerId) {
+        self.pending_requests.remove(peer_id);
+        self.incoming_requests.remove(peer_id);
+        let state = self.app_handle.state::<crate::AppState>();
+        if let Ok(conn) = state.db_conn.lock() {
+            if let Err(e) = crate::storage::db::delete_handshake_state(&conn, &peer_id.to_string())
+            {
+                eprintln!(
+                    "[Handshake] Failed to delete request for {}: {}",
+                    crate::logging::peer(&peer_id),
+                    e
+                );
+            }
+        }
+    }
+
+    pub(super) async fn expire_handshakes(&mut self) {
+        let cutoff = self.handshake_cutoff().await;
+        let expired = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            match crate::storage::db::expire_handshake_states(&conn, cutoff) {
+                Ok(expired) => expired,
+                Err(e) => {
+                    eprintln!("[Handshake] Failed to expire handshakes: {}", e);
+                    return;
+                }
+            }
+        };
+        for peer_id in expired.iter().filter_map(|id| id.parse::<PeerId>().ok()) {
+            self.pending_requests.remove(&peer_id);
+            self.incoming_requests.remove(&peer_id);
+            println!(
+                "[Handshake] ⏰ Request with {} expired",
+                crate::logging::peer(&peer_id)
+            );
+        }
+    }
+}
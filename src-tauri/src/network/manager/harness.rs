@@ -0,0 +1,414 @@
+//! Two in-process peers for networking tests.
+//!
+//! A [`TestPeer`] is a real [`NetworkManager`] on a memory transport, built
+//! over a mock Tauri app that manages what the desktop app does: an in-memory
+//! database, its own chunk directory and a locked config. [`TestNet`] hands
+//! both managers their swarm, transfer-worker and blob-stream events through
+//! the handlers the run loop calls, until a flow completes:
+//!
+//! ```ignore
+//! let mut net = TestNet::connected().await;
+//! let id = net.a.send_text(net.b.peer_id, "hi").await;
+//! net.run_until(|net| net.a.message_status(&id).as_deref() == Some("delivered"))
+//!     .await;
+//! ```
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use libp2p::core::transport::MemoryTransport;
+use libp2p::core::upgrade::Version;
+use libp2p::request_response::{Event as RequestResponseEvent, Message as RequestResponseMessage};
+use libp2p::swarm::SwarmEvent;
+use libp2p::{identity::Keypair, Multiaddr, PeerId, Swarm, SwarmBuilder, Transport};
+use rusqlite::Connection;
+use tauri::Manager;
+
+use super::blob::BlobStreamEvent;
+use super::transfer::TransferResult;
+use super::NetworkManager;
+use crate::network::behaviour::{RChatBehaviour, RChatBehaviourEvent};
+use crate::network::capabilities::{self, PeerCapabilities};
+use crate::network::command::{DirectMediaKind, NetworkCommand};
+use crate::network::direct_message::{
+    DirectMessageKind, DirectMessageRequest, DirectMessageResponse,
+};
+use crate::storage::db;
+
+/// How long [`TestNet::run_until`] waits before failing the test.
+const FLOW_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Something the run loop would hand to one of the manager's handlers.
+enum Input {
+    Swarm(SwarmEvent<RChatBehaviourEvent>),
+    Transfer(TransferResult),
+    Blob(BlobStreamEvent),
+}
+
+pub(super) struct TestPeer {
+    pub peer_id: PeerId,
+    pub manager: NetworkManager,
+    /// Responses to our direct requests, in arrival order.
+    pub responses: Vec<DirectMessageResponse>,
+    app: tauri::App<crate::Runtime>,
+    /// Holds `chunks/`, the config and the journal.
+    files: tempfile::TempDir,
+}
+
+fn memory_swarm(key: &Keypair) -> Swarm<RChatBehaviour> {
+    SwarmBuilder::with_existing_identity(key.clone())
+        .with_tokio()
+        .with_other_transport(|key| {
+            Ok::<_, libp2p::noise::Error>(
+                MemoryTransport::default()
+                    .upgrade(Version::V1)
+                    .authenticate(crate::network::configure_noise(key)?)
+                    .multiplex(libp2p::yamux::Config::default()),
+            )
+        })
+        .expect("memory transport")
+        .with_relay_client(crate::network::configure_noise, || {
+            libp2p::yamux::Config::default()
+        })
+        .expect("relay client")
+        .with_behaviour(|key, relay_client| RChatBehaviour::new(key.clone(), relay_client, false))
+        .expect("behaviour")
+        .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+        .build()
+}
+
+impl TestPeer {
+    pub fn new() -> Self {
+        let key = Keypair::generate_ed25519();
+        let peer_id = key.public().to_peer_id();
+        let files = tempfile::tempdir().expect("tempdir");
+
+        let app = tauri::test::mock_app();
+        app.manage(crate::AppState::for_tests(files.path().to_path_buf()));
+        app.manage(crate::bot_api::BotApiRuntime::default());
+        app.manage(crate::http_api::HttpApiRuntime::default());
+        app.manage(crate::bridge::BridgeRuntime::default());
+        app.manage(crate::chat::suggest::ReplySuggestions::default());
+
+        let (sender, crx) = tokio::sync::mpsc::channel(32);
+        app.manage(crate::NetworkState::new(
+            sender,
+            peer_id.to_string(),
+            Default::default(),
+        ));
+        let (_, disc_rx) = tokio::sync::mpsc::channel(1);
+        let (mdns_tx, mdns_rx) = tokio::sync::mpsc::channel(1);
+        let manager = NetworkManager::new(
+            memory_swarm(&key),
+            &key,
+            crx,
+            disc_rx,
+            mdns_rx,
+            mdns_tx,
+            app.handle().clone(),
+        );
+
+        Self {
+            peer_id,
+            manager,
+            responses: Vec::new(),
+            app,
+            files,
+        }
+    }
+
+    fn with_db<T>(&self, op: impl FnOnce(&Connection) -> T) -> T {
+        let state = self.app.state::<crate::AppState>();
+        let conn = state.db_conn.lock().expect("db lock");
+        op(&conn)
+    }
+
+    /// What `peer` advertised over identify.
+    pub fn remote_caps(&self, peer: &PeerId) -> Option<&PeerCapabilities> {
+        self.manager.peer_capabilities.get(peer)
+    }
+
+    /// The chat the manager opened for `peer` when it connected.
+    pub fn chat_with(&self, peer: PeerId) -> String {
+        self.with_db(|conn| db::find_existing_local_chat_id_for_peer(conn, &peer.to_string()))
+            .expect("chat lookup")
+            .expect("chat opened on connect")
+    }
+
+    /// Store an outgoing message the way the send commands do.
+    fn store_outgoing(
+        &self,
+        to: PeerId,
+        content_type: &str,
+        text: &str,
+        file_hash: Option<&str>,
+    ) -> String {
+        let msg = db::Message {
+            id: crate::chat::message_id::new_message_id(),
+            chat_id: self.chat_with(to),
+            peer_id: "Me".to_string(),
+            timestamp: 1_700_000_000,
+            content: crate::chat::message::MessageContent::from_parts(
+                content_type,
+                Some(text.to_string()),
+                file_hash.map(ToOwned::to_owned),
+                Default::default(),
+            ),
+            status: "pending".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+        self.with_db(|conn| db::insert_message(conn, &msg))
+            .expect("store outgoing message");
+        msg.id
+    }
+
+    /// Store a text message as ours and send it; returns its id.
+    pub async fn send_text(&mut self, to: PeerId, text: &str) -> String {
+        let msg_id = self.store_outgoing(to, "text", text, None);
+        self.manager
+            .dispatch_command(NetworkCommand::SendDirectText {
+                target_peer_id: to.to_string(),
+                msg_id: msg_id.clone(),
+                timestamp: 1_700_000_000,
+                sender_alias: None,
+                content: text.to_string(),
+                translation: None,
+            })
+            .await;
+        msg_id
+    }
+
+    pub async fn send_read_receipt(&mut self, to: PeerId, msg_ids: &[String]) {
+        self.manager
+            .dispatch_command(NetworkCommand::SendReadReceipt {
+                target_peer_id: to.to_string(),
+                msg_ids: msg_ids.to_vec(),
+            })
+            .await;
+    }
+
+    /// Chunk `data` into our object store and send it as a document; returns
+    /// its file hash.
+    pub async fn send_document(&mut self, to: PeerId, data: &[u8]) -> String {
+        let root = Some(self.files.path().to_path_buf());
+        let file_hash = self
+            .with_db(|conn| {
+                crate::storage::object::create(conn, data, Some("harness.bin"), None, root)
+            })
+            .expect("store object");
+        let msg_id = self.store_outgoing(to, "document", "harness.bin", Some(&file_hash));
+        self.manager
+            .dispatch_command(NetworkCommand::SendDirectMedia {
+                kind: DirectMediaKind::Document,
+                target_peer_id: to.to_string(),
+                file_hash: file_hash.clone(),
+                file_name: Some("harness.bin".to_string()),
+                msg_id,
+                timestamp: 1_700_000_000,
+            })
+            .await;
+        file_hash
+    }
+
+    /// Send a bare request of `msg_type`, as a newer peer might.
+    pub fn send_raw(&mut self, to: PeerId, id: &str, msg_type: DirectMessageKind) {
+        let request = DirectMessageRequest {
+            id: id.to_string(),
+            sender_id: self.peer_id.to_string(),
+            msg_type,
+            text_content: None,
+            file_hash: None,
+            timestamp: 1_700_000_000,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        };
+        self.manager.send_direct_request(&to, request);
+    }
+
+    pub fn message(&self, msg_id: &str) -> Option<db::Message> {
+        self.with_db(|conn| db::get_message(conn, msg_id).ok().flatten())
+    }
+
+    pub fn message_status(&self, msg_id: &str) -> Option<String> {
+        self.message(msg_id).map(|msg| msg.status)
+    }
+
+    pub fn file_complete(&self, file_hash: &str) -> bool {
+        self.with_db(|conn| crate::storage::object::info(conn, file_hash))
+            .ok()
+            .flatten()
+            .is_some_and(|info| info.is_complete)
+    }
+
+    pub fn load_file(&self, file_hash: &str) -> Vec<u8> {
+        let root = Some(self.files.path().to_path_buf());
+        self.with_db(|conn| crate::storage::object::load(conn, file_hash, root))
+            .expect("load object")
+    }
+
+    /// The next input for the manager. Only waits on receivers, so dropping
+    /// it for the other peer's input loses nothing.
+    async fn next_input(&mut self) -> Input {
+        let manager = &mut self.manager;
+        tokio::select! {
+            event = manager.swarm.select_next_some() => Input::Swarm(event),
+            Some(result) = manager.transfer_result_rx.recv() => Input::Transfer(result),
+            Some(event) = manager.blob_stream_event_rx.recv() => Input::Blob(event),
+        }
+    }
+
+    async fn handle(&mut self, input: Input) {
+        match input {
+            Input::Swarm(event) => {
+                if let SwarmEvent::Behaviour(RChatBehaviourEvent::DirectMessage(
+                    RequestResponseEvent::Message {
+                        message: RequestResponseMessage::Response { response, .. },
+                        ..
+                    },
+                )) = &event
+                {
+                    self.responses.push(response.clone());
+                }
+                self.manager.handle_swarm_event(event).await;
+            }
+            Input::Transfer(result) => self.manager.handle_transfer_result(result).await,
+            Input::Blob(event) => self.manager.handle_blob_stream_event(event).await,
+        }
+    }
+}
+
+/// Two peers, `a` dialing `b`.
+pub(super) struct TestNet {
+    pub a: TestPeer,
+    pub b: TestPeer,
+}
+
+impl TestNet {
+    /// Both peers connected and identified.
+    pub async fn connected() -> Self {
+        let mut net = Self {
+            a: TestPeer::new(),
+            b: TestPeer::new(),
+        };
+        net.b
+            .manager
+            .swarm
+            .listen_on("/memory/0".parse().expect("memory address"))
+            .expect("listen");
+        let addr: Multiaddr = tokio::time::timeout(FLOW_TIMEOUT, async {
+            loop {
+                let input = net.b.next_input().await;
+                let address = match &input {
+                    Input::Swarm(SwarmEvent::NewListenAddr { address, .. }) => {
+                        Some(address.clone())
+                    }
+                    _ => None,
+                };
+                net.b.handle(input).await;
+                if let Some(address) = address {
+                    return address;
+                }
+            }
+        })
+        .await
+        .expect("listen address");
+        net.a.manager.swarm.dial(addr).expect("dial");
+        let (a_id, b_id) = (net.a.peer_id, net.b.peer_id);
+        net.run_until(|net| {
+            net.a.remote_caps(&b_id).is_some() && net.b.remote_caps(&a_id).is_some()
+        })
+        .await;
+        net
+    }
+
+    /// Drive both managers until `done` holds; panics after [`FLOW_TIMEOUT`].
+    pub async fn run_until(&mut self, done: impl Fn(&Self) -> bool) {
+        tokio::time::timeout(FLOW_TIMEOUT, async {
+            while !done(self) {
+                let (from_a, input) = tokio::select! {
+                    input = self.a.next_input() => (true, input),
+                    input = self.b.next_input() => (false, input),
+                };
+                if from_a {
+                    self.a.handle(input).await;
+                } else {
+                    self.b.handle(input).await;
+                }
+            }
+        })
+        .await
+        .expect("flow did not complete in time");
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn peers_handshake_and_advertise_capabilities() {
+        let net = TestNet::connected().await;
+        let local: PeerCapabilities = capabilities::LOCAL.iter().copied().collect();
+        assert_eq!(net.a.remote_caps(&net.b.peer_id), Some(&local));
+        assert_eq!(net.b.remote_caps(&net.a.peer_id), Some(&local));
+    }
+
+    #[tokio::test]
+    async fn text_is_delivered_then_read() {
+        let mut net = TestNet::connected().await;
+        let (a_id, b_id) = (net.a.peer_id, net.b.peer_id);
+        let msg_id = net.a.send_text(b_id, "hello over memory").await;
+        net.run_until(|net| net.a.message_status(&msg_id).as_deref() == Some("delivered"))
+            .await;
+        let received = net.b.message(&msg_id).expect("stored by b");
+        assert_eq!(received.chat_id, net.b.chat_with(a_id));
+        assert_eq!(received.content.text(), Some("hello over memory"));
+
+        net.b
+            .send_read_receipt(a_id, std::slice::from_ref(&msg_id))
+            .await;
+        net.run_until(|net| net.a.message_status(&msg_id).as_deref() == Some("read"))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn file_is_transferred_in_sealed_chunks() {
+        let mut net = TestNet::connected().await;
+        let b_id = net.b.peer_id;
+        let data: Vec<u8> = (0..600_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let file_hash = net.a.send_document(b_id, &data).await;
+        net.run_until(|net| net.b.file_complete(&file_hash)).await;
+        assert_eq!(net.b.load_file(&file_hash), data);
+    }
+
+    #[tokio::test]
+    async fn unknown_kinds_are_acknowledged_and_ignored() {
+        let mut net = TestNet::connected().await;
+        let b_id = net.b.peer_id;
+        net.a
+            .send_raw(b_id, "from-the-future", DirectMessageKind::Unknown);
+        net.run_until(|net| {
+            net.a
+                .responses
+                .iter()
+                .any(|response| response.msg_id == "from-the-future")
+        })
+        .await;
+        let response = net
+            .a
+            .responses
+            .iter()
+            .find(|response| response.msg_id == "from-the-future")
+            .unwrap();
+        assert_eq!(response.status, "ignored");
+        assert!(net.b.message("from-the-future").is_none());
+    }
+}
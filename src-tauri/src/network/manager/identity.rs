@@ -0,0 +1,257 @@
+use super::*;
+use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+
+/// Larger avatars are not offered to friends; they keep showing the last one.
+const MAX_AVATAR_BYTES: usize = 2 * 1024 * 1024;
+
+impl NetworkManager {
+    /// Peers-table id for a friend we share a direct chat with.
+    pub(super) fn friend_row_id(&self, peer_id: &PeerId) -> Option<String> {
+        let peer_id_str = peer_id.to_string();
+        if self.temp_chat_by_peer_id.contains_key(&peer_id_str) {
+            return None;
+        }
+        let state = self.app_handle.state::<crate::AppState>();
+        let conn = state.db_conn.lock().ok()?;
+        crate::storage::db::find_existing_direct_chat_id_for_peer(&conn, &peer_id_str)
+            .ok()
+            .flatten()
+            .filter(|chat_id| crate::storage::db::is_peer(&conn, chat_id))
+            .or_else(|| {
+                (self.trusted_peer_ids.contains(peer_id)
+                    && crate::storage::db::is_peer(&conn, &peer_id_str))
+                .then_some(peer_id_str)
+            })
+    }
+
+    /// Our alias, avatar hash and mDNS discovery key (in `text_content`); the
+    /// avatar is put in the object store so friends can fetch it with the
+    /// regular file protocol.
+    async fn local_identity_request(&self) -> Option<DirectMessageRequest> {
+        let (profile, discovery_key) = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let mgr = state.config_manager.lock().await;
+            let mut config = mgr.load().await.ok()?;
+            if config.user.ensure_mdns_discovery_key() {
+                if let Err(e) = mgr.save(&config).await {
+                    eprintln!("[Identity] ⚠️ Failed to save mDNS discovery key: {}", e);
+                }
+            }
+            (config.user.profile, config.user.mdns_discovery_key)
+        };
+
+        let avatar_hash = profile.avatar_path.as_deref().and_then(|path| {
+            let bytes = match std::fs::read(path) {
+                Ok(bytes) if bytes.len() <= MAX_AVATAR_BYTES => bytes,
+                Ok(bytes) => {
+                    eprintln!(
+                        "[Identity] ⚠️ Avatar is {} bytes, not offering it (limit {})",
+                        bytes.len(),
+                        MAX_AVATAR_BYTES
+                    );
+                    return None;
+                }
+                Err(e) => {
+                    eprintln!("[Identity] ⚠️ Failed to read avatar {}: {}", path, e);
+                    return None;
+                }
+            };
+            let file_name = std::path::Path::new(path)
+                .file_name()
+                .and_then(|n| n.to_str());
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().ok()?;
+            crate::storage::object::create(&conn, &bytes, file_name, None, None)
+                .map_err(|e| eprintln!("[Identity] ⚠️ Failed to store avatar: {}", e))
+                .ok()
+        });
+
+        let now = now_unix_secs();
+        Some(DirectMessageRequest {
+            id: format!("identity-{}", now),
+            sender_id: self.swarm.local_peer_id().to_string(),
+            msg_type: DirectMessageKind::IdentityUpdate,
+            text_content: discovery_key,
+            file_hash: avatar_hash,
+            timestamp: now,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: profile.alias.filter(|alias| !alias.trim().is_empty()),
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        })
+    }
+
+    /// Tell a newly connected friend our current alias and avatar.
+    pub(super) async fn send_identity_update(&mut self, peer_id: PeerId) {
+        if self.friend_row_id(&peer_id).is_none() {
+            return;
+        }
+        if let Some(request) = self.local_identity_request().await {
+            self.send_direct_request(&peer_id, request);
+            println!(
+                "[Identity] 📤 Sent identity update to {}",
+                crate::logging::peer(&peer_id)
+            );
+        }
+    }
+
+    /// Push a profile change to every connected friend.
+    pub(super) async fn broadcast_identity_update(&mut self) {
+        let Some(request) = self.local_identity_request().await else {
+            return;
+        };
+        let friends: Vec<PeerId> = self
+            .swarm
+            .connected_peers()
+            .copied()
+            .filter(|peer_id| self.friend_row_id(peer_id).is_some())
+            .collect();
+        for peer_id in &friends {
+            self.send_direct_request(peer_id, request.clone());
+        }
+        println!(
+            "[Identity] 📤 Broadcast identity update to {} friend(s)",
+            friends.len()
+        );
+    }
+
+    fn emit_peer_identity_updated(&self, row_id: &str) {
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        let _ = self.app_handle.emit(
+            "peer-identity-updated",
+            serde_json::json!({
+                "peer_id": row_id,
+                "alias": crate::storage::db::get_peer_alias(&conn, row_id).ok().flatten(),
+                "avatar_hash": crate::storage::db::get_peer_avatar_hash(&conn, row_id).ok().flatten(),
+            }),
+        );
+    }
+
+    pub(super) async fn handle_identity_update(
+        &mut self,
+        peer: PeerId,
+        request: &DirectMessageRequest,
+    ) -> Result<(), String> {
+        let Some(row_id) = self.friend_row_id(&peer) else {
+            return Err("identity updates are only accepted from friends".to_string());
+        };
+        // A newer update supersedes any avatar still being fetched for this friend.
+        self.pending_avatars
+            .retain(|_, (_, pending_row)| *pending_row != row_id);
+
+        let alias = request
+            .sender_alias
+            .as_deref()
+            .map(str::trim)
+            .filter(|alias| !alias.is_empty());
+
+        let (updated, fetch_avatar) = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().map_err(|e| e.to_string())?;
+            if let Some(key) = request
+                .text_content
+                .as_deref()
+                .filter(|key| crate::network::mdns::decode_discovery_key(key).is_some())
+            {
+                crate::storage::db::set_peer_discovery_key(
+                    &conn,
+                    &peer.to_string(),
+                    key,
+                    now_unix_secs(),
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            let mut updated = match alias {
+                Some(alias) => crate::storage::db::apply_peer_alias(&conn, &row_id, alias)
+                    .map_err(|e| e.to_string())?,
+                None => false,
+            };
+            let current = crate::storage::db::get_peer_avatar_hash(&conn, &row_id)
+                .map_err(|e| e.to_string())?;
+            let mut fetch_avatar = None;
+            if current != request.file_hash {
+                let have_it = match request.file_hash.as_deref() {
+                    Some(hash) => crate::storage::object::info(&conn, hash)
+                        .map_err(|e| e.to_string())?
+                        .is_some_and(|info| info.is_complete),
+                    None => true,
+                };
+                if have_it {
+                    updated |= crate::storage::db::apply_peer_avatar(
+                        &conn,
+                        &row_id,
+                        request.file_hash.as_deref(),
+                    )
+                    .map_err(|e| e.to_string())?;
+                } else {
+                    fetch_avatar = request.file_hash.clone();
+                }
+            }
+            (updated, fetch_avatar)
+        };
+
+        if let Some(file_hash) = fetch_avatar {
+            if crate::storage::disk::is_degraded() {
+                println!(
+                    "[Storage] ⏸️ Low disk space, not fetching avatar from {}",
+                    crate::logging::peer(&peer)
+                );
+            } else {
+                println!(
+                    "[Identity] 📤 Fetching avatar {} from {}",
+                    file_hash,
+                    crate::logging::peer(&peer)
+                );
+                self.pending_avatars
+                    .insert(file_hash.clone(), (peer, row_id.clone()));
+                self.request_file_metadata(peer, &file_hash);
+            }
+        }
+
+        if updated {
+            println!(
+                "[Identity] 📥 {} updated alias/avatar",
+                crate::logging::peer(&peer)
+            );
+            self.emit_peer_identity_updated(&row_id);
+        }
+        Ok(())
+    }
+
+    /// Attach a fetched avatar to the friend that announced it.
+    pub(super) fn on_avatar_transfer_complete(&mut self, file_hash: &str) {
+        let Some((peer, row_id)) = self.pending_avatars.remove(file_hash) else {
+            return;
+        };
+        let updated = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            crate::storage::db::apply_peer_avatar(&conn, &row_id, Some(file_hash))
+        };
+        match updated {
+            Ok(true) => {
+                println!(
+                    "[Identity] 📥 Avatar from {} downloaded",
+                    crate::logging::peer(&peer)
+                );
+                self.emit_peer_identity_updated(&row_id);
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!(
+                "[Identity] ❌ Failed to save avatar for {}: {}",
+                crate::logging::peer(&peer),
+                e
+            ),
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! Ingest pipeline for incoming chat messages, direct and group alike.
+//!
+//! The swarm handlers only decode and validate the wire format, then hand an
+//! [`IncomingMessage`] to [`NetworkManager::ingest`], which runs the fixed
+//! stages of [`IngestStage::ORDER`]. After each stage's built-in step the
+//! hooks registered for it in [`HOOKS`] run in order; any of them can stop
+//! the message. New behaviour such as scoring, translation or filing goes into
+//! a hook instead of the event handlers.
+
+use super::*;
+use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+use std::collections::VecDeque;
+
+/// Message ids remembered for duplicate detection.
+const RECENT_IDS_CAPACITY: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum IngestStage {
+    /// Redelivered messages and echoes of our own.
+    Dedup,
+    /// Decide whether and how the message is accepted.
+    Security,
+    /// Hooks may adjust `db_msg` before it is stored.
+    Enrichment,
+    Persistence,
+    /// Attachments, bridges, metrics, OS notification, UI event, auto reply.
+    Notification,
+}
+
+impl IngestStage {
+    pub(super) const ORDER: [IngestStage; 5] = [
+        IngestStage::Dedup,
+        IngestStage::Security,
+        IngestStage::Enrichment,
+        IngestStage::Persistence,
+        IngestStage::Notification,
+    ];
+}
+
+pub(super) enum IngestOrigin {
+    Direct {
+        peer: PeerId,
+        request: DirectMessageRequest,
+    },
+    Group {
+        envelope: GroupMessageEnvelope,
+    },
+}
+
+pub(super) struct IncomingMessage {
+    pub origin: IngestOrigin,
+    pub chat_id: String,
+    pub db_msg: crate::storage::db::Message,
+    /// Temporary chats live in memory only.
+    pub temporary: bool,
+    /// First contact in a direct chat; set during [`IngestStage::Security`].
+    pub message_request: bool,
+    /// Chats/topics that saw the same text recently, including this one.
+    pub repeated_targets: usize,
+}
+
+impl IncomingMessage {
+    pub(super) fn direct(peer: PeerId, request: DirectMessageRequest, chat_id: String) -> Self {
+        let db_msg = super::build_incoming_dm_db_message(&request, chat_id.clone());
+        Self {
+            temporary: matches!(
+                crate::chat_kind::parse_chat_kind(&chat_id),
+                crate::chat_kind::ChatKind::TemporaryDirect
+            ),
+            origin: IngestOrigin::Direct { peer, request },
+            chat_id,
+            db_msg,
+            message_request: false,
+            repeated_targets: 1,
+        }
+    }
+
+    pub(super) fn group(envelope: GroupMessageEnvelope) -> Self {
+        let db_msg = super::build_incoming_group_db_message(&envelope);
+        Self {
+            temporary: crate::chat_kind::is_temp_group_chat_id(&envelope.group_id),
+            chat_id: envelope.group_id.clone(),
+            origin: IngestOrigin::Group { envelope },
+            db_msg,
+            message_request: false,
+            repeated_targets: 1,
+        }
+    }
+
+    fn sender_id(&self) -> &str {
+        match &self.origin {
+            IngestOrigin::Direct { request, .. } => &request.sender_id,
+            IngestOrigin::Group { envelope } => &envelope.sender_id,
+        }
+    }
+
+    fn text(&self) -> Option<&str> {
+        match &self.origin {
+            IngestOrigin::Direct { request, .. } => request.text_content.as_deref(),
+            IngestOrigin::Group { envelope } => envelope.text_content.as_deref(),
+        }
+    }
+
+    /// Sender and hash of an attachment we still have to fetch.
+    fn attachment(&self) -> Option<(PeerId, &str)> {
+        match &self.origin {
+            IngestOrigin::Direct { peer, request } => request
+                .msg_type
+                .needs_file_transfer()
+                .then_some(*peer)
+                .zip(request.file_hash.as_deref()),
+            IngestOrigin::Group { envelope } => envelope
+                .content_type
+                .needs_file_transfer()
+                .then(|| envelope.sender_id.parse::<PeerId>().ok())
+                .flatten()
+                .zip(envelope.file_hash.as_deref()),
+        }
+    }
+}
+
+/// Why a stage or hook stopped a message. Stopped messages still count as
+/// delivered towards the sender.
+pub(super) enum IngestFlow {
+    Continue,
+    Stop(&'static str),
+}
+
+type IngestHook = fn(&mut NetworkManager, &mut IncomingMessage) -> IngestFlow;
+
+/// Hooks per stage, run in this order after the stage's built-in step.
+const HOOKS: &[(IngestStage, &str, IngestHook)] = &[
+    (IngestStage::Security, "repeat_tracker", track_repeats),
+    (IngestStage::Enrichment, "group_typing", clear_group_typing),
+    (IngestStage::Enrichment, "mentions", resolve_mentions),
+    (IngestStage::Enrichment, "formatting", format_text),
+    (
+        IngestStage::Persistence,
+        "request_score",
+        score_message_request,
+    ),
+];
+
+fn track_repeats(manager: &mut NetworkManager, msg: &mut IncomingMessage) -> IngestFlow {
+    if let Some(text) = msg.text() {
+        msg.repeated_targets = manager.spam_tracker.observe(text, &msg.chat_id);
+    }
+    IngestFlow::Continue
+}
+
+/// A member who just sent a message is no longer typing it.
+fn clear_group_typing(manager: &mut NetworkManager, msg: &mut IncomingMessage) -> IngestFlow {
+    if let IngestOrigin::Group { envelope } = &msg.origin {
+        manager
+            .group_activity
+            .note_message(&envelope.group_id, &envelope.sender_id);
+    }
+    IngestFlow::Continue
+}
+
+/// Record which members a group message @-mentions.
+fn resolve_mentions(manager: &mut NetworkManager, msg: &mut IncomingMessage) -> IngestFlow {
+    if !matches!(msg.origin, IngestOrigin::Group { .. }) {
+        return IngestFlow::Continue;
+    }
+    let Some(text) = msg.text().map(str::to_string) else {
+        return IngestFlow::Continue;
+    };
+    let members = {
+        let state = manager.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return IngestFlow::Continue;
+        };
+        crate::storage::db::get_chat_member_aliases(&conn, &msg.chat_id).unwrap_or_default()
+    };
+    let mentions = crate::chat::mentions::parse(&text, &members);
+    msg.db_msg.content_metadata =
+        crate::chat::mentions::with_mentions(msg.db_msg.content_metadata.take(), &mentions);
+    IngestFlow::Continue
+}
+
+/// Parse markdown and shortcodes the same way the sender did.
+fn format_text(_manager: &mut NetworkManager, msg: &mut IncomingMessage) -> IngestFlow {
+    if let crate::chat::message::MessageContent::Text { text } = &msg.db_msg.content {
+        msg.db_msg.content_metadata =
+            crate::chat::format::with_formatting(msg.db_msg.content_metadata.take(), text);
+    }
+    IngestFlow::Continue
+}
+
+fn score_message_request(manager: &mut NetworkManager, msg: &mut IncomingMessage) -> IngestFlow {
+    if msg.message_request && !msg.temporary {
+        manager.record_message_request_score(&msg.db_msg, msg.repeated_targets);
+    }
+    IngestFlow::Continue
+}
+
+/// Bounded set of recently ingested message ids, oldest evicted first.
+#[derive(Debug, Default)]
+pub(super) struct RecentIds {
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl RecentIds {
+    pub(super) fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    pub(super) fn insert(&mut self, id: &str) {
+        if !self.ids.insert(id.to_string()) {
+            return;
+        }
+        self.order.push_back(id.to_string());
+        if self.order.len() > RECENT_IDS_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl NetworkManager {
+    /// Run `msg` through every stage. `Err` only for persistence failures,
+    /// which direct senders see as an error status and retry.
+    pub(super) async fn ingest(&mut self, mut msg: IncomingMessage) -> Result<(), String> {
+        for stage in IngestStage::ORDER {
+            let flow = match stage {
+                IngestStage::Dedup => self.ingest_dedup(&msg),
+                IngestStage::Security => self.ingest_security(&mut msg),
+                IngestStage::Enrichment => IngestFlow::Continue,
+                IngestStage::Persistence => self.ingest_persist(&msg).await?,
+                IngestStage::Notification => {
+                    self.ingest_notify(&msg).await;
+                    IngestFlow::Continue
+                }
+            };
+            if let IngestFlow::Stop(reason) = flow {
+                println!(
+                    "[Ingest] ⏹️ {} stopped at {:?}: {}",
+                    msg.db_msg.id, stage, reason
+                );
+                return Ok(());
+            }
+
+            for (hook_stage, name, hook) in HOOKS {
+                if *hook_stage != stage {
+                    continue;
+                }
+                if let IngestFlow::Stop(reason) = hook(self, &mut msg) {
+                    println!(
+                        "[Ingest] ⏹️ {} stopped by {} at {:?}: {}",
+                        msg.db_msg.id, name, stage, reason
+                    );
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn ingest_dedup(&self, msg: &IncomingMessage) -> IngestFlow {
+        if msg.sender_id() == self.swarm.local_peer_id().to_string() {
+            return IngestFlow::Stop("own message");
+        }
+        if self.ingested_ids.contains(&msg.db_msg.id) {
+            return IngestFlow::Stop("duplicate");
+        }
+        if !msg.temporary {
+            let state = self.app_handle.state::<crate::AppState>();
+            let stored = state
+                .db_conn
+                .lock()
+                .ok()
+                .and_then(|conn| crate::storage::db::get_message(&conn, &msg.db_msg.id).ok())
+                .flatten();
+            if stored.is_some() {
+                return IngestFlow::Stop("already stored");
+            }
+        }
+        IngestFlow::Continue
+    }
+
+    fn ingest_security(&mut self, msg: &mut IncomingMessage) -> IngestFlow {
+        msg.message_request = matches!(msg.origin, IngestOrigin::Direct { .. })
+            && matches!(
+                crate::chat_kind::parse_chat_kind(&msg.chat_id),
+                crate::chat_kind::ChatKind::Direct
+            )
+            && self.is_message_request(&msg.chat_id, msg.sender_id());
+        IngestFlow::Continue
+    }
+
+    async fn ingest_persist(&mut self, msg: &IncomingMessage) -> Result<IngestFlow, String> {
+        if msg.temporary {
+            let network_state = self.app_handle.state::<crate::NetworkState>();
+            let mut temp_state = network_state.temporary_state.lock().await;
+            temp_state
+                .messages
+                .entry(msg.chat_id.clone())
+                .or_default()
+                .push(msg.db_msg.clone());
+        } else {
+            match &msg.origin {
+                IngestOrigin::Direct { request, .. } => {
+                    self.persist_incoming_dm_message(request, msg.chat_id.clone(), msg.db_msg.clone())
+                        .await
+                        .map_err(|e| {
+                            format!(
+                                "Failed to persist {} message (id={}, chat_id={}, peer_id={}, file_hash={:?}): {}",
+                                request.msg_type.as_str(),
+                                request.id,
+                                msg.chat_id,
+                                request.sender_id,
+                                request.file_hash,
+                                e
+                            )
+                        })?;
+                    println!("[DM] ✅ Message saved");
+                }
+                IngestOrigin::Group { envelope } => {
+                    self.persist_incoming_group_message(envelope, msg.db_msg.clone())
+                        .await
+                        .map_err(|e| {
+                            format!(
+                                "Failed to save message {} for {}: {}",
+                                msg.db_msg.id, msg.db_msg.chat_id, e
+                            )
+                        })?;
+                }
+            }
+        }
+        self.ingested_ids.insert(&msg.db_msg.id);
+        Ok(IngestFlow::Continue)
+    }
+
+    fn auto_download_allowed(&self, msg: &IncomingMessage) -> bool {
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return true;
+        };
+        crate::chat::auto_download::should_download(
+            &conn,
+            &msg.db_msg.chat_id,
+            msg.db_msg.content.content_type(),
+        )
+    }
+
+    async fn ingest_notify(&mut self, msg: &IncomingMessage) {
+        if let Some((sender, file_hash)) = msg.attachment() {
+            if crate::storage::disk::is_degraded() {
+                println!(
+                    "[Storage] ⏸️ Low disk space, not auto-downloading media for {}",
+                    msg.db_msg.id
+                );
+            } else if !self.auto_download_allowed(msg) {
+                println!(
+                    "[ChunkTransfer] ⏸️ Auto-download off for {} in {}, keeping the file offer only",
+                    msg.db_msg.content.content_type(),
+                    msg.db_msg.chat_id
+                );
+            } else {
+                let swarm = match &msg.origin {
+                    IngestOrigin::Group { envelope } => {
+                        self.start_swarm_download(file_hash, sender, &envelope.group_id)
+                    }
+                    IngestOrigin::Direct { .. } => false,
+                };
+                if !swarm {
+                    println!("[ChunkTransfer] 📤 Requesting metadata for {}", file_hash);
+                    self.request_file_metadata(sender, file_hash);
+                }
+            }
+        }
+
+        if let IngestOrigin::Group { envelope } = &msg.origin {
+            // Bridged in by another member; their bridge already covers the remote side.
+            if !msg.temporary && envelope.bridged.is_none() {
+                let sender_name = envelope
+                    .sender_alias
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_else(|| envelope.sender_id.chars().take(12).collect());
+                crate::bridge::relay_outbound(
+                    &self.app_handle,
+                    &envelope.group_id,
+                    &envelope.id,
+                    &envelope.sender_id,
+                    &sender_name,
+                    envelope.content_type,
+                    envelope.text_content.as_deref(),
+                )
+                .await;
+            }
+        }
+
+        crate::storage::metrics::record(crate::storage::metrics::MESSAGES_RECEIVED);
+        crate::chat::notify::dispatch(&self.app_handle, &msg.db_msg);
+        crate::chat::suggest::on_message_received(&self.app_handle, &msg.db_msg);
+        let _ = self.app_handle.emit("message-received", msg.db_msg.clone());
+
+        if let IngestOrigin::Direct { peer, request } = &msg.origin {
+            if !msg.message_request {
+                self.maybe_auto_reply(*peer, &msg.chat_id, request).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_ids_evict_oldest_first() {
+        let mut recent = RecentIds::default();
+        for i in 0..=RECENT_IDS_CAPACITY {
+            recent.insert(&i.to_string());
+        }
+        recent.insert("1");
+        assert!(!recent.contains("0"));
+        assert!(recent.contains("1"));
+        assert!(recent.contains(&RECENT_IDS_CAPACITY.to_string()));
+        assert_eq!(recent.order.len(), RECENT_IDS_CAPACITY);
+    }
+}
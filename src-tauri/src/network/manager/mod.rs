@@ -1,6 +1,7 @@
 use crate::network::behaviour::{RChatBehaviour, RChatBehaviourEvent};
 use crate::network::command::NetworkCommand;
 use crate::network::gossip::GroupMessageEnvelope;
+use crate::AppHandle;
 use futures::StreamExt;
 use libp2p::{
     swarm::{ConnectionId, SwarmEvent},
@@ -12,10 +13,23 @@ use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tauri::async_runtime::Receiver;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{Emitter, Manager};
 
+mod auto_reply;
+mod blob;
 #[path = "../../live/broadcast/manager.rs"]
 mod broadcast;
+mod capabilities;
+mod connection_test;
+mod group_activity;
+mod group_admin;
+mod group_history;
+mod group_invite;
+mod group_keys;
+mod handshakes;
+mod identity;
+mod ingest;
+mod outbox;
 mod persistence;
 mod punching;
 mod run_loop;
@@ -27,6 +41,8 @@ mod video_call;
 #[path = "../../live/voice/manager.rs"]
 mod voice_call;
 
+#[cfg(test)]
+mod harness;
 #[cfg(test)]
 mod tests;
 
@@ -324,18 +340,32 @@ impl PeerTransportRegistry {
             .get(peer_id)
             .and_then(|ids| ids.last().copied())
     }
+
+    /// Newest QUIC connection, or else the newest TCP one.
+    fn newest_connection_id(&self, peer_id: &PeerId) -> Option<ConnectionId> {
+        self.newest_quic_connection_id(peer_id).or_else(|| {
+            self.tcp_connections_by_peer
+                .get(peer_id)
+                .and_then(|ids| ids.last().copied())
+        })
+    }
+}
+
+fn now_unix_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
 }
 
 pub(super) fn quic_addresses_for_peer(
-    local_peers: &HashMap<PeerId, Vec<Multiaddr>>,
+    local_peers: &crate::network::local_peers::LocalPeerTable,
     peer_id: &PeerId,
 ) -> Vec<Multiaddr> {
     local_peers
-        .get(peer_id)
+        .addresses(peer_id)
         .into_iter()
-        .flat_map(|addrs| addrs.iter())
         .filter(|addr| PeerTransportRegistry::is_quic_addr(addr))
-        .cloned()
         .collect()
 }
 
@@ -346,6 +376,10 @@ pub(super) enum OutgoingDialSource {
     Gist,
     Punch,
     VoiceQuic,
+    PathUpgrade,
+    ConnectionTest,
+    Reconnect,
+    GroupInvite,
     Unknown,
 }
 
@@ -357,6 +391,10 @@ impl OutgoingDialSource {
             Self::Gist => "gist",
             Self::Punch => "punch",
             Self::VoiceQuic => "voice_quic",
+            Self::PathUpgrade => "path_upgrade",
+            Self::ConnectionTest => "connection_test",
+            Self::Reconnect => "reconnect",
+            Self::GroupInvite => "group_invite",
             Self::Unknown => "unknown",
         }
     }
@@ -429,8 +467,13 @@ pub struct NetworkManager {
     mdns_started: bool,
     // Lifecycle handle for mDNS service threads.
     mdns_handle: Option<crate::network::mdns::MdnsServiceHandle>,
+    // Advertisement level of the running mDNS service.
+    mdns_advertised: crate::storage::config::MdnsPrivacy,
+    // Last connectivity settings read from `NetworkState`, used while the
+    // runtime copy is locked so a busy lock never turns features back on.
+    last_connectivity: std::sync::Mutex<crate::storage::config::ConnectivitySettings>,
     // Track local peers discovered via mDNS
-    local_peers: HashMap<PeerId, Vec<Multiaddr>>,
+    local_peers: crate::network::local_peers::LocalPeerTable,
     // Per-peer in-flight mDNS dial timestamps.
     mdns_dial_inflight: HashMap<PeerId, std::time::Instant>,
     // Per-peer next-allowed mDNS dial instant (debounce + backoff).
@@ -461,6 +504,10 @@ pub struct NetworkManager {
     active_punch_targets: HashMap<String, (Multiaddr, std::time::Instant)>,
     // Joined group IDs we are currently subscribed to
     subscribed_group_ids: HashSet<String>,
+    // Typing/presence of group members, emitted to the UI as `group-activity`.
+    group_activity: crate::chat::group_activity::GroupActivityTracker,
+    // When we last announced typing per group, to throttle the signal.
+    typing_sent_at: HashMap<String, std::time::Instant>,
     // Fast lookup cache: GitHub username -> PeerId string
     peer_id_by_github: HashMap<String, String>,
     // Reverse lookup cache: PeerId string -> GitHub username
@@ -469,10 +516,56 @@ pub struct NetworkManager {
     temp_peer_by_chat_id: HashMap<String, String>,
     // Reverse temporary routing cache: peer id -> temp chat id
     temp_chat_by_peer_id: HashMap<String, String>,
+    // Identical-text sightings across chats/topics for spam scoring.
+    spam_tracker: crate::chat::spam::RepeatTracker,
+    // Ids of messages that went through the ingest pipeline, for dedup.
+    ingested_ids: ingest::RecentIds,
+    // Per-peer keys sealing file-transfer chunk payloads end to end.
+    chunk_keyring: crate::network::chunk_crypto::ChunkKeyring,
+    // Our libp2p identity, for signing connection requests.
+    local_key: libp2p::identity::Keypair,
+    // App identity key (config `identity_private_key`) signing group envelopes.
+    identity_signing_key: Option<ed25519_dalek::SigningKey>,
+    // Friends' stored `ed25519_pubkey`, by their mapped peer id.
+    friend_identity_keys: HashMap<String, ed25519_dalek::VerifyingKey>,
+    // Our libp2p identity as used to hand out and open group keys.
+    group_identity: Option<crate::network::group_key::GroupIdentity>,
+    // Last key update published per group we administer, for late subscribers.
+    group_key_updates: HashMap<String, Vec<u8>>,
+    // Group invites we handed out this session.
+    group_invites: Vec<crate::network::group_invite::GroupInvite>,
+    // Group invites we are redeeming, by inviter, until their welcome arrives.
+    pending_group_joins: HashMap<PeerId, crate::network::group_invite::GroupInvite>,
+    // History backfills we started, by the admin asked and the group.
+    pending_history_requests: HashSet<(PeerId, String)>,
+    // Optional features each identified peer advertises.
+    peer_capabilities: crate::network::capabilities::CapabilityRegistry,
     // Connection transport capability registry per peer.
     peer_transport_registry: PeerTransportRegistry,
+    // Live connections per peer tagged LAN / direct / relay for path selection.
+    path_registry: crate::network::path::PathRegistry,
+    // Recent ping round trips and losses per peer.
+    quality_registry: crate::network::quality::QualityRegistry,
+    // Redials friends whose connection dropped.
+    reconnect_scheduler: crate::network::reconnect::ReconnectScheduler,
+    // Chat messages awaiting the peer's answer, retried on failure.
+    outbox: crate::network::outbox::Outbox<libp2p::request_response::OutboundRequestId>,
+    // Running `test_peer_connection` probes.
+    connection_tests: Vec<crate::network::probe::ProbeRun>,
+    // Partially received fragmented DMs and gossip messages.
+    fragment_reassembler: crate::network::fragment::Reassembler,
+    // Reputation per peer (persisted for known peers) and inbound rate limits.
+    peer_reputation: HashMap<PeerId, crate::storage::db::PeerReputation>,
+    flood_guard: crate::network::flood::FloodGuard,
     // Transfer per-file ordering/emit state.
     transfer_states: HashMap<String, transfer::TransferState>,
+    /// File metadata responses held back by the bandwidth cap, by file hash.
+    paused_downloads:
+        HashMap<String, (PeerId, crate::network::direct_message::DirectMessageRequest)>,
+    /// Group attachments fetched from several members, by file hash.
+    swarm_downloads: HashMap<String, crate::network::swarm_download::SwarmDownload>,
+    /// Avatar file hash -> (friend, peers-table row) while its chunks download.
+    pending_avatars: HashMap<String, (PeerId, String)>,
     // Transfer worker queue sender.
     transfer_task_tx: tokio::sync::mpsc::Sender<transfer::TransferTask>,
     // Transfer worker queue result receiver.
@@ -514,6 +607,10 @@ pub struct NetworkManager {
     screen_broadcast_stream_session_id: Option<String>,
     // Current active outbound screen broadcast stream writer task.
     screen_broadcast_stream_writer_handle: Option<tauri::async_runtime::JoinHandle<()>>,
+    // Blob stream requests, chunks and failures from stream tasks.
+    blob_stream_event_rx: tokio::sync::mpsc::Receiver<blob::BlobStreamEvent>,
+    // Blob stream event sender cloned into accept/download tasks.
+    blob_stream_event_tx: tokio::sync::mpsc::Sender<blob::BlobStreamEvent>,
     // Screen broadcast capture/encode worker events.
     screen_broadcast_worker_event_rx:
         tokio::sync::mpsc::Receiver<broadcast::ScreenBroadcastWorkerEvent>,
@@ -671,18 +768,59 @@ fn build_incoming_dm_db_message(
         _ => request.file_hash.clone(),
     };
 
+    let content_type = request.msg_type.as_str();
+    let content_metadata = request
+        .translation
+        .as_ref()
+        .filter(|_| request.msg_type == DirectMessageKind::Text)
+        .map(crate::chat::translate::translation_metadata)
+        .or_else(|| {
+            wire_content_metadata(
+                request.content.as_ref(),
+                content_type,
+                text_content.as_deref(),
+                file_hash.as_deref(),
+            )
+        });
     crate::storage::db::Message {
         id: request.id.clone(),
         chat_id,
         peer_id: request.sender_id.clone(),
         timestamp: request.timestamp,
-        content_type: request.msg_type.as_str().to_string(),
-        text_content,
-        file_hash,
+        content: crate::chat::message::MessageContent::from_columns(
+            content_type,
+            text_content,
+            file_hash,
+            content_metadata.as_deref(),
+        ),
         status: "delivered".to_string(),
-        content_metadata: None,
+        content_metadata,
         sender_alias: request.sender_alias.clone(),
+        logical: request.logical.unwrap_or(0),
+    }
+}
+
+/// Media metadata from the typed content of a payload, if that content agrees
+/// with the flat fields the message is built from. Size and poster frame are
+/// left for hydration once the file is here: the poster object only exists on
+/// the sender's side.
+fn wire_content_metadata(
+    content: Option<&crate::chat::message::MessageContent>,
+    content_type: &str,
+    text_content: Option<&str>,
+    file_hash: Option<&str>,
+) -> Option<String> {
+    let content =
+        content.filter(|content| content.matches_parts(content_type, text_content, file_hash))?;
+    let metadata = crate::chat::message::ContentMetadata {
+        size_bytes: None,
+        thumbnail_hash: None,
+        ..content.metadata()?.clone()
+    };
+    if metadata == crate::chat::message::ContentMetadata::default() {
+        return None;
     }
+    serde_json::to_string(&metadata).ok()
 }
 
 fn build_incoming_group_db_message(envelope: &GroupMessageEnvelope) -> crate::storage::db::Message {
@@ -718,17 +856,28 @@ fn build_incoming_group_db_message(envelope: &GroupMessageEnvelope) -> crate::st
         _ => envelope.file_hash.clone(),
     };
 
+    let content_type = envelope.content_type.as_str();
+    let content_metadata = wire_content_metadata(
+        envelope.content.as_ref(),
+        content_type,
+        text_content.as_deref(),
+        file_hash.as_deref(),
+    );
     crate::storage::db::Message {
         id: envelope.id.clone(),
         chat_id: envelope.group_id.clone(),
         peer_id: envelope.sender_id.clone(),
         timestamp: envelope.timestamp,
-        content_type: envelope.content_type.as_str().to_string(),
-        text_content,
-        file_hash,
+        content: crate::chat::message::MessageContent::from_columns(
+            content_type,
+            text_content,
+            file_hash,
+            content_metadata.as_deref(),
+        ),
         status: "delivered".to_string(),
-        content_metadata: None,
+        content_metadata,
         sender_alias: envelope.sender_alias.clone(),
+        logical: envelope.logical.unwrap_or(0),
     }
 }
 
@@ -742,6 +891,7 @@ impl NetworkManager {
 
     pub fn new(
         mut swarm: Swarm<RChatBehaviour>,
+        local_key: &libp2p::identity::Keypair,
         crx: Receiver<NetworkCommand>,
         disc_rx: Receiver<Multiaddr>,
         mdns_rx: Receiver<crate::network::mdns::MdnsPeer>,
@@ -788,6 +938,13 @@ impl NetworkManager {
         } else {
             eprintln!("[Broadcast] Screen broadcast stream incoming receiver was already taken");
         }
+        let (blob_stream_event_tx, blob_stream_event_rx) =
+            tokio::sync::mpsc::channel(blob::BLOB_STREAM_EVENT_CAPACITY);
+        if let Some(incoming) = swarm.behaviour_mut().blob_stream.take_incoming() {
+            blob::start_blob_stream_accept_loop(incoming, blob_stream_event_tx.clone());
+        } else {
+            eprintln!("[Blob] Blob stream incoming receiver was already taken");
+        }
         let (screen_broadcast_worker_event_tx, screen_broadcast_worker_event_rx) =
             tokio::sync::mpsc::channel(512);
         let (video_encode_tx, video_encode_event_rx, video_encode_worker_handle) =
@@ -801,8 +958,12 @@ impl NetworkManager {
             mdns_tx,
             mdns_started: false,
             mdns_handle: None,
+            mdns_advertised: crate::storage::config::MdnsPrivacy::Full,
+            last_connectivity: std::sync::Mutex::new(
+                crate::storage::config::ConnectivitySettings::invisible(),
+            ),
             app_handle,
-            local_peers: HashMap::new(),
+            local_peers: crate::network::local_peers::LocalPeerTable::default(),
             mdns_dial_inflight: HashMap::new(),
             mdns_backoff_until: HashMap::new(),
             mdns_dial_failures: HashMap::new(),
@@ -817,12 +978,37 @@ impl NetworkManager {
             pending_shadow_polls: HashMap::new(),
             active_punch_targets: HashMap::new(),
             subscribed_group_ids: HashSet::new(),
+            group_activity: Default::default(),
+            typing_sent_at: HashMap::new(),
             peer_id_by_github: HashMap::new(),
             github_by_peer_id: HashMap::new(),
             temp_peer_by_chat_id: HashMap::new(),
             temp_chat_by_peer_id: HashMap::new(),
+            spam_tracker: crate::chat::spam::RepeatTracker::default(),
+            ingested_ids: ingest::RecentIds::default(),
+            chunk_keyring: crate::network::chunk_crypto::ChunkKeyring::new(local_key),
+            local_key: local_key.clone(),
+            identity_signing_key: None,
+            friend_identity_keys: HashMap::new(),
+            group_identity: crate::network::group_key::GroupIdentity::new(local_key),
+            group_key_updates: HashMap::new(),
+            group_invites: Vec::new(),
+            pending_group_joins: HashMap::new(),
+            pending_history_requests: HashSet::new(),
+            peer_capabilities: crate::network::capabilities::CapabilityRegistry::default(),
             peer_transport_registry: PeerTransportRegistry::default(),
+            path_registry: crate::network::path::PathRegistry::default(),
+            quality_registry: crate::network::quality::QualityRegistry::default(),
+            reconnect_scheduler: crate::network::reconnect::ReconnectScheduler::default(),
+            outbox: crate::network::outbox::Outbox::default(),
+            connection_tests: Vec::new(),
+            fragment_reassembler: crate::network::fragment::Reassembler::default(),
+            peer_reputation: HashMap::new(),
+            flood_guard: crate::network::flood::FloodGuard::default(),
             transfer_states: HashMap::new(),
+            paused_downloads: HashMap::new(),
+            swarm_downloads: HashMap::new(),
+            pending_avatars: HashMap::new(),
             transfer_task_tx,
             transfer_result_rx,
             transfer_worker_shutdown,
@@ -840,6 +1026,8 @@ impl NetworkManager {
             active_broadcast: None,
             screen_broadcast_stream_event_rx,
             screen_broadcast_stream_event_tx,
+            blob_stream_event_rx,
+            blob_stream_event_tx,
             screen_broadcast_stream_tx: None,
             screen_broadcast_stream_session_id: None,
             screen_broadcast_stream_writer_handle: None,
@@ -916,7 +1104,7 @@ impl NetworkManager {
             }
             println!(
                 "[AutoConnect] Cleared stale in-flight attempt for {} (timed out)",
-                peer_id
+                crate::logging::peer(&peer_id)
             );
             false
         });
@@ -942,7 +1130,7 @@ impl NetworkManager {
         let (peer_present, peer_known_mdns, peer_inflight_mdns) = if let Some(peer) = peer_id {
             (
                 true,
-                self.local_peers.contains_key(&peer),
+                self.local_peers.contains(&peer),
                 self.mdns_dial_inflight.contains_key(&peer),
             )
         } else {
@@ -965,14 +1153,18 @@ impl NetworkManager {
         self.prune_stale_mdns_dials(now);
 
         if self.swarm.is_connected(&peer_id) {
-            println!("[mDNS] Dial skipped for {}: already connected", peer_id);
+            println!(
+                "[mDNS] Dial skipped for {}: already connected",
+                crate::logging::peer(&peer_id)
+            );
             return;
         }
         if let Some(started) = self.mdns_dial_inflight.get(&peer_id) {
             let elapsed_ms = now.duration_since(*started).as_millis();
             println!(
                 "[mDNS] Dial skipped for {}: in-flight ({}ms elapsed)",
-                peer_id, elapsed_ms
+                crate::logging::peer(&peer_id),
+                elapsed_ms
             );
             return;
         }
@@ -982,7 +1174,9 @@ impl NetworkManager {
                 let attempts = self.mdns_dial_failures.get(&peer_id).copied().unwrap_or(0);
                 println!(
                     "[mDNS] Dial skipped for {}: backoff active (attempt {}, retry in {:.1}s)",
-                    peer_id, attempts, remaining
+                    crate::logging::peer(&peer_id),
+                    attempts,
+                    remaining
                 );
             }
         }
@@ -1034,7 +1228,7 @@ impl NetworkManager {
         self.mdns_backoff_until.insert(peer_id, now + backoff);
         println!(
             "[mDNS] Dial failure recorded for {}: attempt {}, next retry in {:.1}s",
-            peer_id,
+            crate::logging::peer(&peer_id),
             *attempts,
             backoff.as_secs_f32()
         );
@@ -1053,9 +1247,13 @@ impl NetworkManager {
         let mut next_peer_id_by_github: HashMap<String, String> = HashMap::new();
         let mut next_github_by_peer_id: HashMap<String, String> = HashMap::new();
 
-        let state = self.app_handle.state::<crate::AppState>();
-        let mgr = state.config_manager.lock().await;
-        if let Ok(config) = mgr.load().await {
+        let config = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let mgr = state.config_manager.lock().await;
+            mgr.load().await.ok()
+        };
+        if let Some(config) = config {
+            self.refresh_identity_keys(&config.user);
             for (gh_user, peer_id) in config.user.github_peer_mapping {
                 next_peer_id_by_github.insert(gh_user.clone(), peer_id.clone());
                 next_github_by_peer_id.insert(peer_id, gh_user);
@@ -1066,6 +1264,27 @@ impl NetworkManager {
         self.github_by_peer_id = next_github_by_peer_id;
     }
 
+    fn refresh_identity_keys(&mut self, user: &crate::storage::config::UserConfig) {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+        let decode = |b64: &str| -> Option<[u8; 32]> { BASE64.decode(b64).ok()?.try_into().ok() };
+
+        self.identity_signing_key = user
+            .identity_private_key
+            .as_deref()
+            .and_then(decode)
+            .map(|seed| ed25519_dalek::SigningKey::from_bytes(&seed));
+        self.friend_identity_keys = user
+            .friends
+            .iter()
+            .filter_map(|friend| {
+                let peer_id = user.github_peer_mapping.get(&friend.username)?;
+                let key = decode(friend.ed25519_pubkey.as_deref()?)?;
+                let key = ed25519_dalek::VerifyingKey::from_bytes(&key).ok()?;
+                Some((peer_id.clone(), key))
+            })
+            .collect();
+    }
+
     pub(super) async fn refresh_trusted_peer_registry(&mut self) {
         let mut trusted = HashSet::new();
 
@@ -1081,6 +1300,13 @@ impl NetworkManager {
                     }
                 }
             }
+            if let Ok(reputations) = crate::storage::db::list_peer_reputations(&conn) {
+                for rep in reputations {
+                    if let Ok(peer_id) = rep.peer_id.parse::<PeerId>() {
+                        self.peer_reputation.insert(peer_id, rep);
+                    }
+                }
+            }
         }
 
         let mgr = state.config_manager.lock().await;
@@ -1139,7 +1365,7 @@ impl NetworkManager {
             .insert(peer_id, now + backoff);
         println!(
             "[AutoConnect] Attempt failed for {} (attempt {}), retry in {:.1}s",
-            peer_id,
+            crate::logging::peer(&peer_id),
             *attempts,
             backoff.as_secs_f32()
         );
@@ -1150,25 +1376,31 @@ impl NetworkManager {
         self.prune_stale_mdns_dials(now);
 
         if !self.trusted_peer_ids.contains(&peer_id) {
-            println!("[AutoConnect] Skipped unknown peer {}", peer_id);
+            println!(
+                "[AutoConnect] Skipped unknown peer {}",
+                crate::logging::peer(&peer_id)
+            );
             return;
         }
         if self.swarm.is_connected(&peer_id) {
             self.note_auto_connect_success(peer_id);
-            println!("[AutoConnect] Skipped {} (already connected)", peer_id);
+            println!(
+                "[AutoConnect] Skipped {} (already connected)",
+                crate::logging::peer(&peer_id)
+            );
             return;
         }
         if self.pending_requests.contains(&peer_id) || self.incoming_requests.contains(&peer_id) {
             println!(
                 "[AutoConnect] Skipped {} (request already in-flight)",
-                peer_id
+                crate::logging::peer(&peer_id)
             );
             return;
         }
         if self.auto_connect_inflight.contains_key(&peer_id) {
             println!(
                 "[AutoConnect] Skipped {} (auto-connect attempt in-flight)",
-                peer_id
+                crate::logging::peer(&peer_id)
             );
             return;
         }
@@ -1176,14 +1408,26 @@ impl NetworkManager {
             if *until > now {
                 println!(
                     "[AutoConnect] Skipped {} (cooldown {:.1}s)",
-                    peer_id,
+                    crate::logging::peer(&peer_id),
                     until.duration_since(now).as_secs_f32()
                 );
                 return;
             }
         }
+        let reputation = self.peer_reputation_score(&peer_id);
+        if reputation <= crate::network::reputation::DIAL_SKIP_THRESHOLD {
+            println!(
+                "[AutoConnect] Skipped {} (reputation {:.1})",
+                crate::logging::peer(&peer_id),
+                reputation
+            );
+            return;
+        }
 
-        println!("[AutoConnect] Auto-requesting trusted peer {}", peer_id);
+        println!(
+            "[AutoConnect] Auto-requesting trusted peer {}",
+            crate::logging::peer(&peer_id)
+        );
         self.note_auto_connect_started(peer_id);
         self.handle_connection_request(&peer_id.to_string()).await;
     }
@@ -1204,7 +1448,8 @@ impl NetworkManager {
                 } else {
                     eprintln!(
                         "[{}] ❌ Invalid canonical direct chat id {}. Message queued.",
-                        context, target_peer_id
+                        context,
+                        crate::logging::peer(&target_peer_id)
                     );
                     return None;
                 }
@@ -1217,7 +1462,9 @@ impl NetworkManager {
             Err(e) => {
                 eprintln!(
                     "[{}] ❌ Invalid peer_id: {} ({})",
-                    context, actual_peer_id_str, e
+                    context,
+                    crate::logging::peer(&actual_peer_id_str),
+                    e
                 );
                 None
             }
@@ -1387,6 +1634,22 @@ impl NetworkManager {
     ) {
         self.peer_transport_registry
             .record_connected(peer_id, connection_id, remote_addr);
+        self.path_registry
+            .record_connected(peer_id, connection_id, remote_addr);
+        let reputation = self.peer_reputation_score(&peer_id);
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .set_application_score(&peer_id, reputation);
+
+        // A direct path is up: stop routing this peer's traffic through the relay.
+        for relayed in self.path_registry.redundant_relay_connections(&peer_id) {
+            println!(
+                "[Path] Direct path to {} established, closing relayed connection",
+                crate::logging::peer(&peer_id)
+            );
+            self.swarm.close_connection(relayed);
+        }
     }
 
     pub(super) fn note_peer_transport_disconnected(
@@ -1395,10 +1658,610 @@ impl NetworkManager {
         connection_id: ConnectionId,
         remote_addr: &Multiaddr,
     ) -> bool {
+        self.path_registry
+            .record_disconnected(peer_id, connection_id);
         self.peer_transport_registry
             .record_disconnected(peer_id, connection_id, remote_addr)
     }
 
+    /// Dial known LAN/direct addresses of peers that are only connected via a
+    /// relay. Called on the heartbeat; the registry rate-limits per peer.
+    pub(super) fn attempt_path_upgrades(&mut self) {
+        use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
+
+        let mut due = self
+            .path_registry
+            .take_upgrade_due(std::time::Instant::now());
+        // Best-behaved peers first; graylisted peers stay on the relay.
+        due.retain(|peer| {
+            self.peer_reputation_score(peer) > crate::network::reputation::DIAL_SKIP_THRESHOLD
+        });
+        due.sort_by(|a, b| {
+            self.peer_reputation_score(b)
+                .total_cmp(&self.peer_reputation_score(a))
+        });
+        for peer_id in due {
+            let known = self.local_peers.addresses(&peer_id);
+            let candidates = crate::network::path::upgrade_candidates(
+                self.path_registry.best_path(&peer_id),
+                &known,
+            );
+            if candidates.is_empty() {
+                continue;
+            }
+            println!(
+                "[Path] {} is relay-only, trying {} direct address(es)",
+                crate::logging::peer(&peer_id),
+                candidates.len()
+            );
+            for addr in &candidates {
+                self.record_outgoing_dial(addr, OutgoingDialSource::PathUpgrade);
+            }
+            let opts = DialOpts::peer_id(peer_id)
+                .addresses(candidates)
+                .condition(PeerCondition::NotDialing)
+                .build();
+            if let Err(e) = self.swarm.dial(opts) {
+                eprintln!(
+                    "[Path] Upgrade dial to {} failed: {}",
+                    crate::logging::peer(&peer_id),
+                    e
+                );
+            }
+        }
+    }
+
+    fn reconnect_jitter() -> f64 {
+        rand::random::<f64>() * 2.0 - 1.0
+    }
+
+    pub(super) fn emit_reconnect_state(
+        &self,
+        peer_id: PeerId,
+        state: crate::network::reconnect::ReconnectState,
+    ) {
+        let _ = self.app_handle.emit(
+            "peer-reconnect-state",
+            crate::network::reconnect::ReconnectEvent {
+                peer_id: peer_id.to_string(),
+                state,
+            },
+        );
+    }
+
+    /// Remember an address we reached `peer_id` at, here and across restarts.
+    pub(super) fn remember_peer_address(&mut self, peer_id: PeerId, addr: &Multiaddr) {
+        self.reconnect_scheduler.remember(peer_id, addr.clone());
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return;
+        };
+        if let Err(e) = crate::storage::db::record_peer_address(
+            &conn,
+            &peer_id.to_string(),
+            &addr.to_string(),
+            now_unix_secs(),
+        ) {
+            eprintln!(
+                "[Reconnect] Failed to save address of {}: {}",
+                crate::logging::peer(&peer_id),
+                e
+            );
+        }
+    }
+
+    /// Seed Kademlia and the reconnect scheduler with the addresses saved by
+    /// earlier runs, and start dialing friends right away.
+    pub(super) fn restore_peer_addresses(&mut self) {
+        let rows = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            match crate::storage::db::list_peer_addresses(&conn) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    eprintln!("[Reconnect] Failed to load saved peer addresses: {}", e);
+                    return;
+                }
+            }
+        };
+
+        let mut peers = HashSet::new();
+        for (peer_id, addr) in rows {
+            let (Ok(peer_id), Ok(addr)) = (peer_id.parse::<PeerId>(), addr.parse::<Multiaddr>())
+            else {
+                continue;
+            };
+            self.swarm
+                .behaviour_mut()
+                .kademlia
+                .add_address(&peer_id, addr.clone());
+            // Oldest first, so the most recent address ends up in front.
+            self.reconnect_scheduler.remember(peer_id, addr);
+            peers.insert(peer_id);
+        }
+        println!(
+            "[Reconnect] Restored saved addresses of {} peer(s)",
+            peers.len()
+        );
+        for peer_id in peers {
+            self.schedule_reconnect(peer_id);
+        }
+    }
+
+    /// Start redialing a friend whose last connection just closed.
+    pub(super) fn schedule_reconnect(&mut self, peer_id: PeerId) {
+        if !self.trusted_peer_ids.contains(&peer_id)
+            || self.current_connectivity_settings().mode
+                == crate::storage::config::ConnectivityMode::Invisible
+        {
+            return;
+        }
+        let now = std::time::Instant::now();
+        if let Some(state) =
+            self.reconnect_scheduler
+                .schedule(peer_id, now, Self::reconnect_jitter())
+        {
+            println!(
+                "[Reconnect] Scheduling reconnect to {}",
+                crate::logging::peer(&peer_id)
+            );
+            self.emit_reconnect_state(peer_id, state);
+        }
+    }
+
+    pub(super) fn note_reconnect_dial_failure(&mut self, peer_id: PeerId) {
+        let now = std::time::Instant::now();
+        let Some(state) = self
+            .reconnect_scheduler
+            .failed(&peer_id, now, Self::reconnect_jitter())
+        else {
+            return;
+        };
+        match &state {
+            crate::network::reconnect::ReconnectState::GaveUp { attempts } => println!(
+                "[Reconnect] Giving up on {} after {} attempts",
+                crate::logging::peer(&peer_id),
+                attempts
+            ),
+            crate::network::reconnect::ReconnectState::Waiting { retry_in_ms, .. } => println!(
+                "[Reconnect] Dial to {} failed, retry in {:.1}s",
+                crate::logging::peer(&peer_id),
+                *retry_in_ms as f64 / 1000.0
+            ),
+            _ => {}
+        }
+        self.emit_reconnect_state(peer_id, state);
+    }
+
+    /// Dial friends whose reconnect backoff ran out.
+    pub(super) fn run_due_reconnects(&mut self) {
+        use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
+
+        if self.current_connectivity_settings().mode
+            == crate::storage::config::ConnectivityMode::Invisible
+        {
+            for peer_id in self.reconnect_scheduler.cancel_all() {
+                println!(
+                    "[Reconnect] Offline, no longer reconnecting to {}",
+                    crate::logging::peer(&peer_id)
+                );
+            }
+            return;
+        }
+        let now = std::time::Instant::now();
+        for (peer_id, attempt, addrs) in self.reconnect_scheduler.due(now) {
+            if self.swarm.is_connected(&peer_id) {
+                if let Some(state) = self.reconnect_scheduler.connected(&peer_id) {
+                    self.emit_reconnect_state(peer_id, state);
+                }
+                continue;
+            }
+            println!(
+                "[Reconnect] Attempt {} to {} at {} address(es)",
+                attempt,
+                crate::logging::peer(&peer_id),
+                addrs.len()
+            );
+            for addr in &addrs {
+                self.record_outgoing_dial(addr, OutgoingDialSource::Reconnect);
+            }
+            self.emit_reconnect_state(
+                peer_id,
+                crate::network::reconnect::ReconnectState::Dialing { attempt },
+            );
+            let opts = DialOpts::peer_id(peer_id)
+                .addresses(addrs)
+                .condition(PeerCondition::DisconnectedAndNotDialing)
+                .build();
+            if let Err(e) = self.swarm.dial(opts) {
+                eprintln!(
+                    "[Reconnect] Dial to {} failed: {}",
+                    crate::logging::peer(&peer_id),
+                    e
+                );
+                self.note_reconnect_dial_failure(peer_id);
+            }
+        }
+    }
+
+    pub(super) fn record_ping(&mut self, event: libp2p::ping::Event) {
+        let rtt = match event.result {
+            Ok(rtt) => Some(rtt),
+            // Not a loss: the peer just doesn't answer pings.
+            Err(libp2p::ping::Failure::Unsupported) => return,
+            Err(_) => None,
+        };
+        self.quality_registry.record(event.peer, rtt);
+    }
+
+    /// Emit `peer-quality` with the stats of every connected peer.
+    pub(super) fn emit_peer_quality(&self) {
+        let reports: Vec<_> = self
+            .quality_registry
+            .reports()
+            .into_iter()
+            .filter(|report| {
+                report
+                    .peer_id
+                    .parse::<PeerId>()
+                    .is_ok_and(|peer| self.swarm.is_connected(&peer))
+            })
+            .collect();
+        if !reports.is_empty() {
+            let _ = self.app_handle.emit("peer-quality", reports);
+        }
+    }
+
+    pub(super) fn connection_path_report(
+        &self,
+        peer_id: &PeerId,
+    ) -> crate::network::path::PathReport {
+        let known = self.local_peers.addresses(peer_id);
+        self.path_registry.report(peer_id, &known)
+    }
+
+    /// Logical clock of an outgoing message we stored, to send along with it.
+    pub(super) fn stored_logical(&self, msg_id: &str) -> Option<i64> {
+        let state = self.app_handle.state::<crate::AppState>();
+        let conn = state.db_conn.lock().ok()?;
+        crate::storage::db::message_logical(&conn, msg_id)
+            .ok()
+            .flatten()
+    }
+
+    /// Typed content of the stored message `msg_id`, if it agrees with the
+    /// flat fields about to be sent for it.
+    pub(super) fn stored_content(
+        &self,
+        msg_id: &str,
+        content_type: &str,
+        text_content: Option<&str>,
+        file_hash: Option<&str>,
+    ) -> Option<crate::chat::message::MessageContent> {
+        let state = self.app_handle.state::<crate::AppState>();
+        let conn = state.db_conn.lock().ok()?;
+        let content = crate::storage::db::get_message(&conn, msg_id)
+            .ok()
+            .flatten()?
+            .content;
+        content
+            .matches_parts(content_type, text_content, file_hash)
+            .then_some(content)
+    }
+
+    /// Send a DM request, splitting it into `Fragment` requests when its
+    /// serialized form exceeds the fragment threshold. Chunk responses are
+    /// bounded by the CDC chunk size and always go out whole.
+    pub(super) fn send_direct_request(
+        &mut self,
+        peer: &PeerId,
+        mut request: crate::network::direct_message::DirectMessageRequest,
+    ) {
+        use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+        use crate::network::fragment;
+
+        if request.content.is_none()
+            && (request.msg_type == DirectMessageKind::Text
+                || request.msg_type.needs_file_transfer())
+        {
+            request.content = self.stored_content(
+                &request.id,
+                request.msg_type.as_str(),
+                request.text_content.as_deref(),
+                request.file_hash.as_deref(),
+            );
+        }
+        crate::storage::bandwidth::record_sent(
+            &peer.to_string(),
+            request.msg_type.bandwidth_protocol(),
+            request.approx_wire_len(),
+        );
+        let payload = match serde_json::to_vec(&request) {
+            Ok(payload)
+                if request.msg_type != DirectMessageKind::ChunkResponse
+                    && fragment::needs_fragmenting(&payload) =>
+            {
+                payload
+            }
+            _ => {
+                if crate::network::outbox::tracks(&request) {
+                    let request_id = self
+                        .swarm
+                        .behaviour_mut()
+                        .direct_message
+                        .send_request(peer, request.clone());
+                    self.outbox.sent(request_id, *peer, &request);
+                    self.set_outgoing_status(&request.id, "sent");
+                } else {
+                    self.swarm
+                        .behaviour_mut()
+                        .direct_message
+                        .send_request(peer, request);
+                }
+                return;
+            }
+        };
+
+        let Some(fragments) = fragment::split(&request.id, &payload) else {
+            eprintln!(
+                "[Fragment] ❌ {} is too large to send ({} bytes)",
+                request.id,
+                payload.len()
+            );
+            return;
+        };
+        println!(
+            "[Fragment] 📤 Sending {} to {} as {} fragments",
+            request.id,
+            crate::logging::peer(&peer),
+            fragments.len()
+        );
+        for piece in fragments {
+            let Ok(text) = serde_json::to_string(&piece) else {
+                continue;
+            };
+            let fragment_request = DirectMessageRequest {
+                id: format!("{}#frag{}", request.id, piece.seq),
+                sender_id: request.sender_id.clone(),
+                msg_type: DirectMessageKind::Fragment,
+                text_content: Some(text),
+                file_hash: None,
+                timestamp: request.timestamp,
+                chunk_hash: None,
+                chunk_data: None,
+                chunk_nonce: None,
+                chunk_list: None,
+                sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
+            };
+            self.swarm
+                .behaviour_mut()
+                .direct_message
+                .send_request(peer, fragment_request);
+        }
+    }
+
+    /// Feed a `Fragment` DM into the reassembler; returns the original request
+    /// once all of its fragments have arrived.
+    pub(super) fn reassemble_direct_fragment(
+        &mut self,
+        peer: PeerId,
+        request: &crate::network::direct_message::DirectMessageRequest,
+    ) -> Result<Option<crate::network::direct_message::DirectMessageRequest>, String> {
+        use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+
+        let piece: crate::network::fragment::Fragment =
+            serde_json::from_str(request.text_content.as_deref().unwrap_or_default())
+                .map_err(|e| format!("invalid fragment: {}", e))?;
+        let origin = format!("dm:{}", peer);
+        let Some(payload) =
+            self.fragment_reassembler
+                .accept(&origin, piece, std::time::Instant::now())?
+        else {
+            return Ok(None);
+        };
+        let inner: DirectMessageRequest = serde_json::from_slice(&payload)
+            .map_err(|e| format!("invalid reassembled request: {}", e))?;
+        if inner.msg_type == DirectMessageKind::Fragment {
+            return Err("nested fragments are not allowed".to_string());
+        }
+        println!(
+            "[Fragment] 📥 Reassembled {} ({} bytes) from {}",
+            inner.id,
+            payload.len(),
+            crate::logging::peer(&peer)
+        );
+        Ok(Some(inner))
+    }
+
+    /// Reassemble a gossip payload that may be a fragment; `None` while the
+    /// message is still incomplete or when the fragment is rejected.
+    pub(super) fn reassemble_gossip_payload(
+        &mut self,
+        source: Option<PeerId>,
+        data: Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        let Ok(piece) = serde_json::from_slice::<crate::network::fragment::Fragment>(&data) else {
+            return Some(data);
+        };
+        let origin = match source {
+            Some(peer) => format!("gossip:{}", peer),
+            None => "gossip:anonymous".to_string(),
+        };
+        match self
+            .fragment_reassembler
+            .accept(&origin, piece, std::time::Instant::now())
+        {
+            Ok(complete) => complete,
+            Err(e) => {
+                eprintln!(
+                    "[Fragment] ❌ Dropping gossip fragment from {}: {}",
+                    origin, e
+                );
+                if let Some(peer) = source {
+                    self.record_peer_behavior(
+                        peer,
+                        crate::network::reputation::PeerBehavior::InvalidMessage,
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    pub(super) fn expire_fragments(&mut self) {
+        let dropped = self.fragment_reassembler.expire(std::time::Instant::now());
+        if dropped > 0 {
+            println!(
+                "[Fragment] ⏱️ Dropped {} incomplete message(s) after timeout",
+                dropped
+            );
+        }
+    }
+
+    /// Current (decayed) reputation; peers never penalised are neutral.
+    pub(super) fn peer_reputation_score(&self, peer_id: &PeerId) -> f64 {
+        self.peer_reputation
+            .get(peer_id)
+            .map(|rep| crate::network::reputation::effective_score(rep, now_unix_secs()))
+            .unwrap_or(0.0)
+    }
+
+    /// Penalise `peer_id`, push the new score to gossipsub and persist it.
+    pub(super) fn record_peer_behavior(
+        &mut self,
+        peer_id: PeerId,
+        behavior: crate::network::reputation::PeerBehavior,
+    ) {
+        let rep = self.peer_reputation.entry(peer_id).or_insert_with(|| {
+            crate::storage::db::PeerReputation {
+                peer_id: peer_id.to_string(),
+                ..Default::default()
+            }
+        });
+        crate::network::reputation::apply(rep, behavior, now_unix_secs());
+        let rep = rep.clone();
+        println!(
+            "[Reputation] {} {:?} -> score {:.1}",
+            crate::logging::peer(&peer_id),
+            behavior,
+            rep.score
+        );
+
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .set_application_score(&peer_id, rep.score);
+
+        let state = self.app_handle.state::<crate::AppState>();
+        if let Ok(conn) = state.db_conn.lock() {
+            if let Err(e) = crate::storage::db::save_peer_reputation(&conn, &rep) {
+                eprintln!(
+                    "[Reputation] ❌ Failed to persist score for {}: {}",
+                    crate::logging::peer(&peer_id),
+                    e
+                );
+            }
+        };
+    }
+
+    /// Token-bucket check for gossip or direct messages a peer sends. A trip
+    /// is penalised and reported once per burst rather than for every dropped
+    /// message; repeated trips ban the peer for a while.
+    pub(super) fn admit_inbound(
+        &mut self,
+        peer_id: PeerId,
+        traffic: crate::network::flood::Traffic,
+    ) -> bool {
+        use crate::network::flood::Verdict;
+
+        let banned = match self.flood_guard.check(peer_id, traffic) {
+            Verdict::Allow => return true,
+            Verdict::Limited { first: false } | Verdict::Banned { new: false } => return false,
+            Verdict::Limited { first: true } => false,
+            Verdict::Banned { new: true } => true,
+        };
+        self.record_peer_behavior(
+            peer_id,
+            crate::network::reputation::PeerBehavior::RateLimited,
+        );
+        let banned_until = if banned {
+            let ban_secs = self
+                .flood_guard
+                .ban_remaining(&peer_id)
+                .map(|left| left.as_secs() as i64)
+                .unwrap_or(0);
+            eprintln!(
+                "[Reputation] 🚫 {} keeps flooding {:?}, banned for {}s",
+                crate::logging::peer(&peer_id),
+                traffic,
+                ban_secs
+            );
+            self.reconnect_scheduler.suppress(peer_id);
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+            Some(now_unix_secs() + ban_secs)
+        } else {
+            eprintln!(
+                "[Reputation] ⚠️ {} exceeded the {:?} rate limit",
+                crate::logging::peer(&peer_id),
+                traffic
+            );
+            None
+        };
+        let _ = self.app_handle.emit(
+            "peer-rate-limited",
+            serde_json::json!({
+                "peer_id": peer_id.to_string(),
+                "traffic": traffic,
+                "banned_until": banned_until,
+            }),
+        );
+        false
+    }
+
+    /// Close connections from peers serving a flood ban.
+    pub(super) fn refuse_if_banned(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+    ) -> bool {
+        if self.flood_guard.ban_remaining(&peer_id).is_none() {
+            return false;
+        }
+        println!(
+            "[Reputation] 🚫 Closing connection from banned peer {}",
+            crate::logging::peer(&peer_id)
+        );
+        self.swarm.close_connection(connection_id);
+        true
+    }
+
+    /// Heartbeat upkeep: refresh decayed scores in gossipsub and forget rate
+    /// limiters of peers that went away.
+    pub(super) fn refresh_peer_reputation(&mut self) {
+        let connected: Vec<PeerId> = self
+            .peer_reputation
+            .keys()
+            .filter(|peer| self.swarm.is_connected(peer))
+            .copied()
+            .collect();
+        for peer_id in connected {
+            let score = self.peer_reputation_score(&peer_id);
+            self.swarm
+                .behaviour_mut()
+                .gossipsub
+                .set_application_score(&peer_id, score);
+        }
+        let swarm = &self.swarm;
+        self.flood_guard
+            .retain_connected(|peer| swarm.is_connected(peer));
+    }
+
     pub(super) fn peer_has_quic_path(&self, peer_id: &PeerId) -> bool {
         self.peer_transport_registry.has_quic(peer_id)
     }
@@ -1422,10 +2285,16 @@ impl NetworkManager {
             if let Err(e) = self.swarm.dial(addr.clone()) {
                 eprintln!(
                     "[Voice][QUIC] Dial failed for {} at {}: {}",
-                    peer_id, addr, e
+                    crate::logging::peer(&peer_id),
+                    addr,
+                    e
                 );
             } else {
-                eprintln!("[Voice][QUIC] Dialing {} at {}", peer_id, addr);
+                eprintln!(
+                    "[Voice][QUIC] Dialing {} at {}",
+                    crate::logging::peer(&peer_id),
+                    addr
+                );
             }
         }
         addrs.len()
@@ -1436,7 +2305,9 @@ impl NetworkManager {
         if quic_count > 0 {
             eprintln!(
                 "[Voice][QUIC] peer={} quic_connections={}, tcp_connections={}",
-                peer_id, quic_count, tcp_count
+                crate::logging::peer(&peer_id),
+                quic_count,
+                tcp_count
             );
             return true;
         }
@@ -1444,7 +2315,7 @@ impl NetworkManager {
         let dial_count = self.dial_known_voice_quic_addresses(peer_id);
         eprintln!(
             "[Voice][QUIC] peer={} missing QUIC path, tcp_connections={}, quic_candidates_dialed={}",
-            peer_id, tcp_count, dial_count
+            crate::logging::peer(&peer_id), tcp_count, dial_count
         );
         false
     }
@@ -1472,7 +2343,7 @@ impl NetworkManager {
         eprintln!(
             "[Voice][Network][{}] peer={}, quic_connections={}, tcp_connections={}, outbound_frames={}, inbound_frames={}, inbound_seq_gaps={}, inbound_out_of_order_frames={}, outbound_failures={}, inbound_failures={}, rejected_responses={}, opus_encode_errors={}, opus_decode_errors={}, opus_out_bytes={}, opus_in_bytes={}, avg_opus_out_bytes={:.1}, avg_opus_in_bytes={:.1}",
             label,
-            peer_id,
+            crate::logging::peer(&peer_id),
             quic_count,
             tcp_count,
             self.voice_network_stats.outbound_frames,
@@ -1495,11 +2366,13 @@ impl NetworkManager {
         &self,
     ) -> crate::storage::config::ConnectivitySettings {
         let state = self.app_handle.state::<crate::NetworkState>();
-        let settings = match state.connectivity.try_lock() {
-            Ok(settings) => settings.clone(),
-            Err(_) => crate::storage::config::ConnectivitySettings::default(),
+        let Ok(mut last) = self.last_connectivity.lock() else {
+            return crate::storage::config::ConnectivitySettings::invisible();
         };
-        settings
+        if let Ok(settings) = state.connectivity.try_lock() {
+            *last = settings.clone();
+        }
+        last.clone()
     }
 
     pub(super) fn is_mdns_enabled(&self) -> bool {
@@ -1517,6 +2390,169 @@ impl NetworkManager {
     pub(super) fn is_punch_assist_enabled(&self) -> bool {
         self.current_connectivity_settings().punch_assist_enabled
     }
+
+    /// Forget local addresses that mDNS stopped announcing; connected peers
+    /// keep theirs until they disconnect.
+    pub(super) fn expire_local_peers(&mut self) {
+        let swarm = &self.swarm;
+        let expired = self.local_peers.expire(
+            std::time::Instant::now(),
+            crate::network::local_peers::ADDRESS_TTL,
+            |peer| swarm.is_connected(peer),
+        );
+        for peer_id in expired {
+            println!(
+                "[mDNS] Peer {} no longer announced, forgetting it",
+                crate::logging::peer(&peer_id)
+            );
+            let _ = self
+                .app_handle
+                .emit("local-peer-expired", peer_id.to_string());
+        }
+    }
+
+    /// Current mDNS-discovered peers with their known addresses and stored aliases.
+    pub(super) fn get_local_peers(&self) -> Vec<crate::network::command::LocalPeerInfo> {
+        let aliases: HashMap<String, String> = {
+            let state = self.app_handle.state::<crate::AppState>();
+            match state.db_conn.lock() {
+                Ok(conn) => self
+                    .local_peers
+                    .peers()
+                    .filter_map(|peer_id| {
+                        let peer_id = peer_id.to_string();
+                        crate::storage::db::get_peer_alias(&conn, &peer_id)
+                            .ok()
+                            .flatten()
+                            .map(|alias| (peer_id, alias))
+                    })
+                    .collect(),
+                Err(_) => HashMap::new(),
+            }
+        };
+
+        let mut peers: Vec<_> = self
+            .local_peers
+            .peers()
+            .map(|peer_id| {
+                let peer_id_str = peer_id.to_string();
+                crate::network::command::LocalPeerInfo {
+                    alias: aliases.get(&peer_id_str).cloned(),
+                    addresses: self
+                        .local_peers
+                        .addresses(peer_id)
+                        .iter()
+                        .map(|a| a.to_string())
+                        .collect(),
+                    connected: self.swarm.is_connected(peer_id),
+                    peer_id: peer_id_str,
+                }
+            })
+            .collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        peers
+    }
+
+    /// A DM chat is a message request until we reply; GitHub-linked friends never are.
+    pub(super) fn is_message_request(&self, chat_id: &str, sender_id: &str) -> bool {
+        if self.github_by_peer_id.contains_key(sender_id) {
+            return false;
+        }
+        let state = self.app_handle.state::<crate::AppState>();
+        let Ok(conn) = state.db_conn.lock() else {
+            return false;
+        };
+        !crate::storage::db::has_outgoing_messages(&conn, chat_id)
+    }
+
+    pub(super) fn record_message_request_score(
+        &self,
+        msg: &crate::storage::db::Message,
+        repeated_targets: usize,
+    ) {
+        let spam = crate::chat::spam::score_text(
+            msg.content.text_content().unwrap_or_default(),
+            repeated_targets,
+        );
+        let record = crate::storage::db::MessageRequestScore {
+            chat_id: msg.chat_id.clone(),
+            peer_id: msg.peer_id.clone(),
+            msg_id: msg.id.clone(),
+            score: spam.score,
+            level: spam.level.as_str().to_string(),
+            reasons: spam.reasons,
+            scored_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+        };
+
+        {
+            let state = self.app_handle.state::<crate::AppState>();
+            if let Ok(conn) = state.db_conn.lock() {
+                if let Err(e) = crate::storage::db::upsert_message_request_score(&conn, &record) {
+                    eprintln!("[Spam] Failed to store score for {}: {}", record.chat_id, e);
+                }
+            }
+        }
+        if record.score > 0 {
+            println!(
+                "[Spam] Message request {} scored {} ({})",
+                record.chat_id, record.score, record.level
+            );
+        }
+        let _ = self.app_handle.emit("message-request-scored", record);
+    }
+
+    pub(super) async fn refresh_network_stats(&mut self) {
+        let gossipsub = &self.swarm.behaviour().gossipsub;
+        let mesh_sizes = gossipsub
+            .topics()
+            .map(|topic| {
+                (
+                    crate::network::diagnostics::topic_label(topic.as_str()),
+                    gossipsub.mesh_peers(topic).count(),
+                )
+            })
+            .collect();
+        let connected_peers = self.swarm.connected_peers().count();
+        let discovered_local_peers = self.local_peers.len();
+
+        let state = self.app_handle.state::<crate::NetworkState>();
+        let mut stats = state.network_stats.lock().await;
+        stats.connected_peers = connected_peers;
+        stats.discovered_local_peers = discovered_local_peers;
+        stats.mesh_sizes = mesh_sizes;
+        stats.updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+    }
+
+    pub(super) async fn note_dial_failure(
+        &mut self,
+        peer_id: Option<PeerId>,
+        source: OutgoingDialSource,
+        address: Option<String>,
+        error: &libp2p::swarm::DialError,
+    ) {
+        let failure = crate::network::diagnostics::DialFailure {
+            at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+            peer_id: peer_id.map(|p| p.to_string()),
+            source: source.as_str().to_string(),
+            address,
+            error: error.to_string(),
+        };
+        let state = self.app_handle.state::<crate::NetworkState>();
+        state
+            .network_stats
+            .lock()
+            .await
+            .record_dial_failure(failure);
+    }
 }
 
 impl Drop for NetworkManager {
@@ -0,0 +1,98 @@
+use super::*;
+use crate::network::outbox::{Failure, SendFailedEvent, RETRY_COMMAND};
+
+impl NetworkManager {
+    /// Set a stored message's status and tell the UI, if the status may
+    /// change that way.
+    pub(super) fn set_outgoing_status(&self, msg_id: &str, status: &str) {
+        let changed = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                return;
+            };
+            match crate::storage::db::update_message_status(&conn, msg_id, status) {
+                Ok(changed) => changed,
+                Err(e) => {
+                    eprintln!("[Outbox] ❌ Failed to mark {} {}: {}", msg_id, status, e);
+                    false
+                }
+            }
+        };
+        if !changed {
+            return;
+        }
+        let _ = self.app_handle.emit(
+            "message-status-updated",
+            serde_json::json!({
+                "msg_id": msg_id,
+                "status": status,
+            }),
+        );
+    }
+
+    fn emit_send_failed(&self, msg_id: String, peer: PeerId, attempts: u32, error: String) {
+        eprintln!(
+            "[Outbox] ❌ Giving up on {} to {} after {} attempt(s)",
+            msg_id,
+            crate::logging::peer(&peer),
+            attempts
+        );
+        let _ = self.app_handle.emit(
+            "message-send-failed",
+            SendFailedEvent {
+                msg_id,
+                peer_id: peer.to_string(),
+                attempts,
+                error,
+                retry_command: RETRY_COMMAND,
+            },
+        );
+    }
+
+    /// A DM request failed; if it was a chat message, mark it failed and
+    /// schedule a retry while the peer is still connected.
+    pub(super) fn handle_outbox_failure(
+        &mut self,
+        request_id: &libp2p::request_response::OutboundRequestId,
+        error: String,
+    ) {
+        let now = std::time::Instant::now();
+        let Some((msg_id, peer, failure)) = self.outbox.failed(request_id, now) else {
+            return;
+        };
+        self.set_outgoing_status(&msg_id, "failed");
+        match failure {
+            Failure::Retry { attempts, retry_in } if self.swarm.is_connected(&peer) => {
+                println!(
+                    "[Outbox] 🔁 Retrying {} in {:?} (attempt {} failed)",
+                    msg_id, retry_in, attempts
+                );
+            }
+            Failure::Retry { attempts, .. } => {
+                self.outbox.give_up(&msg_id);
+                self.emit_send_failed(msg_id, peer, attempts, error);
+            }
+            Failure::GaveUp { attempts } => {
+                self.emit_send_failed(msg_id, peer, attempts, error);
+            }
+        }
+    }
+
+    /// Send messages whose backoff ran out again.
+    pub(super) fn retry_due_messages(&mut self) {
+        for (peer, request) in self.outbox.due(std::time::Instant::now()) {
+            if !self.swarm.is_connected(&peer) {
+                if let Some(attempts) = self.outbox.give_up(&request.id) {
+                    self.emit_send_failed(
+                        request.id,
+                        peer,
+                        attempts,
+                        "Peer disconnected".to_string(),
+                    );
+                }
+                continue;
+            }
+            self.send_direct_request(&peer, request);
+        }
+    }
+}
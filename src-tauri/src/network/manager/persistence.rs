@@ -7,6 +7,9 @@ use std::sync::Arc;
 const PERSISTENCE_WORKER_POOL_SIZE: usize = 2;
 const PERSISTENCE_QUEUE_CAPACITY: usize = 512;
 const QUEUE_PRESSURE_THRESHOLD: usize = 32;
+/// Incoming messages already queued behind the one a worker picked up are
+/// written together, up to this many, in one transaction.
+const PERSISTENCE_BATCH_MAX: usize = 32;
 
 pub(super) enum PersistenceTask {
     PersistIncomingDirectMessage {
@@ -22,7 +25,7 @@ pub(super) enum PersistenceTask {
     },
     UpdateDeliveredStatus {
         msg_id: String,
-        reply: tokio::sync::oneshot::Sender<Result<(), String>>,
+        reply: tokio::sync::oneshot::Sender<Result<bool, String>>,
     },
     UpdateReadStatuses {
         msg_ids: Vec<String>,
@@ -31,6 +34,39 @@ pub(super) enum PersistenceTask {
     Shutdown,
 }
 
+/// What an incoming message needs besides its row: sender, chat and file offer.
+enum IncomingContext {
+    Direct {
+        request: DirectMessageRequest,
+        chat_id: String,
+    },
+    Group {
+        envelope: GroupMessageEnvelope,
+    },
+}
+
+type IncomingReply = tokio::sync::oneshot::Sender<Result<(), String>>;
+
+/// Split an incoming-message task into its parts; other tasks come back as is.
+fn into_incoming(
+    task: PersistenceTask,
+) -> Result<(IncomingContext, crate::storage::db::Message, IncomingReply), PersistenceTask> {
+    match task {
+        PersistenceTask::PersistIncomingDirectMessage {
+            request,
+            chat_id,
+            db_msg,
+            reply,
+        } => Ok((IncomingContext::Direct { request, chat_id }, db_msg, reply)),
+        PersistenceTask::PersistIncomingGroupMessage {
+            envelope,
+            db_msg,
+            reply,
+        } => Ok((IncomingContext::Group { envelope }, db_msg, reply)),
+        other => Err(other),
+    }
+}
+
 pub(super) fn start_persistence_workers(
     app_handle: AppHandle,
 ) -> (
@@ -58,10 +94,15 @@ pub(super) fn start_persistence_workers(
         let inflight_tasks = inflight_tasks.clone();
 
         let handle = tauri::async_runtime::spawn(async move {
+            // Tasks taken off the queue while collecting a batch.
+            let mut deferred = std::collections::VecDeque::new();
             loop {
-                let task = {
-                    let mut rx = task_rx.lock().await;
-                    rx.recv().await
+                let task = match deferred.pop_front() {
+                    Some(task) => Some(task),
+                    None => {
+                        let mut rx = task_rx.lock().await;
+                        rx.recv().await
+                    }
                 };
 
                 let Some(task) = task else {
@@ -75,41 +116,55 @@ pub(super) fn start_persistence_workers(
                 pending_tasks.fetch_sub(1, Ordering::SeqCst);
                 inflight_tasks.fetch_add(1, Ordering::SeqCst);
 
-                match task {
-                    PersistenceTask::PersistIncomingDirectMessage {
-                        request,
-                        chat_id,
-                        db_msg,
-                        reply,
-                    } => {
-                        let app_handle_for_work = app_handle.clone();
-                        let result = tauri::async_runtime::spawn_blocking(move || {
-                            persist_incoming_direct_message(
-                                &app_handle_for_work,
-                                &request,
-                                &chat_id,
-                                &db_msg,
-                            )
-                        })
-                        .await
-                        .map_err(|e| e.to_string())
-                        .and_then(|r| r);
-                        let _ = reply.send(result);
-                    }
-                    PersistenceTask::PersistIncomingGroupMessage {
-                        envelope,
-                        db_msg,
-                        reply,
-                    } => {
+                let task = match into_incoming(task) {
+                    Ok(first) => {
+                        let mut batch = vec![first];
+                        {
+                            let mut rx = task_rx.lock().await;
+                            while batch.len() < PERSISTENCE_BATCH_MAX {
+                                let Ok(next) = rx.try_recv() else {
+                                    break;
+                                };
+                                if matches!(next, PersistenceTask::Shutdown) {
+                                    deferred.push_back(next);
+                                    break;
+                                }
+                                pending_tasks.fetch_sub(1, Ordering::SeqCst);
+                                inflight_tasks.fetch_add(1, Ordering::SeqCst);
+                                match into_incoming(next) {
+                                    Ok(incoming) => batch.push(incoming),
+                                    Err(other) => {
+                                        // Counted as in flight once it runs.
+                                        pending_tasks.fetch_add(1, Ordering::SeqCst);
+                                        inflight_tasks.fetch_sub(1, Ordering::SeqCst);
+                                        deferred.push_back(other);
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+
+                        let count = batch.len();
+                        let (items, replies): (Vec<_>, Vec<_>) = batch
+                            .into_iter()
+                            .map(|(context, db_msg, reply)| ((context, db_msg), reply))
+                            .unzip();
                         let app_handle_for_work = app_handle.clone();
-                        let result = tauri::async_runtime::spawn_blocking(move || {
-                            persist_incoming_group_message(&app_handle_for_work, &envelope, &db_msg)
+                        let results = tauri::async_runtime::spawn_blocking(move || {
+                            persist_incoming_batch(&app_handle_for_work, &items)
                         })
                         .await
-                        .map_err(|e| e.to_string())
-                        .and_then(|r| r);
-                        let _ = reply.send(result);
+                        .unwrap_or_else(|e| vec![Err(e.to_string()); count]);
+                        for (reply, result) in replies.into_iter().zip(results) {
+                            let _ = reply.send(result);
+                        }
+                        inflight_tasks.fetch_sub(count, Ordering::SeqCst);
+                        continue;
                     }
+                    Err(task) => task,
+                };
+
+                match task {
                     PersistenceTask::UpdateDeliveredStatus { msg_id, reply } => {
                         let app_handle_for_work = app_handle.clone();
                         let result = tauri::async_runtime::spawn_blocking(move || {
@@ -145,7 +200,9 @@ pub(super) fn start_persistence_workers(
                         .and_then(|r| r);
                         let _ = reply.send(result);
                     }
-                    PersistenceTask::Shutdown => unreachable!(),
+                    PersistenceTask::PersistIncomingDirectMessage { .. }
+                    | PersistenceTask::PersistIncomingGroupMessage { .. }
+                    | PersistenceTask::Shutdown => unreachable!(),
                 }
 
                 inflight_tasks.fetch_sub(1, Ordering::SeqCst);
@@ -180,121 +237,177 @@ fn with_db_conn<T>(
     op(&conn)
 }
 
-fn persist_incoming_direct_message(
-    app_handle: &AppHandle,
+/// Sender, chat and file-offer rows an incoming direct message depends on.
+fn prepare_incoming_direct_message(
+    conn: &rusqlite::Connection,
     request: &DirectMessageRequest,
     chat_id: &str,
-    db_msg: &crate::storage::db::Message,
 ) -> Result<(), String> {
-    with_db_conn(app_handle, |conn| {
-        let sender_name = request
-            .sender_alias
+    let sender_name = request
+        .sender_alias
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(ToOwned::to_owned)
+        .or_else(|| {
+            crate::chat_identity::extract_name_from_chat_id(chat_id)
+                .filter(|name| !name.trim().is_empty())
+        })
+        .unwrap_or_else(|| "peer".to_string());
+
+    let peer_exists = crate::storage::db::is_peer(conn, &request.sender_id);
+    if !peer_exists {
+        crate::storage::db::add_peer(conn, &request.sender_id, Some(&sender_name), None, "direct")
+            .map_err(|e| e.to_string())?;
+    }
+
+    let chat_exists = crate::storage::db::chat_exists(conn, chat_id);
+    if !chat_exists {
+        crate::storage::db::create_chat(conn, chat_id, &sender_name, false)
+            .map_err(|e| e.to_string())?;
+    } else if let Ok(existing_name) = crate::storage::db::get_chat_name(conn, chat_id) {
+        if existing_name
             .as_deref()
             .map(str::trim)
-            .filter(|name| !name.is_empty())
-            .map(ToOwned::to_owned)
-            .or_else(|| {
-                crate::chat_identity::extract_name_from_chat_id(chat_id)
-                    .filter(|name| !name.trim().is_empty())
-            })
-            .unwrap_or_else(|| "peer".to_string());
-
-        let peer_exists = crate::storage::db::is_peer(conn, &request.sender_id);
-        if !peer_exists {
-            crate::storage::db::add_peer(
-                conn,
-                &request.sender_id,
-                Some(&sender_name),
-                None,
-                "direct",
-            )
-            .map_err(|e| e.to_string())?;
+            .filter(|name| !name.is_empty() && *name != chat_id)
+            .is_none()
+        {
+            let _ = crate::storage::db::upsert_chat(conn, chat_id, &sender_name, false);
         }
+    }
 
-        let chat_exists = crate::storage::db::chat_exists(conn, chat_id);
-        if !chat_exists {
-            crate::storage::db::create_chat(conn, chat_id, &sender_name, false)
+    if request.msg_type.needs_file_transfer() {
+        if let Some(ref file_hash) = request.file_hash {
+            let file_exists: bool = conn
+                .query_row(
+                    "SELECT 1 FROM files WHERE file_hash = ?1",
+                    [file_hash],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false);
+
+            if !file_exists {
+                conn.execute(
+                    "INSERT INTO files (file_hash, file_name, mime_type, size_bytes, is_complete) VALUES (?1, NULL, 'application/octet-stream', 0, 0)",
+                    [file_hash],
+                )
                 .map_err(|e| e.to_string())?;
-        } else if let Ok(existing_name) = crate::storage::db::get_chat_name(conn, chat_id) {
-            if existing_name
-                .as_deref()
-                .map(str::trim)
-                .filter(|name| !name.is_empty() && *name != chat_id)
-                .is_none()
-            {
-                let _ = crate::storage::db::upsert_chat(conn, chat_id, &sender_name, false);
             }
         }
+    }
 
-        if request.msg_type.needs_file_transfer() {
-            if let Some(ref file_hash) = request.file_hash {
-                let file_exists: bool = conn
-                    .query_row(
-                        "SELECT 1 FROM files WHERE file_hash = ?1",
-                        [file_hash],
-                        |_| Ok(true),
-                    )
-                    .unwrap_or(false);
-
-                if !file_exists {
-                    conn.execute(
-                        "INSERT INTO files (file_hash, file_name, mime_type, size_bytes, is_complete) VALUES (?1, NULL, 'application/octet-stream', 0, 0)",
-                        [file_hash],
-                    )
-                    .map_err(|e| e.to_string())?;
-                }
+    Ok(())
+}
+
+/// Sender, group chat and file-offer rows an incoming group message depends on.
+fn prepare_incoming_group_message(
+    conn: &rusqlite::Connection,
+    envelope: &GroupMessageEnvelope,
+) -> Result<(), String> {
+    if !crate::storage::db::is_peer(conn, &envelope.sender_id) {
+        crate::storage::db::add_peer(conn, &envelope.sender_id, None, None, "group")
+            .map_err(|e| e.to_string())?;
+    }
+
+    let group_name = crate::chat_kind::default_group_name(&envelope.group_id);
+    crate::storage::db::upsert_chat(conn, &envelope.group_id, &group_name, true)
+        .map_err(|e| e.to_string())?;
+    crate::storage::db::add_chat_member(conn, &envelope.group_id, "Me", "member")
+        .map_err(|e| e.to_string())?;
+    crate::storage::db::add_chat_member(conn, &envelope.group_id, &envelope.sender_id, "member")
+        .map_err(|e| e.to_string())?;
+
+    if envelope.content_type.needs_file_transfer() {
+        if let Some(ref file_hash) = envelope.file_hash {
+            let file_exists: bool = conn
+                .query_row(
+                    "SELECT 1 FROM files WHERE file_hash = ?1",
+                    [file_hash],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false);
+            if !file_exists {
+                conn.execute(
+                    "INSERT INTO files (file_hash, file_name, mime_type, size_bytes, is_complete) VALUES (?1, NULL, 'application/octet-stream', 0, 0)",
+                    [file_hash],
+                )
+                .map_err(|e| e.to_string())?;
             }
         }
+    }
 
-        crate::storage::db::insert_message(conn, db_msg).map_err(|e| e.to_string())
-    })
+    Ok(())
 }
 
-fn persist_incoming_group_message(
+fn prepare_incoming(conn: &rusqlite::Connection, context: &IncomingContext) -> Result<(), String> {
+    match context {
+        IncomingContext::Direct { request, chat_id } => {
+            prepare_incoming_direct_message(conn, request, chat_id)
+        }
+        IncomingContext::Group { envelope } => prepare_incoming_group_message(conn, envelope),
+    }
+}
+
+fn persist_incoming(
     app_handle: &AppHandle,
-    envelope: &GroupMessageEnvelope,
+    context: &IncomingContext,
     db_msg: &crate::storage::db::Message,
 ) -> Result<(), String> {
+    crate::storage::disk::ensure_db_writable()?;
     with_db_conn(app_handle, |conn| {
-        if !crate::storage::db::is_peer(conn, &envelope.sender_id) {
-            crate::storage::db::add_peer(conn, &envelope.sender_id, None, None, "group")
-                .map_err(|e| e.to_string())?;
-        }
-
-        let group_name = crate::chat_kind::default_group_name(&envelope.group_id);
-        crate::storage::db::upsert_chat(conn, &envelope.group_id, &group_name, true)
-            .map_err(|e| e.to_string())?;
-        crate::storage::db::add_chat_member(conn, &envelope.group_id, "Me", "member")
-            .map_err(|e| e.to_string())?;
-        crate::storage::db::add_chat_member(
+        prepare_incoming(conn, context)?;
+        crate::storage::db::insert_message(conn, db_msg).map_err(|e| e.to_string())?;
+        crate::storage::journal::record_message(
+            &app_handle.state::<crate::AppState>().journal,
             conn,
-            &envelope.group_id,
-            &envelope.sender_id,
-            "member",
-        )
-        .map_err(|e| e.to_string())?;
+            db_msg,
+        );
+        Ok(())
+    })
+}
 
-        if envelope.content_type.needs_file_transfer() {
-            if let Some(ref file_hash) = envelope.file_hash {
-                let file_exists: bool = conn
-                    .query_row(
-                        "SELECT 1 FROM files WHERE file_hash = ?1",
-                        [file_hash],
-                        |_| Ok(true),
-                    )
-                    .unwrap_or(false);
-                if !file_exists {
-                    conn.execute(
-                        "INSERT INTO files (file_hash, file_name, mime_type, size_bytes, is_complete) VALUES (?1, NULL, 'application/octet-stream', 0, 0)",
-                        [file_hash],
-                    )
+/// Write queued incoming messages with one batch insert. If the batch fails
+/// (e.g. one duplicate id), fall back to one message at a time so a single bad
+/// message doesn't fail the others.
+fn persist_incoming_batch(
+    app_handle: &AppHandle,
+    items: &[(IncomingContext, crate::storage::db::Message)],
+) -> Vec<Result<(), String>> {
+    if let [(context, db_msg)] = items {
+        return vec![persist_incoming(app_handle, context, db_msg)];
+    }
+    let batched = crate::storage::disk::ensure_db_writable()
+        .map_err(String::from)
+        .and_then(|()| {
+            with_db_conn(app_handle, |conn| {
+                for (context, _) in items {
+                    prepare_incoming(conn, context)?;
+                }
+                let db_msgs: Vec<crate::storage::db::Message> =
+                    items.iter().map(|(_, db_msg)| db_msg.clone()).collect();
+                crate::storage::db::insert_messages_batch(conn, &db_msgs)
                     .map_err(|e| e.to_string())?;
+                let journal = &app_handle.state::<crate::AppState>().journal;
+                for db_msg in &db_msgs {
+                    crate::storage::journal::record_message(journal, conn, db_msg);
                 }
-            }
+                Ok(())
+            })
+        });
+    match batched {
+        Ok(()) => vec![Ok(()); items.len()],
+        Err(e) => {
+            eprintln!(
+                "[Persistence] ⚠️ Batch of {} messages failed ({}), retrying one by one",
+                items.len(),
+                e
+            );
+            items
+                .iter()
+                .map(|(context, db_msg)| persist_incoming(app_handle, context, db_msg))
+                .collect()
         }
-
-        crate::storage::db::insert_message(conn, db_msg).map_err(|e| e.to_string())
-    })
+    }
 }
 
 impl NetworkManager {
@@ -373,7 +486,12 @@ impl NetworkManager {
             .map_err(|_| "Persistence worker dropped group response".to_string())?
     }
 
-    pub(super) async fn persist_delivered_status(&mut self, msg_id: String) -> Result<(), String> {
+    /// Returns whether the message changed; a receipt that arrives after the
+    /// read receipt doesn't.
+    pub(super) async fn persist_delivered_status(
+        &mut self,
+        msg_id: String,
+    ) -> Result<bool, String> {
         let (tx, rx) = tokio::sync::oneshot::channel();
 
         self.enqueue_persistence_task(
@@ -20,6 +20,47 @@ impl NetworkManager {
         println!("[Shadow] 📋 Registered poll for {}", invitee);
     }
 
+    pub(super) fn cancel_shadow_poll(&mut self, invitee: &str) {
+        if self.pending_shadow_polls.remove(invitee).is_some() {
+            println!("[Shadow] 🛑 Stopped polling for {}", invitee);
+        }
+    }
+
+    /// `invitee` redeemed our invite: stop publishing it and add them as a
+    /// friend with the keys from their shadow.
+    async fn complete_invitation(
+        &self,
+        invitee: &str,
+        keys: Option<&crate::network::invite::IdentityKeys>,
+    ) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let state = self.app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        let mut config = match mgr.load().await {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("[Shadow] Failed to load config for {}: {}", invitee, e);
+                return;
+            }
+        };
+        let removed = crate::network::gist::prune_pending_invitations(
+            &mut config.user.pending_invitations,
+            now,
+            Some(invitee),
+        );
+        let added = config.user.remember_friend(invitee, keys);
+        if removed == 0 && !added {
+            return;
+        }
+        match mgr.save(&config).await {
+            Ok(()) => println!("[Shadow] ✅ {} redeemed our invitation", invitee),
+            Err(e) => eprintln!("[Shadow] Failed to save friend {}: {}", invitee, e),
+        }
+    }
+
     /// Poll for shadow invites from all pending invitees
     pub(super) async fn poll_shadow_invites(&mut self) {
         use crate::network::gist;
@@ -39,6 +80,15 @@ impl NetworkManager {
         self.pending_shadow_polls
             .retain(|_, (_, _, created)| now - *created < 120);
 
+        let backend =
+            match crate::network::discovery_backend::Backend::from_app(&self.app_handle).await {
+                Ok(backend) => backend,
+                Err(e) => {
+                    eprintln!("[Shadow] Cannot poll, config unavailable: {}", e);
+                    return;
+                }
+            };
+
         // Clone keys to avoid borrow issues
         let invitees: Vec<String> = self.pending_shadow_polls.keys().cloned().collect();
 
@@ -49,7 +99,7 @@ impl NetworkManager {
             };
 
             // Fetch shadow invites from invitee's Gist
-            match gist::get_friend_shadows(&invitee).await {
+            match gist::get_friend_shadows(&backend, &invitee).await {
                 Ok(shadows) => {
                     for shadow in shadows {
                         // Try to decrypt with our key
@@ -72,6 +122,8 @@ impl NetworkManager {
 
                                 // Remove from pending shadow polls
                                 self.pending_shadow_polls.remove(&invitee);
+                                self.complete_invitation(&invitee, payload.invitee_keys.as_ref())
+                                    .await;
                             }
                             Ok(None) => {
                                 // Wrong key or not for us, continue
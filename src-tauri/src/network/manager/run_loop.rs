@@ -5,6 +5,18 @@ impl NetworkManager {
         println!("🛜 Network Manager: Running!");
         self.refresh_peer_mapping_cache().await;
         self.refresh_trusted_peer_registry().await;
+        self.restore_handshakes().await;
+        self.restore_peer_addresses();
+        {
+            let state = self.app_handle.state::<crate::AppState>();
+            let mgr = state.config_manager.lock().await;
+            if let Ok(config) = mgr.load().await {
+                crate::network::flood::set_limits(config.user.flood_protection);
+                crate::storage::bandwidth::set_monthly_cap_mb(config.user.bandwidth_monthly_cap_mb);
+                crate::storage::quota::set_cache_cap_mb(config.user.media_cache_cap_mb);
+                crate::chat::auto_download::set_defaults(config.user.auto_download);
+            }
+        }
 
         let control_topic = crate::network::gossip::control_topic();
         if let Err(e) = self
@@ -43,6 +55,7 @@ impl NetworkManager {
                     if let Err(e) = self.swarm.behaviour_mut().gossipsub.subscribe(&topic) {
                         eprintln!("[Gossipsub] Failed to subscribe {}: {:?}", group_id, e);
                     } else {
+                        self.subscribe_group_activity(&group_id);
                         self.subscribed_group_ids.insert(group_id);
                     }
                 }
@@ -64,7 +77,8 @@ impl NetworkManager {
         let mut shadow_poll_interval = tokio::time::interval(std::time::Duration::from_secs(2));
         // Aggressive punch interval - 500ms for continuous hole punching
         let mut punch_interval = tokio::time::interval(std::time::Duration::from_millis(500));
-        // Cleanup stale transfer states every minute.
+        // Cleanup stale transfer states and expired handshakes, and resume
+        // downloads paused by the bandwidth cap, every minute.
         let mut transfer_cleanup_interval =
             tokio::time::interval(std::time::Duration::from_secs(60));
         // Voice-call tick: ring timeout + outgoing frame pump.
@@ -75,6 +89,14 @@ impl NetworkManager {
         let mut broadcast_tick = tokio::time::interval(std::time::Duration::from_millis(33));
         // Ensure mDNS runtime reflects current connectivity settings.
         let mut mdns_reconcile_interval = tokio::time::interval(std::time::Duration::from_secs(2));
+        // Redial dropped friends whose backoff ran out.
+        let mut reconnect_tick = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut swarm_download_tick = tokio::time::interval(std::time::Duration::from_secs(5));
+        // Resend chat messages whose retry backoff ran out.
+        let mut outbox_tick = tokio::time::interval(std::time::Duration::from_secs(1));
+        // Coalesce group typing/presence into one event per group.
+        let mut group_activity_tick =
+            tokio::time::interval(crate::chat::group_activity::EMIT_INTERVAL);
 
         loop {
             tokio::select! {
@@ -88,6 +110,12 @@ impl NetworkManager {
                         "[Network Debug] Heartbeat: Swarm active. Connected: {}, discovered: {}. Listening...",
                         connected_count, discovered_count
                     );
+                    self.refresh_network_stats().await;
+                    self.attempt_path_upgrades();
+                    self.finish_connection_tests();
+                    self.expire_fragments();
+                    self.refresh_peer_reputation();
+                    self.emit_peer_quality();
                 }
                 _ = nat_keepalive_interval.tick() => {
                     // Dial a dummy address to send outbound UDP and keep NAT mapping alive
@@ -110,8 +138,21 @@ impl NetworkManager {
                         self.punch_active_targets();
                     }
                 }
+                _ = reconnect_tick.tick() => {
+                    self.run_due_reconnects();
+                }
+                _ = swarm_download_tick.tick() => {
+                    self.tick_swarm_downloads();
+                }
+                _ = outbox_tick.tick() => {
+                    self.retry_due_messages();
+                }
                 _ = transfer_cleanup_interval.tick() => {
                     self.cleanup_stale_transfer_states();
+                    self.expire_local_peers();
+                    self.expire_handshakes().await;
+                    self.resume_paused_downloads().await;
+                    self.enforce_media_cache_cap();
                 }
                 _ = voice_call_tick.tick() => {
                     self.tick_voice_call().await;
@@ -137,9 +178,15 @@ impl NetworkManager {
                 Some(event) = self.screen_broadcast_stream_event_rx.recv() => {
                     self.handle_screen_broadcast_stream_event(event).await;
                 }
+                Some(event) = self.blob_stream_event_rx.recv() => {
+                    self.handle_blob_stream_event(event).await;
+                }
                 _ = mdns_reconcile_interval.tick() => {
                     self.reconcile_mdns_runtime();
                 }
+                _ = group_activity_tick.tick() => {
+                    self.tick_group_activity();
+                }
                 Some(cmd) = self.crx.recv() => {
                     self.dispatch_command(cmd).await;
                 }
@@ -167,17 +214,25 @@ impl NetworkManager {
         }
 
         use tauri::Manager;
-        let listeners: Vec<String> = self.swarm.listeners().map(|l| l.to_string()).collect();
+        let listeners: Vec<String> = self
+            .swarm
+            .listeners()
+            .chain(self.swarm.external_addresses())
+            .map(|l| l.to_string())
+            .collect();
         if listeners.is_empty() {
             return;
         }
 
         let state = self.app_handle.state::<crate::AppState>();
-        let (token, is_online) = {
+        let (backend, is_online) = {
             let mgr = state.config_manager.lock().await;
             if let Ok(config) = mgr.load().await {
                 (
-                    config.system.github_token.clone(),
+                    Some(crate::network::discovery_backend::Backend::from_system(
+                        &config.system,
+                    ))
+                    .filter(|backend| backend.can_publish()),
                     config.user.connectivity.github_sync_enabled,
                 )
             } else {
@@ -189,17 +244,18 @@ impl NetworkManager {
             return;
         }
 
-        if let Some(token) = token {
+        if let Some(backend) = backend {
             println!("Publishing listeners to Gist...");
             if !listeners.is_empty() {
                 if let Err(e) = crate::network::discovery::publish_peer_info(
-                    &token,
+                    &backend,
                     listeners,
                     self.app_handle.clone(),
                 )
                 .await
                 {
                     eprintln!("Failed to publish peer info: {}", e);
+                    crate::network::gist::report_auth_failure(&self.app_handle, &e);
                 }
             }
         }
@@ -14,23 +14,43 @@ impl NetworkManager {
                 }) => {
                     self.handle_gossipsub_message(message).await;
                 }
+                RChatBehaviourEvent::Gossipsub(libp2p::gossipsub::Event::Subscribed {
+                    topic,
+                    ..
+                }) => {
+                    self.handle_group_subscription(&topic);
+                }
                 RChatBehaviourEvent::DirectMessage(event) => {
                     self.handle_direct_message_event(event).await;
                 }
                 RChatBehaviourEvent::VoiceCall(()) => {}
                 RChatBehaviourEvent::VideoCall(()) => {}
                 RChatBehaviourEvent::BroadcastStream(()) => {}
+                RChatBehaviourEvent::BlobStream(()) => {}
                 RChatBehaviourEvent::Broadcast(event) => {
                     self.handle_broadcast_frame_event(event).await;
                 }
-                RChatBehaviourEvent::Identify(_) => {}
-                RChatBehaviourEvent::Ping(_) => {}
+                RChatBehaviourEvent::Identify(event) => {
+                    self.handle_identify_event(event);
+                }
+                RChatBehaviourEvent::Ping(event) => {
+                    self.record_ping(event);
+                }
                 RChatBehaviourEvent::Kademlia(_) => {}
                 RChatBehaviourEvent::RelayClient(event) => {
-                    println!("[Relay] 📡 Event: {:?}", event);
+                    println!(
+                        "[Relay] 📡 Event: {}",
+                        crate::logging::scrub(&format!("{:?}", event))
+                    );
                 }
                 RChatBehaviourEvent::Dcutr(event) => {
-                    println!("[DCUtR] 🔄 Event: {:?}", event);
+                    println!(
+                        "[DCUtR] 🔄 Event: {}",
+                        crate::logging::scrub(&format!("{:?}", event))
+                    );
+                }
+                RChatBehaviourEvent::Upnp(event) => {
+                    self.handle_upnp_event(event);
                 }
                 other => {
                     eprintln!(
@@ -45,6 +65,10 @@ impl NetworkManager {
                 endpoint,
                 ..
             } => {
+                self.settle_connection_test(connection_id, Ok(()));
+                if self.refuse_if_banned(peer_id, connection_id) {
+                    return;
+                }
                 self.handle_connection_established(peer_id, connection_id, endpoint)
                     .await;
             }
@@ -73,10 +97,16 @@ impl NetworkManager {
             }
             SwarmEvent::Dialing { peer_id, .. } => {
                 if let Some(peer) = peer_id {
-                    println!("[Swarm] Dialing peer: {}", peer);
+                    println!("[Swarm] Dialing peer: {}", crate::logging::peer(&peer));
                 }
             }
-            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+            SwarmEvent::OutgoingConnectionError {
+                connection_id,
+                peer_id,
+                error,
+                ..
+            } => {
+                self.settle_connection_test(connection_id, Err(error.to_string()));
                 let error_debug = format!("{:?}", error);
                 let (source, candidate_addr) = self.classify_outgoing_error(peer_id, &error_debug);
 
@@ -102,7 +132,8 @@ impl NetworkManager {
                 }
 
                 let known_addrs = peer_id
-                    .and_then(|peer| self.local_peers.get(&peer))
+                    .map(|peer| self.local_peers.addresses(&peer))
+                    .filter(|addrs| !addrs.is_empty())
                     .map(|addrs| {
                         addrs
                             .iter()
@@ -123,14 +154,23 @@ impl NetworkManager {
                     })
                     .unwrap_or_else(|| "-".to_string());
 
+                self.note_dial_failure(peer_id, source, candidate_addr.clone(), &error)
+                    .await;
+                if let Some(peer) = peer_id {
+                    self.note_reconnect_dial_failure(peer);
+                }
+                crate::storage::metrics::record_error("outgoing_connection");
+
                 eprintln!(
-                    "[Swarm] ❌ Outgoing connection error: source={}, peer={:?}, candidate_addr={}, mdns_known_addrs=[{}], mdns_backoff_remaining={}, error={:?}",
+                    "[Swarm] ❌ Outgoing connection error: source={}, peer={}, candidate_addr={}, mdns_known_addrs=[{}], mdns_backoff_remaining={}, error={}",
                     source.as_str(),
-                    peer_id,
-                    candidate_addr.as_deref().unwrap_or("-"),
-                    known_addrs,
+                    peer_id
+                        .as_ref()
+                        .map_or_else(|| "-".to_string(), crate::logging::peer),
+                    crate::logging::scrub(candidate_addr.as_deref().unwrap_or("-")),
+                    crate::logging::scrub(&known_addrs),
                     backoff_state,
-                    error
+                    crate::logging::scrub(&format!("{:?}", error))
                 );
             }
             SwarmEvent::IncomingConnectionError {
@@ -143,6 +183,7 @@ impl NetworkManager {
                     "[Swarm] ❌ Incoming connection error from {} to {}: {:?}",
                     send_back_addr, local_addr, error
                 );
+                crate::storage::metrics::record_error("incoming_connection");
             }
             SwarmEvent::ListenerError { listener_id, error } => {
                 eprintln!("[Swarm] ❌ Listener {:?} error: {:?}", listener_id, error);
@@ -163,12 +204,56 @@ impl NetworkManager {
         }
     }
 
-    pub(super) async fn handle_mdns_peer(&mut self, peer: crate::network::mdns::MdnsPeer) {
+    /// Map an anonymous advertisement to the friend whose discovery key
+    /// produced it; strangers (and our own record) stay unresolved.
+    fn resolve_mdns_token(&self, token: &crate::network::mdns::DiscoveryToken) -> Option<String> {
+        use tauri::Manager;
+        let keys: Vec<(String, [u8; 32])> = {
+            let state = self.app_handle.state::<crate::AppState>();
+            let conn = state.db_conn.lock().ok()?;
+            crate::storage::db::get_peer_discovery_keys(&conn)
+                .ok()?
+                .into_iter()
+                .filter_map(|(peer_id, key)| {
+                    Some((peer_id, crate::network::mdns::decode_discovery_key(&key)?))
+                })
+                .collect()
+        };
+        crate::network::mdns::resolve_token(token, &keys, crate::network::mdns::current_epoch())
+    }
+
+    pub(super) async fn handle_mdns_peer(&mut self, mut peer: crate::network::mdns::MdnsPeer) {
         if !self.is_mdns_enabled() {
             return;
         }
 
-        println!("[NetworkManager] Received mDNS peer: {}", peer.peer_id);
+        if let Some(token) = &peer.token {
+            match self.resolve_mdns_token(token) {
+                Some(peer_id) if peer_id != self.swarm.local_peer_id().to_string() => {
+                    // The record carries no name; keep the one we already know.
+                    use tauri::Manager;
+                    peer.device_name = None;
+                    peer.alias = self
+                        .app_handle
+                        .state::<crate::AppState>()
+                        .db_conn
+                        .lock()
+                        .ok()
+                        .and_then(|conn| {
+                            crate::storage::db::get_peer_alias(&conn, &peer_id)
+                                .ok()
+                                .flatten()
+                        });
+                    peer.peer_id = peer_id;
+                }
+                _ => return,
+            }
+        }
+
+        println!(
+            "[NetworkManager] Received mDNS peer: {}",
+            crate::logging::peer(&peer.peer_id)
+        );
 
         // Parse peer ID
         let peer_id_res = peer.peer_id.parse::<PeerId>();
@@ -193,31 +278,26 @@ impl NetworkManager {
                     );
                 }
 
+                // Refresh what we know first: re-announcements keep addresses
+                // alive without adding duplicates.
+                let now = std::time::Instant::now();
+                let mut addresses = Vec::new();
+                for addr_str in peer.addresses {
+                    // Filter out invalid 0.0.0.0 addresses
+                    if addr_str.contains("0.0.0.0") {
+                        continue;
+                    }
+                    if let Ok(addr) = addr_str.parse::<Multiaddr>() {
+                        self.local_peers.observe(peer_id, addr.clone(), now);
+                        addresses.push(addr);
+                    }
+                }
+
                 // Skip if already connected to this peer
                 if self.swarm.is_connected(&peer_id) {
                     // Still emit/update discovery so frontend local-scan can show connected peers
                     // even when they were connected before the modal/listener was opened.
-                    for addr_str in peer.addresses {
-                        if addr_str.contains("0.0.0.0") {
-                            continue;
-                        }
-                        if let Ok(addr) = addr_str.parse::<Multiaddr>() {
-                            let entry = self.local_peers.entry(peer_id).or_insert_with(Vec::new);
-                            if !entry.iter().any(|existing| existing == &addr) {
-                                entry.push(addr);
-                            }
-                        }
-                    }
-
-                    let peer_info = LocalPeer {
-                        peer_id: peer.peer_id.clone(),
-                        addresses: self
-                            .local_peers
-                            .get(&peer_id)
-                            .map(|a| a.iter().map(|m| m.to_string()).collect())
-                            .unwrap_or_default(),
-                    };
-                    let _ = self.app_handle.emit("local-peer-discovered", peer_info);
+                    self.emit_local_peer_discovered(peer_id);
                     self.maybe_auto_connect_trusted_peer(peer_id).await;
                     return;
                 }
@@ -225,72 +305,39 @@ impl NetworkManager {
                 if !self.can_start_mdns_dial(peer_id) {
                     self.log_mdns_dial_skip(peer_id);
                     // Still refresh local peer list in UI even when dial is debounced/backed off.
-                    let peer_info = LocalPeer {
-                        peer_id: peer.peer_id.clone(),
-                        addresses: self
-                            .local_peers
-                            .get(&peer_id)
-                            .map(|a| a.iter().map(|m| m.to_string()).collect())
-                            .unwrap_or_default(),
-                    };
-                    let _ = self.app_handle.emit("local-peer-discovered", peer_info);
+                    self.emit_local_peer_discovered(peer_id);
                     self.maybe_auto_connect_trusted_peer(peer_id).await;
                     return;
                 }
 
-                let mut dial_started = false;
+                // Prefer LAN, then direct public, then relay addresses.
+                addresses
+                    .sort_by_key(|addr| std::cmp::Reverse(crate::network::path::preference(addr)));
 
-                // 1. Add to known peers
-                for addr_str in peer.addresses {
-                    // Filter out invalid 0.0.0.0 addresses
-                    if addr_str.contains("0.0.0.0") {
-                        println!("[NetworkManager] ⚠️ Skipping invalid address: {}", addr_str);
-                        continue;
-                    }
-
-                    if let Ok(addr) = addr_str.parse::<Multiaddr>() {
-                        println!("[NetworkManager] Dialing mDNS peer {} at {}", peer_id, addr);
-                        self.note_mdns_dial_started(peer_id);
-                        self.record_outgoing_dial(&addr, OutgoingDialSource::Mdns);
-                        dial_started = true;
-
-                        // 2. Explicitly Dial
-                        if let Err(e) = self.swarm.dial(addr.clone()) {
-                            eprintln!("[NetworkManager] Dial failed: {}", e);
-                            self.note_mdns_dial_failure(peer_id);
-                        }
-
-                        // 3. Track it
-                        let entry = self.local_peers.entry(peer_id).or_insert_with(Vec::new);
-                        if !entry.iter().any(|existing| existing == &addr) {
-                            entry.push(addr);
-                        }
-
-                        // 4. Add to Gossipsub
-                        self.swarm
-                            .behaviour_mut()
-                            .gossipsub
-                            .add_explicit_peer(&peer_id);
+                // One active dial attempt per peer is enough.
+                if let Some(addr) = addresses.into_iter().next() {
+                    println!(
+                        "[NetworkManager] Dialing mDNS peer {} at {}",
+                        crate::logging::peer(&peer_id),
+                        addr
+                    );
+                    self.note_mdns_dial_started(peer_id);
+                    self.record_outgoing_dial(&addr, OutgoingDialSource::Mdns);
 
-                        // One active dial attempt per peer is enough.
-                        break;
+                    if let Err(e) = self.swarm.dial(addr) {
+                        eprintln!("[NetworkManager] Dial failed: {}", e);
+                        self.note_mdns_dial_failure(peer_id);
                     }
-                }
 
-                if !dial_started {
+                    self.swarm
+                        .behaviour_mut()
+                        .gossipsub
+                        .add_explicit_peer(&peer_id);
+                } else {
                     self.note_mdns_dial_failure(peer_id);
                 }
 
-                // 5. Emit event to UI
-                let peer_info = LocalPeer {
-                    peer_id: peer.peer_id.clone(),
-                    addresses: self
-                        .local_peers
-                        .get(&peer_id)
-                        .map(|a| a.iter().map(|m| m.to_string()).collect())
-                        .unwrap_or_default(),
-                };
-                let _ = self.app_handle.emit("local-peer-discovered", peer_info);
+                self.emit_local_peer_discovered(peer_id);
                 self.maybe_auto_connect_trusted_peer(peer_id).await;
             }
             Err(e) => {
@@ -298,4 +345,17 @@ impl NetworkManager {
             }
         }
     }
+
+    fn emit_local_peer_discovered(&self, peer_id: PeerId) {
+        let peer_info = LocalPeer {
+            peer_id: peer_id.to_string(),
+            addresses: self
+                .local_peers
+                .addresses(&peer_id)
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+        };
+        let _ = self.app_handle.emit("local-peer-discovered", peer_info);
+    }
 }
@@ -1,5 +1,31 @@
 use super::*;
 
+fn advertisement_for(
+    user: &crate::storage::config::UserConfig,
+) -> crate::network::mdns::Advertisement {
+    use crate::network::mdns::Advertisement;
+    use crate::storage::config::MdnsPrivacy;
+
+    match user.mdns_privacy {
+        MdnsPrivacy::Off => Advertisement::Off,
+        MdnsPrivacy::Full => Advertisement::Full {
+            alias: user.profile.alias.clone(),
+        },
+        MdnsPrivacy::Anonymous => match user
+            .mdns_discovery_key
+            .as_deref()
+            .and_then(crate::network::mdns::decode_discovery_key)
+        {
+            Some(key) => Advertisement::Anonymous { key },
+            None => {
+                // Never fall back to revealing ourselves.
+                eprintln!("[mDNS] ⚠️ No discovery key yet, not advertising");
+                Advertisement::Off
+            }
+        },
+    }
+}
+
 impl NetworkManager {
     fn record_chat_reconnection(&self, chat_id: &str, connected_at: i64) {
         use tauri::Manager;
@@ -7,13 +33,13 @@ impl NetworkManager {
         let Ok(conn) = state.db_conn.lock() else {
             return;
         };
-        if let Err(e) =
-            crate::storage::db::record_chat_connection_established(&conn, chat_id, connected_at)
-        {
-            eprintln!(
+        match crate::storage::db::record_chat_connection_established(&conn, chat_id, connected_at) {
+            Ok(true) => crate::storage::metrics::record(crate::storage::metrics::RECONNECTS),
+            Ok(false) => {}
+            Err(e) => eprintln!(
                 "[Connection] Failed to update reconnect counters for {}: {}",
                 chat_id, e
-            );
+            ),
         }
     }
 
@@ -23,15 +49,23 @@ impl NetworkManager {
         connection_id: libp2p::swarm::ConnectionId,
         endpoint: libp2p::core::ConnectedPoint,
     ) {
-        println!("[Swarm] Connected to {}", peer_id);
+        println!("[Swarm] Connected to {}", crate::logging::peer(&peer_id));
         self.note_mdns_dial_success(peer_id);
 
         let remote_addr = endpoint.get_remote_address().clone();
         self.note_peer_transport_connected(peer_id, connection_id, &remote_addr);
         self.local_peers
-            .entry(peer_id)
-            .or_insert_with(Vec::new)
-            .push(remote_addr.clone());
+            .observe(peer_id, remote_addr.clone(), std::time::Instant::now());
+        if endpoint.is_dialer() {
+            self.remember_peer_address(peer_id, &remote_addr);
+        }
+        if let Some(state) = self.reconnect_scheduler.connected(&peer_id) {
+            println!(
+                "[Reconnect] Reconnected to {}",
+                crate::logging::peer(&peer_id)
+            );
+            self.emit_reconnect_state(peer_id, state);
+        }
 
         let mut to_remove = Vec::new();
         for (name, (addr, _)) in self.active_punch_targets.iter() {
@@ -62,6 +96,7 @@ impl NetworkManager {
         let transitioned = self
             .note_chat_connection_established(&peer_id_str, &remote_addr_str, connected_at)
             .await;
+        let first_connection = transitioned;
         if transitioned {
             self.record_chat_reconnection(&peer_id_str, connected_at);
         }
@@ -142,8 +177,13 @@ impl NetworkManager {
                     .as_secs() as i64,
                 chunk_hash: None,
                 chunk_data: None,
+                chunk_nonce: None,
                 chunk_list: None,
                 sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
             self.swarm
@@ -160,6 +200,8 @@ impl NetworkManager {
             );
         }
 
+        self.send_pending_group_join(peer_id);
+
         let mut matched_data = None;
         for (pending_addr, (inviter_user, my_user)) in self.pending_github_mappings.iter() {
             if remote_addr_str.starts_with("/ip4/") && pending_addr.starts_with("/ip4/") {
@@ -177,7 +219,8 @@ impl NetworkManager {
             self.pending_github_mappings.remove(&addr_key);
             println!(
                 "[DIAL] ✅ GitHub user {} connected with PeerId {}",
-                inviter_github_user, peer_id_str
+                inviter_github_user,
+                crate::logging::peer(&peer_id_str)
             );
             self.cache_peer_mapping(&inviter_github_user, &peer_id_str);
             let chat_id =
@@ -206,7 +249,8 @@ impl NetworkManager {
                     } else {
                         println!(
                             "[DIAL] ✅ Saved mapping: {} → {}",
-                            gh_user, peer_id_for_mapping
+                            gh_user,
+                            crate::logging::peer(&peer_id_for_mapping)
                         );
                     }
                 }
@@ -214,7 +258,8 @@ impl NetworkManager {
 
             println!(
                 "[HANDSHAKE] 🤝 Sending invite_handshake to {} with my username: {}",
-                peer_id, my_username
+                crate::logging::peer(&peer_id),
+                my_username
             );
 
             use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
@@ -236,15 +281,23 @@ impl NetworkManager {
                     .as_secs() as i64,
                 chunk_hash: None,
                 chunk_data: None,
+                chunk_nonce: None,
                 chunk_list: None,
                 sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
             self.swarm
                 .behaviour_mut()
                 .direct_message
                 .send_request(&peer_id, handshake);
-            println!("[HANDSHAKE] ✅ Handshake sent to {}", peer_id);
+            println!(
+                "[HANDSHAKE] ✅ Handshake sent to {}",
+                crate::logging::peer(&peer_id)
+            );
 
             let peer_info = LocalPeer {
                 peer_id: chat_id.clone(),
@@ -256,6 +309,10 @@ impl NetworkManager {
                 chat_id
             );
         }
+
+        if first_connection {
+            self.send_identity_update(peer_id).await;
+        }
     }
 
     pub(super) async fn handle_connection_closed(
@@ -265,7 +322,10 @@ impl NetworkManager {
         num_established: u32,
         endpoint: libp2p::core::ConnectedPoint,
     ) {
-        println!("[Swarm] Disconnected from {}", peer_id);
+        println!(
+            "[Swarm] Disconnected from {}",
+            crate::logging::peer(&peer_id)
+        );
         let remote_addr = endpoint.get_remote_address().clone();
         let quic_path_lost =
             self.note_peer_transport_disconnected(peer_id, connection_id, &remote_addr);
@@ -287,10 +347,15 @@ impl NetworkManager {
         }
 
         if num_established == 0 {
+            self.quality_registry.forget(&peer_id);
+            self.schedule_reconnect(peer_id);
             self.handle_peer_disconnect_for_voice_call(&peer_id).await;
             self.handle_peer_disconnect_for_broadcast(&peer_id).await;
-            if self.local_peers.remove(&peer_id).is_some() {
-                println!("[Swarm] Peer {} fully disconnected, notifying UI", peer_id);
+            if self.local_peers.remove(&peer_id) {
+                println!(
+                    "[Swarm] Peer {} fully disconnected, notifying UI",
+                    crate::logging::peer(&peer_id)
+                );
 
                 let peer_id_str = peer_id.to_string();
                 self.unmark_connected_chat_id(&peer_id_str).await;
@@ -361,7 +426,7 @@ impl NetworkManager {
         );
         let peer_id = *self.swarm.local_peer_id();
 
-        let user_alias = {
+        let user = {
             use tauri::Manager;
             let state = self.app_handle.state::<crate::AppState>();
             state
@@ -369,14 +434,28 @@ impl NetworkManager {
                 .try_lock()
                 .ok()
                 .and_then(|mgr| mgr.load_sync().ok())
-                .and_then(|c| c.user.profile.alias.clone())
+                .map(|c| c.user)
+        };
+        let (requested, advertisement) = match user {
+            Some(user) => {
+                crate::network::mdns::set_privacy(user.mdns_privacy);
+                (user.mdns_privacy, advertisement_for(&user))
+            }
+            // Config busy: keep the last known level, without the alias.
+            None => match crate::network::mdns::privacy() {
+                crate::storage::config::MdnsPrivacy::Full => (
+                    crate::storage::config::MdnsPrivacy::Full,
+                    crate::network::mdns::Advertisement::Full { alias: None },
+                ),
+                level => (level, crate::network::mdns::Advertisement::Off),
+            },
         };
 
         if let Err(e) = crate::network::mdns::start_mdns_service(
             peer_id,
             port,
             self.mdns_tx.clone(),
-            user_alias,
+            advertisement,
         )
         .map(|handle| {
             self.mdns_handle = Some(handle);
@@ -384,6 +463,7 @@ impl NetworkManager {
             eprintln!("[NetworkManager] Failed to start mDNS: {}", e);
         } else {
             self.mdns_started = true;
+            self.mdns_advertised = requested;
             println!("[NetworkManager] mDNS started (advertising + browsing)");
         }
     }
@@ -397,19 +477,30 @@ impl NetworkManager {
                     handle.stop();
                 }
                 self.mdns_started = false;
+                println!("[mDNS] 📴 Offline: service deregistered, browsing stopped");
 
-                let expired_peers: Vec<String> =
-                    self.local_peers.keys().map(|p| p.to_string()).collect();
-                self.local_peers.clear();
-                for peer_id in expired_peers {
-                    let _ = self.app_handle.emit("local-peer-expired", peer_id);
+                for peer_id in self.local_peers.clear() {
+                    let _ = self
+                        .app_handle
+                        .emit("local-peer-expired", peer_id.to_string());
                 }
             }
             return;
         }
 
         if self.mdns_started {
-            return;
+            if crate::network::mdns::privacy() == self.mdns_advertised {
+                return;
+            }
+            // Advertisement level changed: re-register with the new TXT record.
+            println!(
+                "[mDNS] 🔁 Privacy changed to {:?}, restarting service",
+                crate::network::mdns::privacy()
+            );
+            if let Some(mut handle) = self.mdns_handle.take() {
+                handle.stop();
+            }
+            self.mdns_started = false;
         }
 
         let listen_port = self
@@ -448,4 +539,38 @@ impl NetworkManager {
             }
         }
     }
+
+    /// Mapped addresses are confirmed as external addresses by the behaviour
+    /// and published with our listeners.
+    pub(super) fn handle_upnp_event(&mut self, event: libp2p::upnp::Event) {
+        let (address, mapped) = match event {
+            libp2p::upnp::Event::NewExternalAddr(address) => {
+                println!("[UPnP] ✅ Port mapped, reachable at {}", address);
+                (address, true)
+            }
+            libp2p::upnp::Event::ExpiredExternalAddr(address) => {
+                println!("[UPnP] ⏰ Mapping for {} expired", address);
+                (address, false)
+            }
+            libp2p::upnp::Event::GatewayNotFound => {
+                println!("[UPnP] No UPnP gateway found; forward the port manually");
+                return;
+            }
+            libp2p::upnp::Event::NonRoutableGateway => {
+                println!("[UPnP] ⚠️ Gateway has no public address (double NAT?)");
+                return;
+            }
+        };
+
+        let app_handle = self.app_handle.clone();
+        let addr_str = address.to_string();
+        tauri::async_runtime::spawn(async move {
+            let state = app_handle.state::<crate::NetworkState>();
+            let mut addrs = state.listening_addresses.lock().await;
+            addrs.retain(|existing| *existing != addr_str);
+            if mapped {
+                addrs.push(addr_str);
+            }
+        });
+    }
 }
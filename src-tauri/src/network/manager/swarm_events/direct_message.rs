@@ -1,20 +1,6 @@
 use super::*;
 
 impl NetworkManager {
-    fn is_persisted_chat_message_id(msg_id: &str) -> bool {
-        let mut parts = msg_id.split('-');
-        let Some(ts) = parts.next() else {
-            return false;
-        };
-        let Some(rand) = parts.next() else {
-            return false;
-        };
-        if parts.next().is_some() {
-            return false;
-        }
-        ts.parse::<i64>().is_ok() && rand.parse::<u32>().is_ok()
-    }
-
     pub(super) async fn handle_direct_message_event(
         &mut self,
         event: libp2p::request_response::Event<
@@ -29,9 +15,63 @@ impl NetworkManager {
                 Message::Request {
                     request, channel, ..
                 } => {
-                    println!("[DM] 📥 Received {:?} from {}", request.msg_type, peer);
+                    println!(
+                        "[DM] 📥 Received {:?} from {}",
+                        request.msg_type,
+                        crate::logging::peer(&peer)
+                    );
+                    crate::storage::bandwidth::record_received(
+                        &peer.to_string(),
+                        request.msg_type.bandwidth_protocol(),
+                        request.approx_wire_len(),
+                    );
+                    if !self.admit_inbound(peer, crate::network::flood::Traffic::DirectMessage) {
+                        self.send_status_response(
+                            channel,
+                            request.id,
+                            "error",
+                            Some("rate limited".to_string()),
+                        );
+                        return;
+                    }
 
                     use crate::network::direct_message::DirectMessageKind;
+                    let request = if request.msg_type == DirectMessageKind::Fragment {
+                        match self.reassemble_direct_fragment(peer, &request) {
+                            Ok(Some(inner)) => inner,
+                            Ok(None) => {
+                                self.send_status_response(channel, request.id, "delivered", None);
+                                return;
+                            }
+                            Err(err) => {
+                                eprintln!(
+                                    "[Fragment] ❌ Bad fragment from {}: {}",
+                                    crate::logging::peer(&peer),
+                                    err
+                                );
+                                self.send_status_response(channel, request.id, "error", Some(err));
+                                return;
+                            }
+                        }
+                    } else {
+                        request
+                    };
+                    if !request.wire.is_supported() {
+                        println!(
+                            "[DM] ⏭️ Ignoring {} from {}: needs protocol v{}, we speak v{}",
+                            request.id,
+                            crate::logging::peer(&peer),
+                            request.wire.min_supported_version,
+                            crate::network::wire::PROTOCOL_VERSION
+                        );
+                        self.send_status_response(
+                            channel,
+                            request.id,
+                            "ignored",
+                            Some("unsupported protocol version".to_string()),
+                        );
+                        return;
+                    }
                     match request.msg_type {
                         DirectMessageKind::Text
                         | DirectMessageKind::Image
@@ -148,9 +188,114 @@ impl NetworkManager {
                             self.send_status_response(channel, request.id, "delivered", None);
                         }
                         DirectMessageKind::ChunkResponse => {
-                            self.handle_chunk_response(&request).await;
+                            self.handle_chunk_response(peer, &request).await;
                             self.send_status_response(channel, request.id, "delivered", None);
                         }
+                        DirectMessageKind::IdentityUpdate => {
+                            match self.handle_identity_update(peer, &request).await {
+                                Ok(()) => self.send_status_response(
+                                    channel,
+                                    request.id,
+                                    "delivered",
+                                    None,
+                                ),
+                                Err(err) => self.send_status_response(
+                                    channel,
+                                    request.id,
+                                    "error",
+                                    Some(err),
+                                ),
+                            }
+                        }
+                        DirectMessageKind::ConnectionRequest => {
+                            match self.verify_connection_request(peer, &request) {
+                                Ok(()) => {
+                                    self.handle_incoming_connection_request(peer);
+                                    self.send_status_response(
+                                        channel,
+                                        request.id,
+                                        "delivered",
+                                        None,
+                                    );
+                                }
+                                Err(err) => {
+                                    eprintln!(
+                                        "[Handshake] ⚠️ Rejected connection request from {}: {}",
+                                        crate::logging::peer(&peer),
+                                        err
+                                    );
+                                    self.send_status_response(
+                                        channel,
+                                        request.id,
+                                        "error",
+                                        Some(err),
+                                    );
+                                }
+                            }
+                        }
+                        DirectMessageKind::GroupJoinRequest
+                        | DirectMessageKind::GroupWelcome
+                        | DirectMessageKind::GroupHistoryRequest
+                        | DirectMessageKind::GroupHistoryPage => {
+                            let handled = match request.msg_type {
+                                DirectMessageKind::GroupJoinRequest => {
+                                    self.handle_group_join_request(peer, &request)
+                                }
+                                DirectMessageKind::GroupWelcome => {
+                                    self.handle_group_welcome(peer, &request)
+                                }
+                                DirectMessageKind::GroupHistoryRequest => {
+                                    self.handle_group_history_request(peer, &request)
+                                }
+                                _ => self.handle_group_history_page(peer, &request),
+                            };
+                            match handled {
+                                Ok(()) => self.send_status_response(
+                                    channel,
+                                    request.id,
+                                    "delivered",
+                                    None,
+                                ),
+                                Err(err) => {
+                                    eprintln!(
+                                        "[Group] ⚠️ {:?} from {} rejected: {}",
+                                        request.msg_type,
+                                        crate::logging::peer(&peer),
+                                        err
+                                    );
+                                    self.send_status_response(
+                                        channel,
+                                        request.id,
+                                        "error",
+                                        Some(err),
+                                    );
+                                }
+                            }
+                        }
+                        DirectMessageKind::Fragment => {
+                            // Reassembly above never yields another fragment.
+                            self.send_status_response(
+                                channel,
+                                request.id,
+                                "error",
+                                Some("unexpected fragment".to_string()),
+                            );
+                        }
+                        DirectMessageKind::Unknown => {
+                            // A newer peer's kind: acknowledged, so it isn't
+                            // retried, but not read as anything we know.
+                            println!(
+                                "[DM] ⏭️ Ignoring {} of an unknown type from {}",
+                                request.id,
+                                crate::logging::peer(&peer)
+                            );
+                            self.send_status_response(
+                                channel,
+                                request.id,
+                                "ignored",
+                                Some("unsupported message type".to_string()),
+                            );
+                        }
                     }
                 }
                 Message::Response {
@@ -161,53 +306,50 @@ impl NetworkManager {
                         "[DM] 📦 Response for {:?}: {} for msg {}",
                         request_id, response.status, response.msg_id
                     );
+                    self.outbox.answered(&request_id);
 
                     if response.status == "delivered"
-                        && Self::is_persisted_chat_message_id(&response.msg_id)
+                        && crate::chat::message_id::is_message_id(&response.msg_id)
                     {
-                        match self.persist_delivered_status(response.msg_id.clone()).await {
-                            Ok(()) => {
-                                let _ = self.app_handle.emit(
-                                    "message-status-updated",
-                                    serde_json::json!({
-                                        "msg_id": response.msg_id,
-                                        "status": "delivered",
-                                    }),
-                                );
+                        let persisted =
+                            self.persist_delivered_status(response.msg_id.clone()).await;
+                        let updated = match persisted {
+                            Ok(true) => true,
+                            // Not stored: a temporary chat message, perhaps.
+                            Ok(false) | Err(_) => {
+                                use crate::chat::message::MessageStatus;
+                                use tauri::Manager;
+                                let network_state = self.app_handle.state::<crate::NetworkState>();
+                                let mut temp_state = network_state.temporary_state.lock().await;
+                                temp_state
+                                    .messages
+                                    .values_mut()
+                                    .flat_map(|msgs| msgs.iter_mut())
+                                    .find(|m| m.id == response.msg_id)
+                                    .filter(|m| {
+                                        crate::storage::db::status_transition_allowed(
+                                            MessageStatus::from_str(&m.status),
+                                            MessageStatus::Delivered,
+                                        )
+                                    })
+                                    .map(|m| m.status = "delivered".to_string())
+                                    .is_some()
                             }
-                            Err(err) => {
-                                let mut updated_runtime = false;
-                                {
-                                    use tauri::Manager;
-                                    let network_state =
-                                        self.app_handle.state::<crate::NetworkState>();
-                                    let mut temp_state = network_state.temporary_state.lock().await;
-                                    for msgs in temp_state.messages.values_mut() {
-                                        if let Some(found) =
-                                            msgs.iter_mut().find(|m| m.id == response.msg_id)
-                                        {
-                                            found.status = "delivered".to_string();
-                                            updated_runtime = true;
-                                            break;
-                                        }
-                                    }
-                                }
+                        };
 
-                                if updated_runtime {
-                                    let _ = self.app_handle.emit(
-                                        "message-status-updated",
-                                        serde_json::json!({
-                                            "msg_id": response.msg_id,
-                                            "status": "delivered",
-                                        }),
-                                    );
-                                } else {
-                                    eprintln!(
-                                        "[DM] ❌ Failed to persist delivered status {}: {}",
-                                        response.msg_id, err
-                                    );
-                                }
-                            }
+                        if updated {
+                            let _ = self.app_handle.emit(
+                                "message-status-updated",
+                                serde_json::json!({
+                                    "msg_id": response.msg_id,
+                                    "status": "delivered",
+                                }),
+                            );
+                        } else if let Err(err) = persisted {
+                            eprintln!(
+                                "[DM] ❌ Failed to persist delivered status {}: {}",
+                                response.msg_id, err
+                            );
                         }
                     }
                 }
@@ -220,11 +362,31 @@ impl NetworkManager {
             } => {
                 eprintln!(
                     "[DM] Outbound failure to {} for {:?}: {:?}",
-                    peer, request_id, error
+                    crate::logging::peer(&peer),
+                    request_id,
+                    error
                 );
+                crate::storage::metrics::record_error("dm_outbound");
+                self.handle_outbox_failure(&request_id, error.to_string());
+                // An unreachable peer is not misbehaving; a dropped or timed-out
+                // request on a live connection is.
+                if !matches!(
+                    error,
+                    libp2p::request_response::OutboundFailure::DialFailure
+                ) {
+                    self.record_peer_behavior(
+                        peer,
+                        crate::network::reputation::PeerBehavior::FailedDelivery,
+                    );
+                }
             }
             Event::InboundFailure { peer, error, .. } => {
-                eprintln!("[DM] Inbound failure from {}: {:?}", peer, error);
+                eprintln!(
+                    "[DM] Inbound failure from {}: {:?}",
+                    crate::logging::peer(&peer),
+                    error
+                );
+                crate::storage::metrics::record_error("dm_inbound");
             }
             _ => {}
         }
@@ -261,69 +423,36 @@ impl NetworkManager {
             .await;
         println!(
             "[DM] Using chat_id: {} for sender {}",
-            chat_id, request.sender_id
+            chat_id,
+            crate::logging::peer(&request.sender_id)
         );
 
-        let db_msg = super::super::build_incoming_dm_db_message(request, chat_id.clone());
-
-        let chat_kind = crate::chat_kind::parse_chat_kind(&chat_id);
-
-        if matches!(chat_kind, crate::chat_kind::ChatKind::TemporaryDirect) {
-            use tauri::Manager;
-            let network_state = self.app_handle.state::<crate::NetworkState>();
-            let mut temp_state = network_state.temporary_state.lock().await;
-            temp_state
-                .messages
-                .entry(chat_id.clone())
-                .or_default()
-                .push(db_msg.clone());
-        } else {
-            self.persist_incoming_dm_message(request, chat_id.clone(), db_msg.clone())
-                .await
-                .map_err(|e| {
-                    format!(
-                        "Failed to persist {} message (id={}, chat_id={}, peer_id={}, file_hash={:?}): {}",
-                        request.msg_type.as_str(),
-                        request.id,
-                        chat_id,
-                        request.sender_id,
-                        request.file_hash,
-                        e
-                    )
-                })?;
-            println!("[DM] ✅ Message saved");
-        }
-
-        if request.msg_type.needs_file_transfer() {
-            if let Some(ref file_hash) = request.file_hash {
-                println!("[ChunkTransfer] 📤 Requesting metadata for {}", file_hash);
-
-                let metadata_req = crate::network::direct_message::DirectMessageRequest {
-                    id: format!("meta-req-{}", file_hash),
-                    sender_id: self.swarm.local_peer_id().to_string(),
-                    msg_type:
-                        crate::network::direct_message::DirectMessageKind::FileMetadataRequest,
-                    text_content: None,
-                    file_hash: Some(file_hash.clone()),
-                    timestamp: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as i64,
-                    chunk_hash: None,
-                    chunk_data: None,
-                    chunk_list: None,
-                    sender_alias: None,
-                };
-
-                self.swarm
-                    .behaviour_mut()
-                    .direct_message
-                    .send_request(&peer, metadata_req);
-            }
-        }
+        self.ingest(super::super::ingest::IncomingMessage::direct(
+            peer,
+            request.clone(),
+            chat_id,
+        ))
+        .await
+    }
 
-        let _ = self.app_handle.emit("message-received", db_msg);
-        Ok(())
+    fn verify_connection_request(
+        &self,
+        peer: PeerId,
+        request: &crate::network::direct_message::DirectMessageRequest,
+    ) -> Result<(), String> {
+        let signed: crate::network::connection_request::SignedConnectionRequest = request
+            .text_content
+            .as_deref()
+            .ok_or("missing connection request")
+            .and_then(|text| {
+                serde_json::from_str(text).map_err(|_| "malformed connection request")
+            })?;
+        crate::network::connection_request::verify(
+            &signed,
+            &peer,
+            self.swarm.local_peer_id(),
+            now_unix_secs(),
+        )
     }
 
     async fn handle_invite_handshake(
@@ -334,77 +463,148 @@ impl NetworkManager {
             let invitee_peer_id = request.sender_id.clone();
             println!(
                 "[HANDSHAKE] 🤝 Received handshake from GitHub user: {} (PeerId: {})",
-                invitee_github, invitee_peer_id
+                invitee_github,
+                crate::logging::peer(&invitee_peer_id)
             );
 
-            let chat_id =
-                crate::chat_identity::build_github_chat_id(&invitee_github, &invitee_peer_id);
-            self.cache_peer_mapping(&invitee_github, &invitee_peer_id);
-            self.mark_connected_chat_id(chat_id.clone()).await;
-
-            use tauri::Manager;
-            let state = self.app_handle.state::<crate::AppState>();
-
+            if self
+                .expects_github_peer(&invitee_github, &invitee_peer_id)
+                .await
             {
-                let app_handle = self.app_handle.clone();
-                let gh_user = invitee_github.clone();
-                let peer_id_str = invitee_peer_id.clone();
-                tauri::async_runtime::spawn(async move {
-                    let state = app_handle.state::<crate::AppState>();
-                    let mgr = state.config_manager.lock().await;
-                    if let Ok(mut config) = mgr.load().await {
-                        config
-                            .user
-                            .github_peer_mapping
-                            .insert(gh_user.clone(), peer_id_str.clone());
-                        if let Err(e) = mgr.save(&config).await {
-                            eprintln!("[HANDSHAKE] Failed to save mapping: {}", e);
-                        } else {
-                            println!(
-                                "[HANDSHAKE] ✅ Saved mapping: {} → {}",
-                                gh_user, peer_id_str
-                            );
-                        }
-                    }
-                });
+                self.accept_github_peer(invitee_github, invitee_peer_id)
+                    .await;
+            } else {
+                self.queue_friend_request(invitee_github, invitee_peer_id)
+                    .await;
             }
+        }
+    }
 
-            if let Ok(conn) = state.db_conn.lock() {
-                if !crate::storage::db::is_peer(&conn, &chat_id) {
-                    let _ = crate::storage::db::add_peer(
-                        &conn,
-                        &chat_id,
-                        Some(&invitee_github),
-                        None,
-                        "github",
-                    );
-                }
-                if !crate::storage::db::chat_exists(&conn, &chat_id) {
-                    let _ =
-                        crate::storage::db::create_chat(&conn, &chat_id, &invitee_github, false);
+    /// Whether we asked for this contact: one of our invites to them is still
+    /// being completed, or they already are a friend.
+    async fn expects_github_peer(&self, github_username: &str, peer_id: &str) -> bool {
+        if self.pending_shadow_polls.contains_key(github_username)
+            || self.active_punch_targets.contains_key(github_username)
+        {
+            return true;
+        }
+        use tauri::Manager;
+        let chat_id = crate::chat_identity::build_github_chat_id(github_username, peer_id);
+        let known_peer = self
+            .app_handle
+            .state::<crate::AppState>()
+            .db_conn
+            .lock()
+            .is_ok_and(|conn| crate::storage::db::is_peer(&conn, &chat_id));
+        if known_peer {
+            return true;
+        }
+        let state = self.app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        mgr.load().await.is_ok_and(|config| {
+            config
+                .user
+                .friends
+                .iter()
+                .any(|friend| friend.username == github_username)
+        })
+    }
+
+    /// Someone who has us on their roster dialed us unasked. Nothing is stored
+    /// until the user accepts with `accept_friend_request`.
+    async fn queue_friend_request(&self, github_username: String, peer_id: String) {
+        use tauri::Manager;
+        let request = crate::app_state::FriendRequest {
+            github_username,
+            peer_id,
+            received_at: now_unix_secs(),
+        };
+        println!(
+            "[HANDSHAKE] 📨 Friend request from {} ({}), waiting for approval",
+            request.github_username,
+            crate::logging::peer(&request.peer_id)
+        );
+        let net_state = self.app_handle.state::<crate::NetworkState>();
+        net_state
+            .friend_requests
+            .lock()
+            .await
+            .insert(request.peer_id.clone(), request.clone());
+        let _ = self.app_handle.emit("friend-request", request);
+    }
+
+    /// Map a GitHub user to their peer and create the chat with them.
+    pub(in crate::network::manager) async fn accept_github_peer(
+        &mut self,
+        invitee_github: String,
+        invitee_peer_id: String,
+    ) {
+        let chat_id = crate::chat_identity::build_github_chat_id(&invitee_github, &invitee_peer_id);
+        self.cache_peer_mapping(&invitee_github, &invitee_peer_id);
+        self.mark_connected_chat_id(chat_id.clone()).await;
+
+        use tauri::Manager;
+        let state = self.app_handle.state::<crate::AppState>();
+
+        {
+            let app_handle = self.app_handle.clone();
+            let gh_user = invitee_github.clone();
+            let peer_id_str = invitee_peer_id.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<crate::AppState>();
+                let mgr = state.config_manager.lock().await;
+                if let Ok(mut config) = mgr.load().await {
+                    config
+                        .user
+                        .github_peer_mapping
+                        .insert(gh_user.clone(), peer_id_str.clone());
+                    if let Err(e) = mgr.save(&config).await {
+                        eprintln!("[HANDSHAKE] Failed to save mapping: {}", e);
+                    } else {
+                        println!(
+                            "[HANDSHAKE] ✅ Saved mapping: {} → {}",
+                            gh_user,
+                            crate::logging::peer(&peer_id_str)
+                        );
+                    }
                 }
-                println!("[HANDSHAKE] ✅ Created chat: {}", chat_id);
+            });
+        }
+
+        if let Ok(conn) = state.db_conn.lock() {
+            if !crate::storage::db::is_peer(&conn, &chat_id) {
+                let _ = crate::storage::db::add_peer(
+                    &conn,
+                    &chat_id,
+                    Some(&invitee_github),
+                    None,
+                    "github",
+                );
+            }
+            if !crate::storage::db::chat_exists(&conn, &chat_id) {
+                let _ = crate::storage::db::create_chat(&conn, &chat_id, &invitee_github, false);
             }
+            println!("[HANDSHAKE] ✅ Created chat: {}", chat_id);
+        }
 
-            let _ = self.app_handle.emit(
-                "new-github-chat",
-                serde_json::json!({
-                    "chat_id": chat_id,
-                    "github_username": invitee_github,
-                    "peer_id": invitee_peer_id,
-                }),
-            );
+        let _ = self.app_handle.emit(
+            "new-github-chat",
+            serde_json::json!({
+                "chat_id": chat_id,
+                "github_username": invitee_github,
+                "peer_id": invitee_peer_id,
+            }),
+        );
 
-            let peer_info = LocalPeer {
-                peer_id: chat_id.clone(),
-                addresses: vec![],
-            };
-            let _ = self.app_handle.emit("local-peer-discovered", peer_info);
-            println!(
-                "[HANDSHAKE] ✅ Emitted local-peer-discovered for {}",
-                chat_id
-            );
-        }
+        let peer_info = LocalPeer {
+            peer_id: chat_id.clone(),
+            addresses: vec![],
+        };
+        let _ = self.app_handle.emit("local-peer-discovered", peer_info);
+        println!(
+            "[HANDSHAKE] ✅ Emitted local-peer-discovered for {}",
+            chat_id
+        );
     }
 
     async fn handle_temp_handshake(
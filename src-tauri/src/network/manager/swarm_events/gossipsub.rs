@@ -1,24 +1,149 @@
 use super::*;
+use crate::network::gossip::{GroupActivitySignal, GroupControlMessage, GroupMessageEnvelope};
+use crate::network::wire::{self, Decoded};
+
+/// What a group topic carries, once opened.
+enum GroupInbound {
+    Control(GroupControlMessage),
+    Message(GroupMessageEnvelope),
+}
+
+/// `Ok(None)` for types we don't know, which newer peers may send: those are
+/// skipped rather than read as something else.
+fn parse_group_inbound(decoded: Decoded) -> Result<Option<GroupInbound>, String> {
+    match decoded {
+        Decoded::Legacy(data) => {
+            if let Ok(control) = serde_json::from_slice(&data) {
+                return Ok(Some(GroupInbound::Control(control)));
+            }
+            serde_json::from_slice(&data)
+                .map(|envelope| Some(GroupInbound::Message(envelope)))
+                .map_err(|e| e.to_string())
+        }
+        Decoded::Typed { kind, body } if kind == wire::kind::GROUP_CONTROL => Decoded::body(body)
+            .map(|control| Some(GroupInbound::Control(control)))
+            .map_err(|e| e.to_string()),
+        Decoded::Typed { kind, body } if kind == wire::kind::GROUP_MESSAGE => Decoded::body(body)
+            .map(|envelope| Some(GroupInbound::Message(envelope)))
+            .map_err(|e| e.to_string()),
+        Decoded::Typed { kind, .. } => {
+            println!(
+                "[Gossipsub] Ignoring group payload of unknown type {}",
+                kind
+            );
+            Ok(None)
+        }
+        Decoded::Unsupported {
+            kind,
+            min_supported_version,
+        } => {
+            println!(
+                "[Gossipsub] Ignoring {} payload: needs protocol v{}, we speak v{}",
+                kind,
+                min_supported_version,
+                wire::PROTOCOL_VERSION
+            );
+            Ok(None)
+        }
+    }
+}
 
 impl NetworkManager {
+    fn penalize_invalid_gossip(&mut self, source: Option<PeerId>) {
+        if let Some(source) = source {
+            self.record_peer_behavior(
+                source,
+                crate::network::reputation::PeerBehavior::InvalidMessage,
+            );
+        }
+    }
+
+    /// Envelopes claiming to be from a friend whose identity key we store
+    /// must be signed with it; anyone else's are accepted unverified.
+    fn check_group_signature(&self, envelope: &GroupMessageEnvelope) -> bool {
+        let Some(key) = self.friend_identity_keys.get(&envelope.sender_id) else {
+            return true;
+        };
+        if envelope.verify_signature(key) {
+            return true;
+        }
+        eprintln!(
+            "[Group] ⚠️ Dropping {}: signature does not match {}'s identity key",
+            envelope.id,
+            crate::logging::peer(&envelope.sender_id)
+        );
+        let _ = self.app_handle.emit(
+            "group-signature-mismatch",
+            serde_json::json!({
+                "group_id": envelope.group_id,
+                "sender_id": envelope.sender_id,
+                "message_id": envelope.id,
+            }),
+        );
+        false
+    }
+
+    fn handle_group_activity_signal(
+        &mut self,
+        source: Option<PeerId>,
+        topic_group_id: &str,
+        data: &[u8],
+    ) {
+        // Signals must be attributable: the claimed sender is the gossip source.
+        let Some(source) = source else {
+            return;
+        };
+        if !self.admit_inbound(source, crate::network::flood::Traffic::Gossip) {
+            return;
+        }
+        let signal: serde_json::Result<GroupActivitySignal> = match wire::decode(data.to_vec()) {
+            Decoded::Legacy(data) => serde_json::from_slice(&data),
+            Decoded::Typed { kind, body } if kind == wire::kind::GROUP_ACTIVITY => {
+                Decoded::body(body)
+            }
+            // Newer kinds of activity we can't show.
+            _ => return,
+        };
+        let Ok(signal) = signal else {
+            self.penalize_invalid_gossip(Some(source));
+            return;
+        };
+        if signal.group_id != topic_group_id || signal.sender_id != source.to_string() {
+            self.penalize_invalid_gossip(Some(source));
+            return;
+        }
+        let alias = signal
+            .sender_alias
+            .map(|alias| alias.trim().to_string())
+            .filter(|alias| !alias.is_empty());
+        self.group_activity.note_typing(
+            topic_group_id,
+            &signal.sender_id,
+            alias,
+            signal.typing,
+            std::time::Instant::now(),
+        );
+    }
+
     pub(super) async fn handle_gossipsub_message(&mut self, message: libp2p::gossipsub::Message) {
         let topic = message.topic.to_string();
+        crate::storage::bandwidth::record_received(
+            &message
+                .source
+                .map(|source| source.to_string())
+                .unwrap_or_else(|| crate::storage::bandwidth::ANY_PEER.to_string()),
+            crate::storage::bandwidth::GOSSIP,
+            message.data.len(),
+        );
 
         if topic == crate::network::gossip::CONTROL_TOPIC {
-            let control: Result<crate::network::gossip::ControlEnvelope, _> =
-                serde_json::from_slice(&message.data);
-            if let Ok(crate::network::gossip::ControlEnvelope::ConnectionRequest {
-                from_peer_id,
-                to_peer_id,
-            }) = control
-            {
-                let local = self.swarm.local_peer_id().to_string();
-                if to_peer_id == local {
-                    if let Ok(from_peer) = from_peer_id.parse::<PeerId>() {
-                        self.handle_incoming_connection_request(from_peer);
-                    }
-                }
-            }
+            // Connection requests used to be broadcast here unsigned; they now
+            // travel as signed direct messages, so anything here is ignored.
+            return;
+        }
+
+        if let Some(group_id) = crate::network::gossip::group_id_from_activity_topic(&topic) {
+            self.handle_group_activity_signal(message.source, &group_id, &message.data);
             return;
         }
 
@@ -27,20 +152,41 @@ impl NetworkManager {
             return;
         };
 
-        let mut envelope: crate::network::gossip::GroupMessageEnvelope =
-            match serde_json::from_slice(&message.data) {
-                Ok(v) => v,
-                Err(e) => {
-                    println!("[Gossipsub] Ignoring non-group payload: {}", e);
-                    return;
+        if let Some(source) = message.source {
+            if !self.admit_inbound(source, crate::network::flood::Traffic::Gossip) {
+                return;
+            }
+        }
+
+        let Some(data) = self.reassemble_gossip_payload(message.source, message.data) else {
+            return;
+        };
+        let Some(opened) = self.open_group_payload(message.source, &topic_group_id, data) else {
+            return;
+        };
+
+        let mut envelope = match parse_group_inbound(opened.decoded) {
+            Ok(Some(GroupInbound::Message(envelope))) => envelope,
+            Ok(Some(GroupInbound::Control(control))) => {
+                if !self.handle_group_control(message.source, &topic_group_id, control) {
+                    self.penalize_invalid_gossip(message.source);
                 }
-            };
+                return;
+            }
+            Ok(None) => return,
+            Err(e) => {
+                println!("[Gossipsub] Ignoring non-group payload: {}", e);
+                self.penalize_invalid_gossip(message.source);
+                return;
+            }
+        };
 
         if envelope.group_id != topic_group_id {
             eprintln!(
                 "[Group] Topic/group mismatch. topic={}, payload={}",
                 topic_group_id, envelope.group_id
             );
+            self.penalize_invalid_gossip(message.source);
             return;
         }
 
@@ -48,67 +194,54 @@ impl NetworkManager {
             && !crate::chat_kind::is_temp_group_chat_id(&envelope.group_id)
         {
             eprintln!("[Group] Invalid group id in payload: {}", envelope.group_id);
+            self.penalize_invalid_gossip(message.source);
             return;
         }
 
-        if envelope.sender_id.is_empty() {
-            envelope.sender_id = message.source.map(|p| p.to_string()).unwrap_or_default();
+        if !self.check_group_signature(&envelope) {
+            self.penalize_invalid_gossip(message.source);
+            return;
         }
 
-        if envelope.sender_id == self.swarm.local_peer_id().to_string() {
+        // Gossip is signed by its publisher, so the claimed sender must be it.
+        let Some(source) = message.source else {
             return;
+        };
+        if envelope.sender_id.is_empty() {
+            envelope.sender_id = source.to_string();
         }
-
-        let db_msg = super::super::build_incoming_group_db_message(&envelope);
-
-        let is_temp_group = crate::chat_kind::is_temp_group_chat_id(&envelope.group_id);
-        if is_temp_group {
-            use tauri::Manager;
-            let network_state = self.app_handle.state::<crate::NetworkState>();
-            let mut temp_state = network_state.temporary_state.lock().await;
-            temp_state
-                .messages
-                .entry(envelope.group_id.clone())
-                .or_default()
-                .push(db_msg.clone());
-        } else if let Err(e) = self
-            .persist_incoming_group_message(&envelope, db_msg.clone())
-            .await
-        {
+        if envelope.sender_id != source.to_string() {
             eprintln!(
-                "[Group] Failed to save message {} for {}: {}",
-                db_msg.id, db_msg.chat_id, e
+                "[Group] ⚠️ Dropping {}: sender {} is not its publisher {}",
+                envelope.id,
+                crate::logging::peer(&envelope.sender_id),
+                crate::logging::peer(&source)
             );
+            self.penalize_invalid_gossip(Some(source));
             return;
         }
 
-        if envelope.content_type.needs_file_transfer() {
-            if let Some(ref file_hash) = envelope.file_hash {
-                if let Ok(sender_peer_id) = envelope.sender_id.parse::<PeerId>() {
-                    use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
-                    let metadata_req = DirectMessageRequest {
-                        id: format!("meta-req-{}", file_hash),
-                        sender_id: self.swarm.local_peer_id().to_string(),
-                        msg_type: DirectMessageKind::FileMetadataRequest,
-                        text_content: None,
-                        file_hash: Some(file_hash.clone()),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs() as i64,
-                        chunk_hash: None,
-                        chunk_data: None,
-                        chunk_list: None,
-                        sender_alias: None,
-                    };
-                    self.swarm
-                        .behaviour_mut()
-                        .direct_message
-                        .send_request(&sender_peer_id, metadata_req);
-                }
-            }
+        // Membership changes only through invites and admin control. An older
+        // epoch's key may be held by someone since removed, so it only
+        // counts for current members.
+        if opened.keyed
+            && !opened.current_epoch
+            && !self.is_group_member(&envelope.group_id, &envelope.sender_id)
+        {
+            println!(
+                "[Group] 🔒 Dropping {} in {}: {} is not a member",
+                envelope.id,
+                envelope.group_id,
+                crate::logging::peer(&source)
+            );
+            return;
         }
 
-        let _ = self.app_handle.emit("message-received", db_msg);
+        if let Err(e) = self
+            .ingest(super::super::ingest::IncomingMessage::group(envelope))
+            .await
+        {
+            eprintln!("[Group] {}", e);
+        }
     }
 }
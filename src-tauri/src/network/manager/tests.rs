@@ -22,8 +22,13 @@ fn incoming_request(
         timestamp: 1_700_000_000,
         chunk_hash: None,
         chunk_data: None,
+        chunk_nonce: None,
         chunk_list: None,
         sender_alias: Some("peer".to_string()),
+        translation: None,
+        logical: None,
+        content: None,
+        wire: crate::network::wire::WireVersion::CURRENT,
     }
 }
 
@@ -32,9 +37,9 @@ fn dm_text_maps_to_expected_db_shape() {
     let req = incoming_request(DirectMessageKind::Text, Some("hello"), None);
     let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
 
-    assert_eq!(db.content_type, "text");
-    assert_eq!(db.text_content.as_deref(), Some("hello"));
-    assert!(db.file_hash.is_none());
+    assert_eq!(db.content.content_type(), "text");
+    assert_eq!(db.content.text_content(), Some("hello"));
+    assert!(db.content.file_hash().is_none());
 }
 
 #[test]
@@ -42,9 +47,9 @@ fn dm_image_maps_to_expected_db_shape() {
     let req = incoming_request(DirectMessageKind::Image, None, Some("img-hash"));
     let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
 
-    assert_eq!(db.content_type, "image");
-    assert!(db.text_content.is_none());
-    assert_eq!(db.file_hash.as_deref(), Some("img-hash"));
+    assert_eq!(db.content.content_type(), "image");
+    assert!(db.content.text_content().is_none());
+    assert_eq!(db.content.file_hash(), Some("img-hash"));
 }
 
 #[test]
@@ -52,9 +57,9 @@ fn dm_sticker_maps_to_expected_db_shape() {
     let req = incoming_request(DirectMessageKind::Sticker, None, Some("sticker-hash"));
     let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
 
-    assert_eq!(db.content_type, "sticker");
-    assert!(db.text_content.is_none());
-    assert_eq!(db.file_hash.as_deref(), Some("sticker-hash"));
+    assert_eq!(db.content.content_type(), "sticker");
+    assert!(db.content.text_content().is_none());
+    assert_eq!(db.content.file_hash(), Some("sticker-hash"));
 }
 
 #[test]
@@ -66,9 +71,9 @@ fn dm_document_maps_to_expected_db_shape() {
     );
     let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
 
-    assert_eq!(db.content_type, "document");
-    assert_eq!(db.text_content.as_deref(), Some("spec.pdf"));
-    assert_eq!(db.file_hash.as_deref(), Some("doc-hash"));
+    assert_eq!(db.content.content_type(), "document");
+    assert_eq!(db.content.text_content(), Some("spec.pdf"));
+    assert_eq!(db.content.file_hash(), Some("doc-hash"));
 }
 
 #[test]
@@ -80,9 +85,9 @@ fn dm_video_maps_to_expected_db_shape() {
     );
     let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
 
-    assert_eq!(db.content_type, "video");
-    assert_eq!(db.text_content.as_deref(), Some("clip.mp4"));
-    assert_eq!(db.file_hash.as_deref(), Some("video-hash"));
+    assert_eq!(db.content.content_type(), "video");
+    assert_eq!(db.content.text_content(), Some("clip.mp4"));
+    assert_eq!(db.content.file_hash(), Some("video-hash"));
 }
 
 #[test]
@@ -94,9 +99,40 @@ fn dm_audio_maps_to_expected_db_shape() {
     );
     let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
 
-    assert_eq!(db.content_type, "audio");
-    assert_eq!(db.text_content.as_deref(), Some("note.m4a"));
-    assert_eq!(db.file_hash.as_deref(), Some("audio-hash"));
+    assert_eq!(db.content.content_type(), "audio");
+    assert_eq!(db.content.text_content(), Some("note.m4a"));
+    assert_eq!(db.content.file_hash(), Some("audio-hash"));
+}
+
+#[test]
+fn dm_typed_content_brings_metadata_only_when_it_agrees() {
+    use crate::chat::message::{ContentMetadata, MessageContent};
+
+    let content = |file_hash: &str| MessageContent::Photo {
+        file_hash: file_hash.to_string(),
+        caption: None,
+        metadata: ContentMetadata {
+            width: Some(640),
+            height: Some(480),
+            size_bytes: Some(1234),
+            thumbnail_hash: Some("senders-poster".to_string()),
+            ..Default::default()
+        },
+    };
+    let mut req = incoming_request(DirectMessageKind::Image, None, Some("img-hash"));
+    req.content = Some(content("img-hash"));
+    let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
+    let metadata: ContentMetadata =
+        serde_json::from_str(db.content_metadata.as_deref().expect("metadata")).expect("json");
+    assert_eq!(metadata.width, Some(640));
+    assert_eq!(metadata.height, Some(480));
+    // Computed locally once the file arrives.
+    assert_eq!(metadata.size_bytes, None);
+    assert_eq!(metadata.thumbnail_hash, None);
+
+    req.content = Some(content("other-hash"));
+    let db = build_incoming_dm_db_message(&req, "chat-a".to_string());
+    assert!(db.content_metadata.is_none());
 }
 
 #[test]
@@ -110,14 +146,18 @@ fn group_document_maps_to_expected_db_shape() {
         content_type: GroupContentType::Document,
         text_content: Some("brief.pdf".to_string()),
         file_hash: Some("doc-hash".to_string()),
+        signature: None,
+        logical: None,
+        bridged: None,
+        content: None,
     };
 
     let db = build_incoming_group_db_message(&envelope);
     assert_eq!(db.chat_id, envelope.group_id);
     assert_eq!(db.peer_id, "peer-2");
-    assert_eq!(db.content_type, "document");
-    assert_eq!(db.text_content.as_deref(), Some("brief.pdf"));
-    assert_eq!(db.file_hash.as_deref(), Some("doc-hash"));
+    assert_eq!(db.content.content_type(), "document");
+    assert_eq!(db.content.text_content(), Some("brief.pdf"));
+    assert_eq!(db.content.file_hash(), Some("doc-hash"));
 }
 
 #[test]
@@ -131,14 +171,18 @@ fn group_audio_maps_to_expected_db_shape() {
         content_type: GroupContentType::Audio,
         text_content: Some("voice-note.webm".to_string()),
         file_hash: Some("audio-hash".to_string()),
+        signature: None,
+        logical: None,
+        bridged: None,
+        content: None,
     };
 
     let db = build_incoming_group_db_message(&envelope);
     assert_eq!(db.chat_id, envelope.group_id);
     assert_eq!(db.peer_id, "peer-2");
-    assert_eq!(db.content_type, "audio");
-    assert_eq!(db.text_content.as_deref(), Some("voice-note.webm"));
-    assert_eq!(db.file_hash.as_deref(), Some("audio-hash"));
+    assert_eq!(db.content.content_type(), "audio");
+    assert_eq!(db.content.text_content(), Some("voice-note.webm"));
+    assert_eq!(db.content.file_hash(), Some("audio-hash"));
 }
 
 #[test]
@@ -294,8 +338,11 @@ fn quic_addresses_for_peer_filters_known_addresses_to_quic_only() {
     let quic_a: Multiaddr = "/ip4/10.0.0.5/udp/4242/quic-v1".parse().unwrap();
     let tcp: Multiaddr = "/ip4/10.0.0.5/tcp/4242".parse().unwrap();
     let quic_b: Multiaddr = "/ip6/::1/udp/5252/quic-v1".parse().unwrap();
-    let mut local_peers = HashMap::new();
-    local_peers.insert(peer, vec![quic_a.clone(), tcp, quic_b.clone()]);
+    let mut local_peers = crate::network::local_peers::LocalPeerTable::default();
+    let now = std::time::Instant::now();
+    for addr in [quic_a.clone(), tcp, quic_b.clone()] {
+        local_peers.observe(peer, addr, now);
+    }
 
     let addrs = quic_addresses_for_peer(&local_peers, &peer);
 
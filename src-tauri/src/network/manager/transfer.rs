@@ -1,4 +1,5 @@
 use super::*;
+use crate::network::chunk_crypto;
 use crate::network::direct_message::{ChunkInfo, DirectMessageKind, DirectMessageRequest};
 use base64::Engine;
 use std::path::{Path, PathBuf};
@@ -14,7 +15,7 @@ const TRANSFER_STATE_STALE_TTL: std::time::Duration = std::time::Duration::from_
 #[derive(Debug, Clone)]
 pub(super) struct TransferState {
     pub manifest_persisted: bool,
-    pub buffered_chunks: Vec<(String, String)>,
+    pub buffered_chunks: Vec<(String, ReceivedChunk)>,
     pub completion_emitted: bool,
     pub expected_chunks: usize,
     pub stored_chunk_results: usize,
@@ -34,6 +35,15 @@ impl Default for TransferState {
     }
 }
 
+/// Chunk payload as it arrived; sealed payloads are opened on a worker.
+#[derive(Debug, Clone)]
+pub(super) struct ReceivedChunk {
+    /// Base64 chunk bytes, or base64 ciphertext when `nonce` is set.
+    pub data: String,
+    pub nonce: Option<String>,
+    pub key: Option<[u8; 32]>,
+}
+
 #[derive(Debug)]
 pub(super) enum TransferTask {
     BuildFileMetadataResponse {
@@ -46,6 +56,7 @@ pub(super) enum TransferTask {
         request_id: String,
         file_hash: Option<String>,
         chunk_hash: String,
+        chunk_key: [u8; 32],
     },
     PersistChunkManifest {
         file_hash: String,
@@ -54,7 +65,7 @@ pub(super) enum TransferTask {
     StoreChunkAndCheckComplete {
         file_hash: String,
         chunk_hash: String,
-        chunk_b64: String,
+        chunk: ReceivedChunk,
     },
     Shutdown,
 }
@@ -73,6 +84,12 @@ pub(super) enum TransferResult {
         chunk_hash: String,
         chunk_size: usize,
     },
+    /// The chunk isn't in the file's manifest or doesn't match its hash.
+    ChunkRejected {
+        file_hash: String,
+        chunk_hash: String,
+        reason: String,
+    },
 }
 
 pub(super) fn start_transfer_workers(
@@ -138,6 +155,7 @@ pub(super) fn start_transfer_workers(
                     Ok(Ok(None)) => {}
                     Ok(Err(err)) => {
                         eprintln!("[ChunkTransfer] worker-{} task failed: {}", worker_id, err);
+                        crate::storage::metrics::record_error("transfer");
                     }
                     Err(join_err) => {
                         eprintln!(
@@ -191,8 +209,13 @@ fn process_transfer_task(
                 timestamp: unix_timestamp_secs(),
                 chunk_hash: None,
                 chunk_data: None,
+                chunk_nonce: None,
                 chunk_list: Some(chunks),
                 sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
             Ok(Some(TransferResult::SendDirectRequest {
@@ -205,8 +228,9 @@ fn process_transfer_task(
             request_id,
             file_hash,
             chunk_hash,
+            chunk_key,
         } => {
-            let chunk_path = chunks_dir().join(&chunk_hash);
+            let chunk_path = chunks_dir(app_handle).join(&chunk_hash);
             let chunk_data = match std::fs::read(&chunk_path) {
                 Ok(data) => data,
                 Err(err) => {
@@ -218,10 +242,10 @@ fn process_transfer_task(
                 }
             };
 
-            let chunk_b64 = base64::engine::general_purpose::STANDARD.encode(&chunk_data);
+            let (sealed_b64, nonce_b64) = chunk_crypto::seal_chunk(&chunk_key, &chunk_data)?;
 
             println!(
-                "[ChunkTransfer] 📦 Prepared chunk {} ({} bytes)",
+                "[ChunkTransfer] 📦 Prepared sealed chunk {} ({} bytes)",
                 chunk_hash,
                 chunk_data.len()
             );
@@ -234,9 +258,14 @@ fn process_transfer_task(
                 file_hash,
                 timestamp: unix_timestamp_secs(),
                 chunk_hash: Some(chunk_hash),
-                chunk_data: Some(chunk_b64),
+                chunk_data: Some(sealed_b64),
+                chunk_nonce: Some(nonce_b64),
                 chunk_list: None,
                 sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
             Ok(Some(TransferResult::SendDirectRequest {
@@ -253,13 +282,38 @@ fn process_transfer_task(
         TransferTask::StoreChunkAndCheckComplete {
             file_hash,
             chunk_hash,
-            chunk_b64,
+            chunk,
         } => {
-            let chunk_data = base64::engine::general_purpose::STANDARD
-                .decode(chunk_b64)
-                .map_err(|e| format!("Failed to decode chunk data: {}", e))?;
+            let chunk_data = match (chunk.nonce, chunk.key) {
+                (Some(nonce), Some(key)) => chunk_crypto::open_chunk(&key, &chunk.data, &nonce)?,
+                (Some(_), None) => {
+                    return Err(format!("No chunk key to open sealed chunk {}", chunk_hash));
+                }
+                // Peers predating chunk encryption still send plain base64.
+                (None, _) => base64::engine::general_purpose::STANDARD
+                    .decode(chunk.data)
+                    .map_err(|e| format!("Failed to decode chunk data: {}", e))?,
+            };
 
-            let chunk_size = store_chunk_file(&chunks_dir(), &chunk_hash, &chunk_data)?;
+            let listed = with_db_conn(app_handle, |conn| {
+                Ok(chunk_in_manifest(conn, &file_hash, &chunk_hash))
+            })?;
+            let reason = if !listed {
+                Some("not in the file manifest")
+            } else if crate::storage::object::sha256_hex(&chunk_data) != chunk_hash {
+                Some("hash mismatch")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Ok(Some(TransferResult::ChunkRejected {
+                    file_hash,
+                    chunk_hash,
+                    reason: reason.to_string(),
+                }));
+            }
+
+            let chunk_size = store_chunk_file(&chunks_dir(app_handle), &chunk_hash, &chunk_data)?;
 
             Ok(Some(TransferResult::ChunkStored {
                 file_hash,
@@ -283,10 +337,8 @@ fn with_db_conn<T>(
     op(&conn)
 }
 
-fn chunks_dir() -> PathBuf {
-    directories::ProjectDirs::from("io.github", "ata-sesli", "RChat")
-        .map(|p| p.data_dir().join("chunks"))
-        .unwrap_or_else(|| PathBuf::from("chunks"))
+pub(super) fn chunks_dir(app_handle: &AppHandle) -> PathBuf {
+    app_handle.state::<crate::AppState>().chunks_dir.clone()
 }
 
 fn unix_timestamp_secs() -> i64 {
@@ -296,7 +348,7 @@ fn unix_timestamp_secs() -> i64 {
         .as_secs() as i64
 }
 
-fn load_chunk_manifest(
+pub(super) fn load_chunk_manifest(
     conn: &rusqlite::Connection,
     file_hash: &str,
 ) -> Result<Vec<ChunkInfo>, String> {
@@ -323,7 +375,7 @@ fn load_chunk_manifest(
     Ok(chunks)
 }
 
-fn persist_chunk_manifest(
+pub(super) fn persist_chunk_manifest(
     conn: &rusqlite::Connection,
     file_hash: &str,
     chunks: &[ChunkInfo],
@@ -343,22 +395,32 @@ fn persist_chunk_manifest(
     Ok(())
 }
 
-fn store_chunk_file(
+fn chunk_in_manifest(conn: &rusqlite::Connection, file_hash: &str, chunk_hash: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM file_chunks WHERE file_hash = ?1 AND chunk_hash = ?2 LIMIT 1",
+        [file_hash, chunk_hash],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+pub(super) fn store_chunk_file(
     chunks_dir: &Path,
     chunk_hash: &str,
     chunk_data: &[u8],
 ) -> Result<usize, String> {
+    crate::storage::disk::ensure_media_writable()?;
     std::fs::create_dir_all(chunks_dir)
         .map_err(|e| format!("failed to create chunk dir {:?}: {}", chunks_dir, e))?;
 
     let chunk_path = chunks_dir.join(chunk_hash);
-    std::fs::write(&chunk_path, chunk_data)
+    crate::storage::recovery::write_atomic(&chunk_path, chunk_data)
         .map_err(|e| format!("failed to write chunk {}: {}", chunk_hash, e))?;
 
     Ok(chunk_data.len())
 }
 
-fn evaluate_file_completion(
+pub(super) fn evaluate_file_completion(
     conn: &rusqlite::Connection,
     chunks_dir: &Path,
     file_hash: &str,
@@ -417,6 +479,19 @@ impl NetworkManager {
             .retain(|_, state| now.duration_since(state.updated_at) < TRANSFER_STATE_STALE_TTL);
     }
 
+    pub(super) fn enforce_media_cache_cap(&self) {
+        match with_db_conn(&self.app_handle, |conn| {
+            crate::storage::quota::enforce_cache_cap(conn).map_err(|e| e.to_string())
+        }) {
+            Ok(report) if report.evicted_files > 0 => println!(
+                "[Storage] 🧹 Evicted {} cached file(s), freed {} bytes",
+                report.evicted_files, report.freed_bytes
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!("[Storage] ❌ Media cache eviction failed: {}", e),
+        }
+    }
+
     async fn enqueue_transfer_task(
         &mut self,
         task: TransferTask,
@@ -447,10 +522,7 @@ impl NetworkManager {
         match result {
             TransferResult::SendDirectRequest { peer, mut request } => {
                 request.sender_id = self.swarm.local_peer_id().to_string();
-                self.swarm
-                    .behaviour_mut()
-                    .direct_message
-                    .send_request(&peer, request);
+                self.send_direct_request(&peer, request);
             }
             TransferResult::ManifestPersisted { file_hash } => {
                 let buffered = {
@@ -459,13 +531,13 @@ impl NetworkManager {
                     std::mem::take(&mut state.buffered_chunks)
                 };
 
-                for (chunk_hash, chunk_b64) in buffered {
+                for (chunk_hash, chunk) in buffered {
                     let _ = self
                         .enqueue_transfer_task(
                             TransferTask::StoreChunkAndCheckComplete {
                                 file_hash: file_hash.clone(),
                                 chunk_hash,
-                                chunk_b64,
+                                chunk,
                             },
                             "flush_buffered_chunk",
                         )
@@ -481,6 +553,14 @@ impl NetworkManager {
                     "[ChunkTransfer] 💾 Stored chunk {} ({} bytes)",
                     chunk_hash, chunk_size
                 );
+                if let Some(download) = self.swarm_downloads.get_mut(&file_hash) {
+                    download.chunk_stored(&chunk_hash);
+                    if download.is_finished() {
+                        self.swarm_downloads.remove(&file_hash);
+                    } else {
+                        self.pump_swarm_download(&file_hash);
+                    }
+                }
                 let should_finalize = {
                     let state = self.touch_transfer_state(&file_hash);
                     state.stored_chunk_results = state.stored_chunk_results.saturating_add(1);
@@ -497,7 +577,7 @@ impl NetworkManager {
 
                 if should_finalize {
                     match with_db_conn(&self.app_handle, |conn| {
-                        evaluate_file_completion(conn, &chunks_dir(), &file_hash)
+                        evaluate_file_completion(conn, &chunks_dir(&self.app_handle), &file_hash)
                     }) {
                         Ok(true) => {
                             if let Some(state) = self.transfer_states.get_mut(&file_hash) {
@@ -508,6 +588,10 @@ impl NetworkManager {
                                 serde_json::json!({ "file_hash": file_hash }),
                             );
                             self.transfer_states.remove(&file_hash);
+                            crate::storage::metrics::record(
+                                crate::storage::metrics::TRANSFERS_COMPLETED,
+                            );
+                            self.on_avatar_transfer_complete(&file_hash);
                         }
                         Ok(false) => {
                             eprintln!(
@@ -524,6 +608,26 @@ impl NetworkManager {
                     }
                 }
             }
+            TransferResult::ChunkRejected {
+                file_hash,
+                chunk_hash,
+                reason,
+            } => {
+                eprintln!(
+                    "[ChunkTransfer] ❌ Rejected chunk {} of {}: {}",
+                    chunk_hash, file_hash, reason
+                );
+                if let Some(download) = self.swarm_downloads.get_mut(&file_hash) {
+                    if let Some(peer) = download.chunk_rejected(&chunk_hash) {
+                        eprintln!(
+                            "[SwarmDownload] 🚫 Dropped {} as a source for {}",
+                            crate::logging::peer(&peer),
+                            file_hash
+                        );
+                    }
+                    self.pump_swarm_download(&file_hash);
+                }
+            }
         }
     }
 
@@ -557,6 +661,14 @@ impl NetworkManager {
     ) {
         if let Some(ref chunk_hash) = request.chunk_hash {
             println!("[ChunkTransfer] 📦 Chunk request for: {}", chunk_hash);
+            let Some(chunk_key) = self.chunk_keyring.key_for(&peer) else {
+                eprintln!(
+                    "[ChunkTransfer] ❌ No chunk key for {}; not sending {} unencrypted",
+                    crate::logging::peer(&peer),
+                    chunk_hash
+                );
+                return;
+            };
             if let Err(e) = self
                 .enqueue_transfer_task(
                     TransferTask::BuildChunkResponse {
@@ -564,6 +676,7 @@ impl NetworkManager {
                         request_id: request.id.clone(),
                         file_hash: request.file_hash.clone(),
                         chunk_hash: chunk_hash.clone(),
+                        chunk_key,
                     },
                     "chunk_request",
                 )
@@ -586,6 +699,42 @@ impl NetworkManager {
                 file_hash
             );
 
+            if let Some(download) = self.swarm_downloads.get_mut(file_hash) {
+                let first = !download.has_manifest();
+                if !download.add_source(peer, chunks) {
+                    return;
+                }
+                println!(
+                    "[SwarmDownload] ➕ {} is a source for {} ({} sources)",
+                    crate::logging::peer(&peer),
+                    file_hash,
+                    download.sources().len()
+                );
+                if !first {
+                    self.pump_swarm_download(file_hash);
+                    return;
+                }
+            }
+
+            let total_size: i64 = chunks.iter().map(|chunk| chunk.chunk_size).sum();
+            if total_size >= crate::storage::bandwidth::LARGE_TRANSFER_BYTES
+                && crate::storage::bandwidth::cap_exceeded()
+            {
+                println!(
+                    "[ChunkTransfer] ⏸️ Monthly bandwidth cap reached, pausing {} ({} bytes)",
+                    file_hash, total_size
+                );
+                // Resumes from this one peer.
+                self.swarm_downloads.remove(file_hash);
+                self.paused_downloads
+                    .insert(file_hash.clone(), (peer, request.clone()));
+                let _ = self.app_handle.emit(
+                    "file-transfer-paused",
+                    serde_json::json!({ "file_hash": file_hash, "reason": "bandwidth_cap" }),
+                );
+                return;
+            }
+
             {
                 let state = self.touch_transfer_state(file_hash);
                 state.manifest_persisted = false;
@@ -594,6 +743,7 @@ impl NetworkManager {
                 state.stored_chunk_results = 0;
                 state.buffered_chunks.clear();
             }
+            crate::storage::metrics::record(crate::storage::metrics::TRANSFERS_STARTED);
 
             if let Err(e) = self
                 .enqueue_transfer_task(
@@ -609,59 +759,247 @@ impl NetworkManager {
                 return;
             }
 
-            for chunk_info in chunks {
-                let chunk_req = DirectMessageRequest {
-                    id: format!("chunk-req-{}-{}", file_hash, chunk_info.chunk_order),
-                    sender_id: self.swarm.local_peer_id().to_string(),
-                    msg_type: DirectMessageKind::ChunkRequest,
-                    text_content: None,
-                    file_hash: Some(file_hash.clone()),
-                    timestamp: unix_timestamp_secs(),
-                    chunk_hash: Some(chunk_info.chunk_hash.clone()),
-                    chunk_data: None,
-                    chunk_list: None,
-                    sender_alias: None,
-                };
+            if self.swarm_downloads.contains_key(file_hash) {
+                self.pump_swarm_download(file_hash);
+            } else if !self.start_blob_download(peer, file_hash, chunks, total_size) {
+                self.request_chunks(peer, file_hash, chunks);
+            }
+        }
+    }
+
+    /// Ask `peer` for each of `chunks` with a `ChunkRequest`.
+    pub(super) fn request_chunks(&mut self, peer: PeerId, file_hash: &str, chunks: &[ChunkInfo]) {
+        for chunk_info in chunks {
+            let chunk_req = DirectMessageRequest {
+                id: format!("chunk-req-{}-{}", file_hash, chunk_info.chunk_order),
+                sender_id: self.swarm.local_peer_id().to_string(),
+                msg_type: DirectMessageKind::ChunkRequest,
+                text_content: None,
+                file_hash: Some(file_hash.to_string()),
+                timestamp: unix_timestamp_secs(),
+                chunk_hash: Some(chunk_info.chunk_hash.clone()),
+                chunk_data: None,
+                chunk_nonce: None,
+                chunk_list: None,
+                sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
+            };
+
+            crate::storage::bandwidth::record_sent(
+                &peer.to_string(),
+                crate::storage::bandwidth::FILE_TRANSFER,
+                chunk_req.approx_wire_len(),
+            );
+            self.swarm
+                .behaviour_mut()
+                .direct_message
+                .send_request(&peer, chunk_req);
 
-                self.swarm
-                    .behaviour_mut()
-                    .direct_message
-                    .send_request(&peer, chunk_req);
+            println!(
+                "[ChunkTransfer] 📤 Requested chunk #{} from {}",
+                chunk_info.chunk_order + 1,
+                crate::logging::peer(&peer)
+            );
+        }
+    }
+
+    /// Ask `peer` for the chunk manifest of `file_hash`.
+    pub(super) fn request_file_metadata(&mut self, peer: PeerId, file_hash: &str) {
+        let metadata_req = DirectMessageRequest {
+            id: format!("meta-req-{}", file_hash),
+            sender_id: self.swarm.local_peer_id().to_string(),
+            msg_type: DirectMessageKind::FileMetadataRequest,
+            text_content: None,
+            file_hash: Some(file_hash.to_string()),
+            timestamp: unix_timestamp_secs(),
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        };
+        self.swarm
+            .behaviour_mut()
+            .direct_message
+            .send_request(&peer, metadata_req);
+    }
+
+    /// Fetch a group attachment from its sender and every other connected
+    /// member that has it. Returns false if the sender is the only candidate.
+    pub(super) fn start_swarm_download(
+        &mut self,
+        file_hash: &str,
+        sender: PeerId,
+        group_id: &str,
+    ) -> bool {
+        if self
+            .swarm_downloads
+            .get(file_hash)
+            .is_some_and(|download| download.has_manifest())
+        {
+            return true;
+        }
+        let members = with_db_conn(&self.app_handle, |conn| {
+            crate::storage::db::get_chat_members(conn, group_id).map_err(|e| e.to_string())
+        })
+        .unwrap_or_default();
+        let local_peer_id = *self.swarm.local_peer_id();
+        let others: Vec<PeerId> = members
+            .iter()
+            .filter_map(|member| member.peer_id.parse::<PeerId>().ok())
+            .filter(|peer| *peer != sender && *peer != local_peer_id)
+            .filter(|peer| {
+                self.swarm.is_connected(peer) && self.chunk_keyring.key_for(peer).is_some()
+            })
+            .collect();
+        if others.is_empty() {
+            return false;
+        }
+
+        println!(
+            "[SwarmDownload] 🐝 Asking {} peers for {}",
+            others.len() + 1,
+            file_hash
+        );
+        self.swarm_downloads
+            .insert(file_hash.to_string(), Default::default());
+        for peer in std::iter::once(sender).chain(others) {
+            self.request_file_metadata(peer, file_hash);
+        }
+        true
+    }
+
+    /// Request queued chunks of a swarm download from sources with free slots.
+    fn pump_swarm_download(&mut self, file_hash: &str) {
+        let Some(download) = self.swarm_downloads.get_mut(file_hash) else {
+            return;
+        };
+        for (peer, chunk) in download.next_requests(std::time::Instant::now()) {
+            self.request_chunks(peer, file_hash, std::slice::from_ref(&chunk));
+        }
+    }
 
+    /// Retry timed-out chunks, drop disconnected sources and give up on
+    /// downloads nobody can serve anymore.
+    pub(super) fn tick_swarm_downloads(&mut self) {
+        let now = std::time::Instant::now();
+        let file_hashes: Vec<String> = self.swarm_downloads.keys().cloned().collect();
+        for file_hash in file_hashes {
+            let Some(download) = self.swarm_downloads.get_mut(&file_hash) else {
+                continue;
+            };
+            let gone: Vec<PeerId> = download
+                .sources()
+                .iter()
+                .filter(|peer| !self.swarm.is_connected(peer))
+                .copied()
+                .collect();
+            for peer in gone {
+                download.remove_source(&peer);
+            }
+            let expired = download.expire(now);
+            if expired > 0 {
                 println!(
-                    "[ChunkTransfer] 📤 Requested chunk {}/{}",
-                    chunk_info.chunk_order + 1,
-                    chunks.len()
+                    "[SwarmDownload] ⏱️ Retrying {} chunks of {}",
+                    expired, file_hash
+                );
+            }
+            if download.is_stalled() {
+                eprintln!(
+                    "[SwarmDownload] ❌ No sources left for {}, giving up",
+                    file_hash
+                );
+                self.swarm_downloads.remove(&file_hash);
+                let _ = self.app_handle.emit(
+                    "file-transfer-failed",
+                    serde_json::json!({ "file_hash": file_hash, "reason": "no_sources" }),
                 );
+                continue;
             }
+            self.pump_swarm_download(&file_hash);
         }
     }
 
-    pub(super) async fn handle_chunk_response(&mut self, request: &DirectMessageRequest) {
+    /// Start downloads held back by the bandwidth cap once it no longer applies.
+    pub(super) async fn resume_paused_downloads(&mut self) {
+        if self.paused_downloads.is_empty() || crate::storage::bandwidth::cap_exceeded() {
+            return;
+        }
+        for (file_hash, (peer, request)) in std::mem::take(&mut self.paused_downloads) {
+            println!("[ChunkTransfer] ▶️ Resuming {}", file_hash);
+            let _ = self.app_handle.emit(
+                "file-transfer-resumed",
+                serde_json::json!({ "file_hash": file_hash }),
+            );
+            self.handle_file_metadata_response(peer, &request).await;
+        }
+    }
+
+    pub(super) async fn handle_chunk_response(
+        &mut self,
+        peer: PeerId,
+        request: &DirectMessageRequest,
+    ) {
         if let (Some(ref file_hash), Some(ref chunk_hash), Some(ref chunk_b64)) =
             (&request.file_hash, &request.chunk_hash, &request.chunk_data)
         {
-            let state = self.touch_transfer_state(file_hash);
-            if !state.manifest_persisted {
-                state
-                    .buffered_chunks
-                    .push((chunk_hash.clone(), chunk_b64.clone()));
+            if request.chunk_nonce.is_none()
+                && self.peer_supports(&peer, crate::network::capabilities::Capability::E2eeV2)
+            {
+                eprintln!(
+                    "[ChunkTransfer] ⚠️ Dropping unsealed chunk {} from {}, which seals chunks",
+                    chunk_hash,
+                    crate::logging::peer(&peer)
+                );
                 return;
             }
+            let chunk = ReceivedChunk {
+                data: chunk_b64.clone(),
+                nonce: request.chunk_nonce.clone(),
+                key: if request.chunk_nonce.is_some() {
+                    self.chunk_keyring.key_for(&peer)
+                } else {
+                    None
+                },
+            };
+            self.accept_chunk(file_hash, chunk_hash, chunk, "chunk_response")
+                .await;
+        }
+    }
 
-            if let Err(e) = self
-                .enqueue_transfer_task(
-                    TransferTask::StoreChunkAndCheckComplete {
-                        file_hash: file_hash.clone(),
-                        chunk_hash: chunk_hash.clone(),
-                        chunk_b64: chunk_b64.clone(),
-                    },
-                    "chunk_response",
-                )
-                .await
-            {
-                eprintln!("[ChunkTransfer] ❌ {}", e);
-            }
+    /// Store a received chunk, or buffer it until the manifest is persisted.
+    pub(super) async fn accept_chunk(
+        &mut self,
+        file_hash: &str,
+        chunk_hash: &str,
+        chunk: ReceivedChunk,
+        source: &'static str,
+    ) {
+        let state = self.touch_transfer_state(file_hash);
+        if !state.manifest_persisted {
+            state.buffered_chunks.push((chunk_hash.to_string(), chunk));
+            return;
+        }
+
+        if let Err(e) = self
+            .enqueue_transfer_task(
+                TransferTask::StoreChunkAndCheckComplete {
+                    file_hash: file_hash.to_string(),
+                    chunk_hash: chunk_hash.to_string(),
+                    chunk,
+                },
+                source,
+            )
+            .await
+        {
+            eprintln!("[ChunkTransfer] ❌ {}", e);
         }
     }
 
@@ -821,8 +1159,16 @@ mod tests {
     fn transfer_state_buffers_until_manifest() {
         let mut state = TransferState::default();
         assert!(!state.manifest_persisted);
-        state.buffered_chunks.push(("h1".into(), "d1".into()));
-        state.buffered_chunks.push(("h2".into(), "d2".into()));
+        for (hash, data) in [("h1", "d1"), ("h2", "d2")] {
+            state.buffered_chunks.push((
+                hash.into(),
+                ReceivedChunk {
+                    data: data.into(),
+                    nonce: None,
+                    key: None,
+                },
+            ));
+        }
 
         state.manifest_persisted = true;
         let flushed = std::mem::take(&mut state.buffered_chunks);
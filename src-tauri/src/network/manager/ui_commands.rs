@@ -24,6 +24,12 @@ impl NetworkManager {
                 password,
                 my_username,
             } => self.register_shadow_poll(&invitee, &password, &my_username),
+            NetworkCommand::CancelShadowPoll { invitee } => self.cancel_shadow_poll(&invitee),
+            NetworkCommand::AcceptFriendRequest {
+                github_username,
+                peer_id,
+            } => self.accept_github_peer(github_username, peer_id).await,
+            NetworkCommand::ReloadIdentityKeys => self.refresh_peer_mapping_cache().await,
             NetworkCommand::RegisterTemporarySession {
                 chat_id,
                 peer_id,
@@ -37,23 +43,53 @@ impl NetworkManager {
             }
             NetworkCommand::SubscribeGroup { group_id } => self.subscribe_group(&group_id),
             NetworkCommand::UnsubscribeGroup { group_id } => self.unsubscribe_group(&group_id),
+            NetworkCommand::RekeyGroup { group_id } => self.rekey_group(&group_id),
+            NetworkCommand::PublishGroupControl { group_id, action } => {
+                self.publish_group_control(&group_id, action);
+            }
+            NetworkCommand::CreateGroupInvite { invite, reply } => {
+                let _ = reply.send(self.create_group_invite(invite));
+            }
+            NetworkCommand::JoinGroup { invite } => self.join_group(invite),
+            NetworkCommand::RequestGroupHistory { group_id, peer_id } => {
+                self.request_group_history(&group_id, peer_id);
+            }
             NetworkCommand::PublishGroup { mut envelope } => {
                 self.publish_group_message(&mut envelope);
             }
+            NetworkCommand::SendGroupTyping {
+                group_id,
+                sender_alias,
+                typing,
+            } => self.send_group_typing(group_id, sender_alias, typing),
             NetworkCommand::SendDirectText {
                 target_peer_id,
                 msg_id,
                 timestamp,
                 sender_alias,
                 content,
+                translation,
             } => {
-                self.send_direct_text(target_peer_id, msg_id, timestamp, sender_alias, content)
-                    .await;
+                self.send_direct_text(
+                    target_peer_id,
+                    msg_id,
+                    timestamp,
+                    sender_alias,
+                    content,
+                    translation,
+                )
+                .await;
             }
             NetworkCommand::SendReadReceipt {
                 target_peer_id,
                 msg_ids,
             } => self.send_read_receipt(target_peer_id, msg_ids).await,
+            NetworkCommand::BroadcastIdentity => self.broadcast_identity_update().await,
+            NetworkCommand::UpdateMdnsAlias { alias } => {
+                if let Some(handle) = &self.mdns_handle {
+                    handle.update_alias(alias);
+                }
+            }
             NetworkCommand::SendDirectMedia {
                 kind,
                 target_peer_id,
@@ -72,6 +108,21 @@ impl NetworkManager {
                 )
                 .await;
             }
+            NetworkCommand::FetchFile { peer_id, file_hash } => match peer_id.parse::<PeerId>() {
+                Ok(peer) => {
+                    println!(
+                        "[ChunkTransfer] 📤 Fetching {} from {}",
+                        file_hash,
+                        crate::logging::peer(&peer)
+                    );
+                    self.request_file_metadata(peer, &file_hash);
+                }
+                Err(e) => eprintln!(
+                    "[ChunkTransfer] ❌ Invalid peer id {}: {}",
+                    crate::logging::peer(&peer_id),
+                    e
+                ),
+            },
             NetworkCommand::StartVoiceCall { peer_id } => {
                 self.handle_start_voice_call(peer_id).await;
             }
@@ -171,6 +222,21 @@ impl NetworkManager {
             NetworkCommand::EndScreenBroadcast { session_id } => {
                 self.handle_end_screen_broadcast(session_id).await;
             }
+            NetworkCommand::GetLocalPeers { reply } => {
+                let _ = reply.send(self.get_local_peers());
+            }
+            NetworkCommand::GetConnectionPath { peer_id, reply } => {
+                let _ = reply.send(self.connection_path_report(&peer_id));
+            }
+            NetworkCommand::TestPeerConnection { peer_id, reply } => {
+                self.start_connection_test(peer_id, reply).await;
+            }
+            NetworkCommand::GetPeerCapabilities { peer_id, reply } => {
+                let _ = reply.send(self.peer_capability_list(&peer_id));
+            }
+            NetworkCommand::GetPeerQuality { peer_id, reply } => {
+                let _ = reply.send(self.quality_registry.report(&peer_id));
+            }
         }
     }
 }
@@ -50,7 +50,10 @@ impl NetworkManager {
 
     /// Handle a connection request from UI (user pressed Connect on a peer)
     pub(crate) async fn handle_connection_request(&mut self, peer_id_str: &str) {
-        println!("[Handshake] User requested connection to: {}", peer_id_str);
+        println!(
+            "[Handshake] User requested connection to: {}",
+            crate::logging::peer(&peer_id_str)
+        );
 
         let peer_id = if let Some(p) = self.resolve_peer_id(peer_id_str, "Handshake").await {
             p
@@ -60,22 +63,51 @@ impl NetworkManager {
 
         let already_requested_us = self.incoming_requests.contains(&peer_id);
         if already_requested_us {
-            println!("[Handshake] 🤝 Mutual handshake complete with {}!", peer_id);
+            println!(
+                "[Handshake] 🤝 Mutual handshake complete with {}!",
+                crate::logging::peer(&peer_id)
+            );
             self.complete_handshake(peer_id);
         } else {
-            self.pending_requests.insert(peer_id);
-            println!("[Handshake] ⏳ Waiting for {} to accept...", peer_id);
+            self.track_outgoing_request(peer_id);
+            println!(
+                "[Handshake] ⏳ Waiting for {} to accept...",
+                crate::logging::peer(&peer_id)
+            );
             let _ = self.app_handle.emit("connection-waiting", peer_id_str);
         }
 
-        let envelope = crate::network::gossip::ControlEnvelope::ConnectionRequest {
-            from_peer_id: self.swarm.local_peer_id().to_string(),
-            to_peer_id: peer_id.to_string(),
+        let timestamp = now_unix_secs();
+        let signed =
+            match crate::network::connection_request::sign(&self.local_key, &peer_id, timestamp) {
+                Ok(signed) => signed,
+                Err(e) => {
+                    eprintln!("[Handshake] ❌ {}", e);
+                    return;
+                }
+            };
+        let Ok(text_content) = serde_json::to_string(&signed) else {
+            return;
         };
-        if let Ok(payload) = serde_json::to_vec(&envelope) {
-            let topic = crate::network::gossip::control_topic();
-            let _ = self.swarm.behaviour_mut().gossipsub.publish(topic, payload);
-        }
+        use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+        let request = DirectMessageRequest {
+            id: format!("connect-{}", timestamp),
+            sender_id: signed.from_peer_id,
+            msg_type: DirectMessageKind::ConnectionRequest,
+            text_content: Some(text_content),
+            file_hash: None,
+            timestamp,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        };
+        self.send_direct_request(&peer_id, request);
     }
 
     pub(super) async fn handle_drop_connection(&mut self, peer_id_str: &str) {
@@ -83,9 +115,17 @@ impl NetworkManager {
             return;
         };
 
+        self.reconnect_scheduler.suppress(peer_id);
         match self.swarm.disconnect_peer_id(peer_id) {
-            Ok(()) => println!("[Connection] 🔌 Disconnect requested for {}", peer_id),
-            Err(e) => eprintln!("[Connection] ❌ Failed to disconnect {}: {:?}", peer_id, e),
+            Ok(()) => println!(
+                "[Connection] 🔌 Disconnect requested for {}",
+                crate::logging::peer(&peer_id)
+            ),
+            Err(e) => eprintln!(
+                "[Connection] ❌ Failed to disconnect {}: {:?}",
+                crate::logging::peer(&peer_id),
+                e
+            ),
         }
     }
 
@@ -93,19 +133,19 @@ impl NetworkManager {
     pub(crate) fn handle_incoming_connection_request(&mut self, from_peer_id: PeerId) {
         println!(
             "[Handshake] Received connection request from: {}",
-            from_peer_id
+            crate::logging::peer(&from_peer_id)
         );
 
         if self.pending_requests.contains(&from_peer_id) {
             println!(
                 "[Handshake] 🤝 Mutual handshake complete with {}!",
-                from_peer_id
+                crate::logging::peer(&from_peer_id)
             );
             self.complete_handshake(from_peer_id);
             return;
         }
 
-        self.incoming_requests.insert(from_peer_id);
+        self.track_incoming_request(from_peer_id);
 
         let _ = self
             .app_handle
@@ -114,8 +154,7 @@ impl NetworkManager {
 
     /// Complete the handshake - both sides have agreed
     fn complete_handshake(&mut self, peer_id: PeerId) {
-        self.pending_requests.remove(&peer_id);
-        self.incoming_requests.remove(&peer_id);
+        self.forget_handshake(&peer_id);
         self.remember_trusted_peer_id(peer_id);
 
         use tauri::Manager;
@@ -126,7 +165,10 @@ impl NetworkManager {
             {
                 eprintln!("[Handshake] Failed to save peer: {}", e);
             } else {
-                println!("[Handshake] ✅ {} saved to peers table!", peer_id);
+                println!(
+                    "[Handshake] ✅ {} saved to peers table!",
+                    crate::logging::peer(&peer_id)
+                );
             }
         }
 
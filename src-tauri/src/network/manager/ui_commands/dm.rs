@@ -9,16 +9,17 @@ impl NetworkManager {
         timestamp: i64,
         sender_alias: Option<String>,
         content: String,
+        translation: Option<crate::network::direct_message::MessageTranslation>,
     ) {
         println!(
-            "[DM] 📤 Sending direct message to {} (alias: {}): {}",
-            target_peer_id,
-            sender_alias.as_deref().unwrap_or_default(),
-            content
+            "[DM] 📤 Sending direct message {} to {}",
+            msg_id,
+            crate::logging::peer(&target_peer_id)
         );
 
         if let Some(peer_id) = self.resolve_peer_id(&target_peer_id, "DM").await {
             use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+            let logical = self.stored_logical(&msg_id);
             let request = DirectMessageRequest {
                 id: msg_id,
                 sender_id: self.swarm.local_peer_id().to_string(),
@@ -28,25 +29,34 @@ impl NetworkManager {
                 timestamp,
                 chunk_hash: None,
                 chunk_data: None,
+                chunk_nonce: None,
                 chunk_list: None,
                 sender_alias,
+                translation,
+                logical,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
-            self.swarm
-                .behaviour_mut()
-                .direct_message
-                .send_request(&peer_id, request);
-            println!("[DM] ✅ Request sent to {}", peer_id);
+            self.send_direct_request(&peer_id, request);
+            println!("[DM] ✅ Request sent to {}", crate::logging::peer(&peer_id));
         }
     }
 
     pub(super) async fn send_read_receipt(&mut self, target_peer_id: String, msg_ids: Vec<String>) {
         println!(
             "[READ_RECEIPT] 📤 Sending read receipt to {}",
-            target_peer_id
+            crate::logging::peer(&target_peer_id)
         );
 
         if let Some(peer_id) = self.resolve_peer_id(&target_peer_id, "READ_RECEIPT").await {
+            if !self.peer_supports(&peer_id, crate::network::capabilities::Capability::Receipts) {
+                println!(
+                    "[READ_RECEIPT] ⏭️ {} doesn't take read receipts",
+                    crate::logging::peer(&peer_id)
+                );
+                return;
+            }
             use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
             let request = DirectMessageRequest {
                 id: format!(
@@ -66,15 +76,23 @@ impl NetworkManager {
                     .as_secs() as i64,
                 chunk_hash: None,
                 chunk_data: None,
+                chunk_nonce: None,
                 chunk_list: None,
                 sender_alias: None,
+                translation: None,
+                logical: None,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
             self.swarm
                 .behaviour_mut()
                 .direct_message
                 .send_request(&peer_id, request);
-            println!("[READ_RECEIPT] ✅ Sent to {}", peer_id);
+            println!(
+                "[READ_RECEIPT] ✅ Sent to {}",
+                crate::logging::peer(&peer_id)
+            );
         }
     }
 
@@ -100,7 +118,7 @@ impl NetworkManager {
             context,
             context.to_ascii_lowercase(),
             file_hash,
-            target_peer_id
+            crate::logging::peer(&target_peer_id)
         );
 
         if let Some(peer_id) = self.resolve_peer_id(&target_peer_id, context).await {
@@ -122,6 +140,7 @@ impl NetworkManager {
                 ),
             };
 
+            let logical = self.stored_logical(&msg_id);
             let request = DirectMessageRequest {
                 id: msg_id,
                 sender_id: self.swarm.local_peer_id().to_string(),
@@ -131,15 +150,21 @@ impl NetworkManager {
                 timestamp,
                 chunk_hash: None,
                 chunk_data: None,
+                chunk_nonce: None,
                 chunk_list: None,
                 sender_alias: None,
+                translation: None,
+                logical,
+                content: None,
+                wire: crate::network::wire::WireVersion::CURRENT,
             };
 
-            self.swarm
-                .behaviour_mut()
-                .direct_message
-                .send_request(&peer_id, request);
-            println!("[{}] ✅ Direct request sent to {}", context, peer_id);
+            self.send_direct_request(&peer_id, request);
+            println!(
+                "[{}] ✅ Direct request sent to {}",
+                context,
+                crate::logging::peer(&peer_id)
+            );
         }
     }
 }
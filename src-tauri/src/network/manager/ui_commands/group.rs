@@ -7,22 +7,95 @@ impl NetworkManager {
     ) {
         if let Some(topic) = crate::network::gossip::topic_for_group_id(&envelope.group_id) {
             envelope.sender_id = self.swarm.local_peer_id().to_string();
+            if envelope.logical.is_none() {
+                envelope.logical = self.stored_logical(&envelope.id);
+            }
+            if envelope.content.is_none() {
+                envelope.content = self.stored_content(
+                    &envelope.id,
+                    envelope.content_type.as_str(),
+                    envelope.text_content.as_deref(),
+                    envelope.file_hash.as_deref(),
+                );
+            }
+            if let Some(key) = &self.identity_signing_key {
+                envelope.sign(key);
+            }
 
-            let payload = match serde_json::to_vec(envelope) {
+            let payload = match crate::network::wire::encode(
+                crate::network::wire::kind::GROUP_MESSAGE,
+                envelope,
+            ) {
                 Ok(v) => v,
                 Err(e) => {
                     eprintln!("[Group] ❌ Failed to encode publish envelope: {}", e);
                     return;
                 }
             };
-            let _ = self.swarm.behaviour_mut().gossipsub.subscribe(&topic);
+            if let Ok(true) = self.swarm.behaviour_mut().gossipsub.subscribe(&topic) {
+                self.subscribe_group_activity(&envelope.group_id);
+            }
             self.subscribed_group_ids.insert(envelope.group_id.clone());
-            match self.swarm.behaviour_mut().gossipsub.publish(topic, payload) {
+            // The message ends our typing; the next keystroke announces again.
+            self.typing_sent_at.remove(&envelope.group_id);
+
+            let payload = match self.seal_group_payload(&envelope.group_id, payload) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    eprintln!("[GroupKey] ❌ Not publishing {}: {}", envelope.id, e);
+                    self.set_outgoing_status(&envelope.id, "failed");
+                    return;
+                }
+            };
+            self.publish_on_group_topic(&topic, &envelope.id, payload);
+        } else {
+            eprintln!("[Group] ❌ Invalid group id: {}", envelope.group_id);
+        }
+    }
+
+    /// Publish `payload` on `topic`, in fragments if it is too large.
+    pub(super) fn publish_on_group_topic(
+        &mut self,
+        topic: &libp2p::gossipsub::IdentTopic,
+        id: &str,
+        payload: Vec<u8>,
+    ) {
+        let payloads = if crate::network::fragment::needs_fragmenting(&payload) {
+            let Some(fragments) = crate::network::fragment::split(id, &payload) else {
+                eprintln!(
+                    "[Group] ❌ Envelope {} is too large to publish ({} bytes)",
+                    id,
+                    payload.len()
+                );
+                return;
+            };
+            println!(
+                "[Fragment] 📤 Publishing {} as {} fragments",
+                id,
+                fragments.len()
+            );
+            fragments
+                .iter()
+                .filter_map(|piece| serde_json::to_vec(piece).ok())
+                .collect()
+        } else {
+            vec![payload]
+        };
+        for payload in payloads {
+            crate::storage::bandwidth::record_sent(
+                crate::storage::bandwidth::ANY_PEER,
+                crate::storage::bandwidth::GOSSIP,
+                payload.len(),
+            );
+            match self
+                .swarm
+                .behaviour_mut()
+                .gossipsub
+                .publish(topic.clone(), payload)
+            {
                 Ok(msg_id) => println!("[Group] ✅ Published group message {:?}", msg_id),
                 Err(e) => eprintln!("[Group] ❌ Publish failed: {:?}", e),
             }
-        } else {
-            eprintln!("[Group] ❌ Invalid group id: {}", envelope.group_id);
         }
     }
 
@@ -37,6 +110,7 @@ impl NetworkManager {
         if let Some(topic) = crate::network::gossip::topic_for_group_id(group_id) {
             match self.swarm.behaviour_mut().gossipsub.subscribe(&topic) {
                 Ok(_) => {
+                    self.subscribe_group_activity(group_id);
                     self.subscribed_group_ids.insert(group_id.to_string());
                     println!("[Group] ✅ Subscribed {}", group_id);
                 }
@@ -52,6 +126,7 @@ impl NetworkManager {
         if let Some(topic) = crate::network::gossip::topic_for_group_id(group_id) {
             if self.swarm.behaviour_mut().gossipsub.unsubscribe(&topic) {
                 self.subscribed_group_ids.remove(group_id);
+                self.unsubscribe_group_activity(group_id);
                 println!("[Group] ✅ Unsubscribed {}", group_id);
             } else {
                 eprintln!("[Group] ❌ Failed to unsubscribe {}", group_id);
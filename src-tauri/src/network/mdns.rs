@@ -1,9 +1,19 @@
+//! LAN discovery over mDNS (`_rchat._udp`).
+//!
+//! What we advertise depends on [`MdnsPrivacy`]: `Full` puts the peer ID and
+//! alias in the TXT record, `Off` only browses, and `Anonymous` publishes a
+//! token that changes every [`TOKEN_EPOCH_SECS`]. The token is a keyed hash of
+//! the epoch under our discovery key, which friends receive with identity
+//! updates, so only they can map it back to us.
+
+use crate::storage::config::MdnsPrivacy;
 use anyhow::{anyhow, Result};
 use libp2p::PeerId;
-use local_ip_address::local_ip;
+use local_ip_address::{local_ip, local_ipv6};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::any::Any;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Duration;
@@ -15,6 +25,88 @@ static MDNS_INITIALIZED: AtomicBool = AtomicBool::new(false);
 /// When true, use fast requery interval (5s) - for active discovery mode
 static FAST_DISCOVERY: AtomicBool = AtomicBool::new(false);
 
+/// Advertisement level the user wants; the manager restarts the service when
+/// it differs from the running one.
+static PRIVACY: AtomicU8 = AtomicU8::new(MdnsPrivacy::Full as u8);
+
+/// Anonymous tokens rotate this often.
+pub const TOKEN_EPOCH_SECS: u64 = 15 * 60;
+const TOKEN_HEX_CHARS: usize = 32;
+
+pub fn set_privacy(level: MdnsPrivacy) {
+    PRIVACY.store(level as u8, Ordering::SeqCst);
+}
+
+pub fn privacy() -> MdnsPrivacy {
+    match PRIVACY.load(Ordering::SeqCst) {
+        x if x == MdnsPrivacy::Off as u8 => MdnsPrivacy::Off,
+        x if x == MdnsPrivacy::Anonymous as u8 => MdnsPrivacy::Anonymous,
+        _ => MdnsPrivacy::Full,
+    }
+}
+
+/// What the registration thread puts on the LAN.
+#[derive(Clone)]
+pub enum Advertisement {
+    Off,
+    Anonymous { key: [u8; 32] },
+    Full { alias: Option<String> },
+}
+
+impl Advertisement {
+    pub fn level(&self) -> MdnsPrivacy {
+        match self {
+            Self::Off => MdnsPrivacy::Off,
+            Self::Anonymous { .. } => MdnsPrivacy::Anonymous,
+            Self::Full { .. } => MdnsPrivacy::Full,
+        }
+    }
+}
+
+/// An anonymous advertisement as seen by the browser.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiscoveryToken {
+    pub epoch: u64,
+    pub token: String,
+}
+
+pub fn current_epoch() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() / TOKEN_EPOCH_SECS)
+        .unwrap_or(0)
+}
+
+pub fn discovery_token(key: &[u8; 32], epoch: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rchat-mdns-token-v1");
+    hasher.update(key);
+    hasher.update(epoch.to_be_bytes());
+    let mut token = hex::encode(hasher.finalize());
+    token.truncate(TOKEN_HEX_CHARS);
+    token
+}
+
+pub fn decode_discovery_key(b64: &str) -> Option<[u8; 32]> {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+    BASE64.decode(b64.trim()).ok()?.try_into().ok()
+}
+
+/// The peer whose key produced `token`. Tokens from the previous or next
+/// epoch are accepted to tolerate clock skew at the boundary.
+pub fn resolve_token(
+    token: &DiscoveryToken,
+    keys: &[(String, [u8; 32])],
+    now_epoch: u64,
+) -> Option<String> {
+    if token.epoch.abs_diff(now_epoch) > 1 {
+        return None;
+    }
+    keys.iter()
+        .find(|(_, key)| discovery_token(key, token.epoch) == token.token)
+        .map(|(peer_id, _)| peer_id.clone())
+}
+
 /// Enable fast discovery mode (called when Add Person modal opens)
 pub fn enable_fast_discovery() {
     FAST_DISCOVERY.store(true, Ordering::SeqCst);
@@ -42,15 +134,28 @@ pub struct MdnsPeer {
     pub addresses: Vec<String>,
     pub device_name: Option<String>,
     pub alias: Option<String>, // User's display name from TXT record
+    /// Set for anonymous advertisements; `peer_id` is empty until resolved.
+    #[serde(default)]
+    pub token: Option<DiscoveryToken>,
 }
 
 pub struct MdnsServiceHandle {
     shutdown: Arc<AtomicBool>,
+    /// New alias for the registration thread to pick up (`Some`), if any.
+    pending_alias: Arc<std::sync::Mutex<Option<Option<String>>>>,
     registration_thread: Option<JoinHandle<()>>,
     browser_thread: Option<JoinHandle<()>>,
 }
 
 impl MdnsServiceHandle {
+    /// Re-register with `alias` in the TXT record. Only `Full` advertisements
+    /// carry an alias; for the others this does nothing.
+    pub fn update_alias(&self, alias: Option<String>) {
+        if let Ok(mut pending) = self.pending_alias.lock() {
+            *pending = Some(alias);
+        }
+    }
+
     pub fn stop(&mut self) {
         if self.registration_thread.is_none() && self.browser_thread.is_none() {
             return;
@@ -76,12 +181,12 @@ impl Drop for MdnsServiceHandle {
     }
 }
 
-/// Start mDNS service - always advertises and browses at startup
+/// Start mDNS service: browses, and advertises per `advertisement`
 pub fn start_mdns_service(
     peer_id: PeerId,
     port: u16,
     sender: mpsc::Sender<MdnsPeer>,
-    user_alias: Option<String>, // User's alias from settings
+    advertisement: Advertisement,
 ) -> Result<MdnsServiceHandle> {
     if MDNS_INITIALIZED.swap(true, Ordering::SeqCst) {
         return Err(anyhow!("mDNS already initialized"));
@@ -112,28 +217,40 @@ pub fn start_mdns_service(
     };
 
     println!(
-        "[mDNS] 📡 Starting service: {} (hostname: {}, IP: {}) on port {}",
-        instance_name, valid_hostname, local_ip, port
+        "[mDNS] 📡 Starting service: {} (hostname: {}, IP: {}) on port {}, advertising: {:?}",
+        instance_name,
+        valid_hostname,
+        local_ip,
+        port,
+        advertisement.level()
     );
 
     let shutdown = Arc::new(AtomicBool::new(false));
+    let pending_alias = Arc::new(std::sync::Mutex::new(None));
 
     // Spawn registration thread (advertising)
     let instance_name_reg = instance_name.clone();
     let valid_hostname_reg = valid_hostname.clone();
-    let alias_reg = user_alias.clone();
     let reg_shutdown = shutdown.clone();
-    let registration_thread = std::thread::spawn(move || {
-        if let Err(e) = run_service_registration(
-            instance_name_reg,
-            valid_hostname_reg,
-            port,
-            alias_reg,
-            reg_shutdown,
-        ) {
-            eprintln!("[mDNS] Registration error: {}", e);
+    let registration_thread = match advertisement {
+        Advertisement::Off => None,
+        Advertisement::Full { alias } => {
+            let pending_alias = pending_alias.clone();
+            Some(std::thread::spawn(move || {
+                run_full_registration(
+                    valid_hostname_reg,
+                    &instance_name_reg,
+                    alias,
+                    port,
+                    pending_alias,
+                    reg_shutdown,
+                )
+            }))
         }
-    });
+        Advertisement::Anonymous { key } => Some(std::thread::spawn(move || {
+            run_anonymous_registration(key, port, reg_shutdown)
+        })),
+    };
 
     // Spawn browser thread (discovery)
     let my_peer_id = instance_name;
@@ -146,42 +263,115 @@ pub fn start_mdns_service(
 
     Ok(MdnsServiceHandle {
         shutdown,
-        registration_thread: Some(registration_thread),
+        pending_alias,
+        registration_thread,
         browser_thread: Some(browser_thread),
     })
 }
 
-fn run_service_registration(
-    instance_name: String,
-    hostname: String,
-    port: u16,
-    user_alias: Option<String>,
-    shutdown: Arc<AtomicBool>,
-) -> Result<()> {
-    let service_type = ServiceType::new("rchat", "udp")
-        .map_err(|e| anyhow::anyhow!("Invalid service type: {:?}", e))?;
-
-    let mut service = MdnsService::new(service_type, port);
-
+fn base_txt_record() -> Result<TxtRecord> {
     let mut txt_record = TxtRecord::new();
-    txt_record
-        .insert("peer_id", &instance_name)
-        .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
     txt_record
         .insert("version", "1.0.0")
         .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
     txt_record
         .insert("protocol", "rchat/1.0")
         .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
+    // The resolver hands browsers a single address, usually IPv4.
+    if let Ok(ip6) = local_ipv6() {
+        txt_record
+            .insert("ip6", &ip6.to_string())
+            .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
+    }
+    Ok(txt_record)
+}
+
+fn full_txt_record(instance_name: &str, user_alias: Option<&str>) -> Result<TxtRecord> {
+    let mut txt_record = base_txt_record()?;
+    txt_record
+        .insert("peer_id", instance_name)
+        .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
 
     // Add user alias if set
-    if let Some(alias) = &user_alias {
+    if let Some(alias) = user_alias {
         txt_record
             .insert("alias", alias)
             .map_err(|e| anyhow::anyhow!("Failed to insert alias TXT record: {:?}", e))?;
     }
+    Ok(txt_record)
+}
+
+fn anonymous_txt_record(token: &str, epoch: u64) -> Result<TxtRecord> {
+    let mut txt_record = base_txt_record()?;
+    txt_record
+        .insert("token", token)
+        .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
+    txt_record
+        .insert("epoch", &epoch.to_string())
+        .map_err(|e| anyhow::anyhow!("Failed to insert TXT record: {:?}", e))?;
+    Ok(txt_record)
+}
+
+/// Re-register whenever [`MdnsServiceHandle::update_alias`] hands us a new
+/// alias, so the LAN sees renames without a restart.
+fn run_full_registration(
+    name: String,
+    instance_name: &str,
+    mut alias: Option<String>,
+    port: u16,
+    pending_alias: Arc<std::sync::Mutex<Option<Option<String>>>>,
+    shutdown: Arc<AtomicBool>,
+) {
+    let alias_pending = || pending_alias.lock().is_ok_and(|p| p.is_some());
+    while !shutdown.load(Ordering::SeqCst) {
+        let result = run_service_registration(
+            name.clone(),
+            port,
+            full_txt_record(instance_name, alias.as_deref()),
+            || shutdown.load(Ordering::SeqCst) || alias_pending(),
+        );
+        if let Err(e) = result {
+            eprintln!("[mDNS] Registration error: {}", e);
+            return;
+        }
+        if let Some(next) = pending_alias.lock().ok().and_then(|mut p| p.take()) {
+            println!("[mDNS] 🔁 Alias changed, re-registering");
+            alias = next;
+        }
+    }
+}
 
-    service.set_name(&hostname);
+/// Re-register under a fresh token (and service name) every epoch.
+fn run_anonymous_registration(key: [u8; 32], port: u16, shutdown: Arc<AtomicBool>) {
+    while !shutdown.load(Ordering::SeqCst) {
+        let epoch = current_epoch();
+        let token = discovery_token(&key, epoch);
+        // Neither the hostname nor the peer ID: the name rotates with the token.
+        let name = format!("rchat-{}", &token[..8]);
+        let txt_record = anonymous_txt_record(&token, epoch);
+        let result = run_service_registration(name, port, txt_record, || {
+            shutdown.load(Ordering::SeqCst) || current_epoch() != epoch
+        });
+        if let Err(e) = result {
+            eprintln!("[mDNS] Registration error: {}", e);
+            return;
+        }
+    }
+}
+
+fn run_service_registration(
+    name: String,
+    port: u16,
+    txt_record: Result<TxtRecord>,
+    should_stop: impl Fn() -> bool,
+) -> Result<()> {
+    let service_type = ServiceType::new("rchat", "udp")
+        .map_err(|e| anyhow::anyhow!("Invalid service type: {:?}", e))?;
+
+    let mut service = MdnsService::new(service_type, port);
+    let txt_record = txt_record?;
+
+    service.set_name(&name);
     service.set_txt_record(txt_record);
     service.set_registered_callback(Box::new(on_service_registered));
 
@@ -191,7 +381,7 @@ fn run_service_registration(
 
     println!("[mDNS] ✅ Service registered, polling...");
 
-    while !shutdown.load(Ordering::SeqCst) {
+    while !should_stop() {
         if let Err(e) = event_loop.poll(Duration::from_secs(1)) {
             eprintln!("[mDNS] Poll error: {:?}", e);
         }
@@ -269,6 +459,23 @@ fn run_service_browser(
     Ok(())
 }
 
+/// QUIC addresses of a resolved service, the advertised IPv6 one first.
+fn service_addresses(resolved: &str, txt_ip6: Option<&str>, port: u16) -> Vec<String> {
+    let mut addresses = Vec::new();
+    if let Some(ip6) = txt_ip6.and_then(|ip| ip.parse::<std::net::Ipv6Addr>().ok()) {
+        addresses.push(format!("/ip6/{}/udp/{}/quic-v1", ip6, port));
+    }
+    let resolved = match resolved.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => format!("/ip4/{}/udp/{}/quic-v1", ip, port),
+        Ok(std::net::IpAddr::V6(ip)) => format!("/ip6/{}/udp/{}/quic-v1", ip, port),
+        Err(_) => return addresses,
+    };
+    if !addresses.contains(&resolved) {
+        addresses.push(resolved);
+    }
+    addresses
+}
+
 fn handle_browser_event(
     result: zeroconf::Result<BrowserEvent>,
     sender: &Arc<std::sync::Mutex<mpsc::Sender<MdnsPeer>>>,
@@ -301,9 +508,16 @@ fn handle_browser_event(
 
             // Extract peer_id from TXT record first for self-check
             let txt = discovery.txt();
+            let token = txt.as_ref().and_then(|t| {
+                Some(DiscoveryToken {
+                    epoch: t.get("epoch")?.parse().ok()?,
+                    token: t.get("token")?,
+                })
+            });
             let discovered_peer_id = txt
                 .as_ref()
                 .and_then(|t| t.get("peer_id"))
+                .or_else(|| token.is_some().then(String::new))
                 .unwrap_or_else(|| device_name.clone());
 
             // Skip self - don't even log it
@@ -315,13 +529,13 @@ fn handle_browser_event(
 
             let discovered_alias = txt.as_ref().and_then(|t| t.get("alias"));
 
-            let multiaddr = format!("/ip4/{}/udp/{}/quic-v1", addr, port);
-
+            let txt_ip6 = txt.as_ref().and_then(|t| t.get("ip6"));
             let peer = MdnsPeer {
                 peer_id: discovered_peer_id,
-                addresses: vec![multiaddr],
+                addresses: service_addresses(&addr, txt_ip6.as_deref(), port),
                 device_name: Some(device_name),
                 alias: discovered_alias,
+                token,
             };
 
             if let Ok(sender) = sender.lock() {
@@ -338,3 +552,48 @@ fn handle_browser_event(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymous_tokens_resolve_only_with_the_key() {
+        let friend_key = [7u8; 32];
+        let keys = vec![("12D3KooFriend".to_string(), friend_key)];
+        let epoch = 1_900_000;
+        let token = DiscoveryToken {
+            epoch,
+            token: discovery_token(&friend_key, epoch),
+        };
+        assert_eq!(token.token.len(), TOKEN_HEX_CHARS);
+        assert_ne!(token.token, discovery_token(&friend_key, epoch + 1));
+
+        assert_eq!(
+            resolve_token(&token, &keys, epoch + 1).as_deref(),
+            Some("12D3KooFriend")
+        );
+        // Stale tokens and unknown keys don't resolve.
+        assert_eq!(resolve_token(&token, &keys, epoch + 2), None);
+        assert_eq!(
+            resolve_token(&token, &[("x".to_string(), [8u8; 32])], epoch),
+            None
+        );
+    }
+
+    #[test]
+    fn advertised_ipv6_is_dialed_first() {
+        assert_eq!(
+            service_addresses("192.168.1.20", Some("2001:db8::5"), 4001),
+            vec![
+                "/ip6/2001:db8::5/udp/4001/quic-v1".to_string(),
+                "/ip4/192.168.1.20/udp/4001/quic-v1".to_string(),
+            ]
+        );
+        assert_eq!(
+            service_addresses("2001:db8::5", Some("2001:db8::5"), 4001).len(),
+            1
+        );
+        assert!(service_addresses("unknown", Some("not-an-ip"), 4001).is_empty());
+    }
+}
@@ -1,28 +1,45 @@
 mod behaviour;
+pub mod chunk_crypto;
 pub mod command;
-pub mod direct_message;
+pub mod connection_request;
+pub mod diagnostics;
 pub mod discovery;
+pub mod discovery_backend;
+pub mod discovery_debug;
+pub mod flood;
+pub mod fragment;
 pub mod gist;
 pub mod gossip;
-pub mod hks;
-pub mod invite;
+pub mod group_history;
+pub mod group_invite;
+pub mod group_key;
+pub mod keys;
+pub mod local_peers;
 mod manager;
 pub mod mdns;
+pub mod outbox;
+pub mod path;
+pub mod probe;
+pub mod proxy;
+pub mod quality;
+pub mod reconnect;
+pub mod reputation;
 pub mod stun;
+pub mod swarm_download;
 pub(crate) mod voice_stream;
+pub mod websocket;
+
+pub use rchat_core::network::{blob_stream, capabilities, direct_message, hks, invite, wire};
+
+use crate::AppHandle;
 use anyhow::Result;
 use libp2p::{identity, PeerId, SwarmBuilder};
-use tauri::{AppHandle, Manager};
+use tauri::Manager;
 use tokio::sync::mpsc;
 
 use crate::network::behaviour::RChatBehaviour;
 use crate::network::manager::NetworkManager;
-
-fn configure_noise(
-    keypair: &libp2p::identity::Keypair,
-) -> Result<libp2p::noise::Config, libp2p::noise::Error> {
-    libp2p::noise::Config::new(keypair)
-}
+use rchat_core::network::configure_noise;
 
 pub async fn init(app_handle: AppHandle) -> Result<()> {
     println!("[Backend] network::init starting...");
@@ -35,6 +52,7 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
         let state = app_handle.state::<crate::AppState>();
         let config_manager = state.config_manager.lock().await;
         let mut config = config_manager.load().await.unwrap_or_default();
+        proxy::set_proxy(config.system.socks5_proxy.clone());
 
         if let Some(ref key_b64) = config.user.libp2p_keypair {
             // Load existing keypair (saved as protobuf-encoded)
@@ -72,32 +90,130 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
     };
 
     let local_peer_id = PeerId::from_public_key(&local_key.public());
-    println!("[Backend] Local Peer ID: {local_peer_id}");
+    println!(
+        "[Backend] Local Peer ID: {}",
+        crate::logging::peer(&local_peer_id)
+    );
+
+    let (websocket_settings, fixed_port) = {
+        let state = app_handle.state::<crate::AppState>();
+        let mgr = state.config_manager.lock().await;
+        mgr.load()
+            .await
+            .map(|c| (c.user.websocket, c.user.listen_port))
+            .unwrap_or_default()
+    };
+    let websocket_tls = if websocket_settings.enabled {
+        websocket::tls_config(&websocket_settings).unwrap_or_else(|e| {
+            eprintln!("[Backend] ⚠️ Not serving /wss: {}", e);
+            None
+        })
+    } else {
+        None
+    };
+    let serve_wss = websocket_tls.is_some();
 
     println!("[Backend] Building swarm...");
-    let mut swarm = SwarmBuilder::with_existing_identity(local_key.clone())
-        .with_tokio()
-        .with_tcp(libp2p::tcp::Config::default(), configure_noise, || {
-            libp2p::yamux::Config::default()
-        })?
-        .with_quic()
-        .with_dns()?
-        .with_relay_client(configure_noise, || libp2p::yamux::Config::default())?
-        .with_behaviour(|key, relay_client| RChatBehaviour::new(key.clone(), relay_client))?
-        .with_swarm_config(|c| c.with_idle_connection_timeout(std::time::Duration::from_secs(60)))
-        .build();
+    let socks5_proxy = proxy::proxy();
+    // Random ports change on every start, so only a fixed port is worth
+    // forwarding; behind a proxy we don't advertise ourselves at all.
+    let upnp = fixed_port.is_some() && socks5_proxy.is_none();
+    let mut swarm = match &socks5_proxy {
+        // Outgoing TCP through the proxy only: no QUIC and no local DNS, so
+        // nothing reaches peers without going through it.
+        Some(proxy_addr) => {
+            println!(
+                "[Backend] Routing outgoing TCP through SOCKS5 proxy {}",
+                proxy_addr
+            );
+            let proxy_addr = proxy_addr.clone();
+            SwarmBuilder::with_existing_identity(local_key.clone())
+                .with_tokio()
+                .with_other_transport({
+                    let proxy_addr = proxy_addr.clone();
+                    move |key| {
+                        use libp2p::core::upgrade::Version;
+                        use libp2p::Transport;
+                        Ok::<_, libp2p::noise::Error>(
+                            proxy::Socks5Transport::new(proxy_addr)
+                                .or_transport(libp2p::tcp::tokio::Transport::new(
+                                    libp2p::tcp::Config::default(),
+                                ))
+                                .upgrade(Version::V1Lazy)
+                                .authenticate(configure_noise(key)?)
+                                .multiplex(libp2p::yamux::Config::default()),
+                        )
+                    }
+                })?
+                .with_other_transport(move |key| {
+                    use libp2p::Transport;
+                    websocket::transport(
+                        proxy::Socks5Transport::new(proxy_addr).or_transport(
+                            libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default()),
+                        ),
+                        key,
+                        websocket_tls,
+                    )
+                })?
+                .with_relay_client(configure_noise, || libp2p::yamux::Config::default())?
+                .with_behaviour(|key, relay_client| {
+                    RChatBehaviour::new(key.clone(), relay_client, upnp)
+                })?
+                .with_swarm_config(|c| {
+                    c.with_idle_connection_timeout(std::time::Duration::from_secs(60))
+                })
+                .build()
+        }
+        None => SwarmBuilder::with_existing_identity(local_key.clone())
+            .with_tokio()
+            .with_tcp(libp2p::tcp::Config::default(), configure_noise, || {
+                libp2p::yamux::Config::default()
+            })?
+            .with_quic()
+            .with_other_transport(|key| {
+                websocket::transport(
+                    libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default()),
+                    key,
+                    websocket_tls,
+                )
+            })?
+            .with_dns()?
+            .with_relay_client(configure_noise, || libp2p::yamux::Config::default())?
+            .with_behaviour(|key, relay_client| {
+                RChatBehaviour::new(key.clone(), relay_client, upnp)
+            })?
+            .with_swarm_config(|c| {
+                c.with_idle_connection_timeout(std::time::Duration::from_secs(60))
+            })
+            .build(),
+    };
 
     println!("[Backend] Swarm built. Listening...");
 
-    // Get a random available port first, then use it for both IPv4 and IPv6
-    // This ensures mDNS advertises a port that works for both protocols
-    let tcp_port = {
-        let socket = std::net::TcpListener::bind("0.0.0.0:0")?;
-        socket.local_addr()?.port()
+    // Get a random available port first (unless one is configured), then use
+    // it for both IPv4 and IPv6. This ensures mDNS advertises a port that
+    // works for both protocols
+    let fixed_port = fixed_port.filter(|port| {
+        let free = std::net::TcpListener::bind(("0.0.0.0", *port)).is_ok()
+            && std::net::UdpSocket::bind(("0.0.0.0", *port)).is_ok();
+        if !free {
+            eprintln!(
+                "[Backend] ⚠️ Configured port {} is in use, using random ports",
+                port
+            );
+        }
+        free
+    });
+    let tcp_port = match fixed_port {
+        Some(port) => port,
+        None => free_tcp_port()?,
     };
-    let udp_port = {
-        let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
-        socket.local_addr()?.port()
+    let udp_port = match fixed_port {
+        Some(port) => port,
+        None => {
+            let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
+            socket.local_addr()?.port()
+        }
     };
 
     println!(
@@ -105,8 +221,17 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
         tcp_port, udp_port
     );
 
-    // Do STUN discovery (socket closes after discovery)
-    let stun_result = stun::discover_on_port(udp_port).await;
+    // Do STUN discovery (socket closes after discovery). Skipped behind a
+    // proxy: it would reveal our public address over plain UDP.
+    let stun_result = if socks5_proxy.is_some() {
+        stun::StunResult {
+            ipv6: None,
+            ipv4: None,
+            external_port: None,
+        }
+    } else {
+        stun::discover_on_port(udp_port).await
+    };
     let stun_external_port = stun_result.external_port;
     let stun_public_ip_v6 = stun_result.ipv6.map(|a| a.ip().to_string());
     let stun_public_ip = stun_result.ipv4.map(|a| a.ip().to_string());
@@ -120,10 +245,25 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
 
     // Bind QUIC to the SAME port (socket was closed after STUN discovery)
     // On most NATs, binding to the same local port gets the same external mapping
-    swarm.listen_on(format!("/ip6/::/udp/{}/quic-v1", udp_port).parse()?)?;
+    if socks5_proxy.is_none() {
+        swarm.listen_on(format!("/ip6/::/udp/{}/quic-v1", udp_port).parse()?)?;
+        swarm.listen_on(format!("/ip4/0.0.0.0/udp/{}/quic-v1", udp_port).parse()?)?;
+    }
     swarm.listen_on(format!("/ip6/::/tcp/{}", tcp_port).parse()?)?;
-    swarm.listen_on(format!("/ip4/0.0.0.0/udp/{}/quic-v1", udp_port).parse()?)?;
     swarm.listen_on(format!("/ip4/0.0.0.0/tcp/{}", tcp_port).parse()?)?;
+    if websocket_settings.enabled {
+        let ws_port = websocket_settings.ws_port.map_or_else(free_tcp_port, Ok)?;
+        let mut listeners = websocket::listen_addrs(ws_port, false).to_vec();
+        if serve_wss {
+            let wss_port = websocket_settings.wss_port.map_or_else(free_tcp_port, Ok)?;
+            listeners.extend(websocket::listen_addrs(wss_port, true));
+        }
+        for addr in listeners {
+            if let Err(e) = swarm.listen_on(addr.clone()) {
+                eprintln!("[Backend] ⚠️ Failed to listen on {}: {}", addr, e);
+            }
+        }
+    }
 
     println!(
         "[Backend] Swarm listeners started (QUIC on port {}, TCP on port {})",
@@ -132,7 +272,7 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
 
     let listener_snapshot: Vec<String> = swarm.listeners().map(|l| l.to_string()).collect();
     let quic_port_bound = is_quic_udp_port_bound(&listener_snapshot, udp_port);
-    let effective_stun_external_port = if quic_port_bound {
+    let effective_stun_external_port = if quic_port_bound || socks5_proxy.is_some() {
         stun_external_port
     } else {
         eprintln!(
@@ -160,18 +300,10 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
 
     // Store the sender in app state (with STUN results)
     let network_state = crate::NetworkState {
-        sender: tokio::sync::Mutex::new(ctx),
-        local_peer_id: tokio::sync::Mutex::new(Some(local_peer_id.to_string())),
-        listening_addresses: tokio::sync::Mutex::new(vec![]),
         public_address_v6: tokio::sync::Mutex::new(stun_public_ip_v6),
         public_address_v4: tokio::sync::Mutex::new(stun_public_ip),
         stun_external_port: tokio::sync::Mutex::new(effective_stun_external_port),
-        temporary_state: tokio::sync::Mutex::new(crate::app_state::TemporaryRuntimeState::default()),
-        connected_chat_ids: tokio::sync::Mutex::new(std::collections::HashSet::new()),
-        chat_connections: tokio::sync::Mutex::new(std::collections::HashMap::new()),
-        voice_call_state: tokio::sync::Mutex::new(crate::app_state::VoiceCallState::default()),
-        broadcast_state: tokio::sync::Mutex::new(crate::app_state::BroadcastState::default()),
-        connectivity: tokio::sync::Mutex::new(connectivity_settings),
+        ..crate::NetworkState::new(ctx, local_peer_id.to_string(), connectivity_settings)
     };
     app_handle.manage(network_state);
 
@@ -195,7 +327,9 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
     tauri::async_runtime::spawn(async move {
         println!("[Backend] NetworkManager starting");
         // Move the 'swarm' and 'app_handle' into this thread
-        let manager = NetworkManager::new(swarm, crx, disc_rx, mdns_rx, mdns_tx, app_handle);
+        let manager = NetworkManager::new(
+            swarm, &local_key, crx, disc_rx, mdns_rx, mdns_tx, app_handle,
+        );
 
         // Run the infinite loop
         manager.run().await;
@@ -203,6 +337,12 @@ pub async fn init(app_handle: AppHandle) -> Result<()> {
     Ok(())
 }
 
+/// A TCP port that is free right now, picked by the OS.
+fn free_tcp_port() -> std::io::Result<u16> {
+    let socket = std::net::TcpListener::bind("0.0.0.0:0")?;
+    Ok(socket.local_addr()?.port())
+}
+
 fn get_port_from_multiaddr(addr: &libp2p::Multiaddr) -> Option<u16> {
     use libp2p::multiaddr::Protocol;
     for proto in addr.iter() {
@@ -0,0 +1,214 @@
+//! Retrying chat messages a peer didn't acknowledge.
+//!
+//! Every direct chat message (text or media) is tracked from the moment its
+//! request goes out until the peer answers it. When the request fails
+//! (`OutboundFailure`: timeout, closed stream, ...) the message is marked
+//! failed and sent again after an exponential backoff, as long as the peer
+//! stays connected. After [`MAX_ATTEMPTS`] sends, or once the peer is gone,
+//! the outbox gives up: the UI gets `message-send-failed` and can resend with
+//! the `retry_message` command. Fragmented requests aren't tracked.
+
+use libp2p::PeerId;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::network::direct_message::{DirectMessageKind, DirectMessageRequest};
+
+pub const BASE_DELAY: Duration = Duration::from_secs(2);
+pub const MAX_DELAY: Duration = Duration::from_secs(60);
+/// Sends per message, the first one included.
+pub const MAX_ATTEMPTS: u32 = 5;
+/// Tauri command the UI calls to resend a message the outbox gave up on.
+pub const RETRY_COMMAND: &str = "retry_message";
+
+/// Payload of `message-send-failed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SendFailedEvent {
+    pub msg_id: String,
+    pub peer_id: String,
+    pub attempts: u32,
+    pub error: String,
+    pub retry_command: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    /// Sent again in `retry_in` unless the peer disconnects.
+    Retry {
+        attempts: u32,
+        retry_in: Duration,
+    },
+    GaveUp {
+        attempts: u32,
+    },
+}
+
+/// Backoff after the `attempts`-th failed send.
+pub fn delay(attempts: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(1u32 << attempts.saturating_sub(1).min(16))
+        .min(MAX_DELAY)
+}
+
+/// Whether `request` is a chat message the outbox looks after.
+pub fn tracks(request: &DirectMessageRequest) -> bool {
+    request.msg_type == DirectMessageKind::Text || request.msg_type.needs_file_transfer()
+}
+
+#[derive(Debug)]
+struct Entry {
+    peer: PeerId,
+    request: DirectMessageRequest,
+    attempts: u32,
+    /// `None` while a send is in flight.
+    next_at: Option<Instant>,
+}
+
+/// Tracked messages by id. `R` is the request id of the send in flight
+/// (libp2p's `OutboundRequestId`).
+#[derive(Debug)]
+pub struct Outbox<R> {
+    entries: HashMap<String, Entry>,
+    in_flight: HashMap<R, String>,
+}
+
+impl<R> Default for Outbox<R> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            in_flight: HashMap::new(),
+        }
+    }
+}
+
+impl<R: Hash + Eq> Outbox<R> {
+    /// `request` went out to `peer` as `request_id`.
+    pub fn sent(&mut self, request_id: R, peer: PeerId, request: &DirectMessageRequest) {
+        let entry = self.entries.entry(request.id.clone()).or_insert(Entry {
+            peer,
+            request: request.clone(),
+            attempts: 0,
+            next_at: None,
+        });
+        entry.peer = peer;
+        entry.attempts += 1;
+        entry.next_at = None;
+        self.in_flight.insert(request_id, request.id.clone());
+    }
+
+    /// The peer answered; returns the message id if it was tracked.
+    pub fn answered(&mut self, request_id: &R) -> Option<String> {
+        let msg_id = self.in_flight.remove(request_id)?;
+        self.entries.remove(&msg_id);
+        Some(msg_id)
+    }
+
+    /// The send failed: schedule another one or give up.
+    pub fn failed(&mut self, request_id: &R, now: Instant) -> Option<(String, PeerId, Failure)> {
+        let msg_id = self.in_flight.remove(request_id)?;
+        let entry = self.entries.get_mut(&msg_id)?;
+        let peer = entry.peer;
+        let attempts = entry.attempts;
+        if attempts >= MAX_ATTEMPTS {
+            self.entries.remove(&msg_id);
+            return Some((msg_id, peer, Failure::GaveUp { attempts }));
+        }
+        let retry_in = delay(attempts);
+        entry.next_at = Some(now + retry_in);
+        Some((msg_id, peer, Failure::Retry { attempts, retry_in }))
+    }
+
+    /// Messages whose backoff ran out, to be sent again now.
+    pub fn due(&mut self, now: Instant) -> Vec<(PeerId, DirectMessageRequest)> {
+        let mut due = Vec::new();
+        for entry in self.entries.values_mut() {
+            if entry.next_at.is_some_and(|at| at <= now) {
+                entry.next_at = None;
+                due.push((entry.peer, entry.request.clone()));
+            }
+        }
+        due
+    }
+
+    /// Stop retrying `msg_id`; returns how often it was sent.
+    pub fn give_up(&mut self, msg_id: &str) -> Option<u32> {
+        self.in_flight.retain(|_, id| id != msg_id);
+        self.entries.remove(msg_id).map(|entry| entry.attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(id: &str) -> DirectMessageRequest {
+        DirectMessageRequest {
+            id: id.to_string(),
+            sender_id: "me".to_string(),
+            msg_type: DirectMessageKind::Text,
+            text_content: Some("hi".to_string()),
+            file_hash: None,
+            timestamp: 0,
+            chunk_hash: None,
+            chunk_data: None,
+            chunk_nonce: None,
+            chunk_list: None,
+            sender_alias: None,
+            translation: None,
+            logical: None,
+            content: None,
+            wire: crate::network::wire::WireVersion::CURRENT,
+        }
+    }
+
+    #[test]
+    fn retries_with_backoff_until_answered_or_out_of_attempts() {
+        assert_eq!(delay(1), BASE_DELAY);
+        assert_eq!(delay(3), BASE_DELAY * 4);
+        assert_eq!(delay(30), MAX_DELAY);
+
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let now = Instant::now();
+        let mut outbox = Outbox::<u64>::default();
+        let mut read_receipt = text("r");
+        read_receipt.msg_type = DirectMessageKind::ReadReceipt;
+        assert!(tracks(&text("m")) && !tracks(&read_receipt));
+
+        outbox.sent(1, peer, &text("m"));
+        assert_eq!(outbox.failed(&2, now), None);
+        let mut request_id = 1;
+        for attempts in 1..=MAX_ATTEMPTS {
+            let (msg_id, _, failure) = outbox.failed(&request_id, now).expect("tracked");
+            assert_eq!(msg_id, "m");
+            if attempts < MAX_ATTEMPTS {
+                assert_eq!(
+                    failure,
+                    Failure::Retry {
+                        attempts,
+                        retry_in: delay(attempts)
+                    }
+                );
+                assert!(outbox.due(now).is_empty());
+                let due = outbox.due(now + MAX_DELAY);
+                assert_eq!(due.len(), 1);
+                request_id += 1;
+                outbox.sent(request_id, due[0].0, &due[0].1);
+            } else {
+                assert_eq!(failure, Failure::GaveUp { attempts });
+            }
+        }
+        assert!(outbox.due(now + MAX_DELAY).is_empty());
+
+        outbox.sent(10, peer, &text("n"));
+        assert_eq!(outbox.answered(&10), Some("n".to_string()));
+        assert_eq!(outbox.failed(&10, now), None);
+
+        outbox.sent(11, peer, &text("o"));
+        assert_eq!(outbox.give_up("o"), Some(1));
+        assert_eq!(outbox.answered(&11), None);
+    }
+}
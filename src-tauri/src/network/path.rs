@@ -0,0 +1,266 @@
+//! Connection path policy: LAN, then direct public, then relay.
+//!
+//! Dials try the best-ranked address first. While a peer is only reachable
+//! through a relay, the manager keeps retrying known non-relay addresses
+//! (dcutr runs coordinated hole punching on the relayed connection in
+//! parallel), and once a direct path is up the relayed connections are closed
+//! so traffic stops flowing through the relay.
+
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::ConnectionId;
+use libp2p::{Multiaddr, PeerId};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+pub const UPGRADE_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Ordered worst to best so `max()` picks the preferred path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionPath {
+    Relay,
+    Direct,
+    Lan,
+}
+
+fn is_lan_v4(ip: &Ipv4Addr) -> bool {
+    ip.is_private() || ip.is_loopback() || ip.is_link_local()
+}
+
+fn is_lan_v6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || (first & 0xfe00) == 0xfc00 // unique local, fc00::/7
+        || (first & 0xffc0) == 0xfe80 // link local, fe80::/10
+}
+
+pub fn classify(addr: &Multiaddr) -> ConnectionPath {
+    if addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)) {
+        return ConnectionPath::Relay;
+    }
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::Ip4(ip) if is_lan_v4(&ip) => return ConnectionPath::Lan,
+            Protocol::Ip6(ip) if is_lan_v6(&ip) => return ConnectionPath::Lan,
+            Protocol::Ip4(_) | Protocol::Ip6(_) => return ConnectionPath::Direct,
+            _ => {}
+        }
+    }
+    ConnectionPath::Direct
+}
+
+fn is_ipv6(addr: &Multiaddr) -> bool {
+    matches!(
+        addr.iter().next(),
+        Some(Protocol::Ip6(_) | Protocol::Dns6(_))
+    )
+}
+
+/// Sort key, higher is better: the path, then IPv6 over IPv4, which avoids
+/// NAT when both ends have it.
+pub fn preference(addr: &Multiaddr) -> (ConnectionPath, bool) {
+    (classify(addr), is_ipv6(addr))
+}
+
+/// Sort `addrs` best first by [`preference`], keeping the original order
+/// among equals.
+pub fn rank_addresses(addrs: &mut [Multiaddr]) {
+    addrs.sort_by_key(|addr| std::cmp::Reverse(preference(addr)));
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathConnection {
+    pub path: ConnectionPath,
+    pub address: String,
+}
+
+/// Answer for `get_connection_path`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PathReport {
+    pub peer_id: String,
+    /// Best live path, `None` when not connected.
+    pub path: Option<ConnectionPath>,
+    pub connections: Vec<PathConnection>,
+    /// Known addresses better than the current path that upgrades will try.
+    pub upgrade_candidates: Vec<String>,
+}
+
+/// Live connections per peer, tagged with their path.
+#[derive(Debug, Default)]
+pub struct PathRegistry {
+    by_peer: HashMap<PeerId, Vec<(ConnectionId, Multiaddr)>>,
+    last_upgrade_attempt: HashMap<PeerId, Instant>,
+}
+
+impl PathRegistry {
+    pub fn record_connected(&mut self, peer: PeerId, id: ConnectionId, addr: &Multiaddr) {
+        let conns = self.by_peer.entry(peer).or_default();
+        conns.retain(|(existing, _)| *existing != id);
+        conns.push((id, addr.clone()));
+    }
+
+    pub fn record_disconnected(&mut self, peer: PeerId, id: ConnectionId) {
+        if let Some(conns) = self.by_peer.get_mut(&peer) {
+            conns.retain(|(existing, _)| *existing != id);
+            if conns.is_empty() {
+                self.by_peer.remove(&peer);
+                self.last_upgrade_attempt.remove(&peer);
+            }
+        }
+    }
+
+    pub fn best_path(&self, peer: &PeerId) -> Option<ConnectionPath> {
+        self.by_peer
+            .get(peer)?
+            .iter()
+            .map(|(_, addr)| classify(addr))
+            .max()
+    }
+
+    pub fn connected_addresses(&self, peer: &PeerId) -> Vec<Multiaddr> {
+        self.by_peer
+            .get(peer)
+            .into_iter()
+            .flatten()
+            .map(|(_, addr)| addr.clone())
+            .collect()
+    }
+
+    /// Relayed connections that are redundant because a direct path exists.
+    pub fn redundant_relay_connections(&self, peer: &PeerId) -> Vec<ConnectionId> {
+        if self.best_path(peer).unwrap_or(ConnectionPath::Relay) == ConnectionPath::Relay {
+            return Vec::new();
+        }
+        self.by_peer
+            .get(peer)
+            .into_iter()
+            .flatten()
+            .filter(|(_, addr)| classify(addr) == ConnectionPath::Relay)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Peers currently reachable only through a relay whose retry interval elapsed.
+    pub fn take_upgrade_due(&mut self, now: Instant) -> Vec<PeerId> {
+        let due: Vec<PeerId> = self
+            .by_peer
+            .keys()
+            .filter(|peer| self.best_path(peer) == Some(ConnectionPath::Relay))
+            .filter(|peer| {
+                self.last_upgrade_attempt
+                    .get(*peer)
+                    .is_none_or(|at| now.duration_since(*at) >= UPGRADE_RETRY_INTERVAL)
+            })
+            .copied()
+            .collect();
+        for peer in &due {
+            self.last_upgrade_attempt.insert(*peer, now);
+        }
+        due
+    }
+
+    pub fn report(&self, peer: &PeerId, known_addrs: &[Multiaddr]) -> PathReport {
+        let path = self.best_path(peer);
+        let connections = self
+            .by_peer
+            .get(peer)
+            .into_iter()
+            .flatten()
+            .map(|(_, addr)| PathConnection {
+                path: classify(addr),
+                address: addr.to_string(),
+            })
+            .collect();
+        PathReport {
+            peer_id: peer.to_string(),
+            path,
+            connections,
+            upgrade_candidates: upgrade_candidates(path, known_addrs)
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Known addresses that would improve on `current`, best first.
+pub fn upgrade_candidates(
+    current: Option<ConnectionPath>,
+    known_addrs: &[Multiaddr],
+) -> Vec<Multiaddr> {
+    let mut candidates: Vec<Multiaddr> = Vec::new();
+    for addr in known_addrs {
+        if current.is_none_or(|path| classify(addr) > path) && !candidates.contains(addr) {
+            candidates.push(addr.clone());
+        }
+    }
+    rank_addresses(&mut candidates);
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().expect("multiaddr")
+    }
+
+    #[test]
+    fn classifies_and_ranks_paths() {
+        let relay = addr("/ip4/203.0.113.7/tcp/4001/p2p/12D3KooWDpJ7As7BWAwRMfu1VU2WCqNjvq387JEYKDBj4kx6nXTN/p2p-circuit");
+        let public = addr("/ip4/198.51.100.4/udp/4001/quic-v1");
+        let lan = addr("/ip4/192.168.1.20/tcp/4001");
+        let lan_v6 = addr("/ip6/fe80::1/udp/4001/quic-v1");
+        assert_eq!(classify(&relay), ConnectionPath::Relay);
+        assert_eq!(classify(&public), ConnectionPath::Direct);
+        assert_eq!(classify(&lan), ConnectionPath::Lan);
+        assert_eq!(classify(&lan_v6), ConnectionPath::Lan);
+
+        let mut addrs = vec![relay.clone(), public.clone(), lan.clone()];
+        rank_addresses(&mut addrs);
+        assert_eq!(addrs, vec![lan.clone(), public.clone(), relay.clone()]);
+
+        let candidates = upgrade_candidates(Some(ConnectionPath::Direct), &addrs);
+        assert_eq!(candidates, vec![lan]);
+
+        let public_v6 = addr("/ip6/2001:db8::5/udp/4001/quic-v1");
+        let mut addrs = vec![public.clone(), relay.clone(), public_v6.clone()];
+        rank_addresses(&mut addrs);
+        assert_eq!(addrs, vec![public_v6, public, relay]);
+    }
+
+    #[test]
+    fn registry_tracks_best_path_and_upgrade_schedule() {
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let relay = addr("/ip4/203.0.113.7/tcp/4001/p2p-circuit");
+        let lan = addr("/ip4/10.0.0.5/tcp/4001");
+        let mut registry = PathRegistry::default();
+        let now = Instant::now();
+
+        registry.record_connected(peer, ConnectionId::new_unchecked(1), &relay);
+        assert_eq!(registry.best_path(&peer), Some(ConnectionPath::Relay));
+        assert!(registry.redundant_relay_connections(&peer).is_empty());
+        assert_eq!(registry.take_upgrade_due(now), vec![peer]);
+        assert!(registry.take_upgrade_due(now).is_empty());
+        assert_eq!(
+            registry.take_upgrade_due(now + UPGRADE_RETRY_INTERVAL),
+            vec![peer]
+        );
+
+        registry.record_connected(peer, ConnectionId::new_unchecked(2), &lan);
+        assert_eq!(registry.best_path(&peer), Some(ConnectionPath::Lan));
+        assert_eq!(
+            registry.redundant_relay_connections(&peer),
+            vec![ConnectionId::new_unchecked(1)]
+        );
+
+        registry.record_disconnected(peer, ConnectionId::new_unchecked(1));
+        registry.record_disconnected(peer, ConnectionId::new_unchecked(2));
+        assert_eq!(registry.best_path(&peer), None);
+    }
+}
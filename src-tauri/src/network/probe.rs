@@ -0,0 +1,244 @@
+//! Active reachability test for a single peer (`test_peer_connection`).
+//!
+//! Every address we know for the peer is dialed on its own connection, tagged
+//! with where we learned it: remembered from an earlier connection, announced
+//! over mDNS, or a relay circuit. The manager feeds `ConnectionEstablished` /
+//! `OutgoingConnectionError` events for those dials into a [`ProbeRun`] and
+//! answers with a [`PeerConnectionTest`] once every dial has settled or
+//! [`PROBE_TIMEOUT`] passed.
+
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::ConnectionId;
+use libp2p::{Multiaddr, PeerId};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::path::{classify, ConnectionPath};
+
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeSource {
+    /// Address of an earlier or current connection.
+    Stored,
+    Mdns,
+    Relay,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub source: ProbeSource,
+    pub path: ConnectionPath,
+    pub address: String,
+    pub success: bool,
+    /// Time until the connection was established.
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Answer for `test_peer_connection`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerConnectionTest {
+    pub peer_id: String,
+    pub started_at: i64,
+    pub connected_before: bool,
+    pub reachable: bool,
+    /// Best successful path, fastest first within a path.
+    pub best: Option<ProbeResult>,
+    /// Empty when we know no address for the peer at all.
+    pub results: Vec<ProbeResult>,
+}
+
+fn is_circuit(addr: &Multiaddr) -> bool {
+    addr.iter().any(|p| matches!(p, Protocol::P2pCircuit))
+}
+
+/// Addresses to test, deduplicated. mDNS wins over a stored copy of the same
+/// address and any circuit address counts as a relay path.
+pub fn candidates(stored: &[Multiaddr], mdns: &[Multiaddr]) -> Vec<(ProbeSource, Multiaddr)> {
+    let mut out: Vec<(ProbeSource, Multiaddr)> = Vec::new();
+    let tagged = mdns
+        .iter()
+        .map(|a| (ProbeSource::Mdns, a))
+        .chain(stored.iter().map(|a| (ProbeSource::Stored, a)));
+    for (source, addr) in tagged {
+        if out.iter().any(|(_, existing)| existing == addr) {
+            continue;
+        }
+        let source = if is_circuit(addr) {
+            ProbeSource::Relay
+        } else {
+            source
+        };
+        out.push((source, addr.clone()));
+    }
+    out
+}
+
+/// One running test; dials are keyed by the connection id they were issued with.
+pub struct ProbeRun {
+    pub peer: PeerId,
+    reply: tokio::sync::oneshot::Sender<PeerConnectionTest>,
+    started: Instant,
+    started_at: i64,
+    connected_before: bool,
+    pending: HashMap<ConnectionId, (ProbeSource, Multiaddr, Instant)>,
+    results: Vec<ProbeResult>,
+}
+
+impl ProbeRun {
+    pub fn new(
+        peer: PeerId,
+        reply: tokio::sync::oneshot::Sender<PeerConnectionTest>,
+        started_at: i64,
+        connected_before: bool,
+    ) -> Self {
+        Self {
+            peer,
+            reply,
+            started: Instant::now(),
+            started_at,
+            connected_before,
+            pending: HashMap::new(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn dialing(&mut self, id: ConnectionId, source: ProbeSource, addr: Multiaddr) {
+        self.pending.insert(id, (source, addr, Instant::now()));
+    }
+
+    /// A dial that could not even be started.
+    pub fn dial_rejected(&mut self, source: ProbeSource, addr: &Multiaddr, error: String) {
+        self.results.push(ProbeResult {
+            source,
+            path: classify(addr),
+            address: addr.to_string(),
+            success: false,
+            latency_ms: None,
+            error: Some(error),
+        });
+    }
+
+    /// Record the outcome of a probe dial; `false` if `id` is not one of ours.
+    pub fn settle(&mut self, id: ConnectionId, outcome: Result<(), String>) -> bool {
+        let Some((source, addr, dialed_at)) = self.pending.remove(&id) else {
+            return false;
+        };
+        let success = outcome.is_ok();
+        self.results.push(ProbeResult {
+            source,
+            path: classify(&addr),
+            address: addr.to_string(),
+            success,
+            latency_ms: success.then(|| dialed_at.elapsed().as_millis() as u64),
+            error: outcome.err(),
+        });
+        true
+    }
+
+    pub fn is_done(&self, now: Instant) -> bool {
+        self.pending.is_empty() || now.duration_since(self.started) >= PROBE_TIMEOUT
+    }
+
+    pub fn finish(mut self) {
+        for (_, (source, addr, _)) in self.pending.drain() {
+            self.results.push(ProbeResult {
+                source,
+                path: classify(&addr),
+                address: addr.to_string(),
+                success: false,
+                latency_ms: None,
+                error: Some("timed out".to_string()),
+            });
+        }
+        let _ = self.reply.send(report(
+            &self.peer,
+            self.started_at,
+            self.connected_before,
+            self.results,
+        ));
+    }
+}
+
+pub fn report(
+    peer: &PeerId,
+    started_at: i64,
+    connected_before: bool,
+    results: Vec<ProbeResult>,
+) -> PeerConnectionTest {
+    let best = results
+        .iter()
+        .filter(|r| r.success)
+        .max_by_key(|r| (r.path, std::cmp::Reverse(r.latency_ms.unwrap_or(u64::MAX))))
+        .cloned();
+    PeerConnectionTest {
+        peer_id: peer.to_string(),
+        started_at,
+        connected_before,
+        reachable: best.is_some(),
+        best,
+        results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().expect("multiaddr")
+    }
+
+    fn result(address: &str, latency_ms: Option<u64>) -> ProbeResult {
+        let parsed = addr(address);
+        ProbeResult {
+            source: ProbeSource::Stored,
+            path: classify(&parsed),
+            address: address.to_string(),
+            success: latency_ms.is_some(),
+            latency_ms,
+            error: latency_ms.is_none().then(|| "refused".to_string()),
+        }
+    }
+
+    #[test]
+    fn candidates_are_tagged_and_deduplicated() {
+        let relay = addr("/ip4/203.0.113.7/tcp/4001/p2p/12D3KooWDpJ7As7BWAwRMfu1VU2WCqNjvq387JEYKDBj4kx6nXTN/p2p-circuit");
+        let lan = addr("/ip4/192.168.1.20/udp/4001/quic-v1");
+        let public = addr("/ip4/198.51.100.4/udp/4001/quic-v1");
+
+        let found = candidates(
+            &[public.clone(), lan.clone(), relay.clone()],
+            &[lan.clone()],
+        );
+        assert_eq!(
+            found,
+            vec![
+                (ProbeSource::Mdns, lan),
+                (ProbeSource::Stored, public),
+                (ProbeSource::Relay, relay),
+            ]
+        );
+    }
+
+    #[test]
+    fn report_prefers_best_path_then_latency() {
+        let peer = PeerId::random();
+        let results = vec![
+            result("/ip4/198.51.100.4/udp/4001/quic-v1", Some(40)),
+            result("/ip4/192.168.1.20/tcp/4001", Some(9)),
+            result("/ip4/192.168.1.20/udp/4001/quic-v1", Some(3)),
+            result("/ip4/192.168.1.21/udp/4001/quic-v1", None),
+        ];
+        let test = report(&peer, 0, false, results);
+        assert!(test.reachable);
+        assert_eq!(
+            test.best.map(|b| b.address).as_deref(),
+            Some("/ip4/192.168.1.20/udp/4001/quic-v1")
+        );
+        assert!(!report(&peer, 0, false, Vec::new()).reachable);
+    }
+}
@@ -0,0 +1,276 @@
+//! Optional SOCKS5 proxy (`SystemConfig::socks5_proxy`), e.g. Tor's
+//! `127.0.0.1:9050`.
+//!
+//! When set, HTTP clients made with [`http_client`] (Gist, GitLab, OAuth)
+//! and outgoing libp2p TCP connections go through the proxy. Host names are
+//! resolved by the proxy, not locally. QUIC is UDP and cannot be proxied, so
+//! the network starts without it; incoming TCP connections are still accepted
+//! directly. Changing the proxy takes effect for libp2p on the next network
+//! start and for HTTP immediately.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+use futures::future::{BoxFuture, FutureExt};
+use libp2p::core::transport::{DialOpts, ListenerId, TransportError, TransportEvent};
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, Transport};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+static PROXY: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_proxy(proxy: Option<String>) {
+    if let Ok(mut current) = PROXY.lock() {
+        *current = proxy;
+    }
+}
+
+pub fn proxy() -> Option<String> {
+    PROXY.lock().ok().and_then(|proxy| proxy.clone())
+}
+
+/// `host:port` with a non-zero port.
+pub fn validate_proxy_addr(addr: &str) -> Result<(), String> {
+    let invalid = || format!("Proxy must be host:port, got \"{}\"", addr);
+    let (host, port) = addr.rsplit_once(':').ok_or_else(invalid)?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(()),
+        _ => Err(invalid()),
+    }
+}
+
+/// reqwest client routed through the proxy when one is set.
+pub fn http_client() -> reqwest::Client {
+    let Some(proxy) = proxy() else {
+        return reqwest::Client::new();
+    };
+    match reqwest::Proxy::all(format!("socks5h://{}", proxy))
+        .and_then(|proxy| reqwest::Client::builder().proxy(proxy).build())
+    {
+        Ok(client) => client,
+        Err(e) => {
+            // Never fall back to a direct connection behind the user's back.
+            eprintln!("[Proxy] ❌ Invalid proxy {}: {}", proxy, e);
+            reqwest::Client::builder()
+                .proxy(reqwest::Proxy::all("socks5h://0.0.0.0:0").expect("static proxy url"))
+                .build()
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Where a SOCKS5 CONNECT should go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Ip(SocketAddr),
+    Domain(String, u16),
+}
+
+/// `/ip4|ip6|dns|dns4|dns6/<host>/tcp/<port>[/p2p/<id>]`; anything else
+/// (QUIC, websockets, relayed addresses) is not ours to dial.
+fn target_of(addr: &Multiaddr) -> Option<Target> {
+    let mut parts = addr.iter();
+    let host = parts.next()?;
+    let Some(Protocol::Tcp(port)) = parts.next() else {
+        return None;
+    };
+    match parts.next() {
+        None | Some(Protocol::P2p(_)) => {}
+        Some(_) => return None,
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    match host {
+        Protocol::Ip4(ip) => Some(Target::Ip(SocketAddr::new(IpAddr::V4(ip), port))),
+        Protocol::Ip6(ip) => Some(Target::Ip(SocketAddr::new(IpAddr::V6(ip), port))),
+        Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => {
+            Some(Target::Domain(name.to_string(), port))
+        }
+        _ => None,
+    }
+}
+
+fn socks_error(message: impl Into<String>) -> io::Error {
+    io::Error::other(message.into())
+}
+
+/// Open a TCP stream to `target` through the SOCKS5 proxy at `proxy`
+/// (RFC 1928, no authentication).
+async fn connect(proxy: &str, target: &Target) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect(proxy).await?;
+
+    stream.write_all(&[0x05, 0x01, 0x00]).await?;
+    let mut greeting = [0u8; 2];
+    stream.read_exact(&mut greeting).await?;
+    if greeting != [0x05, 0x00] {
+        return Err(socks_error(
+            "proxy requires an unsupported authentication method",
+        ));
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    let port = match target {
+        Target::Ip(SocketAddr::V4(addr)) => {
+            request.push(0x01);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        Target::Ip(SocketAddr::V6(addr)) => {
+            request.push(0x04);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        Target::Domain(name, port) => {
+            let len = u8::try_from(name.len()).map_err(|_| socks_error("host name too long"))?;
+            request.push(0x03);
+            request.push(len);
+            request.extend_from_slice(name.as_bytes());
+            *port
+        }
+    };
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        return Err(socks_error(format!(
+            "proxy refused the connection (SOCKS5 reply {})",
+            reply[1]
+        )));
+    }
+    // Skip the bound address and port.
+    let bound_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        other => {
+            return Err(socks_error(format!(
+                "unknown SOCKS5 address type {}",
+                other
+            )))
+        }
+    };
+    let mut bound = vec![0u8; bound_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(stream)
+}
+
+/// Dial-only libp2p TCP transport through the proxy.
+#[derive(Debug, Clone)]
+pub struct Socks5Transport {
+    proxy: String,
+}
+
+impl Socks5Transport {
+    pub fn new(proxy: String) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Transport for Socks5Transport {
+    type Output = libp2p::tcp::tokio::TcpStream;
+    type Error = io::Error;
+    type ListenerUpgrade = futures::future::Pending<Result<Self::Output, Self::Error>>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(
+        &mut self,
+        _id: ListenerId,
+        addr: Multiaddr,
+    ) -> Result<(), TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn remove_listener(&mut self, _id: ListenerId) -> bool {
+        false
+    }
+
+    fn dial(
+        &mut self,
+        addr: Multiaddr,
+        _opts: DialOpts,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let Some(target) = target_of(&addr) else {
+            return Err(TransportError::MultiaddrNotSupported(addr));
+        };
+        let proxy = self.proxy.clone();
+        Ok(async move {
+            let stream = connect(&proxy, &target).await?;
+            Ok(libp2p::tcp::tokio::TcpStream(stream))
+        }
+        .boxed())
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn only_plain_tcp_addresses_are_proxied() {
+        let parse = |addr: &str| target_of(&addr.parse::<Multiaddr>().unwrap());
+        assert_eq!(
+            parse("/ip4/1.2.3.4/tcp/4001"),
+            Some(Target::Ip("1.2.3.4:4001".parse().unwrap()))
+        );
+        assert_eq!(
+            parse("/dns4/example.onion/tcp/80/p2p/12D3KooWDpJ7As7BWAwRMfu1VU2WCqNjvq387JEYKDBj4kx6nXTN"),
+            Some(Target::Domain("example.onion".to_string(), 80))
+        );
+        assert_eq!(parse("/ip4/1.2.3.4/udp/4001/quic-v1"), None);
+        assert_eq!(parse("/ip4/1.2.3.4/tcp/4001/ws"), None);
+
+        assert!(validate_proxy_addr("127.0.0.1:9050").is_ok());
+        assert!(validate_proxy_addr("[::1]:9050").is_ok());
+        assert!(validate_proxy_addr("localhost").is_err());
+        assert!(validate_proxy_addr("localhost:0").is_err());
+    }
+
+    #[tokio::test]
+    async fn connects_through_a_socks5_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            socket.write_all(&[0x05, 0x00]).await.unwrap();
+            let mut head = [0u8; 5];
+            socket.read_exact(&mut head).await.unwrap();
+            let mut rest = vec![0u8; head[4] as usize + 2];
+            socket.read_exact(&mut rest).await.unwrap();
+            socket
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            socket.write_all(b"hi").await.unwrap();
+            String::from_utf8(rest[..head[4] as usize].to_vec()).unwrap()
+        });
+
+        let mut stream = connect(&proxy, &Target::Domain("peer.example".to_string(), 4001))
+            .await
+            .expect("connect");
+        let mut hello = [0u8; 2];
+        stream.read_exact(&mut hello).await.unwrap();
+        assert_eq!(&hello, b"hi");
+        assert_eq!(server.await.unwrap(), "peer.example");
+    }
+}
@@ -0,0 +1,104 @@
+//! Per-peer connection quality from the ping behaviour.
+//!
+//! Every ping result (across all connections to a peer) goes into a window
+//! of the last [`WINDOW`] pings; latency and loss are computed over it. The
+//! manager emits the reports of connected peers as `peer-quality` on its
+//! heartbeat.
+
+use libp2p::PeerId;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+pub const WINDOW: usize = 20;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PeerQuality {
+    pub peer_id: String,
+    pub last_rtt_ms: Option<u64>,
+    pub avg_rtt_ms: Option<u64>,
+    /// Mean difference between consecutive round trips.
+    pub jitter_ms: Option<u64>,
+    pub loss_percent: f64,
+    pub samples: usize,
+}
+
+/// Recent ping outcomes per peer; `None` is a lost ping.
+#[derive(Debug, Default)]
+pub struct QualityRegistry {
+    by_peer: HashMap<PeerId, VecDeque<Option<Duration>>>,
+}
+
+impl QualityRegistry {
+    pub fn record(&mut self, peer: PeerId, rtt: Option<Duration>) {
+        let samples = self.by_peer.entry(peer).or_default();
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(rtt);
+    }
+
+    pub fn forget(&mut self, peer: &PeerId) {
+        self.by_peer.remove(peer);
+    }
+
+    pub fn report(&self, peer: &PeerId) -> Option<PeerQuality> {
+        let samples = self.by_peer.get(peer)?;
+        let rtts: Vec<u64> = samples
+            .iter()
+            .flatten()
+            .map(|rtt| rtt.as_millis() as u64)
+            .collect();
+        let lost = samples.len() - rtts.len();
+        let jitter_ms = (rtts.len() > 1).then(|| {
+            rtts.windows(2).map(|w| w[0].abs_diff(w[1])).sum::<u64>() / (rtts.len() as u64 - 1)
+        });
+        Some(PeerQuality {
+            peer_id: peer.to_string(),
+            last_rtt_ms: rtts.last().copied(),
+            avg_rtt_ms: (!rtts.is_empty()).then(|| rtts.iter().sum::<u64>() / rtts.len() as u64),
+            jitter_ms,
+            loss_percent: lost as f64 * 100.0 / samples.len() as f64,
+            samples: samples.len(),
+        })
+    }
+
+    pub fn reports(&self) -> Vec<PeerQuality> {
+        self.by_peer
+            .keys()
+            .filter_map(|peer| self.report(peer))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_and_loss_over_the_window() {
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let mut registry = QualityRegistry::default();
+        assert_eq!(registry.report(&peer), None);
+
+        for rtt in [Some(40), None, Some(60), Some(50)] {
+            registry.record(peer, rtt.map(Duration::from_millis));
+        }
+        let quality = registry.report(&peer).expect("report");
+        assert_eq!(quality.last_rtt_ms, Some(50));
+        assert_eq!(quality.avg_rtt_ms, Some(50));
+        assert_eq!(quality.jitter_ms, Some(15));
+        assert_eq!(quality.loss_percent, 25.0);
+
+        for _ in 0..WINDOW {
+            registry.record(peer, Some(Duration::from_millis(10)));
+        }
+        let quality = registry.report(&peer).expect("report");
+        assert_eq!((quality.samples, quality.loss_percent), (WINDOW, 0.0));
+
+        registry.forget(&peer);
+        assert!(registry.reports().is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+//! Reconnecting to friends after their connection drops.
+//!
+//! The manager remembers the addresses it dialed each friend at. When the
+//! last connection to a friend closes, the friend is scheduled here and
+//! redialed at those addresses with exponential backoff (plus jitter, so a
+//! network blip doesn't make every peer redial in lockstep) until a
+//! connection comes back or [`MAX_ATTEMPTS`] dials failed. Every transition
+//! is emitted to the UI as `peer-reconnect-state`.
+
+use libp2p::{Multiaddr, PeerId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+pub const BASE_DELAY: Duration = Duration::from_secs(2);
+pub const MAX_DELAY: Duration = Duration::from_secs(5 * 60);
+pub const MAX_ATTEMPTS: u32 = 10;
+/// Delays are scaled by a random factor within ±this fraction.
+pub const JITTER: f64 = 0.2;
+/// Most recent addresses kept per peer.
+const MAX_ADDRESSES: usize = 8;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ReconnectState {
+    Waiting { attempt: u32, retry_in_ms: u64 },
+    Dialing { attempt: u32 },
+    Connected,
+    GaveUp { attempts: u32 },
+}
+
+/// Payload of `peer-reconnect-state`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconnectEvent {
+    pub peer_id: String,
+    #[serde(flatten)]
+    pub state: ReconnectState,
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// Dials made so far.
+    attempt: u32,
+    /// `None` while a dial is in flight.
+    next_at: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+pub struct ReconnectScheduler {
+    /// Dialable addresses per peer, most recent first.
+    last_addrs: HashMap<PeerId, Vec<Multiaddr>>,
+    pending: HashMap<PeerId, Pending>,
+    /// Peers we hung up on ourselves; their next disconnect is final.
+    suppressed: HashSet<PeerId>,
+}
+
+/// Backoff before dial number `attempt + 1`; `jitter` is in `[-1, 1]`.
+pub fn delay(attempt: u32, jitter: f64) -> Duration {
+    let base = BASE_DELAY
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(MAX_DELAY);
+    base.mul_f64(1.0 + JITTER * jitter.clamp(-1.0, 1.0))
+}
+
+impl ReconnectScheduler {
+    pub fn remember(&mut self, peer: PeerId, addr: Multiaddr) {
+        let addrs = self.last_addrs.entry(peer).or_default();
+        addrs.retain(|known| *known != addr);
+        addrs.insert(0, addr);
+        addrs.truncate(MAX_ADDRESSES);
+    }
+
+    pub fn addresses(&self, peer: &PeerId) -> Vec<Multiaddr> {
+        self.last_addrs.get(peer).cloned().unwrap_or_default()
+    }
+
+    pub fn is_pending(&self, peer: &PeerId) -> bool {
+        self.pending.contains_key(peer)
+    }
+
+    /// Start reconnecting to `peer`, unless there is nowhere to dial it.
+    pub fn schedule(&mut self, peer: PeerId, now: Instant, jitter: f64) -> Option<ReconnectState> {
+        if self.suppressed.remove(&peer) || !self.last_addrs.contains_key(&peer) {
+            return None;
+        }
+        let wait = delay(0, jitter);
+        self.pending.insert(
+            peer,
+            Pending {
+                attempt: 0,
+                next_at: Some(now + wait),
+            },
+        );
+        Some(ReconnectState::Waiting {
+            attempt: 0,
+            retry_in_ms: wait.as_millis() as u64,
+        })
+    }
+
+    /// Peers whose backoff ran out, now marked as dialing, with the dial
+    /// number and the addresses to try.
+    pub fn due(&mut self, now: Instant) -> Vec<(PeerId, u32, Vec<Multiaddr>)> {
+        let mut due = Vec::new();
+        for (peer, pending) in self.pending.iter_mut() {
+            if pending.next_at.is_some_and(|at| at <= now) {
+                pending.next_at = None;
+                pending.attempt += 1;
+                let addrs = self.last_addrs.get(peer).cloned().unwrap_or_default();
+                due.push((*peer, pending.attempt, addrs));
+            }
+        }
+        due
+    }
+
+    /// A reconnect dial failed; back off further or give up.
+    pub fn failed(&mut self, peer: &PeerId, now: Instant, jitter: f64) -> Option<ReconnectState> {
+        let pending = self.pending.get_mut(peer)?;
+        if pending.next_at.is_some() {
+            // Not one of our dials.
+            return None;
+        }
+        if pending.attempt >= MAX_ATTEMPTS {
+            let attempts = pending.attempt;
+            self.pending.remove(peer);
+            return Some(ReconnectState::GaveUp { attempts });
+        }
+        let wait = delay(pending.attempt, jitter);
+        pending.next_at = Some(now + wait);
+        Some(ReconnectState::Waiting {
+            attempt: pending.attempt,
+            retry_in_ms: wait.as_millis() as u64,
+        })
+    }
+
+    /// Don't reconnect after the upcoming disconnect of `peer`.
+    pub fn suppress(&mut self, peer: PeerId) {
+        self.pending.remove(&peer);
+        self.suppressed.insert(peer);
+    }
+
+    /// `peer` is connected again, by our dial or theirs.
+    pub fn connected(&mut self, peer: &PeerId) -> Option<ReconnectState> {
+        self.suppressed.remove(peer);
+        self.pending.remove(peer).map(|_| ReconnectState::Connected)
+    }
+
+    /// Stop all reconnects; returns the peers that were pending.
+    pub fn cancel_all(&mut self) -> Vec<PeerId> {
+        self.pending.drain().map(|(peer, _)| peer).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_with_jitter_and_caps() {
+        assert_eq!(delay(0, 0.0), BASE_DELAY);
+        assert_eq!(delay(3, 0.0), BASE_DELAY * 8);
+        assert_eq!(delay(30, 0.0), MAX_DELAY);
+        assert_eq!(delay(0, 1.0), BASE_DELAY.mul_f64(1.0 + JITTER));
+        assert_eq!(delay(0, -5.0), BASE_DELAY.mul_f64(1.0 - JITTER));
+    }
+
+    #[test]
+    fn reconnects_until_connected_or_out_of_attempts() {
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let addr: Multiaddr = "/ip4/192.168.1.10/tcp/4001".parse().unwrap();
+        let now = Instant::now();
+        let mut scheduler = ReconnectScheduler::default();
+
+        // Nothing to dial yet.
+        assert_eq!(scheduler.schedule(peer, now, 0.0), None);
+
+        scheduler.remember(peer, addr.clone());
+        scheduler.remember(peer, addr.clone());
+        assert_eq!(scheduler.addresses(&peer), vec![addr.clone()]);
+        assert!(scheduler.schedule(peer, now, 0.0).is_some());
+        assert!(scheduler.due(now).is_empty());
+
+        let mut at = now;
+        for attempt in 1..=MAX_ATTEMPTS {
+            at += MAX_DELAY;
+            assert_eq!(scheduler.due(at), vec![(peer, attempt, vec![addr.clone()])]);
+            let state = scheduler.failed(&peer, at, 0.0);
+            if attempt < MAX_ATTEMPTS {
+                assert!(matches!(state, Some(ReconnectState::Waiting { .. })));
+            } else {
+                assert_eq!(state, Some(ReconnectState::GaveUp { attempts: attempt }));
+            }
+        }
+        assert!(!scheduler.is_pending(&peer));
+
+        scheduler.schedule(peer, now, 0.0);
+        assert_eq!(scheduler.connected(&peer), Some(ReconnectState::Connected));
+        assert_eq!(scheduler.connected(&peer), None);
+
+        // Hanging up ourselves doesn't trigger a reconnect.
+        scheduler.suppress(peer);
+        assert_eq!(scheduler.schedule(peer, now, 0.0), None);
+        assert!(scheduler.schedule(peer, now, 0.0).is_some());
+    }
+}
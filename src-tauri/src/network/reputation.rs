@@ -0,0 +1,135 @@
+//! Local peer reputation built from observed behaviour.
+//!
+//! Invalid gossip payloads, rate-limit trips (see `flood`) and failed DM deliveries
+//! each cost a peer some reputation. The score is persisted in the `peers`
+//! table, decays back toward neutral over time, and is fed to gossipsub as the
+//! application-specific score so badly behaving peers are pruned from meshes
+//! and eventually graylisted. Auto-connect and path upgrades use it to decide
+//! who gets dialed first (or at all).
+
+use crate::storage::db::PeerReputation;
+use libp2p::gossipsub::{PeerScoreParams, PeerScoreThresholds};
+use serde::Serialize;
+
+pub const MIN_SCORE: f64 = -100.0;
+/// Below this we stop dialing a peer; matches gossipsub's default graylist threshold.
+pub const DIAL_SKIP_THRESHOLD: f64 = -80.0;
+const SUSPECT_THRESHOLD: f64 = -20.0;
+/// Penalties halve every six hours without further misbehaviour.
+const HALF_LIFE_SECS: f64 = 6.0 * 3600.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerBehavior {
+    InvalidMessage,
+    RateLimited,
+    FailedDelivery,
+}
+
+impl PeerBehavior {
+    fn penalty(self) -> f64 {
+        match self {
+            Self::InvalidMessage => 10.0,
+            Self::RateLimited => 5.0,
+            Self::FailedDelivery => 1.0,
+        }
+    }
+}
+
+/// Score after decaying the stored value up to `now`.
+pub fn effective_score(rep: &PeerReputation, now: i64) -> f64 {
+    let elapsed = (now - rep.updated_at).max(0) as f64;
+    rep.score * 0.5f64.powf(elapsed / HALF_LIFE_SECS)
+}
+
+pub fn apply(rep: &mut PeerReputation, behavior: PeerBehavior, now: i64) {
+    rep.score = (effective_score(rep, now) - behavior.penalty()).max(MIN_SCORE);
+    rep.updated_at = now;
+    match behavior {
+        PeerBehavior::InvalidMessage => rep.invalid_messages += 1,
+        PeerBehavior::RateLimited => rep.rate_limit_trips += 1,
+        PeerBehavior::FailedDelivery => rep.failed_deliveries += 1,
+    }
+}
+
+/// Gossipsub scoring with our reputation as the application-specific score.
+pub fn gossipsub_scoring() -> (PeerScoreParams, PeerScoreThresholds) {
+    let params = PeerScoreParams {
+        // Reputation is already on gossipsub's threshold scale (0 to -100).
+        app_specific_weight: 1.0,
+        ..PeerScoreParams::default()
+    };
+    (params, PeerScoreThresholds::default())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Standing {
+    Good,
+    Suspect,
+    Graylisted,
+}
+
+pub fn standing(score: f64) -> Standing {
+    if score <= DIAL_SKIP_THRESHOLD {
+        Standing::Graylisted
+    } else if score <= SUSPECT_THRESHOLD {
+        Standing::Suspect
+    } else {
+        Standing::Good
+    }
+}
+
+/// Answer for `get_peer_security_info`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerSecurityInfo {
+    pub peer_id: String,
+    /// Whether the peer is in the local peers table (completed a handshake).
+    pub known: bool,
+    pub score: f64,
+    pub standing: Standing,
+    pub invalid_messages: i64,
+    pub rate_limit_trips: i64,
+    pub failed_deliveries: i64,
+    pub updated_at: i64,
+}
+
+impl PeerSecurityInfo {
+    pub fn new(peer_id: &str, known: bool, rep: Option<PeerReputation>, now: i64) -> Self {
+        let rep = rep.unwrap_or_default();
+        let score = effective_score(&rep, now);
+        Self {
+            peer_id: peer_id.to_string(),
+            known,
+            score,
+            standing: standing(score),
+            invalid_messages: rep.invalid_messages,
+            rate_limit_trips: rep.rate_limit_trips,
+            failed_deliveries: rep.failed_deliveries,
+            updated_at: rep.updated_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn penalties_accumulate_clamp_and_decay() {
+        let mut rep = PeerReputation::default();
+        apply(&mut rep, PeerBehavior::InvalidMessage, 1000);
+        apply(&mut rep, PeerBehavior::FailedDelivery, 1000);
+        assert_eq!(rep.score, -11.0);
+        assert_eq!((rep.invalid_messages, rep.failed_deliveries), (1, 1));
+        assert_eq!(standing(rep.score), Standing::Good);
+
+        let half_life = HALF_LIFE_SECS as i64;
+        assert!((effective_score(&rep, 1000 + half_life) + 5.5).abs() < 1e-9);
+
+        for _ in 0..20 {
+            apply(&mut rep, PeerBehavior::InvalidMessage, 2000);
+        }
+        assert_eq!(rep.score, MIN_SCORE);
+        assert_eq!(standing(rep.score), Standing::Graylisted);
+    }
+}
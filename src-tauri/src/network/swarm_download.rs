@@ -0,0 +1,209 @@
+//! Downloading one file from several peers at once.
+//!
+//! Group media is usually held by more than one member. When a group message
+//! carries a file, the manager asks the sender and every other connected
+//! member for the chunk manifest; each peer that answers with the same
+//! manifest becomes a source. Sources take blocks of consecutive chunks from a
+//! shared queue, at most [`MAX_IN_FLIGHT_PER_SOURCE`] at a time, so faster
+//! peers end up serving more of the file. A chunk that fails verification or
+//! isn't answered within [`CHUNK_TIMEOUT`] goes back to the front of the queue
+//! for another source; a source that sent a bad chunk is dropped.
+
+use libp2p::PeerId;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::network::direct_message::ChunkInfo;
+
+pub const MAX_IN_FLIGHT_PER_SOURCE: usize = 8;
+pub const CHUNK_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug)]
+struct InFlight {
+    peer: PeerId,
+    since: Instant,
+    chunk: ChunkInfo,
+}
+
+#[derive(Debug, Default)]
+pub struct SwarmDownload {
+    /// Chunk hashes in order, once the first manifest arrived.
+    manifest: Option<Vec<String>>,
+    queue: VecDeque<ChunkInfo>,
+    in_flight: HashMap<String, InFlight>,
+    /// Sources in the order they joined.
+    sources: Vec<PeerId>,
+    dropped: HashSet<PeerId>,
+    stored: HashSet<String>,
+}
+
+impl SwarmDownload {
+    pub fn has_manifest(&self) -> bool {
+        self.manifest.is_some()
+    }
+
+    /// Add `peer` as a source for `chunks`. The first manifest fills the
+    /// queue; later ones must match it. Returns whether `peer` was added.
+    pub fn add_source(&mut self, peer: PeerId, chunks: &[ChunkInfo]) -> bool {
+        if chunks.is_empty() || self.dropped.contains(&peer) {
+            return false;
+        }
+        let hashes: Vec<String> = chunks.iter().map(|c| c.chunk_hash.clone()).collect();
+        match &self.manifest {
+            Some(manifest) if *manifest != hashes => return false,
+            Some(_) => {}
+            None => {
+                self.manifest = Some(hashes);
+                let mut ordered = chunks.to_vec();
+                ordered.sort_by_key(|c| c.chunk_order);
+                self.queue = ordered.into();
+            }
+        }
+        if !self.sources.contains(&peer) {
+            self.sources.push(peer);
+        }
+        true
+    }
+
+    /// Assign queued chunks to sources with free slots.
+    pub fn next_requests(&mut self, now: Instant) -> Vec<(PeerId, ChunkInfo)> {
+        let mut requests = Vec::new();
+        for peer in self.sources.clone() {
+            let busy = self.in_flight.values().filter(|f| f.peer == peer).count();
+            for _ in busy..MAX_IN_FLIGHT_PER_SOURCE {
+                let Some(chunk) = self.queue.pop_front() else {
+                    return requests;
+                };
+                self.in_flight.insert(
+                    chunk.chunk_hash.clone(),
+                    InFlight {
+                        peer,
+                        since: now,
+                        chunk: chunk.clone(),
+                    },
+                );
+                requests.push((peer, chunk));
+            }
+        }
+        requests
+    }
+
+    pub fn chunk_stored(&mut self, chunk_hash: &str) {
+        self.in_flight.remove(chunk_hash);
+        self.queue.retain(|c| c.chunk_hash != chunk_hash);
+        self.stored.insert(chunk_hash.to_string());
+    }
+
+    /// A chunk failed verification: drop its source and fetch it elsewhere.
+    pub fn chunk_rejected(&mut self, chunk_hash: &str) -> Option<PeerId> {
+        let peer = self.in_flight.get(chunk_hash)?.peer;
+        self.remove_source(&peer);
+        Some(peer)
+    }
+
+    /// Stop using `peer`; its outstanding chunks are queued again.
+    pub fn remove_source(&mut self, peer: &PeerId) {
+        self.sources.retain(|p| p != peer);
+        self.dropped.insert(*peer);
+        let mut requeued: Vec<ChunkInfo> = self
+            .in_flight
+            .values()
+            .filter(|f| f.peer == *peer)
+            .map(|f| f.chunk.clone())
+            .collect();
+        self.in_flight.retain(|_, f| f.peer != *peer);
+        requeued.sort_by_key(|c| std::cmp::Reverse(c.chunk_order));
+        for chunk in requeued {
+            self.queue.push_front(chunk);
+        }
+    }
+
+    /// Queue chunks that were requested too long ago again. Returns how many.
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let expired: Vec<String> = self
+            .in_flight
+            .iter()
+            .filter(|(_, f)| now.saturating_duration_since(f.since) >= CHUNK_TIMEOUT)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        for hash in &expired {
+            if let Some(flight) = self.in_flight.remove(hash) {
+                self.queue.push_front(flight.chunk);
+            }
+        }
+        expired.len()
+    }
+
+    pub fn sources(&self) -> &[PeerId] {
+        &self.sources
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.manifest
+            .as_ref()
+            .is_some_and(|manifest| manifest.iter().all(|h| self.stored.contains(h)))
+    }
+
+    /// Chunks are missing and nobody is left to ask.
+    pub fn is_stalled(&self) -> bool {
+        self.has_manifest() && self.sources.is_empty() && !self.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> PeerId {
+        libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id()
+    }
+
+    fn manifest(n: i64) -> Vec<ChunkInfo> {
+        (0..n)
+            .map(|i| ChunkInfo {
+                chunk_hash: format!("c{}", i),
+                chunk_order: i,
+                chunk_size: 10,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_chunks_across_sources_and_recovers_from_bad_ones() {
+        let (a, b) = (peer(), peer());
+        let chunks = manifest(20);
+        let now = Instant::now();
+        let mut download = SwarmDownload::default();
+
+        assert!(download.add_source(a, &chunks));
+        assert!(download.add_source(b, &chunks));
+        assert!(!download.add_source(peer(), &manifest(3)));
+
+        let requests = download.next_requests(now);
+        assert_eq!(requests.len(), 2 * MAX_IN_FLIGHT_PER_SOURCE);
+        assert!(requests[..8].iter().all(|(p, _)| *p == a));
+        assert_eq!(requests[8].1.chunk_hash, "c8");
+        assert!(requests[8..].iter().all(|(p, _)| *p == b));
+        assert!(download.next_requests(now).is_empty());
+
+        // `b` sends garbage: its chunks go back to the front for `a`.
+        assert_eq!(download.chunk_rejected("c8"), Some(b));
+        assert!(!download.add_source(b, &chunks));
+        download.chunk_stored("c0");
+        assert_eq!(download.next_requests(now)[0], (a, chunks[8].clone()));
+
+        // Unanswered requests are retried.
+        assert_eq!(
+            download.expire(now + CHUNK_TIMEOUT),
+            MAX_IN_FLIGHT_PER_SOURCE
+        );
+
+        for chunk in &chunks {
+            download.chunk_stored(&chunk.chunk_hash);
+        }
+        assert!(download.is_finished());
+        assert!(!download.is_stalled());
+    }
+}
@@ -1,5 +1,4 @@
 use anyhow::{anyhow, Result};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 // You should replace this with your actual Client ID for the production app.
@@ -33,7 +32,7 @@ pub struct AuthState {
 }
 
 pub async fn start_device_flow() -> Result<AuthState> {
-    let client = Client::new();
+    let client = crate::network::proxy::http_client();
     let params = [
         ("client_id", CLIENT_ID),
         ("scope", "gist"), // Standard gist scope
@@ -62,7 +61,7 @@ pub async fn start_device_flow() -> Result<AuthState> {
 }
 
 pub async fn poll_for_token(device_code: &str) -> Result<String> {
-    let client = Client::new();
+    let client = crate::network::proxy::http_client();
     let params = [
         ("client_id", CLIENT_ID),
         ("device_code", device_code),
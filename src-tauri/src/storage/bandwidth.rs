@@ -0,0 +1,169 @@
+//! Bandwidth accounting and the optional monthly cap.
+//!
+//! The network manager reports the size of every direct message and gossip
+//! payload it sends or receives, per peer and protocol. Sizes are estimated
+//! from the decoded messages, so transport framing and encryption are not
+//! included. Totals are buffered in memory and added to daily rows of the
+//! `bandwidth_stats` table once a minute and whenever they are read. Once this
+//! month's total passes `UserConfig::bandwidth_monthly_cap_mb`, large file
+//! downloads are held back until the cap is raised or the month rolls over.
+
+use crate::AppHandle;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use super::db::{self, BandwidthStat};
+
+pub use rchat_core::storage::bandwidth::{ANY_PEER, DIRECT_MESSAGE, FILE_TRANSFER, GOSSIP};
+
+/// Downloads at least this large are paused while over the cap.
+pub const LARGE_TRANSFER_BYTES: i64 = 1024 * 1024;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// (peer id, protocol) -> (bytes sent, bytes received) since the last flush.
+static PENDING: Mutex<BTreeMap<(String, &'static str), (i64, i64)>> = Mutex::new(BTreeMap::new());
+static MONTHLY_CAP_BYTES: Mutex<Option<i64>> = Mutex::new(None);
+static CAP_EXCEEDED: AtomicBool = AtomicBool::new(false);
+
+fn record(peer_id: &str, protocol: &'static str, sent: usize, received: usize) {
+    if let Ok(mut pending) = PENDING.lock() {
+        let entry = pending
+            .entry((peer_id.to_string(), protocol))
+            .or_insert((0, 0));
+        entry.0 += sent as i64;
+        entry.1 += received as i64;
+    }
+}
+
+pub fn record_sent(peer_id: &str, protocol: &'static str, bytes: usize) {
+    record(peer_id, protocol, bytes, 0);
+}
+
+pub fn record_received(peer_id: &str, protocol: &'static str, bytes: usize) {
+    record(peer_id, protocol, 0, bytes);
+}
+
+/// `None` removes the cap. Takes effect on the next flush.
+pub fn set_monthly_cap_mb(cap_mb: Option<u64>) {
+    if let Ok(mut cap) = MONTHLY_CAP_BYTES.lock() {
+        *cap = cap_mb.map(|mb| mb.saturating_mul(1024 * 1024).min(i64::MAX as u64) as i64);
+    }
+}
+
+pub fn monthly_cap_bytes() -> Option<i64> {
+    MONTHLY_CAP_BYTES.lock().ok().and_then(|cap| *cap)
+}
+
+/// Whether this month's usage passed the cap, as of the last flush.
+pub fn cap_exceeded() -> bool {
+    CAP_EXCEEDED.load(Ordering::SeqCst)
+}
+
+/// Calendar date (UTC) of a unix timestamp, as `YYYY-MM-DD`.
+pub fn utc_day(unix_secs: i64) -> String {
+    let (year, month, day) = civil_from_days(unix_secs.div_euclid(SECS_PER_DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// First day of the UTC month containing `unix_secs`, as `YYYY-MM-DD`.
+pub fn month_start(unix_secs: i64) -> String {
+    let (year, month, _) = civil_from_days(unix_secs.div_euclid(SECS_PER_DAY));
+    format!("{:04}-{:02}-01", year, month)
+}
+
+/// Days since 1970-01-01 to (year, month, day), after Howard Hinnant's
+/// `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn now_unix_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Write buffered usage to today's rows and re-check the monthly cap.
+pub fn flush(conn: &Connection) -> anyhow::Result<()> {
+    let pending = match PENDING.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return Ok(()),
+    };
+    let now = now_unix_secs();
+    if !pending.is_empty() {
+        let day = utc_day(now);
+        let usage: Vec<BandwidthStat> = pending
+            .iter()
+            .map(|((peer_id, protocol), (sent, received))| BandwidthStat {
+                day: day.clone(),
+                peer_id: peer_id.clone(),
+                protocol: protocol.to_string(),
+                bytes_sent: *sent,
+                bytes_received: *received,
+            })
+            .collect();
+        if let Err(e) = db::add_bandwidth_usage(conn, &usage) {
+            // Put them back so the next flush retries.
+            for ((peer_id, protocol), (sent, received)) in pending {
+                record(&peer_id, protocol, sent as usize, received as usize);
+            }
+            return Err(e);
+        }
+    }
+
+    let exceeded = match monthly_cap_bytes() {
+        Some(cap) => db::bandwidth_total_since(conn, &month_start(now))? >= cap,
+        None => false,
+    };
+    if CAP_EXCEEDED.swap(exceeded, Ordering::SeqCst) != exceeded {
+        println!(
+            "[Bandwidth] Monthly cap {}",
+            if exceeded { "reached" } else { "cleared" }
+        );
+    }
+    Ok(())
+}
+
+pub fn spawn_flusher(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            let state = app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                continue;
+            };
+            if let Err(e) = flush(&conn) {
+                eprintln!("[Bandwidth] ⚠️ Failed to flush bandwidth stats: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_days_and_month_starts() {
+        assert_eq!(utc_day(0), "1970-01-01");
+        assert_eq!(utc_day(951_782_400), "2000-02-29");
+        assert_eq!(utc_day(1_791_590_399), "2026-10-09");
+        assert_eq!(month_start(1_791_590_399), "2026-10-01");
+        assert_eq!(utc_day(-1), "1969-12-31");
+    }
+}
@@ -0,0 +1,25 @@
+pub use rchat_core::storage::disk::*;
+
+use crate::AppHandle;
+use tauri::Emitter;
+
+/// Sample periodically and emit `storage-low` / `storage-recovered` on mode changes.
+pub fn spawn_monitor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match tauri::async_runtime::spawn_blocking(refresh).await {
+                Ok(Some(true)) => {
+                    eprintln!("[Storage] ⚠️ Low disk space, entering degraded mode");
+                    let _ = app_handle.emit("storage-low", status());
+                }
+                Ok(Some(false)) => {
+                    println!("[Storage] ✅ Disk space recovered, leaving degraded mode");
+                    let _ = app_handle.emit("storage-recovered", status());
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("[Storage] ❌ Free-space check failed: {}", e),
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
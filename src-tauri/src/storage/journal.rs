@@ -0,0 +1,291 @@
+//! Append-only encrypted message journal for personal archival.
+//!
+//! Messages of chats that opted in are appended to one file per UTC day
+//! (`journal/journal-YYYY-MM-DD.rjl`). Each line is a JSON record holding the
+//! base64 ciphertext and nonce of a single entry, so files can be copied or
+//! rotated without touching the live database.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::calendar::day_label;
+use super::db::Message;
+
+const JOURNAL_DIR: &str = "journal";
+const JOURNAL_FILE_PREFIX: &str = "journal-";
+const JOURNAL_FILE_EXT: &str = "rjl";
+
+/// Plaintext form of one journal record.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub direction: String, // "sent" or "received"
+    pub journaled_at: i64,
+    pub msg_id: String,
+    pub chat_id: String,
+    pub peer_id: String,
+    pub sender_alias: Option<String>,
+    pub timestamp: i64,
+    pub content_type: String,
+    pub text_content: Option<String>,
+    pub file_hash: Option<String>,
+}
+
+impl JournalEntry {
+    pub fn from_message(msg: &Message, journaled_at: i64) -> Self {
+        let (content_type, text_content, file_hash) = msg.content.clone().into_parts();
+        Self {
+            direction: if msg.peer_id == "Me" {
+                "sent".to_string()
+            } else {
+                "received".to_string()
+            },
+            journaled_at,
+            msg_id: msg.id.clone(),
+            chat_id: msg.chat_id.clone(),
+            peer_id: msg.peer_id.clone(),
+            sender_alias: msg.sender_alias.clone(),
+            timestamp: msg.timestamp,
+            content_type: content_type.to_string(),
+            text_content,
+            file_hash,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalLine {
+    ciphertext: String,
+    nonce: String,
+}
+
+/// Whether `day` has the `YYYY-MM-DD` shape of [`day_label`]. Checked before a
+/// caller-supplied day becomes part of a file path.
+fn is_day_label(day: &str) -> bool {
+    let bytes = day.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+fn journal_file_name(day: &str) -> String {
+    format!("{}{}.{}", JOURNAL_FILE_PREFIX, day, JOURNAL_FILE_EXT)
+}
+
+/// Holds the journal directory and the key derived from the unlocked vault.
+pub struct JournalWriter {
+    dir: PathBuf,
+    key: Mutex<Option<[u8; 32]>>,
+}
+
+impl JournalWriter {
+    pub fn new(app_dir: &Path) -> Self {
+        Self {
+            dir: app_dir.join(JOURNAL_DIR),
+            key: Mutex::new(None),
+        }
+    }
+
+    /// Install (or clear, on lock/reset) the journal encryption key.
+    pub fn set_key(&self, key: Option<[u8; 32]>) {
+        if let Ok(mut slot) = self.key.lock() {
+            *slot = key;
+        }
+    }
+
+    fn current_key(&self) -> Option<[u8; 32]> {
+        self.key.lock().ok().and_then(|k| *k)
+    }
+
+    /// Append one entry to the file for the day it was journaled.
+    pub fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let key = self
+            .current_key()
+            .ok_or(crate::error::RChatError::VaultLocked)?;
+        let plain = serde_json::to_vec(entry)?;
+        let (ciphertext, nonce) = rvault_core::crypto::encrypt_with_key(&key, &plain)
+            .map_err(|e| anyhow::anyhow!("Journal encryption failed: {}", e))?;
+        let mut line = serde_json::to_string(&JournalLine { ciphertext, nonce })?;
+        line.push('\n');
+
+        std::fs::create_dir_all(&self.dir).context("Failed to create journal directory")?;
+        let path = self
+            .dir
+            .join(journal_file_name(&day_label(entry.journaled_at)));
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open journal file {}", path.display()))?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Day labels of all journal files, oldest first.
+    pub fn list_days(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut days = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(day) = name
+                .strip_prefix(JOURNAL_FILE_PREFIX)
+                .and_then(|rest| rest.strip_suffix(&format!(".{}", JOURNAL_FILE_EXT)))
+                .filter(|day| is_day_label(day))
+            {
+                days.push(day.to_string());
+            }
+        }
+        days.sort();
+        Ok(days)
+    }
+
+    /// Decrypt every entry journaled on `day` (`YYYY-MM-DD`).
+    pub fn read_day(&self, day: &str) -> Result<Vec<JournalEntry>> {
+        let key = self
+            .current_key()
+            .ok_or(crate::error::RChatError::VaultLocked)?;
+        self.read_day_with(&key, day)
+    }
+
+    /// Re-encrypt every journal file written under `old_key` with the current
+    /// key, e.g. after the vault key changed. Returns the number of days.
+    pub fn rekey(&self, old_key: &[u8; 32]) -> Result<usize> {
+        let key = self
+            .current_key()
+            .ok_or(crate::error::RChatError::VaultLocked)?;
+        let days = self.list_days()?;
+        for day in &days {
+            let mut out = String::new();
+            for entry in self.read_day_with(old_key, day)? {
+                let plain = serde_json::to_vec(&entry)?;
+                let (ciphertext, nonce) = rvault_core::crypto::encrypt_with_key(&key, &plain)
+                    .map_err(|e| anyhow::anyhow!("Journal encryption failed: {}", e))?;
+                out.push_str(&serde_json::to_string(&JournalLine { ciphertext, nonce })?);
+                out.push('\n');
+            }
+            super::recovery::write_atomic(&self.dir.join(journal_file_name(day)), out.as_bytes())?;
+        }
+        Ok(days.len())
+    }
+
+    fn read_day_with(&self, key: &[u8; 32], day: &str) -> Result<Vec<JournalEntry>> {
+        if !is_day_label(day) {
+            return Err(crate::error::RChatError::InvalidInput(format!(
+                "Invalid journal day: {}",
+                day
+            ))
+            .into());
+        }
+        let path = self.dir.join(journal_file_name(day));
+        let file = std::fs::File::open(&path).with_context(|| format!("No journal for {}", day))?;
+
+        let mut entries = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: JournalLine = serde_json::from_str(&line)?;
+            let plain =
+                rvault_core::crypto::decrypt_with_key(key, &record.ciphertext, &record.nonce)
+                    .map_err(|e| anyhow::anyhow!("Journal decryption failed: {}", e))?;
+            entries.push(serde_json::from_str(&plain)?);
+        }
+        Ok(entries)
+    }
+}
+
+/// Journal `msg` if its chat opted in. Failures are logged, never surfaced to
+/// the messaging path.
+pub fn record_message(writer: &JournalWriter, conn: &Connection, msg: &Message) {
+    if !super::db::is_chat_journaled(conn, &msg.chat_id) {
+        return;
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    if let Err(e) = writer.append(&JournalEntry::from_message(msg, now)) {
+        eprintln!("[Journal] ❌ Failed to journal message {}: {}", msg.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_day_rejects_anything_but_a_date() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let writer = JournalWriter::new(dir.path());
+        writer.set_key(Some([1u8; 32]));
+        assert!(is_day_label("2024-01-01"));
+        for day in [
+            "../secrets",
+            "2024-01-01/../x",
+            "2024-1-01",
+            "2024_01_01",
+            "",
+        ] {
+            let err = writer.read_day(day).expect_err("invalid day");
+            assert!(matches!(
+                crate::error::RChatError::from(err),
+                crate::error::RChatError::InvalidInput(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn entry_direction_follows_sender() {
+        let mut msg = Message {
+            id: "1-1".to_string(),
+            chat_id: "group:x".to_string(),
+            peer_id: "Me".to_string(),
+            timestamp: 1,
+            content: crate::chat::message::MessageContent::Text {
+                text: "hi".to_string(),
+            },
+            status: "delivered".to_string(),
+            content_metadata: None,
+            sender_alias: None,
+            logical: 0,
+        };
+        assert_eq!(JournalEntry::from_message(&msg, 5).direction, "sent");
+        msg.peer_id = "12D3KooW".to_string();
+        assert_eq!(JournalEntry::from_message(&msg, 5).direction, "received");
+    }
+
+    #[test]
+    fn rekey_keeps_entries_readable() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let writer = JournalWriter::new(dir.path());
+        let old_key = [1u8; 32];
+        writer.set_key(Some(old_key));
+        let entry = JournalEntry {
+            direction: "sent".to_string(),
+            journaled_at: 1_704_067_200,
+            msg_id: "1-1".to_string(),
+            chat_id: "group:x".to_string(),
+            peer_id: "Me".to_string(),
+            sender_alias: None,
+            timestamp: 1,
+            content_type: "text".to_string(),
+            text_content: Some("hi".to_string()),
+            file_hash: None,
+        };
+        writer.append(&entry).expect("append");
+
+        writer.set_key(Some([2u8; 32]));
+        assert!(writer.read_day("2024-01-01").is_err());
+        assert_eq!(writer.rekey(&old_key).expect("rekey"), 1);
+        assert_eq!(writer.read_day("2024-01-01").expect("read"), vec![entry]);
+    }
+}
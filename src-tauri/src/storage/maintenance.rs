@@ -0,0 +1,157 @@
+//! SQLite upkeep: WAL checkpoints, VACUUM/ANALYZE and integrity checks.
+//!
+//! In WAL mode SQLite only checkpoints automatically, and never shrinks the
+//! `-wal` file, so a long session can leave it much larger than the database.
+//! [`spawn_checkpointer`] truncates it every [`CHECKPOINT_INTERVAL`].
+//! [`optimize`] (the `optimize_database` command) additionally rebuilds the
+//! file and refreshes the query planner statistics, and [`health`] runs the
+//! full `PRAGMA integrity_check` for the UI.
+
+use crate::AppHandle;
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// `integrity_check` stops after this many problems.
+const MAX_INTEGRITY_ERRORS: u32 = 100;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckpointReport {
+    /// A reader or writer blocked part of the checkpoint.
+    pub busy: bool,
+    pub wal_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OptimizeReport {
+    pub size_before: u64,
+    pub size_after: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DbHealth {
+    pub ok: bool,
+    /// `["ok"]`, or the problems `integrity_check` found.
+    pub integrity: Vec<String>,
+    pub size_bytes: u64,
+    pub wal_bytes: u64,
+    /// Unused pages that VACUUM would reclaim.
+    pub free_bytes: u64,
+    pub schema_version: u32,
+}
+
+fn pragma_i64(conn: &Connection, pragma: &str) -> Result<i64> {
+    Ok(conn.query_row(&format!("PRAGMA {}", pragma), [], |row| row.get(0))?)
+}
+
+fn size_bytes(conn: &Connection) -> Result<u64> {
+    Ok((pragma_i64(conn, "page_count")? * pragma_i64(conn, "page_size")?).max(0) as u64)
+}
+
+fn wal_bytes(conn: &Connection) -> u64 {
+    conn.path()
+        .filter(|path| !path.is_empty())
+        .and_then(|path| std::fs::metadata(format!("{}-wal", path)).ok())
+        .map(|meta| meta.len())
+        .unwrap_or(0)
+}
+
+/// Copy the WAL into the database and truncate it.
+pub fn checkpoint(conn: &Connection) -> Result<CheckpointReport> {
+    Ok(
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+            Ok(CheckpointReport {
+                busy: row.get::<_, i64>(0)? != 0,
+                wal_frames: row.get(1)?,
+                checkpointed_frames: row.get(2)?,
+            })
+        })?,
+    )
+}
+
+/// Checkpoint, rebuild the file without free pages and refresh statistics.
+pub fn optimize(conn: &Connection) -> Result<OptimizeReport> {
+    let started = Instant::now();
+    let size_before = size_bytes(conn)? + wal_bytes(conn);
+    checkpoint(conn)?;
+    conn.execute_batch("VACUUM; ANALYZE; PRAGMA optimize;")?;
+    checkpoint(conn)?;
+    Ok(OptimizeReport {
+        size_before,
+        size_after: size_bytes(conn)? + wal_bytes(conn),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+pub fn health(conn: &Connection) -> Result<DbHealth> {
+    let mut stmt = conn.prepare(&format!("PRAGMA integrity_check({})", MAX_INTEGRITY_ERRORS))?;
+    let integrity: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(DbHealth {
+        ok: integrity == ["ok"],
+        integrity,
+        size_bytes: size_bytes(conn)?,
+        wal_bytes: wal_bytes(conn),
+        free_bytes: (pragma_i64(conn, "freelist_count")? * pragma_i64(conn, "page_size")?).max(0)
+            as u64,
+        schema_version: super::migrations::current_version(conn)?,
+    })
+}
+
+pub fn spawn_checkpointer(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECKPOINT_INTERVAL).await;
+            let state = app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                continue;
+            };
+            match checkpoint(&conn) {
+                Ok(report) if report.busy => {
+                    println!("[DB] ⏳ WAL checkpoint was busy, retrying later");
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[DB] ⚠️ WAL checkpoint failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn checkpoint_truncates_wal_and_optimize_reclaims_space() {
+        let dir = tempdir().unwrap();
+        let conn = Connection::open(dir.path().join("test.sqlite")).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (data BLOB);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 200)
+             INSERT INTO t SELECT zeroblob(4096) FROM n;",
+        )
+        .unwrap();
+        assert!(wal_bytes(&conn) > 0);
+
+        let report = checkpoint(&conn).unwrap();
+        assert!(!report.busy);
+        assert_eq!(wal_bytes(&conn), 0);
+
+        conn.execute("DELETE FROM t", []).unwrap();
+        let before = health(&conn).unwrap();
+        assert!(before.ok);
+        assert!(before.free_bytes > 0);
+
+        let report = optimize(&conn).unwrap();
+        assert!(report.size_after < report.size_before);
+        assert_eq!(health(&conn).unwrap().free_bytes, 0);
+    }
+}
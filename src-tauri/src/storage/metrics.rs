@@ -0,0 +1,80 @@
+//! Local-only usage metrics.
+//!
+//! Counters (messages sent, transfers, reconnects, errors by type) are bumped
+//! in memory from anywhere in the app and flushed to the `usage_metrics` table
+//! periodically and whenever they are read. Nothing here is ever sent to peers
+//! or any server; it exists so users and developers can see how the app behaves
+//! over time.
+
+use crate::AppHandle;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use super::db;
+
+pub const MESSAGES_SENT: &str = "messages_sent";
+pub const MESSAGES_RECEIVED: &str = "messages_received";
+pub const TRANSFERS_STARTED: &str = "transfers_started";
+pub const TRANSFERS_COMPLETED: &str = "transfers_completed";
+pub const RECONNECTS: &str = "reconnects";
+/// Prefix for per-type error counters, e.g. `error.dm_outbound`.
+pub const ERROR_PREFIX: &str = "error.";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+static PENDING: Mutex<BTreeMap<String, i64>> = Mutex::new(BTreeMap::new());
+
+pub fn record(metric: &str) {
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending.entry(metric.to_string()).or_insert(0) += 1;
+    }
+}
+
+pub fn record_error(kind: &str) {
+    record(&format!("{}{}", ERROR_PREFIX, kind));
+}
+
+/// Write buffered increments to the database.
+pub fn flush(conn: &Connection) -> anyhow::Result<()> {
+    let pending = match PENDING.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return Ok(()),
+    };
+    if pending.is_empty() {
+        return Ok(());
+    }
+    if let Err(e) = db::add_usage_metrics(conn, &pending) {
+        // Put them back so the next flush retries.
+        if let Ok(mut current) = PENDING.lock() {
+            for (metric, count) in pending {
+                *current.entry(metric).or_insert(0) += count;
+            }
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Drop buffered increments, for a reset.
+pub fn discard_pending() {
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.clear();
+    }
+}
+
+pub fn spawn_flusher(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            let state = app_handle.state::<crate::AppState>();
+            let Ok(conn) = state.db_conn.lock() else {
+                continue;
+            };
+            if let Err(e) = flush(&conn) {
+                eprintln!("[Metrics] ⚠️ Failed to flush usage metrics: {}", e);
+            }
+        }
+    });
+}
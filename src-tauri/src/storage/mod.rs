@@ -1,4 +1,10 @@
-pub mod config;
-pub mod db;
-pub mod object;
-pub mod theme;
+pub mod bandwidth;
+pub mod disk;
+pub mod journal;
+pub mod maintenance;
+pub mod metrics;
+pub mod quota;
+
+pub use rchat_core::storage::{
+    calendar, config, config_migration, db, escrow, keychain, migrations, object, recovery, theme,
+};
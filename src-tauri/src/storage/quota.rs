@@ -0,0 +1,277 @@
+//! Disk usage reporting and the received-media cache cap.
+//!
+//! Media other peers sent us can be fetched again from them, so it is treated
+//! as a cache. With `UserConfig::media_cache_cap_mb` set, complete received
+//! files beyond the cap are evicted least recently used first: their chunk
+//! files are deleted and the file is marked incomplete, but the `files` and
+//! `file_chunks` rows stay so `refetch_media` can download it again. Our own
+//! uploads, stickers and avatars are never evicted, and chunks shared with a
+//! file we keep stay on disk.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::object::get_chunks_dir;
+
+static CACHE_CAP_BYTES: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Received files that may be evicted: complete, referenced by a message from
+/// someone else and not by one of ours, a sticker or an avatar.
+const EVICTABLE_FILES: &str = "
+    SELECT f.file_hash, COALESCE(f.size_bytes, 0)
+    FROM files f
+    WHERE f.is_complete = 1
+      AND EXISTS (SELECT 1 FROM messages m WHERE m.file_hash = f.file_hash AND m.peer_id != 'Me')
+      AND NOT EXISTS (SELECT 1 FROM messages m WHERE m.file_hash = f.file_hash AND m.peer_id = 'Me')
+      AND NOT EXISTS (SELECT 1 FROM stickers s WHERE s.file_hash = f.file_hash)
+      AND NOT EXISTS (SELECT 1 FROM peers p WHERE p.avatar_hash = f.file_hash)
+    ORDER BY COALESCE(
+        f.last_accessed_at,
+        (SELECT MAX(m.timestamp) FROM messages m WHERE m.file_hash = f.file_hash),
+        0
+    ) ASC";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMediaUsage {
+    pub chat_id: String,
+    pub files: i64,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    pub database_bytes: u64,
+    pub chunks_bytes: u64,
+    /// Media stored per chat, largest first. Files shared by several chats
+    /// count in each of them.
+    pub chats: Vec<ChatMediaUsage>,
+    /// Complete received files that count against the cache cap.
+    pub media_cache_bytes: u64,
+    pub media_cache_cap_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvictionReport {
+    pub evicted_files: usize,
+    pub removed_chunks: usize,
+    pub freed_bytes: u64,
+}
+
+/// `None` removes the cap.
+pub fn set_cache_cap_mb(cap_mb: Option<u64>) {
+    if let Ok(mut cap) = CACHE_CAP_BYTES.lock() {
+        *cap = cap_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    }
+}
+
+pub fn cache_cap_bytes() -> Option<u64> {
+    CACHE_CAP_BYTES.lock().ok().and_then(|cap| *cap)
+}
+
+fn evictable_files(conn: &Connection) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare(EVICTABLE_FILES)?;
+    let files = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(files)
+}
+
+pub fn usage(conn: &Connection, root_dir: Option<PathBuf>) -> Result<StorageUsage> {
+    let database_bytes: i64 = conn.query_row(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        [],
+        |row| row.get(0),
+    )?;
+
+    let chunks_dir = get_chunks_dir(root_dir)?;
+    let mut chunks_bytes = 0;
+    for entry in fs::read_dir(&chunks_dir).context("Failed to list chunks directory")? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            chunks_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT m.chat_id, COUNT(*), COALESCE(SUM(f.size_bytes), 0)
+         FROM (SELECT DISTINCT chat_id, file_hash FROM messages WHERE file_hash IS NOT NULL) m
+         JOIN files f ON f.file_hash = m.file_hash
+         WHERE f.is_complete = 1
+         GROUP BY m.chat_id
+         ORDER BY 3 DESC",
+    )?;
+    let chats = stmt
+        .query_map([], |row| {
+            Ok(ChatMediaUsage {
+                chat_id: row.get(0)?,
+                files: row.get(1)?,
+                bytes: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(StorageUsage {
+        database_bytes: database_bytes.max(0) as u64,
+        chunks_bytes,
+        chats,
+        media_cache_bytes: evictable_files(conn)?
+            .iter()
+            .map(|(_, size)| (*size).max(0) as u64)
+            .sum(),
+        media_cache_cap_bytes: cache_cap_bytes(),
+    })
+}
+
+/// Evict least recently used received media until it fits in `cap_bytes`.
+pub fn evict_received_media(
+    conn: &Connection,
+    cap_bytes: u64,
+    root_dir: Option<PathBuf>,
+) -> Result<EvictionReport> {
+    let candidates = evictable_files(conn)?;
+    let mut cached: u64 = candidates
+        .iter()
+        .map(|(_, size)| (*size).max(0) as u64)
+        .sum();
+    let mut report = EvictionReport::default();
+    if cached <= cap_bytes {
+        return Ok(report);
+    }
+
+    let chunks_dir = get_chunks_dir(root_dir)?;
+    let mut references: HashMap<String, usize> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT chunk_hash FROM file_chunks")?;
+    for chunk_hash in stmt.query_map([], |row| row.get::<_, String>(0))? {
+        *references.entry(chunk_hash?).or_default() += 1;
+    }
+
+    let mut chunks_stmt =
+        conn.prepare("SELECT chunk_hash FROM file_chunks WHERE file_hash = ?1")?;
+    for (file_hash, size) in candidates {
+        if cached <= cap_bytes {
+            break;
+        }
+        let chunk_hashes: Vec<String> = chunks_stmt
+            .query_map([&file_hash], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        conn.execute(
+            "UPDATE files SET is_complete = 0 WHERE file_hash = ?1",
+            [&file_hash],
+        )?;
+        for chunk_hash in chunk_hashes {
+            let Some(count) = references.get_mut(&chunk_hash) else {
+                continue;
+            };
+            *count -= 1;
+            if *count > 0 {
+                continue;
+            }
+            let path = chunks_dir.join(&chunk_hash);
+            let Ok(meta) = fs::metadata(&path) else {
+                continue;
+            };
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove cached chunk {}", chunk_hash))?;
+            report.removed_chunks += 1;
+            report.freed_bytes += meta.len();
+        }
+        report.evicted_files += 1;
+        cached = cached.saturating_sub(size.max(0) as u64);
+    }
+    Ok(report)
+}
+
+/// Apply the configured cap, if any.
+pub fn enforce_cache_cap(conn: &Connection) -> Result<EvictionReport> {
+    match cache_cap_bytes() {
+        Some(cap_bytes) => evict_received_media(conn, cap_bytes, None),
+        None => Ok(EvictionReport::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::object::create;
+    use tempfile::tempdir;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE files (
+                 file_hash TEXT PRIMARY KEY,
+                 file_name TEXT,
+                 mime_type TEXT,
+                 size_bytes INTEGER,
+                 is_complete BOOLEAN DEFAULT 0,
+                 last_accessed_at INTEGER
+             );
+             CREATE TABLE file_chunks (
+                 file_hash TEXT NOT NULL,
+                 chunk_order INTEGER NOT NULL,
+                 chunk_hash TEXT NOT NULL,
+                 chunk_size INTEGER NOT NULL,
+                 PRIMARY KEY (file_hash, chunk_order)
+             );
+             CREATE TABLE messages (
+                 id TEXT PRIMARY KEY,
+                 chat_id TEXT NOT NULL,
+                 peer_id TEXT NOT NULL,
+                 timestamp INTEGER NOT NULL,
+                 file_hash TEXT
+             );
+             CREATE TABLE stickers (file_hash TEXT PRIMARY KEY);
+             CREATE TABLE peers (id TEXT PRIMARY KEY, avatar_hash TEXT);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn evicts_least_recently_used_received_media_only() {
+        let conn = setup_test_db();
+        let temp = tempdir().unwrap();
+        let root = Some(temp.path().to_path_buf());
+
+        let old = create(&conn, &[1u8; 20_000], None, None, root.clone()).unwrap();
+        let recent = create(&conn, &[2u8; 20_000], None, None, root.clone()).unwrap();
+        let mine = create(&conn, &[3u8; 20_000], None, None, root.clone()).unwrap();
+        conn.execute_batch(&format!(
+            "INSERT INTO messages VALUES ('1', 'c1', 'peer', 100, '{old}');
+             INSERT INTO messages VALUES ('2', 'c1', 'peer', 200, '{recent}');
+             INSERT INTO messages VALUES ('3', 'c2', 'Me', 50, '{mine}');
+             UPDATE files SET last_accessed_at = 300 WHERE file_hash = '{old}';"
+        ))
+        .unwrap();
+
+        let before = usage(&conn, root.clone()).unwrap();
+        assert_eq!(before.media_cache_bytes, 40_000);
+        assert_eq!(before.chats[0].chat_id, "c1");
+        assert_eq!(before.chats[0].bytes, 40_000);
+
+        let report = evict_received_media(&conn, 25_000, root.clone()).unwrap();
+        assert_eq!(report.evicted_files, 1);
+        assert!(report.freed_bytes > 0);
+        let complete = |hash: &str| -> bool {
+            conn.query_row(
+                "SELECT is_complete FROM files WHERE file_hash = ?1",
+                [hash],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        // `old` was viewed after `recent` arrived, so `recent` goes first.
+        assert!(!complete(&recent));
+        assert!(complete(&old));
+        assert!(complete(&mine));
+
+        let report = evict_received_media(&conn, 0, root).unwrap();
+        assert_eq!(report.evicted_files, 1);
+        assert!(complete(&mine));
+    }
+}
@@ -1,3 +1,5 @@
+import { errorMessage } from "../tauri/errors";
+
 export type MediaSupportSnapshot = {
   mediaDevices: boolean;
   getUserMedia: boolean;
@@ -51,9 +53,5 @@ export function describeMediaError(error: unknown): string {
     }
   }
 
-  if (error instanceof Error && error.message) {
-    return error.message;
-  }
-
-  return String(error || "Unknown media device error");
+  return errorMessage(error, "Unknown media device error");
 }
@@ -2,6 +2,7 @@ import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { get, writable } from "svelte/store";
 import {
   api,
+  errorMessage,
   type AuthStatus,
   type ConnectivityMode,
   type ConnectivitySettings,
@@ -56,16 +57,6 @@ let protectedCleanups: UnlistenFn[] = [];
 let appReadyPromise: Promise<boolean> | null = null;
 let sessionRefreshSeq = 0;
 
-function startupErrorMessage(e: unknown): string {
-  if (e instanceof Error) return e.message;
-  if (typeof e === "string") return e;
-  try {
-    return JSON.stringify(e);
-  } catch {
-    return String(e);
-  }
-}
-
 function logStartupFailure(message: string) {
   const line = `[Startup] ${message}`;
   void api.frontendLog(line).catch(() => {});
@@ -136,7 +127,7 @@ export async function refreshAppSession(): Promise<boolean> {
       return appReadyPromise ?? get(appSession).appReady;
     }
 
-    const message = startupErrorMessage(e);
+    const message = errorMessage(e);
     console.error("App session refresh failed:", e);
     logStartupFailure(`session refresh failed: ${message}`);
     resetProtectedStores();
@@ -190,7 +181,7 @@ export async function ensureAppReady(freshAuthStatus?: AuthStatus): Promise<bool
       }));
       return true;
     } catch (e) {
-      const message = startupErrorMessage(e);
+      const message = errorMessage(e);
       console.error("App startup failed:", e);
       logStartupFailure(`app startup failed: ${message}`);
       resetProtectedStores();
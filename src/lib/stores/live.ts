@@ -6,6 +6,7 @@ import { screenBroadcastCapabilitiesFromChecks } from "$lib/stores/liveSupport";
 import { isChatConnected, presencePeerKey } from "$lib/stores/presence";
 import {
   api,
+  errorMessage,
   type BroadcastState,
   type ScreenBroadcastProfile,
   type VoiceCallState,
@@ -90,7 +91,7 @@ async function detectVideoCallSupport(): Promise<{
   } catch (e) {
     return {
       supported: false,
-      reason: e instanceof Error ? e.message : "Native camera capture check failed.",
+      reason: errorMessage(e, "Native camera capture check failed."),
     };
   }
 }
@@ -133,8 +134,7 @@ async function detectScreenBroadcastSupport(): Promise<{
       decodeSupported: true,
       decodeReason: null,
       captureSupported: false,
-      captureReason:
-        e instanceof Error ? e.message : "Native screen capture support check failed.",
+      captureReason: errorMessage(e, "Native screen capture support check failed."),
     });
   }
 }
@@ -1,5 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
 
+export {
+  errorMessage,
+  isCommandError,
+  type CommandError,
+  type CommandErrorCode,
+} from "./errors";
+
 export const COMMANDS = {
   saveApiToken: "save_api_token",
   checkAuthStatus: "check_auth_status",
@@ -106,6 +113,124 @@ export const COMMANDS = {
   endScreenBroadcast: "end_screen_broadcast",
   getBroadcastState: "get_broadcast_state",
   getConnectedChatIds: "get_connected_chat_ids",
+  configureChatBridge: "configure_chat_bridge",
+  getChatBridge: "get_chat_bridge",
+  setChatBridgeEnabled: "set_chat_bridge_enabled",
+  removeChatBridge: "remove_chat_bridge",
+  listBridgeIdentities: "list_bridge_identities",
+  setBridgeIdentity: "set_bridge_identity",
+  setChatJournaling: "set_chat_journaling",
+  getJournaledChats: "get_journaled_chats",
+  listJournalDays: "list_journal_days",
+  readJournalDay: "read_journal_day",
+  exportChat: "export_chat",
+  getChatSettings: "get_chat_settings",
+  setChatNotificationPriority: "set_chat_notification_priority",
+  setChatNotificationSound: "set_chat_notification_sound",
+  getReplySuggestions: "get_reply_suggestions",
+  getReplySuggestionSettings: "get_reply_suggestion_settings",
+  setReplySuggestionSettings: "set_reply_suggestion_settings",
+  getNetworkDiagnostics: "get_network_diagnostics",
+  getMessageRequestScores: "get_message_request_scores",
+  getLocalPeers: "get_local_peers",
+  setChatMute: "set_chat_mute",
+  setChatNotificationLevel: "set_chat_notification_level",
+  getDraft: "get_draft",
+  saveDraft: "save_draft",
+  getConnectionPath: "get_connection_path",
+  getPeerSecurityInfo: "get_peer_security_info",
+  setMessageStarred: "set_message_starred",
+  getStarredMessages: "get_starred_messages",
+  getStorageStatus: "get_storage_status",
+  runStorageGc: "run_storage_gc",
+  getStartupRecoveryReport: "get_startup_recovery_report",
+  setPeerAlias: "set_peer_alias",
+  setPeerAvatar: "set_peer_avatar",
+  getPeerAvatar: "get_peer_avatar",
+  addSelfTask: "add_self_task",
+  setTaskDone: "set_task_done",
+  getSelfTasks: "get_self_tasks",
+  scheduleReminder: "schedule_reminder",
+  cancelReminder: "cancel_reminder",
+  getReminders: "get_reminders",
+  getTranslationSettings: "get_translation_settings",
+  setTranslationSettings: "set_translation_settings",
+  setChatSendTranslation: "set_chat_send_translation",
+  getUsageMetrics: "get_usage_metrics",
+  resetUsageMetrics: "reset_usage_metrics",
+  setChatHidden: "set_chat_hidden",
+  revealHiddenChats: "reveal_hidden_chats",
+  concealHiddenChats: "conceal_hidden_chats",
+  exportTheme: "export_theme",
+  importTheme: "import_theme",
+  sendThemeToPeer: "send_theme_to_peer",
+  getAppearance: "get_appearance",
+  setAppearance: "set_appearance",
+  setChatMediaExportRestricted: "set_chat_media_export_restricted",
+  authorizeMediaExport: "authorize_media_export",
+  getAutoReplySettings: "get_auto_reply_settings",
+  setAutoReplySettings: "set_auto_reply_settings",
+  getSettings: "get_settings",
+  updateSettings: "update_settings",
+  testPeerConnection: "test_peer_connection",
+  setGroupTyping: "set_group_typing",
+  recoverVault: "recover_vault",
+  getKeychainUnlock: "get_keychain_unlock",
+  setKeychainUnlock: "set_keychain_unlock",
+  disconnectGithub: "disconnect_github",
+  setDiscoveryBackend: "set_discovery_backend",
+  getFriendRequests: "get_friend_requests",
+  acceptFriendRequest: "accept_friend_request",
+  declineFriendRequest: "decline_friend_request",
+  rotateIdentityKeys: "rotate_identity_keys",
+  getBandwidthStats: "get_bandwidth_stats",
+  setBandwidthCap: "set_bandwidth_cap",
+  setSocks5Proxy: "set_socks5_proxy",
+  getPeerConnectionQuality: "get_peer_connection_quality",
+  createChat: "create_chat",
+  getChats: "get_chats",
+  getSelfFiles: "get_self_files",
+  saveSelfFile: "save_self_file",
+  deleteSelfNote: "delete_self_note",
+  scheduleMessage: "schedule_message",
+  listScheduledMessages: "list_scheduled_messages",
+  cancelScheduledMessage: "cancel_scheduled_message",
+  getFileStream: "get_file_stream",
+  getStorageUsage: "get_storage_usage",
+  setMediaCacheCap: "set_media_cache_cap",
+  refetchMedia: "refetch_media",
+  setChatAutoDownload: "set_chat_auto_download",
+  setNetworkMetered: "set_network_metered",
+  getDbSchemaVersion: "get_db_schema_version",
+  getDatabaseHealth: "get_database_health",
+  optimizeDatabase: "optimize_database",
+  getEnvelopeSummaries: "get_envelope_summaries",
+  reorderEnvelopes: "reorder_envelopes",
+  addChatTag: "add_chat_tag",
+  removeChatTag: "remove_chat_tag",
+  getChatsWithTags: "get_chats_with_tags",
+  debugExportDiscoveryState: "debug_export_discovery_state",
+  getPendingInvitations: "get_pending_invitations",
+  cancelInvite: "cancel_invite",
+  retryMessage: "retry_message",
+  kickMember: "kick_member",
+  promoteMember: "promote_member",
+  setGroupName: "set_group_name",
+  setGroupAvatar: "set_group_avatar",
+  createGroupInvite: "create_group_invite",
+  joinGroup: "join_group",
+  setGroupHistorySharing: "set_group_history_sharing",
+  requestGroupHistory: "request_group_history",
+  getPeerCapabilities: "get_peer_capabilities",
+  getBotApiInfo: "get_bot_api_info",
+  rotateBotApiToken: "rotate_bot_api_token",
+  getMatrixAccount: "get_matrix_account",
+  setMatrixAccount: "set_matrix_account",
+  listBridgeNicks: "list_bridge_nicks",
+  getHttpApiInfo: "get_http_api_info",
+  rotateHttpApiToken: "rotate_http_api_token",
+  previewChatImport: "preview_chat_import",
+  importChatHistory: "import_chat_history",
 } as const;
 
 export type FriendConfig = {
@@ -124,6 +249,7 @@ export type AuthStatus = {
   is_github_connected: boolean;
   is_online: boolean;
   connectivity: ConnectivitySettings;
+  recovery_phrase?: string;
 };
 
 export type ConnectivityMode = "invisible" | "lan" | "reachable" | "custom";
@@ -196,6 +322,8 @@ export type Envelope = {
   id: string;
   name: string;
   icon?: string | null;
+  parent_id?: string | null;
+  sort_order: number;
 };
 
 export type ChatListItem = {
@@ -398,6 +526,482 @@ export type GithubAuthState = {
   interval: number;
 };
 
+export type BridgeKind = "matrix" | "irc" | "xmpp";
+
+export type ChatBridgeInfo = {
+  chat_id: string;
+  kind: BridgeKind;
+  endpoint: string;
+  target: string;
+  nick: string;
+  has_credential: boolean;
+  enabled: boolean;
+  created_at: number;
+};
+
+export type BridgeIdentity = {
+  chat_id: string;
+  remote_user: string;
+  display_name: string;
+};
+
+export type JournalEntry = {
+  direction: "sent" | "received";
+  journaled_at: number;
+  msg_id: string;
+  chat_id: string;
+  peer_id: string;
+  sender_alias?: string | null;
+  timestamp: number;
+  content_type: string;
+  text_content?: string | null;
+  file_hash?: string | null;
+};
+
+export type ExportFormat = "json" | "markdown" | "html";
+export type MediaExportMode = "none" | "linked" | "embedded";
+
+export type ExportSummary = {
+  path: string;
+  message_count: number;
+  media_count: number;
+  missing_media: number;
+};
+
+export type NotificationPriority = "low" | "normal" | "high" | "urgent";
+
+export type ChatSettings = {
+  chat_id: string;
+  custom_sound_hash?: string | null;
+  priority: NotificationPriority;
+  mute_until?: number | null;
+  notification_level: NotificationLevel;
+  send_translated: boolean;
+  translate_to?: string | null;
+  hidden: boolean;
+  restrict_media_export: boolean;
+  auto_download?: string | null;
+  history_share_days?: number | null;
+};
+
+export type ReplySuggestionSettings = {
+  enabled: boolean;
+  model_endpoint?: string | null;
+  model_name?: string | null;
+  model_api_key?: string | null;
+};
+
+export type NatStatus = "unknown" | "public" | "behind_nat";
+
+export type DialFailure = {
+  at: number;
+  peer_id?: string | null;
+  source: string;
+  address?: string | null;
+  error: string;
+};
+
+export type NetworkDiagnostics = {
+  listen_addresses: string[];
+  external_addresses: string[];
+  stun_external_port?: number | null;
+  connected_peers: number;
+  discovered_local_peers: number;
+  mesh_sizes: Record<string, number>;
+  nat_status: NatStatus;
+  recent_dial_failures: DialFailure[];
+  stats_updated_at: number;
+};
+
+export type MessageRequestScore = {
+  chat_id: string;
+  peer_id: string;
+  msg_id: string;
+  score: number;
+  level: string;
+  reasons: string[];
+  scored_at: number;
+};
+
+export type LocalPeerInfo = {
+  peer_id: string;
+  addresses: string[];
+  alias?: string | null;
+  connected: boolean;
+};
+
+export type NotificationLevel = "all" | "mentions" | "off";
+
+export type ConnectionPath = "relay" | "direct" | "lan";
+
+export type PathConnection = {
+  path: ConnectionPath;
+  address: string;
+};
+
+export type PathReport = {
+  peer_id: string;
+  path?: ConnectionPath | null;
+  connections: PathConnection[];
+  upgrade_candidates: string[];
+};
+
+export type PeerStanding = "good" | "suspect" | "graylisted";
+
+export type PeerSecurityInfo = {
+  peer_id: string;
+  known: boolean;
+  score: number;
+  standing: PeerStanding;
+  invalid_messages: number;
+  rate_limit_trips: number;
+  failed_deliveries: number;
+  updated_at: number;
+};
+
+export type StarredMessage = {
+  message: DbMessage;
+  chat_name: string;
+  is_group: boolean;
+  starred_at: number;
+};
+
+export type StorageStatus = {
+  degraded: boolean;
+  available_bytes?: number | null;
+  threshold_bytes: number;
+  suggestion?: string | null;
+};
+
+export type GcReport = {
+  removed_chunks: number;
+  freed_bytes: number;
+};
+
+export type RecoveryReport = {
+  repairs: string[];
+  db_integrity: string;
+};
+
+export type SelfTask = {
+  message: DbMessage;
+  done: boolean;
+  done_at?: number | null;
+};
+
+export type Reminder = {
+  id: number;
+  message_id: string;
+  remind_at: number;
+  created_at: number;
+  fired_at?: number | null;
+  text?: string | null;
+};
+
+export type TranslationSettings = {
+  model_endpoint?: string | null;
+  model_name?: string | null;
+  model_api_key?: string | null;
+};
+
+export type UsageMetrics = {
+  since?: number | null;
+  counters: Record<string, number>;
+  errors: Record<string, number>;
+};
+
+export type Appearance = "light" | "dark" | "system";
+
+export type AppearanceState = {
+  appearance: Appearance | null;
+  system: "light" | "dark";
+  effective: "light" | "dark";
+  theme: ThemeConfig;
+};
+
+export type AutoReplySettings = {
+  enabled: boolean;
+  message: string;
+  window_secs: number;
+  keywords: string[];
+};
+
+export type MdnsPrivacy = "off" | "anonymous" | "full";
+
+export type FloodProtectionSettings = {
+  gossip_burst: number;
+  gossip_per_sec: number;
+  direct_burst: number;
+  direct_per_sec: number;
+  trips_before_ban: number;
+  ban_secs: number;
+};
+
+export type AutoDownloadPolicy = "always" | "wifi_only" | "never";
+
+export type AutoDownloadSettings = {
+  image: AutoDownloadPolicy;
+  video: AutoDownloadPolicy;
+  audio: AutoDownloadPolicy;
+  document: AutoDownloadPolicy;
+  sticker: AutoDownloadPolicy;
+};
+
+export type WebsocketSettings = {
+  enabled: boolean;
+  ws_port?: number | null;
+  wss_port?: number | null;
+  tls_cert_path?: string | null;
+  tls_key_path?: string | null;
+};
+
+export type HttpApiSettings = {
+  enabled: boolean;
+  port?: number | null;
+};
+
+export type AppSettings = {
+  dark_mode: boolean;
+  timeout: number;
+  is_online: boolean;
+  appearance: Appearance | null;
+  connectivity: ConnectivitySettings;
+  reply_suggestions: ReplySuggestionSettings;
+  translation: TranslationSettings;
+  auto_reply: AutoReplySettings;
+  mdns_privacy: MdnsPrivacy;
+  handshake_ttl_secs: number;
+  flood_protection: FloodProtectionSettings;
+  auto_download: AutoDownloadSettings;
+  websocket: WebsocketSettings;
+  listen_port: number | null;
+  bot_api_enabled: boolean;
+  http_api: HttpApiSettings;
+};
+
+export type SettingsPatch = {
+  dark_mode?: boolean;
+  timeout?: number;
+  appearance?: Appearance;
+  connectivity?: ConnectivitySettingsPatch;
+  reply_suggestions?: ReplySuggestionSettings;
+  translation?: TranslationSettings;
+  auto_reply?: AutoReplySettings;
+  mdns_privacy?: MdnsPrivacy;
+  handshake_ttl_secs?: number;
+  flood_protection?: FloodProtectionSettings;
+  auto_download?: AutoDownloadSettings;
+  websocket?: WebsocketSettings;
+  listen_port?: number;
+  bot_api_enabled?: boolean;
+  http_api?: HttpApiSettings;
+};
+
+export type ProbeSource = "stored" | "mdns" | "relay";
+
+export type ProbeResult = {
+  source: ProbeSource;
+  path: ConnectionPath;
+  address: string;
+  success: boolean;
+  latency_ms?: number | null;
+  error?: string | null;
+};
+
+export type PeerConnectionTest = {
+  peer_id: string;
+  started_at: number;
+  connected_before: boolean;
+  reachable: boolean;
+  best?: ProbeResult | null;
+  results: ProbeResult[];
+};
+
+export type DiscoveryBackendKind = "github" | "gitlab";
+
+export type GitlabDiscoveryInput = {
+  base_url?: string | null;
+  token: string;
+  project: string;
+};
+
+export type FriendRequest = {
+  github_username: string;
+  peer_id: string;
+  received_at: number;
+};
+
+export type BandwidthStat = {
+  day: string;
+  peer_id: string;
+  protocol: string;
+  bytes_sent: number;
+  bytes_received: number;
+};
+
+export type BandwidthReport = {
+  days: BandwidthStat[];
+  month_total_bytes: number;
+  monthly_cap_bytes: number | null;
+  cap_exceeded: boolean;
+};
+
+export type PeerQuality = {
+  peer_id: string;
+  last_rtt_ms?: number | null;
+  avg_rtt_ms?: number | null;
+  jitter_ms?: number | null;
+  loss_percent: number;
+  samples: number;
+};
+
+export type ChatMember = {
+  peer_id: string;
+  role: string;
+  joined_at: number;
+};
+
+export type ChatInfo = {
+  id: string;
+  name: string;
+  is_group: boolean;
+  participants: ChatMember[];
+  settings: ChatSettings;
+  avatar_hash?: string | null;
+};
+
+export type ScheduledMessage = {
+  id: number;
+  chat_id: string;
+  text: string;
+  send_at: number;
+  created_at: number;
+  status: "pending" | "sent" | "failed";
+  message_id?: string | null;
+  error?: string | null;
+};
+
+export type ChatMediaUsage = {
+  chat_id: string;
+  files: number;
+  bytes: number;
+};
+
+export type StorageUsage = {
+  database_bytes: number;
+  chunks_bytes: number;
+  chats: ChatMediaUsage[];
+  media_cache_bytes: number;
+  media_cache_cap_bytes: number | null;
+};
+
+export type EvictionReport = {
+  evicted_files: number;
+  removed_chunks: number;
+  freed_bytes: number;
+};
+
+export type AppliedMigration = {
+  version: number;
+  name: string;
+  applied_at: number;
+};
+
+export type SchemaVersion = {
+  version: number;
+  latest: number;
+  applied: AppliedMigration[];
+};
+
+export type DbHealth = {
+  ok: boolean;
+  integrity: string[];
+  size_bytes: number;
+  wal_bytes: number;
+  free_bytes: number;
+  schema_version: number;
+};
+
+export type OptimizeReport = {
+  size_before: number;
+  size_after: number;
+  duration_ms: number;
+};
+
+export type EnvelopeSummary = {
+  envelope_id: string;
+  chat_count: number;
+  unread_count: number;
+  latest_activity?: number | null;
+};
+
+export type PendingInvitationView = {
+  invitee: string | null;
+  created_at: number;
+  expires_at: number;
+};
+
+export type GroupInviteView = {
+  code: string;
+  group_id: string;
+  expires_at: number;
+};
+
+export type PeerCapability = "e2ee-v2" | "blob-stream" | "reactions" | "receipts";
+
+export type BotApiInfo = {
+  enabled: boolean;
+  endpoint: string;
+  token: string;
+};
+
+export type MatrixAccountInfo = {
+  homeserver: string;
+  user_id: string | null;
+};
+
+export type BridgeNick = {
+  chat_id: string;
+  peer_id: string;
+  nick: string;
+};
+
+export type HttpApiInfo = {
+  enabled: boolean;
+  url: string;
+  token: string;
+};
+
+export type ImportSource = "telegram" | "whatsapp";
+
+export type ImportOptions = {
+  sender_map?: Record<string, string>;
+  self_name?: string | null;
+  utc_offset_minutes?: number;
+  chat_name?: string | null;
+};
+
+export type ImportSender = {
+  name: string;
+  message_count: number;
+  peer_id: string | null;
+};
+
+export type ImportPreview = {
+  name: string;
+  message_count: number;
+  senders: ImportSender[];
+};
+
+export type ImportSummary = {
+  chat_id: string;
+  name: string;
+  message_count: number;
+  media_count: number;
+  missing_media: number;
+  placeholder_peers: number;
+};
+
 type CommandSpec = {
   [COMMANDS.saveApiToken]: { args: { token: string }; result: void };
   [COMMANDS.checkAuthStatus]: { args?: undefined; result: AuthStatus };
@@ -415,7 +1019,10 @@ type CommandSpec = {
   };
   [COMMANDS.toggleOnlineStatus]: { args: { online: boolean }; result: void };
   [COMMANDS.frontendLog]: { args: { message: string }; result: void };
-  [COMMANDS.initVault]: { args: { password: string }; result: AuthStatus };
+  [COMMANDS.initVault]: {
+    args: { password: string; recovery_phrase?: boolean | null };
+    result: AuthStatus;
+  };
   [COMMANDS.unlockVault]: { args: { password: string }; result: AuthStatus };
   [COMMANDS.startNetwork]: { args?: undefined; result: void };
   [COMMANDS.startGithubAuth]: { args?: undefined; result: GithubAuthState };
@@ -510,7 +1117,10 @@ type CommandSpec = {
     args: { chat_id: string };
     result: void;
   };
-  [COMMANDS.sendMessageToSelf]: { args: { message: string }; result: void };
+  [COMMANDS.sendMessageToSelf]: {
+    args: { message: string; file_path?: string | null };
+    result: DbMessage;
+  };
   [COMMANDS.sendMessage]: {
     args: { peer_id: string; message: string };
     result: string;
@@ -525,7 +1135,12 @@ type CommandSpec = {
     result: Record<string, number>;
   };
   [COMMANDS.createEnvelope]: {
-    args: { id: string; name: string; icon?: string | null };
+    args: {
+      id: string;
+      name: string;
+      icon?: string | null;
+      parent_id?: string | null;
+    };
     result: void;
   };
   [COMMANDS.updateEnvelope]: {
@@ -698,6 +1313,367 @@ type CommandSpec = {
   };
   [COMMANDS.getBroadcastState]: { args?: undefined; result: BroadcastState };
   [COMMANDS.getConnectedChatIds]: { args?: undefined; result: string[] };
+  [COMMANDS.configureChatBridge]: {
+    args: {
+      chat_id: string;
+      kind: BridgeKind;
+      endpoint: string;
+      target: string;
+      credential: string;
+      nick?: string | null;
+      enabled: boolean;
+    };
+    result: ChatBridgeInfo;
+  };
+  [COMMANDS.getChatBridge]: {
+    args: { chat_id: string };
+    result: ChatBridgeInfo | null;
+  };
+  [COMMANDS.setChatBridgeEnabled]: {
+    args: { chat_id: string; enabled: boolean };
+    result: void;
+  };
+  [COMMANDS.removeChatBridge]: { args: { chat_id: string }; result: void };
+  [COMMANDS.listBridgeIdentities]: {
+    args: { chat_id: string };
+    result: BridgeIdentity[];
+  };
+  [COMMANDS.setBridgeIdentity]: {
+    args: { chat_id: string; remote_user: string; display_name: string };
+    result: void;
+  };
+  [COMMANDS.setChatJournaling]: {
+    args: { chat_id: string; enabled: boolean };
+    result: void;
+  };
+  [COMMANDS.getJournaledChats]: { args?: undefined; result: string[] };
+  [COMMANDS.listJournalDays]: { args?: undefined; result: string[] };
+  [COMMANDS.readJournalDay]: { args: { day: string }; result: JournalEntry[] };
+  [COMMANDS.exportChat]: {
+    args: {
+      chat_id: string;
+      format: ExportFormat;
+      include_media: MediaExportMode;
+      path: string;
+    };
+    result: ExportSummary;
+  };
+  [COMMANDS.getChatSettings]: {
+    args: { chat_id: string };
+    result: ChatSettings;
+  };
+  [COMMANDS.setChatNotificationPriority]: {
+    args: { chat_id: string; priority: NotificationPriority };
+    result: ChatSettings;
+  };
+  [COMMANDS.setChatNotificationSound]: {
+    args: { chat_id: string; file_path?: string | null };
+    result: ChatSettings;
+  };
+  [COMMANDS.getReplySuggestions]: {
+    args: { msg_id: string };
+    result: string[];
+  };
+  [COMMANDS.getReplySuggestionSettings]: {
+    args?: undefined;
+    result: ReplySuggestionSettings;
+  };
+  [COMMANDS.setReplySuggestionSettings]: {
+    args: { settings: ReplySuggestionSettings };
+    result: void;
+  };
+  [COMMANDS.getNetworkDiagnostics]: {
+    args?: undefined;
+    result: NetworkDiagnostics;
+  };
+  [COMMANDS.getMessageRequestScores]: {
+    args?: undefined;
+    result: MessageRequestScore[];
+  };
+  [COMMANDS.getLocalPeers]: { args?: undefined; result: LocalPeerInfo[] };
+  [COMMANDS.setChatMute]: {
+    args: { chat_id: string; muted: boolean; duration_secs?: number | null };
+    result: ChatSettings;
+  };
+  [COMMANDS.setChatNotificationLevel]: {
+    args: { chat_id: string; level: NotificationLevel };
+    result: ChatSettings;
+  };
+  [COMMANDS.getDraft]: { args: { chat_id: string }; result: string | null };
+  [COMMANDS.saveDraft]: { args: { chat_id: string; text: string }; result: void };
+  [COMMANDS.getConnectionPath]: {
+    args: { peer_id: string };
+    result: PathReport;
+  };
+  [COMMANDS.getPeerSecurityInfo]: {
+    args: { peer_id: string };
+    result: PeerSecurityInfo;
+  };
+  [COMMANDS.setMessageStarred]: {
+    args: { msg_id: string; starred: boolean };
+    result: void;
+  };
+  [COMMANDS.getStarredMessages]: {
+    args?: undefined;
+    result: StarredMessage[];
+  };
+  [COMMANDS.getStorageStatus]: { args?: undefined; result: StorageStatus };
+  [COMMANDS.runStorageGc]: { args?: undefined; result: GcReport };
+  [COMMANDS.getStartupRecoveryReport]: {
+    args?: undefined;
+    result: RecoveryReport;
+  };
+  [COMMANDS.setPeerAlias]: {
+    args: { peer_id: string; alias: string };
+    result: void;
+  };
+  [COMMANDS.setPeerAvatar]: {
+    args: { peer_id: string; image_path?: string | null };
+    result: string | null;
+  };
+  [COMMANDS.getPeerAvatar]: {
+    args: { peer_id: string };
+    result: string | null;
+  };
+  [COMMANDS.addSelfTask]: { args: { text: string }; result: DbMessage };
+  [COMMANDS.setTaskDone]: {
+    args: { message_id: string; done: boolean };
+    result: void;
+  };
+  [COMMANDS.getSelfTasks]: {
+    args: { include_done?: boolean | null };
+    result: SelfTask[];
+  };
+  [COMMANDS.scheduleReminder]: {
+    args: { message_id: string; remind_at: number };
+    result: number;
+  };
+  [COMMANDS.cancelReminder]: { args: { reminder_id: number }; result: boolean };
+  [COMMANDS.getReminders]: { args?: undefined; result: Reminder[] };
+  [COMMANDS.getTranslationSettings]: {
+    args?: undefined;
+    result: TranslationSettings;
+  };
+  [COMMANDS.setTranslationSettings]: {
+    args: { settings: TranslationSettings };
+    result: void;
+  };
+  [COMMANDS.setChatSendTranslation]: {
+    args: { chat_id: string; enabled: boolean; target_lang?: string | null };
+    result: ChatSettings;
+  };
+  [COMMANDS.getUsageMetrics]: { args?: undefined; result: UsageMetrics };
+  [COMMANDS.resetUsageMetrics]: { args?: undefined; result: void };
+  [COMMANDS.setChatHidden]: {
+    args: { chat_id: string; hidden: boolean };
+    result: ChatSettings;
+  };
+  [COMMANDS.revealHiddenChats]: { args: { password: string }; result: number };
+  [COMMANDS.concealHiddenChats]: { args?: undefined; result: void };
+  [COMMANDS.exportTheme]: { args: { file_path: string }; result: string };
+  [COMMANDS.importTheme]: { args: { file_path: string }; result: PresetInfo };
+  [COMMANDS.sendThemeToPeer]: {
+    args: { peer_id: string };
+    result: SentMediaResult;
+  };
+  [COMMANDS.getAppearance]: { args?: undefined; result: AppearanceState };
+  [COMMANDS.setAppearance]: {
+    args: { appearance: Appearance };
+    result: AppearanceState;
+  };
+  [COMMANDS.setChatMediaExportRestricted]: {
+    args: { chat_id: string; restricted: boolean; password?: string | null };
+    result: ChatSettings;
+  };
+  [COMMANDS.authorizeMediaExport]: {
+    args: { file_hash: string; password: string };
+    result: void;
+  };
+  [COMMANDS.getAutoReplySettings]: {
+    args?: undefined;
+    result: AutoReplySettings;
+  };
+  [COMMANDS.setAutoReplySettings]: {
+    args: { settings: AutoReplySettings };
+    result: void;
+  };
+  [COMMANDS.getSettings]: { args?: undefined; result: AppSettings };
+  [COMMANDS.updateSettings]: {
+    args: { patch: SettingsPatch };
+    result: AppSettings;
+  };
+  [COMMANDS.testPeerConnection]: {
+    args: { peer_id: string };
+    result: PeerConnectionTest;
+  };
+  [COMMANDS.setGroupTyping]: {
+    args: { chat_id: string; typing: boolean };
+    result: void;
+  };
+  [COMMANDS.recoverVault]: {
+    args: { phrase: string; new_password: string };
+    result: AuthStatus;
+  };
+  [COMMANDS.getKeychainUnlock]: { args?: undefined; result: boolean };
+  [COMMANDS.setKeychainUnlock]: { args: { enabled: boolean }; result: boolean };
+  [COMMANDS.disconnectGithub]: { args?: undefined; result: void };
+  [COMMANDS.setDiscoveryBackend]: {
+    args: {
+      backend: DiscoveryBackendKind;
+      gitlab?: GitlabDiscoveryInput | null;
+    };
+    result: string | null;
+  };
+  [COMMANDS.getFriendRequests]: { args?: undefined; result: FriendRequest[] };
+  [COMMANDS.acceptFriendRequest]: { args: { peer_id: string }; result: string };
+  [COMMANDS.declineFriendRequest]: { args: { peer_id: string }; result: void };
+  [COMMANDS.rotateIdentityKeys]: { args?: undefined; result: void };
+  [COMMANDS.getBandwidthStats]: {
+    args: { days?: number | null };
+    result: BandwidthReport;
+  };
+  [COMMANDS.setBandwidthCap]: {
+    args: { monthly_cap_mb?: number | null };
+    result: void;
+  };
+  [COMMANDS.setSocks5Proxy]: { args: { proxy?: string | null }; result: void };
+  [COMMANDS.getPeerConnectionQuality]: {
+    args: { peer_id: string };
+    result: PeerQuality | null;
+  };
+  [COMMANDS.createChat]: {
+    args: { peer_ids: string[]; name?: string | null; is_group: boolean };
+    result: ChatInfo;
+  };
+  [COMMANDS.getChats]: { args?: undefined; result: ChatInfo[] };
+  [COMMANDS.getSelfFiles]: {
+    args: {
+      filter?: ChatFileFilter | null;
+      limit?: number | null;
+      offset?: number | null;
+    };
+    result: ChatFileRow[];
+  };
+  [COMMANDS.saveSelfFile]: {
+    args: { file_hash: string; target_path: string };
+    result: void;
+  };
+  [COMMANDS.deleteSelfNote]: { args: { msg_id: string }; result: void };
+  [COMMANDS.scheduleMessage]: {
+    args: { chat_id: string; text: string; send_at: number };
+    result: ScheduledMessage;
+  };
+  [COMMANDS.listScheduledMessages]: {
+    args: { chat_id?: string | null };
+    result: ScheduledMessage[];
+  };
+  [COMMANDS.cancelScheduledMessage]: { args: { id: number }; result: boolean };
+  [COMMANDS.getFileStream]: {
+    args: { file_hash: string; offset: number; len: number };
+    result: ArrayBuffer;
+  };
+  [COMMANDS.getStorageUsage]: { args?: undefined; result: StorageUsage };
+  [COMMANDS.setMediaCacheCap]: {
+    args: { cap_mb?: number | null };
+    result: EvictionReport;
+  };
+  [COMMANDS.refetchMedia]: { args: { message_id: string }; result: void };
+  [COMMANDS.setChatAutoDownload]: {
+    args: {
+      chat_id: string;
+      media_type: keyof AutoDownloadSettings;
+      policy?: AutoDownloadPolicy | null;
+    };
+    result: ChatSettings;
+  };
+  [COMMANDS.setNetworkMetered]: { args: { metered: boolean }; result: void };
+  [COMMANDS.getDbSchemaVersion]: { args?: undefined; result: SchemaVersion };
+  [COMMANDS.getDatabaseHealth]: { args?: undefined; result: DbHealth };
+  [COMMANDS.optimizeDatabase]: { args?: undefined; result: OptimizeReport };
+  [COMMANDS.getEnvelopeSummaries]: {
+    args: { my_peer_id: string };
+    result: EnvelopeSummary[];
+  };
+  [COMMANDS.reorderEnvelopes]: {
+    args: { parent_id?: string | null; envelope_ids: string[] };
+    result: void;
+  };
+  [COMMANDS.addChatTag]: {
+    args: { chat_id: string; envelope_id: string };
+    result: void;
+  };
+  [COMMANDS.removeChatTag]: {
+    args: { chat_id: string; envelope_id: string };
+    result: void;
+  };
+  [COMMANDS.getChatsWithTags]: {
+    args: { envelope_ids: string[]; match_all: boolean };
+    result: ChatInfo[];
+  };
+  [COMMANDS.debugExportDiscoveryState]: { args?: undefined; result: string };
+  [COMMANDS.getPendingInvitations]: {
+    args?: undefined;
+    result: PendingInvitationView[];
+  };
+  [COMMANDS.cancelInvite]: { args: { invitee: string }; result: void };
+  [COMMANDS.retryMessage]: { args: { msg_id: string }; result: void };
+  [COMMANDS.kickMember]: {
+    args: { chat_id: string; peer_id: string };
+    result: void;
+  };
+  [COMMANDS.promoteMember]: {
+    args: { chat_id: string; peer_id: string };
+    result: void;
+  };
+  [COMMANDS.setGroupName]: {
+    args: { chat_id: string; name: string };
+    result: void;
+  };
+  [COMMANDS.setGroupAvatar]: {
+    args: { chat_id: string; file_hash?: string | null };
+    result: void;
+  };
+  [COMMANDS.createGroupInvite]: {
+    args: { chat_id: string };
+    result: GroupInviteView;
+  };
+  [COMMANDS.joinGroup]: { args: { code: string }; result: string };
+  [COMMANDS.setGroupHistorySharing]: {
+    args: { chat_id: string; days?: number | null };
+    result: ChatSettings;
+  };
+  [COMMANDS.requestGroupHistory]: {
+    args: { chat_id: string; peer_id?: string | null };
+    result: void;
+  };
+  [COMMANDS.getPeerCapabilities]: {
+    args: { peer_id: string };
+    result: PeerCapability[] | null;
+  };
+  [COMMANDS.getBotApiInfo]: { args?: undefined; result: BotApiInfo };
+  [COMMANDS.rotateBotApiToken]: { args?: undefined; result: BotApiInfo };
+  [COMMANDS.getMatrixAccount]: {
+    args?: undefined;
+    result: MatrixAccountInfo | null;
+  };
+  [COMMANDS.setMatrixAccount]: {
+    args: { homeserver?: string | null; access_token?: string | null };
+    result: MatrixAccountInfo | null;
+  };
+  [COMMANDS.listBridgeNicks]: {
+    args: { chat_id: string };
+    result: BridgeNick[];
+  };
+  [COMMANDS.getHttpApiInfo]: { args?: undefined; result: HttpApiInfo };
+  [COMMANDS.rotateHttpApiToken]: { args?: undefined; result: HttpApiInfo };
+  [COMMANDS.previewChatImport]: {
+    args: { source: ImportSource; path: string; options?: ImportOptions | null };
+    result: ImportPreview;
+  };
+  [COMMANDS.importChatHistory]: {
+    args: { source: ImportSource; path: string; options?: ImportOptions | null };
+    result: ImportSummary;
+  };
 };
 
 type KnownCommand = keyof CommandSpec;
@@ -762,7 +1738,11 @@ export const api = {
     invokeCommand(COMMANDS.toggleOnlineStatus, { online }),
   frontendLog: (message: string) =>
     invokeCommand(COMMANDS.frontendLog, { message }),
-  initVault: (password: string) => invokeCommand(COMMANDS.initVault, { password }),
+  initVault: (password: string, recoveryPhrase?: boolean | null) =>
+    invokeCommand(COMMANDS.initVault, {
+      password,
+      recovery_phrase: recoveryPhrase,
+    }),
   unlockVault: (password: string) =>
     invokeCommand(COMMANDS.unlockVault, { password }),
   startNetwork: () => invokeCommand(COMMANDS.startNetwork),
@@ -853,8 +1833,11 @@ export const api = {
     invokeCommand(COMMANDS.joinGroupChat, { chat_id: chatId, name }),
   leaveGroupChat: (chatId: string) =>
     invokeCommand(COMMANDS.leaveGroupChat, { chat_id: chatId }),
-  sendMessageToSelf: (message: string) =>
-    invokeCommand(COMMANDS.sendMessageToSelf, { message }),
+  sendMessageToSelf: (message: string, filePath?: string | null) =>
+    invokeCommand(COMMANDS.sendMessageToSelf, {
+      message,
+      file_path: filePath,
+    }),
   sendMessage: (peerId: string, message: string) =>
     invokeCommand(COMMANDS.sendMessage, { peer_id: peerId, message }),
   getChatHistory: (chatId: string) =>
@@ -863,8 +1846,18 @@ export const api = {
     invokeCommand(COMMANDS.markMessagesRead, { chat_id: chatId }),
   getUnreadCounts: (myPeerId: string) =>
     invokeCommand(COMMANDS.getUnreadCounts, { my_peer_id: myPeerId }),
-  createEnvelope: (id: string, name: string, icon?: string | null) =>
-    invokeCommand(COMMANDS.createEnvelope, { id, name, icon }),
+  createEnvelope: (
+    id: string,
+    name: string,
+    icon?: string | null,
+    parentId?: string | null,
+  ) =>
+    invokeCommand(COMMANDS.createEnvelope, {
+      id,
+      name,
+      icon,
+      parent_id: parentId,
+    }),
   updateEnvelope: (id: string, name: string, icon?: string | null) =>
     invokeCommand(COMMANDS.updateEnvelope, { id, name, icon }),
   deleteEnvelope: (id: string) => invokeCommand(COMMANDS.deleteEnvelope, { id }),
@@ -1029,4 +2022,330 @@ export const api = {
     invokeCommand(COMMANDS.endScreenBroadcast, { session_id: sessionId }),
   getBroadcastState: () => invokeCommand(COMMANDS.getBroadcastState),
   getConnectedChatIds: () => invokeCommand(COMMANDS.getConnectedChatIds),
+  configureChatBridge: (
+    chatId: string,
+    kind: BridgeKind,
+    endpoint: string,
+    target: string,
+    credential: string,
+    nick: string | null,
+    enabled: boolean,
+  ) =>
+    invokeCommand(COMMANDS.configureChatBridge, {
+      chat_id: chatId,
+      kind,
+      endpoint,
+      target,
+      credential,
+      nick,
+      enabled,
+    }),
+  getChatBridge: (chatId: string) =>
+    invokeCommand(COMMANDS.getChatBridge, { chat_id: chatId }),
+  setChatBridgeEnabled: (chatId: string, enabled: boolean) =>
+    invokeCommand(COMMANDS.setChatBridgeEnabled, { chat_id: chatId, enabled }),
+  removeChatBridge: (chatId: string) =>
+    invokeCommand(COMMANDS.removeChatBridge, { chat_id: chatId }),
+  listBridgeIdentities: (chatId: string) =>
+    invokeCommand(COMMANDS.listBridgeIdentities, { chat_id: chatId }),
+  setBridgeIdentity: (chatId: string, remoteUser: string, displayName: string) =>
+    invokeCommand(COMMANDS.setBridgeIdentity, {
+      chat_id: chatId,
+      remote_user: remoteUser,
+      display_name: displayName,
+    }),
+  setChatJournaling: (chatId: string, enabled: boolean) =>
+    invokeCommand(COMMANDS.setChatJournaling, { chat_id: chatId, enabled }),
+  getJournaledChats: () => invokeCommand(COMMANDS.getJournaledChats),
+  listJournalDays: () => invokeCommand(COMMANDS.listJournalDays),
+  readJournalDay: (day: string) =>
+    invokeCommand(COMMANDS.readJournalDay, { day }),
+  exportChat: (
+    chatId: string,
+    format: ExportFormat,
+    includeMedia: MediaExportMode,
+    path: string,
+  ) =>
+    invokeCommand(COMMANDS.exportChat, {
+      chat_id: chatId,
+      format,
+      include_media: includeMedia,
+      path,
+    }),
+  getChatSettings: (chatId: string) =>
+    invokeCommand(COMMANDS.getChatSettings, { chat_id: chatId }),
+  setChatNotificationPriority: (
+    chatId: string,
+    priority: NotificationPriority,
+  ) =>
+    invokeCommand(COMMANDS.setChatNotificationPriority, {
+      chat_id: chatId,
+      priority,
+    }),
+  setChatNotificationSound: (chatId: string, filePath?: string | null) =>
+    invokeCommand(COMMANDS.setChatNotificationSound, {
+      chat_id: chatId,
+      file_path: filePath,
+    }),
+  getReplySuggestions: (msgId: string) =>
+    invokeCommand(COMMANDS.getReplySuggestions, { msg_id: msgId }),
+  getReplySuggestionSettings: () =>
+    invokeCommand(COMMANDS.getReplySuggestionSettings),
+  setReplySuggestionSettings: (settings: ReplySuggestionSettings) =>
+    invokeCommand(COMMANDS.setReplySuggestionSettings, { settings }),
+  getNetworkDiagnostics: () => invokeCommand(COMMANDS.getNetworkDiagnostics),
+  getMessageRequestScores: () =>
+    invokeCommand(COMMANDS.getMessageRequestScores),
+  getLocalPeers: () => invokeCommand(COMMANDS.getLocalPeers),
+  setChatMute: (chatId: string, muted: boolean, durationSecs?: number | null) =>
+    invokeCommand(COMMANDS.setChatMute, {
+      chat_id: chatId,
+      muted,
+      duration_secs: durationSecs,
+    }),
+  setChatNotificationLevel: (chatId: string, level: NotificationLevel) =>
+    invokeCommand(COMMANDS.setChatNotificationLevel, { chat_id: chatId, level }),
+  getDraft: (chatId: string) =>
+    invokeCommand(COMMANDS.getDraft, { chat_id: chatId }),
+  saveDraft: (chatId: string, text: string) =>
+    invokeCommand(COMMANDS.saveDraft, { chat_id: chatId, text }),
+  getConnectionPath: (peerId: string) =>
+    invokeCommand(COMMANDS.getConnectionPath, { peer_id: peerId }),
+  getPeerSecurityInfo: (peerId: string) =>
+    invokeCommand(COMMANDS.getPeerSecurityInfo, { peer_id: peerId }),
+  setMessageStarred: (msgId: string, starred: boolean) =>
+    invokeCommand(COMMANDS.setMessageStarred, { msg_id: msgId, starred }),
+  getStarredMessages: () => invokeCommand(COMMANDS.getStarredMessages),
+  getStorageStatus: () => invokeCommand(COMMANDS.getStorageStatus),
+  runStorageGc: () => invokeCommand(COMMANDS.runStorageGc),
+  getStartupRecoveryReport: () =>
+    invokeCommand(COMMANDS.getStartupRecoveryReport),
+  setPeerAlias: (peerId: string, alias: string) =>
+    invokeCommand(COMMANDS.setPeerAlias, { peer_id: peerId, alias }),
+  setPeerAvatar: (peerId: string, imagePath?: string | null) =>
+    invokeCommand(COMMANDS.setPeerAvatar, {
+      peer_id: peerId,
+      image_path: imagePath,
+    }),
+  getPeerAvatar: (peerId: string) =>
+    invokeCommand(COMMANDS.getPeerAvatar, { peer_id: peerId }),
+  addSelfTask: (text: string) => invokeCommand(COMMANDS.addSelfTask, { text }),
+  setTaskDone: (messageId: string, done: boolean) =>
+    invokeCommand(COMMANDS.setTaskDone, { message_id: messageId, done }),
+  getSelfTasks: (includeDone?: boolean | null) =>
+    invokeCommand(COMMANDS.getSelfTasks, { include_done: includeDone }),
+  scheduleReminder: (messageId: string, remindAt: number) =>
+    invokeCommand(COMMANDS.scheduleReminder, {
+      message_id: messageId,
+      remind_at: remindAt,
+    }),
+  cancelReminder: (reminderId: number) =>
+    invokeCommand(COMMANDS.cancelReminder, { reminder_id: reminderId }),
+  getReminders: () => invokeCommand(COMMANDS.getReminders),
+  getTranslationSettings: () => invokeCommand(COMMANDS.getTranslationSettings),
+  setTranslationSettings: (settings: TranslationSettings) =>
+    invokeCommand(COMMANDS.setTranslationSettings, { settings }),
+  setChatSendTranslation: (
+    chatId: string,
+    enabled: boolean,
+    targetLang?: string | null,
+  ) =>
+    invokeCommand(COMMANDS.setChatSendTranslation, {
+      chat_id: chatId,
+      enabled,
+      target_lang: targetLang,
+    }),
+  getUsageMetrics: () => invokeCommand(COMMANDS.getUsageMetrics),
+  resetUsageMetrics: () => invokeCommand(COMMANDS.resetUsageMetrics),
+  setChatHidden: (chatId: string, hidden: boolean) =>
+    invokeCommand(COMMANDS.setChatHidden, { chat_id: chatId, hidden }),
+  revealHiddenChats: (password: string) =>
+    invokeCommand(COMMANDS.revealHiddenChats, { password }),
+  concealHiddenChats: () => invokeCommand(COMMANDS.concealHiddenChats),
+  exportTheme: (filePath: string) =>
+    invokeCommand(COMMANDS.exportTheme, { file_path: filePath }),
+  importTheme: (filePath: string) =>
+    invokeCommand(COMMANDS.importTheme, { file_path: filePath }),
+  sendThemeToPeer: (peerId: string) =>
+    invokeCommand(COMMANDS.sendThemeToPeer, { peer_id: peerId }),
+  getAppearance: () => invokeCommand(COMMANDS.getAppearance),
+  setAppearance: (appearance: Appearance) =>
+    invokeCommand(COMMANDS.setAppearance, { appearance }),
+  setChatMediaExportRestricted: (
+    chatId: string,
+    restricted: boolean,
+    password?: string | null,
+  ) =>
+    invokeCommand(COMMANDS.setChatMediaExportRestricted, {
+      chat_id: chatId,
+      restricted,
+      password,
+    }),
+  authorizeMediaExport: (fileHash: string, password: string) =>
+    invokeCommand(COMMANDS.authorizeMediaExport, {
+      file_hash: fileHash,
+      password,
+    }),
+  getAutoReplySettings: () => invokeCommand(COMMANDS.getAutoReplySettings),
+  setAutoReplySettings: (settings: AutoReplySettings) =>
+    invokeCommand(COMMANDS.setAutoReplySettings, { settings }),
+  getSettings: () => invokeCommand(COMMANDS.getSettings),
+  updateSettings: (patch: SettingsPatch) =>
+    invokeCommand(COMMANDS.updateSettings, { patch }),
+  testPeerConnection: (peerId: string) =>
+    invokeCommand(COMMANDS.testPeerConnection, { peer_id: peerId }),
+  setGroupTyping: (chatId: string, typing: boolean) =>
+    invokeCommand(COMMANDS.setGroupTyping, { chat_id: chatId, typing }),
+  recoverVault: (phrase: string, newPassword: string) =>
+    invokeCommand(COMMANDS.recoverVault, {
+      phrase,
+      new_password: newPassword,
+    }),
+  getKeychainUnlock: () => invokeCommand(COMMANDS.getKeychainUnlock),
+  setKeychainUnlock: (enabled: boolean) =>
+    invokeCommand(COMMANDS.setKeychainUnlock, { enabled }),
+  disconnectGithub: () => invokeCommand(COMMANDS.disconnectGithub),
+  setDiscoveryBackend: (
+    backend: DiscoveryBackendKind,
+    gitlab?: GitlabDiscoveryInput | null,
+  ) => invokeCommand(COMMANDS.setDiscoveryBackend, { backend, gitlab }),
+  getFriendRequests: () => invokeCommand(COMMANDS.getFriendRequests),
+  acceptFriendRequest: (peerId: string) =>
+    invokeCommand(COMMANDS.acceptFriendRequest, { peer_id: peerId }),
+  declineFriendRequest: (peerId: string) =>
+    invokeCommand(COMMANDS.declineFriendRequest, { peer_id: peerId }),
+  rotateIdentityKeys: () => invokeCommand(COMMANDS.rotateIdentityKeys),
+  getBandwidthStats: (days?: number | null) =>
+    invokeCommand(COMMANDS.getBandwidthStats, { days }),
+  setBandwidthCap: (monthlyCapMb?: number | null) =>
+    invokeCommand(COMMANDS.setBandwidthCap, { monthly_cap_mb: monthlyCapMb }),
+  setSocks5Proxy: (proxy?: string | null) =>
+    invokeCommand(COMMANDS.setSocks5Proxy, { proxy }),
+  getPeerConnectionQuality: (peerId: string) =>
+    invokeCommand(COMMANDS.getPeerConnectionQuality, { peer_id: peerId }),
+  createChat: (peerIds: string[], name: string | null, isGroup: boolean) =>
+    invokeCommand(COMMANDS.createChat, {
+      peer_ids: peerIds,
+      name,
+      is_group: isGroup,
+    }),
+  getChats: () => invokeCommand(COMMANDS.getChats),
+  getSelfFiles: (filter: ChatFileFilter = "all", limit = 50, offset = 0) =>
+    invokeCommand(COMMANDS.getSelfFiles, { filter, limit, offset }),
+  saveSelfFile: (fileHash: string, targetPath: string) =>
+    invokeCommand(COMMANDS.saveSelfFile, {
+      file_hash: fileHash,
+      target_path: targetPath,
+    }),
+  deleteSelfNote: (msgId: string) =>
+    invokeCommand(COMMANDS.deleteSelfNote, { msg_id: msgId }),
+  scheduleMessage: (chatId: string, text: string, sendAt: number) =>
+    invokeCommand(COMMANDS.scheduleMessage, {
+      chat_id: chatId,
+      text,
+      send_at: sendAt,
+    }),
+  listScheduledMessages: (chatId?: string | null) =>
+    invokeCommand(COMMANDS.listScheduledMessages, { chat_id: chatId }),
+  cancelScheduledMessage: (id: number) =>
+    invokeCommand(COMMANDS.cancelScheduledMessage, { id }),
+  getFileStream: (fileHash: string, offset: number, len: number) =>
+    invokeCommand(COMMANDS.getFileStream, { file_hash: fileHash, offset, len }),
+  getStorageUsage: () => invokeCommand(COMMANDS.getStorageUsage),
+  setMediaCacheCap: (capMb?: number | null) =>
+    invokeCommand(COMMANDS.setMediaCacheCap, { cap_mb: capMb }),
+  refetchMedia: (messageId: string) =>
+    invokeCommand(COMMANDS.refetchMedia, { message_id: messageId }),
+  setChatAutoDownload: (
+    chatId: string,
+    mediaType: keyof AutoDownloadSettings,
+    policy?: AutoDownloadPolicy | null,
+  ) =>
+    invokeCommand(COMMANDS.setChatAutoDownload, {
+      chat_id: chatId,
+      media_type: mediaType,
+      policy,
+    }),
+  setNetworkMetered: (metered: boolean) =>
+    invokeCommand(COMMANDS.setNetworkMetered, { metered }),
+  getDbSchemaVersion: () => invokeCommand(COMMANDS.getDbSchemaVersion),
+  getDatabaseHealth: () => invokeCommand(COMMANDS.getDatabaseHealth),
+  optimizeDatabase: () => invokeCommand(COMMANDS.optimizeDatabase),
+  getEnvelopeSummaries: (myPeerId: string) =>
+    invokeCommand(COMMANDS.getEnvelopeSummaries, { my_peer_id: myPeerId }),
+  reorderEnvelopes: (parentId: string | null, envelopeIds: string[]) =>
+    invokeCommand(COMMANDS.reorderEnvelopes, {
+      parent_id: parentId,
+      envelope_ids: envelopeIds,
+    }),
+  addChatTag: (chatId: string, envelopeId: string) =>
+    invokeCommand(COMMANDS.addChatTag, {
+      chat_id: chatId,
+      envelope_id: envelopeId,
+    }),
+  removeChatTag: (chatId: string, envelopeId: string) =>
+    invokeCommand(COMMANDS.removeChatTag, {
+      chat_id: chatId,
+      envelope_id: envelopeId,
+    }),
+  getChatsWithTags: (envelopeIds: string[], matchAll: boolean) =>
+    invokeCommand(COMMANDS.getChatsWithTags, {
+      envelope_ids: envelopeIds,
+      match_all: matchAll,
+    }),
+  debugExportDiscoveryState: () =>
+    invokeCommand(COMMANDS.debugExportDiscoveryState),
+  getPendingInvitations: () => invokeCommand(COMMANDS.getPendingInvitations),
+  cancelInvite: (invitee: string) =>
+    invokeCommand(COMMANDS.cancelInvite, { invitee }),
+  retryMessage: (msgId: string) =>
+    invokeCommand(COMMANDS.retryMessage, { msg_id: msgId }),
+  kickMember: (chatId: string, peerId: string) =>
+    invokeCommand(COMMANDS.kickMember, { chat_id: chatId, peer_id: peerId }),
+  promoteMember: (chatId: string, peerId: string) =>
+    invokeCommand(COMMANDS.promoteMember, { chat_id: chatId, peer_id: peerId }),
+  setGroupName: (chatId: string, name: string) =>
+    invokeCommand(COMMANDS.setGroupName, { chat_id: chatId, name }),
+  setGroupAvatar: (chatId: string, fileHash?: string | null) =>
+    invokeCommand(COMMANDS.setGroupAvatar, {
+      chat_id: chatId,
+      file_hash: fileHash,
+    }),
+  createGroupInvite: (chatId: string) =>
+    invokeCommand(COMMANDS.createGroupInvite, { chat_id: chatId }),
+  joinGroup: (code: string) => invokeCommand(COMMANDS.joinGroup, { code }),
+  setGroupHistorySharing: (chatId: string, days?: number | null) =>
+    invokeCommand(COMMANDS.setGroupHistorySharing, { chat_id: chatId, days }),
+  requestGroupHistory: (chatId: string, peerId?: string | null) =>
+    invokeCommand(COMMANDS.requestGroupHistory, {
+      chat_id: chatId,
+      peer_id: peerId,
+    }),
+  getPeerCapabilities: (peerId: string) =>
+    invokeCommand(COMMANDS.getPeerCapabilities, { peer_id: peerId }),
+  getBotApiInfo: () => invokeCommand(COMMANDS.getBotApiInfo),
+  rotateBotApiToken: () => invokeCommand(COMMANDS.rotateBotApiToken),
+  getMatrixAccount: () => invokeCommand(COMMANDS.getMatrixAccount),
+  setMatrixAccount: (
+    homeserver?: string | null,
+    accessToken?: string | null,
+  ) =>
+    invokeCommand(COMMANDS.setMatrixAccount, {
+      homeserver,
+      access_token: accessToken,
+    }),
+  listBridgeNicks: (chatId: string) =>
+    invokeCommand(COMMANDS.listBridgeNicks, { chat_id: chatId }),
+  getHttpApiInfo: () => invokeCommand(COMMANDS.getHttpApiInfo),
+  rotateHttpApiToken: () => invokeCommand(COMMANDS.rotateHttpApiToken),
+  previewChatImport: (
+    source: ImportSource,
+    path: string,
+    options?: ImportOptions | null,
+  ) => invokeCommand(COMMANDS.previewChatImport, { source, path, options }),
+  importChatHistory: (
+    source: ImportSource,
+    path: string,
+    options?: ImportOptions | null,
+  ) => invokeCommand(COMMANDS.importChatHistory, { source, path, options }),
 };
@@ -0,0 +1,40 @@
+export type CommandErrorCode =
+  | "VAULT_LOCKED"
+  | "NOT_FOUND"
+  | "INVALID_INPUT"
+  | "PEER_OFFLINE"
+  | "NETWORK"
+  | "CRYPTO"
+  | "IO"
+  | "DATABASE"
+  | "STORAGE_LOW"
+  | "EXPORT_RESTRICTED"
+  | "INTERNAL";
+
+/** What a failed command rejects with (`RChatError` in the backend). */
+export type CommandError = {
+  code: CommandErrorCode;
+  message: string;
+};
+
+export function isCommandError(error: unknown): error is CommandError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as { code?: unknown }).code === "string" &&
+    typeof (error as { message?: unknown }).message === "string"
+  );
+}
+
+/** Text to show for anything a `catch` can receive, command errors included. */
+export function errorMessage(error: unknown, fallback = "Unknown error"): string {
+  if (isCommandError(error)) return error.message || fallback;
+  if (error instanceof Error) return error.message || fallback;
+  if (typeof error === "string") return error || fallback;
+  if (error === null || error === undefined) return fallback;
+  try {
+    return JSON.stringify(error);
+  } catch {
+    return String(error);
+  }
+}
@@ -1,5 +1,6 @@
 // @ts-nocheck
 import { describe, expect, mock, test } from "bun:test";
+import { errorMessage } from "../src/lib/tauri/errors";
 import { readFileSync } from "node:fs";
 import { get } from "svelte/store";
 
@@ -46,7 +47,7 @@ describe("app session startup", () => {
       frontendLog: mock(async () => {}),
     };
 
-    mock.module("$lib/tauri/api", () => ({ api }));
+    mock.module("$lib/tauri/api", () => ({ api, errorMessage }));
     mock.module("$lib/stores/chat", () => ({
       initChatStore: mock(async () => () => {}),
       resetChatStore: mock(() => {}),
@@ -1,5 +1,6 @@
 // @ts-nocheck
 import { describe, expect, mock, test } from "bun:test";
+import { errorMessage } from "../src/lib/tauri/errors";
 import { get } from "svelte/store";
 
 const lockedStatus = {
@@ -32,7 +33,7 @@ describe("app session readiness", () => {
       frontendLog: mock(async () => {}),
     };
 
-    mock.module("$lib/tauri/api", () => ({ api }));
+    mock.module("$lib/tauri/api", () => ({ api, errorMessage }));
     mock.module("$lib/stores/chat", () => ({
       initChatStore: mock(async () => () => {}),
       resetChatStore: mock(() => {}),
@@ -0,0 +1,24 @@
+// @ts-nocheck
+import { describe, expect, test } from "bun:test";
+import { errorMessage, isCommandError } from "../src/lib/tauri/errors";
+
+describe("command errors", () => {
+  test("recognizes the backend's { code, message } shape", () => {
+    expect(isCommandError({ code: "VAULT_LOCKED", message: "Vault is locked" })).toBe(
+      true,
+    );
+    expect(isCommandError(new Error("boom"))).toBe(false);
+    expect(isCommandError("boom")).toBe(false);
+    expect(isCommandError(null)).toBe(false);
+  });
+
+  test("shows the message instead of [object Object]", () => {
+    expect(errorMessage({ code: "NOT_FOUND", message: "Chat not found" })).toBe(
+      "Chat not found",
+    );
+    expect(errorMessage(new Error("boom"))).toBe("boom");
+    expect(errorMessage("plain")).toBe("plain");
+    expect(errorMessage(undefined, "Failed to load")).toBe("Failed to load");
+    expect(errorMessage({ code: "INTERNAL", message: "" }, "Failed")).toBe("Failed");
+  });
+});